@@ -1,5 +1,11 @@
+use crate::api::database::{provision_database_from_dir, DatabaseState};
 use crate::error::{GatewayError, Result};
-use crate::pool::PoolManager;
+use crate::pool::{PoolManager, PoolStats, ServerVersion};
+use crate::registry::PlatformRegistry;
+use crate::schema::{
+    find_checksum_drift, AppliedChecksum, ChecksumDrift, DropImpact, DropImpactAnalyzer, MatviewDeployer,
+    MigrationRunner, RefreshStrategy, TrackingRepair, TrackingRepairReport, TypeImpact, TypeImpactAnalyzer,
+};
 use axum::{
     extract::{Query, State},
     http::StatusCode,
@@ -7,9 +13,10 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::info;
+use tracing::{info, warn};
 
 #[derive(Debug, Deserialize)]
 pub struct ListDatabasesQuery {
@@ -22,6 +29,8 @@ pub struct DatabaseInfo {
     #[serde(rename = "type")]
     pub db_type: String,
     pub size_mb: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_version: Option<ServerVersion>,
 }
 
 #[derive(Serialize)]
@@ -57,10 +66,14 @@ pub async fn admin_list_databases(
 
         let size_mb = size_bytes / (1024 * 1024);
 
+        // Server version is cached per pool after the first lookup
+        let server_version = pool_manager.server_version(db_name).await.ok();
+
         db_infos.push(DatabaseInfo {
             name: db_name.clone(),
             db_type: db_type.to_string(),
             size_mb,
+            server_version,
         });
     }
 
@@ -76,6 +89,33 @@ pub async fn admin_list_databases(
     ))
 }
 
+#[derive(Serialize)]
+pub struct PoolStatsResponse {
+    pub pools: Vec<PoolStats>,
+    pub active_pools: usize,
+    pub total_connections: u32,
+    pub max_connections_per_pool: u32,
+    pub max_total_connections: u32,
+}
+
+/// Live pools with their deadpool status, plus the global connection budget
+pub async fn admin_pool_stats(
+    State((pool_manager, _)): State<(Arc<PoolManager>, Instant)>,
+) -> Result<impl IntoResponse> {
+    let config = pool_manager.config();
+
+    Ok((
+        StatusCode::OK,
+        Json(PoolStatsResponse {
+            pools: pool_manager.pool_stats().await,
+            active_pools: pool_manager.active_pools(),
+            total_connections: pool_manager.total_connections(),
+            max_connections_per_pool: config.max_connections_per_pool,
+            max_total_connections: config.max_total_connections,
+        }),
+    ))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateTenantRequest {
     pub platform: String,
@@ -103,7 +143,8 @@ pub async fn admin_create_tenant(
     }
 
     // Create the database
-    pool_manager.create_database(&db_name).await?;
+    let database_options = pool_manager.database_options_for(&request.platform)?;
+    pool_manager.create_database(&db_name, &database_options).await?;
 
     info!("Created tenant database: {}", db_name);
 
@@ -116,3 +157,638 @@ pub async fn admin_create_tenant(
         }),
     ))
 }
+
+#[derive(Debug, Deserialize)]
+pub struct DropImpactRequest {
+    pub database: String,
+    pub table: String,
+}
+
+#[derive(Serialize)]
+pub struct DropImpactResponse {
+    pub database: String,
+    /// True when no other table, view or function depends on the table
+    pub isolated: bool,
+    pub impact: DropImpact,
+}
+
+/// Report what dropping a table would affect, without dropping anything
+pub async fn admin_drop_impact(
+    State((pool_manager, _)): State<(Arc<PoolManager>, Instant)>,
+    Json(request): Json<DropImpactRequest>,
+) -> Result<impl IntoResponse> {
+    if !pool_manager.database_exists(&request.database).await? {
+        return Err(GatewayError::InvalidRequest {
+            message: format!("Database '{}' does not exist", request.database),
+        });
+    }
+
+    let pool = pool_manager.get_pool_by_name(&request.database).await?;
+    let impact = DropImpactAnalyzer::analyze(&pool, &request.database, &request.table).await?;
+
+    info!(
+        "Drop impact for {}.{}: {} dependent tables, {} views, {} functions",
+        request.database,
+        request.table,
+        impact.dependent_tables.len(),
+        impact.views.len(),
+        impact.functions.len()
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(DropImpactResponse {
+            database: request.database,
+            isolated: impact.is_isolated(),
+            impact,
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TypeImpactRequest {
+    pub database: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+#[derive(Serialize)]
+pub struct TypeImpactResponse {
+    pub database: String,
+    /// True when no table column uses the type
+    pub unused: bool,
+    pub impact: TypeImpact,
+}
+
+/// Report which table columns changing a custom type would affect
+pub async fn admin_type_impact(
+    State((pool_manager, _)): State<(Arc<PoolManager>, Instant)>,
+    Json(request): Json<TypeImpactRequest>,
+) -> Result<impl IntoResponse> {
+    if !pool_manager.database_exists(&request.database).await? {
+        return Err(GatewayError::InvalidRequest {
+            message: format!("Database '{}' does not exist", request.database),
+        });
+    }
+
+    let pool = pool_manager.get_pool_by_name(&request.database).await?;
+    let impact = TypeImpactAnalyzer::analyze(&pool, &request.database, &request.type_name).await?;
+
+    info!(
+        "Type impact for {}.{}: {} columns",
+        request.database,
+        request.type_name,
+        impact.columns.len()
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(TypeImpactResponse {
+            database: request.database,
+            unused: impact.is_unused(),
+            impact,
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshMatviewRequest {
+    pub database: String,
+    pub name: String,
+    /// Overrides the view's declared `@refresh` strategy
+    #[serde(default)]
+    pub concurrently: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct RefreshMatviewResponse {
+    pub database: String,
+    pub name: String,
+    pub refresh: RefreshStrategy,
+    pub execution_time_ms: u64,
+}
+
+/// Refresh a materialized view's data
+pub async fn admin_refresh_matview(
+    State((pool_manager, _)): State<(Arc<PoolManager>, Instant)>,
+    Json(request): Json<RefreshMatviewRequest>,
+) -> Result<impl IntoResponse> {
+    if !pool_manager.database_exists(&request.database).await? {
+        return Err(GatewayError::InvalidRequest {
+            message: format!("Database '{}' does not exist", request.database),
+        });
+    }
+
+    let start = Instant::now();
+    let pool = pool_manager.get_pool_by_name(&request.database).await?;
+    let refresh = MatviewDeployer::new()
+        .refresh_matview(&pool, &request.database, &request.name, request.concurrently)
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(RefreshMatviewResponse {
+            database: request.database,
+            name: request.name,
+            refresh,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RepairTrackingRequest {
+    pub database: String,
+}
+
+#[derive(Serialize)]
+pub struct RepairTrackingResponse {
+    pub database: String,
+    pub repaired: bool,
+    #[serde(flatten)]
+    pub report: TrackingRepairReport,
+}
+
+/// Rewrite a database's tracking tables to match the objects it actually contains
+pub async fn admin_repair_tracking(
+    State((pool_manager, _)): State<(Arc<PoolManager>, Instant)>,
+    Json(request): Json<RepairTrackingRequest>,
+) -> Result<impl IntoResponse> {
+    if !pool_manager.database_exists(&request.database).await? {
+        return Err(GatewayError::InvalidRequest {
+            message: format!("Database '{}' does not exist", request.database),
+        });
+    }
+
+    let pool = pool_manager.get_pool_by_name(&request.database).await?;
+    let report = TrackingRepair::new().repair(&pool, &request.database).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(RepairTrackingResponse {
+            database: request.database,
+            repaired: !report.is_empty(),
+            report,
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MigrationChecksumsQuery {
+    pub platform: String,
+}
+
+#[derive(Serialize)]
+pub struct DatabaseChecksums {
+    pub database: String,
+    pub migrations: Vec<AppliedChecksum>,
+}
+
+#[derive(Serialize)]
+pub struct ChecksumFailure {
+    pub database: String,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+pub struct MigrationChecksumsResponse {
+    pub platform: String,
+    /// True when every migration has the same checksum in every database that applied it
+    pub consistent: bool,
+    pub drift: Vec<ChecksumDrift>,
+    pub databases: Vec<DatabaseChecksums>,
+    /// Databases whose tracking table could not be read
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<ChecksumFailure>,
+}
+
+/// Compare applied migration checksums across all of a platform's databases
+pub async fn admin_migration_checksums(
+    State((pool_manager, _)): State<(Arc<PoolManager>, Instant)>,
+    Query(query): Query<MigrationChecksumsQuery>,
+) -> Result<impl IntoResponse> {
+    let runner = MigrationRunner::new();
+    let mut applied = BTreeMap::new();
+    let mut failed = Vec::new();
+
+    for db_name in pool_manager.list_databases_for_platform(&query.platform).await? {
+        let checksums = match pool_manager.get_pool_by_name(&db_name).await {
+            Ok(pool) => runner.applied_checksums(&pool, &db_name).await,
+            Err(e) => Err(e),
+        };
+
+        match checksums {
+            Ok(checksums) => {
+                applied.insert(db_name, checksums);
+            }
+            Err(e) => failed.push(ChecksumFailure {
+                database: db_name,
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    let drift = find_checksum_drift(&applied);
+    for d in &drift {
+        warn!(
+            "Migration {} checksum differs in {} database(s) of platform {}",
+            d.migration_file,
+            d.divergent.len(),
+            query.platform
+        );
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(MigrationChecksumsResponse {
+            platform: query.platform,
+            consistent: drift.is_empty(),
+            drift,
+            databases: applied
+                .into_iter()
+                .map(|(database, migrations)| DatabaseChecksums { database, migrations })
+                .collect(),
+            failed,
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloneSchemaRequest {
+    pub source_database: String,
+    pub target_database: String,
+}
+
+#[derive(Serialize)]
+pub struct CloneSchemaResponse {
+    pub status: String,
+    pub platform: String,
+    pub schema_name: String,
+    pub source_database: String,
+    pub target_database: String,
+    /// Stored schema version both databases are on
+    pub schema_version: String,
+    pub tables_created: usize,
+    pub functions_deployed: usize,
+    pub execution_time_ms: u64,
+}
+
+/// Provision a new database from the schema version another database is on
+///
+/// The target is built from the stored schema history (not a data copy), so it
+/// matches the source's structure even if the schema was registered again since.
+pub async fn admin_clone_schema(
+    State(state): State<Arc<DatabaseState>>,
+    Json(request): Json<CloneSchemaRequest>,
+) -> Result<impl IntoResponse> {
+    let start_time = Instant::now();
+    let registry = &state.platform_state.registry;
+
+    let (platform, source) = registry
+        .find_database(&request.source_database)?
+        .ok_or_else(|| GatewayError::InvalidRequest {
+            message: format!(
+                "Database '{}' is not recorded in the platform registry",
+                request.source_database
+            ),
+        })?;
+
+    let schema_version = source.schema_version.clone().ok_or_else(|| GatewayError::InvalidRequest {
+        message: format!(
+            "Database '{}' has no recorded schema version; it must be provisioned or migrated with SCHEMA_HISTORY enabled",
+            request.source_database
+        ),
+    })?;
+
+    // Keep the clone inside the source's platform; as for /admin/drop-database,
+    // the longest matching platform owns the name ("app_b_x" is app_b's)
+    match owning_platform(registry, &request.target_database)? {
+        Some(owner) if owner == platform => {}
+        Some(owner) => {
+            return Err(GatewayError::PlatformIsolationViolation {
+                requesting_platform: platform,
+                target_platform: owner,
+            });
+        }
+        None => {
+            return Err(GatewayError::InvalidRequest {
+                message: format!(
+                    "Target database '{}' must belong to platform '{}' (prefix '{}_')",
+                    request.target_database, platform, platform
+                ),
+            });
+        }
+    }
+
+    if state.pool_manager.database_exists(&request.target_database).await? {
+        return Err(GatewayError::DatabaseAlreadyExists {
+            database: request.target_database,
+        });
+    }
+
+    let schema_dir = tempfile::TempDir::new().map_err(|e| {
+        GatewayError::Internal(format!("Failed to create temp directory: {}", e))
+    })?;
+    state.platform_state.schema_store.restore_version(
+        &platform,
+        &source.schema_name,
+        &schema_version,
+        schema_dir.path(),
+    )?;
+
+    let provisioned = provision_database_from_dir(
+        &state.pool_manager,
+        &state.platform_state,
+        &platform,
+        &source.schema_name,
+        schema_dir.path(),
+        Some(&schema_version),
+        &request.target_database,
+        None,
+    )
+    .await?;
+
+    info!(
+        "Cloned schema of {} into {} (schema '{}' version {})",
+        request.source_database, request.target_database, source.schema_name, schema_version
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CloneSchemaResponse {
+            status: "cloned".to_string(),
+            platform,
+            schema_name: source.schema_name,
+            source_database: request.source_database,
+            target_database: request.target_database,
+            schema_version,
+            tables_created: provisioned.tables_created,
+            functions_deployed: provisioned.functions_deployed,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DropDatabaseRequest {
+    pub platform: String,
+    /// "main" for the platform's main database, otherwise the tenant/database id
+    pub database_id: String,
+    /// Terminate other sessions connected to the database (DROP DATABASE ... WITH (FORCE))
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Serialize)]
+pub struct DropDatabaseResponse {
+    pub status: String,
+    pub platform: String,
+    pub database: String,
+    /// Whether the database had a record in the platform registry
+    pub registry_updated: bool,
+}
+
+/// Drop a platform's database, e.g. when a tenant is offboarded
+pub async fn admin_drop_database(
+    State(state): State<Arc<DatabaseState>>,
+    Json(request): Json<DropDatabaseRequest>,
+) -> Result<impl IntoResponse> {
+    let registry = &state.platform_state.registry;
+
+    // Same naming as /v2/migrate
+    let db_name = if request.database_id == "main" {
+        format!("{}_main", request.platform)
+    } else {
+        format!("{}_{}", request.platform, request.database_id)
+    };
+
+    // Platform names may contain underscores, so "app" + "b_main" could name
+    // platform "app_b"'s database; the longest matching platform owns it
+    let owner = owning_platform(registry, &db_name)?;
+    if let Some(owner) = owner.filter(|owner| *owner != request.platform) {
+        return Err(GatewayError::PlatformIsolationViolation {
+            requesting_platform: request.platform,
+            target_platform: owner,
+        });
+    }
+
+    if !state.pool_manager.database_exists(&db_name).await? {
+        return Err(GatewayError::DatabaseNotFound {
+            platform: request.platform,
+            tenant_id: (request.database_id != "main").then_some(request.database_id),
+        });
+    }
+
+    state.pool_manager.drop_database(&db_name, request.force).await?;
+
+    let registry_updated = registry.is_registered(&request.platform)
+        && registry.remove_database(&request.platform, &db_name)?;
+
+    info!("Dropped database {} for platform {}", db_name, request.platform);
+
+    Ok((
+        StatusCode::OK,
+        Json(DropDatabaseResponse {
+            status: "dropped".to_string(),
+            platform: request.platform,
+            database: db_name,
+            registry_updated,
+        }),
+    ))
+}
+
+/// Find the platform a database belongs to: its registry record, or else the
+/// longest registered platform name that prefixes it
+fn owning_platform(registry: &PlatformRegistry, db_name: &str) -> Result<Option<String>> {
+    if let Some((platform, _)) = registry.find_database(db_name)? {
+        return Ok(Some(platform));
+    }
+
+    Ok(registry
+        .list_platforms()?
+        .into_iter()
+        .filter(|platform| db_name.starts_with(&format!("{}_", platform)))
+        .max_by_key(|platform| platform.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::database::provision_database;
+    use crate::api::platform::PlatformState;
+    use crate::config::Config;
+    use crate::schema::test_db::test_database_url;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn archive_with(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut archive_data = Vec::new();
+        {
+            let encoder = GzEncoder::new(&mut archive_data, Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            for (path, content) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_path(path).unwrap();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append(&header, content.as_bytes()).unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        archive_data
+    }
+
+    #[tokio::test]
+    async fn test_clone_uses_source_schema_version() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let pool_manager = Arc::new(PoolManager::new(Config::for_test(&url, data_dir.path())).await.unwrap());
+        let platform_state = Arc::new(PlatformState::new(data_dir.path(), false, true));
+        for db in ["clonetest_src", "clonetest_dst"] {
+            pool_manager.drop_database(db, true).await.ok();
+        }
+
+        platform_state.registry.register_platform("clonetest").unwrap();
+        let v1 = platform_state
+            .schema_store
+            .store_schema(
+                "clonetest",
+                "tenant_db",
+                &archive_with(&[("tables/items.pssql", "CREATE TABLE items (id SERIAL PRIMARY KEY);")]),
+            )
+            .unwrap();
+        provision_database(&pool_manager, &platform_state, "clonetest", "tenant_db", "clonetest_src", None)
+            .await
+            .unwrap();
+
+        // The schema moves on after the source was provisioned
+        platform_state
+            .schema_store
+            .store_schema(
+                "clonetest",
+                "tenant_db",
+                &archive_with(&[(
+                    "tables/items.pssql",
+                    "CREATE TABLE items (id SERIAL PRIMARY KEY, name TEXT);",
+                )]),
+            )
+            .unwrap();
+
+        let state = Arc::new(DatabaseState {
+            pool_manager: pool_manager.clone(),
+            platform_state: platform_state.clone(),
+        });
+
+        // "clonetest_b_dst" carries the source's prefix but belongs to clonetest_b
+        platform_state.registry.register_platform("clonetest_b").unwrap();
+        let result = admin_clone_schema(
+            State(state.clone()),
+            Json(CloneSchemaRequest {
+                source_database: "clonetest_src".to_string(),
+                target_database: "clonetest_b_dst".to_string(),
+            }),
+        )
+        .await;
+        assert!(matches!(result, Err(GatewayError::PlatformIsolationViolation { .. })));
+
+        let result = admin_clone_schema(
+            State(state),
+            Json(CloneSchemaRequest {
+                source_database: "clonetest_src".to_string(),
+                target_database: "clonetest_dst".to_string(),
+            }),
+        )
+        .await;
+        assert!(result.is_ok());
+
+        let (_, record) = platform_state
+            .registry
+            .find_database("clonetest_dst")
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.schema_version, Some(v1.checksum));
+
+        let pool = pool_manager.get_pool_by_name("clonetest_dst").await.unwrap();
+        let columns: i64 = pool
+            .get()
+            .await
+            .unwrap()
+            .query_one(
+                "SELECT COUNT(*) FROM information_schema.columns WHERE table_name = 'items'",
+                &[],
+            )
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(columns, 1);
+
+        drop(pool);
+        for db in ["clonetest_src", "clonetest_dst"] {
+            pool_manager.drop_database(db, true).await.ok();
+        }
+    }
+
+    #[test]
+    fn test_owning_platform_prefers_longest_prefix() {
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let registry = PlatformRegistry::new(data_dir.path());
+        registry.register_platform("app").unwrap();
+        registry.register_platform("app_b").unwrap();
+        registry.record_database("app", "tenant_db", "app_b_legacy", None).unwrap();
+
+        assert_eq!(owning_platform(&registry, "app_t1").unwrap().as_deref(), Some("app"));
+        assert_eq!(owning_platform(&registry, "app_b_main").unwrap().as_deref(), Some("app_b"));
+        // A registry record wins over the name
+        assert_eq!(owning_platform(&registry, "app_b_legacy").unwrap().as_deref(), Some("app"));
+        assert!(owning_platform(&registry, "other_main").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_drop_database() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let pool_manager = Arc::new(PoolManager::new(Config::for_test(&url, data_dir.path())).await.unwrap());
+        let platform_state = Arc::new(PlatformState::new(data_dir.path(), false, true));
+        platform_state.registry.register_platform("droptest").unwrap();
+        platform_state.registry.register_platform("droptest_b").unwrap();
+
+        pool_manager
+            .create_database("droptest_t1", &Default::default())
+            .await
+            .unwrap();
+        platform_state
+            .registry
+            .record_database("droptest", "tenant_db", "droptest_t1", None)
+            .unwrap();
+        // The gateway's own cached pool is closed before dropping
+        pool_manager.get_pool_by_name("droptest_t1").await.unwrap();
+
+        let state = Arc::new(DatabaseState {
+            pool_manager: pool_manager.clone(),
+            platform_state: platform_state.clone(),
+        });
+        let request = |platform: &str, database_id: &str| {
+            Json(DropDatabaseRequest {
+                platform: platform.to_string(),
+                database_id: database_id.to_string(),
+                force: true,
+            })
+        };
+
+        // "droptest" + "b_main" names platform droptest_b's database
+        let result = admin_drop_database(State(state.clone()), request("droptest", "b_main")).await;
+        assert!(matches!(result, Err(GatewayError::PlatformIsolationViolation { .. })));
+
+        assert!(admin_drop_database(State(state.clone()), request("droptest", "t1")).await.is_ok());
+        assert!(!pool_manager.database_exists("droptest_t1").await.unwrap());
+        assert!(platform_state.registry.find_database("droptest_t1").unwrap().is_none());
+
+        let result = admin_drop_database(State(state), request("droptest", "t1")).await;
+        assert!(matches!(result, Err(GatewayError::DatabaseNotFound { .. })));
+    }
+}
@@ -0,0 +1,110 @@
+//! SQL analysis API
+//!
+//! POST /analyze/sql - Dependency analysis of a single SQL snippet (tables,
+//! foreign keys, creation order, cycles). Nothing is registered or stored.
+
+use crate::schema::{DependencyAnalysis, DependencyAnalyzer};
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyzeSqlRequest {
+    pub sql: String,
+}
+
+#[derive(Serialize)]
+pub struct AnalyzeSqlError {
+    /// 1-based line the problem starts on, when known
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct AnalyzeSqlResponse {
+    pub valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<DependencyAnalysis>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<AnalyzeSqlError>,
+}
+
+/// Parse errors are part of the response rather than an error status, so an
+/// editor can show them inline
+pub async fn analyze_sql(Json(request): Json<AnalyzeSqlRequest>) -> impl IntoResponse {
+    (StatusCode::OK, Json(analyze(&request.sql)))
+}
+
+fn analyze(sql: &str) -> AnalyzeSqlResponse {
+    match DependencyAnalyzer::analyze_file_sql(Path::new("sql"), sql) {
+        Ok(analysis) => AnalyzeSqlResponse {
+            valid: true,
+            analysis: Some(analysis),
+            errors: Vec::new(),
+        },
+        Err(e) => AnalyzeSqlResponse {
+            valid: false,
+            analysis: None,
+            errors: vec![AnalyzeSqlError {
+                line: e.line,
+                message: e.message,
+            }],
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::post, Router};
+    use tower::Service;
+
+    async fn post_sql(sql: &str) -> (StatusCode, serde_json::Value) {
+        let mut app = Router::new().route("/analyze/sql", post(analyze_sql));
+        let request = Request::post("/analyze/sql")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "sql": sql }).to_string()))
+            .unwrap();
+        let response = app.call(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_analyze_related_tables() {
+        let (status, json) = post_sql(
+            "CREATE TABLE posts (id SERIAL PRIMARY KEY, user_id INT REFERENCES users(id));\n\
+             CREATE TABLE users (id SERIAL PRIMARY KEY);",
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["valid"], true);
+        let analysis = &json["analysis"];
+        assert_eq!(analysis["creation_order"], serde_json::json!(["users", "posts"]));
+
+        let posts = analysis["tables"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|t| t["name"] == "posts")
+            .unwrap();
+        let fk = &posts["foreign_keys"][0];
+        assert_eq!(fk["from_column"], "user_id");
+        assert_eq!(fk["to_table"], "users");
+        assert_eq!(fk["to_column"], "id");
+        assert!(analysis["circular_dependencies"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_analyze_reports_parse_error() {
+        let (status, json) = post_sql("CREATE TABLE users (\n  id SERIAL PRIMARY KEY;\n").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["valid"], false);
+        assert!(json.get("analysis").is_none());
+        assert_eq!(json["errors"][0]["line"], 1);
+        assert!(json["errors"][0]["message"].as_str().unwrap().contains("not closed"));
+    }
+}
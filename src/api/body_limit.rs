@@ -0,0 +1,52 @@
+//! Request body limits for JSON endpoints
+//!
+//! Applied per route to the JSON endpoints only, so the limit can be kept
+//! small without affecting schema uploads (multipart).
+
+use axum::extract::DefaultBodyLimit;
+
+/// Layer rejecting JSON bodies larger than `max_bytes` with 413 Payload Too Large
+pub fn json_body_limit(max_bytes: usize) -> DefaultBodyLimit {
+    DefaultBodyLimit::max(max_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::{Request, StatusCode}, routing::post, Json, Router};
+    use tower::Service;
+
+    fn app(max_bytes: usize) -> Router {
+        Router::new().route(
+            "/call",
+            post(|Json(body): Json<serde_json::Value>| async move { Json(body) })
+                .layer(json_body_limit(max_bytes)),
+        )
+    }
+
+    fn json_request(body: String) -> Request<Body> {
+        Request::post("/call")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_over_limit_json_is_rejected() {
+        let args = "x".repeat(2048);
+        let response = app(1024)
+            .call(json_request(format!(r#"{{"args":["{}"]}}"#, args)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_within_limit_json_is_accepted() {
+        let response = app(1024)
+            .call(json_request(r#"{"args":["small"]}"#.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
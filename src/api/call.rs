@@ -1,5 +1,6 @@
 use crate::error::{GatewayError, Result};
 use crate::pool::PoolManager;
+use crate::security::ClientIp;
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -24,9 +25,11 @@ pub struct CallResponse {
 
 pub async fn call_function(
     State((pool_manager, _)): State<(Arc<PoolManager>, Instant)>,
+    ClientIp(ip): ClientIp,
     Json(request): Json<CallRequest>,
 ) -> Result<impl IntoResponse> {
     let start_time = Instant::now();
+    pool_manager.check_client_ip(&request.platform, ip).await?;
 
     let db_name = pool_manager.database_name(&request.platform, request.tenant_id.as_deref());
 
@@ -49,6 +52,9 @@ pub async fn call_function(
         .get_pool(&request.platform, request.tenant_id.as_deref())
         .await?;
 
+    // Held until the response is built; over the limit, fail fast instead of queueing
+    let _call_slot = pool_manager.acquire_call_slot(&db_name)?;
+
     let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
         database: db_name.clone(),
         cause: e.to_string(),
@@ -247,6 +253,10 @@ fn row_to_json_value(row: &tokio_postgres::Row, idx: usize) -> Value {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Config;
+    use crate::registry::PlatformRegistry;
+    use crate::schema::test_db::test_database_url;
+    use tempfile::TempDir;
 
     #[test]
     fn test_valid_function_name() {
@@ -259,4 +269,36 @@ mod tests {
         assert!(!is_valid_function_name("Get_Patient")); // Contains uppercase
         assert!(!is_valid_function_name("123_fn")); // Starts with number
     }
+
+    #[tokio::test]
+    async fn test_call_rejects_ip_outside_platform_networks() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let dir = TempDir::new().unwrap();
+        let registry = PlatformRegistry::new(dir.path());
+        let mut info = registry.register_platform("call_scoped").unwrap();
+        info.allowed_networks = vec!["203.0.113.0/24".to_string()];
+        registry.save_platform_info(&info).unwrap();
+
+        let pool_manager = Arc::new(PoolManager::new(Config::for_test(&url, dir.path())).await.unwrap());
+        let request = || CallRequest {
+            platform: "call_scoped".to_string(),
+            tenant_id: None,
+            function: "get_anything".to_string(),
+            params: Vec::new(),
+        };
+
+        let outside = Some("198.51.100.7".parse().unwrap());
+        let result = call_function(State((pool_manager.clone(), Instant::now())), ClientIp(outside), Json(request())).await;
+        assert!(matches!(result, Err(GatewayError::Unauthorized { .. })));
+
+        // The platform is validated before it becomes a path under DATA_DIR
+        let escaping = CallRequest {
+            platform: "../call_scoped".to_string(),
+            ..request()
+        };
+        let result = call_function(State((pool_manager, Instant::now())), ClientIp(outside), Json(escaping)).await;
+        assert!(matches!(result, Err(GatewayError::InvalidRequest { .. })));
+    }
 }
@@ -0,0 +1,320 @@
+//! Changelog API v2 - Audit trail of what the gateway changed in a database
+//!
+//! GET /v2/changelog?platform=X&database_id=Y[&change_type=Z][&limit=N]
+//!
+//! Detail keys listed in CHANGELOG_REDACT_KEYS (e.g. checksums, source file
+//! paths) are removed from entries unless the caller presents the admin token.
+
+use crate::api::migrate_v2::MigrateV2State;
+use crate::error::{GatewayError, Result};
+use crate::schema::{ChangelogChangeType, ChangelogManager, ChangelogRecord};
+use crate::security::{has_admin_token, ClientIp};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Entries returned when no limit is given
+const DEFAULT_LIMIT: i64 = 50;
+/// Upper bound on entries per request
+const MAX_LIMIT: i64 = 500;
+
+/// Header carrying the client's deploy ID, recorded as the changelog's correlation_id
+pub(crate) const DEPLOY_ID_HEADER: &str = "x-deploy-id";
+/// Longest accepted deploy ID
+const MAX_DEPLOY_ID_LEN: usize = 200;
+
+/// Read the optional `X-Deploy-Id` header of a migrate/register request
+pub(crate) fn deploy_id(headers: &HeaderMap) -> Result<Option<String>> {
+    let Some(value) = headers.get(DEPLOY_ID_HEADER) else {
+        return Ok(None);
+    };
+
+    let id = value
+        .to_str()
+        .map_err(|_| GatewayError::InvalidRequest {
+            message: format!("{} must be printable ASCII", DEPLOY_ID_HEADER),
+        })?
+        .trim();
+    if id.len() > MAX_DEPLOY_ID_LEN {
+        return Err(GatewayError::InvalidRequest {
+            message: format!("{} is longer than {} characters", DEPLOY_ID_HEADER, MAX_DEPLOY_ID_LEN),
+        });
+    }
+
+    Ok((!id.is_empty()).then(|| id.to_string()))
+}
+
+/// What a caller may see of each entry's `change_detail`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetailAccess {
+    /// Every detail key
+    Full,
+    /// Redacted keys removed
+    Restricted,
+}
+
+/// Detail keys hidden from callers without the admin token
+#[derive(Debug, Clone, Default)]
+pub struct DetailRedaction {
+    keys: Vec<String>,
+    admin_token: Option<String>,
+}
+
+impl DetailRedaction {
+    pub fn new(keys: Vec<String>, admin_token: Option<String>) -> Self {
+        Self { keys, admin_token }
+    }
+
+    /// Access level of the caller sending `headers`
+    pub fn access(&self, headers: &HeaderMap) -> DetailAccess {
+        if has_admin_token(headers, self.admin_token.as_deref()) {
+            DetailAccess::Full
+        } else {
+            DetailAccess::Restricted
+        }
+    }
+
+    /// Remove the redacted keys from every entry's details
+    pub fn apply(&self, entries: &mut [ChangelogRecord], access: DetailAccess) {
+        if access == DetailAccess::Full || self.keys.is_empty() {
+            return;
+        }
+        for entry in entries {
+            if let Some(serde_json::Value::Object(detail)) = entry.change_detail.as_mut() {
+                for key in &self.keys {
+                    detail.remove(key);
+                }
+            }
+        }
+    }
+}
+
+/// State of the changelog endpoint
+pub struct ChangelogState {
+    pub migrate_v2: Arc<MigrateV2State>,
+    pub redaction: DetailRedaction,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangelogQuery {
+    pub platform: String,
+    /// "main" for the main database, or a tenant ID
+    pub database_id: String,
+    /// Only entries of this type (e.g. "migration_applied")
+    pub change_type: Option<ChangelogChangeType>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct ChangelogResponse {
+    platform: String,
+    database: String,
+    entries: Vec<ChangelogRecord>,
+}
+
+pub async fn get_changelog_v2(
+    State(state): State<Arc<ChangelogState>>,
+    headers: HeaderMap,
+    Query(query): Query<ChangelogQuery>,
+    ClientIp(ip): ClientIp,
+) -> Result<impl IntoResponse> {
+    state.migrate_v2.platform_state.registry.check_client_ip(&query.platform, ip)?;
+    let response = changelog_for_caller(&state, &headers, query).await?;
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Entries matching `query`, redacted for the caller sending `headers`
+async fn changelog_for_caller(
+    state: &ChangelogState,
+    headers: &HeaderMap,
+    query: ChangelogQuery,
+) -> Result<ChangelogResponse> {
+    let mut response = get_changelog(&state.migrate_v2, query).await?;
+    state.redaction.apply(&mut response.entries, state.redaction.access(headers));
+    Ok(response)
+}
+
+async fn get_changelog(state: &MigrateV2State, query: ChangelogQuery) -> Result<ChangelogResponse> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(GatewayError::InvalidRequest {
+            message: format!("limit must be between 1 and {}, got {}", MAX_LIMIT, limit),
+        });
+    }
+
+    let db_name = if query.database_id == "main" {
+        format!("{}_main", query.platform)
+    } else {
+        format!("{}_{}", query.platform, query.database_id)
+    };
+
+    if !state.pool_manager.database_exists(&db_name).await? {
+        return Err(GatewayError::DatabaseNotFound {
+            platform: query.platform,
+            tenant_id: Some(query.database_id),
+        });
+    }
+
+    let pool = state.pool_manager.get_pool_by_name(&db_name).await?;
+    let changelog_manager = ChangelogManager::new();
+    // Databases that were never migrated have no changelog table yet
+    changelog_manager.ensure_changelog_table(&pool, &db_name).await?;
+
+    let entries = match query.change_type {
+        Some(change_type) => {
+            changelog_manager
+                .get_entries_by_type(&pool, &db_name, &change_type.to_string(), limit)
+                .await?
+        }
+        None => changelog_manager.get_recent_entries(&pool, &db_name, limit).await?,
+    };
+
+    Ok(ChangelogResponse {
+        platform: query.platform,
+        database: db_name,
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::platform::PlatformState;
+    use crate::config::Config;
+    use crate::pool::PoolManager;
+    use crate::schema::test_db::test_database_url;
+
+    async fn state(url: &str, data_dir: &std::path::Path) -> MigrateV2State {
+        MigrateV2State {
+            pool_manager: Arc::new(PoolManager::new(Config::for_test(url, data_dir)).await.unwrap()),
+            platform_state: Arc::new(PlatformState::new(data_dir, false, true)),
+        }
+    }
+
+    fn parse(params: &str) -> std::result::Result<ChangelogQuery, String> {
+        let uri: axum::http::Uri = format!("/v2/changelog?{}", params).parse().unwrap();
+        Query::<ChangelogQuery>::try_from_uri(&uri)
+            .map(|Query(query)| query)
+            .map_err(|e| e.to_string())
+    }
+
+    fn query(params: &str) -> ChangelogQuery {
+        parse(params).unwrap()
+    }
+
+    #[test]
+    fn test_change_type_parsed_from_query() {
+        let parsed = query("platform=myapp&database_id=main&change_type=migration_applied&limit=10");
+        assert!(matches!(parsed.change_type, Some(ChangelogChangeType::MigrationApplied)));
+        assert_eq!(parsed.limit, Some(10));
+        assert!(parse("platform=a&database_id=b&change_type=bogus").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_changelog_filters_and_limits() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let state = state(&url, data_dir.path()).await;
+        let pool_manager = &state.pool_manager;
+        pool_manager.drop_database("changelogtest_t1", true).await.ok();
+        pool_manager.create_database("changelogtest_t1", &Default::default()).await.unwrap();
+
+        let pool = pool_manager.get_pool_by_name("changelogtest_t1").await.unwrap();
+        let manager = ChangelogManager::new();
+        manager.ensure_changelog_table(&pool, "changelogtest_t1").await.unwrap();
+        manager.log_migration(&pool, "changelogtest_t1", "001_init.pssql", "abc").await.unwrap();
+        manager.log_migration(&pool, "changelogtest_t1", "002_next.pssql", "def").await.unwrap();
+        manager
+            .log_function_deployed(&pool, "changelogtest_t1", "get_items", "get_items()", "123", "functions/get_items.pssql")
+            .await
+            .unwrap();
+
+        let all = get_changelog(&state, query("platform=changelogtest&database_id=t1"))
+            .await
+            .unwrap();
+        assert_eq!(all.database, "changelogtest_t1");
+        assert_eq!(all.entries.len(), 3);
+
+        let migrations = get_changelog(
+            &state,
+            query("platform=changelogtest&database_id=t1&change_type=migration_applied"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(migrations.entries.len(), 2);
+        assert!(migrations.entries.iter().all(|e| e.change_type == "migration_applied"));
+        assert_eq!(migrations.entries[0].change_detail.as_ref().unwrap()["checksum"], "def");
+
+        let limited = get_changelog(&state, query("platform=changelogtest&database_id=t1&limit=1"))
+            .await
+            .unwrap();
+        assert_eq!(limited.entries.len(), 1);
+
+        let too_many = get_changelog(&state, query("platform=changelogtest&database_id=t1&limit=501")).await;
+        assert!(matches!(too_many, Err(GatewayError::InvalidRequest { .. })));
+
+        let missing = get_changelog(&state, query("platform=changelogtest&database_id=nope")).await;
+        assert!(matches!(missing, Err(GatewayError::DatabaseNotFound { .. })));
+
+        pool_manager.drop_database("changelogtest_t1", true).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restricted_caller_gets_redacted_details() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let state = ChangelogState {
+            migrate_v2: Arc::new(state(&url, data_dir.path()).await),
+            redaction: DetailRedaction::new(
+                vec!["checksum".to_string(), "source_file".to_string()],
+                Some("admin-secret".to_string()),
+            ),
+        };
+        let pool_manager = &state.migrate_v2.pool_manager;
+        pool_manager.drop_database("changelogredact_t1", true).await.ok();
+        pool_manager.create_database("changelogredact_t1", &Default::default()).await.unwrap();
+
+        let pool = pool_manager.get_pool_by_name("changelogredact_t1").await.unwrap();
+        let manager = ChangelogManager::new();
+        manager.ensure_changelog_table(&pool, "changelogredact_t1").await.unwrap();
+        manager
+            .log_function_deployed(&pool, "changelogredact_t1", "get_items", "get_items()", "123", "functions/get_items.pssql")
+            .await
+            .unwrap();
+
+        let restricted = changelog_for_caller(&state, &HeaderMap::new(), query("platform=changelogredact&database_id=t1"))
+            .await
+            .unwrap();
+        let detail = restricted.entries[0].change_detail.as_ref().unwrap();
+        assert!(detail.get("checksum").is_none(), "{}", detail);
+        assert!(detail.get("source_file").is_none(), "{}", detail);
+        assert_eq!(detail["signature"], "get_items()");
+
+        // A wrong token is just another restricted caller
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer wrong".parse().unwrap());
+        let wrong = changelog_for_caller(&state, &headers, query("platform=changelogredact&database_id=t1"))
+            .await
+            .unwrap();
+        assert!(wrong.entries[0].change_detail.as_ref().unwrap().get("checksum").is_none());
+
+        headers.insert("authorization", "Bearer admin-secret".parse().unwrap());
+        let admin = changelog_for_caller(&state, &headers, query("platform=changelogredact&database_id=t1"))
+            .await
+            .unwrap();
+        let detail = admin.entries[0].change_detail.as_ref().unwrap();
+        assert_eq!(detail["checksum"], "123");
+        assert_eq!(detail["source_file"], "functions/get_items.pssql");
+
+        pool_manager.drop_database("changelogredact_t1", true).await.unwrap();
+    }
+}
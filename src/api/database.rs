@@ -5,9 +5,10 @@
 use crate::api::platform::PlatformState;
 use crate::error::{GatewayError, Result};
 use crate::pool::PoolManager;
+use crate::security::ClientIp;
 use crate::schema::{
-    ChangelogManager, CustomTypeManager, ExtensionManager, FunctionDeployer, SeederRunner,
-    TableDeployer,
+    ChangelogManager, CustomTypeManager, ExtensionManager, FunctionDeployer, GrantManager, MatviewDeployer, PolicyManager,
+    SeederRunner, TableDeployer, UpdatedAtTriggerManager,
 };
 use axum::{
     extract::State,
@@ -16,6 +17,7 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::info;
@@ -38,6 +40,9 @@ pub struct SeederInfo {
     table: String,
     inserted: usize,
     skipped: usize,
+    /// Rows the table already had, when that made the seeder skip records
+    #[serde(skip_serializing_if = "Option::is_none")]
+    existing_rows: Option<i64>,
 }
 
 #[derive(Serialize)]
@@ -56,6 +61,7 @@ pub struct CreateDatabaseResponse {
 
 pub async fn create_database(
     State(state): State<Arc<DatabaseState>>,
+    ClientIp(ip): ClientIp,
     Json(request): Json<CreateDatabaseRequest>,
 ) -> Result<impl IntoResponse> {
     let start_time = Instant::now();
@@ -69,6 +75,7 @@ pub async fn create_database(
             ),
         });
     }
+    state.platform_state.registry.check_client_ip(&request.platform, ip)?;
 
     // Check schema exists
     if !state
@@ -106,16 +113,115 @@ pub async fn create_database(
         return Err(GatewayError::DatabaseAlreadyExists { database: db_name });
     }
 
+    let provisioned = provision_database(
+        &state.pool_manager,
+        &state.platform_state,
+        &request.platform,
+        &request.schema_name,
+        &db_name,
+        None,
+    )
+    .await?;
+
+    let execution_time_ms = start_time.elapsed().as_millis() as u64;
+    let total_seeded: usize = provisioned.seeders.iter().map(|s| s.inserted).sum();
+
+    info!(
+        "Database '{}' created: {} extensions, {} types, {} tables, {} functions, {} seeder records in {}ms",
+        db_name,
+        provisioned.extensions_installed,
+        provisioned.types_deployed,
+        provisioned.tables_created,
+        provisioned.functions_deployed,
+        total_seeded,
+        execution_time_ms
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateDatabaseResponse {
+            status: "created".to_string(),
+            platform: request.platform,
+            schema_name: request.schema_name,
+            database_name: db_name,
+            extensions_installed: provisioned.extensions_installed,
+            types_deployed: provisioned.types_deployed,
+            tables_created: provisioned.tables_created,
+            functions_deployed: provisioned.functions_deployed,
+            seeders: provisioned.seeders,
+            execution_time_ms,
+        }),
+    ))
+}
+
+/// Summary of a freshly provisioned database
+pub struct ProvisionedDatabase {
+    pub extensions_installed: usize,
+    pub types_deployed: usize,
+    pub tables_created: usize,
+    pub functions_deployed: usize,
+    pub seeders: Vec<SeederInfo>,
+}
+
+/// Create a database and provision it from a stored schema
+///
+/// Runs extensions -> types -> tables -> functions -> seeders and records the
+/// database in the platform registry. Changelog entries are recorded with
+/// `correlation_id` when the request carried one.
+pub(crate) async fn provision_database(
+    pool_manager: &PoolManager,
+    platform_state: &PlatformState,
+    platform: &str,
+    schema_name: &str,
+    db_name: &str,
+    correlation_id: Option<&str>,
+) -> Result<ProvisionedDatabase> {
+    let schema_dir = platform_state.schema_store.schema_dir(platform, schema_name);
+    let schema_version = platform_state
+        .schema_store
+        .current_version(platform, schema_name)?;
+
+    provision_database_from_dir(
+        pool_manager,
+        platform_state,
+        platform,
+        schema_name,
+        &schema_dir,
+        schema_version.as_deref(),
+        db_name,
+        correlation_id,
+    )
+    .await
+}
+
+/// Create a database and provision it from a schema directory
+///
+/// `schema_dir` is the stored schema or a restored older version of it;
+/// `schema_version` is recorded for the database in the platform registry.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn provision_database_from_dir(
+    pool_manager: &PoolManager,
+    platform_state: &PlatformState,
+    platform: &str,
+    schema_name: &str,
+    schema_dir: &Path,
+    schema_version: Option<&str>,
+    db_name: &str,
+    correlation_id: Option<&str>,
+) -> Result<ProvisionedDatabase> {
     // Create new database
-    state.pool_manager.create_database(&db_name).await?;
+    let platform_info = platform_state.registry.get_platform_info(platform)?;
+    pool_manager
+        .create_database(db_name, &platform_info.database_options)
+        .await?;
 
     // Get pool for this database
-    let pool = state.pool_manager.get_pool_by_name(&db_name).await?;
+    let pool = pool_manager.get_pool_by_name(db_name).await?;
 
     // Initialize changelog table
-    let changelog_manager = ChangelogManager::new();
+    let changelog_manager = ChangelogManager::with_correlation_id(correlation_id.map(str::to_string));
     changelog_manager
-        .ensure_changelog_table(&pool, &db_name)
+        .ensure_changelog_table(&pool, db_name)
         .await?;
 
     // Install extensions
@@ -123,11 +229,9 @@ pub async fn create_database(
     let extensions_installed = extension_manager
         .install_extensions(
             &pool,
-            &db_name,
-            &state
-                .platform_state
-                .schema_store
-                .extensions_dir(&request.platform, &request.schema_name),
+            db_name,
+            &schema_dir.join("extensions"),
+            &changelog_manager,
         )
         .await?;
 
@@ -136,11 +240,8 @@ pub async fn create_database(
     let types_deployed = type_manager
         .deploy_types(
             &pool,
-            &db_name,
-            &state
-                .platform_state
-                .schema_store
-                .types_dir(&request.platform, &request.schema_name),
+            db_name,
+            &schema_dir.join("types"),
         )
         .await?;
 
@@ -149,37 +250,50 @@ pub async fn create_database(
     let tables_created = table_deployer
         .deploy_tables(
             &pool,
-            &db_name,
-            &state
-                .platform_state
-                .schema_store
-                .tables_dir(&request.platform, &request.schema_name),
+            db_name,
+            &schema_dir.join("tables"),
         )
         .await?;
 
+    // Keep updated_at columns current, for platforms that opt in
+    if platform_info.managed_updated_at {
+        UpdatedAtTriggerManager::new()
+            .deploy_triggers(&pool, db_name, &schema_dir.join("tables"))
+            .await?;
+    }
+
     // Deploy functions
     let function_deployer = FunctionDeployer::new();
     let functions_deployed = function_deployer
         .deploy_functions(
             &pool,
-            &db_name,
-            &state
-                .platform_state
-                .schema_store
-                .functions_dir(&request.platform, &request.schema_name),
+            db_name,
+            &schema_dir.join("functions"),
         )
         .await?;
 
+    // Enable row-level security and create policies
+    PolicyManager::new()
+        .deploy_policies(&pool, db_name, &schema_dir.join("policies"))
+        .await?;
+
+    // Create materialized views after the tables and functions they select from
+    MatviewDeployer::new()
+        .deploy_matviews(&pool, db_name, &schema_dir.join("matviews"))
+        .await?;
+
+    // Apply privileges once every object exists
+    GrantManager::new()
+        .deploy_grants(&pool, db_name, &schema_dir.join("grants"))
+        .await?;
+
     // Run seeders
     let seeder_runner = SeederRunner::new();
     let seeder_results = seeder_runner
         .run_seeders_on_register(
             &pool,
-            &db_name,
-            &state
-                .platform_state
-                .schema_store
-                .seeders_dir(&request.platform, &request.schema_name),
+            db_name,
+            &schema_dir.join("seeders"),
         )
         .await?;
 
@@ -189,26 +303,21 @@ pub async fn create_database(
             table: r.table,
             inserted: r.inserted,
             skipped: r.skipped,
+            existing_rows: (r.existing_rows > 0).then_some(r.existing_rows),
         })
         .collect();
 
-    let total_seeded: usize = seeders.iter().map(|s| s.inserted).sum();
-
     // Record database in platform registry
-    state.platform_state.registry.record_database(
-        &request.platform,
-        &request.schema_name,
-        &db_name,
-    )?;
-
-    let execution_time_ms = start_time.elapsed().as_millis() as u64;
+    platform_state
+        .registry
+        .record_database(platform, schema_name, db_name, schema_version)?;
 
     // Log to changelog
     if extensions_installed > 0 {
         changelog_manager
             .log_extension_installed(
                 &pool,
-                &db_name,
+                db_name,
                 &format!("{} extensions", extensions_installed),
                 None,
                 None,
@@ -220,7 +329,7 @@ pub async fn create_database(
         changelog_manager
             .log_migration(
                 &pool,
-                &db_name,
+                db_name,
                 &format!("{} tables created", tables_created),
                 "create",
             )
@@ -231,7 +340,7 @@ pub async fn create_database(
         changelog_manager
             .log_function_deployed(
                 &pool,
-                &db_name,
+                db_name,
                 &format!("{} functions", functions_deployed),
                 "batch",
                 "batch",
@@ -243,30 +352,24 @@ pub async fn create_database(
     for seeder in &seeders {
         if seeder.inserted > 0 {
             changelog_manager
-                .log_seeder_run(&pool, &db_name, &seeder.table, seeder.inserted, seeder.skipped)
+                .log_seeder_run(&pool, db_name, &seeder.table, seeder.inserted, seeder.skipped)
+                .await
+                .ok();
+        }
+        // Recorded so a failing seeder validation on migrate can say why
+        if let Some(rows) = seeder.existing_rows {
+            changelog_manager
+                .log_seeder_skipped(&pool, db_name, &seeder.table, "table not empty", rows, seeder.skipped)
                 .await
                 .ok();
         }
     }
 
-    info!(
-        "Database '{}' created: {} extensions, {} types, {} tables, {} functions, {} seeder records in {}ms",
-        db_name, extensions_installed, types_deployed, tables_created, functions_deployed, total_seeded, execution_time_ms
-    );
-
-    Ok((
-        StatusCode::CREATED,
-        Json(CreateDatabaseResponse {
-            status: "created".to_string(),
-            platform: request.platform,
-            schema_name: request.schema_name,
-            database_name: db_name,
-            extensions_installed,
-            types_deployed,
-            tables_created,
-            functions_deployed,
-            seeders,
-            execution_time_ms,
-        }),
-    ))
+    Ok(ProvisionedDatabase {
+        extensions_installed,
+        types_deployed,
+        tables_created,
+        functions_deployed,
+        seeders,
+    })
 }
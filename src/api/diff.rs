@@ -0,0 +1,277 @@
+//! Schema diff API v2 - Preview declarative table drift
+//!
+//! POST /v2/diff - Compare a database's tables with the stored schema's tables/
+//! directory without running any DDL
+
+use crate::api::migrate_v2::{diff_to_validation_info, migration_sql, MigrateV2State, SchemaValidationInfo};
+use crate::error::{GatewayError, Result};
+use crate::schema::{MigrationGenerator, SchemaDiffChecker};
+use crate::security::ClientIp;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub struct DiffRequest {
+    pub platform: String,
+    pub schema_name: String,
+    /// Database to compare against ("main" or a tenant ID)
+    pub database_id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DiffQuery {
+    /// Leave `safe_changes` out of the response, for concise CI output
+    #[serde(default)]
+    pub blocking_only: bool,
+    /// Report a one-to-one dropped+added column of compatible type as a rename
+    #[serde(default)]
+    pub detect_renames: bool,
+    /// Add the rebuild of tables with column order drift to `migration_sql`
+    #[serde(default)]
+    pub rebuild_column_order: bool,
+}
+
+#[derive(Serialize)]
+pub struct DiffResponse {
+    platform: String,
+    schema_name: String,
+    database: String,
+    has_changes: bool,
+    /// No data-loss or incompatible changes
+    is_safe: bool,
+    schema_diff: SchemaValidationInfo,
+    execution_time_ms: u64,
+}
+
+pub async fn diff_schema_v2(
+    State(state): State<Arc<MigrateV2State>>,
+    Query(query): Query<DiffQuery>,
+    ClientIp(ip): ClientIp,
+    Json(request): Json<DiffRequest>,
+) -> Result<impl IntoResponse> {
+    state.platform_state.registry.check_client_ip(&request.platform, ip)?;
+    let response = diff_schema(&state, request, &query).await?;
+    Ok((StatusCode::OK, Json(response)))
+}
+
+async fn diff_schema(state: &MigrateV2State, request: DiffRequest, query: &DiffQuery) -> Result<DiffResponse> {
+    let start_time = Instant::now();
+    let schema_store = &state.platform_state.schema_store;
+
+    // Check platform is registered
+    if !state.platform_state.registry.is_registered(&request.platform) {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Platform '{}' is not registered. Register it first.",
+                request.platform
+            ),
+        });
+    }
+
+    // Check schema exists
+    if !schema_store.schema_exists(&request.platform, &request.schema_name) {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Schema '{}' not found for platform '{}'. Register the schema first.",
+                request.schema_name, request.platform
+            ),
+        });
+    }
+
+    let db_name = if request.database_id == "main" {
+        format!("{}_main", request.platform)
+    } else {
+        format!("{}_{}", request.platform, request.database_id)
+    };
+
+    if !state.pool_manager.database_exists(&db_name).await? {
+        return Err(GatewayError::DatabaseNotFound {
+            platform: request.platform.clone(),
+            tenant_id: Some(request.database_id.clone()),
+        });
+    }
+
+    let tables_dir = schema_store.tables_dir(&request.platform, &request.schema_name);
+    let diff_checker = SchemaDiffChecker::new();
+    let desired = diff_checker.parse_desired_schema(&tables_dir)?;
+
+    // Read-only: the pool is only used for catalog queries
+    let pool = state.pool_manager.get_pool_by_name(&db_name).await?;
+    let current = diff_checker.query_current_schema(&pool, &db_name).await?;
+
+    let unmanaged = SchemaDiffChecker::load_unmanaged_tables(&tables_dir);
+    let diff = diff_checker.diff_schemas_with_renames(&desired, &current, &unmanaged, query.detect_renames);
+
+    let mut schema_diff = diff_to_validation_info(&diff);
+    let generator = MigrationGenerator::from_directory(&tables_dir)?;
+    schema_diff.migration_sql = migration_sql(&generator, &diff, query.rebuild_column_order);
+    if query.blocking_only {
+        schema_diff.safe_changes = None;
+    }
+
+    info!(
+        "Schema diff for '{}' against '{}': {} safe, {} dataloss, {} incompatible",
+        db_name,
+        request.schema_name,
+        diff.safe_changes.len(),
+        diff.dataloss_changes.len(),
+        diff.incompatible_changes.len()
+    );
+
+    Ok(DiffResponse {
+        platform: request.platform,
+        schema_name: request.schema_name,
+        database: db_name,
+        has_changes: diff.has_changes(),
+        is_safe: diff.is_safe(),
+        schema_diff,
+        execution_time_ms: start_time.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::database::provision_database;
+    use crate::api::platform::PlatformState;
+    use crate::config::Config;
+    use crate::pool::PoolManager;
+    use crate::schema::test_db::test_database_url;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn archive_with(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut archive_data = Vec::new();
+        {
+            let encoder = GzEncoder::new(&mut archive_data, Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            for (path, content) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_path(path).unwrap();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append(&header, content.as_bytes()).unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        archive_data
+    }
+
+    #[tokio::test]
+    async fn test_diff_reports_drift_without_applying_it() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let pool_manager = Arc::new(PoolManager::new(Config::for_test(&url, data_dir.path())).await.unwrap());
+        let platform_state = Arc::new(PlatformState::new(data_dir.path(), false, true));
+        pool_manager.drop_database("difftest_t1", true).await.ok();
+
+        platform_state.registry.register_platform("difftest").unwrap();
+        platform_state
+            .schema_store
+            .store_schema(
+                "difftest",
+                "tenant_db",
+                &archive_with(&[("tables/items.pssql", "CREATE TABLE items (id SERIAL PRIMARY KEY, legacy TEXT);")]),
+            )
+            .unwrap();
+        provision_database(&pool_manager, &platform_state, "difftest", "tenant_db", "difftest_t1", None)
+            .await
+            .unwrap();
+
+        platform_state
+            .schema_store
+            .store_schema(
+                "difftest",
+                "tenant_db",
+                &archive_with(&[(
+                    "tables/items.pssql",
+                    "CREATE TABLE items (id SERIAL PRIMARY KEY, name TEXT);\nCREATE INDEX idx_items_name ON items (name);",
+                )]),
+            )
+            .unwrap();
+
+        let state = MigrateV2State {
+            pool_manager: pool_manager.clone(),
+            platform_state: platform_state.clone(),
+        };
+        let request = || DiffRequest {
+            platform: "difftest".to_string(),
+            schema_name: "tenant_db".to_string(),
+            database_id: "t1".to_string(),
+        };
+        let response = diff_schema(&state, request(), &DiffQuery::default()).await.unwrap();
+
+        assert!(response.has_changes);
+        assert!(!response.is_safe);
+        let json = serde_json::to_value(&response.schema_diff).unwrap();
+        let find = |kind: &str, change_type: &str| {
+            json[kind]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|c| c["change_type"] == change_type)
+                .cloned()
+        };
+        assert_eq!(find("safe_changes", "AddColumn").unwrap()["column"], "name");
+        assert_eq!(find("dataloss_changes", "DropColumn").unwrap()["column"], "legacy");
+        assert_eq!(find("safe_changes", "AddIndex").unwrap()["column"], "idx_items_name");
+        // The primary key's index belongs to the constraint, not to CREATE INDEX
+        assert!(find("dataloss_changes", "DropIndex").is_none());
+        // The live primary key is read and matches the desired one
+        assert!(find("dataloss_changes", "ModifyPrimaryKey").is_none());
+        assert!(find("incompatible_changes", "ModifyPrimaryKey").is_none());
+
+        // Nothing was applied: a second diff reports the same drift
+        let again = diff_schema(&state, request(), &DiffQuery::default()).await.unwrap();
+        let again = serde_json::to_value(&again.schema_diff).unwrap();
+        for kind in ["safe_changes", "dataloss_changes", "incompatible_changes"] {
+            assert_eq!(again[kind].as_array().unwrap().len(), json[kind].as_array().unwrap().len());
+        }
+
+        // blocking_only drops the safe changes but keeps the data-loss ones
+        let query = DiffQuery {
+            blocking_only: true,
+            ..Default::default()
+        };
+        let blocking = diff_schema(&state, request(), &query).await.unwrap();
+        assert!(!blocking.is_safe);
+        let blocking = serde_json::to_value(&blocking.schema_diff).unwrap();
+        assert!(blocking.get("safe_changes").is_none());
+        assert_eq!(blocking["dataloss_changes"], json["dataloss_changes"]);
+
+        // legacy -> name (both TEXT) looks like a rename once detection is on
+        let query = DiffQuery {
+            detect_renames: true,
+            ..Default::default()
+        };
+        let renamed = diff_schema(&state, request(), &query).await.unwrap();
+        assert!(renamed.is_safe);
+        let renamed = serde_json::to_value(&renamed.schema_diff).unwrap();
+        let rename = renamed["safe_changes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["change_type"] == "RenameColumn")
+            .cloned()
+            .unwrap();
+        assert_eq!(rename["from_type"], "legacy");
+        assert_eq!(rename["to_type"], "name");
+        assert!(renamed["migration_sql"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("ALTER TABLE items RENAME COLUMN legacy TO name;")));
+
+        pool_manager.drop_database("difftest_t1", true).await.unwrap();
+    }
+}
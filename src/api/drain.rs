@@ -0,0 +1,167 @@
+//! Draining of migrate/register operations on shutdown
+//!
+//! Operations that change schemas are counted while they run. On shutdown the
+//! gateway stops accepting new ones (503) and waits, up to a grace period, for
+//! the running ones to finish before the server stops.
+
+use crate::error::GatewayError;
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+#[derive(Default)]
+struct DrainInner {
+    active: AtomicUsize,
+    draining: AtomicBool,
+    /// Notified whenever the last active operation finishes
+    idle: Notify,
+}
+
+/// Counts in-flight operations and blocks new ones once draining
+#[derive(Clone, Default)]
+pub struct DrainTracker {
+    inner: Arc<DrainInner>,
+}
+
+/// Keeps an operation counted until dropped
+pub struct OperationGuard {
+    inner: Arc<DrainInner>,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if self.inner.active.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+impl DrainTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of operations currently running
+    pub fn active(&self) -> usize {
+        self.inner.active.load(Ordering::SeqCst)
+    }
+
+    /// Count a new operation, or None once shutdown has started
+    pub fn begin(&self) -> Option<OperationGuard> {
+        self.inner.active.fetch_add(1, Ordering::SeqCst);
+        if self.inner.draining.load(Ordering::SeqCst) {
+            // Undo through the guard so a waiting drain still sees zero
+            drop(OperationGuard {
+                inner: self.inner.clone(),
+            });
+            return None;
+        }
+        Some(OperationGuard {
+            inner: self.inner.clone(),
+        })
+    }
+
+    /// Reject new operations and wait up to `grace` for running ones
+    ///
+    /// Returns the number of operations still running when it gave up.
+    pub async fn drain(&self, grace: Duration) -> usize {
+        self.inner.draining.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + grace;
+
+        loop {
+            // Register before checking, so a finish in between is not missed
+            let idle = self.inner.idle.notified();
+            let active = self.active();
+            if active == 0 {
+                return 0;
+            }
+            if tokio::time::timeout_at(deadline, idle).await.is_err() {
+                return self.active();
+            }
+        }
+    }
+}
+
+/// Middleware counting the request as an operation; 503 while shutting down
+pub async fn track_operation(State(tracker): State<DrainTracker>, request: Request, next: Next) -> Response {
+    match tracker.begin() {
+        Some(_guard) => next.run(request).await,
+        None => GatewayError::ShuttingDown.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::post, Router};
+    use tower::Service;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight_operation() {
+        let tracker = DrainTracker::new();
+        let guard = tracker.begin().unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            drop(guard);
+        });
+
+        let started = Instant::now();
+        assert_eq!(tracker.drain(Duration::from_secs(5)).await, 0);
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_millis(200), "{:?}", waited);
+        assert!(waited < Duration::from_secs(5), "{:?}", waited);
+    }
+
+    #[tokio::test]
+    async fn test_drain_gives_up_after_grace_period() {
+        let tracker = DrainTracker::new();
+        let _guard = tracker.begin().unwrap();
+
+        let started = Instant::now();
+        assert_eq!(tracker.drain(Duration::from_millis(200)).await, 1);
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_drain_without_operations_returns_immediately() {
+        let tracker = DrainTracker::new();
+        assert_eq!(tracker.drain(Duration::from_secs(5)).await, 0);
+        assert!(tracker.begin().is_none());
+        assert_eq!(tracker.active(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_new_operations_rejected_while_draining() {
+        let tracker = DrainTracker::new();
+        let mut app = Router::new()
+            .route("/v2/migrate", post(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(tracker.clone(), track_operation));
+
+        let request = || Request::post("/v2/migrate").body(Body::empty()).unwrap();
+        assert_eq!(app.call(request()).await.unwrap().status(), StatusCode::OK);
+
+        let guard = tracker.begin().unwrap();
+        let drain = tokio::spawn({
+            let tracker = tracker.clone();
+            async move { tracker.drain(Duration::from_secs(5)).await }
+        });
+        tokio::task::yield_now().await;
+        while tracker.begin().is_some() {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(
+            app.call(request()).await.unwrap().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        drop(guard);
+        assert_eq!(drain.await.unwrap(), 0);
+    }
+}
@@ -1,12 +1,17 @@
 use crate::pool::PoolManager;
-use axum::{extract::State, Json};
+use axum::{extract::State, http::StatusCode, Json};
+use deadpool_postgres::Pool;
 use serde::Serialize;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Version from Cargo.toml
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Upper bound for the readiness ping, so a hung database fails the probe
+/// instead of stalling it
+const READY_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Serialize)]
 pub struct HealthResponse {
     status: String,
@@ -36,3 +41,83 @@ pub async fn health_check(
         uptime_seconds: start_time.elapsed().as_secs(),
     })
 }
+
+#[derive(Serialize)]
+pub struct ReadinessResponse {
+    status: String,
+    version: &'static str,
+    active_pools: usize,
+    total_connections: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Readiness probe: 200 once `SELECT 1` succeeds on the admin pool, 503 otherwise
+pub async fn readiness_check(
+    State((pool_manager, _start_time)): State<(Arc<PoolManager>, Instant)>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let result = ping(pool_manager.admin_pool(), READY_TIMEOUT).await;
+
+    let status = if result.is_ok() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(ReadinessResponse {
+            status: if result.is_ok() { "ready" } else { "unavailable" }.to_string(),
+            version: VERSION,
+            active_pools: pool_manager.active_pools(),
+            total_connections: pool_manager.total_connections(),
+            error: result.err(),
+        }),
+    )
+}
+
+/// Run `SELECT 1` on a pooled connection within `timeout`
+async fn ping(pool: &Pool, timeout: Duration) -> Result<(), String> {
+    let attempt = async {
+        let client = pool.get().await.map_err(|e| e.to_string())?;
+        client.execute("SELECT 1", &[]).await.map_err(|e| e.to_string())?;
+        Ok(())
+    };
+
+    tokio::time::timeout(timeout, attempt)
+        .await
+        .unwrap_or_else(|_| Err(format!("database ping timed out after {}s", timeout.as_secs())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::schema::test_db::test_database_url;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_ping_unreachable_database_fails() {
+        let mut cfg = deadpool_postgres::Config::new();
+        cfg.url = Some("postgres://postgres@127.0.0.1:1/postgres".to_string());
+        let pool = cfg
+            .create_pool(Some(deadpool_postgres::Runtime::Tokio1), tokio_postgres::NoTls)
+            .unwrap();
+
+        assert!(ping(&pool, Duration::from_secs(2)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_readiness_check_reports_ready() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let temp_dir = TempDir::new().unwrap();
+        let pool_manager = Arc::new(PoolManager::new(Config::for_test(&url, temp_dir.path())).await.unwrap());
+
+        let (status, Json(body)) = readiness_check(State((pool_manager, Instant::now()))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.status, "ready");
+        assert!(body.error.is_none());
+    }
+}
@@ -1,12 +1,16 @@
+use crate::api::changelog::deploy_id;
+use crate::api::multipart::reject_unknown_field;
 use crate::error::{GatewayError, Result};
 use crate::pool::PoolManager;
 use crate::schema::{
-    ChangeCompatibility, ChangelogManager, FunctionDeployer, MigrationRunner, SchemaExtractor,
-    SchemaDiff, SchemaDiffChecker, SchemaVerifier,
+    ChangeCompatibility, ChangelogManager, FunctionDeployer, GrantManager, MatviewDeployer, MigrationRunner, PolicyManager,
+    RolloutCheckpoint,
+    SchemaExtractor, SchemaDiff, SchemaDiffChecker, SchemaVerifier,
 };
+use crate::security::ClientIp;
 use axum::{
     extract::State,
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -48,13 +52,28 @@ pub struct VerificationInfo {
     types_verified: bool,
     tables_verified: bool,
     seeders_verified: bool,
+    policies_verified: bool,
+    matviews_verified: bool,
+    grants_verified: bool,
     error_log: Option<String>,
 }
 
+#[derive(Serialize)]
+pub struct DatabaseFailureInfo {
+    database: String,
+    error: String,
+}
+
 #[derive(Serialize)]
 pub struct MigrateResponse {
     status: String,
     databases_updated: Vec<String>,
+    /// Databases already completed for this schema by an earlier run (resume=true)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    databases_skipped: Vec<String>,
+    /// Databases that failed while continue_on_error=true
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    databases_failed: Vec<DatabaseFailureInfo>,
     migrations_applied: usize,
     functions_updated: usize,
     seeder_validations: Vec<SeederValidationInfo>,
@@ -65,14 +84,20 @@ pub struct MigrateResponse {
 
 pub async fn migrate_schema(
     State((pool_manager, _)): State<(Arc<PoolManager>, Instant)>,
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
     mut multipart: Multipart,
 ) -> Result<impl IntoResponse> {
     let start_time = Instant::now();
+    let deploy_id = deploy_id(&headers)?;
 
     let mut platform: Option<String> = None;
     let mut tenant_id: Option<String> = None;
     let mut schema_data: Option<Vec<u8>> = None;
     let mut force: bool = false;
+    let mut continue_on_error: bool = false;
+    let mut resume: bool = false;
+    let mut allow_checksum_drift: bool = false;
 
     // Parse multipart form
     while let Some(field) = multipart.next_field().await.map_err(|e| {
@@ -124,8 +149,47 @@ pub async fn migrate_schema(
                     })?;
                 force = text == "true" || text == "1";
             }
+            "continue_on_error" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|e| GatewayError::InvalidRequest {
+                        message: format!("Failed to read continue_on_error field: {}", e),
+                    })?;
+                continue_on_error = text == "true" || text == "1";
+            }
+            "resume" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|e| GatewayError::InvalidRequest {
+                        message: format!("Failed to read resume field: {}", e),
+                    })?;
+                resume = text == "true" || text == "1";
+            }
+            "allow_checksum_drift" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|e| GatewayError::InvalidRequest {
+                        message: format!("Failed to read allow_checksum_drift field: {}", e),
+                    })?;
+                allow_checksum_drift = text == "true" || text == "1";
+            }
             _ => {
-                warn!("Unknown field in multipart: {}", name);
+                reject_unknown_field(
+                    &name,
+                    &[
+                        "platform",
+                        "tenant_id",
+                        "schema",
+                        "force",
+                        "continue_on_error",
+                        "resume",
+                        "allow_checksum_drift",
+                    ],
+                    pool_manager.config().strict_multipart,
+                )?;
             }
         }
     }
@@ -134,6 +198,7 @@ pub async fn migrate_schema(
     let platform = platform.ok_or_else(|| GatewayError::InvalidRequest {
         message: "Missing required field: platform".to_string(),
     })?;
+    pool_manager.check_client_ip(&platform, ip).await?;
 
     let schema_data = schema_data.ok_or_else(|| GatewayError::InvalidRequest {
         message: "Missing required field: schema".to_string(),
@@ -142,13 +207,18 @@ pub async fn migrate_schema(
     // Extract schema
     let extractor = SchemaExtractor::from_bytes(&schema_data)?;
 
-    let changelog_manager = ChangelogManager::new();
+    let changelog_manager = ChangelogManager::with_correlation_id(deploy_id.clone());
     let migration_runner = MigrationRunner::new();
     let function_deployer = FunctionDeployer::new();
+    let policy_manager = PolicyManager::new();
+    let matview_deployer = MatviewDeployer::new();
+    let grant_manager = GrantManager::new();
     let schema_verifier = SchemaVerifier::new();
     let diff_checker = SchemaDiffChecker::new();
 
     let mut databases_updated = Vec::new();
+    let mut databases_skipped = Vec::new();
+    let mut databases_failed = Vec::new();
     let mut total_migrations = 0;
     let mut total_functions = 0;
     let mut all_seeder_validations = Vec::new();
@@ -173,14 +243,14 @@ pub async fn migrate_schema(
 
         // Validate schema changes before migration (will fail if dataloss detected and force=false)
         let diff = diff_checker
-            .validate_migration(&pool, &db_name, &extractor.tables_dir(), force)
+            .validate_migration(&pool, &db_name, &extractor.tables_dir(), force, false)
             .await?;
 
         schema_validation = Some(diff_to_validation_info(&diff));
 
         // 1. Run migrations ONLY from migrations/ folder
         let migrations = migration_runner
-            .run_migrations(&pool, &db_name, &extractor.migrations_dir())
+            .run_migrations(&pool, &db_name, &extractor.migrations_dir(), allow_checksum_drift)
             .await?;
 
         // 2. Deploy functions (always redeployed)
@@ -188,6 +258,21 @@ pub async fn migrate_schema(
             .deploy_functions(&pool, &db_name, &extractor.functions_dir())
             .await?;
 
+        // Policies are redeployed after functions they may call
+        policy_manager
+            .deploy_policies(&pool, &db_name, &extractor.policies_dir())
+            .await?;
+
+        // Materialized views select from the tables and functions deployed above
+        matview_deployer
+            .deploy_matviews(&pool, &db_name, &extractor.matviews_dir())
+            .await?;
+
+        // Privileges are applied once every object exists
+        grant_manager
+            .deploy_grants(&pool, &db_name, &extractor.grants_dir())
+            .await?;
+
         // 3. Verify schema matches declarative definitions
         let verification = schema_verifier
             .verify_schema(
@@ -197,6 +282,10 @@ pub async fn migrate_schema(
                 &extractor.types_dir(),
                 &extractor.tables_dir(),
                 &extractor.seeders_dir(),
+                &extractor.policies_dir(),
+                &extractor.matviews_dir(),
+                &extractor.grants_dir(),
+                &extractor.functions_dir(),
             )
             .await?;
 
@@ -216,6 +305,9 @@ pub async fn migrate_schema(
             types_verified: verification.types.missing.is_empty(),
             tables_verified: verification.tables.missing.is_empty() && verification.tables.mismatches.is_empty(),
             seeders_verified: verification.seeders.missing.is_empty(),
+            policies_verified: verification.policies.is_ok(),
+            matviews_verified: verification.matviews.is_ok(),
+            grants_verified: verification.grants.is_ok(),
             error_log: if verification.passed {
                 None
             } else {
@@ -253,105 +345,171 @@ pub async fn migrate_schema(
         // Migrate ALL databases for this platform
         let all_databases = pool_manager.list_databases_for_platform(&platform).await?;
 
+        // Completed databases are checkpointed per target version (schema checksum)
+        let checkpoint = RolloutCheckpoint::new();
+        let target_version = RolloutCheckpoint::target_version(&schema_data);
+        let admin_pool = pool_manager.admin_pool();
+        checkpoint.ensure_table(admin_pool).await?;
+
+        let pending = if resume {
+            let completed = checkpoint
+                .completed_databases(admin_pool, &platform, &target_version)
+                .await?;
+            let pending = RolloutCheckpoint::remaining(&all_databases, &completed);
+            databases_skipped = all_databases
+                .iter()
+                .filter(|db| completed.contains(*db))
+                .cloned()
+                .collect();
+            pending
+        } else {
+            all_databases
+        };
+
         info!(
-            "Migrating {} databases for platform {}",
-            all_databases.len(),
-            platform
+            "Migrating {} databases for platform {} ({} already completed)",
+            pending.len(),
+            platform,
+            databases_skipped.len()
         );
 
-        for (i, db_name) in all_databases.iter().enumerate() {
-            let pool = pool_manager.get_pool_by_name(db_name).await?;
+        for db_name in &pending {
+            let outcome: Result<(usize, usize)> = async {
+                let pool = pool_manager.get_pool_by_name(db_name).await?;
 
-            // Ensure changelog table exists
-            changelog_manager.ensure_changelog_table(&pool, db_name).await?;
+                // Ensure changelog table exists
+                changelog_manager.ensure_changelog_table(&pool, db_name).await?;
 
-            // Validate schema changes before migration (only once, on first database)
-            if i == 0 {
-                let diff = diff_checker
-                    .validate_migration(&pool, db_name, &extractor.tables_dir(), force)
+                // Validate schema changes before migration (only once, on first database processed)
+                if schema_validation.is_none() {
+                    let diff = diff_checker
+                        .validate_migration(&pool, db_name, &extractor.tables_dir(), force, false)
+                        .await?;
+                    schema_validation = Some(diff_to_validation_info(&diff));
+                }
+
+                // 1. Run migrations ONLY from migrations/ folder
+                let migrations = migration_runner
+                    .run_migrations(&pool, db_name, &extractor.migrations_dir(), allow_checksum_drift)
                     .await?;
-                schema_validation = Some(diff_to_validation_info(&diff));
-            }
 
-            // 1. Run migrations ONLY from migrations/ folder
-            let migrations = migration_runner
-                .run_migrations(&pool, db_name, &extractor.migrations_dir())
-                .await?;
+                // 2. Deploy functions (always redeployed)
+                let functions = function_deployer
+                    .deploy_functions(&pool, db_name, &extractor.functions_dir())
+                    .await?;
 
-            // 2. Deploy functions (always redeployed)
-            let functions = function_deployer
-                .deploy_functions(&pool, db_name, &extractor.functions_dir())
-                .await?;
+                // Policies are redeployed after functions they may call
+                policy_manager
+                    .deploy_policies(&pool, db_name, &extractor.policies_dir())
+                    .await?;
 
-            // 3. Verify schema matches declarative definitions (only on first database)
-            if i == 0 {
-                let verification = schema_verifier
-                    .verify_schema(
-                        &pool,
-                        db_name,
-                        &extractor.extensions_dir(),
-                        &extractor.types_dir(),
-                        &extractor.tables_dir(),
-                        &extractor.seeders_dir(),
-                    )
+                // Materialized views select from the tables and functions deployed above
+                matview_deployer
+                    .deploy_matviews(&pool, db_name, &extractor.matviews_dir())
                     .await?;
 
-                // Collect seeder validations from verification result
-                for seeder_missing in &verification.seeders.missing {
-                    all_seeder_validations.push(SeederValidationInfo {
-                        table: seeder_missing.table.clone(),
-                        expected: seeder_missing.count,
-                        found: 0,
+                // Privileges are applied once every object exists
+                grant_manager
+                    .deploy_grants(&pool, db_name, &extractor.grants_dir())
+                    .await?;
+
+                // 3. Verify schema matches declarative definitions (only on first database processed)
+                if verification_info.is_none() {
+                    let verification = schema_verifier
+                        .verify_schema(
+                            &pool,
+                            db_name,
+                            &extractor.extensions_dir(),
+                            &extractor.types_dir(),
+                            &extractor.tables_dir(),
+                            &extractor.seeders_dir(),
+                            &extractor.policies_dir(),
+                            &extractor.matviews_dir(),
+                            &extractor.grants_dir(),
+                            &extractor.functions_dir(),
+                        )
+                        .await?;
+
+                    // Collect seeder validations from verification result
+                    for seeder_missing in &verification.seeders.missing {
+                        all_seeder_validations.push(SeederValidationInfo {
+                            table: seeder_missing.table.clone(),
+                            expected: seeder_missing.count,
+                            found: 0,
+                        });
+                    }
+
+                    // Build verification info
+                    verification_info = Some(VerificationInfo {
+                        passed: verification.passed,
+                        extensions_verified: verification.extensions.missing.is_empty(),
+                        types_verified: verification.types.missing.is_empty(),
+                        tables_verified: verification.tables.missing.is_empty() && verification.tables.mismatches.is_empty(),
+                        seeders_verified: verification.seeders.missing.is_empty(),
+                        policies_verified: verification.policies.is_ok(),
+                        matviews_verified: verification.matviews.is_ok(),
+                        grants_verified: verification.grants.is_ok(),
+                        error_log: if verification.passed {
+                            None
+                        } else {
+                            Some(verification.error_log())
+                        },
                     });
+
+                    // If verification failed and not forced, return error
+                    if !verification.passed && !force {
+                        return Err(GatewayError::MigrationFailed {
+                            database: db_name.clone(),
+                            migration: "schema verification".to_string(),
+                            cause: verification.error_log(),
+                        });
+                    }
                 }
 
-                // Build verification info
-                verification_info = Some(VerificationInfo {
-                    passed: verification.passed,
-                    extensions_verified: verification.extensions.missing.is_empty(),
-                    types_verified: verification.types.missing.is_empty(),
-                    tables_verified: verification.tables.missing.is_empty() && verification.tables.mismatches.is_empty(),
-                    seeders_verified: verification.seeders.missing.is_empty(),
-                    error_log: if verification.passed {
-                        None
-                    } else {
-                        Some(verification.error_log())
-                    },
-                });
-
-                // If verification failed and not forced, return error
-                if !verification.passed && !force {
-                    return Err(GatewayError::MigrationFailed {
-                        database: db_name.clone(),
-                        migration: "schema verification".to_string(),
-                        cause: verification.error_log(),
-                    });
+                // Log migration summary to changelog for this database
+                if migrations > 0 {
+                    changelog_manager
+                        .log_migration(&pool, db_name, &format!("{} migrations applied", migrations), "batch")
+                        .await
+                        .ok();
+                }
+                if functions > 0 {
+                    changelog_manager
+                        .log_function_deployed(&pool, db_name, &format!("{} functions", functions), "batch", "batch", "migrate")
+                        .await
+                        .ok();
                 }
-            }
 
-            // Log migration summary to changelog for this database
-            if migrations > 0 {
-                changelog_manager
-                    .log_migration(&pool, db_name, &format!("{} migrations applied", migrations), "batch")
-                    .await
-                    .ok();
+                Ok((migrations, functions))
             }
-            if functions > 0 {
-                changelog_manager
-                    .log_function_deployed(&pool, db_name, &format!("{} functions", functions), "batch", "batch", "migrate")
-                    .await
-                    .ok();
+            .await;
+
+            match outcome {
+                Ok((migrations, functions)) => {
+                    checkpoint
+                        .mark_complete(admin_pool, &platform, &target_version, db_name)
+                        .await?;
+                    total_migrations += migrations;
+                    total_functions += functions;
+                    databases_updated.push(db_name.clone());
+                }
+                Err(e) if continue_on_error => {
+                    warn!("Migration failed for {}, continuing: {}", db_name, e);
+                    databases_failed.push(DatabaseFailureInfo {
+                        database: db_name.clone(),
+                        error: e.to_string(),
+                    });
+                }
+                Err(e) => return Err(e),
             }
-
-            total_migrations += migrations;
-            total_functions += functions;
-            databases_updated.push(db_name.clone());
         }
     }
 
     let execution_time_ms = start_time.elapsed().as_millis() as u64;
 
-    let status = if verification_info.as_ref().map(|v| v.passed).unwrap_or(true) {
+    let status = if !databases_failed.is_empty() {
+        "completed_with_errors".to_string()
+    } else if verification_info.as_ref().map(|v| v.passed).unwrap_or(true) {
         "completed".to_string()
     } else {
         "completed_with_warnings".to_string()
@@ -371,6 +529,8 @@ pub async fn migrate_schema(
         Json(MigrateResponse {
             status,
             databases_updated,
+            databases_skipped,
+            databases_failed,
             migrations_applied: total_migrations,
             functions_updated: total_functions,
             seeder_validations: all_seeder_validations,
@@ -2,18 +2,29 @@
 //!
 //! POST /v2/migrate - Migrate databases using stored schema
 
+use crate::api::changelog::deploy_id;
+use crate::api::database::provision_database;
 use crate::api::platform::PlatformState;
 use crate::error::{GatewayError, Result};
 use crate::pool::PoolManager;
+use crate::security::ClientIp;
 use crate::schema::{
-    ChangeCompatibility, ChangelogManager, FunctionDeployer, MigrationRunner, SchemaDiff,
-    SchemaDiffChecker, SchemaVerifier,
+    ChangeCompatibility, ColumnOrderDrift, LaggingSequence, SequenceChecker, VerificationResult, ChangelogManager, FunctionDeployer, HookRunner, HookStage, GrantManager, MatviewDeployer, MigrationGenerator, MigrationRunner, PolicyManager,
+    OutOfScopeObject, OwnershipChecker, SchemaDiff, SchemaDiffChecker, SchemaVerifier, UpdatedAtTriggerManager,
 };
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use deadpool_postgres::Pool;
 use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Shared state for migrate v2 endpoint
 pub struct MigrateV2State {
@@ -21,6 +32,19 @@ pub struct MigrateV2State {
     pub platform_state: Arc<PlatformState>,
 }
 
+/// How a failed post-migration verification is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationMode {
+    /// Fail the migration unless `force` is set
+    #[default]
+    Block,
+    /// Return the findings (`passed: false`) but never fail the migration
+    ReportOnly,
+    /// Skip verification entirely
+    Off,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct MigrateV2Request {
     pub platform: String,
@@ -29,6 +53,36 @@ pub struct MigrateV2Request {
     pub database_id: String,
     #[serde(default)]
     pub force: bool,
+    /// Treat warnings (forced data-loss changes, failed verification) as errors
+    #[serde(default)]
+    pub strict: bool,
+    /// Create and fully provision the database if it does not exist yet
+    #[serde(default)]
+    pub auto_provision: bool,
+    /// Skip (with a warning) applied migrations whose file changed since they were applied
+    #[serde(default)]
+    pub allow_checksum_drift: bool,
+    /// Report what would be applied without changing the database
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Reset serial/identity sequences that fell behind their column (setval)
+    #[serde(default)]
+    pub fix_sequences: bool,
+    /// block (default), report_only or off
+    #[serde(default)]
+    pub verification_mode: VerificationMode,
+    /// Also compare deployed function bodies with their source (pg_get_functiondef)
+    #[serde(default)]
+    pub verify_function_bodies: bool,
+    /// Report a one-to-one dropped+added column of compatible type as a safe rename
+    #[serde(default)]
+    pub detect_renames: bool,
+    /// Rebuild tables whose column order drifted from the declaration (copies the data)
+    #[serde(default)]
+    pub rebuild_column_order: bool,
+    /// Warn when pending migrations touch tables the schema does not own (shared databases)
+    #[serde(default)]
+    pub check_ownership: bool,
 }
 
 #[derive(Serialize)]
@@ -43,6 +97,9 @@ pub struct SchemaChangeInfo {
     table: String,
     change_type: String,
     column: Option<String>,
+    /// Position of the column in the declared table (column changes only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ordinal: Option<usize>,
     from_type: Option<String>,
     to_type: Option<String>,
     compatibility: String,
@@ -51,9 +108,17 @@ pub struct SchemaChangeInfo {
 
 #[derive(Serialize)]
 pub struct SchemaValidationInfo {
-    safe_changes: Vec<SchemaChangeInfo>,
+    /// Left out of the response with `blocking_only`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) safe_changes: Option<Vec<SchemaChangeInfo>>,
     dataloss_changes: Vec<SchemaChangeInfo>,
     incompatible_changes: Vec<SchemaChangeInfo>,
+    /// Tables whose column order does not (or will not) match the declaration
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    column_order_drift: Vec<ColumnOrderDrift>,
+    /// DDL that would apply the diff; DataLoss/Incompatible statements are commented out
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) migration_sql: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -63,7 +128,36 @@ pub struct VerificationInfo {
     types_verified: bool,
     tables_verified: bool,
     seeders_verified: bool,
+    policies_verified: bool,
+    matviews_verified: bool,
+    grants_verified: bool,
+    /// Only meaningful when `verify_function_bodies` was requested
+    functions_verified: bool,
+    /// Only meaningful for platforms with managed `updated_at` triggers
+    updated_at_triggers_verified: bool,
     error_log: Option<String>,
+    /// Sequences whose next value is already used (reset when fix_sequences is set)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    lagging_sequences: Vec<LaggingSequence>,
+    /// Installed extensions the schema does not declare (fail only strict migrates)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    extra_extensions: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DependencyIssueInfo {
+    migration: String,
+    table: String,
+    depends_on: String,
+    depends_on_defined_in: Option<String>,
+    message: String,
+}
+
+#[derive(Serialize)]
+pub struct DependencyValidationInfo {
+    issues: Vec<DependencyIssueInfo>,
+    /// Table creation order that satisfies all foreign keys
+    suggested_order: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -72,19 +166,49 @@ pub struct MigrateV2Response {
     platform: String,
     schema_name: String,
     databases_updated: Vec<String>,
+    /// Databases created by auto_provision during this run
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    databases_provisioned: Vec<String>,
     migrations_applied: usize,
     functions_updated: usize,
     seeder_validations: Vec<SeederValidationInfo>,
     schema_validation: Option<SchemaValidationInfo>,
     verification: Option<VerificationInfo>,
+    /// Sequences reset with setval (fix_sequences)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sequences_fixed: Vec<String>,
+    /// Hooks that ran, in order (hooks/pre_migrate.pssql, hooks/post_migrate.pssql)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hooks_run: Vec<String>,
+    /// Tables rebuilt in declared column order (rebuild_column_order)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tables_rebuilt: Vec<String>,
+    /// Tables that got the managed `updated_at` trigger (platform option)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    updated_at_triggers_created: Vec<String>,
+    /// check_ownership only: tables the migrations touched outside the schema
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    out_of_scope: Vec<OutOfScopeObject>,
+    /// dry_run only: migration files that would be applied, in execution order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    planned_migrations: Option<Vec<String>>,
+    /// dry_run only: applied migrations edited since (allow_checksum_drift)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    checksum_drift: Vec<String>,
+    /// dry_run only: migrations that reference tables created by a later migration
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dependency_validation: Option<DependencyValidationInfo>,
     execution_time_ms: u64,
 }
 
 pub async fn migrate_schema_v2(
     State(state): State<Arc<MigrateV2State>>,
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
     Json(request): Json<MigrateV2Request>,
 ) -> Result<impl IntoResponse> {
     let start_time = Instant::now();
+    let deploy_id = deploy_id(&headers)?;
 
     // Check platform is registered
     if !state.platform_state.registry.is_registered(&request.platform) {
@@ -95,6 +219,7 @@ pub async fn migrate_schema_v2(
             ),
         });
     }
+    state.platform_state.registry.check_client_ip(&request.platform, ip)?;
 
     // Check schema exists
     if !state
@@ -135,12 +260,39 @@ pub async fn migrate_schema_v2(
         .platform_state
         .schema_store
         .seeders_dir(&request.platform, &request.schema_name);
+    let hooks_dir = state
+        .platform_state
+        .schema_store
+        .hooks_dir(&request.platform, &request.schema_name);
+    let policies_dir = state
+        .platform_state
+        .schema_store
+        .policies_dir(&request.platform, &request.schema_name);
+    let matviews_dir = state
+        .platform_state
+        .schema_store
+        .matviews_dir(&request.platform, &request.schema_name);
+    let grants_dir = state
+        .platform_state
+        .schema_store
+        .grants_dir(&request.platform, &request.schema_name);
 
-    let changelog_manager = ChangelogManager::new();
+    let changelog_manager = ChangelogManager::with_correlation_id(deploy_id.clone());
     let migration_runner = MigrationRunner::new();
     let function_deployer = FunctionDeployer::new();
-    let schema_verifier = SchemaVerifier::new();
+    let policy_manager = PolicyManager::new();
+    let matview_deployer = MatviewDeployer::new();
+    let grant_manager = GrantManager::new();
+    let managed_updated_at = state
+        .platform_state
+        .registry
+        .get_platform_info(&request.platform)?
+        .managed_updated_at;
+    let schema_verifier = SchemaVerifier::new()
+        .with_function_bodies(request.verify_function_bodies)
+        .with_updated_at_triggers(managed_updated_at);
     let diff_checker = SchemaDiffChecker::new();
+    let hook_runner = HookRunner::new();
 
     let mut databases_updated = Vec::new();
     let mut total_migrations = 0;
@@ -148,6 +300,11 @@ pub async fn migrate_schema_v2(
     let mut all_seeder_validations = Vec::new();
     let mut schema_validation: Option<SchemaValidationInfo> = None;
     let mut verification_info: Option<VerificationInfo> = None;
+    let mut sequences_fixed = Vec::new();
+    let mut hooks_run = Vec::new();
+    let mut tables_rebuilt = Vec::new();
+    let mut updated_at_triggers_created = Vec::new();
+    let mut out_of_scope = Vec::new();
 
     // Construct database name from platform, schema, and database_id
     // database_id can be "main" or a tenant identifier
@@ -157,14 +314,104 @@ pub async fn migrate_schema_v2(
         format!("{}_{}", request.platform, request.database_id)
     };
 
-    // Verify database exists
-    if !state.pool_manager.database_exists(&db_name).await? {
-        return Err(GatewayError::InvalidRequest {
-            message: format!(
-                "Database '{}' not found for platform '{}', database_id '{}'",
-                db_name, request.platform, request.database_id
-            ),
-        });
+    // Verify database exists, provisioning it first if requested
+    let exists = state.pool_manager.database_exists(&db_name).await?;
+
+    if request.dry_run {
+        if !exists {
+            return Err(GatewayError::InvalidRequest {
+                message: format!(
+                    "Database '{}' not found; dry_run only previews existing databases",
+                    db_name
+                ),
+            });
+        }
+
+        let pool = state.pool_manager.get_pool_by_name(&db_name).await?;
+
+        // Same gate as a real run: fails on data-loss changes unless forced
+        let diff = diff_checker
+            .validate_migration(&pool, &db_name, &tables_dir, request.force, request.detect_renames)
+            .await?;
+        if request.strict {
+            enforce_strict(&db_name, diff_findings(&diff))?;
+        }
+
+        let mut validation = diff_to_validation_info(&diff);
+        let generator = MigrationGenerator::from_directory(&tables_dir)?;
+        validation.migration_sql = migration_sql(&generator, &diff, request.rebuild_column_order);
+
+        let dependencies = migration_runner.validate_dependencies(&migrations_dir)?;
+        let plan = migration_runner
+            .plan_migrations(&pool, &db_name, &migrations_dir, request.allow_checksum_drift)
+            .await?;
+        let out_of_scope = if request.check_ownership {
+            out_of_scope_objects(&plan.pending, &tables_dir, &migrations_dir)?
+        } else {
+            Vec::new()
+        };
+
+        info!(
+            "Dry run for database '{}': {} migrations would be applied",
+            db_name,
+            plan.pending.len()
+        );
+
+        return Ok((
+            StatusCode::OK,
+            Json(MigrateV2Response {
+                status: "dry_run".to_string(),
+                platform: request.platform,
+                schema_name: request.schema_name,
+                databases_updated: Vec::new(),
+                databases_provisioned: Vec::new(),
+                migrations_applied: 0,
+                functions_updated: 0,
+                seeder_validations: Vec::new(),
+                schema_validation: Some(validation),
+                verification: None,
+                sequences_fixed: Vec::new(),
+                hooks_run: Vec::new(),
+                tables_rebuilt: Vec::new(),
+                updated_at_triggers_created: Vec::new(),
+                out_of_scope,
+                planned_migrations: Some(plan.pending),
+                checksum_drift: plan.drifted,
+                dependency_validation: (!dependencies.is_valid).then(|| DependencyValidationInfo {
+                    issues: dependencies
+                        .issues
+                        .into_iter()
+                        .map(|issue| DependencyIssueInfo {
+                            migration: issue.migration,
+                            table: issue.table,
+                            depends_on: issue.depends_on,
+                            depends_on_defined_in: issue.depends_on_defined_in,
+                            message: issue.message,
+                        })
+                        .collect(),
+                    suggested_order: dependencies.suggested_order,
+                }),
+                execution_time_ms: start_time.elapsed().as_millis() as u64,
+            }),
+        ));
+    }
+    let mut databases_provisioned = Vec::new();
+
+    if should_provision(exists, &request, &db_name)? {
+        info!(
+            "Database '{}' not found, provisioning from schema '{}'",
+            db_name, request.schema_name
+        );
+        provision_database(
+            &state.pool_manager,
+            &state.platform_state,
+            &request.platform,
+            &request.schema_name,
+            &db_name,
+            deploy_id.as_deref(),
+        )
+        .await?;
+        databases_provisioned.push(db_name.clone());
     }
 
     info!(
@@ -175,6 +422,10 @@ pub async fn migrate_schema_v2(
     );
 
     let databases_to_migrate = vec![db_name.clone()];
+    let schema_version = state
+        .platform_state
+        .schema_store
+        .current_version(&request.platform, &request.schema_name)?;
 
     for (i, db_name) in databases_to_migrate.iter().enumerate() {
         let pool = state.pool_manager.get_pool_by_name(db_name).await?;
@@ -187,23 +438,87 @@ pub async fn migrate_schema_v2(
         // Validate schema changes before migration (only once, on first database)
         if i == 0 {
             let diff = diff_checker
-                .validate_migration(&pool, db_name, &tables_dir, request.force)
+                .validate_migration(&pool, db_name, &tables_dir, request.force, request.detect_renames)
                 .await?;
-            schema_validation = Some(diff_to_validation_info(&diff));
+            if request.strict {
+                enforce_strict(db_name, diff_findings(&diff))?;
+            }
+
+            let mut validation = diff_to_validation_info(&diff);
+            let generator = MigrationGenerator::from_directory(&tables_dir)?;
+            validation.migration_sql = migration_sql(&generator, &diff, request.rebuild_column_order);
+            schema_validation = Some(validation);
+        }
+
+        // Pre-migration hook (hooks/pre_migrate.pssql)
+        let hook_start = Instant::now();
+        if let Some(hook) = hook_runner
+            .run(&pool, db_name, &hooks_dir, HookStage::PreMigrate)
+            .await?
+        {
+            changelog_manager
+                .log_hook_executed(&pool, db_name, &hook, hook_start.elapsed().as_millis() as u64)
+                .await
+                .ok();
+            hooks_run.push(hook);
+        }
+
+        // Checked before applying, so strict mode can still stop the run
+        if request.check_ownership {
+            let plan = migration_runner
+                .plan_migrations(&pool, db_name, &migrations_dir, request.allow_checksum_drift)
+                .await?;
+            let touched = out_of_scope_objects(&plan.pending, &tables_dir, &migrations_dir)?;
+            if request.strict {
+                enforce_strict(db_name, touched.iter().map(|o| o.finding()).collect())?;
+            }
+            for object in &touched {
+                warn!("{} ({})", object.finding(), db_name);
+            }
+            out_of_scope.extend(touched);
         }
 
         // 1. Run migrations ONLY from migrations/ folder
         let migrations = migration_runner
-            .run_migrations(&pool, db_name, &migrations_dir)
+            .run_migrations(&pool, db_name, &migrations_dir, request.allow_checksum_drift)
             .await?;
 
+        // Tables are rebuilt before policies, matviews and grants are redeployed on them
+        if request.rebuild_column_order {
+            tables_rebuilt.extend(rebuild_drifted_tables(&pool, db_name, &tables_dir).await?);
+        }
+
+        // Also covers tables added by the migrations or rebuilt above
+        if managed_updated_at {
+            updated_at_triggers_created.extend(
+                UpdatedAtTriggerManager::new()
+                    .deploy_triggers(&pool, db_name, &tables_dir)
+                    .await?,
+            );
+        }
+
         // 2. Deploy functions (always redeployed)
         let functions = function_deployer
             .deploy_functions(&pool, db_name, &functions_dir)
             .await?;
 
+        // Policies are redeployed after functions they may call
+        policy_manager
+            .deploy_policies(&pool, db_name, &policies_dir)
+            .await?;
+
+        // Materialized views select from the tables and functions deployed above
+        matview_deployer
+            .deploy_matviews(&pool, db_name, &matviews_dir)
+            .await?;
+
+        // Privileges are applied once every object exists
+        grant_manager
+            .deploy_grants(&pool, db_name, &grants_dir)
+            .await?;
+
         // 3. Verify schema matches declarative definitions (only on first database)
-        if i == 0 {
+        if i == 0 && request.verification_mode != VerificationMode::Off {
             let verification = schema_verifier
                 .verify_schema(
                     &pool,
@@ -212,6 +527,10 @@ pub async fn migrate_schema_v2(
                     &types_dir,
                     &tables_dir,
                     &seeders_dir,
+                    &policies_dir,
+                    &matviews_dir,
+                    &grants_dir,
+                    &functions_dir,
                 )
                 .await?;
 
@@ -232,15 +551,41 @@ pub async fn migrate_schema_v2(
                 tables_verified: verification.tables.missing.is_empty()
                     && verification.tables.mismatches.is_empty(),
                 seeders_verified: verification.seeders.missing.is_empty(),
+                policies_verified: verification.policies.is_ok(),
+                matviews_verified: verification.matviews.is_ok(),
+                grants_verified: verification.grants.is_ok(),
+                functions_verified: verification.functions.is_ok(),
+                updated_at_triggers_verified: verification.updated_at_triggers.is_ok(),
                 error_log: if verification.passed {
                     None
                 } else {
                     Some(verification.error_log())
                 },
+                lagging_sequences: verification.lagging_sequences.clone(),
+                extra_extensions: verification.extensions.extra.clone(),
             });
 
+            if request.fix_sequences {
+                SequenceChecker::new()
+                    .fix(&pool, db_name, &verification.lagging_sequences)
+                    .await?;
+                sequences_fixed.extend(
+                    verification
+                        .lagging_sequences
+                        .iter()
+                        .map(|s| s.sequence.clone()),
+                );
+            }
+
+            // Report-only verification is advisory, even in strict mode
+            let blocking = request.verification_mode == VerificationMode::Block;
+
+            if request.strict && blocking {
+                enforce_strict(db_name, verification_findings(&verification))?;
+            }
+
             // If verification failed and not forced, return error
-            if !verification.passed && !request.force {
+            if !verification.passed && !request.force && blocking {
                 return Err(GatewayError::MigrationFailed {
                     database: db_name.clone(),
                     migration: "schema verification".to_string(),
@@ -249,6 +594,19 @@ pub async fn migrate_schema_v2(
             }
         }
 
+        // Post-migration hook (hooks/post_migrate.pssql)
+        let hook_start = Instant::now();
+        if let Some(hook) = hook_runner
+            .run(&pool, db_name, &hooks_dir, HookStage::PostMigrate)
+            .await?
+        {
+            changelog_manager
+                .log_hook_executed(&pool, db_name, &hook, hook_start.elapsed().as_millis() as u64)
+                .await
+                .ok();
+            hooks_run.push(hook);
+        }
+
         // Log migration summary to changelog for this database
         if migrations > 0 {
             changelog_manager
@@ -275,6 +633,14 @@ pub async fn migrate_schema_v2(
                 .ok();
         }
 
+        // Track the schema version the database is now on (used by clone-schema)
+        if let Some(version) = &schema_version {
+            state
+                .platform_state
+                .registry
+                .set_schema_version(&request.platform, db_name, version)?;
+        }
+
         total_migrations += migrations;
         total_functions += functions;
         databases_updated.push(db_name.clone());
@@ -282,7 +648,8 @@ pub async fn migrate_schema_v2(
 
     let execution_time_ms = start_time.elapsed().as_millis() as u64;
 
-    let status = if verification_info.as_ref().map(|v| v.passed).unwrap_or(true) {
+    let verified = verification_info.as_ref().map(|v| v.passed).unwrap_or(true);
+    let status = if verified && out_of_scope.is_empty() {
         "completed".to_string()
     } else {
         "completed_with_warnings".to_string()
@@ -305,22 +672,116 @@ pub async fn migrate_schema_v2(
             platform: request.platform,
             schema_name: request.schema_name,
             databases_updated,
+            databases_provisioned,
             migrations_applied: total_migrations,
             functions_updated: total_functions,
             seeder_validations: all_seeder_validations,
             schema_validation,
             verification: verification_info,
+            sequences_fixed,
+            hooks_run,
+            tables_rebuilt,
+            updated_at_triggers_created,
+            out_of_scope,
+            planned_migrations: None,
+            checksum_drift: Vec::new(),
+            dependency_validation: None,
             execution_time_ms,
         }),
     ))
 }
 
+/// Non-blocking diff findings: changes allowed through by force, and safe changes
+/// that still carry a warning
+fn diff_findings(diff: &SchemaDiff) -> Vec<String> {
+    diff.dataloss_changes
+        .iter()
+        .chain(diff.incompatible_changes.iter())
+        .chain(diff.safe_changes.iter().filter(|c| c.reason.is_some()))
+        .map(|change| {
+            format!(
+                "{:?} on {}.{}: {}",
+                change.change_type,
+                change.table,
+                change.column.as_deref().unwrap_or("*"),
+                change.reason.as_deref().unwrap_or("requires review")
+            )
+        })
+        .collect()
+}
+
+/// Verification findings, which only warn when force is set
+///
+/// Warnings such as undeclared extensions never fail verification on their
+/// own, so they are only enforced here (strict mode).
+fn verification_findings(verification: &VerificationResult) -> Vec<String> {
+    let mut findings = if verification.passed {
+        Vec::new()
+    } else {
+        verification.findings()
+    };
+    findings.extend(verification.warnings());
+    findings
+}
+
+/// Tables the given pending migrations touch that the schema does not own
+fn out_of_scope_objects(
+    planned: &[String],
+    tables_dir: &Path,
+    migrations_dir: &Path,
+) -> Result<Vec<OutOfScopeObject>> {
+    let checker = OwnershipChecker::from_schema(tables_dir, migrations_dir)?;
+    let mut out_of_scope = Vec::new();
+    for migration in planned {
+        let sql = fs::read_to_string(migrations_dir.join(migration)).map_err(|e| {
+            GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to read migration {}: {}", migration, e),
+            }
+        })?;
+        out_of_scope.extend(checker.check(migration, &sql));
+    }
+    Ok(out_of_scope)
+}
+
+/// Fail the migration if strict mode collected any findings
+fn enforce_strict(database: &str, findings: Vec<String>) -> Result<()> {
+    if findings.is_empty() {
+        return Ok(());
+    }
+
+    Err(GatewayError::StrictModeViolation {
+        database: database.to_string(),
+        findings,
+    })
+}
+
+/// Decide whether a target database has to be provisioned before migrating
+///
+/// Errors when the database is missing and auto_provision is off.
+fn should_provision(exists: bool, request: &MigrateV2Request, db_name: &str) -> Result<bool> {
+    if exists {
+        return Ok(false);
+    }
+
+    if !request.auto_provision {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Database '{}' not found for platform '{}', database_id '{}'",
+                db_name, request.platform, request.database_id
+            ),
+        });
+    }
+
+    Ok(true)
+}
+
 /// Convert SchemaDiff to SchemaValidationInfo for JSON response
-fn diff_to_validation_info(diff: &SchemaDiff) -> SchemaValidationInfo {
+pub(crate) fn diff_to_validation_info(diff: &SchemaDiff) -> SchemaValidationInfo {
     let convert_change = |change: &crate::schema::SchemaChange| SchemaChangeInfo {
         table: change.table.clone(),
         change_type: format!("{:?}", change.change_type),
         column: change.column.clone(),
+        ordinal: change.ordinal,
         from_type: change.from_type.clone(),
         to_type: change.to_type.clone(),
         compatibility: match change.compatibility {
@@ -332,12 +793,562 @@ fn diff_to_validation_info(diff: &SchemaDiff) -> SchemaValidationInfo {
     };
 
     SchemaValidationInfo {
-        safe_changes: diff.safe_changes.iter().map(convert_change).collect(),
+        safe_changes: Some(diff.safe_changes.iter().map(convert_change).collect()),
         dataloss_changes: diff.dataloss_changes.iter().map(convert_change).collect(),
         incompatible_changes: diff
             .incompatible_changes
             .iter()
             .map(convert_change)
             .collect(),
+        column_order_drift: diff.column_order_drift.clone(),
+        migration_sql: Vec::new(),
+    }
+}
+
+/// DDL for a diff, plus the column order rebuilds when requested
+pub(crate) fn migration_sql(generator: &MigrationGenerator, diff: &SchemaDiff, rebuild_column_order: bool) -> Vec<String> {
+    let mut statements = generator.generate(diff);
+    if rebuild_column_order {
+        for drift in &diff.column_order_drift {
+            statements.extend(generator.rebuild_plan(drift));
+        }
+    }
+    statements
+}
+
+/// Rebuild the tables whose column order drifted from their declaration
+///
+/// Runs after the migrations, against the live schema, in one transaction.
+/// Returns the rebuilt tables.
+async fn rebuild_drifted_tables(pool: &Pool, database: &str, tables_dir: &Path) -> Result<Vec<String>> {
+    let diff_checker = SchemaDiffChecker::new();
+    let desired = diff_checker.parse_desired_schema(tables_dir)?;
+    if desired.is_empty() {
+        return Ok(Vec::new());
+    }
+    let current = diff_checker.query_current_schema(pool, database).await?;
+    let unmanaged = SchemaDiffChecker::load_unmanaged_tables(tables_dir);
+    let diff = diff_checker.diff_schemas(&desired, &current, &unmanaged);
+    if diff.column_order_drift.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let generator = MigrationGenerator::from_directory(tables_dir)?;
+    let mut client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+        database: database.to_string(),
+        cause: e.to_string(),
+    })?;
+    let rebuild_error = |table: &str, e: tokio_postgres::Error| GatewayError::MigrationFailed {
+        database: database.to_string(),
+        migration: format!("column order rebuild: {}", table),
+        cause: e.to_string(),
+    };
+
+    let transaction = client
+        .transaction()
+        .await
+        .map_err(|e| rebuild_error("BEGIN", e))?;
+    for drift in &diff.column_order_drift {
+        for statement in generator.rebuild_plan(drift) {
+            transaction
+                .batch_execute(&statement)
+                .await
+                .map_err(|e| rebuild_error(&drift.table, e))?;
+        }
+        info!(
+            "Rebuilt {} in {} in declared column order",
+            drift.table, database
+        );
+    }
+    transaction
+        .commit()
+        .await
+        .map_err(|e| rebuild_error("COMMIT", e))?;
+
+    Ok(diff.column_order_drift.into_iter().map(|d| d.table).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_db::test_database_url;
+
+    fn request(auto_provision: bool) -> MigrateV2Request {
+        serde_json::from_value(serde_json::json!({
+            "platform": "myapp",
+            "schema_name": "tenant_db",
+            "database_id": "store_001",
+            "auto_provision": auto_provision,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_dry_run_defaults_to_false() {
+        assert!(!request(false).dry_run);
+
+        let dry: MigrateV2Request = serde_json::from_value(serde_json::json!({
+            "platform": "myapp",
+            "schema_name": "tenant_db",
+            "database_id": "store_001",
+            "dry_run": true,
+        }))
+        .unwrap();
+        assert!(dry.dry_run);
+    }
+
+    #[test]
+    fn test_verify_function_bodies_defaults_to_false() {
+        assert!(!request(false).verify_function_bodies);
+    }
+
+    #[test]
+    fn test_auto_provision_defaults_to_false() {
+        let request: MigrateV2Request = serde_json::from_value(serde_json::json!({
+            "platform": "myapp",
+            "schema_name": "tenant_db",
+            "database_id": "main",
+        }))
+        .unwrap();
+
+        assert!(!request.auto_provision);
+    }
+
+    #[test]
+    fn test_missing_database_provisioned_with_auto_provision() {
+        let request = request(true);
+        assert!(should_provision(false, &request, "myapp_store_001").unwrap());
+    }
+
+    #[test]
+    fn test_missing_database_without_auto_provision_fails() {
+        let request = request(false);
+        let err = should_provision(false, &request, "myapp_store_001")
+            .unwrap_err();
+        assert!(err.to_string().contains("myapp_store_001"));
+    }
+
+    #[test]
+    fn test_strict_rejects_verification_mismatch() {
+        // A missing table only produces "completed_with_warnings" under force
+        let mut verification = VerificationResult::new();
+        verification.passed = false;
+        verification.tables.missing.push("orders".to_string());
+
+        let err = enforce_strict("myapp_main", verification_findings(&verification)).unwrap_err();
+        match err {
+            GatewayError::StrictModeViolation { database, findings } => {
+                assert_eq!(database, "myapp_main");
+                assert_eq!(findings, vec!["Missing table: orders"]);
+            }
+            other => panic!("expected StrictModeViolation, got {:?}", other),
+        }
+
+        assert!(enforce_strict("myapp_main", verification_findings(&VerificationResult::new())).is_ok());
+    }
+
+    #[test]
+    fn test_strict_rejects_forced_dataloss() {
+        let mut diff = SchemaDiff::new();
+        diff.add_change(crate::schema::SchemaChange {
+            table: "users".to_string(),
+            change_type: crate::schema::ChangeType::DropColumn,
+            column: Some("legacy".to_string()),
+            ordinal: None,
+            from_type: Some("TEXT".to_string()),
+            to_type: None,
+            compatibility: ChangeCompatibility::DataLoss,
+            reason: Some("Dropping column will delete all data".to_string()),
+        });
+
+        let findings = diff_findings(&diff);
+        assert_eq!(
+            findings,
+            vec!["DropColumn on users.legacy: Dropping column will delete all data"]
+        );
+        assert!(enforce_strict("myapp_main", findings).is_err());
+    }
+
+    #[test]
+    fn test_existing_database_not_provisioned() {
+        let request = request(true);
+        assert!(!should_provision(true, &request, "myapp_store_001").unwrap());
+    }
+
+    #[test]
+    fn test_verification_mode_defaults_to_block() {
+        assert_eq!(request(false).verification_mode, VerificationMode::Block);
+
+        let report_only: MigrateV2Request = serde_json::from_value(serde_json::json!({
+            "platform": "myapp",
+            "schema_name": "tenant_db",
+            "database_id": "main",
+            "verification_mode": "report_only",
+        }))
+        .unwrap();
+        assert_eq!(report_only.verification_mode, VerificationMode::ReportOnly);
+    }
+
+    fn archive_with(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut archive_data = Vec::new();
+        {
+            let encoder = flate2::write::GzEncoder::new(&mut archive_data, flate2::Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            for (path, content) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_path(path).unwrap();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append(&header, content.as_bytes()).unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        archive_data
+    }
+
+    #[tokio::test]
+    async fn test_report_only_verification_does_not_block() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let pool_manager = Arc::new(
+            PoolManager::new(crate::config::Config::for_test(&url, data_dir.path()))
+                .await
+                .unwrap(),
+        );
+        let platform_state = Arc::new(PlatformState::new(data_dir.path(), false, true));
+        pool_manager.drop_database("verifymodetest_t1", true).await.ok();
+
+        let table = ("tables/items.pssql", "CREATE TABLE items (id SERIAL PRIMARY KEY);");
+        platform_state.registry.register_platform("verifymodetest").unwrap();
+        platform_state
+            .schema_store
+            .store_schema("verifymodetest", "tenant_db", &archive_with(&[table]))
+            .unwrap();
+        provision_database(&pool_manager, &platform_state, "verifymodetest", "tenant_db", "verifymodetest_t1", None)
+            .await
+            .unwrap();
+
+        // The new version declares a table that no migration creates, so
+        // verification fails after the migration is applied
+        platform_state
+            .schema_store
+            .store_schema(
+                "verifymodetest",
+                "tenant_db",
+                &archive_with(&[
+                    table,
+                    ("tables/orders.pssql", "CREATE TABLE orders (id SERIAL PRIMARY KEY);"),
+                    ("migrations/001_add_name.pssql", "ALTER TABLE items ADD COLUMN name TEXT;"),
+                ]),
+            )
+            .unwrap();
+
+        let state = Arc::new(MigrateV2State {
+            pool_manager: pool_manager.clone(),
+            platform_state: platform_state.clone(),
+        });
+        let request: MigrateV2Request = serde_json::from_value(serde_json::json!({
+            "platform": "verifymodetest",
+            "schema_name": "tenant_db",
+            "database_id": "t1",
+            "verification_mode": "report_only",
+        }))
+        .unwrap();
+
+        let response = migrate_schema_v2(State(state), HeaderMap::new(), ClientIp(None), Json(request))
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json["status"], "completed_with_warnings");
+        assert_eq!(json["migrations_applied"], 1);
+        assert_eq!(json["verification"]["passed"], false);
+        assert!(json["verification"]["error_log"]
+            .as_str()
+            .unwrap()
+            .contains("orders"));
+
+        let pool = pool_manager.get_pool_by_name("verifymodetest_t1").await.unwrap();
+        let client = pool.get().await.unwrap();
+        let row = client
+            .query_one(
+                "SELECT count(*) FROM information_schema.columns WHERE table_name = 'items' AND column_name = 'name'",
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(row.get::<_, i64>(0), 1);
+        drop(client);
+
+        pool_manager.drop_database("verifymodetest_t1", true).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_deploy_id_is_recorded_in_changelog() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let pool_manager = Arc::new(
+            PoolManager::new(crate::config::Config::for_test(&url, data_dir.path()))
+                .await
+                .unwrap(),
+        );
+        let platform_state = Arc::new(PlatformState::new(data_dir.path(), false, true));
+        pool_manager.drop_database("deployidtest_t1", true).await.ok();
+
+        platform_state.registry.register_platform("deployidtest").unwrap();
+        platform_state
+            .schema_store
+            .store_schema(
+                "deployidtest",
+                "tenant_db",
+                &archive_with(&[
+                    ("tables/items.pssql", "CREATE TABLE items (id SERIAL PRIMARY KEY, name TEXT);"),
+                    ("migrations/001_add_name.pssql", "ALTER TABLE items ADD COLUMN IF NOT EXISTS name TEXT;"),
+                ]),
+            )
+            .unwrap();
+
+        let state = Arc::new(MigrateV2State {
+            pool_manager: pool_manager.clone(),
+            platform_state: platform_state.clone(),
+        });
+        let request: MigrateV2Request = serde_json::from_value(serde_json::json!({
+            "platform": "deployidtest",
+            "schema_name": "tenant_db",
+            "database_id": "t1",
+            "auto_provision": true,
+        }))
+        .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Deploy-Id", "deploy-2024-07-01.3".parse().unwrap());
+
+        let response = migrate_schema_v2(State(state), headers, ClientIp(None), Json(request))
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Provisioning and migration entries of the request all carry the ID
+        let pool = pool_manager.get_pool_by_name("deployidtest_t1").await.unwrap();
+        let entries = ChangelogManager::new()
+            .get_recent_entries(&pool, "deployidtest_t1", 50)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 2, "{:?}", entries);
+        assert_eq!(entries[0].object_name, "1 migrations applied");
+        for entry in &entries {
+            assert_eq!(entry.correlation_id.as_deref(), Some("deploy-2024-07-01.3"), "{:?}", entry);
+        }
+        drop(pool);
+
+        pool_manager.drop_database("deployidtest_t1", true).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_auto_provision_creates_missing_tenant() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let pool_manager = Arc::new(
+            PoolManager::new(crate::config::Config::for_test(&url, data_dir.path()))
+                .await
+                .unwrap(),
+        );
+        let platform_state = Arc::new(PlatformState::new(data_dir.path(), false, true));
+        pool_manager.drop_database("autoprovtest_t1", true).await.ok();
+
+        platform_state.registry.register_platform("autoprovtest").unwrap();
+        platform_state
+            .schema_store
+            .store_schema(
+                "autoprovtest",
+                "tenant_db",
+                &archive_with(&[("tables/items.pssql", "CREATE TABLE items (id SERIAL PRIMARY KEY, name TEXT);")]),
+            )
+            .unwrap();
+
+        let state = Arc::new(MigrateV2State {
+            pool_manager: pool_manager.clone(),
+            platform_state: platform_state.clone(),
+        });
+        let request: MigrateV2Request = serde_json::from_value(serde_json::json!({
+            "platform": "autoprovtest",
+            "schema_name": "tenant_db",
+            "database_id": "t1",
+            "auto_provision": true,
+        }))
+        .unwrap();
+
+        let response = migrate_schema_v2(State(state), HeaderMap::new(), ClientIp(None), Json(request))
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["databases_provisioned"], serde_json::json!(["autoprovtest_t1"]), "{}", json);
+
+        // The tenant exists with the schema's tables
+        assert!(pool_manager.database_exists("autoprovtest_t1").await.unwrap());
+        let pool = pool_manager.get_pool_by_name("autoprovtest_t1").await.unwrap();
+        let client = pool.get().await.unwrap();
+        let row = client
+            .query_one("SELECT to_regclass('public.items') IS NOT NULL", &[])
+            .await
+            .unwrap();
+        assert!(row.get::<_, bool>(0));
+        drop(client);
+        drop(pool);
+
+        pool_manager.drop_database("autoprovtest_t1", true).await.unwrap();
+    }
+
+    #[test]
+    fn test_deploy_id_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(deploy_id(&headers).unwrap(), None);
+
+        headers.insert("x-deploy-id", " run-17 ".parse().unwrap());
+        assert_eq!(deploy_id(&headers).unwrap().as_deref(), Some("run-17"));
+
+        headers.insert("x-deploy-id", "x".repeat(201).parse().unwrap());
+        assert!(deploy_id(&headers).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_column_order_drift_reported_and_rebuilt_on_request() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let pool_manager = Arc::new(
+            PoolManager::new(crate::config::Config::for_test(&url, data_dir.path()))
+                .await
+                .unwrap(),
+        );
+        let platform_state = Arc::new(PlatformState::new(data_dir.path(), false, true));
+        pool_manager.drop_database("reordertest_t1", true).await.ok();
+
+        platform_state.registry.register_platform("reordertest").unwrap();
+        platform_state
+            .schema_store
+            .store_schema(
+                "reordertest",
+                "tenant_db",
+                &archive_with(&[("tables/items.pssql", "CREATE TABLE items (id SERIAL PRIMARY KEY, email TEXT);")]),
+            )
+            .unwrap();
+        provision_database(&pool_manager, &platform_state, "reordertest", "tenant_db", "reordertest_t1", None)
+            .await
+            .unwrap();
+        let pool = pool_manager.get_pool_by_name("reordertest_t1").await.unwrap();
+        pool.get()
+            .await
+            .unwrap()
+            .batch_execute("INSERT INTO items (email) VALUES ('a@example.com'), ('b@example.com')")
+            .await
+            .unwrap();
+
+        // name is declared before email, but the migration can only append it
+        platform_state
+            .schema_store
+            .store_schema(
+                "reordertest",
+                "tenant_db",
+                &archive_with(&[
+                    ("tables/items.pssql", "CREATE TABLE items (id SERIAL PRIMARY KEY, name TEXT, email TEXT);"),
+                    ("migrations/001_add_name.pssql", "ALTER TABLE items ADD COLUMN name TEXT;"),
+                ]),
+            )
+            .unwrap();
+
+        let state = Arc::new(MigrateV2State {
+            pool_manager: pool_manager.clone(),
+            platform_state: platform_state.clone(),
+        });
+        let migrate = |extra: serde_json::Value| {
+            let state = state.clone();
+            async move {
+                let mut body = serde_json::json!({
+                    "platform": "reordertest",
+                    "schema_name": "tenant_db",
+                    "database_id": "t1",
+                });
+                body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+                let request: MigrateV2Request = serde_json::from_value(body).unwrap();
+                let response = migrate_schema_v2(State(state), HeaderMap::new(), ClientIp(None), Json(request))
+                    .await
+                    .unwrap()
+                    .into_response();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        // Without the flag the drift is only reported
+        let json = migrate(serde_json::json!({ "dry_run": true })).await;
+        let drift = &json["schema_validation"]["column_order_drift"][0];
+        assert_eq!(drift["table"], "items");
+        assert_eq!(drift["declared"], serde_json::json!(["id", "name", "email"]));
+        assert_eq!(drift["actual"], serde_json::json!(["id", "email", "name"]));
+        let sql = json["schema_validation"]["migration_sql"].as_array().cloned().unwrap_or_default();
+        assert!(!sql.iter().any(|s| s.as_str().unwrap().contains("_gw_reorder_items")));
+
+        // With it, the preview carries the rebuild plan
+        let json = migrate(serde_json::json!({ "dry_run": true, "rebuild_column_order": true })).await;
+        let sql = json["schema_validation"]["migration_sql"].as_array().unwrap();
+        assert!(sql.contains(&serde_json::json!("DROP TABLE items;")), "{:?}", sql);
+
+        let json = migrate(serde_json::json!({ "rebuild_column_order": true })).await;
+        assert_eq!(json["migrations_applied"], 1);
+        assert_eq!(json["tables_rebuilt"], serde_json::json!(["items"]));
+        assert_eq!(json["verification"]["passed"], true, "{}", json);
+
+        let client = pool.get().await.unwrap();
+        let columns: Vec<String> = client
+            .query(
+                "SELECT column_name::text FROM information_schema.columns WHERE table_name = 'items' ORDER BY ordinal_position",
+                &[],
+            )
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.get(0))
+            .collect();
+        assert_eq!(columns, vec!["id", "name", "email"]);
+
+        // Rows and the serial sequence survive the rebuild
+        client
+            .batch_execute("INSERT INTO items (email) VALUES ('c@example.com')")
+            .await
+            .unwrap();
+        let rows: Vec<(i32, String)> = client
+            .query("SELECT id, email FROM items ORDER BY id", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| (r.get(0), r.get(1)))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (1, "a@example.com".to_string()),
+                (2, "b@example.com".to_string()),
+                (3, "c@example.com".to_string())
+            ]
+        );
+        drop(client);
+
+        pool_manager.drop_database("reordertest_t1", true).await.unwrap();
     }
 }
@@ -1,16 +1,35 @@
 mod admin;
+mod analyze;
+mod body_limit;
 mod call;
+mod changelog;
 mod database;
+mod diff;
+mod drain;
 mod health;
 mod migrate;
 mod migrate_v2;
+mod multipart;
 mod platform;
 mod register;
+mod reseed;
+mod rollback;
+mod shadow;
+mod types;
+mod validate_archive;
 
-pub use admin::{admin_create_tenant, admin_list_databases};
+pub use admin::{
+    admin_clone_schema, admin_create_tenant, admin_drop_database, admin_drop_impact, admin_list_databases,
+    admin_migration_checksums, admin_pool_stats, admin_refresh_matview, admin_repair_tracking, admin_type_impact,
+};
+pub use analyze::analyze_sql;
+pub use body_limit::json_body_limit;
 pub use call::call_function;
+pub use changelog::{get_changelog_v2, ChangelogState, DetailRedaction};
 pub use database::{create_database, DatabaseState};
-pub use health::health_check;
+pub use diff::diff_schema_v2;
+pub use drain::{track_operation, DrainTracker};
+pub use health::{health_check, readiness_check};
 pub use migrate::migrate_schema;
 pub use migrate_v2::{migrate_schema_v2, MigrateV2State};
 pub use platform::{
@@ -18,3 +37,8 @@ pub use platform::{
     PlatformState,
 };
 pub use register::register_schema;
+pub use reseed::reseed_v2;
+pub use rollback::rollback_migration_v2;
+pub use shadow::shadow_migrate_v2;
+pub use types::{check_types, type_matrix};
+pub use validate_archive::validate_archive;
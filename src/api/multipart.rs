@@ -0,0 +1,66 @@
+//! Shared handling for multipart form uploads
+
+use crate::error::{GatewayError, Result};
+use tracing::warn;
+
+/// Handle a multipart field the endpoint does not recognize
+///
+/// In lenient mode the field is logged and ignored. In strict mode the
+/// request is rejected with the list of accepted field names, so typos
+/// like `schemaname` don't surface as a confusing "missing field" error.
+pub(crate) fn reject_unknown_field(name: &str, accepted: &[&str], strict: bool) -> Result<()> {
+    if !strict {
+        warn!("Unknown field in multipart: {}", name);
+        return Ok(());
+    }
+
+    let normalize = |s: &str| s.replace(['_', '-'], "").to_lowercase();
+    let suggestion = accepted
+        .iter()
+        .find(|candidate| normalize(candidate) == normalize(name))
+        .map(|candidate| format!(" Did you mean '{}'?", candidate))
+        .unwrap_or_default();
+
+    Err(GatewayError::InvalidRequest {
+        message: format!(
+            "Unknown multipart field '{}'.{} Accepted fields: {}",
+            name,
+            suggestion,
+            accepted.join(", ")
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCEPTED: &[&str] = &["schema_name", "name", "schema", "file"];
+
+    #[test]
+    fn test_lenient_mode_ignores_unknown_field() {
+        assert!(reject_unknown_field("schemaname", ACCEPTED, false).is_ok());
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_unknown_field() {
+        let err = reject_unknown_field("schemaname", ACCEPTED, true).unwrap_err();
+
+        match err {
+            GatewayError::InvalidRequest { message } => {
+                assert!(message.contains("'schemaname'"));
+                assert!(message.contains("Did you mean 'schema_name'?"));
+                assert!(message.contains("Accepted fields: schema_name, name, schema, file"));
+            }
+            other => panic!("Expected InvalidRequest, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_strict_mode_without_suggestion() {
+        let err = reject_unknown_field("tenant", ACCEPTED, true).unwrap_err();
+        let message = err.to_string();
+        assert!(!message.contains("Did you mean"));
+        assert!(message.contains("Accepted fields"));
+    }
+}
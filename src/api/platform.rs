@@ -6,8 +6,11 @@
 //! - GET /platform/{platform}/databases - List databases for a platform
 //! - GET /platforms - List all registered platforms
 
+use crate::api::multipart::reject_unknown_field;
 use crate::error::{GatewayError, Result};
-use crate::registry::{PlatformRegistry, SchemaStore};
+use crate::registry::{DatabaseOptions, PlatformRegistry, SchemaStore};
+use crate::schema::{CustomTypeManager, SchemaExtractor, SchemaValidator};
+use crate::security::ClientIp;
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
@@ -15,6 +18,7 @@ use axum::{
     Json,
 };
 use axum_extra::extract::Multipart;
+use ipnetwork::IpNetwork;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::{info, warn};
@@ -23,13 +27,16 @@ use tracing::{info, warn};
 pub struct PlatformState {
     pub registry: PlatformRegistry,
     pub schema_store: SchemaStore,
+    /// Reject unknown multipart fields instead of ignoring them
+    pub strict_multipart: bool,
 }
 
 impl PlatformState {
-    pub fn new(data_dir: &std::path::Path) -> Self {
+    pub fn new(data_dir: &std::path::Path, strict_multipart: bool, schema_history: bool) -> Self {
         Self {
             registry: PlatformRegistry::new(data_dir),
-            schema_store: SchemaStore::new(data_dir),
+            schema_store: SchemaStore::new(data_dir, schema_history),
+            strict_multipart,
         }
     }
 }
@@ -44,6 +51,15 @@ pub struct RegisterPlatformRequest {
     pub db_user: Option<String>,
     /// Optional: PostgreSQL password for platform-specific database isolation
     pub db_password: Option<String>,
+    /// Optional: encoding/locale/template used when creating this platform's databases
+    #[serde(default)]
+    pub database_options: Option<DatabaseOptions>,
+    /// Optional: networks allowed to call this platform's endpoints, on top of the global filter
+    #[serde(default)]
+    pub allowed_networks: Option<Vec<String>>,
+    /// Optional: keep `updated_at` columns current with a gateway-managed trigger
+    #[serde(default)]
+    pub managed_updated_at: Option<bool>,
 }
 
 #[derive(Serialize)]
@@ -58,8 +74,21 @@ pub async fn register_platform(
     State(state): State<Arc<PlatformState>>,
     Json(request): Json<RegisterPlatformRequest>,
 ) -> Result<impl IntoResponse> {
+    if let Some(options) = &request.database_options {
+        options.validate()?;
+    }
+    if let Some(networks) = &request.allowed_networks {
+        for network in networks {
+            if network.parse::<IpNetwork>().is_err() {
+                return Err(GatewayError::InvalidRequest {
+                    message: format!("Invalid network '{}' in allowed_networks", network),
+                });
+            }
+        }
+    }
+
     // Register platform with optional credentials
-    let info = if let (Some(db_user), Some(db_password)) = (request.db_user, request.db_password) {
+    let mut info = if let (Some(db_user), Some(db_password)) = (request.db_user, request.db_password) {
         // Validate credentials are not empty
         if db_user.is_empty() || db_password.is_empty() {
             return Err(GatewayError::InvalidRequest {
@@ -76,6 +105,21 @@ pub async fn register_platform(
         state.registry.register_platform(&request.platform)?
     };
 
+    if let Some(options) = request.database_options {
+        info.database_options = options;
+        state.registry.save_platform_info(&info)?;
+    }
+
+    if let Some(networks) = request.allowed_networks {
+        info.allowed_networks = networks;
+        state.registry.save_platform_info(&info)?;
+    }
+
+    if let Some(managed_updated_at) = request.managed_updated_at {
+        info.managed_updated_at = managed_updated_at;
+        state.registry.save_platform_info(&info)?;
+    }
+
     let has_dedicated_credentials = info.db_user.is_some();
 
     let message = if has_dedicated_credentials {
@@ -106,11 +150,15 @@ pub struct RegisterSchemaResponse {
     pub has_functions: bool,
     pub has_migrations: bool,
     pub checksum: String,
+    /// Non-blocking findings, such as duplicate indexes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 pub async fn register_schema(
     State(state): State<Arc<PlatformState>>,
     Path(platform): Path<String>,
+    ClientIp(ip): ClientIp,
     mut multipart: Multipart,
 ) -> Result<impl IntoResponse> {
     // Check platform is registered
@@ -119,6 +167,7 @@ pub async fn register_schema(
             message: format!("Platform '{}' is not registered. Register it first.", platform),
         });
     }
+    state.registry.check_client_ip(&platform, ip)?;
 
     let mut schema_name: Option<String> = None;
     let mut schema_data: Option<Vec<u8>> = None;
@@ -154,7 +203,11 @@ pub async fn register_schema(
                 );
             }
             _ => {
-                warn!("Unknown field in multipart: {}", name);
+                reject_unknown_field(
+                    &name,
+                    &["schema_name", "name", "schema", "file"],
+                    state.strict_multipart,
+                )?;
             }
         }
     }
@@ -165,9 +218,48 @@ pub async fn register_schema(
     })?;
 
     let schema_data = schema_data.ok_or_else(|| GatewayError::InvalidRequest {
-        message: "Missing required field: schema (tar.gz file)".to_string(),
+        message: "Missing required field: schema (tar.gz or zip file)".to_string(),
     })?;
 
+    // An identical archive is already extracted (and validated)
+    let checksum = SchemaStore::archive_checksum(&schema_data);
+    if !state.schema_store.schema_changed(&platform, &schema_name, &checksum) {
+        let stored = state.schema_store.get_schema(&platform, &schema_name)?;
+        state.registry.add_schema(&platform, &schema_name)?;
+
+        info!("Schema '{}' for platform '{}' is unchanged", schema_name, platform);
+
+        return Ok((
+            StatusCode::OK,
+            Json(RegisterSchemaResponse {
+                status: "unchanged".to_string(),
+                platform,
+                schema_name: stored.name,
+                has_tables: stored.has_tables,
+                has_functions: stored.has_functions,
+                has_migrations: stored.has_migrations,
+                checksum: stored.checksum,
+                warnings: Vec::new(),
+            }),
+        ));
+    }
+
+    // Validate before replacing the stored schema
+    let extractor = SchemaExtractor::from_bytes(&schema_data)?;
+    let issues = SchemaValidator::new().validate(
+        &extractor.tables_dir(),
+        &extractor.migrations_dir(),
+        &extractor.types_dir(),
+    )?;
+    if !issues.is_empty() {
+        return Err(GatewayError::SchemaValidationFailed { issues });
+    }
+    CustomTypeManager::new().validate_enum_defaults(&extractor.types_dir(), &extractor.tables_dir())?;
+    let warnings = SchemaValidator::new().lint(&extractor.tables_dir());
+    for warning in &warnings {
+        warn!("Schema '{}' for platform '{}': {}", schema_name, platform, warning);
+    }
+
     // Store schema
     let stored = state.schema_store.store_schema(&platform, &schema_name, &schema_data)?;
 
@@ -186,6 +278,7 @@ pub async fn register_schema(
             has_functions: stored.has_functions,
             has_migrations: stored.has_migrations,
             checksum: stored.checksum,
+            warnings,
         }),
     ))
 }
@@ -211,6 +304,7 @@ pub struct ListSchemasResponse {
 pub async fn list_schemas(
     State(state): State<Arc<PlatformState>>,
     Path(platform): Path<String>,
+    ClientIp(ip): ClientIp,
 ) -> Result<impl IntoResponse> {
     // Check platform is registered
     if !state.registry.is_registered(&platform) {
@@ -218,6 +312,7 @@ pub async fn list_schemas(
             message: format!("Platform '{}' is not registered", platform),
         });
     }
+    state.registry.check_client_ip(&platform, ip)?;
 
     let schema_names = state.schema_store.list_schemas(&platform)?;
 
@@ -272,6 +367,7 @@ pub async fn list_databases(
     State(state): State<Arc<PlatformState>>,
     Path(platform): Path<String>,
     Query(query): Query<ListDatabasesQuery>,
+    ClientIp(ip): ClientIp,
 ) -> Result<impl IntoResponse> {
     // Check platform is registered
     if !state.registry.is_registered(&platform) {
@@ -279,6 +375,7 @@ pub async fn list_databases(
             message: format!("Platform '{}' is not registered", platform),
         });
     }
+    state.registry.check_client_ip(&platform, ip)?;
 
     let records = state.registry.list_databases(&platform, query.schema.as_deref())?;
 
@@ -355,3 +452,50 @@ pub async fn list_platforms(
         }),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_platform_endpoints_honour_allowed_networks() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = Arc::new(PlatformState::new(temp_dir.path(), false, true));
+
+        let request = RegisterPlatformRequest {
+            platform: "allowlisttest".to_string(),
+            db_user: None,
+            db_password: None,
+            database_options: None,
+            allowed_networks: Some(vec!["10.1.0.0/16".to_string()]),
+            managed_updated_at: None,
+        };
+        register_platform(State(state.clone()), Json(request)).await.unwrap();
+
+        let outside = ClientIp(Some("10.2.0.5".parse().unwrap()));
+        let result = list_schemas(State(state.clone()), Path("allowlisttest".to_string()), outside).await;
+        assert!(matches!(result, Err(GatewayError::Unauthorized { ip }) if ip == "10.2.0.5"));
+
+        let inside = ClientIp(Some("10.1.4.2".parse().unwrap()));
+        assert!(list_schemas(State(state.clone()), Path("allowlisttest".to_string()), inside).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_invalid_allowed_network() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = Arc::new(PlatformState::new(temp_dir.path(), false, true));
+
+        let request = RegisterPlatformRequest {
+            platform: "badnetwork".to_string(),
+            db_user: None,
+            db_password: None,
+            database_options: None,
+            allowed_networks: Some(vec!["10.1.0.0/33".to_string()]),
+            managed_updated_at: None,
+        };
+        let result = register_platform(State(state.clone()), Json(request)).await;
+        assert!(matches!(result, Err(GatewayError::InvalidRequest { .. })));
+        assert!(!state.registry.is_registered("badnetwork"));
+    }
+}
@@ -1,9 +1,15 @@
+use crate::api::changelog::deploy_id;
+use crate::api::multipart::reject_unknown_field;
 use crate::error::{GatewayError, Result};
 use crate::pool::PoolManager;
-use crate::schema::{ChangelogManager, CustomTypeManager, ExtensionManager, FunctionDeployer, SchemaExtractor, SeederRunner, TableDeployer};
+use crate::schema::{
+    ChangelogManager, CustomTypeManager, ExtensionManager, FunctionDeployer, GrantManager, MatviewDeployer, PolicyManager,
+    SchemaExtractor, SeederRunner, TableDeployer,
+};
+use crate::security::ClientIp;
 use axum::{
     extract::State,
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -18,6 +24,9 @@ pub struct SeederInfo {
     table: String,
     inserted: usize,
     skipped: usize,
+    /// Rows the table already had, when that made the seeder skip records
+    #[serde(skip_serializing_if = "Option::is_none")]
+    existing_rows: Option<i64>,
 }
 
 #[derive(Serialize)]
@@ -34,9 +43,12 @@ pub struct RegisterResponse {
 
 pub async fn register_schema(
     State((pool_manager, _)): State<(Arc<PoolManager>, Instant)>,
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
     mut multipart: Multipart,
 ) -> Result<impl IntoResponse> {
     let start_time = Instant::now();
+    let deploy_id = deploy_id(&headers)?;
 
     let mut platform: Option<String> = None;
     let mut tenant_id: Option<String> = None;
@@ -84,7 +96,11 @@ pub async fn register_schema(
                 );
             }
             _ => {
-                warn!("Unknown field in multipart: {}", name);
+                reject_unknown_field(
+                    &name,
+                    &["platform", "tenant_id", "schema"],
+                    pool_manager.config().strict_multipart,
+                )?;
             }
         }
     }
@@ -93,6 +109,7 @@ pub async fn register_schema(
     let platform = platform.ok_or_else(|| GatewayError::InvalidRequest {
         message: "Missing required field: platform".to_string(),
     })?;
+    pool_manager.check_client_ip(&platform, ip).await?;
 
     let schema_data = schema_data.ok_or_else(|| GatewayError::InvalidRequest {
         message: "Missing required field: schema".to_string(),
@@ -113,11 +130,13 @@ pub async fn register_schema(
         });
     }
 
-    // Create new database
-    pool_manager.create_database(&db_name).await?;
-
-    // Extract schema
+    // Extract and validate schema before creating anything
     let extractor = SchemaExtractor::from_bytes(&schema_data)?;
+    CustomTypeManager::new().validate_enum_defaults(&extractor.types_dir(), &extractor.tables_dir())?;
+
+    // Create new database
+    let database_options = pool_manager.database_options_for(&platform)?;
+    pool_manager.create_database(&db_name, &database_options).await?;
 
     // Deploy schema - if anything fails, we'll drop the database to maintain atomicity
     // Database creation is outside this block, and we use DROP DATABASE on failure for rollback
@@ -126,13 +145,13 @@ pub async fn register_schema(
         let pool = pool_manager.get_pool(&platform, tenant_id.as_deref()).await?;
 
         // Initialize changelog table for tracking all schema changes
-        let changelog_manager = ChangelogManager::new();
+        let changelog_manager = ChangelogManager::with_correlation_id(deploy_id.clone());
         changelog_manager.ensure_changelog_table(&pool, &db_name).await?;
 
         // Install extensions first (before types/migrations, as they may depend on them)
         let extension_manager = ExtensionManager::new();
         let extensions_installed = extension_manager
-            .install_extensions(&pool, &db_name, &extractor.extensions_dir())
+            .install_extensions(&pool, &db_name, &extractor.extensions_dir(), &changelog_manager)
             .await?;
 
         // Deploy custom types (after extensions, before tables)
@@ -153,6 +172,21 @@ pub async fn register_schema(
             .deploy_functions(&pool, &db_name, &extractor.functions_dir())
             .await?;
 
+        // Enable row-level security and create policies
+        PolicyManager::new()
+            .deploy_policies(&pool, &db_name, &extractor.policies_dir())
+            .await?;
+
+        // Create materialized views after the tables and functions they select from
+        MatviewDeployer::new()
+            .deploy_matviews(&pool, &db_name, &extractor.matviews_dir())
+            .await?;
+
+        // Apply privileges once every object exists
+        GrantManager::new()
+            .deploy_grants(&pool, &db_name, &extractor.grants_dir())
+            .await?;
+
         // Run seeders (only inserts into empty tables)
         // This is critical - if seeder fails, the entire registration fails
         let seeder_runner = SeederRunner::new();
@@ -177,7 +211,7 @@ pub async fn register_schema(
         Err(e) => {
             warn!("Schema deployment failed for '{}', dropping database: {}", db_name, e);
             // Drop the database on any failure
-            if let Err(drop_err) = pool_manager.drop_database(&db_name).await {
+            if let Err(drop_err) = pool_manager.drop_database(&db_name, true).await {
                 warn!("Failed to drop database '{}' after deployment failure: {}", db_name, drop_err);
             }
             return Err(e);
@@ -190,6 +224,7 @@ pub async fn register_schema(
             table: r.table,
             inserted: r.inserted,
             skipped: r.skipped,
+            existing_rows: (r.existing_rows > 0).then_some(r.existing_rows),
         })
         .collect();
 
@@ -230,6 +265,13 @@ pub async fn register_schema(
                 .await
                 .ok();
         }
+        // Recorded so a failing seeder validation on migrate can say why
+        if let Some(rows) = seeder.existing_rows {
+            changelog_manager
+                .log_seeder_skipped(&pool, &db_name, &seeder.table, "table not empty", rows, seeder.skipped)
+                .await
+                .ok();
+        }
     }
 
     info!(
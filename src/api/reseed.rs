@@ -0,0 +1,141 @@
+//! Reseed API v2 - Reset seeded reference data (development only)
+//!
+//! POST /v2/reseed - Truncate a database's seeded tables and insert the stored seeders again.
+//! Only routed when `ALLOW_RESEED` is enabled.
+
+use crate::api::migrate_v2::MigrateV2State;
+use crate::error::{GatewayError, Result};
+use crate::schema::SeederRunner;
+use crate::security::ClientIp;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+
+#[derive(Debug, Deserialize)]
+pub struct ReseedV2Request {
+    pub platform: String,
+    pub schema_name: String,
+    /// Database to reseed ("main" for the main DB, or a tenant ID)
+    pub database_id: String,
+    /// Must be true; guards against reseeding by accident
+    #[serde(default)]
+    pub reseed: bool,
+    /// Also truncate tables whose foreign keys reference a seeded table
+    #[serde(default)]
+    pub cascade: bool,
+}
+
+#[derive(Serialize)]
+pub struct ReseededTable {
+    table: String,
+    inserted: usize,
+}
+
+#[derive(Serialize)]
+pub struct ReseedV2Response {
+    status: String,
+    platform: String,
+    schema_name: String,
+    database: String,
+    tables: Vec<ReseededTable>,
+    execution_time_ms: u64,
+}
+
+pub async fn reseed_v2(
+    State(state): State<Arc<MigrateV2State>>,
+    ClientIp(ip): ClientIp,
+    Json(request): Json<ReseedV2Request>,
+) -> Result<impl IntoResponse> {
+    let start_time = Instant::now();
+
+    if !request.reseed {
+        return Err(GatewayError::InvalidRequest {
+            message: "Reseeding deletes the seeded tables' data; set \"reseed\": true to confirm".to_string(),
+        });
+    }
+
+    // Check platform is registered
+    if !state.platform_state.registry.is_registered(&request.platform) {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Platform '{}' is not registered. Register it first.",
+                request.platform
+            ),
+        });
+    }
+    state.platform_state.registry.check_client_ip(&request.platform, ip)?;
+
+    // Check schema exists
+    if !state
+        .platform_state
+        .schema_store
+        .schema_exists(&request.platform, &request.schema_name)
+    {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Schema '{}' not found for platform '{}'. Register the schema first.",
+                request.schema_name, request.platform
+            ),
+        });
+    }
+
+    let seeders_dir = state
+        .platform_state
+        .schema_store
+        .seeders_dir(&request.platform, &request.schema_name);
+
+    let db_name = if request.database_id == "main" {
+        format!("{}_main", request.platform)
+    } else {
+        format!("{}_{}", request.platform, request.database_id)
+    };
+
+    if !state.pool_manager.database_exists(&db_name).await? {
+        return Err(GatewayError::DatabaseNotFound {
+            platform: request.platform.clone(),
+            tenant_id: Some(request.database_id.clone()),
+        });
+    }
+
+    let pool = state.pool_manager.get_pool_by_name(&db_name).await?;
+
+    warn!(
+        "Reseeding database '{}' from schema '{}'{}",
+        db_name,
+        request.schema_name,
+        if request.cascade { " (cascade)" } else { "" }
+    );
+
+    let results = SeederRunner::new()
+        .run_seeders_force(&pool, &db_name, &seeders_dir, request.cascade)
+        .await?;
+
+    let execution_time_ms = start_time.elapsed().as_millis() as u64;
+
+    info!(
+        "Reseeded {} tables in database '{}' in {}ms",
+        results.len(),
+        db_name,
+        execution_time_ms
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(ReseedV2Response {
+            status: "reseeded".to_string(),
+            platform: request.platform,
+            schema_name: request.schema_name,
+            database: db_name,
+            tables: results
+                .into_iter()
+                .map(|r| ReseededTable {
+                    table: r.table,
+                    inserted: r.inserted,
+                })
+                .collect(),
+            execution_time_ms,
+        }),
+    ))
+}
@@ -0,0 +1,119 @@
+//! Rollback API v2 - Undo the last applied migration
+//!
+//! POST /v2/rollback - Roll back the latest migration of a database using its `.down.pssql` file
+
+use crate::api::migrate_v2::MigrateV2State;
+use crate::error::{GatewayError, Result};
+use crate::schema::{ChangelogManager, MigrationRunner};
+use crate::security::ClientIp;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub struct RollbackV2Request {
+    pub platform: String,
+    pub schema_name: String,
+    /// Database to roll back ("main" for the main DB, or a tenant ID)
+    pub database_id: String,
+}
+
+#[derive(Serialize)]
+pub struct RollbackV2Response {
+    status: String,
+    platform: String,
+    schema_name: String,
+    database: String,
+    migration_rolled_back: String,
+    execution_time_ms: u64,
+}
+
+pub async fn rollback_migration_v2(
+    State(state): State<Arc<MigrateV2State>>,
+    ClientIp(ip): ClientIp,
+    Json(request): Json<RollbackV2Request>,
+) -> Result<impl IntoResponse> {
+    let start_time = Instant::now();
+
+    // Check platform is registered
+    if !state.platform_state.registry.is_registered(&request.platform) {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Platform '{}' is not registered. Register it first.",
+                request.platform
+            ),
+        });
+    }
+    state.platform_state.registry.check_client_ip(&request.platform, ip)?;
+
+    // Check schema exists
+    if !state
+        .platform_state
+        .schema_store
+        .schema_exists(&request.platform, &request.schema_name)
+    {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Schema '{}' not found for platform '{}'. Register the schema first.",
+                request.schema_name, request.platform
+            ),
+        });
+    }
+
+    let migrations_dir = state
+        .platform_state
+        .schema_store
+        .migrations_dir(&request.platform, &request.schema_name);
+
+    let db_name = if request.database_id == "main" {
+        format!("{}_main", request.platform)
+    } else {
+        format!("{}_{}", request.platform, request.database_id)
+    };
+
+    if !state.pool_manager.database_exists(&db_name).await? {
+        return Err(GatewayError::DatabaseNotFound {
+            platform: request.platform.clone(),
+            tenant_id: Some(request.database_id.clone()),
+        });
+    }
+
+    let pool = state.pool_manager.get_pool_by_name(&db_name).await?;
+
+    let migration_name = MigrationRunner::new()
+        .rollback_last(&pool, &db_name, &migrations_dir)
+        .await?;
+
+    let changelog_manager = ChangelogManager::new();
+    changelog_manager.ensure_changelog_table(&pool, &db_name).await?;
+    changelog_manager
+        .log_migration_rolled_back(
+            &pool,
+            &db_name,
+            &migration_name,
+            &crate::schema::down_file_name(&migration_name),
+        )
+        .await
+        .ok();
+
+    let execution_time_ms = start_time.elapsed().as_millis() as u64;
+
+    info!(
+        "Rolled back migration '{}' on database '{}' in {}ms",
+        migration_name, db_name, execution_time_ms
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(RollbackV2Response {
+            status: "rolled_back".to_string(),
+            platform: request.platform,
+            schema_name: request.schema_name,
+            database: db_name,
+            migration_rolled_back: migration_name,
+            execution_time_ms,
+        }),
+    ))
+}
@@ -0,0 +1,334 @@
+//! Shadow migration API v2 - Dry-run migrations against a throwaway database
+//!
+//! POST /v2/migrate/shadow - Rebuild a tenant's schema in a temporary database,
+//! apply the pending migrations there, verify, and drop it again
+
+use crate::api::database::provision_database_from_dir;
+use crate::api::migrate_v2::MigrateV2State;
+use crate::error::{GatewayError, Result};
+use crate::schema::{
+    FunctionDeployer, HookRunner, HookStage, GrantManager, MatviewDeployer, MigrationRunner, PolicyManager, SchemaVerifier,
+};
+use crate::security::ClientIp;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+
+#[derive(Debug, Deserialize)]
+pub struct ShadowMigrateRequest {
+    pub platform: String,
+    pub schema_name: String,
+    /// Representative database whose schema version and applied migrations are mirrored
+    pub database_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShadowMigrateResponse {
+    status: String,
+    would_succeed: bool,
+    platform: String,
+    schema_name: String,
+    database: String,
+    shadow_database: String,
+    migrations_applied: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    findings: Vec<String>,
+    execution_time_ms: u64,
+}
+
+pub async fn shadow_migrate_v2(
+    State(state): State<Arc<MigrateV2State>>,
+    ClientIp(ip): ClientIp,
+    Json(request): Json<ShadowMigrateRequest>,
+) -> Result<impl IntoResponse> {
+    state.platform_state.registry.check_client_ip(&request.platform, ip)?;
+    let response = shadow_migrate(&state, request).await?;
+    Ok((StatusCode::OK, Json(response)))
+}
+
+async fn shadow_migrate(state: &MigrateV2State, request: ShadowMigrateRequest) -> Result<ShadowMigrateResponse> {
+    let start_time = Instant::now();
+    let registry = &state.platform_state.registry;
+    let schema_store = &state.platform_state.schema_store;
+
+    // Check platform is registered
+    if !registry.is_registered(&request.platform) {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Platform '{}' is not registered. Register it first.",
+                request.platform
+            ),
+        });
+    }
+
+    // Check schema exists
+    if !schema_store.schema_exists(&request.platform, &request.schema_name) {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Schema '{}' not found for platform '{}'. Register the schema first.",
+                request.schema_name, request.platform
+            ),
+        });
+    }
+
+    let db_name = if request.database_id == "main" {
+        format!("{}_main", request.platform)
+    } else {
+        format!("{}_{}", request.platform, request.database_id)
+    };
+
+    if !state.pool_manager.database_exists(&db_name).await? {
+        return Err(GatewayError::DatabaseNotFound {
+            platform: request.platform.clone(),
+            tenant_id: Some(request.database_id.clone()),
+        });
+    }
+
+    let schema_version = registry
+        .find_database(&db_name)?
+        .filter(|(platform, _)| *platform == request.platform)
+        .and_then(|(_, record)| record.schema_version)
+        .ok_or_else(|| GatewayError::InvalidRequest {
+            message: format!(
+                "Database '{}' has no recorded schema version; it must be provisioned or migrated with SCHEMA_HISTORY enabled",
+                db_name
+            ),
+        })?;
+
+    let migration_runner = MigrationRunner::new();
+    let tenant_pool = state.pool_manager.get_pool_by_name(&db_name).await?;
+    let applied = migration_runner.applied_checksums(&tenant_pool, &db_name).await?;
+
+    // Rebuild the tenant's schema version in a fresh database
+    let schema_dir = tempfile::TempDir::new().map_err(|e| {
+        GatewayError::Internal(format!("Failed to create temp directory: {}", e))
+    })?;
+    schema_store.restore_version(
+        &request.platform,
+        &request.schema_name,
+        &schema_version,
+        schema_dir.path(),
+    )?;
+
+    let shadow_db = format!(
+        "{}_shadow_{}",
+        request.platform,
+        chrono::Utc::now().timestamp_millis()
+    );
+
+    info!(
+        "Shadow migration of {} (schema '{}' version {}) in {}",
+        db_name, request.schema_name, schema_version, shadow_db
+    );
+
+    let outcome = async {
+        provision_database_from_dir(
+            &state.pool_manager,
+            &state.platform_state,
+            &request.platform,
+            &request.schema_name,
+            schema_dir.path(),
+            Some(&schema_version),
+            &shadow_db,
+            None,
+        )
+        .await?;
+
+        let pool = state.pool_manager.get_pool_by_name(&shadow_db).await?;
+        migration_runner.record_applied(&pool, &shadow_db, &applied).await?;
+
+        Ok::<_, GatewayError>(apply_pending(state, &request, &shadow_db, &pool).await)
+    }
+    .await;
+
+    // The shadow database never outlives the request
+    if let Err(e) = state.pool_manager.drop_database(&shadow_db, true).await {
+        warn!("Failed to drop shadow database {}: {}", shadow_db, e);
+    }
+    registry.remove_database(&request.platform, &shadow_db).ok();
+
+    let (migrations_applied, error, findings) = match outcome? {
+        Ok((migrations, findings)) => (migrations, None, findings),
+        Err(e) => (0, Some(e.to_string()), Vec::new()),
+    };
+    let would_succeed = error.is_none() && findings.is_empty();
+
+    Ok(ShadowMigrateResponse {
+        status: if would_succeed { "passed" } else { "failed" }.to_string(),
+        would_succeed,
+        platform: request.platform,
+        schema_name: request.schema_name,
+        database: db_name,
+        shadow_database: shadow_db,
+        migrations_applied,
+        error,
+        findings,
+        execution_time_ms: start_time.elapsed().as_millis() as u64,
+    })
+}
+
+/// Run the same steps as /v2/migrate against the shadow database
+///
+/// Returns the number of migrations applied and any verification findings.
+async fn apply_pending(
+    state: &MigrateV2State,
+    request: &ShadowMigrateRequest,
+    shadow_db: &str,
+    pool: &deadpool_postgres::Pool,
+) -> Result<(usize, Vec<String>)> {
+    let store = &state.platform_state.schema_store;
+    let (platform, schema_name) = (request.platform.as_str(), request.schema_name.as_str());
+    let hooks_dir = store.hooks_dir(platform, schema_name);
+    let hook_runner = HookRunner::new();
+
+    hook_runner
+        .run(pool, shadow_db, &hooks_dir, HookStage::PreMigrate)
+        .await?;
+
+    let migrations = MigrationRunner::new()
+        .run_migrations(pool, shadow_db, &store.migrations_dir(platform, schema_name), false)
+        .await?;
+
+    FunctionDeployer::new()
+        .deploy_functions(pool, shadow_db, &store.functions_dir(platform, schema_name))
+        .await?;
+    PolicyManager::new()
+        .deploy_policies(pool, shadow_db, &store.policies_dir(platform, schema_name))
+        .await?;
+    MatviewDeployer::new()
+        .deploy_matviews(pool, shadow_db, &store.matviews_dir(platform, schema_name))
+        .await?;
+    GrantManager::new()
+        .deploy_grants(pool, shadow_db, &store.grants_dir(platform, schema_name))
+        .await?;
+
+    let verification = SchemaVerifier::new()
+        .verify_schema(
+            pool,
+            shadow_db,
+            &store.extensions_dir(platform, schema_name),
+            &store.types_dir(platform, schema_name),
+            &store.tables_dir(platform, schema_name),
+            &store.seeders_dir(platform, schema_name),
+            &store.policies_dir(platform, schema_name),
+            &store.matviews_dir(platform, schema_name),
+            &store.grants_dir(platform, schema_name),
+            &store.functions_dir(platform, schema_name),
+        )
+        .await?;
+
+    hook_runner
+        .run(pool, shadow_db, &hooks_dir, HookStage::PostMigrate)
+        .await?;
+
+    let findings = if verification.passed {
+        Vec::new()
+    } else {
+        verification.findings()
+    };
+
+    Ok((migrations, findings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::database::provision_database;
+    use crate::api::platform::PlatformState;
+    use crate::config::Config;
+    use crate::pool::PoolManager;
+    use crate::schema::test_db::test_database_url;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn archive_with(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut archive_data = Vec::new();
+        {
+            let encoder = GzEncoder::new(&mut archive_data, Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            for (path, content) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_path(path).unwrap();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append(&header, content.as_bytes()).unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        archive_data
+    }
+
+    #[tokio::test]
+    async fn test_shadow_catches_ddl_error_and_drops_shadow() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let pool_manager = Arc::new(PoolManager::new(Config::for_test(&url, data_dir.path())).await.unwrap());
+        let platform_state = Arc::new(PlatformState::new(data_dir.path(), false, true));
+        pool_manager.drop_database("shadowtest_t1", true).await.ok();
+
+        let table = ("tables/items.pssql", "CREATE TABLE items (id SERIAL PRIMARY KEY);");
+        platform_state.registry.register_platform("shadowtest").unwrap();
+        platform_state
+            .schema_store
+            .store_schema("shadowtest", "tenant_db", &archive_with(&[table]))
+            .unwrap();
+        provision_database(&pool_manager, &platform_state, "shadowtest", "tenant_db", "shadowtest_t1", None)
+            .await
+            .unwrap();
+
+        platform_state
+            .schema_store
+            .store_schema(
+                "shadowtest",
+                "tenant_db",
+                &archive_with(&[
+                    table,
+                    ("migrations/001_bad.pssql", "ALTER TABLE items ADD COLUMN x NOT_A_TYPE;"),
+                ]),
+            )
+            .unwrap();
+
+        let state = MigrateV2State {
+            pool_manager: pool_manager.clone(),
+            platform_state: platform_state.clone(),
+        };
+        let response = shadow_migrate(
+            &state,
+            ShadowMigrateRequest {
+                platform: "shadowtest".to_string(),
+                schema_name: "tenant_db".to_string(),
+                database_id: "t1".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(!response.would_succeed);
+        assert_eq!(response.status, "failed");
+        assert!(response.error.unwrap().contains("001_bad.pssql"));
+
+        // The shadow database is gone, the tenant is untouched
+        let remaining = pool_manager.list_databases_for_platform("shadowtest").await.unwrap();
+        assert_eq!(remaining, vec!["shadowtest_t1".to_string()]);
+        assert!(platform_state
+            .registry
+            .find_database(&response.shadow_database)
+            .unwrap()
+            .is_none());
+        let tenant_pool = pool_manager.get_pool_by_name("shadowtest_t1").await.unwrap();
+        let applied = MigrationRunner::new()
+            .applied_checksums(&tenant_pool, "shadowtest_t1")
+            .await
+            .unwrap();
+        assert!(applied.is_empty());
+
+        pool_manager.drop_database("shadowtest_t1", true).await.unwrap();
+    }
+}
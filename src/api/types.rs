@@ -0,0 +1,132 @@
+//! Type compatibility API
+//!
+//! - POST /types/check - Classify a batch of column type changes
+//! - GET /types/matrix - Full compatibility matrix as JSON
+
+use crate::error::{GatewayError, Result};
+use crate::schema::{TypeChecker, TypeCompatibility};
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+
+/// Upper bound on pairs per request
+const MAX_PAIRS: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct TypePair {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize)]
+pub struct TypeCheckResult {
+    pub from: String,
+    pub to: String,
+    pub safe: bool,
+    pub compatibility: TypeCompatibility,
+}
+
+#[derive(Serialize)]
+pub struct TypeCheckResponse {
+    pub results: Vec<TypeCheckResult>,
+    pub all_safe: bool,
+}
+
+pub async fn check_types(Json(pairs): Json<Vec<TypePair>>) -> Result<impl IntoResponse> {
+    if pairs.len() > MAX_PAIRS {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Too many type pairs: {} (maximum {})",
+                pairs.len(),
+                MAX_PAIRS
+            ),
+        });
+    }
+
+    let checker = TypeChecker::new();
+
+    Ok((StatusCode::OK, Json(check_pairs(&checker, pairs))))
+}
+
+pub async fn type_matrix() -> Json<serde_json::Value> {
+    Json(TypeChecker::new().matrix_json())
+}
+
+fn check_pairs(checker: &TypeChecker, pairs: Vec<TypePair>) -> TypeCheckResponse {
+    let results: Vec<TypeCheckResult> = pairs
+        .into_iter()
+        .map(|pair| {
+            let compatibility = checker.check_compatibility(&pair.from, &pair.to);
+            TypeCheckResult {
+                safe: compatibility.is_safe(),
+                from: pair.from,
+                to: pair.to,
+                compatibility,
+            }
+        })
+        .collect();
+
+    let all_safe = results.iter().all(|r| r.safe);
+
+    TypeCheckResponse { results, all_safe }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(from: &str, to: &str) -> TypePair {
+        TypePair {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_check_pairs_mixed() {
+        let checker = TypeChecker::new();
+        let response = check_pairs(
+            &checker,
+            vec![
+                pair("INTEGER", "BIGINT"),
+                pair("BIGINT", "INTEGER"),
+                pair("BOOLEAN", "UUID"),
+                pair("TEXT", "TEXT"),
+            ],
+        );
+
+        assert_eq!(response.results.len(), 4);
+        assert_eq!(response.results[0].compatibility, TypeCompatibility::Safe);
+        assert!(matches!(
+            response.results[1].compatibility,
+            TypeCompatibility::DataLoss { .. }
+        ));
+        assert!(matches!(
+            response.results[2].compatibility,
+            TypeCompatibility::Incompatible { .. }
+        ));
+        assert_eq!(response.results[3].compatibility, TypeCompatibility::Identical);
+        assert!(response.results[0].safe);
+        assert!(!response.results[1].safe);
+        assert!(!response.all_safe);
+    }
+
+    #[test]
+    fn test_check_pairs_all_safe() {
+        let checker = TypeChecker::new();
+        let response = check_pairs(
+            &checker,
+            vec![pair("SMALLINT", "INTEGER"), pair("VARCHAR(50)", "VARCHAR(100)")],
+        );
+
+        assert!(response.all_safe);
+    }
+
+    #[test]
+    fn test_check_pairs_empty() {
+        let checker = TypeChecker::new();
+        let response = check_pairs(&checker, Vec::new());
+
+        assert!(response.results.is_empty());
+        assert!(response.all_safe);
+    }
+}
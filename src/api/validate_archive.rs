@@ -0,0 +1,72 @@
+use crate::api::multipart::reject_unknown_field;
+use crate::error::{GatewayError, Result};
+use crate::pool::PoolManager;
+use crate::registry::SchemaStore;
+use crate::schema::ArchiveReport;
+use axum::{extract::State, response::IntoResponse, Json};
+use axum_extra::extract::Multipart;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+
+#[derive(Serialize)]
+pub struct ValidateArchiveResponse {
+    valid: bool,
+    #[serde(flatten)]
+    report: ArchiveReport,
+}
+
+/// Check an archive's structure without storing it
+///
+/// POST /schema/validate-archive
+///
+/// Accepts the same `schema` (or `file`) field as a registration; no platform is needed.
+pub async fn validate_archive(
+    State((pool_manager, _)): State<(Arc<PoolManager>, Instant)>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse> {
+    let mut schema_data: Option<Vec<u8>> = None;
+
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        GatewayError::InvalidRequest {
+            message: format!("Failed to parse multipart form: {}", e),
+        }
+    })? {
+        let name = field.name().unwrap_or("").to_string();
+
+        match name.as_str() {
+            "schema" | "file" => {
+                schema_data = Some(
+                    field
+                        .bytes()
+                        .await
+                        .map_err(|e| GatewayError::InvalidRequest {
+                            message: format!("Failed to read schema file: {}", e),
+                        })?
+                        .to_vec(),
+                );
+            }
+            _ => {
+                reject_unknown_field(&name, &["schema", "file"], pool_manager.config().strict_multipart)?;
+            }
+        }
+    }
+
+    let schema_data = schema_data.ok_or_else(|| GatewayError::InvalidRequest {
+        message: "Missing required field: schema (tar.gz or zip file)".to_string(),
+    })?;
+
+    let report = SchemaStore::inspect_archive(&schema_data)?;
+    let valid = report.is_valid();
+
+    info!(
+        "Validated {} archive: {} entries, {} violations, {} missing folders",
+        report.format,
+        report.entry_count,
+        report.violations.len(),
+        report.missing_folders.len()
+    );
+
+    Ok(Json(ValidateArchiveResponse { valid, report }))
+}
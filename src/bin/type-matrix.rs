@@ -2,6 +2,7 @@
 //!
 //! Usage:
 //!   cargo run --bin type-matrix
+//!   cargo run --bin type-matrix -- --json
 //!   cargo run --bin type-matrix -- VARCHAR(100) VARCHAR(50)
 
 use std::env;
@@ -44,6 +45,12 @@ fn main() {
                 std::process::exit(2);
             }
         }
+    } else if args.len() == 2 && args[1] == "--json" {
+        // Display full matrix as JSON
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&checker.matrix_json()).unwrap_or_default()
+        );
     } else if args.len() == 1 {
         // Display full matrix
         print!("{}", checker.format_matrix());
@@ -51,6 +58,7 @@ fn main() {
         eprintln!("Usage:");
         eprintln!("  {} <from_type> <to_type>  - Check specific conversion", args[0]);
         eprintln!("  {}                        - Display full matrix", args[0]);
+        eprintln!("  {} --json                 - Display full matrix as JSON", args[0]);
         eprintln!();
         eprintln!("Examples:");
         eprintln!("  {} INTEGER BIGINT", args[0]);
@@ -12,12 +12,75 @@ pub struct Config {
     pub gateway_port: u16,
     pub max_connections_per_pool: u32,
     pub max_total_connections: u32,
+    /// Concurrent `/call` requests per database (0 = unlimited)
+    pub max_calls_per_database: usize,
+    /// Largest accepted JSON request body (schema uploads are not affected)
+    pub max_json_body_bytes: usize,
     pub pool_idle_timeout: Duration,
     pub pool_max_lifetime: Duration,
+    /// How often idle pools are swept
+    pub cleanup_interval: Duration,
+    /// Wait for a free connection when the pool is at max size
+    pub pool_wait_timeout: Duration,
+    /// Establishing a new connection
+    pub pool_create_timeout: Duration,
+    /// Health check of a connection before reuse
+    pub pool_recycle_timeout: Duration,
+    /// Retries of a failed connection test when creating a database pool
+    pub connect_retries: u32,
+    /// Delay before the first retry; doubled (with jitter) for each further one
+    pub connect_retry_base_delay: Duration,
     pub allowed_networks: Vec<IpNetwork>,
+    /// Check client addresses against `allowed_networks`
+    pub ip_filter: bool,
+    /// Reverse proxies whose X-Forwarded-For header is trusted
+    pub trusted_proxies: Vec<IpNetwork>,
+    /// Keys accepted by the API key check (empty disables it)
+    pub api_keys: Vec<String>,
     pub data_dir: PathBuf,
     pub admin_token: Option<String>,
     pub allowed_admin_ips: Vec<IpNetwork>,
+    pub strict_multipart: bool,
+    pub schema_history: bool,
+    pub db_sslmode: SslMode,
+    /// CA certificate (PEM) used to verify the server with `verify-full`
+    pub db_ssl_root_cert: Option<PathBuf>,
+    /// How long shutdown waits for running migrate/register operations
+    pub shutdown_grace_period: Duration,
+    /// Extra type compatibility rules (`from_type,to_type,classification[,reason]`)
+    pub type_rules_file: Option<PathBuf>,
+    /// Route `POST /v2/reseed` (development only: it truncates seeded tables)
+    pub allow_reseed: bool,
+    /// Changelog detail keys hidden from callers without the admin token
+    pub changelog_redact_keys: Vec<String>,
+}
+
+/// TLS mode for PostgreSQL connections, named after libpq's `sslmode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SslMode {
+    /// Plain TCP connections
+    #[default]
+    Disable,
+    /// Encrypt, but do not verify the server certificate or host name
+    Require,
+    /// Encrypt and verify the certificate chain and host name
+    VerifyFull,
+}
+
+impl FromStr for SslMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "disable" => Ok(SslMode::Disable),
+            "require" => Ok(SslMode::Require),
+            "verify-full" => Ok(SslMode::VerifyFull),
+            other => Err(anyhow::anyhow!(
+                "Invalid DB_SSLMODE '{}' (expected disable, require or verify-full)",
+                other
+            )),
+        }
+    }
 }
 
 impl Config {
@@ -55,6 +118,16 @@ impl Config {
             .parse()
             .unwrap_or(200);
 
+        let max_calls_per_database = env::var("MAX_CALLS_PER_DATABASE")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .unwrap_or(0);
+
+        let max_json_body_bytes = env::var("MAX_JSON_BODY_BYTES")
+            .unwrap_or_else(|_| "1048576".to_string())
+            .parse()
+            .unwrap_or(1048576);
+
         let pool_idle_timeout_secs: u64 = env::var("POOL_IDLE_TIMEOUT_SECS")
             .unwrap_or_else(|_| "1800".to_string())
             .parse()
@@ -65,6 +138,37 @@ impl Config {
             .parse()
             .unwrap_or(3600);
 
+        let cleanup_interval_secs: u64 = env::var("CLEANUP_INTERVAL_SECS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse()
+            .unwrap_or(300);
+
+        let pool_wait_timeout_secs: u64 = env::var("POOL_WAIT_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .unwrap_or(5);
+
+        let pool_create_timeout_secs: u64 = env::var("POOL_CREATE_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .unwrap_or(5);
+
+        let pool_recycle_timeout_secs: u64 = env::var("POOL_RECYCLE_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .unwrap_or(5);
+
+        // Retry transient connection failures (database restart, failover)
+        let connect_retries: u32 = env::var("CONNECT_RETRIES")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse()
+            .unwrap_or(3);
+
+        let connect_retry_base_delay_ms: u64 = env::var("CONNECT_RETRY_BASE_DELAY_MS")
+            .unwrap_or_else(|_| "200".to_string())
+            .parse()
+            .unwrap_or(200);
+
         let allowed_networks_str =
             env::var("ALLOWED_NETWORKS").unwrap_or_else(|_| "127.0.0.0/8,::1/128,192.168.0.0/16".to_string());
 
@@ -80,6 +184,48 @@ impl Config {
             })
             .collect();
 
+        let ip_filter = env::var("IP_FILTER")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+
+        // X-Forwarded-For is ignored unless the peer is one of these proxies
+        let trusted_proxies = env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|s| {
+                let trimmed = s.trim();
+                if trimmed.is_empty() {
+                    None
+                } else {
+                    IpNetwork::from_str(trimmed).ok()
+                }
+            })
+            .collect();
+
+        // API keys from API_KEYS (comma-separated) and/or API_KEYS_FILE (one per line, # comments)
+        let mut api_keys: Vec<String> = env::var("API_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty())
+            .collect();
+
+        if let Some(path) = env::var("API_KEYS_FILE").ok().filter(|v| !v.is_empty()) {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to read API_KEYS_FILE '{}': {}", path, e))?;
+            api_keys.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|k| !k.is_empty() && !k.starts_with('#'))
+                    .map(String::from),
+            );
+        }
+
+        if !ip_filter && api_keys.is_empty() {
+            anyhow::bail!("IP_FILTER=false requires API_KEYS or API_KEYS_FILE; refusing to start without access control");
+        }
+
         let data_dir = env::var("DATA_DIR")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("./data"));
@@ -102,18 +248,80 @@ impl Config {
             })
             .collect();
 
+        // Reject unknown multipart form fields (default: log and ignore)
+        let strict_multipart = env::var("STRICT_MULTIPART")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        // Keep every registered schema version (deduplicated, compressed) under DATA_DIR
+        let schema_history = env::var("SCHEMA_HISTORY")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        // TLS for connections to PostgreSQL (PGSSLMODE/PGSSLROOTCERT are honoured as fallbacks)
+        let db_sslmode = env::var("DB_SSLMODE")
+            .or_else(|_| env::var("PGSSLMODE"))
+            .map(|v| v.parse())
+            .unwrap_or(Ok(SslMode::Disable))?;
+
+        let db_ssl_root_cert = env::var("DB_SSL_ROOT_CERT")
+            .or_else(|_| env::var("PGSSLROOTCERT"))
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+
+        let shutdown_grace_period_secs: u64 = env::var("SHUTDOWN_GRACE_PERIOD_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .unwrap_or(30);
+
+        let type_rules_file = env::var("TYPE_RULES_FILE")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+
+        let allow_reseed = env::var("ALLOW_RESEED")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        let changelog_redact_keys = env::var("CHANGELOG_REDACT_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty())
+            .collect();
+
         Ok(Config {
             database_url,
             gateway_host,
             gateway_port,
             max_connections_per_pool,
             max_total_connections,
+            max_calls_per_database,
+            max_json_body_bytes,
             pool_idle_timeout: Duration::from_secs(pool_idle_timeout_secs),
             pool_max_lifetime: Duration::from_secs(pool_max_lifetime_secs),
+            cleanup_interval: Duration::from_secs(cleanup_interval_secs),
+            pool_wait_timeout: Duration::from_secs(pool_wait_timeout_secs),
+            pool_create_timeout: Duration::from_secs(pool_create_timeout_secs),
+            pool_recycle_timeout: Duration::from_secs(pool_recycle_timeout_secs),
+            connect_retries,
+            connect_retry_base_delay: Duration::from_millis(connect_retry_base_delay_ms),
             allowed_networks,
+            ip_filter,
+            trusted_proxies,
+            api_keys,
             data_dir,
             admin_token,
             allowed_admin_ips,
+            strict_multipart,
+            schema_history,
+            db_sslmode,
+            db_ssl_root_cert,
+            shutdown_grace_period: Duration::from_secs(shutdown_grace_period_secs),
+            type_rules_file,
+            allow_reseed,
+            changelog_redact_keys,
         })
     }
 
@@ -122,3 +330,42 @@ impl Config {
         addr.parse().map_err(|e| anyhow::anyhow!("Invalid socket address: {}", e))
     }
 }
+
+#[cfg(test)]
+impl Config {
+    /// Defaults for tests that need a live PoolManager
+    pub fn for_test(database_url: &str, data_dir: &std::path::Path) -> Self {
+        Config {
+            database_url: database_url.to_string(),
+            gateway_host: "127.0.0.1".to_string(),
+            gateway_port: 0,
+            max_connections_per_pool: 4,
+            max_total_connections: 20,
+            max_calls_per_database: 0,
+            max_json_body_bytes: 1024 * 1024,
+            pool_idle_timeout: Duration::from_secs(60),
+            pool_max_lifetime: Duration::from_secs(60),
+            cleanup_interval: Duration::from_secs(300),
+            pool_wait_timeout: Duration::from_secs(5),
+            pool_create_timeout: Duration::from_secs(5),
+            pool_recycle_timeout: Duration::from_secs(5),
+            connect_retries: 0,
+            connect_retry_base_delay: Duration::from_millis(10),
+            allowed_networks: Vec::new(),
+            ip_filter: true,
+            trusted_proxies: Vec::new(),
+            api_keys: Vec::new(),
+            data_dir: data_dir.to_path_buf(),
+            admin_token: None,
+            allowed_admin_ips: Vec::new(),
+            strict_multipart: false,
+            schema_history: true,
+            db_sslmode: SslMode::Disable,
+            db_ssl_root_cert: None,
+            shutdown_grace_period: Duration::from_secs(30),
+            type_rules_file: None,
+            allow_reseed: false,
+            changelog_redact_keys: Vec::new(),
+        }
+    }
+}
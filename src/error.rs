@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -17,6 +17,9 @@ pub enum GatewayError {
     #[error("Database already exists: {database}")]
     DatabaseAlreadyExists { database: String },
 
+    #[error("Database {database} exists with different options: {cause}")]
+    DatabaseOptionsMismatch { database: String, cause: String },
+
     #[error("Migration failed in {database}: {migration} - {cause}")]
     MigrationFailed {
         database: String,
@@ -24,6 +27,9 @@ pub enum GatewayError {
         cause: String,
     },
 
+    #[error("Migration already in progress for {database}")]
+    MigrationInProgress { database: String },
+
     #[error("Function deployment failed in {database}: {function} - {cause}")]
     FunctionDeployFailed {
         database: String,
@@ -51,15 +57,24 @@ pub enum GatewayError {
     #[error("Schema extraction failed: {cause}")]
     SchemaExtractionFailed { cause: String },
 
+    #[error("Schema validation failed: {}", issues.join("; "))]
+    SchemaValidationFailed { issues: Vec<String> },
+
     #[error("Connection failed to {database}: {cause}")]
     ConnectionFailed { database: String, cause: String },
 
     #[error("Connection pool exhausted for {database}")]
     PoolExhausted { database: String },
 
+    #[error("Too many concurrent calls to {database} (limit {limit})")]
+    TooManyCalls { database: String, limit: usize },
+
     #[error("Unauthorized access from IP: {ip}")]
     Unauthorized { ip: String },
 
+    #[error("Strict mode rejected migration of {database}: {}", findings.join("; "))]
+    StrictModeViolation { database: String, findings: Vec<String> },
+
     #[error("Invalid request: {message}")]
     InvalidRequest { message: String },
 
@@ -69,6 +84,9 @@ pub enum GatewayError {
         target_platform: String,
     },
 
+    #[error("Gateway is shutting down")]
+    ShuttingDown,
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -107,6 +125,18 @@ impl IntoResponse for GatewayError {
                     cause: None,
                 },
             ),
+            GatewayError::DatabaseOptionsMismatch { database, cause } => (
+                StatusCode::CONFLICT,
+                ErrorResponse {
+                    error: "database_options_mismatch".to_string(),
+                    message: format!(
+                        "Database '{}' already exists with a different encoding/locale",
+                        database
+                    ),
+                    database: Some(database.clone()),
+                    cause: Some(cause.clone()),
+                },
+            ),
             GatewayError::MigrationFailed {
                 database,
                 migration,
@@ -120,6 +150,18 @@ impl IntoResponse for GatewayError {
                     cause: Some(cause.clone()),
                 },
             ),
+            GatewayError::MigrationInProgress { database } => (
+                StatusCode::CONFLICT,
+                ErrorResponse {
+                    error: "migration_in_progress".to_string(),
+                    message: format!(
+                        "Another migration of '{}' is already in progress; retry once it finishes",
+                        database
+                    ),
+                    database: Some(database.clone()),
+                    cause: None,
+                },
+            ),
             GatewayError::FunctionDeployFailed {
                 database,
                 function,
@@ -169,6 +211,15 @@ impl IntoResponse for GatewayError {
                     cause: Some(cause.clone()),
                 },
             ),
+            GatewayError::SchemaValidationFailed { issues } => (
+                StatusCode::BAD_REQUEST,
+                ErrorResponse {
+                    error: "schema_validation_failed".to_string(),
+                    message: format!("Uploaded schema has {} issue(s)", issues.len()),
+                    database: None,
+                    cause: Some(issues.join("\n")),
+                },
+            ),
             GatewayError::ConnectionFailed { database, cause } => (
                 StatusCode::SERVICE_UNAVAILABLE,
                 ErrorResponse {
@@ -187,6 +238,18 @@ impl IntoResponse for GatewayError {
                     cause: None,
                 },
             ),
+            GatewayError::TooManyCalls { database, limit } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                ErrorResponse {
+                    error: "too_many_calls".to_string(),
+                    message: format!(
+                        "Database '{}' already has {} calls in progress; retry shortly",
+                        database, limit
+                    ),
+                    database: Some(database.clone()),
+                    cause: None,
+                },
+            ),
             GatewayError::Unauthorized { ip } => (
                 StatusCode::FORBIDDEN,
                 ErrorResponse {
@@ -196,6 +259,18 @@ impl IntoResponse for GatewayError {
                     cause: None,
                 },
             ),
+            GatewayError::StrictModeViolation { database, findings } => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorResponse {
+                    error: "strict_mode_violation".to_string(),
+                    message: format!(
+                        "Strict mode: {} warning(s) treated as errors",
+                        findings.len()
+                    ),
+                    database: Some(database.clone()),
+                    cause: Some(findings.join("\n")),
+                },
+            ),
             GatewayError::InvalidRequest { message } => (
                 StatusCode::BAD_REQUEST,
                 ErrorResponse {
@@ -220,6 +295,15 @@ impl IntoResponse for GatewayError {
                     cause: None,
                 },
             ),
+            GatewayError::ShuttingDown => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                ErrorResponse {
+                    error: "shutting_down".to_string(),
+                    message: "Gateway is shutting down; retry against another instance or after restart".to_string(),
+                    database: None,
+                    cause: None,
+                },
+            ),
             GatewayError::Internal(msg) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ErrorResponse {
@@ -231,7 +315,14 @@ impl IntoResponse for GatewayError {
             ),
         };
 
-        (status, Json(error_response)).into_response()
+        let mut response = (status, Json(error_response)).into_response();
+        if matches!(self, GatewayError::TooManyCalls { .. }) {
+            // Calls finish quickly; a second is enough for a slot to free up
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+        }
+        response
     }
 }
 
@@ -260,6 +351,14 @@ impl From<std::io::Error> for GatewayError {
     }
 }
 
+impl From<crate::schema::ParseError> for GatewayError {
+    fn from(err: crate::schema::ParseError) -> Self {
+        GatewayError::SchemaExtractionFailed {
+            cause: err.to_string(),
+        }
+    }
+}
+
 impl From<anyhow::Error> for GatewayError {
     fn from(err: anyhow::Error) -> Self {
         GatewayError::Internal(err.to_string())
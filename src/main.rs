@@ -7,25 +7,28 @@ mod schema;
 mod security;
 
 use crate::api::{
-    admin_create_tenant, admin_list_databases, call_function, create_database, health_check,
-    list_databases, list_platforms, list_schemas, migrate_schema, migrate_schema_v2,
-    register_platform, register_platform_schema, register_schema, DatabaseState, MigrateV2State,
-    PlatformState,
+    admin_clone_schema, admin_create_tenant, admin_drop_database, admin_drop_impact, admin_list_databases,
+    admin_migration_checksums, admin_pool_stats, admin_refresh_matview, admin_repair_tracking, admin_type_impact, analyze_sql, call_function, check_types, create_database, diff_schema_v2, get_changelog_v2, health_check, json_body_limit, list_databases, list_platforms, list_schemas, migrate_schema,
+    migrate_schema_v2, readiness_check, register_platform, register_platform_schema, register_schema, reseed_v2, rollback_migration_v2,
+    shadow_migrate_v2, track_operation, type_matrix, validate_archive, ChangelogState, DatabaseState, DetailRedaction, DrainTracker,
+    MigrateV2State, PlatformState,
 };
 use crate::config::Config;
 use crate::pool::PoolManager;
-use crate::schema::AuditLogger;
-use crate::security::{admin_auth_middleware, AdminAuthConfig, IpFilterLayer};
+use crate::schema::{AuditLogger, TypeChecker};
+use crate::security::{admin_auth_middleware, AdminAuthConfig, ApiKeyLayer, IpFilterLayer};
 
 use axum::{
     routing::{get, post},
     Router,
 };
 use std::net::SocketAddr;
+use std::future::IntoFuture;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::signal;
-use tokio::time::{interval, Duration};
+use tokio::sync::Notify;
+use tokio::time::interval;
 use tower_http::trace::TraceLayer;
 use tracing::{debug, info, warn};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
@@ -79,10 +82,24 @@ async fn main() -> anyhow::Result<()> {
     info!("Max connections per pool: {}", config.max_connections_per_pool);
     info!("Max total connections: {}", config.max_total_connections);
     info!(
-        "Pool idle timeout: {:?}",
-        config.pool_idle_timeout
+        "Pool idle timeout: {:?} (checked every {:?})",
+        config.pool_idle_timeout, config.cleanup_interval
+    );
+    info!(
+        "Pool timeouts: wait {:?}, create {:?}, recycle {:?}",
+        config.pool_wait_timeout, config.pool_create_timeout, config.pool_recycle_timeout
     );
     info!("Allowed networks: {:?}", config.allowed_networks);
+    if !config.trusted_proxies.is_empty() {
+        info!("Trusted proxies (X-Forwarded-For): {:?}", config.trusted_proxies);
+    }
+    if config.strict_multipart {
+        info!("Strict multipart mode enabled - unknown form fields are rejected");
+    }
+    if let Some(path) = &config.type_rules_file {
+        let count = TypeChecker::load_rules_file(path)?;
+        info!("Loaded {} custom type compatibility rules from {:?}", count, path);
+    }
 
     // Create pool manager
     let pool_manager = Arc::new(PoolManager::new(config.clone()).await?);
@@ -96,8 +113,63 @@ async fn main() -> anyhow::Result<()> {
         info!("Audit logging table initialized successfully");
     }
 
+    // Migrate/register operations are counted so shutdown can wait for them
+    let drain = DrainTracker::new();
+    let app = build_router(&config, pool_manager.clone(), &drain);
+
+    // Spawn cleanup task for idle pools
+    let cleanup_pool_manager = pool_manager.clone();
+    let cleanup_interval = config.cleanup_interval;
+    tokio::spawn(async move {
+        let mut interval = interval(cleanup_interval);
+
+        loop {
+            interval.tick().await;
+            let removed = cleanup_pool_manager.cleanup_idle_pools().await;
+            if removed > 0 {
+                info!("Cleanup task removed {} idle pools", removed);
+            }
+        }
+    });
+
+    // Create listener
+    let listener = tokio::net::TcpListener::bind(&socket_addr).await?;
+    info!("Server listening on {}", socket_addr);
+
+    // Run server with graceful shutdown; operations still running after the
+    // grace period are abandoned rather than waited for indefinitely
+    let abandoned = Arc::new(Notify::new());
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal(
+        drain.clone(),
+        config.shutdown_grace_period,
+        abandoned.clone(),
+    ));
+
+    tokio::select! {
+        result = server.into_future() => result?,
+        _ = abandoned.notified() => {}
+    }
+
+    info!("Server shutdown complete");
+
+    Ok(())
+}
+
+/// Build the HTTP router with all endpoints and middleware
+///
+/// axum only applies a layer to the routes added before it, so endpoints
+/// behind the IP filter and API key must be registered above those layers.
+fn build_router(config: &Config, pool_manager: Arc<PoolManager>, drain: &DrainTracker) -> Router {
     // Create platform state for schema registry
-    let platform_state = Arc::new(PlatformState::new(&config.data_dir));
+    let platform_state = Arc::new(PlatformState::new(
+        &config.data_dir,
+        config.strict_multipart,
+        config.schema_history,
+    ));
 
     // Create database state (combines pool manager and platform state)
     let database_state = Arc::new(DatabaseState {
@@ -111,11 +183,27 @@ async fn main() -> anyhow::Result<()> {
         platform_state: platform_state.clone(),
     });
 
+    // Changelog details hidden from callers without the admin token
+    let changelog_state = Arc::new(ChangelogState {
+        migrate_v2: migrate_v2_state.clone(),
+        redaction: DetailRedaction::new(config.changelog_redact_keys.clone(), config.admin_token.clone()),
+    });
+
     // Start time for uptime tracking
     let start_time = Instant::now();
 
-    // Create IP filter middleware
-    let ip_filter = IpFilterLayer::new(config.allowed_networks.clone());
+    // Create IP filter and API key middleware (either can be used alone or both together)
+    let ip_filter = if config.ip_filter {
+        IpFilterLayer::new(config.allowed_networks.clone())
+    } else {
+        warn!("IP filter DISABLED - IP_FILTER=false");
+        IpFilterLayer::allow_all()
+    }
+    .with_trusted_proxies(config.trusted_proxies.clone());
+    let api_key = ApiKeyLayer::new(&config.api_keys);
+    if api_key.is_enabled() {
+        info!("API key authentication enabled ({} keys)", config.api_keys.len());
+    }
 
     // Create admin auth config
     let admin_auth_config = Arc::new(AdminAuthConfig::new(
@@ -131,6 +219,12 @@ async fn main() -> anyhow::Result<()> {
         warn!("Admin endpoints DISABLED - ADMIN_TOKEN not configured");
     }
 
+    // JSON bodies get their own (smaller) limit; multipart uploads keep axum's default
+    let json_limit = json_body_limit(config.max_json_body_bytes);
+
+    // Migrate/register operations are counted so shutdown can wait for them
+    let tracked = axum::middleware::from_fn_with_state(drain.clone(), track_operation);
+
     // Build admin routes (protected by admin auth middleware)
     // Note: Different admin endpoints need different state types
     let admin_platforms_routes = Router::new()
@@ -144,20 +238,88 @@ async fn main() -> anyhow::Result<()> {
     let admin_db_routes = Router::new()
         .route("/databases", get(admin_list_databases))
         .route("/create-tenant", post(admin_create_tenant))
+        .route("/drop-impact", post(admin_drop_impact))
+        .route("/type-impact", post(admin_type_impact))
+        .route("/migration-checksums", get(admin_migration_checksums))
+        .route("/pool-stats", get(admin_pool_stats))
+        .route("/repair-tracking", post(admin_repair_tracking))
+        .route("/refresh-matview", post(admin_refresh_matview))
         .with_state((pool_manager.clone(), start_time))
+        .layer(json_limit)
         .layer(axum::middleware::from_fn_with_state(
             admin_auth_config.clone(),
             admin_auth_middleware,
         ));
 
-    // Build router with legacy and new endpoints
-    let app = Router::new()
-        // Health check (no IP filter - for load balancer)
+    // Admin endpoints that also read/update the platform registry
+    let admin_registry_routes = Router::new()
+        .route("/clone-schema", post(admin_clone_schema))
+        .route("/drop-database", post(admin_drop_database))
+        .with_state(database_state.clone())
+        .layer(json_limit)
+        .layer(axum::middleware::from_fn_with_state(
+            admin_auth_config.clone(),
+            admin_auth_middleware,
+        ));
+
+    // Endpoints behind the IP filter and API key
+    let routes = Router::new()
+        // Liveness and readiness checks (exempted by the IP filter - for load balancer)
         .route("/health", get(health_check))
+        .route("/health/ready", get(readiness_check))
         // Legacy endpoints (v1 - multipart form with schema upload)
-        .route("/register", post(register_schema))
-        .route("/migrate", post(migrate_schema))
-        .route("/call", post(call_function))
+        .route("/register", post(register_schema).layer(tracked.clone()))
+        .route("/migrate", post(migrate_schema).layer(tracked.clone()))
+        .route("/call", post(call_function).layer(json_limit))
+        // Type compatibility matrix for tooling
+        .route("/types/check", post(check_types).layer(json_limit))
+        .route("/types/matrix", get(type_matrix))
+        // SQL dependency analysis for schema editors
+        .route("/analyze/sql", post(analyze_sql).layer(json_limit))
+        // Structural check of a schema archive before registering it
+        .route("/schema/validate-archive", post(validate_archive))
+        // New database creation endpoint
+        .route(
+            "/database/create",
+            post(create_database).layer(json_limit).with_state(database_state),
+        )
+        // New migrate endpoint using stored schemas
+        .route(
+            "/v2/migrate",
+            post(migrate_schema_v2).layer(json_limit).layer(tracked.clone()).with_state(migrate_v2_state.clone()),
+        )
+        // Undo the last applied migration (dev)
+        .route(
+            "/v2/rollback",
+            post(rollback_migration_v2).layer(json_limit).layer(tracked.clone()).with_state(migrate_v2_state.clone()),
+        )
+        // Try pending migrations on a throwaway copy of a tenant's schema
+        .route(
+            "/v2/migrate/shadow",
+            post(shadow_migrate_v2).layer(json_limit).layer(tracked.clone()).with_state(migrate_v2_state.clone()),
+        )
+        // Preview table drift against a database without running DDL
+        .route(
+            "/v2/diff",
+            post(diff_schema_v2).layer(json_limit).with_state(migrate_v2_state.clone()),
+        )
+        // Audit trail of schema changes applied to a database
+        .route("/v2/changelog", get(get_changelog_v2).with_state(changelog_state));
+
+    // Development only: truncate seeded tables and insert the seeders again
+    let routes = if config.allow_reseed {
+        warn!("ALLOW_RESEED is enabled - POST /v2/reseed can truncate seeded tables");
+        routes.route(
+            "/v2/reseed",
+            post(reseed_v2).layer(json_limit).layer(tracked.clone()).with_state(migrate_v2_state),
+        )
+    } else {
+        routes
+    };
+
+    // Build router with legacy and new endpoints
+    routes
+        .layer(api_key.clone())
         .layer(ip_filter.clone())
         .layer(TraceLayer::new_for_http())
         .with_state((pool_manager.clone(), start_time))
@@ -165,59 +327,24 @@ async fn main() -> anyhow::Result<()> {
         .nest(
             "/platform",
             Router::new()
-                .route("/register", post(register_platform))
-                .route("/{platform}/schema", post(register_platform_schema))
+                .route("/register", post(register_platform).layer(json_limit).layer(tracked.clone()))
+                .route("/{platform}/schema", post(register_platform_schema).layer(tracked.clone()))
                 .route("/{platform}/schemas", get(list_schemas))
                 .route("/{platform}/databases", get(list_databases))
+                .layer(api_key.clone())
                 .layer(ip_filter.clone())
                 .with_state(platform_state.clone()),
         )
         // Admin endpoints (protected by admin auth + IP filter)
         .nest("/admin", admin_platforms_routes)
         .nest("/admin", admin_db_routes)
-        // New database creation endpoint
-        .route(
-            "/database/create",
-            post(create_database).with_state(database_state),
-        )
-        // New migrate endpoint using stored schemas
-        .route(
-            "/v2/migrate",
-            post(migrate_schema_v2).with_state(migrate_v2_state),
-        );
-
-    // Spawn cleanup task for idle pools
-    let cleanup_pool_manager = pool_manager.clone();
-    tokio::spawn(async move {
-        let mut interval = interval(Duration::from_secs(300)); // Every 5 minutes
-
-        loop {
-            interval.tick().await;
-            let removed = cleanup_pool_manager.cleanup_idle_pools().await;
-            if removed > 0 {
-                info!("Cleanup task removed {} idle pools", removed);
-            }
-        }
-    });
-
-    // Create listener
-    let listener = tokio::net::TcpListener::bind(&socket_addr).await?;
-    info!("Server listening on {}", socket_addr);
-
-    // Run server with graceful shutdown
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .with_graceful_shutdown(shutdown_signal())
-    .await?;
-
-    info!("Server shutdown complete");
-
-    Ok(())
+        .nest("/admin", admin_registry_routes)
 }
 
-async fn shutdown_signal() {
+/// Wait for Ctrl+C/SIGTERM, then drain migrate/register operations
+///
+/// `abandoned` is notified when operations are still running after `grace_period`.
+async fn shutdown_signal(drain: DrainTracker, grace_period: Duration, abandoned: Arc<Notify>) {
     let ctrl_c = async {
         signal::ctrl_c()
             .await
@@ -241,4 +368,84 @@ async fn shutdown_signal() {
     }
 
     info!("Received shutdown signal");
+
+    if drain.active() > 0 {
+        info!(
+            "Waiting up to {:?} for {} running operation(s)",
+            grace_period,
+            drain.active()
+        );
+    }
+    let remaining = drain.drain(grace_period).await;
+    if remaining > 0 {
+        warn!(
+            "Grace period elapsed with {} operation(s) still running; shutting down",
+            remaining
+        );
+        abandoned.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_db::test_database_url;
+    use axum::{
+        body::Body,
+        extract::ConnectInfo,
+        http::{Request, StatusCode},
+    };
+    use tempfile::TempDir;
+    use tower::Service;
+
+    async fn status(app: &mut Router, path: &str, key: Option<&str>) -> StatusCode {
+        let mut req = Request::post(path).header("content-type", "application/json");
+        if let Some(key) = key {
+            req = req.header("x-api-key", key);
+        }
+        let mut req = req.body(Body::from("{}")).unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+        app.call(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_v2_routes_require_api_key() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let dir = TempDir::new().unwrap();
+        let mut config = Config::for_test(&url, dir.path());
+        config.api_keys = vec!["s3cret".to_string()];
+        let pool_manager = Arc::new(PoolManager::new(config.clone()).await.unwrap());
+        let mut app = build_router(&config, pool_manager, &DrainTracker::new());
+
+        for path in ["/v2/migrate", "/v2/rollback", "/v2/migrate/shadow", "/v2/diff", "/database/create"] {
+            assert_eq!(status(&mut app, path, None).await, StatusCode::UNAUTHORIZED, "{}", path);
+        }
+        // With the key the request reaches the handler, which rejects the empty body
+        assert_eq!(
+            status(&mut app, "/v2/migrate", Some("s3cret")).await,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reseed_requires_api_key() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let dir = TempDir::new().unwrap();
+        let mut config = Config::for_test(&url, dir.path());
+        config.api_keys = vec!["s3cret".to_string()];
+        config.allow_reseed = true;
+        let pool_manager = Arc::new(PoolManager::new(config.clone()).await.unwrap());
+        let mut app = build_router(&config, pool_manager, &DrainTracker::new());
+
+        assert_eq!(status(&mut app, "/v2/reseed", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status(&mut app, "/v2/reseed", Some("s3cret")).await,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
 }
@@ -0,0 +1,91 @@
+//! Per-database limit on concurrent function calls
+//!
+//! Calls beyond a pool's size queue for a connection, so a burst to one
+//! tenant keeps its pool saturated and holds gateway resources that other
+//! tenants need. With a limit set, calls over it are rejected up front with
+//! 503 and `Retry-After` instead of queueing.
+
+use crate::error::{GatewayError, Result};
+use dashmap::DashMap;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Concurrent call slots per database
+pub struct CallLimiter {
+    /// Calls allowed at once per database; 0 disables the limit
+    limit: usize,
+    slots: DashMap<String, Arc<Semaphore>>,
+}
+
+impl CallLimiter {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            slots: DashMap::new(),
+        }
+    }
+
+    /// Take a call slot for `database`, held until the permit is dropped
+    ///
+    /// Returns None when no limit is configured, and TooManyCalls when every
+    /// slot of the database is taken.
+    pub fn acquire(&self, database: &str) -> Result<Option<OwnedSemaphorePermit>> {
+        if self.limit == 0 {
+            return Ok(None);
+        }
+
+        let semaphore = self
+            .slots
+            .entry(database.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.limit)))
+            .clone();
+
+        semaphore
+            .try_acquire_owned()
+            .map(Some)
+            .map_err(|_| GatewayError::TooManyCalls {
+                database: database.to_string(),
+                limit: self.limit,
+            })
+    }
+
+    /// Drop the slots of a database whose pool was removed
+    ///
+    /// Calls still holding a permit keep their semaphore alive until they finish.
+    pub fn remove(&self, database: &str) {
+        self.slots.remove(database);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    #[test]
+    fn test_limit_is_per_database() {
+        let limiter = CallLimiter::new(2);
+        let first = limiter.acquire("app_t1").unwrap();
+        let _second = limiter.acquire("app_t1").unwrap();
+
+        let err = limiter.acquire("app_t1").unwrap_err();
+        assert!(matches!(err, GatewayError::TooManyCalls { limit: 2, .. }));
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()["retry-after"], "1");
+
+        // Another tenant is unaffected
+        assert!(limiter.acquire("app_t2").unwrap().is_some());
+
+        // A finished call frees its slot
+        drop(first);
+        assert!(limiter.acquire("app_t1").is_ok());
+    }
+
+    #[test]
+    fn test_zero_disables_limit() {
+        let limiter = CallLimiter::new(0);
+        let permits: Vec<_> = (0..100).map(|_| limiter.acquire("app_main").unwrap()).collect();
+        assert!(permits.iter().all(Option::is_none));
+    }
+}
@@ -1,20 +1,78 @@
 use crate::config::Config;
 use crate::error::{GatewayError, Result};
+use crate::pool::call_limit::CallLimiter;
 use crate::pool::router::DatabaseRouter;
-use crate::registry::PlatformRegistry;
+use crate::pool::tls::make_tls_connector;
+use crate::registry::{validate_platform_name, DatabaseOptions, PlatformRegistry};
 use dashmap::DashMap;
-use deadpool_postgres::{Config as PoolConfig, Pool, Runtime};
+use deadpool_postgres::{Config as PoolConfig, Object, Pool, PoolError, Runtime};
+use postgres_native_tls::MakeTlsConnector;
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{OnceCell, OwnedSemaphorePermit, RwLock};
+use tokio_postgres::error::SqlState;
 use tokio_postgres::NoTls;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 struct PoolEntry {
     pool: Pool,
     last_used: RwLock<Instant>,
+    /// Server version, queried once per pool
+    server_version: OnceCell<ServerVersion>,
+}
+
+/// PostgreSQL server version of a database
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServerVersion {
+    /// Raw `server_version_num` (e.g. 150004)
+    pub version_num: i32,
+    pub major: i32,
+    pub minor: i32,
+    /// Full `version()` string
+    pub full: String,
+}
+
+impl ServerVersion {
+    /// Parse `server_version_num` into major/minor
+    ///
+    /// Since PostgreSQL 10 the format is MMmmmm (150004 = 15.4). Older
+    /// releases use MMmmpp (90624 = 9.6.24), where the major is reported
+    /// as the leading digit and minor as the patch level.
+    pub fn from_version_num(version_num: i32, full: String) -> Self {
+        let (major, minor) = if version_num >= 100000 {
+            (version_num / 10000, version_num % 10000)
+        } else {
+            (version_num / 10000, version_num % 100)
+        };
+
+        Self {
+            version_num,
+            major,
+            minor,
+            full,
+        }
+    }
+}
+
+/// Usage of one cached database pool
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStats {
+    pub database: String,
+    /// Connections currently open
+    pub size: usize,
+    /// Open connections not checked out
+    pub available: usize,
+    /// Requests waiting for a connection
+    pub waiting: usize,
+    pub max_size: usize,
+    pub last_used: chrono::DateTime<chrono::Utc>,
+    pub idle_seconds: u64,
 }
 
 pub struct PoolManager {
@@ -24,12 +82,17 @@ pub struct PoolManager {
     total_connections: AtomicU32,
     admin_pool: Pool,
     data_dir: PathBuf,
+    /// None when DB_SSLMODE=disable
+    tls: Option<MakeTlsConnector>,
+    call_limiter: CallLimiter,
 }
 
 impl PoolManager {
     pub async fn new(config: Config) -> Result<Self> {
+        let tls = make_tls_connector(config.db_sslmode, config.db_ssl_root_cert.as_deref())?;
+
         // Create admin pool for connecting to the main postgres database
-        let admin_pool = create_pool(&config.database_url, config.max_connections_per_pool)?;
+        let admin_pool = create_pool(&config.database_url, &config, tls.clone())?;
 
         // Test admin connection
         let client = admin_pool.get().await.map_err(|e| {
@@ -50,6 +113,7 @@ impl PoolManager {
         info!("Connected to PostgreSQL admin database");
 
         let data_dir = config.data_dir.clone();
+        let call_limiter = CallLimiter::new(config.max_calls_per_database);
 
         Ok(Self {
             pools: DashMap::new(),
@@ -58,13 +122,24 @@ impl PoolManager {
             total_connections: AtomicU32::new(0),
             admin_pool,
             data_dir,
+            tls,
+            call_limiter,
         })
     }
 
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     pub fn admin_pool(&self) -> &Pool {
         &self.admin_pool
     }
 
+    /// Take one of the database's concurrent call slots (MAX_CALLS_PER_DATABASE)
+    pub fn acquire_call_slot(&self, db_name: &str) -> Result<Option<OwnedSemaphorePermit>> {
+        self.call_limiter.acquire(db_name)
+    }
+
     pub async fn get_pool(&self, platform: &str, tenant_id: Option<&str>) -> Result<Pool> {
         let db_name = self.router.database_name(platform, tenant_id);
 
@@ -100,17 +175,15 @@ impl PoolManager {
         // Build database URL for this specific database
         let db_url = self.database_url_for(db_name)?;
 
-        let pool = create_pool(&db_url, self.config.max_connections_per_pool)?;
+        let pool = create_pool(&db_url, &self.config, self.tls.clone())?;
 
         // Test the connection
-        let _ = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
-            database: db_name.to_string(),
-            cause: e.to_string(),
-        })?;
+        let _ = self.connect_with_retry(&pool, db_name).await?;
 
         let entry = Arc::new(PoolEntry {
             pool: pool.clone(),
             last_used: RwLock::new(Instant::now()),
+            server_version: OnceCell::new(),
         });
 
         self.pools.insert(db_name.to_string(), entry);
@@ -122,6 +195,33 @@ impl PoolManager {
         Ok(pool)
     }
 
+    /// Get a connection, retrying transient failures with exponential backoff
+    ///
+    /// Authentication errors and missing databases fail immediately.
+    async fn connect_with_retry(&self, pool: &Pool, db_name: &str) -> Result<Object> {
+        let mut attempt = 0;
+        loop {
+            match pool.get().await {
+                Ok(client) => return Ok(client),
+                Err(e) if attempt < self.config.connect_retries && is_transient(&e) => {
+                    let delay = backoff_delay(self.config.connect_retry_base_delay, attempt, jitter_seed());
+                    attempt += 1;
+                    warn!(
+                        "Connection to {} failed ({}), retry {}/{} in {:?}",
+                        db_name, e, attempt, self.config.connect_retries, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    return Err(GatewayError::ConnectionFailed {
+                        database: db_name.to_string(),
+                        cause: e.to_string(),
+                    })
+                }
+            }
+        }
+    }
+
     fn database_url_for(&self, db_name: &str) -> Result<String> {
         // Extract platform from database name (format: platform_main or platform_tenant)
         let platform = db_name.split('_').next().unwrap_or("");
@@ -202,6 +302,7 @@ impl PoolManager {
 
         if let Some(key) = oldest_key {
             if let Some((_, _removed)) = self.pools.remove(&key) {
+                self.call_limiter.remove(&key);
                 self.total_connections
                     .fetch_sub(self.config.max_connections_per_pool, Ordering::Relaxed);
                 info!("Evicted pool for database: {} (idle since {:?} ago)", key, oldest_time.elapsed());
@@ -227,6 +328,7 @@ impl PoolManager {
 
         for key in to_remove {
             if let Some((_, _)) = self.pools.remove(&key) {
+                self.call_limiter.remove(&key);
                 self.total_connections
                     .fetch_sub(self.config.max_connections_per_pool, Ordering::Relaxed);
                 removed += 1;
@@ -260,7 +362,34 @@ impl PoolManager {
         Ok(row.is_some())
     }
 
-    pub async fn create_database(&self, db_name: &str) -> Result<()> {
+    /// Get the CREATE DATABASE options configured for a platform
+    ///
+    /// Falls back to server defaults when the platform is not registered; an
+    /// unreadable platform.json is an error rather than a silent default.
+    pub fn database_options_for(&self, platform: &str) -> Result<DatabaseOptions> {
+        let registry = PlatformRegistry::new(&self.data_dir);
+        if !registry.is_registered(platform) {
+            return Ok(DatabaseOptions::default());
+        }
+        Ok(registry.get_platform_info(platform)?.database_options)
+    }
+
+    /// Apply the platform's `allowed_networks` to a client address
+    ///
+    /// Unregistered platforms are only subject to the global IP filter. The
+    /// platform name is validated before it is used as a path, and
+    /// platform.json is read on the blocking pool.
+    pub async fn check_client_ip(&self, platform: &str, ip: Option<IpAddr>) -> Result<()> {
+        validate_platform_name(platform)?;
+
+        let registry = PlatformRegistry::new(&self.data_dir);
+        let platform = platform.to_string();
+        tokio::task::spawn_blocking(move || registry.check_client_ip(&platform, ip))
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Platform allowlist check failed: {}", e)))?
+    }
+
+    pub async fn create_database(&self, db_name: &str, options: &DatabaseOptions) -> Result<()> {
         let client = self.admin_pool.get().await.map_err(|e| {
             GatewayError::ConnectionFailed {
                 database: "postgres (admin)".to_string(),
@@ -268,21 +397,41 @@ impl PoolManager {
             }
         })?;
 
-        // Check if already exists
-        if self.database_exists(db_name).await? {
+        // Check if already exists - make sure it matches the requested options
+        let existing = client
+            .query_opt(
+                "SELECT pg_encoding_to_char(encoding), datcollate::text, datctype::text \
+                 FROM pg_database WHERE datname = $1",
+                &[&db_name],
+            )
+            .await
+            .map_err(|e| GatewayError::Internal(e.to_string()))?;
+
+        if let Some(row) = existing {
+            let encoding: String = row.get(0);
+            let collate: Option<String> = row.get(1);
+            let ctype: Option<String> = row.get(2);
+
+            let mismatches = option_mismatches(
+                options,
+                &encoding,
+                collate.as_deref().unwrap_or(""),
+                ctype.as_deref().unwrap_or(""),
+            );
+            if !mismatches.is_empty() {
+                warn!("Database {} exists with different options: {:?}", db_name, mismatches);
+                return Err(GatewayError::DatabaseOptionsMismatch {
+                    database: db_name.to_string(),
+                    cause: mismatches.join("; "),
+                });
+            }
+
             debug!("Database {} already exists", db_name);
             return Ok(());
         }
 
         // Create database (note: can't use parameters for DDL)
-        // Validate db_name to prevent SQL injection
-        if !is_valid_identifier(db_name) {
-            return Err(GatewayError::InvalidRequest {
-                message: format!("Invalid database name: {}", db_name),
-            });
-        }
-
-        let sql = format!("CREATE DATABASE \"{}\"", db_name);
+        let sql = build_create_database_sql(db_name, options)?;
         client
             .batch_execute(&sql)
             .await
@@ -292,14 +441,12 @@ impl PoolManager {
         Ok(())
     }
 
-    pub async fn drop_database(&self, db_name: &str) -> Result<()> {
-        let client = self.admin_pool.get().await.map_err(|e| {
-            GatewayError::ConnectionFailed {
-                database: "postgres (admin)".to_string(),
-                cause: e.to_string(),
-            }
-        })?;
-
+    /// Drop a database, closing its cached pool first
+    ///
+    /// With `force`, other sessions connected to the database are terminated
+    /// (`WITH (FORCE)` on PostgreSQL 13+, `pg_terminate_backend` before that);
+    /// without it, a database in use is not dropped.
+    pub async fn drop_database(&self, db_name: &str, force: bool) -> Result<()> {
         // Validate db_name to prevent SQL injection
         if !is_valid_identifier(db_name) {
             return Err(GatewayError::InvalidRequest {
@@ -307,22 +454,57 @@ impl PoolManager {
             });
         }
 
-        // Remove pool if it exists
-        self.pools.remove(db_name);
+        // Close our own connections so they don't keep the database in use
+        if let Some((_, entry)) = self.pools.remove(db_name) {
+            entry.pool.close();
+            self.call_limiter.remove(db_name);
+            self.total_connections
+                .fetch_sub(self.config.max_connections_per_pool, Ordering::Relaxed);
+            debug!("Closed pool for database: {}", db_name);
+        }
 
-        // Terminate all connections to the database before dropping
-        let terminate_sql = format!(
-            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE datname = '{}' AND pid <> pg_backend_pid()",
-            db_name
-        );
-        client.execute(&terminate_sql, &[]).await.ok(); // Ignore errors - database might not have active connections
+        let client = self.admin_pool.get().await.map_err(|e| {
+            GatewayError::ConnectionFailed {
+                database: "postgres (admin)".to_string(),
+                cause: e.to_string(),
+            }
+        })?;
 
-        // Drop the database
-        let sql = format!("DROP DATABASE IF EXISTS \"{}\"", db_name);
-        client
-            .batch_execute(&sql)
-            .await
-            .map_err(|e| GatewayError::Internal(format!("Failed to drop database: {}", e)))?;
+        // WITH (FORCE) needs PostgreSQL 13; older servers get the sessions
+        // terminated first and a plain DROP
+        let with_force = if force {
+            let row = client
+                .query_one("SELECT current_setting('server_version_num')::int", &[])
+                .await
+                .map_err(|e| GatewayError::Internal(format!("Failed to query server version: {}", e)))?;
+            supports_drop_force(row.get(0))
+        } else {
+            false
+        };
+        if force && !with_force {
+            client
+                .execute(
+                    "SELECT pg_terminate_backend(pid) FROM pg_stat_activity \
+                     WHERE datname = $1 AND pid <> pg_backend_pid()",
+                    &[&db_name],
+                )
+                .await
+                .map_err(|e| GatewayError::Internal(format!("Failed to terminate connections: {}", e)))?;
+        }
+
+        let sql = build_drop_database_sql(db_name, with_force);
+        client.batch_execute(&sql).await.map_err(|e| {
+            if e.code() == Some(&tokio_postgres::error::SqlState::OBJECT_IN_USE) {
+                GatewayError::InvalidRequest {
+                    message: format!(
+                        "Database '{}' has active connections; retry with force to terminate them",
+                        db_name
+                    ),
+                }
+            } else {
+                GatewayError::Internal(format!("Failed to drop database: {}", e))
+            }
+        })?;
 
         info!("Dropped database: {}", db_name);
         Ok(())
@@ -356,10 +538,57 @@ impl PoolManager {
         self.total_connections.load(Ordering::Relaxed)
     }
 
+    /// Status of every cached pool, sorted by database name
+    pub async fn pool_stats(&self) -> Vec<PoolStats> {
+        let now = Instant::now();
+        let wall_now = chrono::Utc::now();
+
+        let entries: Vec<(String, Arc<PoolEntry>)> = self
+            .pools
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+
+        let mut stats = Vec::with_capacity(entries.len());
+        for (database, entry) in entries {
+            let idle = now.duration_since(*entry.last_used.read().await);
+            let status = entry.pool.status();
+            stats.push(PoolStats {
+                database,
+                size: status.size,
+                available: status.available,
+                waiting: status.waiting,
+                max_size: status.max_size,
+                last_used: wall_now - chrono::Duration::from_std(idle).unwrap_or_default(),
+                idle_seconds: idle.as_secs(),
+            });
+        }
+
+        stats.sort_by(|a, b| a.database.cmp(&b.database));
+        stats
+    }
+
     pub fn database_name(&self, platform: &str, tenant_id: Option<&str>) -> String {
         self.router.database_name(platform, tenant_id)
     }
 
+    /// Get the PostgreSQL server version for a database (cached per pool)
+    pub async fn server_version(&self, db_name: &str) -> Result<ServerVersion> {
+        let pool = self.get_pool_by_name(db_name).await?;
+
+        let entry = match self.pools.get(db_name) {
+            Some(entry) => entry.clone(),
+            // Pool was evicted in between; query without caching
+            None => return query_server_version(&pool, db_name).await,
+        };
+
+        entry
+            .server_version
+            .get_or_try_init(|| query_server_version(&entry.pool, db_name))
+            .await
+            .cloned()
+    }
+
     pub async fn get_database_size(&self, db_name: &str) -> Result<i64> {
         let pool = self.get_pool_by_name(db_name).await?;
         let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
@@ -376,22 +605,161 @@ impl PoolManager {
     }
 }
 
-fn create_pool(database_url: &str, max_size: u32) -> Result<Pool> {
+async fn query_server_version(pool: &Pool, db_name: &str) -> Result<ServerVersion> {
+    let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+        database: db_name.to_string(),
+        cause: e.to_string(),
+    })?;
+
+    let row = client
+        .query_one(
+            "SELECT current_setting('server_version_num')::int, version()",
+            &[],
+        )
+        .await
+        .map_err(|e| GatewayError::Internal(format!("Failed to query server version: {}", e)))?;
+
+    Ok(ServerVersion::from_version_num(row.get(0), row.get(1)))
+}
+
+/// Whether a failed connection attempt is worth retrying
+fn is_transient(error: &PoolError) -> bool {
+    match error {
+        PoolError::Timeout(_) => true,
+        PoolError::Backend(e) => is_transient_code(e.code()),
+        _ => false,
+    }
+}
+
+/// Errors without a SQLSTATE (refused, reset, closed) are network-level and
+/// transient; of the server errors, only those raised while it is starting,
+/// shutting down or out of connection slots are.
+fn is_transient_code(code: Option<&SqlState>) -> bool {
+    match code {
+        None => true,
+        Some(code) => {
+            code.code().starts_with("08")
+                || *code == SqlState::CANNOT_CONNECT_NOW
+                || *code == SqlState::ADMIN_SHUTDOWN
+                || *code == SqlState::CRASH_SHUTDOWN
+                || *code == SqlState::TOO_MANY_CONNECTIONS
+        }
+    }
+}
+
+/// Delay before retry `attempt` (0-based): `base * 2^attempt`, of which the
+/// upper half is randomized by `seed` so callers don't retry in lockstep
+fn backoff_delay(base: Duration, attempt: u32, seed: u64) -> Duration {
+    let delay = base.saturating_mul(1u32 << attempt.min(16));
+    let half = delay / 2;
+    let jitter_nanos = half.as_nanos() as u64;
+    if jitter_nanos == 0 {
+        return delay;
+    }
+    half + Duration::from_nanos(seed % (jitter_nanos + 1))
+}
+
+fn jitter_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Build a pool with the configured size and timeouts
+fn create_pool(database_url: &str, config: &Config, tls: Option<MakeTlsConnector>) -> Result<Pool> {
     let mut cfg = PoolConfig::new();
     cfg.url = Some(database_url.to_string());
 
     cfg.pool = Some(deadpool_postgres::PoolConfig {
-        max_size: max_size as usize,
+        max_size: config.max_connections_per_pool as usize,
         timeouts: deadpool_postgres::Timeouts {
-            wait: Some(Duration::from_secs(5)),
-            create: Some(Duration::from_secs(5)),
-            recycle: Some(Duration::from_secs(5)),
+            wait: Some(config.pool_wait_timeout),
+            create: Some(config.pool_create_timeout),
+            recycle: Some(config.pool_recycle_timeout),
         },
         ..Default::default()
     });
 
-    cfg.create_pool(Some(Runtime::Tokio1), NoTls)
-        .map_err(|e| GatewayError::Internal(format!("Failed to create pool: {}", e)))
+    match tls {
+        Some(tls) => {
+            // The default (prefer) silently falls back to plaintext when the server refuses TLS
+            cfg.ssl_mode = Some(deadpool_postgres::SslMode::Require);
+            cfg.create_pool(Some(Runtime::Tokio1), tls)
+        }
+        None => cfg.create_pool(Some(Runtime::Tokio1), NoTls),
+    }
+    .map_err(|e| GatewayError::Internal(format!("Failed to create pool: {}", e)))
+}
+
+/// Build the DROP DATABASE statement for an already validated name
+/// `DROP DATABASE ... WITH (FORCE)` exists since PostgreSQL 13
+fn supports_drop_force(server_version_num: i32) -> bool {
+    server_version_num >= 130000
+}
+
+fn build_drop_database_sql(db_name: &str, force: bool) -> String {
+    if force {
+        format!("DROP DATABASE IF EXISTS \"{}\" WITH (FORCE)", db_name)
+    } else {
+        format!("DROP DATABASE IF EXISTS \"{}\"", db_name)
+    }
+}
+
+/// Build the CREATE DATABASE statement, validating the name and options
+fn build_create_database_sql(db_name: &str, options: &DatabaseOptions) -> Result<String> {
+    // Validate db_name to prevent SQL injection
+    if !is_valid_identifier(db_name) {
+        return Err(GatewayError::InvalidRequest {
+            message: format!("Invalid database name: {}", db_name),
+        });
+    }
+
+    options.validate()?;
+
+    let mut sql = format!("CREATE DATABASE \"{}\"", db_name);
+
+    if let Some(template) = &options.template {
+        sql.push_str(&format!(" TEMPLATE \"{}\"", template));
+    }
+    if let Some(encoding) = &options.encoding {
+        sql.push_str(&format!(" ENCODING '{}'", encoding));
+    }
+    if let Some(lc_collate) = &options.lc_collate {
+        sql.push_str(&format!(" LC_COLLATE '{}'", lc_collate));
+    }
+    if let Some(lc_ctype) = &options.lc_ctype {
+        sql.push_str(&format!(" LC_CTYPE '{}'", lc_ctype));
+    }
+
+    Ok(sql)
+}
+
+/// Compare requested options against an existing database's settings
+fn option_mismatches(
+    options: &DatabaseOptions,
+    encoding: &str,
+    collate: &str,
+    ctype: &str,
+) -> Vec<String> {
+    let mut mismatches = Vec::new();
+
+    if let Some(requested) = &options.encoding {
+        // PostgreSQL accepts aliases like "utf-8" for UTF8
+        let normalize = |s: &str| s.replace(['-', '_'], "").to_uppercase();
+        if normalize(requested) != normalize(encoding) {
+            mismatches.push(format!("encoding is {} (requested {})", encoding, requested));
+        }
+    }
+    if let Some(requested) = &options.lc_collate {
+        if requested != collate {
+            mismatches.push(format!("LC_COLLATE is {} (requested {})", collate, requested));
+        }
+    }
+    if let Some(requested) = &options.lc_ctype {
+        if requested != ctype {
+            mismatches.push(format!("LC_CTYPE is {} (requested {})", ctype, requested));
+        }
+    }
+
+    mismatches
 }
 
 fn is_valid_identifier(name: &str) -> bool {
@@ -411,6 +779,8 @@ fn is_valid_identifier(name: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::SslMode;
+    use crate::schema::test_db::test_database_url;
 
     #[test]
     fn test_valid_identifier() {
@@ -423,4 +793,202 @@ mod tests {
         assert!(!is_valid_identifier("1_test")); // Starts with number
         assert!(!is_valid_identifier("Test_DB")); // Contains uppercase
     }
+
+    #[test]
+    fn test_server_version_from_version_num() {
+        let version = ServerVersion::from_version_num(150004, "PostgreSQL 15.4".to_string());
+        assert_eq!(version.major, 15);
+        assert_eq!(version.minor, 4);
+
+        let version = ServerVersion::from_version_num(160010, String::new());
+        assert_eq!((version.major, version.minor), (16, 10));
+
+        // Pre-10 numbering: 9.6.24
+        let version = ServerVersion::from_version_num(90624, String::new());
+        assert_eq!((version.major, version.minor), (9, 24));
+    }
+
+    #[test]
+    fn test_create_database_sql_with_options() {
+        let options = DatabaseOptions {
+            encoding: Some("UTF8".to_string()),
+            lc_collate: Some("en_US.UTF-8".to_string()),
+            lc_ctype: Some("en_US.UTF-8".to_string()),
+            template: Some("template0".to_string()),
+        };
+
+        let sql = build_create_database_sql("myapp_main", &options).unwrap();
+        assert_eq!(
+            sql,
+            "CREATE DATABASE \"myapp_main\" TEMPLATE \"template0\" ENCODING 'UTF8' LC_COLLATE 'en_US.UTF-8' LC_CTYPE 'en_US.UTF-8'"
+        );
+
+        // No options: server defaults
+        let sql = build_create_database_sql("myapp_main", &DatabaseOptions::default()).unwrap();
+        assert_eq!(sql, "CREATE DATABASE \"myapp_main\"");
+
+        // Invalid option values are rejected
+        let bad = DatabaseOptions {
+            encoding: Some("UTF8' TEMPLATE evil --".to_string()),
+            ..Default::default()
+        };
+        assert!(build_create_database_sql("myapp_main", &bad).is_err());
+    }
+
+    #[test]
+    fn test_drop_database_sql() {
+        assert_eq!(
+            build_drop_database_sql("myapp_clinic_001", false),
+            "DROP DATABASE IF EXISTS \"myapp_clinic_001\""
+        );
+        assert_eq!(
+            build_drop_database_sql("myapp_clinic_001", true),
+            "DROP DATABASE IF EXISTS \"myapp_clinic_001\" WITH (FORCE)"
+        );
+    }
+
+    #[test]
+    fn test_drop_force_needs_postgres_13() {
+        assert!(!supports_drop_force(90624));
+        assert!(!supports_drop_force(120015));
+        assert!(supports_drop_force(130000));
+        assert!(supports_drop_force(160002));
+    }
+
+    #[test]
+    fn test_option_mismatches() {
+        let options = DatabaseOptions {
+            encoding: Some("utf-8".to_string()),
+            lc_collate: Some("C".to_string()),
+            ..Default::default()
+        };
+
+        assert!(option_mismatches(&options, "UTF8", "C", "en_US.UTF-8").is_empty());
+
+        let mismatches = option_mismatches(&options, "LATIN1", "en_US.UTF-8", "en_US.UTF-8");
+        assert_eq!(mismatches.len(), 2);
+        assert!(mismatches[0].contains("LATIN1"));
+
+        // Nothing requested, nothing to compare
+        assert!(option_mismatches(&DatabaseOptions::default(), "SQL_ASCII", "C", "C").is_empty());
+    }
+
+    #[test]
+    fn test_backoff_delay_doubles_with_bounded_jitter() {
+        let base = Duration::from_millis(100);
+        for seed in [0, 1, 12345, u64::MAX] {
+            for attempt in 0..4 {
+                let full = base * 2u32.pow(attempt);
+                let delay = backoff_delay(base, attempt, seed);
+                assert!(delay >= full / 2 && delay <= full, "attempt {}: {:?}", attempt, delay);
+            }
+        }
+        assert_eq!(backoff_delay(base, 2, 0), Duration::from_millis(200));
+        assert_eq!(backoff_delay(Duration::ZERO, 3, 7), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        assert!(is_transient_code(None));
+        assert!(is_transient_code(Some(&SqlState::CANNOT_CONNECT_NOW)));
+        assert!(is_transient_code(Some(&SqlState::ADMIN_SHUTDOWN)));
+        assert!(is_transient_code(Some(&SqlState::CONNECTION_FAILURE)));
+        assert!(is_transient_code(Some(&SqlState::TOO_MANY_CONNECTIONS)));
+
+        assert!(!is_transient_code(Some(&SqlState::INVALID_PASSWORD)));
+        assert!(!is_transient_code(Some(&SqlState::INVALID_AUTHORIZATION_SPECIFICATION)));
+        assert!(!is_transient_code(Some(&SqlState::INVALID_CATALOG_NAME)));
+    }
+
+    #[test]
+    fn test_pool_uses_configured_timeouts() {
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let mut config = Config::for_test("postgres://gateway@localhost/postgres", data_dir.path());
+        config.pool_wait_timeout = Duration::from_secs(30);
+        config.pool_create_timeout = Duration::from_secs(7);
+        config.pool_recycle_timeout = Duration::from_secs(12);
+
+        // Creating a pool does not connect
+        let timeouts = create_pool(&config.database_url, &config, None).unwrap().timeouts();
+        assert_eq!(timeouts.wait, Some(Duration::from_secs(30)));
+        assert_eq!(timeouts.create, Some(Duration::from_secs(7)));
+        assert_eq!(timeouts.recycle, Some(Duration::from_secs(12)));
+    }
+
+    #[tokio::test]
+    async fn test_tls_modes_refuse_plaintext_server() {
+        // Needs a server without TLS, like the usual local test database
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        for mode in [SslMode::Require, SslMode::VerifyFull] {
+            let mut config = Config::for_test(&url, data_dir.path());
+            config.db_sslmode = mode;
+            let err = PoolManager::new(config).await.err().expect("connected without TLS");
+            assert!(matches!(err, GatewayError::ConnectionFailed { .. }), "{:?}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_database_is_not_retried() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let mut config = Config::for_test(&url, data_dir.path());
+        config.connect_retries = 5;
+        config.connect_retry_base_delay = Duration::from_secs(2);
+        let manager = PoolManager::new(config).await.unwrap();
+
+        let started = Instant::now();
+        let err = manager.get_pool_by_name("gw_test_does_not_exist").await.unwrap_err();
+        assert!(matches!(err, GatewayError::ConnectionFailed { .. }), "{:?}", err);
+        assert!(started.elapsed() < Duration::from_secs(2), "retried: {:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn test_pool_stats_reports_cached_pools() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let manager = PoolManager::new(Config::for_test(&url, data_dir.path())).await.unwrap();
+        manager.drop_database("poolstatstest_db", true).await.ok();
+        manager.create_database("poolstatstest_db", &Default::default()).await.unwrap();
+        assert!(manager.pool_stats().await.is_empty());
+
+        let pool = manager.get_pool_by_name("poolstatstest_db").await.unwrap();
+        let client = pool.get().await.unwrap();
+
+        let stats = manager.pool_stats().await;
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].database, "poolstatstest_db");
+        assert_eq!(stats[0].size, 1);
+        assert_eq!(stats[0].available, 0);
+        assert_eq!(stats[0].max_size, manager.config().max_connections_per_pool as usize);
+
+        drop(client);
+        assert_eq!(manager.pool_stats().await[0].available, 1);
+
+        manager.drop_database("poolstatstest_db", true).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_database_options_for_reports_corrupt_registry() {
+        let Some(url) = test_database_url() else {
+            return;
+        };
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let manager = PoolManager::new(Config::for_test(&url, data_dir.path())).await.unwrap();
+
+        // Unregistered platforms get the server defaults
+        assert_eq!(manager.database_options_for("unregistered").unwrap(), DatabaseOptions::default());
+
+        let registry = PlatformRegistry::new(data_dir.path());
+        registry.register_platform("corrupt").unwrap();
+        std::fs::write(registry.platform_dir("corrupt").join("platform.json"), "{not json").unwrap();
+        let err = manager.database_options_for("corrupt").unwrap_err();
+        assert!(matches!(err, GatewayError::Internal(_)), "{:?}", err);
+    }
 }
@@ -1,4 +1,6 @@
+mod call_limit;
 mod manager;
 mod router;
+mod tls;
 
-pub use manager::PoolManager;
+pub use manager::{PoolManager, PoolStats, ServerVersion};
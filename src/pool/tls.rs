@@ -0,0 +1,82 @@
+//! TLS connector for PostgreSQL connections
+//!
+//! Built once from `DB_SSLMODE` / `DB_SSL_ROOT_CERT` and shared by the admin
+//! pool and every per-database pool.
+
+use crate::config::SslMode;
+use crate::error::{GatewayError, Result};
+use native_tls::{Certificate, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use std::fs;
+use std::path::Path;
+
+/// Build the TLS connector for `mode`, or None to connect without TLS
+pub fn make_tls_connector(mode: SslMode, root_cert: Option<&Path>) -> Result<Option<MakeTlsConnector>> {
+    let mut builder = TlsConnector::builder();
+
+    match mode {
+        SslMode::Disable => return Ok(None),
+        SslMode::Require => {
+            // Like libpq's sslmode=require: encrypted, but the server is not authenticated
+            builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+        SslMode::VerifyFull => {
+            if let Some(path) = root_cert {
+                let pem = fs::read(path).map_err(|e| {
+                    GatewayError::Internal(format!(
+                        "Failed to read SSL root certificate {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                let cert = Certificate::from_pem(&pem).map_err(|e| {
+                    GatewayError::Internal(format!(
+                        "Invalid SSL root certificate {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                builder.add_root_certificate(cert);
+            }
+        }
+    }
+
+    let connector = builder
+        .build()
+        .map_err(|e| GatewayError::Internal(format!("Failed to build TLS connector: {}", e)))?;
+
+    Ok(Some(MakeTlsConnector::new(connector)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sslmode() {
+        assert_eq!("disable".parse::<SslMode>().unwrap(), SslMode::Disable);
+        assert_eq!("REQUIRE".parse::<SslMode>().unwrap(), SslMode::Require);
+        assert_eq!("verify-full".parse::<SslMode>().unwrap(), SslMode::VerifyFull);
+        assert!("prefer".parse::<SslMode>().is_err());
+    }
+
+    #[test]
+    fn test_disable_uses_no_tls() {
+        assert!(make_tls_connector(SslMode::Disable, None).unwrap().is_none());
+        assert!(make_tls_connector(SslMode::Require, None).unwrap().is_some());
+        assert!(make_tls_connector(SslMode::VerifyFull, None).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_invalid_root_cert_is_rejected() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let missing = dir.path().join("missing.pem");
+        assert!(make_tls_connector(SslMode::VerifyFull, Some(&missing)).is_err());
+
+        let garbage = dir.path().join("garbage.pem");
+        fs::write(&garbage, "not a certificate").unwrap();
+        assert!(make_tls_connector(SslMode::VerifyFull, Some(&garbage)).is_err());
+    }
+}
@@ -7,7 +7,10 @@
 //! ```
 //! {data_dir}/{platform}/
 //!   ├── platform.json       # Platform metadata
+//!   ├── .blobs/             # Content-addressed schema files (SCHEMA_HISTORY)
+//!   ├── .versions/          # Schema version manifests (SCHEMA_HISTORY)
 //!   ├── main_db/
+//!   │   ├── .schema_meta.json   # Checksum of the uploaded archive
 //!   │   ├── extensions/
 //!   │   ├── types/
 //!   │   ├── tables/
@@ -23,5 +26,5 @@
 mod platform;
 mod schema;
 
-pub use platform::{PlatformRegistry, PlatformInfo};
+pub use platform::{validate_platform_name, DatabaseOptions, PlatformRegistry, PlatformInfo};
 pub use schema::{SchemaStore, StoredSchema};
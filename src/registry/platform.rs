@@ -4,11 +4,13 @@
 
 use crate::error::{GatewayError, Result};
 use chrono::{DateTime, Utc};
+use ipnetwork::IpNetwork;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
-use tracing::info;
+use tracing::{info, warn};
 
 /// Platform metadata stored in platform.json
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +25,66 @@ pub struct PlatformInfo {
     /// PostgreSQL password for this platform (stored encrypted in production)
     #[serde(default)]
     pub db_password: Option<String>,
+    /// Encoding/locale options used when creating databases for this platform
+    #[serde(default)]
+    pub database_options: DatabaseOptions,
+    /// CIDR blocks allowed to call this platform's endpoints, checked in
+    /// addition to the global ALLOWED_NETWORKS (empty = no extra restriction)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_networks: Vec<String>,
+    /// Attach the gateway's trigger keeping `updated_at` columns current to
+    /// every declared table that has one
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub managed_updated_at: bool,
+}
+
+/// CREATE DATABASE options (encoding, locale, template)
+///
+/// Unset fields fall back to the PostgreSQL server defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lc_collate: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lc_ctype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl DatabaseOptions {
+    /// Validate option values so they can be safely embedded in DDL
+    pub fn validate(&self) -> Result<()> {
+        let values = [
+            ("encoding", &self.encoding),
+            ("lc_collate", &self.lc_collate),
+            ("lc_ctype", &self.lc_ctype),
+        ];
+
+        for (option, value) in values {
+            if let Some(value) = value {
+                if !is_valid_option_value(value) {
+                    return Err(GatewayError::InvalidRequest {
+                        message: format!(
+                            "Invalid {} '{}': only letters, digits, '_', '-', '.' and '@' are allowed",
+                            option, value
+                        ),
+                    });
+                }
+            }
+        }
+
+        if let Some(template) = &self.template {
+            if !is_valid_identifier(template) {
+                return Err(GatewayError::InvalidRequest {
+                    message: format!("Invalid template '{}': must be alphanumeric with underscores", template),
+                });
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Record of a created database
@@ -31,6 +93,10 @@ pub struct DatabaseRecord {
     pub schema_name: String,
     pub database_name: String,
     pub created_at: DateTime<Utc>,
+    /// Checksum of the stored schema version the database was last provisioned
+    /// or migrated to (recorded when schema history is enabled)
+    #[serde(default)]
+    pub schema_version: Option<String>,
 }
 
 impl PlatformInfo {
@@ -42,9 +108,27 @@ impl PlatformInfo {
             databases: HashMap::new(),
             db_user: None,
             db_password: None,
+            database_options: DatabaseOptions::default(),
+            allowed_networks: Vec::new(),
+            managed_updated_at: false,
         }
     }
 
+    /// Check a client address against the platform's allowlist
+    ///
+    /// Always true without an allowlist. Loopback is always allowed and
+    /// IPv4-mapped IPv6 addresses match IPv4 blocks, as in the global filter.
+    pub fn allows_ip(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.allowed_networks.is_empty()
+            || ip.is_loopback()
+            || self
+                .allowed_networks
+                .iter()
+                .filter_map(|network| network.parse::<IpNetwork>().ok())
+                .any(|network| network.contains(ip))
+    }
+
     pub fn with_credentials(name: &str, db_user: String, db_password: String) -> Self {
         Self {
             name: name.to_string(),
@@ -53,6 +137,9 @@ impl PlatformInfo {
             databases: HashMap::new(),
             db_user: Some(db_user),
             db_password: Some(db_password),
+            database_options: DatabaseOptions::default(),
+            allowed_networks: Vec::new(),
+            managed_updated_at: false,
         }
     }
 }
@@ -86,12 +173,7 @@ impl PlatformRegistry {
 
     /// Register a new platform
     pub fn register_platform(&self, platform: &str) -> Result<PlatformInfo> {
-        // Validate platform name
-        if !is_valid_identifier(platform) {
-            return Err(GatewayError::InvalidRequest {
-                message: format!("Invalid platform name: {}. Must be alphanumeric with underscores.", platform),
-            });
-        }
+        validate_platform_name(platform)?;
 
         // Check if already registered
         if self.is_registered(platform) {
@@ -137,6 +219,27 @@ impl PlatformRegistry {
         Ok(info)
     }
 
+    /// Reject a client outside the platform's allowlist
+    ///
+    /// Unregistered platforms pass, so handlers report them as usual. With an
+    /// allowlist, a request whose address is unknown is rejected.
+    pub fn check_client_ip(&self, platform: &str, ip: Option<IpAddr>) -> Result<()> {
+        if !self.is_registered(platform) {
+            return Ok(());
+        }
+
+        let info = self.get_platform_info(platform)?;
+        match ip {
+            Some(ip) if info.allows_ip(ip) => Ok(()),
+            None if info.allowed_networks.is_empty() => Ok(()),
+            ip => {
+                let ip = ip.map(|ip| ip.to_string()).unwrap_or_else(|| "unknown".to_string());
+                warn!("Request for platform {} from IP {} outside its allowed networks", platform, ip);
+                Err(GatewayError::Unauthorized { ip })
+            }
+        }
+    }
+
     /// Save platform info
     pub fn save_platform_info(&self, info: &PlatformInfo) -> Result<()> {
         let path = self.platform_json_path(&info.name);
@@ -165,19 +268,64 @@ impl PlatformRegistry {
     }
 
     /// Record a database creation
-    pub fn record_database(&self, platform: &str, schema_name: &str, database_name: &str) -> Result<()> {
+    pub fn record_database(
+        &self,
+        platform: &str,
+        schema_name: &str,
+        database_name: &str,
+        schema_version: Option<&str>,
+    ) -> Result<()> {
         let mut info = self.get_platform_info(platform)?;
 
         info.databases.insert(database_name.to_string(), DatabaseRecord {
             schema_name: schema_name.to_string(),
             database_name: database_name.to_string(),
             created_at: Utc::now(),
+            schema_version: schema_version.map(str::to_string),
         });
 
         self.save_platform_info(&info)?;
         Ok(())
     }
 
+    /// Update the schema version a recorded database is on
+    pub fn set_schema_version(&self, platform: &str, database_name: &str, schema_version: &str) -> Result<()> {
+        let mut info = self.get_platform_info(platform)?;
+
+        if let Some(record) = info.databases.get_mut(database_name) {
+            record.schema_version = Some(schema_version.to_string());
+            self.save_platform_info(&info)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove a dropped database's record
+    ///
+    /// Returns false if the database was not recorded.
+    pub fn remove_database(&self, platform: &str, database_name: &str) -> Result<bool> {
+        let mut info = self.get_platform_info(platform)?;
+
+        if info.databases.remove(database_name).is_none() {
+            return Ok(false);
+        }
+
+        self.save_platform_info(&info)?;
+        Ok(true)
+    }
+
+    /// Find the platform and record of a database by name
+    pub fn find_database(&self, database_name: &str) -> Result<Option<(String, DatabaseRecord)>> {
+        for platform in self.list_platforms()? {
+            let info = self.get_platform_info(&platform)?;
+            if let Some(record) = info.databases.get(database_name) {
+                return Ok(Some((platform, record.clone())));
+            }
+        }
+
+        Ok(None)
+    }
+
     /// List all registered platforms
     pub fn list_platforms(&self) -> Result<Vec<String>> {
         if !self.data_dir.exists() {
@@ -224,11 +372,31 @@ impl PlatformRegistry {
     }
 }
 
+/// Reject a platform name that could not have been registered
+///
+/// Names are used as directory names under the data directory, so this runs
+/// before a request's platform is turned into a path.
+pub fn validate_platform_name(platform: &str) -> Result<()> {
+    if !is_valid_identifier(platform) {
+        return Err(GatewayError::InvalidRequest {
+            message: format!("Invalid platform name: {}. Must be alphanumeric with underscores.", platform),
+        });
+    }
+    Ok(())
+}
+
 /// Check if a string is a valid identifier (alphanumeric + underscore)
 fn is_valid_identifier(s: &str) -> bool {
     !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_')
 }
 
+/// Check if a string is a valid encoding/locale name (e.g. UTF8, en_US.UTF-8)
+fn is_valid_option_value(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 64
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '@'))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -249,6 +417,32 @@ mod tests {
         assert!(registry.register_platform("testapp").is_err());
     }
 
+    #[test]
+    fn test_platform_allowed_networks() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = PlatformRegistry::new(temp_dir.path());
+
+        let mut info = registry.register_platform("customer_a").unwrap();
+        let outside: IpAddr = "198.51.100.7".parse().unwrap();
+        assert!(registry.check_client_ip("customer_a", Some(outside)).is_ok());
+        assert!(registry.check_client_ip("customer_a", None).is_ok());
+
+        info.allowed_networks = vec!["203.0.113.0/24".to_string(), "2001:db8:a::/48".to_string()];
+        registry.save_platform_info(&info).unwrap();
+
+        for allowed in ["203.0.113.9", "::ffff:203.0.113.9", "2001:db8:a:1::5", "127.0.0.1"] {
+            assert!(registry.check_client_ip("customer_a", Some(allowed.parse().unwrap())).is_ok(), "{}", allowed);
+        }
+        match registry.check_client_ip("customer_a", Some(outside)) {
+            Err(GatewayError::Unauthorized { ip }) => assert_eq!(ip, "198.51.100.7"),
+            other => panic!("expected Unauthorized, got {:?}", other),
+        }
+        assert!(registry.check_client_ip("customer_a", None).is_err());
+
+        // Unregistered platforms are left to the handler
+        assert!(registry.check_client_ip("unknown", Some(outside)).is_ok());
+    }
+
     #[test]
     fn test_invalid_platform_name() {
         let temp_dir = TempDir::new().unwrap();
@@ -270,4 +464,69 @@ mod tests {
         let platforms = registry.list_platforms().unwrap();
         assert_eq!(platforms, vec!["app_a", "app_b"]);
     }
+
+    #[test]
+    fn test_find_database_and_schema_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = PlatformRegistry::new(temp_dir.path());
+        registry.register_platform("app_a").unwrap();
+        registry.register_platform("app_b").unwrap();
+
+        registry
+            .record_database("app_b", "tenant_db", "app_b_tenant_db_t1", Some("abc123"))
+            .unwrap();
+        registry
+            .set_schema_version("app_b", "app_b_tenant_db_t1", "def456")
+            .unwrap();
+
+        let (platform, record) = registry.find_database("app_b_tenant_db_t1").unwrap().unwrap();
+        assert_eq!(platform, "app_b");
+        assert_eq!(record.schema_version.as_deref(), Some("def456"));
+        assert!(registry.find_database("app_a_main").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_remove_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = PlatformRegistry::new(temp_dir.path());
+        registry.register_platform("app_a").unwrap();
+        registry
+            .record_database("app_a", "tenant_db", "app_a_t1", None)
+            .unwrap();
+
+        assert!(registry.remove_database("app_a", "app_a_t1").unwrap());
+        assert!(registry.find_database("app_a_t1").unwrap().is_none());
+        assert!(!registry.remove_database("app_a", "app_a_t1").unwrap());
+    }
+
+    #[test]
+    fn test_database_options_validation() {
+        let options = DatabaseOptions {
+            encoding: Some("UTF8".to_string()),
+            lc_collate: Some("en_US.UTF-8".to_string()),
+            lc_ctype: Some("en_US.UTF-8".to_string()),
+            template: Some("template0".to_string()),
+        };
+        assert!(options.validate().is_ok());
+
+        let bad = DatabaseOptions {
+            lc_collate: Some("C'; DROP DATABASE x; --".to_string()),
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+
+        let bad_template = DatabaseOptions {
+            template: Some("template 0".to_string()),
+            ..Default::default()
+        };
+        assert!(bad_template.validate().is_err());
+    }
+
+    #[test]
+    fn test_platform_info_without_database_options() {
+        // platform.json written before database_options existed must still load
+        let json = r#"{"name":"legacy","registered_at":"2024-01-01T00:00:00Z","schemas":[],"databases":{}}"#;
+        let info: PlatformInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(info.database_options, DatabaseOptions::default());
+    }
 }
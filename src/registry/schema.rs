@@ -2,14 +2,50 @@
 //!
 //! Manages schema storage and retrieval for platforms.
 //! Each schema is stored as a directory with subdirectories for each component.
+//!
+//! With history enabled, every registered version is also recorded
+//! content-addressed: each unique file is stored once, gzip-compressed, in
+//! `{platform}/.blobs/{sha256}.gz`, and each version is a manifest in
+//! `{platform}/.versions/{schema}/{archive checksum}.json` mapping file paths
+//! to blob hashes.
 
 use crate::error::{GatewayError, Result};
+use crate::schema::archive::{inspect_archive, read_archive, strip_schema_prefix, ArchiveReport};
 use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use tar::Archive;
-use tracing::info;
+use tracing::{debug, info};
+
+/// Directory (per platform) holding content-addressed schema files
+const BLOBS_DIR: &str = ".blobs";
+
+/// Directory (per platform) holding version manifests
+const VERSIONS_DIR: &str = ".versions";
+
+/// File (inside each schema directory) recording the archive it was extracted from
+const SCHEMA_META_FILE: &str = ".schema_meta.json";
+
+/// Contents of `.schema_meta.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SchemaMeta {
+    checksum: String,
+    stored_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A recorded schema version: relative file path -> blob hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaVersionManifest {
+    pub schema_name: String,
+    pub checksum: String,
+    pub stored_at: chrono::DateTime<chrono::Utc>,
+    pub files: BTreeMap<String, String>,
+}
 
 /// Information about a stored schema
 #[derive(Debug, Clone)]
@@ -28,12 +64,15 @@ pub struct StoredSchema {
 /// Schema store for managing schema files
 pub struct SchemaStore {
     data_dir: PathBuf,
+    /// Record every registered version in the content-addressed history
+    keep_history: bool,
 }
 
 impl SchemaStore {
-    pub fn new(data_dir: &Path) -> Self {
+    pub fn new(data_dir: &Path, keep_history: bool) -> Self {
         Self {
             data_dir: data_dir.to_path_buf(),
+            keep_history,
         }
     }
 
@@ -47,7 +86,7 @@ impl SchemaStore {
         self.schema_dir(platform, schema_name).exists()
     }
 
-    /// Store a schema from a tar.gz archive
+    /// Store a schema from a tar.gz or zip archive
     ///
     /// The archive should contain:
     /// - extensions/ (optional)
@@ -56,6 +95,7 @@ impl SchemaStore {
     /// - functions/
     /// - seeders/ (optional)
     /// - migrations/ (optional)
+    /// - hooks/ (optional)
     pub fn store_schema(
         &self,
         platform: &str,
@@ -69,6 +109,9 @@ impl SchemaStore {
             });
         }
 
+        // Read the archive (tar.gz or zip) before touching the stored schema
+        let entries = read_archive(archive_data)?;
+
         let schema_dir = self.schema_dir(platform, schema_name);
 
         // Remove existing schema if present
@@ -87,25 +130,9 @@ impl SchemaStore {
         let checksum = compute_checksum(archive_data);
 
         // Extract archive
-        let decoder = GzDecoder::new(archive_data);
-        let mut archive = Archive::new(decoder);
-
-        for entry in archive.entries().map_err(|e| GatewayError::SchemaExtractionFailed {
-            cause: format!("Failed to read archive entries: {}", e),
-        })? {
-            let mut entry = entry.map_err(|e| GatewayError::SchemaExtractionFailed {
-                cause: format!("Failed to read entry: {}", e),
-            })?;
-
-            let path = entry.path().map_err(|e| GatewayError::SchemaExtractionFailed {
-                cause: format!("Failed to get entry path: {}", e),
-            })?.to_path_buf();
-
+        for entry in entries {
             // Skip the root "postgresql/" prefix if present
-            let relative_path = path.strip_prefix("postgresql/")
-                .or_else(|_| path.strip_prefix("postgresql"))
-                .unwrap_or(&path)
-                .to_path_buf();
+            let relative_path = strip_schema_prefix(&entry.path);
 
             if relative_path.as_os_str().is_empty() {
                 continue;
@@ -113,21 +140,41 @@ impl SchemaStore {
 
             let target_path = schema_dir.join(&relative_path);
 
+            if entry.is_dir {
+                fs::create_dir_all(&target_path).ok();
+                continue;
+            }
+
             // Create parent directories
             if let Some(parent) = target_path.parent() {
                 fs::create_dir_all(parent).ok();
             }
 
-            // Extract file
-            if entry.header().entry_type().is_file() {
-                entry.unpack(&target_path).map_err(|e| GatewayError::SchemaExtractionFailed {
-                    cause: format!("Failed to extract {}: {}", relative_path.display(), e),
-                })?;
-            } else if entry.header().entry_type().is_dir() {
-                fs::create_dir_all(&target_path).ok();
-            }
+            fs::write(&target_path, &entry.data).map_err(|e| GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to extract {}: {}", relative_path.display(), e),
+            })?;
+        }
+
+        if self.keep_history {
+            let blobs_added = self.record_version(platform, schema_name, &checksum, &schema_dir)?;
+            info!(
+                "Recorded schema '{}' version {} in history ({} new files)",
+                schema_name, checksum, blobs_added
+            );
         }
 
+        // Written after the history snapshot so it is not recorded as a schema file
+        let meta = SchemaMeta {
+            checksum: checksum.clone(),
+            stored_at: chrono::Utc::now(),
+        };
+        let json = serde_json::to_string_pretty(&meta).map_err(|e| GatewayError::Internal(
+            format!("Failed to serialize schema metadata: {}", e)
+        ))?;
+        fs::write(schema_dir.join(SCHEMA_META_FILE), json).map_err(|e| GatewayError::Internal(
+            format!("Failed to write schema metadata: {}", e)
+        ))?;
+
         // Build schema info
         let schema = StoredSchema {
             name: schema_name.to_string(),
@@ -159,8 +206,10 @@ impl SchemaStore {
             });
         }
 
-        // Read checksum from stored file if exists, otherwise compute placeholder
-        let checksum = "stored".to_string();
+        // Schemas stored before .schema_meta.json existed have no known checksum
+        let checksum = self
+            .stored_checksum(platform, schema_name)
+            .unwrap_or_else(|| "unknown".to_string());
 
         Ok(StoredSchema {
             name: schema_name.to_string(),
@@ -175,6 +224,175 @@ impl SchemaStore {
         })
     }
 
+    /// Checksum of the archive a stored schema was extracted from
+    pub fn stored_checksum(&self, platform: &str, schema_name: &str) -> Option<String> {
+        let path = self.schema_dir(platform, schema_name).join(SCHEMA_META_FILE);
+        let json = fs::read_to_string(path).ok()?;
+        serde_json::from_str::<SchemaMeta>(&json).ok().map(|meta| meta.checksum)
+    }
+
+    /// Whether an archive with `new_checksum` differs from the stored schema
+    ///
+    /// True when the schema is not stored yet or its checksum is unknown.
+    pub fn schema_changed(&self, platform: &str, schema_name: &str, new_checksum: &str) -> bool {
+        self.stored_checksum(platform, schema_name).as_deref() != Some(new_checksum)
+    }
+
+    /// SHA256 checksum of a schema archive, as recorded by `store_schema`
+    pub fn archive_checksum(archive_data: &[u8]) -> String {
+        compute_checksum(archive_data)
+    }
+
+    /// Structural report of a schema archive, without extracting or storing it
+    pub fn inspect_archive(archive_data: &[u8]) -> Result<ArchiveReport> {
+        inspect_archive(archive_data)
+    }
+
+    /// Record an extracted schema in the content-addressed history
+    ///
+    /// Returns how many files were new to the blob store.
+    fn record_version(
+        &self,
+        platform: &str,
+        schema_name: &str,
+        checksum: &str,
+        schema_dir: &Path,
+    ) -> Result<usize> {
+        let blobs_dir = self.data_dir.join(platform).join(BLOBS_DIR);
+        let versions_dir = self.data_dir.join(platform).join(VERSIONS_DIR).join(schema_name);
+        fs::create_dir_all(&blobs_dir).map_err(|e| GatewayError::Internal(
+            format!("Failed to create blob directory: {}", e)
+        ))?;
+        fs::create_dir_all(&versions_dir).map_err(|e| GatewayError::Internal(
+            format!("Failed to create versions directory: {}", e)
+        ))?;
+
+        let mut files = Vec::new();
+        collect_files(schema_dir, schema_dir, &mut files)?;
+
+        let mut manifest_files = BTreeMap::new();
+        let mut blobs_added = 0;
+
+        for (relative, path) in files {
+            let content = fs::read(&path).map_err(|e| GatewayError::Internal(
+                format!("Failed to read {}: {}", path.display(), e)
+            ))?;
+            let hash = compute_checksum(&content);
+            let blob_path = blobs_dir.join(format!("{}.gz", hash));
+
+            if !blob_path.exists() {
+                write_blob(&blob_path, &content)?;
+                blobs_added += 1;
+            }
+
+            manifest_files.insert(relative, hash);
+        }
+
+        let manifest = SchemaVersionManifest {
+            schema_name: schema_name.to_string(),
+            checksum: checksum.to_string(),
+            stored_at: chrono::Utc::now(),
+            files: manifest_files,
+        };
+        let json = serde_json::to_string_pretty(&manifest).map_err(|e| GatewayError::Internal(
+            format!("Failed to serialize version manifest: {}", e)
+        ))?;
+        fs::write(versions_dir.join(format!("{}.json", checksum)), json).map_err(|e| GatewayError::Internal(
+            format!("Failed to write version manifest: {}", e)
+        ))?;
+
+        debug!(
+            "Recorded version {} of '{}' for platform '{}' ({} new blobs)",
+            checksum, schema_name, platform, blobs_added
+        );
+
+        Ok(blobs_added)
+    }
+
+    /// Checksum of the most recently registered version of a schema
+    ///
+    /// None when history is disabled or nothing has been recorded yet.
+    pub fn current_version(&self, platform: &str, schema_name: &str) -> Result<Option<String>> {
+        let versions_dir = self.data_dir.join(platform).join(VERSIONS_DIR).join(schema_name);
+        if !self.keep_history || !versions_dir.exists() {
+            return Ok(None);
+        }
+
+        let mut latest: Option<SchemaVersionManifest> = None;
+        for entry in fs::read_dir(&versions_dir).map_err(|e| GatewayError::Internal(
+            format!("Failed to read versions directory: {}", e)
+        ))? {
+            let path = entry
+                .map_err(|e| GatewayError::Internal(format!("Failed to read directory entry: {}", e)))?
+                .path();
+            if path.extension().map(|e| e == "json").unwrap_or(false) {
+                let manifest = read_manifest(&path)?;
+                if latest.as_ref().map(|l| manifest.stored_at > l.stored_at).unwrap_or(true) {
+                    latest = Some(manifest);
+                }
+            }
+        }
+
+        Ok(latest.map(|m| m.checksum))
+    }
+
+    /// Write the files of a recorded schema version into `dest`
+    pub fn restore_version(
+        &self,
+        platform: &str,
+        schema_name: &str,
+        checksum: &str,
+        dest: &Path,
+    ) -> Result<()> {
+        let manifest_path = self
+            .data_dir
+            .join(platform)
+            .join(VERSIONS_DIR)
+            .join(schema_name)
+            .join(format!("{}.json", checksum));
+        if !checksum.chars().all(|c| c.is_ascii_hexdigit()) || !manifest_path.exists() {
+            return Err(GatewayError::InvalidRequest {
+                message: format!(
+                    "Version {} of schema '{}' is not in the history for platform '{}'",
+                    checksum, schema_name, platform
+                ),
+            });
+        }
+
+        let manifest = read_manifest(&manifest_path)?;
+        let blobs_dir = self.data_dir.join(platform).join(BLOBS_DIR);
+
+        for (relative, hash) in &manifest.files {
+            let compressed = fs::read(blobs_dir.join(format!("{}.gz", hash))).map_err(|e| {
+                GatewayError::Internal(format!("Missing blob {} for {}: {}", hash, relative, e))
+            })?;
+            let mut content = Vec::new();
+            GzDecoder::new(compressed.as_slice())
+                .read_to_end(&mut content)
+                .map_err(|e| GatewayError::Internal(format!("Failed to decompress blob {}: {}", hash, e)))?;
+
+            let target = dest.join(relative);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| GatewayError::Internal(
+                    format!("Failed to create {}: {}", parent.display(), e)
+                ))?;
+            }
+            fs::write(&target, content).map_err(|e| GatewayError::Internal(
+                format!("Failed to write {}: {}", target.display(), e)
+            ))?;
+        }
+
+        debug!(
+            "Restored version {} of '{}' ({} files) to {}",
+            checksum,
+            schema_name,
+            manifest.files.len(),
+            dest.display()
+        );
+
+        Ok(())
+    }
+
     /// List schemas for a platform
     pub fn list_schemas(&self, platform: &str) -> Result<Vec<String>> {
         let platform_dir = self.data_dir.join(platform);
@@ -231,6 +449,22 @@ impl SchemaStore {
     pub fn migrations_dir(&self, platform: &str, schema_name: &str) -> PathBuf {
         self.schema_dir(platform, schema_name).join("migrations")
     }
+
+    pub fn hooks_dir(&self, platform: &str, schema_name: &str) -> PathBuf {
+        self.schema_dir(platform, schema_name).join("hooks")
+    }
+
+    pub fn policies_dir(&self, platform: &str, schema_name: &str) -> PathBuf {
+        self.schema_dir(platform, schema_name).join("policies")
+    }
+
+    pub fn matviews_dir(&self, platform: &str, schema_name: &str) -> PathBuf {
+        self.schema_dir(platform, schema_name).join("matviews")
+    }
+
+    pub fn grants_dir(&self, platform: &str, schema_name: &str) -> PathBuf {
+        self.schema_dir(platform, schema_name).join("grants")
+    }
 }
 
 /// Check if a directory has schema structure (at least tables or functions)
@@ -238,6 +472,59 @@ fn has_schema_structure(path: &Path) -> bool {
     path.join("tables").exists() || path.join("functions").exists()
 }
 
+/// Collect all files below `dir` as (path relative to `base` with '/' separators, full path)
+fn collect_files(base: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    let entries = fs::read_dir(dir).map_err(|e| GatewayError::Internal(
+        format!("Failed to read {}: {}", dir.display(), e)
+    ))?;
+
+    for entry in entries {
+        let path = entry
+            .map_err(|e| GatewayError::Internal(format!("Failed to read directory entry: {}", e)))?
+            .path();
+        if path.is_dir() {
+            collect_files(base, &path, files)?;
+        } else if path.is_file() {
+            let relative = path
+                .strip_prefix(base)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((relative, path));
+        }
+    }
+
+    files.sort();
+    Ok(())
+}
+
+/// Write a gzip-compressed blob, via a temporary file so readers never see a partial blob
+fn write_blob(blob_path: &Path, content: &[u8]) -> Result<()> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(content)
+        .map_err(|e| GatewayError::Internal(format!("Failed to compress blob: {}", e)))?;
+    let compressed = encoder
+        .finish()
+        .map_err(|e| GatewayError::Internal(format!("Failed to compress blob: {}", e)))?;
+
+    let tmp_path = blob_path.with_extension("gz.tmp");
+    fs::write(&tmp_path, compressed)
+        .and_then(|_| fs::rename(&tmp_path, blob_path))
+        .map_err(|e| GatewayError::Internal(format!("Failed to write blob: {}", e)))
+}
+
+fn read_manifest(path: &Path) -> Result<SchemaVersionManifest> {
+    let json = fs::read_to_string(path).map_err(|e| GatewayError::Internal(
+        format!("Failed to read version manifest: {}", e)
+    ))?;
+    serde_json::from_str(&json).map_err(|e| GatewayError::Internal(
+        format!("Invalid version manifest {}: {}", path.display(), e)
+    ))
+}
+
 /// Compute SHA256 checksum of data
 fn compute_checksum(data: &[u8]) -> String {
     let mut hasher = Sha256::new();
@@ -253,12 +540,29 @@ fn is_valid_identifier(s: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use flate2::write::GzEncoder;
-    use flate2::Compression;
-    use std::io::Write;
     use tar::Builder;
     use tempfile::TempDir;
 
+    fn archive_with(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut archive_data = Vec::new();
+        {
+            let encoder = GzEncoder::new(&mut archive_data, Compression::default());
+            let mut builder = Builder::new(encoder);
+
+            for (path, content) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_path(path).unwrap();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append(&header, content.as_bytes()).unwrap();
+            }
+
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        archive_data
+    }
+
     fn create_test_archive() -> Vec<u8> {
         let mut archive_data = Vec::new();
         {
@@ -291,7 +595,7 @@ mod tests {
     #[test]
     fn test_store_schema() {
         let temp_dir = TempDir::new().unwrap();
-        let store = SchemaStore::new(temp_dir.path());
+        let store = SchemaStore::new(temp_dir.path(), false);
 
         // Create platform directory first
         fs::create_dir_all(temp_dir.path().join("testapp")).unwrap();
@@ -308,7 +612,7 @@ mod tests {
     #[test]
     fn test_list_schemas() {
         let temp_dir = TempDir::new().unwrap();
-        let store = SchemaStore::new(temp_dir.path());
+        let store = SchemaStore::new(temp_dir.path(), false);
 
         // Create platform directory
         fs::create_dir_all(temp_dir.path().join("testapp")).unwrap();
@@ -320,4 +624,136 @@ mod tests {
         let schemas = store.list_schemas("testapp").unwrap();
         assert_eq!(schemas, vec!["main_db", "tenant_db"]);
     }
+
+    #[test]
+    fn test_history_stores_only_changed_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SchemaStore::new(temp_dir.path(), true);
+        fs::create_dir_all(temp_dir.path().join("testapp")).unwrap();
+
+        let v1 = archive_with(&[
+            ("tables/users.pssql", "CREATE TABLE users (id SERIAL PRIMARY KEY);"),
+            ("tables/orders.pssql", "CREATE TABLE orders (id SERIAL PRIMARY KEY);"),
+            ("functions/get_user.pssql", "CREATE FUNCTION get_user() RETURNS void AS $$ BEGIN END; $$ LANGUAGE plpgsql;"),
+        ]);
+        let v2 = archive_with(&[
+            ("tables/users.pssql", "CREATE TABLE users (id SERIAL PRIMARY KEY, email TEXT);"),
+            ("tables/orders.pssql", "CREATE TABLE orders (id SERIAL PRIMARY KEY);"),
+            ("functions/get_user.pssql", "CREATE FUNCTION get_user() RETURNS void AS $$ BEGIN END; $$ LANGUAGE plpgsql;"),
+        ]);
+
+        let blobs_dir = temp_dir.path().join("testapp").join(BLOBS_DIR);
+        let blob_count = || fs::read_dir(&blobs_dir).unwrap().count();
+
+        store.store_schema("testapp", "tenant_db", &v1).unwrap();
+        assert_eq!(blob_count(), 3);
+
+        // Only the changed file is stored again
+        let second = store.store_schema("testapp", "tenant_db", &v2).unwrap();
+        assert_eq!(blob_count(), 4);
+
+        // Re-registering an identical version adds nothing
+        store.store_schema("testapp", "tenant_db", &v2).unwrap();
+        assert_eq!(blob_count(), 4);
+
+        let versions_dir = temp_dir.path().join("testapp").join(VERSIONS_DIR).join("tenant_db");
+        assert_eq!(fs::read_dir(&versions_dir).unwrap().count(), 2);
+        let manifest: SchemaVersionManifest = serde_json::from_str(
+            &fs::read_to_string(versions_dir.join(format!("{}.json", second.checksum))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(manifest.files.len(), 3);
+        assert!(manifest.files.contains_key("tables/users.pssql"));
+
+        // History directories are not listed as schemas
+        assert_eq!(store.list_schemas("testapp").unwrap(), vec!["tenant_db"]);
+    }
+
+    #[test]
+    fn test_restore_older_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SchemaStore::new(temp_dir.path(), true);
+        fs::create_dir_all(temp_dir.path().join("testapp")).unwrap();
+
+        let v1 = store
+            .store_schema("testapp", "tenant_db", &archive_with(&[("tables/users.pssql", "v1")]))
+            .unwrap();
+        let v2 = store
+            .store_schema(
+                "testapp",
+                "tenant_db",
+                &archive_with(&[("tables/users.pssql", "v2"), ("tables/orders.pssql", "v2")]),
+            )
+            .unwrap();
+        assert_eq!(
+            store.current_version("testapp", "tenant_db").unwrap(),
+            Some(v2.checksum)
+        );
+
+        let dest = TempDir::new().unwrap();
+        store
+            .restore_version("testapp", "tenant_db", &v1.checksum, dest.path())
+            .unwrap();
+        assert_eq!(fs::read_to_string(dest.path().join("tables/users.pssql")).unwrap(), "v1");
+        assert!(!dest.path().join("tables/orders.pssql").exists());
+
+        assert!(store
+            .restore_version("testapp", "tenant_db", "../../etc", dest.path())
+            .is_err());
+    }
+
+    #[test]
+    fn test_no_history_by_default() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SchemaStore::new(temp_dir.path(), false);
+        fs::create_dir_all(temp_dir.path().join("testapp")).unwrap();
+
+        store.store_schema("testapp", "tenant_db", &create_test_archive()).unwrap();
+
+        assert!(!temp_dir.path().join("testapp").join(BLOBS_DIR).exists());
+    }
+
+    #[test]
+    fn test_checksum_is_persisted() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SchemaStore::new(temp_dir.path(), false);
+        fs::create_dir_all(temp_dir.path().join("testapp")).unwrap();
+
+        let archive = create_test_archive();
+        let checksum = SchemaStore::archive_checksum(&archive);
+        assert!(store.schema_changed("testapp", "tenant_db", &checksum));
+
+        let stored = store.store_schema("testapp", "tenant_db", &archive).unwrap();
+        assert_eq!(stored.checksum, checksum);
+        assert_eq!(store.get_schema("testapp", "tenant_db").unwrap().checksum, checksum);
+
+        assert!(!store.schema_changed("testapp", "tenant_db", &checksum));
+        let other = archive_with(&[("tables/users.pssql", "CREATE TABLE users (id INT);")]);
+        assert!(store.schema_changed("testapp", "tenant_db", &SchemaStore::archive_checksum(&other)));
+    }
+
+    #[test]
+    fn test_store_zip_schema() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SchemaStore::new(temp_dir.path(), false);
+        fs::create_dir_all(temp_dir.path().join("testapp")).unwrap();
+
+        let zip = crate::schema::archive::tests::zip_with(&[
+            ("postgresql/tables/users.pssql", "CREATE TABLE users (id SERIAL PRIMARY KEY);"),
+            ("postgresql/functions/", ""),
+        ]);
+        let schema = store.store_schema("testapp", "tenant_db", &zip).unwrap();
+
+        assert!(schema.has_tables);
+        assert!(schema.has_functions);
+        assert!(!schema.has_migrations);
+        assert_eq!(
+            fs::read_to_string(store.tables_dir("testapp", "tenant_db").join("users.pssql")).unwrap(),
+            "CREATE TABLE users (id SERIAL PRIMARY KEY);"
+        );
+
+        // An unreadable upload leaves the stored schema in place
+        assert!(store.store_schema("testapp", "tenant_db", b"PK\x05\x06garbage").is_err());
+        assert!(store.tables_dir("testapp", "tenant_db").join("users.pssql").exists());
+    }
 }
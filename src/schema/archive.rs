@@ -0,0 +1,444 @@
+//! Schema archive reading
+//!
+//! Schemas are uploaded as tar.gz or zip. The format is detected from the
+//! leading magic bytes; both are read into the same list of entries so the
+//! callers apply identical path handling regardless of container.
+
+use crate::error::{GatewayError, Result};
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+use tar::Archive;
+use zip::ZipArchive;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZIP_MAGIC: [u8; 4] = [0x50, 0x4b, 0x03, 0x04];
+
+/// Most entries (files and directories) a schema archive may hold
+pub const MAX_ARCHIVE_ENTRIES: usize = 10_000;
+/// Largest total extracted size of a schema archive
+pub const MAX_UNCOMPRESSED_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Folders a schema archive is expected to contain
+const REQUIRED_FOLDERS: [&str; 2] = ["tables", "functions"];
+
+/// Container format of an uploaded schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+/// A file or directory read from a schema archive
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    /// Path inside the archive (relative, no `..` components)
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub data: Vec<u8>,
+}
+
+/// Structural report of an archive, produced without extracting it
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveReport {
+    /// "tar.gz" or "zip"
+    pub format: &'static str,
+    /// `postgresql/` when the entries are wrapped in it
+    pub root_prefix: Option<String>,
+    pub compressed_bytes: u64,
+    pub uncompressed_bytes: u64,
+    pub entry_count: usize,
+    /// Files per top-level folder (after the root prefix); "." for loose files
+    pub folders: BTreeMap<String, usize>,
+    pub missing_folders: Vec<String>,
+    /// Traversal and limit violations; registration would reject the archive
+    pub violations: Vec<String>,
+}
+
+impl ArchiveReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty() && self.missing_folders.is_empty()
+    }
+}
+
+/// Read every entry of a tar.gz or zip schema archive
+pub fn read_archive(data: &[u8]) -> Result<Vec<ArchiveEntry>> {
+    let (entries, limit_violation) = read_entries(data, ReadLimits::default())?;
+
+    if let Some(violation) = archive_violations(&entries).into_iter().chain(limit_violation).next() {
+        return Err(extraction_error(violation));
+    }
+
+    Ok(entries)
+}
+
+/// Describe an archive's structure, reporting violations instead of failing on them
+///
+/// Only an unreadable archive is an error.
+pub fn inspect_archive(data: &[u8]) -> Result<ArchiveReport> {
+    let format = detect_format(data)?;
+    let (entries, limit_violation) = read_entries(data, ReadLimits::default())?;
+
+    let prefixed = entries.iter().any(|e| e.path.starts_with("postgresql"));
+    let mut folders: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in &entries {
+        let relative = strip_schema_prefix(&entry.path);
+        let mut components = relative.components();
+        let Some(first) = components.next() else {
+            continue;
+        };
+        if entry.is_dir {
+            // Empty folders still show up, with no files
+            if components.next().is_none() {
+                folders.entry(first.as_os_str().to_string_lossy().into_owned()).or_default();
+            }
+            continue;
+        }
+        let folder = if components.next().is_some() {
+            first.as_os_str().to_string_lossy().into_owned()
+        } else {
+            ".".to_string()
+        };
+        *folders.entry(folder).or_default() += 1;
+    }
+
+    let missing_folders = REQUIRED_FOLDERS
+        .iter()
+        .filter(|f| !folders.contains_key(**f))
+        .map(|f| f.to_string())
+        .collect();
+
+    Ok(ArchiveReport {
+        format: match format {
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::Zip => "zip",
+        },
+        root_prefix: prefixed.then(|| "postgresql/".to_string()),
+        compressed_bytes: data.len() as u64,
+        uncompressed_bytes: entries.iter().map(|e| e.data.len() as u64).sum(),
+        entry_count: entries.len(),
+        folders,
+        missing_folders,
+        violations: archive_violations(&entries).into_iter().chain(limit_violation).collect(),
+    })
+}
+
+/// Running entry count and extracted size, checked while an archive is read
+///
+/// Limits are enforced before content is buffered, so a small compressed
+/// archive cannot expand into memory past them.
+struct ReadLimits {
+    max_entries: usize,
+    max_bytes: u64,
+    entries: usize,
+    bytes: u64,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self::new(MAX_ARCHIVE_ENTRIES, MAX_UNCOMPRESSED_BYTES)
+    }
+}
+
+impl ReadLimits {
+    fn new(max_entries: usize, max_bytes: u64) -> Self {
+        Self {
+            max_entries,
+            max_bytes,
+            entries: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Count one more entry
+    fn count_entry(&mut self) -> std::result::Result<(), ReadStop> {
+        self.entries += 1;
+        if self.entries > self.max_entries {
+            return Err(ReadStop::Limit(format!(
+                "Archive has more than {} entries",
+                self.max_entries
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read an entry's content, never reading more than one byte past the size limit
+    fn read_content(&mut self, reader: impl Read, path: &str) -> std::result::Result<Vec<u8>, ReadStop> {
+        let remaining = self.max_bytes - self.bytes;
+        let mut content = Vec::new();
+        reader
+            .take(remaining + 1)
+            .read_to_end(&mut content)
+            .map_err(|e| extraction_error(format!("Failed to extract {}: {}", path, e)))?;
+
+        self.bytes += content.len() as u64;
+        if self.bytes > self.max_bytes {
+            return Err(ReadStop::Limit(format!(
+                "Archive extracts to more than {} bytes",
+                self.max_bytes
+            )));
+        }
+        Ok(content)
+    }
+}
+
+/// Why reading an archive stopped early
+enum ReadStop {
+    Failed(GatewayError),
+    /// A limit was exceeded; the entries read so far are kept
+    Limit(String),
+}
+
+impl From<GatewayError> for ReadStop {
+    fn from(e: GatewayError) -> Self {
+        ReadStop::Failed(e)
+    }
+}
+
+/// Read entries until the end or the first exceeded limit, which is returned alongside
+fn read_entries(data: &[u8], mut limits: ReadLimits) -> Result<(Vec<ArchiveEntry>, Option<String>)> {
+    let mut entries = Vec::new();
+    let read = match detect_format(data)? {
+        ArchiveFormat::TarGz => read_tar_gz(data, &mut entries, &mut limits),
+        ArchiveFormat::Zip => read_zip(data, &mut entries, &mut limits),
+    };
+
+    match read {
+        Ok(()) => Ok((entries, None)),
+        Err(ReadStop::Limit(violation)) => Ok((entries, Some(violation))),
+        Err(ReadStop::Failed(e)) => Err(e),
+    }
+}
+
+/// Entries escaping the schema directory
+fn archive_violations(entries: &[ArchiveEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|entry| {
+            !entry
+                .path
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        })
+        .map(|entry| format!("Archive entry {} escapes the schema directory", entry.path.display()))
+        .collect()
+}
+
+fn detect_format(data: &[u8]) -> Result<ArchiveFormat> {
+    if data.starts_with(&GZIP_MAGIC) {
+        Ok(ArchiveFormat::TarGz)
+    } else if data.starts_with(&ZIP_MAGIC) {
+        Ok(ArchiveFormat::Zip)
+    } else {
+        Err(extraction_error(
+            "Unsupported archive format: expected tar.gz or zip".to_string(),
+        ))
+    }
+}
+
+fn read_tar_gz(
+    data: &[u8],
+    entries: &mut Vec<ArchiveEntry>,
+    limits: &mut ReadLimits,
+) -> std::result::Result<(), ReadStop> {
+    let mut archive = Archive::new(GzDecoder::new(data));
+
+    for entry in archive
+        .entries()
+        .map_err(|e| extraction_error(format!("Failed to read archive entries: {}", e)))?
+    {
+        let mut entry = entry.map_err(|e| extraction_error(format!("Failed to read entry: {}", e)))?;
+        let path = entry
+            .path()
+            .map_err(|e| extraction_error(format!("Failed to get entry path: {}", e)))?
+            .to_path_buf();
+
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            limits.count_entry()?;
+            entries.push(ArchiveEntry { path, is_dir: true, data: Vec::new() });
+        } else if entry_type.is_file() {
+            limits.count_entry()?;
+            let content = limits.read_content(&mut entry, &path.display().to_string())?;
+            entries.push(ArchiveEntry { path, is_dir: false, data: content });
+        }
+    }
+
+    Ok(())
+}
+
+/// Read every entry of a zip archive
+fn read_zip(
+    data: &[u8],
+    entries: &mut Vec<ArchiveEntry>,
+    limits: &mut ReadLimits,
+) -> std::result::Result<(), ReadStop> {
+    let mut archive = ZipArchive::new(Cursor::new(data))
+        .map_err(|e| extraction_error(format!("Invalid zip archive: {}", e)))?;
+
+    for index in 0..archive.len() {
+        limits.count_entry()?;
+        let mut file = archive
+            .by_index(index)
+            .map_err(|e| extraction_error(format!("Failed to read entry: {}", e)))?;
+        let name = file.name().replace('\\', "/");
+
+        if file.is_dir() {
+            entries.push(ArchiveEntry {
+                path: PathBuf::from(name.trim_end_matches('/')),
+                is_dir: true,
+                data: Vec::new(),
+            });
+            continue;
+        }
+
+        let content = limits.read_content(&mut file, &name)?;
+        entries.push(ArchiveEntry {
+            path: PathBuf::from(name),
+            is_dir: false,
+            data: content,
+        });
+    }
+
+    Ok(())
+}
+
+fn extraction_error(cause: String) -> GatewayError {
+    GatewayError::SchemaExtractionFailed { cause }
+}
+
+/// Strip the optional `postgresql/` wrapper from an archive path
+pub fn strip_schema_prefix(path: &Path) -> PathBuf {
+    path.strip_prefix("postgresql/")
+        .or_else(|_| path.strip_prefix("postgresql"))
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    /// Build a zip archive (deflated entries; names ending in `/` are directories)
+    pub(crate) fn zip_with(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        for (name, content) in files {
+            if name.ends_with('/') {
+                writer.add_directory(*name, options).unwrap();
+            } else {
+                writer.start_file(*name, options).unwrap();
+                writer.write_all(content.as_bytes()).unwrap();
+            }
+        }
+
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_read_zip_and_tar_gz_alike() {
+        let files = [
+            ("postgresql/", ""),
+            ("postgresql/tables/users.pssql", "CREATE TABLE users (id SERIAL PRIMARY KEY);"),
+        ];
+
+        let mut tar_gz = Vec::new();
+        {
+            let mut builder = tar::Builder::new(GzEncoder::new(&mut tar_gz, Compression::default()));
+            let content = files[1].1.as_bytes();
+            let mut header = tar::Header::new_gnu();
+            header.set_path(files[1].0).unwrap();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, content).unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+
+        let from_zip = read_archive(&zip_with(&files)).unwrap();
+        let from_tar = read_archive(&tar_gz).unwrap();
+
+        let zip_file = from_zip.iter().find(|e| !e.is_dir).unwrap();
+        assert_eq!(from_zip.iter().filter(|e| e.is_dir).count(), 1);
+        assert_eq!(zip_file.path, from_tar[0].path);
+        assert_eq!(zip_file.data, from_tar[0].data);
+        assert_eq!(strip_schema_prefix(&zip_file.path), PathBuf::from("tables/users.pssql"));
+    }
+
+    #[test]
+    fn test_reject_unknown_and_unsafe_archives() {
+        let err = read_archive(b"not an archive").unwrap_err();
+        assert!(err.to_string().contains("Unsupported archive format"));
+
+        assert!(read_archive(&zip_with(&[("../evil.pssql", "DROP TABLE users;")])).is_err());
+
+        let mut corrupt = zip_with(&[("tables/users.pssql", "CREATE TABLE users (id INT);")]);
+        corrupt.truncate(corrupt.len() - 10);
+        assert!(read_archive(&corrupt).is_err());
+    }
+
+    #[test]
+    fn test_limits_stop_reading_at_first_breach() {
+        let big = "x".repeat(4096);
+        let archive = zip_with(&[
+            ("tables/a.pssql", "SELECT 1;"),
+            ("tables/big.pssql", &big),
+            ("tables/c.pssql", "SELECT 3;"),
+        ]);
+
+        let (entries, violation) = read_entries(&archive, ReadLimits::new(10, 1024)).unwrap();
+        assert_eq!(violation.as_deref(), Some("Archive extracts to more than 1024 bytes"));
+        // Nothing past the limit is buffered, and the entries after it are not read
+        assert_eq!(entries.len(), 1);
+
+        let (entries, violation) = read_entries(&archive, ReadLimits::new(2, 1 << 20)).unwrap();
+        assert_eq!(violation.as_deref(), Some("Archive has more than 2 entries"));
+        assert_eq!(entries.len(), 2);
+
+        let (entries, violation) = read_entries(&archive, ReadLimits::default()).unwrap();
+        assert!(violation.is_none());
+        assert_eq!(entries.len(), 3);
+    }
+
+    #[test]
+    fn test_inspect_well_formed_archive() {
+        let report = inspect_archive(&zip_with(&[
+            ("postgresql/tables/users.pssql", "CREATE TABLE users (id SERIAL PRIMARY KEY);"),
+            ("postgresql/tables/orders.pssql", "CREATE TABLE orders (id SERIAL PRIMARY KEY);"),
+            ("postgresql/functions/get_users.pssql", "SELECT 1;"),
+            ("postgresql/seeders/", ""),
+        ]))
+        .unwrap();
+
+        assert_eq!(report.format, "zip");
+        assert_eq!(report.root_prefix.as_deref(), Some("postgresql/"));
+        assert_eq!(report.folders.get("tables"), Some(&2));
+        assert_eq!(report.folders.get("functions"), Some(&1));
+        assert_eq!(report.folders.get("seeders"), Some(&0));
+        assert_eq!(report.entry_count, 4);
+        assert!(report.uncompressed_bytes > 0);
+        assert!(report.violations.is_empty());
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn test_inspect_reports_traversal_and_missing_folders() {
+        let report = inspect_archive(&zip_with(&[
+            ("tables/users.pssql", "CREATE TABLE users (id INT);"),
+            ("../evil.pssql", "DROP TABLE users;"),
+        ]))
+        .unwrap();
+
+        assert_eq!(report.root_prefix, None);
+        assert_eq!(report.missing_folders, vec!["functions".to_string()]);
+        assert_eq!(report.violations.len(), 1);
+        assert!(report.violations[0].contains("../evil.pssql"), "{:?}", report.violations);
+        assert!(!report.is_valid());
+    }
+}
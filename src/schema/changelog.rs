@@ -14,6 +14,7 @@ use tracing::debug;
 #[serde(rename_all = "snake_case")]
 pub enum ChangeType {
     MigrationApplied,
+    MigrationRolledBack,
     FunctionDeployed,
     FunctionDropped,
     FunctionSkipped,
@@ -22,12 +23,14 @@ pub enum ChangeType {
     SeederRun,
     SeederSkipped,
     SeederValidated,
+    HookExecuted,
 }
 
 impl std::fmt::Display for ChangeType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ChangeType::MigrationApplied => write!(f, "migration_applied"),
+            ChangeType::MigrationRolledBack => write!(f, "migration_rolled_back"),
             ChangeType::FunctionDeployed => write!(f, "function_deployed"),
             ChangeType::FunctionDropped => write!(f, "function_dropped"),
             ChangeType::FunctionSkipped => write!(f, "function_skipped"),
@@ -36,6 +39,7 @@ impl std::fmt::Display for ChangeType {
             ChangeType::SeederRun => write!(f, "seeder_run"),
             ChangeType::SeederSkipped => write!(f, "seeder_skipped"),
             ChangeType::SeederValidated => write!(f, "seeder_validated"),
+            ChangeType::HookExecuted => write!(f, "hook_executed"),
         }
     }
 }
@@ -47,14 +51,23 @@ pub struct ChangelogEntry {
     pub object_name: String,
     pub details: Option<JsonValue>,
     pub forced: bool,
+    /// Client-supplied ID (X-Deploy-Id) of the request that made the change
+    pub correlation_id: Option<String>,
 }
 
 /// Manager for changelog operations
-pub struct ChangelogManager;
+pub struct ChangelogManager {
+    correlation_id: Option<String>,
+}
 
 impl ChangelogManager {
     pub fn new() -> Self {
-        Self
+        Self { correlation_id: None }
+    }
+
+    /// Record `correlation_id` with every entry logged through this manager
+    pub fn with_correlation_id(correlation_id: Option<String>) -> Self {
+        Self { correlation_id }
     }
 
     /// Ensure the changelog table exists
@@ -73,7 +86,8 @@ impl ChangelogManager {
                     object_name TEXT NOT NULL,
                     change_detail JSONB,
                     forced BOOLEAN DEFAULT FALSE,
-                    executed_at TIMESTAMPTZ DEFAULT NOW()
+                    executed_at TIMESTAMPTZ DEFAULT NOW(),
+                    correlation_id TEXT
                 )
                 "#,
                 &[],
@@ -85,6 +99,19 @@ impl ChangelogManager {
                 cause: e.to_string(),
             })?;
 
+        // Changelog tables created before correlation IDs were recorded
+        client
+            .execute(
+                "ALTER TABLE _stonescriptdb_gateway_changelog ADD COLUMN IF NOT EXISTS correlation_id TEXT",
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::MigrationFailed {
+                database: database.to_string(),
+                migration: "_stonescriptdb_gateway_changelog correlation_id column".to_string(),
+                cause: e.to_string(),
+            })?;
+
         // Create index for faster queries by change_type
         client
             .execute(
@@ -144,10 +171,10 @@ impl ChangelogManager {
             .execute(
                 r#"
                 INSERT INTO _stonescriptdb_gateway_changelog
-                    (change_type, object_name, change_detail, forced)
-                VALUES ($1, $2, $3::jsonb, $4)
+                    (change_type, object_name, change_detail, forced, correlation_id)
+                VALUES ($1, $2, $3::text::jsonb, $4, $5)
                 "#,
-                &[&change_type, &entry.object_name, &detail_json, &entry.forced],
+                &[&change_type, &entry.object_name, &detail_json, &entry.forced, &entry.correlation_id],
             )
             .await
             .map_err(|e| GatewayError::MigrationFailed {
@@ -183,6 +210,59 @@ impl ChangelogManager {
                 object_name: migration_name.to_string(),
                 details: Some(details),
                 forced: false,
+                correlation_id: self.correlation_id.clone(),
+            },
+        )
+        .await
+    }
+
+    /// Log a migration rolled back via its down file
+    pub async fn log_migration_rolled_back(
+        &self,
+        pool: &Pool,
+        database: &str,
+        migration_name: &str,
+        down_file: &str,
+    ) -> Result<()> {
+        let details = serde_json::json!({
+            "down_file": down_file
+        });
+
+        self.log_change(
+            pool,
+            database,
+            &ChangelogEntry {
+                change_type: ChangeType::MigrationRolledBack,
+                object_name: migration_name.to_string(),
+                details: Some(details),
+                forced: false,
+                correlation_id: self.correlation_id.clone(),
+            },
+        )
+        .await
+    }
+
+    /// Log a pre/post-migration hook run
+    pub async fn log_hook_executed(
+        &self,
+        pool: &Pool,
+        database: &str,
+        hook_name: &str,
+        execution_time_ms: u64,
+    ) -> Result<()> {
+        let details = serde_json::json!({
+            "execution_time_ms": execution_time_ms
+        });
+
+        self.log_change(
+            pool,
+            database,
+            &ChangelogEntry {
+                change_type: ChangeType::HookExecuted,
+                object_name: hook_name.to_string(),
+                details: Some(details),
+                forced: false,
+                correlation_id: self.correlation_id.clone(),
             },
         )
         .await
@@ -212,6 +292,7 @@ impl ChangelogManager {
                 object_name: function_name.to_string(),
                 details: Some(details),
                 forced: false,
+                correlation_id: self.correlation_id.clone(),
             },
         )
         .await
@@ -239,6 +320,7 @@ impl ChangelogManager {
                 object_name: function_name.to_string(),
                 details: Some(details),
                 forced: false,
+                correlation_id: self.correlation_id.clone(),
             },
         )
         .await
@@ -259,6 +341,7 @@ impl ChangelogManager {
                 object_name: function_name.to_string(),
                 details: None,
                 forced: false,
+                correlation_id: self.correlation_id.clone(),
             },
         )
         .await
@@ -286,6 +369,7 @@ impl ChangelogManager {
                 object_name: extension_name.to_string(),
                 details: Some(details),
                 forced: false,
+                correlation_id: self.correlation_id.clone(),
             },
         )
         .await
@@ -306,6 +390,7 @@ impl ChangelogManager {
                 object_name: extension_name.to_string(),
                 details: None,
                 forced: false,
+                correlation_id: self.correlation_id.clone(),
             },
         )
         .await
@@ -333,6 +418,7 @@ impl ChangelogManager {
                 object_name: table_name.to_string(),
                 details: Some(details),
                 forced: false,
+                correlation_id: self.correlation_id.clone(),
             },
         )
         .await
@@ -345,9 +431,13 @@ impl ChangelogManager {
         database: &str,
         table_name: &str,
         reason: &str,
+        existing_rows: i64,
+        skipped: usize,
     ) -> Result<()> {
         let details = serde_json::json!({
-            "reason": reason
+            "reason": reason,
+            "existing_rows": existing_rows,
+            "skipped": skipped
         });
 
         self.log_change(
@@ -358,6 +448,7 @@ impl ChangelogManager {
                 object_name: table_name.to_string(),
                 details: Some(details),
                 forced: false,
+                correlation_id: self.correlation_id.clone(),
             },
         )
         .await
@@ -385,11 +476,44 @@ impl ChangelogManager {
                 object_name: table_name.to_string(),
                 details: Some(details),
                 forced: false,
+                correlation_id: self.correlation_id.clone(),
             },
         )
         .await
     }
 
+    /// Rows the table had when its seeder was last skipped
+    ///
+    /// None when the latest seeder entry for the table is a run, or there is none.
+    pub async fn seeder_skipped_rows(&self, pool: &Pool, database: &str, table_name: &str) -> Result<Option<i64>> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let row = client
+            .query_opt(
+                r#"
+                SELECT change_type, (change_detail->>'existing_rows')::bigint
+                FROM _stonescriptdb_gateway_changelog
+                WHERE object_name = $1 AND change_type IN ('seeder_run', 'seeder_skipped')
+                ORDER BY executed_at DESC, id DESC
+                LIMIT 1
+                "#,
+                &[&table_name],
+            )
+            .await
+            .map_err(|e| GatewayError::MigrationFailed {
+                database: database.to_string(),
+                migration: "query seeder changelog".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        Ok(row
+            .filter(|r| r.get::<_, String>(0) == ChangeType::SeederSkipped.to_string())
+            .and_then(|r| r.get(1)))
+    }
+
     /// Get recent changelog entries
     pub async fn get_recent_entries(
         &self,
@@ -405,9 +529,9 @@ impl ChangelogManager {
         let rows = client
             .query(
                 r#"
-                SELECT id, change_type, object_name, change_detail, forced, executed_at
+                SELECT id, change_type, object_name, change_detail::text, forced, executed_at, correlation_id
                 FROM _stonescriptdb_gateway_changelog
-                ORDER BY executed_at DESC
+                ORDER BY executed_at DESC, id DESC
                 LIMIT $1
                 "#,
                 &[&limit],
@@ -432,6 +556,7 @@ impl ChangelogManager {
                 change_detail,
                 forced: row.get(4),
                 executed_at: row.get(5),
+                correlation_id: row.get(6),
             });
         }
 
@@ -454,10 +579,10 @@ impl ChangelogManager {
         let rows = client
             .query(
                 r#"
-                SELECT id, change_type, object_name, change_detail, forced, executed_at
+                SELECT id, change_type, object_name, change_detail::text, forced, executed_at, correlation_id
                 FROM _stonescriptdb_gateway_changelog
                 WHERE change_type = $1
-                ORDER BY executed_at DESC
+                ORDER BY executed_at DESC, id DESC
                 LIMIT $2
                 "#,
                 &[&change_type, &limit],
@@ -482,6 +607,7 @@ impl ChangelogManager {
                 change_detail,
                 forced: row.get(4),
                 executed_at: row.get(5),
+                correlation_id: row.get(6),
             });
         }
 
@@ -504,6 +630,8 @@ pub struct ChangelogRecord {
     pub change_detail: Option<JsonValue>,
     pub forced: bool,
     pub executed_at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
 }
 
 #[cfg(test)]
@@ -524,10 +652,12 @@ mod tests {
             object_name: "001_create_users.pssql".to_string(),
             details: Some(serde_json::json!({"checksum": "abc123"})),
             forced: false,
+            correlation_id: Some("deploy-42".to_string()),
         };
 
         let json = serde_json::to_string(&entry).unwrap();
         assert!(json.contains("migration_applied"));
         assert!(json.contains("001_create_users.pssql"));
+        assert!(json.contains("deploy-42"));
     }
 }
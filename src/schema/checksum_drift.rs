@@ -0,0 +1,144 @@
+//! Fleet-wide migration checksum comparison
+//!
+//! Every database records the checksum of each migration it applied. Across a
+//! platform's databases these should be identical; a database whose checksum
+//! for a migration differs from the one most databases applied ran different
+//! content (an edited file, or a tampered tracking table).
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// A migration as recorded in a database's tracking table
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppliedChecksum {
+    pub migration_file: String,
+    pub checksum: String,
+}
+
+/// A migration applied with different content across databases
+#[derive(Debug, Clone, Serialize)]
+pub struct ChecksumDrift {
+    pub migration_file: String,
+    /// Checksum most databases applied (None when there is no single majority)
+    pub expected_checksum: Option<String>,
+    /// Databases that applied something else (every database on a tie)
+    pub divergent: Vec<DivergentChecksum>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DivergentChecksum {
+    pub database: String,
+    pub checksum: String,
+}
+
+/// Find migrations whose checksum is not the same in every database
+///
+/// `applied` maps database name to its applied migrations. Migrations a
+/// database has not applied yet are not drift.
+pub fn find_checksum_drift(applied: &BTreeMap<String, Vec<AppliedChecksum>>) -> Vec<ChecksumDrift> {
+    // migration -> [(database, checksum)]
+    let mut by_migration: BTreeMap<&str, Vec<(&str, &str)>> = BTreeMap::new();
+    for (database, migrations) in applied {
+        for m in migrations {
+            by_migration
+                .entry(m.migration_file.as_str())
+                .or_default()
+                .push((database.as_str(), m.checksum.as_str()));
+        }
+    }
+
+    let mut drift = Vec::new();
+
+    for (migration_file, checksums) in by_migration {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (_, checksum) in &checksums {
+            *counts.entry(checksum).or_insert(0) += 1;
+        }
+        if counts.len() < 2 {
+            continue;
+        }
+
+        let max = counts.values().copied().max().unwrap_or(0);
+        let mut leaders = counts.iter().filter(|(_, &count)| count == max);
+        let expected = match (leaders.next(), leaders.next()) {
+            (Some((checksum, _)), None) => Some(checksum.to_string()),
+            _ => None,
+        };
+
+        let divergent = checksums
+            .iter()
+            .filter(|(_, checksum)| expected.as_deref() != Some(*checksum))
+            .map(|(database, checksum)| DivergentChecksum {
+                database: database.to_string(),
+                checksum: checksum.to_string(),
+            })
+            .collect();
+
+        drift.push(ChecksumDrift {
+            migration_file: migration_file.to_string(),
+            expected_checksum: expected,
+            divergent,
+        });
+    }
+
+    drift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(migrations: &[(&str, &str)]) -> Vec<AppliedChecksum> {
+        migrations
+            .iter()
+            .map(|(file, checksum)| AppliedChecksum {
+                migration_file: file.to_string(),
+                checksum: checksum.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_divergent_database_is_flagged() {
+        let mut fleet = BTreeMap::new();
+        fleet.insert(
+            "myapp_t1".to_string(),
+            applied(&[("001_users.pssql", "aaa"), ("002_orders.pssql", "bbb")]),
+        );
+        fleet.insert(
+            "myapp_t2".to_string(),
+            applied(&[("001_users.pssql", "aaa"), ("002_orders.pssql", "bbb")]),
+        );
+        fleet.insert(
+            "myapp_t3".to_string(),
+            applied(&[("001_users.pssql", "aaa"), ("002_orders.pssql", "tampered")]),
+        );
+
+        let drift = find_checksum_drift(&fleet);
+
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0].migration_file, "002_orders.pssql");
+        assert_eq!(drift[0].expected_checksum.as_deref(), Some("bbb"));
+        assert_eq!(drift[0].divergent.len(), 1);
+        assert_eq!(drift[0].divergent[0].database, "myapp_t3");
+        assert_eq!(drift[0].divergent[0].checksum, "tampered");
+    }
+
+    #[test]
+    fn test_tie_and_pending_migrations() {
+        let mut fleet = BTreeMap::new();
+        fleet.insert("myapp_t1".to_string(), applied(&[("001_users.pssql", "aaa")]));
+        fleet.insert("myapp_t2".to_string(), applied(&[("001_users.pssql", "zzz")]));
+        // Not applied yet: not drift
+        fleet.insert("myapp_t3".to_string(), Vec::new());
+
+        let drift = find_checksum_drift(&fleet);
+
+        assert_eq!(drift.len(), 1);
+        assert!(drift[0].expected_checksum.is_none());
+        assert_eq!(drift[0].divergent.len(), 2);
+
+        fleet.remove("myapp_t2");
+        assert!(find_checksum_drift(&fleet).is_empty());
+    }
+}
@@ -10,6 +10,7 @@
 //! so migrations can use custom types.
 
 use crate::error::{GatewayError, Result};
+use crate::schema::dependency::{DependencyAnalyzer, TableInfo};
 use deadpool_postgres::Pool;
 use regex::Regex;
 use sha2::{Digest, Sha256};
@@ -106,20 +107,9 @@ impl CustomTypeManager {
 
         // Remove comments for parsing
         let sql = self.remove_comments(&content);
-        let sql_upper = sql.to_uppercase();
 
         // Detect type kind
-        let type_kind = if sql_upper.contains("AS ENUM") {
-            TypeKind::Enum
-        } else if sql_upper.contains("CREATE DOMAIN") {
-            TypeKind::Domain
-        } else if sql_upper.contains("CREATE TYPE") && sql_upper.contains(" AS (") {
-            TypeKind::Composite
-        } else if sql_upper.contains("CREATE TYPE") {
-            TypeKind::Unknown
-        } else {
-            TypeKind::Unknown
-        };
+        let type_kind = detect_type_kind(&sql);
 
         // Extract type name
         let name = self.extract_type_name(&sql, &type_kind)?;
@@ -260,6 +250,94 @@ impl CustomTypeManager {
         Ok(row.is_some())
     }
 
+    /// Get the current values of an ENUM type, in sort order
+    async fn get_enum_values(
+        &self,
+        client: &deadpool_postgres::Object,
+        type_name: &str,
+    ) -> Result<Vec<String>> {
+        let rows = client
+            .query(
+                r#"
+                SELECT e.enumlabel
+                FROM pg_enum e
+                JOIN pg_type t ON e.enumtypid = t.oid
+                JOIN pg_namespace n ON t.typnamespace = n.oid
+                WHERE t.typname = $1
+                AND n.nspname = 'public'
+                ORDER BY e.enumsortorder
+                "#,
+                &[&type_name],
+            )
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Failed to query enum values: {}", e)))?;
+
+        Ok(rows.iter().map(|r| r.get(0)).collect())
+    }
+
+    /// Extract the values of a CREATE TYPE ... AS ENUM (...) definition
+    pub fn parse_enum_values(&self, sql: &str) -> Vec<String> {
+        let sql = self.remove_comments(sql);
+        let re = Regex::new(r"(?is)AS\s+ENUM\s*\((.*)\)").unwrap();
+        let body = match re.captures(&sql) {
+            Some(caps) => caps[1].to_string(),
+            None => return Vec::new(),
+        };
+
+        let value_re = Regex::new(r"'((?:[^']|'')*)'").unwrap();
+        value_re
+            .captures_iter(&body)
+            .map(|caps| caps[1].replace("''", "'"))
+            .collect()
+    }
+
+    /// Plan ALTER TYPE ... ADD VALUE statements to go from `existing` to `desired`
+    ///
+    /// Returns None if the change is not purely additive (values removed or reordered).
+    /// New values keep their declared position using BEFORE/AFTER.
+    fn plan_enum_additions(
+        &self,
+        type_name: &str,
+        existing: &[String],
+        desired: &[String],
+    ) -> Option<Vec<String>> {
+        // Existing values must appear in the same relative order
+        let kept: Vec<&String> = desired.iter().filter(|v| existing.contains(v)).collect();
+        if kept.len() != existing.len() || kept.iter().zip(existing).any(|(a, b)| *a != b) {
+            return None;
+        }
+
+        let quote = |v: &str| format!("'{}'", v.replace('\'', "''"));
+        let mut statements = Vec::new();
+
+        for (i, value) in desired.iter().enumerate() {
+            if existing.contains(value) {
+                continue;
+            }
+
+            // Values after the last existing one are simply appended; values
+            // ahead of the first existing one go before it, in declared order
+            let appended = !desired[i + 1..].iter().any(|v| existing.contains(v));
+            let leading = !desired[..i].iter().any(|v| existing.contains(v));
+            let position = if appended || existing.is_empty() {
+                String::new()
+            } else if leading {
+                format!(" BEFORE {}", quote(&existing[0]))
+            } else {
+                format!(" AFTER {}", quote(&desired[i - 1]))
+            };
+
+            statements.push(format!(
+                "ALTER TYPE {} ADD VALUE IF NOT EXISTS {}{}",
+                type_name,
+                quote(value),
+                position
+            ));
+        }
+
+        Some(statements)
+    }
+
     /// Deploy custom types to database
     /// Returns the number of types deployed
     pub async fn deploy_types(
@@ -315,9 +393,48 @@ impl CustomTypeManager {
                 }
 
                 // Type changed - need to handle carefully
-                // For ENUMs, we can add values but not remove/rename
-                // For now, we'll warn and skip if type exists
+                // For ENUMs, purely additive changes are applied with ADD VALUE;
+                // anything else (removal, reordering, other kinds) needs a manual migration
                 if self.type_exists(&client, &custom_type.name).await? {
+                    if custom_type.type_kind == TypeKind::Enum {
+                        let existing = self.get_enum_values(&client, &custom_type.name).await?;
+                        let desired = self.parse_enum_values(&custom_type.sql);
+
+                        if let Some(statements) =
+                            self.plan_enum_additions(&custom_type.name, &existing, &desired)
+                        {
+                            for statement in &statements {
+                                client.execute(statement.as_str(), &[]).await.map_err(|e| {
+                                    GatewayError::MigrationFailed {
+                                        database: database.to_string(),
+                                        migration: format!("type:{}", custom_type.name),
+                                        cause: e.to_string(),
+                                    }
+                                })?;
+                            }
+
+                            if !statements.is_empty() {
+                                info!(
+                                    "Added {} value(s) to ENUM type {} in database {}",
+                                    statements.len(),
+                                    custom_type.name,
+                                    database
+                                );
+                            }
+                            self.update_tracking(&client, &custom_type, file_name).await?;
+                            updated += 1;
+                            continue;
+                        }
+
+                        warn!(
+                            "ENUM type {} had values removed or reordered. Manual migration required.",
+                            custom_type.name
+                        );
+                        self.update_tracking(&client, &custom_type, file_name).await?;
+                        updated += 1;
+                        continue;
+                    }
+
                     warn!(
                         "Type {} already exists with different definition. Manual migration required.",
                         custom_type.name
@@ -437,6 +554,74 @@ impl CustomTypeManager {
         let types: Vec<String> = rows.iter().map(|r| r.get(0)).collect();
         Ok(types)
     }
+
+    /// Check that enum-typed columns only default to values of their enum
+    ///
+    /// Runs against the schema files, so a bad default is reported before the
+    /// deploy fails at the database.
+    pub fn validate_enum_defaults(&self, types_dir: &Path, tables_dir: &Path) -> Result<()> {
+        let mut enums = HashMap::new();
+        for file in self.find_type_files(types_dir)? {
+            let custom_type = self.parse_type(&file)?;
+            if custom_type.type_kind == TypeKind::Enum {
+                let values = self.parse_enum_values(&custom_type.sql);
+                enums.insert(custom_type.name, values);
+            }
+        }
+
+        if enums.is_empty() || !tables_dir.exists() {
+            return Ok(());
+        }
+
+        let analysis = DependencyAnalyzer::analyze_directory(tables_dir)?;
+        let violations = enum_default_violations(&enums, &analysis.tables);
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(GatewayError::InvalidRequest {
+                message: format!("Invalid enum default: {}", violations.join("; ")),
+            })
+        }
+    }
+}
+
+/// Describe every enum-typed column whose DEFAULT literal is not a declared value
+fn enum_default_violations(enums: &HashMap<String, Vec<String>>, tables: &[TableInfo]) -> Vec<String> {
+    let default_re = Regex::new(r"(?i)\bDEFAULT\s+'((?:[^']|'')*)'").unwrap();
+    let mut violations = Vec::new();
+
+    for table in tables {
+        for column in &table.columns {
+            let type_name = column.data_type.to_lowercase().replace('"', "");
+            let type_name = type_name.strip_prefix("public.").unwrap_or(&type_name);
+
+            let Some(values) = enums.get(type_name) else {
+                continue;
+            };
+            let Some(caps) = default_re.captures(&column.definition) else {
+                continue;
+            };
+
+            let default = caps[1].replace("''", "'");
+            if !values.contains(&default) {
+                violations.push(format!(
+                    "column {}.{} defaults to '{}', which is not a value of enum {} (allowed: {})",
+                    table.name,
+                    column.name,
+                    default,
+                    type_name,
+                    values
+                        .iter()
+                        .map(|v| format!("'{}'", v))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        }
+    }
+
+    violations
 }
 
 impl Default for CustomTypeManager {
@@ -445,6 +630,23 @@ impl Default for CustomTypeManager {
     }
 }
 
+/// Classify a type definition, allowing any whitespace (or none) around `AS`
+fn detect_type_kind(sql: &str) -> TypeKind {
+    let enum_re = Regex::new(r#"(?is)\bCREATE\s+TYPE\s+[\w."]+\s+AS\s+ENUM\s*\("#).unwrap();
+    let domain_re = Regex::new(r#"(?is)\bCREATE\s+DOMAIN\s+[\w."]+"#).unwrap();
+    let composite_re = Regex::new(r#"(?is)\bCREATE\s+TYPE\s+[\w."]+\s+AS\s*\("#).unwrap();
+
+    if enum_re.is_match(sql) {
+        TypeKind::Enum
+    } else if domain_re.is_match(sql) {
+        TypeKind::Domain
+    } else if composite_re.is_match(sql) {
+        TypeKind::Composite
+    } else {
+        TypeKind::Unknown
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -496,6 +698,23 @@ CREATE TYPE address AS (
         assert_eq!(custom_type.type_kind, TypeKind::Composite);
     }
 
+    #[test]
+    fn test_detect_type_kind_whitespace() {
+        for sql in [
+            "CREATE TYPE address AS(street TEXT, city TEXT);",
+            "CREATE TYPE address AS (street TEXT, city TEXT);",
+            "CREATE TYPE address AS\n(\n    street TEXT,\n    city TEXT\n);",
+            "create type address\n  as\t(street text);",
+        ] {
+            assert_eq!(detect_type_kind(sql), TypeKind::Composite, "{}", sql);
+        }
+
+        assert_eq!(detect_type_kind("CREATE TYPE status AS ENUM('a', 'b');"), TypeKind::Enum);
+        assert_eq!(detect_type_kind("CREATE TYPE status\nAS\nENUM\n('a');"), TypeKind::Enum);
+        assert_eq!(detect_type_kind("CREATE\nDOMAIN email AS TEXT;"), TypeKind::Domain);
+        assert_eq!(detect_type_kind("CREATE TYPE floatrange AS RANGE (subtype = float8);"), TypeKind::Unknown);
+    }
+
     #[test]
     fn test_parse_domain_type() {
         let manager = CustomTypeManager::new();
@@ -528,6 +747,144 @@ CHECK (VALUE ~ '^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$');
         assert_eq!(files.len(), 2);
     }
 
+    #[test]
+    fn test_enum_default_not_in_type_is_rejected() {
+        let manager = CustomTypeManager::new();
+        let temp_dir = TempDir::new().unwrap();
+        let types_dir = temp_dir.path().join("types");
+        let tables_dir = temp_dir.path().join("tables");
+        fs::create_dir_all(&types_dir).unwrap();
+        fs::create_dir_all(&tables_dir).unwrap();
+
+        fs::write(
+            types_dir.join("order_status.pssql"),
+            "CREATE TYPE order_status AS ENUM ('pending', 'shipped');",
+        )
+        .unwrap();
+        fs::write(
+            tables_dir.join("orders.pssql"),
+            "CREATE TABLE orders (\n    id SERIAL PRIMARY KEY,\n    status order_status NOT NULL DEFAULT 'unknown'\n);",
+        )
+        .unwrap();
+
+        let err = manager
+            .validate_enum_defaults(&types_dir, &tables_dir)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("orders.status defaults to 'unknown'"), "{}", err);
+        assert!(err.contains("enum order_status"), "{}", err);
+
+        fs::write(
+            tables_dir.join("orders.pssql"),
+            "CREATE TABLE orders (\n    id SERIAL PRIMARY KEY,\n    status order_status NOT NULL DEFAULT 'pending'::order_status\n);",
+        )
+        .unwrap();
+        assert!(manager.validate_enum_defaults(&types_dir, &tables_dir).is_ok());
+    }
+
+    fn values(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_enum_values() {
+        let manager = CustomTypeManager::new();
+
+        let sql = r#"
+-- Order status enum
+CREATE TYPE order_status AS ENUM (
+    'pending',
+    'in progress', -- work started
+    'won''t fix'
+);
+"#;
+
+        assert_eq!(
+            manager.parse_enum_values(sql),
+            values(&["pending", "in progress", "won't fix"])
+        );
+    }
+
+    #[test]
+    fn test_enum_values_appended() {
+        let manager = CustomTypeManager::new();
+
+        let statements = manager
+            .plan_enum_additions(
+                "order_status",
+                &values(&["pending", "shipped"]),
+                &values(&["pending", "shipped", "delivered", "returned"]),
+            )
+            .unwrap();
+
+        assert_eq!(
+            statements,
+            vec![
+                "ALTER TYPE order_status ADD VALUE IF NOT EXISTS 'delivered'",
+                "ALTER TYPE order_status ADD VALUE IF NOT EXISTS 'returned'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_enum_values_inserted() {
+        let manager = CustomTypeManager::new();
+
+        let statements = manager
+            .plan_enum_additions(
+                "order_status",
+                &values(&["pending", "shipped"]),
+                &values(&["draft", "pending", "processing", "shipped"]),
+            )
+            .unwrap();
+
+        assert_eq!(
+            statements,
+            vec![
+                "ALTER TYPE order_status ADD VALUE IF NOT EXISTS 'draft' BEFORE 'pending'",
+                "ALTER TYPE order_status ADD VALUE IF NOT EXISTS 'processing' AFTER 'pending'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_several_values_inserted_before_first() {
+        let manager = CustomTypeManager::new();
+
+        let statements = manager
+            .plan_enum_additions("order_status", &values(&["pending"]), &values(&["a", "b", "pending"]))
+            .unwrap();
+
+        // Each goes right before 'pending', so they end up as a, b, pending
+        assert_eq!(
+            statements,
+            vec![
+                "ALTER TYPE order_status ADD VALUE IF NOT EXISTS 'a' BEFORE 'pending'",
+                "ALTER TYPE order_status ADD VALUE IF NOT EXISTS 'b' BEFORE 'pending'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_enum_values_removed_or_reordered() {
+        let manager = CustomTypeManager::new();
+        let existing = values(&["pending", "shipped", "delivered"]);
+
+        // Removed
+        assert!(manager
+            .plan_enum_additions("order_status", &existing, &values(&["pending", "shipped"]))
+            .is_none());
+
+        // Reordered
+        assert!(manager
+            .plan_enum_additions(
+                "order_status",
+                &existing,
+                &values(&["shipped", "pending", "delivered", "returned"])
+            )
+            .is_none());
+    }
+
     #[test]
     fn test_checksum_normalization() {
         let manager = CustomTypeManager::new();
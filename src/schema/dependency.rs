@@ -1,58 +1,212 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use serde::Serialize;
 
-/// Represents a foreign key dependency between tables
+/// File (in a schema's tables/migrations folder) listing tables managed outside
+/// the schema, one name per line. FKs to these tables are treated as external.
+pub const EXTERNAL_TABLES_FILE: &str = "external_tables.txt";
+
+/// Schema assumed for tables without an explicit `schema.` prefix
+pub const DEFAULT_SCHEMA: &str = "public";
+
+/// Regex fragment matching a bare or double-quoted identifier (e.g. `users`, `"User Data"`)
+const IDENTIFIER: &str = r#"(?:"(?:[^"]|"")+"|\w+)"#;
+
+/// Regex fragment matching an optionally schema-qualified, optionally quoted name
+/// (e.g. `users`, `inventory.products`, `auth."users"`)
+const QUALIFIED_NAME: &str = r#"((?:"(?:[^"]|"")+"|\w+)(?:\s*\.\s*(?:"(?:[^"]|"")+"|\w+))?)"#;
+
+/// Failure to parse a schema file, located by file and (where known) line
 #[derive(Debug, Clone, Serialize)]
+pub struct ParseError {
+    pub file: PathBuf,
+    /// 1-based line the problem starts on
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Represents a foreign key dependency between tables
+///
+/// Composite keys list their columns in declaration order; `from_columns[i]`
+/// references `to_columns[i]`.
+#[derive(Debug, Clone)]
 pub struct ForeignKeyDependency {
     pub from_table: String,
-    pub from_column: String,
+    pub from_columns: Vec<String>,
+    /// Schema of the referenced table (None = default schema)
+    pub to_schema: Option<String>,
     pub to_table: String,
-    pub to_column: String,
+    /// Empty for `REFERENCES table` without columns when the target's
+    /// primary key is not declared with a matching number of columns
+    pub to_columns: Vec<String>,
     pub on_delete: Option<String>,
     pub on_update: Option<String>,
 }
 
+impl ForeignKeyDependency {
+    /// Referencing column of a single-column foreign key
+    pub fn referencing_column(&self) -> Option<&str> {
+        match self.from_columns.as_slice() {
+            [column] => Some(column),
+            _ => None,
+        }
+    }
+
+    /// Referenced column of a single-column foreign key
+    pub fn referenced_column(&self) -> Option<&str> {
+        match self.to_columns.as_slice() {
+            [column] => Some(column),
+            _ => None,
+        }
+    }
+}
+
+/// Column lists are also serialized comma-joined as `from_column`/`to_column`,
+/// the shape of the single-column API
+impl Serialize for ForeignKeyDependency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("ForeignKeyDependency", 9)?;
+        state.serialize_field("from_table", &self.from_table)?;
+        state.serialize_field("from_column", &self.from_columns.join(", "))?;
+        state.serialize_field("from_columns", &self.from_columns)?;
+        state.serialize_field("to_schema", &self.to_schema)?;
+        state.serialize_field("to_table", &self.to_table)?;
+        state.serialize_field("to_column", &self.to_columns.join(", "))?;
+        state.serialize_field("to_columns", &self.to_columns)?;
+        state.serialize_field("on_delete", &self.on_delete)?;
+        state.serialize_field("on_update", &self.on_update)?;
+        state.end()
+    }
+}
+
 /// Represents a table with its dependencies
 #[derive(Debug, Clone, Serialize)]
 pub struct TableInfo {
+    /// Schema from a `schema.table` name (None = default schema)
+    pub schema: Option<String>,
     pub name: String,
     pub columns: Vec<ColumnInfo>,
     pub primary_key: Option<Vec<String>>,
     pub foreign_keys: Vec<ForeignKeyDependency>,
-    pub depends_on: Vec<String>,  // Tables this table depends on
+    /// Parent tables from `INHERITS (...)`, or the parent of a partition
+    /// (qualified names)
+    pub inherits: Vec<String>,
+    /// Created with `PARTITION OF parent`; columns come from the parent
+    pub is_partition: bool,
+    /// Column-level and table-level CHECK constraints, in declaration order
+    pub checks: Vec<CheckConstraint>,
+    pub depends_on: Vec<String>,  // Tables this table depends on (qualified names)
+}
+
+impl TableInfo {
+    /// Name used for dependency resolution: `schema.table`, or the bare
+    /// name for tables in the default schema
+    pub fn qualified_name(&self) -> String {
+        qualified_table_name(self.schema.as_deref(), &self.name)
+    }
 }
 
 /// Represents a column definition
 #[derive(Debug, Clone, Serialize)]
 pub struct ColumnInfo {
     pub name: String,
+    /// Type as declared, uppercased (e.g. "VARCHAR(100)", "NUMERIC(10,2)")
     pub data_type: String,
+    /// Length of VARCHAR(n)/CHAR(n) columns
+    pub character_maximum_length: Option<i32>,
+    /// Precision of NUMERIC(p,s)/DECIMAL(p,s) columns
+    pub numeric_precision: Option<i32>,
+    /// Scale of NUMERIC(p,s)/DECIMAL(p,s) columns (0 when only precision is given)
+    pub numeric_scale: Option<i32>,
     pub is_nullable: bool,
     pub is_primary_key: bool,
     pub has_default: bool,
     pub references: Option<ColumnReference>,
+    /// Expression of an inline CHECK constraint (e.g. "age >= 0")
+    pub check_constraint: Option<String>,
+    /// Full column definition as written (e.g. "email VARCHAR(255) NOT NULL")
+    pub definition: String,
+}
+
+/// A CHECK constraint declared on a column or on the table
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckConstraint {
+    /// Declared name, or the one PostgreSQL generates (`table_column_check`)
+    pub name: String,
+    /// Column the constraint is declared on (None for table-level checks)
+    pub column: Option<String>,
+    /// Expression inside `CHECK (...)`, as written
+    pub expression: String,
 }
 
 /// Represents a column reference (inline foreign key)
 #[derive(Debug, Clone, Serialize)]
 pub struct ColumnReference {
+    /// Schema of the referenced table (None = default schema)
+    pub schema: Option<String>,
     pub table: String,
+    /// Referenced column; for `REFERENCES table` the target's primary key once
+    /// resolved, empty if it cannot be
     pub column: String,
     pub on_delete: Option<String>,
     pub on_update: Option<String>,
 }
 
+/// A `CREATE [UNIQUE] INDEX` statement
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexInfo {
+    pub name: String,
+    /// Schema of the indexed table (None = default schema)
+    pub schema: Option<String>,
+    pub table: String,
+    pub unique: bool,
+    /// Access method, lowercased (`btree` unless `USING` says otherwise)
+    pub method: String,
+    /// Key columns/expressions, normalized for comparison (e.g. "name", "lower(email)")
+    pub keys: Vec<String>,
+    /// `WHERE` predicate of a partial index, lowercased with whitespace collapsed
+    pub predicate: Option<String>,
+    /// Statement as written, without the trailing semicolon
+    pub definition: String,
+}
+
+impl IndexInfo {
+    /// Qualified name of the indexed table, as used for [`TableInfo::qualified_name`]
+    pub fn table_name(&self) -> String {
+        qualified_table_name(self.schema.as_deref(), &self.table)
+    }
+}
+
 /// Result of dependency analysis
 #[derive(Debug, Clone, Serialize)]
 pub struct DependencyAnalysis {
     pub tables: Vec<TableInfo>,
+    /// CREATE INDEX statements, unnamed ones under PostgreSQL's default name
+    pub indexes: Vec<IndexInfo>,
     pub creation_order: Vec<String>,
     pub dependency_graph: HashMap<String, Vec<String>>,
     pub reverse_dependencies: HashMap<String, Vec<String>>,
     pub circular_dependencies: Vec<Vec<String>>,
+    /// Foreign keys whose target table is neither defined nor allow-listed as external
+    pub missing_targets: Vec<ForeignKeyDependency>,
+    /// Foreign keys pointing to allow-listed external tables
+    pub external_references: Vec<ForeignKeyDependency>,
 }
 
 /// Analyzes table dependencies from SQL files
@@ -61,12 +215,16 @@ pub struct DependencyAnalyzer;
 impl DependencyAnalyzer {
     /// Analyze all SQL files in a directory (migrations or tables folder)
     /// Supports .pssql and .pgsql extensions
-    pub fn analyze_directory(dir: &Path) -> Result<DependencyAnalysis, String> {
+    pub fn analyze_directory(dir: &Path) -> Result<DependencyAnalysis, ParseError> {
         let mut all_sql = String::new();
 
         // Read all .pssql and .pgsql files
         let mut files: Vec<_> = fs::read_dir(dir)
-            .map_err(|e| format!("Failed to read directory: {}", e))?
+            .map_err(|e| ParseError {
+                file: dir.to_path_buf(),
+                line: None,
+                message: format!("Failed to read directory: {}", e),
+            })?
             .filter_map(|entry| entry.ok())
             .filter(|entry| {
                 entry.path().extension()
@@ -79,70 +237,426 @@ impl DependencyAnalyzer {
         files.sort_by_key(|entry| entry.file_name());
 
         for entry in files {
-            let content = fs::read_to_string(entry.path())
-                .map_err(|e| format!("Failed to read {}: {}", entry.path().display(), e))?;
+            let path = entry.path();
+            let content = fs::read_to_string(&path).map_err(|e| ParseError {
+                file: path.clone(),
+                line: None,
+                message: format!("Failed to read file: {}", e),
+            })?;
+            Self::check_file_syntax(&path, &content)?;
             all_sql.push_str(&content);
             all_sql.push('\n');
         }
 
-        Self::analyze_sql(&all_sql)
+        let external_tables = Self::load_external_tables(dir);
+        Self::analyze_sql_with_external(&all_sql, &external_tables).map_err(|message| ParseError {
+            file: dir.to_path_buf(),
+            line: None,
+            message,
+        })
+    }
+
+    /// Analyze the SQL of a single file, reporting failures against that file
+    pub fn analyze_file_sql(file: &Path, sql: &str) -> Result<DependencyAnalysis, ParseError> {
+        Self::check_file_syntax(file, sql)?;
+        Self::analyze_sql(sql).map_err(|message| ParseError {
+            file: file.to_path_buf(),
+            line: None,
+            message,
+        })
+    }
+
+    fn check_file_syntax(file: &Path, sql: &str) -> Result<(), ParseError> {
+        match Self::check_syntax(sql) {
+            Some((line, message)) => Err(ParseError {
+                file: file.to_path_buf(),
+                line: Some(line),
+                message,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that quotes, comments and parentheses are balanced
+    ///
+    /// The regex-based extraction silently skips statements it cannot match,
+    /// so this catches the common typos up front. Returns the 1-based line the
+    /// problem starts on and a description.
+    fn check_syntax(sql: &str) -> Option<(usize, String)> {
+        let dollar_tag_re = regex::Regex::new(r"^\$(?:[A-Za-z_][A-Za-z0-9_]*)?\$").unwrap();
+        let bytes = sql.as_bytes();
+        let mut line = 1;
+        // Line of each '(' not closed yet
+        let mut open_parens: Vec<usize> = Vec::new();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\n' => line += 1,
+                b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                    continue;
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'*') => match sql[i + 2..].find("*/") {
+                    Some(end) => {
+                        line += sql[i..i + 2 + end].matches('\n').count();
+                        i += end + 4;
+                        continue;
+                    }
+                    None => return Some((line, "Unterminated /* comment".to_string())),
+                },
+                quote @ (b'\'' | b'"') => {
+                    let start = line;
+                    // E'...' strings take backslash escapes (E'O\'Reilly')
+                    let escapes = quote == b'\''
+                        && i > 0
+                        && matches!(bytes[i - 1], b'E' | b'e')
+                        && (i < 2 || !(bytes[i - 2].is_ascii_alphanumeric() || bytes[i - 2] == b'_'));
+                    i += 1;
+                    loop {
+                        match bytes.get(i) {
+                            None => {
+                                let what = if quote == b'\'' { "string literal" } else { "quoted identifier" };
+                                return Some((start, format!("Unterminated {}", what)));
+                            }
+                            Some(b'\n') => line += 1,
+                            Some(b'\\') if escapes => {
+                                if bytes.get(i + 1) == Some(&b'\n') {
+                                    line += 1;
+                                }
+                                i += 1;
+                            }
+                            Some(&c) if c == quote => {
+                                // Doubled quote is an escaped quote
+                                if bytes.get(i + 1) == Some(&quote) {
+                                    i += 1;
+                                } else {
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                        i += 1;
+                    }
+                }
+                b'$' => {
+                    if let Some(tag) = dollar_tag_re.find(&sql[i..]) {
+                        let tag = tag.as_str();
+                        let body_start = i + tag.len();
+                        match sql[body_start..].find(tag) {
+                            Some(end) => {
+                                line += sql[i..body_start + end].matches('\n').count();
+                                i = body_start + end + tag.len();
+                                continue;
+                            }
+                            None => return Some((line, format!("Unterminated {} quoted body", tag))),
+                        }
+                    }
+                }
+                b'(' => open_parens.push(line),
+                // The guard's pop() closes the innermost '('
+                b')' if open_parens.pop().is_none() => {
+                    return Some((line, "Unexpected ')' without a matching '('".to_string()));
+                }
+                b';' => {
+                    if let Some(&open) = open_parens.last() {
+                        return Some((
+                            open,
+                            format!("'(' on line {} is not closed before ';' on line {}", open, line),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        open_parens
+            .last()
+            .map(|&open| (open, format!("'(' on line {} is never closed", open)))
     }
 
     /// Analyze SQL content for table dependencies
     pub fn analyze_sql(sql: &str) -> Result<DependencyAnalysis, String> {
+        Self::analyze_sql_with_external(sql, &[])
+    }
+
+    /// Analyze SQL content, treating FKs to `external_tables` as external references
+    ///
+    /// External tables are still recorded as dependencies, but are not
+    /// reported as missing targets.
+    pub fn analyze_sql_with_external(
+        sql: &str,
+        external_tables: &[String],
+    ) -> Result<DependencyAnalysis, String> {
         let tables = Self::extract_tables(sql);
+        let indexes = Self::extract_indexes(sql);
         let dependency_graph = Self::build_dependency_graph(&tables);
         let reverse_dependencies = Self::build_reverse_dependencies(&dependency_graph);
         let circular_dependencies = Self::detect_circular_dependencies(&dependency_graph);
-        let creation_order = Self::topological_sort(&dependency_graph)?;
+        let creation_order = Self::topological_sort(&dependency_graph).map_err(|message| {
+            let cycles = format_cycles(&circular_dependencies);
+            if cycles.is_empty() {
+                message
+            } else {
+                format!("{}: {}", message, cycles.join("; "))
+            }
+        })?;
+        let (missing_targets, external_references) =
+            Self::classify_fk_targets(&tables, external_tables);
 
         Ok(DependencyAnalysis {
             tables,
+            indexes,
             creation_order,
             dependency_graph,
             reverse_dependencies,
             circular_dependencies,
+            missing_targets,
+            external_references,
         })
     }
 
+    /// Load the external table allow-list from a directory, if present
+    pub fn load_external_tables(dir: &Path) -> Vec<String> {
+        Self::load_table_list(&dir.join(EXTERNAL_TABLES_FILE))
+    }
+
+    /// Read a list of table names, one per line, ignoring comments and blank lines
+    pub(crate) fn load_table_list(path: &Path) -> Vec<String> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => return Vec::new(),
+        };
+
+        content
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty() && !line.starts_with("--"))
+            .map(|line| line.to_lowercase())
+            .collect()
+    }
+
+    /// Split FKs with undefined targets into missing and external
+    fn classify_fk_targets(
+        tables: &[TableInfo],
+        external_tables: &[String],
+    ) -> (Vec<ForeignKeyDependency>, Vec<ForeignKeyDependency>) {
+        let defined: HashSet<String> = tables.iter().map(|t| t.qualified_name()).collect();
+        let mut missing = Vec::new();
+        let mut external = Vec::new();
+
+        for fk in tables.iter().flat_map(|t| &t.foreign_keys) {
+            let target = qualified_table_name(fk.to_schema.as_deref(), &fk.to_table);
+            if defined.contains(&target) {
+                continue;
+            }
+            if external_tables.iter().any(|e| e == &target) {
+                external.push(fk.clone());
+            } else {
+                missing.push(fk.clone());
+            }
+        }
+
+        (missing, external)
+    }
+
     /// Extract table definitions from SQL
     fn extract_tables(sql: &str) -> Vec<TableInfo> {
-        let mut tables = Vec::new();
+        // (statement offset, table) so both statement kinds keep file order
+        let mut tables: Vec<(usize, TableInfo)> = Vec::new();
 
         // Normalize SQL: remove comments and extra whitespace
         let sql = Self::normalize_sql(sql);
 
         // Find all CREATE TABLE statements
-        let create_table_re = regex::Regex::new(
-            r"(?is)CREATE\s+TABLE\s+(?:IF\s+NOT\s+EXISTS\s+)?(\w+)\s*\((.*?)\)(?:\s*;|\s*$)"
-        ).unwrap();
+        let create_table_re = regex::Regex::new(&format!(
+            r"(?is)CREATE\s+TABLE\s+(?:IF\s+NOT\s+EXISTS\s+)?{}\s*\((.*?)\)(?:\s*INHERITS\s*\(([^)]*)\))?(?:\s*PARTITION\s+BY\s+\w+\s*\([^;]*\))?(?:\s*;|\s*$)",
+            QUALIFIED_NAME
+        )).unwrap();
+
+        // CREATE TABLE ... PARTITION OF parent [(constraints)] FOR VALUES ... | DEFAULT
+        let partition_re = regex::Regex::new(&format!(
+            r"(?is)CREATE\s+TABLE\s+(?:IF\s+NOT\s+EXISTS\s+)?{q}\s+PARTITION\s+OF\s+{q}[^;]*",
+            q = QUALIFIED_NAME
+        )).unwrap();
+
+        for cap in partition_re.captures_iter(&sql) {
+            let (schema, table_name) = split_qualified_name(&cap[1]);
+            let (parent_schema, parent) = split_qualified_name(&cap[2]);
+
+            tables.push((cap.get(0).unwrap().start(), TableInfo {
+                schema,
+                name: table_name,
+                columns: Vec::new(),
+                primary_key: None,
+                foreign_keys: Vec::new(),
+                inherits: vec![qualified_table_name(parent_schema.as_deref(), &parent)],
+                is_partition: true,
+                checks: Vec::new(),
+                depends_on: Vec::new(),
+            }));
+        }
 
         for cap in create_table_re.captures_iter(&sql) {
-            let table_name = cap[1].to_lowercase();
+            let (schema, table_name) = split_qualified_name(&cap[1]);
             let body = &cap[2];
 
-            let (columns, foreign_keys, primary_key) = Self::parse_table_body(body, &table_name);
-
-            // Extract tables this table depends on
-            let depends_on: Vec<String> = foreign_keys
-                .iter()
-                .map(|fk| fk.to_table.clone())
-                .collect::<HashSet<_>>()
-                .into_iter()
-                .collect();
-
-            tables.push(TableInfo {
+            let (columns, foreign_keys, primary_key, checks) = Self::parse_table_body(body, &table_name);
+            let inherits = cap
+                .get(3)
+                .map(|parents| {
+                    parents
+                        .as_str()
+                        .split(',')
+                        .filter(|parent| !parent.trim().is_empty())
+                        .map(|parent| {
+                            let (schema, name) = split_qualified_name(parent);
+                            qualified_table_name(schema.as_deref(), &name)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            tables.push((cap.get(0).unwrap().start(), TableInfo {
+                schema,
                 name: table_name,
                 columns,
                 primary_key,
                 foreign_keys,
-                depends_on,
-            });
+                inherits,
+                is_partition: false,
+                checks,
+                depends_on: Vec::new(),
+            }));
         }
 
+        #[cfg(feature = "sql-parser-fallback")]
+        super::sql_fallback::reconcile(&sql, &mut tables);
+
+        tables.sort_by_key(|(start, _)| *start);
+        let mut tables: Vec<TableInfo> = tables.into_iter().map(|(_, table)| table).collect();
+        Self::resolve_dependencies(&mut tables);
+
         tables
     }
 
+    /// Extract CREATE [UNIQUE] INDEX statements from SQL
+    ///
+    /// Unnamed indexes get the name PostgreSQL would choose
+    /// (`table_col1_col2_idx`, `expr` for expressions).
+    fn extract_indexes(sql: &str) -> Vec<IndexInfo> {
+        let sql = Self::normalize_sql(sql);
+        let create_index_re = regex::Regex::new(&format!(
+            r"(?is)CREATE\s+(UNIQUE\s+)?INDEX\s+(?:CONCURRENTLY\s+)?(?:(?:IF\s+NOT\s+EXISTS\s+)?({ident})\s+)??ON\s+(?:ONLY\s+)?{qualified}[^;]*",
+            ident = IDENTIFIER,
+            qualified = QUALIFIED_NAME
+        )).unwrap();
+        let using_re = regex::Regex::new(r"(?is)^\s*USING\s+(\w+)").unwrap();
+        let where_re = regex::Regex::new(r"(?is)\bWHERE\s+(.*)$").unwrap();
+
+        create_index_re
+            .captures_iter(&sql)
+            .map(|cap| {
+                let (schema, table) = split_qualified_name(&cap[3]);
+                let definition = cap[0].trim().to_string();
+                // `USING ... (keys) [INCLUDE ...] [WHERE ...]`
+                let rest = &sql[cap.get(3).unwrap().end()..cap.get(0).unwrap().end()];
+                let (raw_keys, tail) = split_index_keys(rest);
+                let name = match cap.get(2) {
+                    Some(name) => normalize_identifier(name.as_str()),
+                    None => default_index_name(&table, &raw_keys),
+                };
+                let method = using_re
+                    .captures(rest)
+                    .map(|m| m[1].to_lowercase())
+                    .unwrap_or_else(|| "btree".to_string());
+                let predicate = where_re.captures(tail).map(|p| collapse_whitespace(&p[1]).to_lowercase());
+
+                IndexInfo {
+                    name,
+                    schema,
+                    table,
+                    unique: cap.get(1).is_some(),
+                    method,
+                    keys: raw_keys.iter().map(|key| normalize_index_key(key)).collect(),
+                    predicate,
+                    definition,
+                }
+            })
+            .collect()
+    }
+
+    /// Resolve FK targets and INHERITS parents to qualified table names and
+    /// fill `depends_on`
+    ///
+    /// Qualified targets match on `schema.table`. Unqualified targets match a
+    /// table in the default schema, falling back to the bare name when exactly
+    /// one table with that name is defined in another schema. FKs written as
+    /// `REFERENCES table` (no columns) point at the target's primary key when
+    /// it has as many columns as the foreign key.
+    fn resolve_dependencies(tables: &mut [TableInfo]) {
+        let defined: HashSet<String> = tables.iter().map(|t| t.qualified_name()).collect();
+        let primary_keys: HashMap<String, Vec<String>> = tables
+            .iter()
+            .filter_map(|t| Some((t.qualified_name(), t.primary_key.clone()?)))
+            .collect();
+        let mut by_bare_name: HashMap<String, Vec<Option<String>>> = HashMap::new();
+        for table in tables.iter() {
+            by_bare_name
+                .entry(table.name.clone())
+                .or_default()
+                .push(table.schema.clone());
+        }
+
+        for table in tables.iter_mut() {
+            for fk in &mut table.foreign_keys {
+                if fk.to_schema.is_none() && !defined.contains(&fk.to_table) {
+                    if let Some(schemas) = by_bare_name.get(&fk.to_table) {
+                        if schemas.len() == 1 {
+                            fk.to_schema = schemas[0].clone();
+                        }
+                    }
+                }
+
+                if fk.to_columns.is_empty() {
+                    let target = qualified_table_name(fk.to_schema.as_deref(), &fk.to_table);
+                    if let Some(pk) = primary_keys.get(&target).filter(|pk| pk.len() == fk.from_columns.len()) {
+                        fk.to_columns = pk.clone();
+                    }
+                }
+            }
+
+            for column in &mut table.columns {
+                if let Some(refs) = column.references.as_mut().filter(|r| r.column.is_empty()) {
+                    if let Some(fk) = table.foreign_keys.iter().find(|fk| fk.referencing_column() == Some(column.name.as_str())) {
+                        refs.column = fk.referenced_column().unwrap_or_default().to_string();
+                    }
+                }
+            }
+
+            for parent in &mut table.inherits {
+                if !defined.contains(parent.as_str()) {
+                    if let Some([schema]) = by_bare_name.get(parent.as_str()).map(Vec::as_slice) {
+                        *parent = qualified_table_name(schema.as_deref(), parent);
+                    }
+                }
+            }
+
+            // Extract tables this table depends on; children are created after their parents
+            table.depends_on = table
+                .foreign_keys
+                .iter()
+                .map(|fk| qualified_table_name(fk.to_schema.as_deref(), &fk.to_table))
+                .chain(table.inherits.iter().cloned())
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+        }
+    }
+
     /// Normalize SQL by removing comments
     fn normalize_sql(sql: &str) -> String {
         // Remove single-line comments
@@ -156,11 +670,24 @@ impl DependencyAnalyzer {
         sql.to_string()
     }
 
-    /// Parse table body to extract columns and foreign keys
-    fn parse_table_body(body: &str, _table_name: &str) -> (Vec<ColumnInfo>, Vec<ForeignKeyDependency>, Option<Vec<String>>) {
+    /// Parse table body to extract columns, foreign keys, primary key and CHECK constraints
+    #[allow(clippy::type_complexity)]
+    fn parse_table_body(
+        body: &str,
+        _table_name: &str,
+    ) -> (Vec<ColumnInfo>, Vec<ForeignKeyDependency>, Option<Vec<String>>, Vec<CheckConstraint>) {
         let mut columns = Vec::new();
         let mut foreign_keys = Vec::new();
         let mut primary_key: Option<Vec<String>> = None;
+        // (declared name, column, expression) in declaration order
+        let mut declared_checks: Vec<(Option<String>, Option<String>, String)> = Vec::new();
+        let constraint_name_re =
+            regex::Regex::new(&format!(r"(?i)\bCONSTRAINT\s+({})\s+CHECK\b", IDENTIFIER)).unwrap();
+        let check_name = |part: &str| {
+            constraint_name_re
+                .captures(part)
+                .map(|cap| normalize_identifier(&cap[1]))
+        };
 
         // Split by comma, but handle nested parentheses
         let parts = Self::split_table_body(body);
@@ -191,6 +718,9 @@ impl DependencyAnalyzer {
 
             // Check for CHECK constraint at table level
             if part_upper.starts_with("CHECK") || part_upper.starts_with("CONSTRAINT") {
+                if let Some(expression) = Self::parse_inline_check(part) {
+                    declared_checks.push((check_name(part), None, expression));
+                }
                 continue;
             }
 
@@ -206,13 +736,18 @@ impl DependencyAnalyzer {
                     primary_key = Some(vec![col.name.clone()]);
                 }
 
+                if let Some(expression) = &col.check_constraint {
+                    declared_checks.push((check_name(part), Some(col.name.clone()), expression.clone()));
+                }
+
                 // Check for inline REFERENCES
                 if let Some(ref refs) = col.references {
                     foreign_keys.push(ForeignKeyDependency {
                         from_table: _table_name.to_string(),
-                        from_column: col.name.clone(),
+                        from_columns: vec![col.name.clone()],
+                        to_schema: refs.schema.clone(),
                         to_table: refs.table.clone(),
-                        to_column: refs.column.clone(),
+                        to_columns: if refs.column.is_empty() { Vec::new() } else { vec![refs.column.clone()] },
                         on_delete: refs.on_delete.clone(),
                         on_update: refs.on_update.clone(),
                     });
@@ -222,7 +757,8 @@ impl DependencyAnalyzer {
             }
         }
 
-        (columns, foreign_keys, primary_key)
+        let checks = name_check_constraints(_table_name, &columns, declared_checks);
+        (columns, foreign_keys, primary_key, checks)
     }
 
     /// Split table body by commas, handling nested parentheses
@@ -230,9 +766,17 @@ impl DependencyAnalyzer {
         let mut parts = Vec::new();
         let mut current = String::new();
         let mut paren_depth = 0;
+        let mut in_quotes = false;
 
         for ch in body.chars() {
             match ch {
+                '"' => {
+                    in_quotes = !in_quotes;
+                    current.push(ch);
+                }
+                _ if in_quotes => {
+                    current.push(ch);
+                }
                 '(' => {
                     paren_depth += 1;
                     current.push(ch);
@@ -264,26 +808,34 @@ impl DependencyAnalyzer {
         re.captures(part).map(|cap| {
             cap[1]
                 .split(',')
-                .map(|s| s.trim().to_lowercase())
+                .map(|s| normalize_identifier(s.trim()))
                 .collect()
         })
     }
 
-    /// Parse table-level FOREIGN KEY constraint
+    /// Parse table-level FOREIGN KEY constraint, including composite keys
     fn parse_table_level_foreign_key(part: &str, table_name: &str) -> Option<ForeignKeyDependency> {
-        let re = regex::Regex::new(
-            r"(?is)FOREIGN\s+KEY\s*\(\s*(\w+)\s*\)\s*REFERENCES\s+(\w+)\s*\(\s*(\w+)\s*\)(.*)"
-        ).unwrap();
+        let re = regex::Regex::new(&format!(
+            r"(?is)FOREIGN\s+KEY\s*\(\s*({list})\s*\)\s*REFERENCES\s+{qualified}(?:\s*\(\s*({list})\s*\))?(.*)",
+            list = format!(r"{ident}(?:\s*,\s*{ident})*", ident = IDENTIFIER),
+            qualified = QUALIFIED_NAME
+        )).unwrap();
+        let ident_re = regex::Regex::new(IDENTIFIER).unwrap();
+        let columns = |list: &str| -> Vec<String> {
+            ident_re.find_iter(list).map(|m| normalize_identifier(m.as_str())).collect()
+        };
 
         re.captures(part).map(|cap| {
             let on_delete = Self::extract_on_action(&cap[4], "DELETE");
             let on_update = Self::extract_on_action(&cap[4], "UPDATE");
+            let (to_schema, to_table) = split_qualified_name(&cap[2]);
 
             ForeignKeyDependency {
                 from_table: table_name.to_string(),
-                from_column: cap[1].to_lowercase(),
-                to_table: cap[2].to_lowercase(),
-                to_column: cap[3].to_lowercase(),
+                from_columns: columns(&cap[1]),
+                to_schema,
+                to_table,
+                to_columns: cap.get(3).map(|m| columns(m.as_str())).unwrap_or_default(),
                 on_delete,
                 on_update,
             }
@@ -293,13 +845,16 @@ impl DependencyAnalyzer {
     /// Parse a column definition
     fn parse_column(part: &str) -> Option<ColumnInfo> {
         // Column definition pattern: name type [constraints...]
-        let re = regex::Regex::new(
-            r"(?i)^(\w+)\s+(\w+(?:\s*\([^)]+\))?(?:\s*\[\s*\])?)"
-        ).unwrap();
+        let re = regex::Regex::new(&format!(
+            r"(?i)^({})\s+(\w+(?:\s*\([^)]+\))?(?:\s*\[\s*\d*\s*\])*)",
+            IDENTIFIER
+        )).unwrap();
 
         let caps = re.captures(part)?;
-        let name = caps[1].to_lowercase();
+        let name = normalize_identifier(&caps[1]);
         let data_type = caps[2].to_uppercase();
+        let (character_maximum_length, numeric_precision, numeric_scale) =
+            Self::parse_type_modifiers(&data_type);
 
         let part_upper = part.to_uppercase();
 
@@ -309,59 +864,146 @@ impl DependencyAnalyzer {
         // Check for PRIMARY KEY
         let is_primary_key = part_upper.contains("PRIMARY KEY");
 
-        // Check for DEFAULT
-        let has_default = part_upper.contains("DEFAULT") || part_upper.contains("SERIAL");
+        // Check for DEFAULT (not the `ON DELETE SET DEFAULT` referential action)
+        let set_default_re = regex::Regex::new(r"\bSET\s+DEFAULT\b").unwrap();
+        let default_re = regex::Regex::new(r"\bDEFAULT\b").unwrap();
+        let has_default = default_re.is_match(&set_default_re.replace_all(&part_upper, ""))
+            || part_upper.contains("SERIAL");
 
         // Check for REFERENCES (inline foreign key)
         let references = Self::parse_inline_reference(part);
 
+        // Check for inline CHECK constraint
+        let check_constraint = Self::parse_inline_check(part);
+
         Some(ColumnInfo {
             name,
             data_type,
+            character_maximum_length,
+            numeric_precision,
+            numeric_scale,
             is_nullable,
             is_primary_key,
             has_default,
             references,
+            check_constraint,
+            definition: part.trim().to_string(),
         })
     }
 
+    /// Extract length/precision/scale from a type like VARCHAR(100) or NUMERIC(10,2)
+    ///
+    /// Returns (character_maximum_length, numeric_precision, numeric_scale).
+    /// Array types are left alone, matching information_schema which reports them as ARRAY.
+    pub(super) fn parse_type_modifiers(data_type: &str) -> (Option<i32>, Option<i32>, Option<i32>) {
+        let re = regex::Regex::new(r"^(\w+)\s*\(\s*(\d+)\s*(?:,\s*(\d+)\s*)?\)$").unwrap();
+
+        let caps = match re.captures(data_type.trim()) {
+            Some(caps) => caps,
+            None => return (None, None, None),
+        };
+
+        let first: Option<i32> = caps[2].parse().ok();
+        let second: Option<i32> = caps.get(3).and_then(|m| m.as_str().parse().ok());
+
+        match &caps[1] {
+            "VARCHAR" | "CHAR" | "CHARACTER" | "BPCHAR" => (first, None, None),
+            "NUMERIC" | "DECIMAL" => (None, first, Some(second.unwrap_or(0))),
+            _ => (None, None, None),
+        }
+    }
+
     /// Parse inline REFERENCES constraint
+    ///
+    /// The column is optional (`REFERENCES users`); it is left empty here and
+    /// resolved to the target's primary key once all tables are known.
     fn parse_inline_reference(part: &str) -> Option<ColumnReference> {
-        let re = regex::Regex::new(
-            r"(?is)REFERENCES\s+(\w+)\s*\(\s*(\w+)\s*\)(.*)"
-        ).unwrap();
+        let re = regex::Regex::new(&format!(
+            r"(?is)REFERENCES\s+{}(?:\s*\(\s*({})\s*\))?(.*)",
+            QUALIFIED_NAME, IDENTIFIER
+        )).unwrap();
 
         re.captures(part).map(|cap| {
             let suffix = &cap[3];
             let on_delete = Self::extract_on_action(suffix, "DELETE");
             let on_update = Self::extract_on_action(suffix, "UPDATE");
+            let (schema, table) = split_qualified_name(&cap[1]);
 
             ColumnReference {
-                table: cap[1].to_lowercase(),
-                column: cap[2].to_lowercase(),
+                schema,
+                table,
+                column: cap.get(2).map(|m| normalize_identifier(m.as_str())).unwrap_or_default(),
                 on_delete,
                 on_update,
             }
         })
     }
 
+    /// Parse inline CHECK constraint, returning the expression inside the parentheses
+    fn parse_inline_check(part: &str) -> Option<String> {
+        let re = regex::Regex::new(r"(?i)\bCHECK\s*\(").unwrap();
+        let start = re.find(part)?.end();
+
+        let mut depth = 1;
+        let mut in_quotes = false;
+        for (i, c) in part[start..].char_indices() {
+            match c {
+                '\'' => in_quotes = !in_quotes,
+                '(' if !in_quotes => depth += 1,
+                ')' if !in_quotes => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(part[start..start + i].trim().to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
     /// Extract ON DELETE/ON UPDATE action
+    ///
+    /// Whitespace is normalized, and the column list of `SET NULL (col, ...)` /
+    /// `SET DEFAULT (col, ...)` (PostgreSQL 15+) is kept, e.g. "SET NULL (tenant_id)".
     fn extract_on_action(text: &str, action_type: &str) -> Option<String> {
-        let pattern = format!(r"(?i)ON\s+{}\s+(CASCADE|RESTRICT|SET\s+NULL|SET\s+DEFAULT|NO\s+ACTION)", action_type);
+        // String literals in trailing clauses (DEFAULT 'on delete ...') are not actions
+        let literal_re = regex::Regex::new(r"'(?:[^']|'')*'").unwrap();
+        let text = literal_re.replace_all(text, "''");
+
+        let pattern = format!(
+            r"(?i)\bON\s+{}\s+(CASCADE|RESTRICT|NO\s+ACTION|SET\s+(?:NULL|DEFAULT))\b(?:\s*\(([^)]*)\))?",
+            action_type
+        );
         let re = regex::Regex::new(&pattern).unwrap();
-        re.captures(text).map(|cap| cap[1].to_uppercase())
+        re.captures(&text).map(|cap| {
+            let action = cap[1].split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+            match cap.get(2) {
+                // Only the SET actions take a column list
+                Some(columns) if action.starts_with("SET") => {
+                    let columns: Vec<String> = columns
+                        .as_str()
+                        .split(',')
+                        .map(normalize_identifier)
+                        .collect();
+                    format!("{} ({})", action, columns.join(", "))
+                }
+                _ => action,
+            }
+        })
     }
 
     /// Build dependency graph: table -> tables it depends on
     fn build_dependency_graph(tables: &[TableInfo]) -> HashMap<String, Vec<String>> {
         tables
             .iter()
-            .map(|t| (t.name.clone(), t.depends_on.clone()))
+            .map(|t| (t.qualified_name(), t.depends_on.clone()))
             .collect()
     }
 
     /// Build reverse dependency graph: table -> tables that depend on it
-    fn build_reverse_dependencies(graph: &HashMap<String, Vec<String>>) -> HashMap<String, Vec<String>> {
+    pub(crate) fn build_reverse_dependencies(graph: &HashMap<String, Vec<String>>) -> HashMap<String, Vec<String>> {
         let mut reverse: HashMap<String, Vec<String>> = HashMap::new();
 
         // Initialize all tables
@@ -379,103 +1021,112 @@ impl DependencyAnalyzer {
         reverse
     }
 
-    /// Detect circular dependencies using DFS
+    /// Detect circular dependencies using an iterative DFS
+    ///
+    /// Iterative so that long FK chains cannot overflow the stack; the current
+    /// path is indexed by node so each back-edge check is O(1).
     fn detect_circular_dependencies(graph: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
         let mut cycles = Vec::new();
-        let mut visited = HashSet::new();
-        let mut rec_stack = Vec::new();
+        let mut visited: HashSet<&str> = HashSet::new();
+        // Nodes on the current DFS path -> their position in `path`
+        let mut on_path: HashMap<&str, usize> = HashMap::new();
+        let mut path: Vec<&str> = Vec::new();
+        // (node, index of the next neighbor to visit)
+        let mut frames: Vec<(&str, usize)> = Vec::new();
 
         for table in graph.keys() {
-            if !visited.contains(table) {
-                Self::dfs_find_cycles(table, graph, &mut visited, &mut rec_stack, &mut cycles);
+            if !visited.insert(table.as_str()) {
+                continue;
             }
-        }
-
-        cycles
-    }
+            on_path.insert(table, path.len());
+            path.push(table);
+            frames.push((table, 0));
+
+            while let Some(&(node, next)) = frames.last() {
+                let neighbors = graph.get(node).map(Vec::as_slice).unwrap_or(&[]);
+
+                let Some(neighbor) = neighbors.get(next) else {
+                    on_path.remove(node);
+                    path.pop();
+                    frames.pop();
+                    continue;
+                };
+                if let Some(frame) = frames.last_mut() {
+                    frame.1 += 1;
+                }
 
-    /// DFS helper to find cycles
-    fn dfs_find_cycles(
-        node: &str,
-        graph: &HashMap<String, Vec<String>>,
-        visited: &mut HashSet<String>,
-        rec_stack: &mut Vec<String>,
-        cycles: &mut Vec<Vec<String>>,
-    ) {
-        visited.insert(node.to_string());
-        rec_stack.push(node.to_string());
-
-        if let Some(neighbors) = graph.get(node) {
-            for neighbor in neighbors {
-                if !visited.contains(neighbor) {
-                    Self::dfs_find_cycles(neighbor, graph, visited, rec_stack, cycles);
-                } else if rec_stack.contains(neighbor) {
+                if visited.insert(neighbor.as_str()) {
+                    on_path.insert(neighbor, path.len());
+                    path.push(neighbor);
+                    frames.push((neighbor, 0));
+                } else if let Some(&cycle_start) = on_path.get(neighbor.as_str()) {
                     // Found a cycle
-                    let cycle_start = rec_stack.iter().position(|x| x == neighbor).unwrap();
-                    let cycle: Vec<String> = rec_stack[cycle_start..].to_vec();
-                    cycles.push(cycle);
+                    cycles.push(normalize_cycle(&path[cycle_start..]));
                 }
             }
         }
 
-        rec_stack.pop();
+        // The same cycle is found once per back-edge into it (e.g. from
+        // duplicate FKs); keep each distinct cycle once, in a stable order
+        cycles.sort();
+        cycles.dedup();
+        cycles
+    }
+
+    /// Each cycle of a dependency graph as a closed path (`a -> b -> c -> a`)
+    ///
+    /// An edge `a -> b` means `a` depends on `b`. Used to name the cycle
+    /// when a creation order cannot be found.
+    pub(crate) fn describe_cycles(graph: &HashMap<String, Vec<String>>) -> Vec<String> {
+        format_cycles(&Self::detect_circular_dependencies(graph))
     }
 
     /// Topological sort to get creation order
     fn topological_sort(graph: &HashMap<String, Vec<String>>) -> Result<Vec<String>, String> {
-        let mut in_degree: HashMap<String, usize> = HashMap::new();
-        let mut all_nodes: HashSet<String> = HashSet::new();
+        let mut in_degree: HashMap<&str, usize> = HashMap::new();
 
-        // Collect all nodes and compute in-degrees
+        // Compute in-degrees, making sure referenced tables have an entry
         for (node, deps) in graph {
-            all_nodes.insert(node.clone());
-            in_degree.entry(node.clone()).or_insert(0);
+            *in_degree.entry(node.as_str()).or_insert(0) += deps.len();
             for dep in deps {
-                all_nodes.insert(dep.clone());
-                *in_degree.entry(node.clone()).or_insert(0) += 1;
+                in_degree.entry(dep.as_str()).or_insert(0);
             }
         }
 
-        // Ensure all referenced tables have an entry
-        for node in &all_nodes {
-            in_degree.entry(node.clone()).or_insert(0);
-        }
-
         // Build reverse adjacency (who depends on whom)
-        let mut adj: HashMap<String, Vec<String>> = HashMap::new();
+        let mut adj: HashMap<&str, Vec<&str>> = HashMap::new();
         for (node, deps) in graph {
             for dep in deps {
-                adj.entry(dep.clone()).or_default().push(node.clone());
+                adj.entry(dep.as_str()).or_default().push(node.as_str());
             }
         }
 
-        // Kahn's algorithm
-        let mut queue: Vec<String> = in_degree
+        // Kahn's algorithm; the ordered set keeps the output deterministic
+        // without re-sorting the queue on every push
+        let mut queue: BTreeSet<&str> = in_degree
             .iter()
             .filter(|(_, &deg)| deg == 0)
-            .map(|(node, _)| node.clone())
+            .map(|(&node, _)| node)
             .collect();
-        queue.sort(); // Deterministic order
 
-        let mut result = Vec::new();
+        let mut result = Vec::with_capacity(in_degree.len());
 
-        while let Some(node) = queue.pop() {
-            result.push(node.clone());
+        while let Some(node) = queue.pop_last() {
+            result.push(node.to_string());
 
-            if let Some(dependents) = adj.get(&node) {
-                for dependent in dependents {
+            if let Some(dependents) = adj.get(node) {
+                for &dependent in dependents {
                     if let Some(deg) = in_degree.get_mut(dependent) {
                         *deg -= 1;
                         if *deg == 0 {
-                            queue.push(dependent.clone());
-                            queue.sort();
+                            queue.insert(dependent);
                         }
                     }
                 }
             }
         }
 
-        if result.len() != all_nodes.len() {
+        if result.len() != in_degree.len() {
             return Err("Circular dependency detected - cannot determine creation order".to_string());
         }
 
@@ -485,6 +1136,11 @@ impl DependencyAnalyzer {
     /// Format dependency analysis as a readable string
     pub fn format_analysis(analysis: &DependencyAnalysis) -> String {
         let mut output = String::new();
+        // `col` for a single column, `(a, b)` for a composite key
+        let format_columns = |columns: &[String]| match columns {
+            [column] => column.clone(),
+            _ => format!("({})", columns.join(", ")),
+        };
 
         output.push_str("═══════════════════════════════════════════════════════════════\n");
         output.push_str("                    TABLE DEPENDENCY ANALYSIS\n");
@@ -552,13 +1208,50 @@ impl DependencyAnalyzer {
                     };
                     output.push_str(&format!(
                         "    • {}.{} → {}.{}{}\n",
-                        fk.from_table, fk.from_column, fk.to_table, fk.to_column, constraint_str
+                        fk.from_table,
+                        format_columns(&fk.from_columns),
+                        fk.to_table,
+                        format_columns(&fk.to_columns),
+                        constraint_str
                     ));
                 }
             }
         }
         output.push('\n');
 
+        // External references (allow-listed)
+        if !analysis.external_references.is_empty() {
+            output.push_str("EXTERNAL REFERENCES (allow-listed):\n");
+            output.push_str("───────────────────────────────────────────────────────────────\n");
+            for fk in &analysis.external_references {
+                output.push_str(&format!(
+                    "  {}.{} → {}.{} (external)\n",
+                    fk.from_table,
+                    format_columns(&fk.from_columns),
+                    fk.to_table,
+                    format_columns(&fk.to_columns)
+                ));
+            }
+            output.push('\n');
+        }
+
+        // Missing FK targets warning
+        if !analysis.missing_targets.is_empty() {
+            output.push_str("⚠️  MISSING FOREIGN KEY TARGETS:\n");
+            output.push_str("───────────────────────────────────────────────────────────────\n");
+            for fk in &analysis.missing_targets {
+                output.push_str(&format!(
+                    "  {}.{} → {}.{} (not defined; add to {} if external)\n",
+                    fk.from_table,
+                    format_columns(&fk.from_columns),
+                    fk.to_table,
+                    format_columns(&fk.to_columns),
+                    EXTERNAL_TABLES_FILE
+                ));
+            }
+            output.push('\n');
+        }
+
         // Circular dependencies warning
         if !analysis.circular_dependencies.is_empty() {
             output.push_str("⚠️  CIRCULAR DEPENDENCIES DETECTED:\n");
@@ -575,6 +1268,197 @@ impl DependencyAnalyzer {
     }
 }
 
+/// Build the dependency key for a table: `schema.table`, or the bare name
+/// when the schema is omitted or is the default schema
+pub fn qualified_table_name(schema: Option<&str>, name: &str) -> String {
+    match schema {
+        Some(schema) if schema != DEFAULT_SCHEMA => format!("{}.{}", schema, name),
+        _ => name.to_string(),
+    }
+}
+
+/// Normalize an identifier the way PostgreSQL does: quoted identifiers keep
+/// their case (with quotes stripped), bare identifiers are folded to lowercase
+pub(super) fn normalize_identifier(raw: &str) -> String {
+    let raw = raw.trim();
+    if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+        raw[1..raw.len() - 1].replace("\"\"", "\"")
+    } else {
+        raw.to_lowercase()
+    }
+}
+
+/// Give unnamed CHECK constraints the name PostgreSQL would choose
+///
+/// That is `table_column_check` when the expression references exactly one
+/// column (`table_check` otherwise), with a counter appended when the name is
+/// already taken.
+pub(super) fn name_check_constraints(
+    table: &str,
+    columns: &[ColumnInfo],
+    declared: Vec<(Option<String>, Option<String>, String)>,
+) -> Vec<CheckConstraint> {
+    let literal_re = regex::Regex::new(r"'(?:[^']|'')*'").unwrap();
+    let ident_re = regex::Regex::new(IDENTIFIER).unwrap();
+    let mut used: HashSet<String> = declared.iter().filter_map(|(name, _, _)| name.clone()).collect();
+
+    declared
+        .into_iter()
+        .map(|(name, column, expression)| {
+            let name = name.unwrap_or_else(|| {
+                let without_literals = literal_re.replace_all(&expression, "");
+                let referenced: BTreeSet<String> = ident_re
+                    .find_iter(&without_literals)
+                    .map(|m| normalize_identifier(m.as_str()))
+                    .filter(|ident| columns.iter().any(|c| &c.name == ident))
+                    .collect();
+                let base = match referenced.iter().next() {
+                    Some(column) if referenced.len() == 1 => format!("{}_{}_check", table, column),
+                    _ => format!("{}_check", table),
+                };
+
+                let mut candidate = base.clone();
+                let mut suffix = 0;
+                while used.contains(&candidate) {
+                    suffix += 1;
+                    candidate = format!("{}{}", base, suffix);
+                }
+                used.insert(candidate.clone());
+                candidate
+            });
+
+            CheckConstraint { name, column, expression }
+        })
+        .collect()
+}
+
+/// Split the key list of a CREATE INDEX statement
+///
+/// `rest` is the statement after the table name (`USING ... (keys) ...`).
+/// Returns the keys as written and whatever follows the closing parenthesis.
+fn split_index_keys(rest: &str) -> (Vec<String>, &str) {
+    let mut keys = Vec::new();
+    let Some(open) = rest.find('(') else {
+        return (keys, "");
+    };
+
+    let mut depth = 0;
+    let mut current = String::new();
+    for (i, c) in rest[open + 1..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                keys.push(current);
+                return (keys, &rest[open + 1 + i + 1..]);
+            }
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                keys.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    keys.push(current);
+    (keys, "")
+}
+
+/// Key as compared between indexes: a leading column name is normalized like
+/// any identifier, the rest (expressions, ASC/DESC, opclasses) is lowercased
+/// with its whitespace collapsed
+fn normalize_index_key(key: &str) -> String {
+    let ident_re = regex::Regex::new(&format!(r"^\s*({})(\s.*)?$", IDENTIFIER)).unwrap();
+    match ident_re.captures(key) {
+        Some(cap) => {
+            let column = normalize_identifier(&cap[1]);
+            match cap.get(2).map(|m| collapse_whitespace(m.as_str()).to_lowercase()) {
+                Some(modifiers) if !modifiers.is_empty() => format!("{} {}", column, modifiers),
+                _ => column,
+            }
+        }
+        None => collapse_whitespace(key).to_lowercase(),
+    }
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Name PostgreSQL gives an unnamed index: `table_col1_col2_idx`
+///
+/// Expression keys are named `expr`.
+fn default_index_name(table: &str, keys: &[String]) -> String {
+    let ident_re = regex::Regex::new(&format!(r"^\s*({})(?:\s|$)", IDENTIFIER)).unwrap();
+    let columns: Vec<String> = keys
+        .iter()
+        .map(|key| match ident_re.captures(key) {
+            Some(cap) => normalize_identifier(&cap[1]),
+            None => "expr".to_string(),
+        })
+        .collect();
+
+    format!("{}_{}_idx", table, columns.join("_"))
+}
+
+/// Rotate a cycle so it starts at its lexicographically smallest member
+///
+/// `a -> b -> c`, `b -> c -> a` and `c -> a -> b` are the same cycle and
+/// all normalize to `[a, b, c]`.
+fn normalize_cycle(cycle: &[&str]) -> Vec<String> {
+    let start = cycle
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, name)| **name)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    cycle[start..]
+        .iter()
+        .chain(&cycle[..start])
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Closed, sorted paths of normalized cycles
+fn format_cycles(cycles: &[Vec<String>]) -> Vec<String> {
+    let mut paths: Vec<String> = cycles
+        .iter()
+        .filter_map(|cycle| {
+            let first = cycle.first()?;
+            Some(format!("{} -> {}", cycle.join(" -> "), first))
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Split a possibly schema-qualified name into (schema, table)
+pub(super) fn split_qualified_name(raw: &str) -> (Option<String>, String) {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for ch in raw.chars() {
+        match ch {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(ch);
+            }
+            '.' if !in_quotes => parts.push(std::mem::take(&mut current)),
+            c if c.is_whitespace() && !in_quotes => {}
+            c => current.push(c),
+        }
+    }
+    parts.push(current);
+
+    let mut parts = parts.into_iter().map(|p| normalize_identifier(&p));
+    match (parts.next(), parts.next()) {
+        (Some(schema), Some(table)) => (Some(schema), table),
+        (Some(table), None) => (None, table),
+        _ => (None, String::new()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -644,4 +1528,624 @@ mod tests {
         assert!(tags_pos < todo_tags_pos);
         assert!(todos_pos < todo_tags_pos);
     }
+
+    #[test]
+    fn test_external_table_not_missing_target() {
+        let sql = r#"
+            CREATE TABLE invoices (
+                invoice_id SERIAL PRIMARY KEY,
+                account_id INTEGER REFERENCES accounts(account_id),
+                owner_id INTEGER REFERENCES owners(owner_id)
+            );
+        "#;
+
+        // Without an allow-list both targets are missing
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        assert_eq!(analysis.missing_targets.len(), 2);
+
+        let analysis =
+            DependencyAnalyzer::analyze_sql_with_external(sql, &["accounts".to_string()]).unwrap();
+        assert_eq!(analysis.missing_targets.len(), 1);
+        assert_eq!(analysis.missing_targets[0].to_table, "owners");
+        assert_eq!(analysis.external_references.len(), 1);
+        assert_eq!(analysis.external_references[0].to_table, "accounts");
+
+        // Still recorded as a dependency
+        assert!(analysis.tables[0].depends_on.contains(&"accounts".to_string()));
+    }
+
+    #[test]
+    fn test_schema_qualified_names() {
+        let sql = r#"
+            CREATE TABLE inventory.products (
+                product_id SERIAL PRIMARY KEY
+            );
+
+            CREATE TABLE auth."users" (
+                user_id SERIAL PRIMARY KEY
+            );
+
+            CREATE TABLE public.orders (
+                order_id SERIAL PRIMARY KEY,
+                product_id INTEGER REFERENCES inventory.products(product_id),
+                user_id INTEGER,
+                FOREIGN KEY (user_id) REFERENCES auth."users"(user_id)
+            );
+
+            CREATE TABLE order_notes (
+                order_id INTEGER REFERENCES orders(order_id)
+            );
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        assert_eq!(analysis.tables.len(), 4);
+
+        let products = analysis.tables.iter().find(|t| t.name == "products").unwrap();
+        assert_eq!(products.schema, Some("inventory".to_string()));
+        assert_eq!(products.qualified_name(), "inventory.products");
+
+        let orders = analysis.tables.iter().find(|t| t.name == "orders").unwrap();
+        assert_eq!(orders.schema, Some("public".to_string()));
+        assert_eq!(orders.qualified_name(), "orders");
+
+        let product_ref = orders.columns[1].references.as_ref().unwrap();
+        assert_eq!(product_ref.schema, Some("inventory".to_string()));
+        assert_eq!(product_ref.table, "products");
+
+        let mut deps = orders.depends_on.clone();
+        deps.sort();
+        assert_eq!(deps, vec!["auth.users", "inventory.products"]);
+
+        // Unqualified reference resolves to public.orders
+        let notes = analysis.tables.iter().find(|t| t.name == "order_notes").unwrap();
+        assert_eq!(notes.depends_on, vec!["orders"]);
+
+        assert!(analysis.missing_targets.is_empty());
+
+        let pos = |name: &str| analysis.creation_order.iter().position(|x| x == name).unwrap();
+        assert!(pos("inventory.products") < pos("orders"));
+        assert!(pos("auth.users") < pos("orders"));
+        assert!(pos("orders") < pos("order_notes"));
+    }
+
+    #[test]
+    fn test_unqualified_reference_falls_back_to_bare_name() {
+        let sql = r#"
+            CREATE TABLE inventory.products (product_id SERIAL PRIMARY KEY);
+            CREATE TABLE stock (
+                product_id INTEGER REFERENCES products(product_id)
+            );
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let stock = analysis.tables.iter().find(|t| t.name == "stock").unwrap();
+        assert_eq!(stock.depends_on, vec!["inventory.products"]);
+        assert!(analysis.missing_targets.is_empty());
+    }
+
+    #[test]
+    fn test_reference_without_column_uses_primary_key() {
+        let sql = r#"
+            CREATE TABLE posts (
+                post_id SERIAL PRIMARY KEY,
+                user_id INT REFERENCES users ON DELETE CASCADE,
+                editor_id INT,
+                FOREIGN KEY (editor_id) REFERENCES users
+            );
+            CREATE TABLE users (id SERIAL PRIMARY KEY);
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let posts = analysis.tables.iter().find(|t| t.name == "posts").unwrap();
+        assert_eq!(posts.depends_on, vec!["users"]);
+        assert_eq!(posts.foreign_keys.len(), 2);
+        assert!(posts.foreign_keys.iter().all(|fk| fk.to_table == "users" && fk.referenced_column() == Some("id")));
+        assert_eq!(posts.foreign_keys[0].on_delete, Some("CASCADE".to_string()));
+
+        let user_id = posts.columns.iter().find(|c| c.name == "user_id").unwrap();
+        assert_eq!(user_id.references.as_ref().unwrap().column, "id");
+        assert_eq!(analysis.creation_order, vec!["users", "posts"]);
+
+        // Target without a known primary key: dependency is still recorded
+        let analysis = DependencyAnalyzer::analyze_sql("CREATE TABLE posts (user_id INT REFERENCES users);").unwrap();
+        assert_eq!(analysis.tables[0].depends_on, vec!["users"]);
+        assert!(analysis.tables[0].foreign_keys[0].to_columns.is_empty());
+    }
+
+    #[test]
+    fn test_composite_foreign_key() {
+        let sql = r#"
+            CREATE TABLE order_lines (
+                tenant_id INT NOT NULL,
+                order_id INT NOT NULL,
+                line_no INT NOT NULL,
+                PRIMARY KEY (tenant_id, order_id, line_no),
+                FOREIGN KEY (tenant_id, order_id) REFERENCES orders (tenant_id, id) ON DELETE CASCADE
+            );
+            CREATE TABLE orders (tenant_id INT NOT NULL, id INT NOT NULL, PRIMARY KEY (tenant_id, id));
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let lines = analysis.tables.iter().find(|t| t.name == "order_lines").unwrap();
+        assert_eq!(lines.depends_on, vec!["orders"]);
+        assert_eq!(lines.foreign_keys.len(), 1);
+
+        let fk = &lines.foreign_keys[0];
+        assert_eq!(fk.to_table, "orders");
+        assert_eq!(fk.from_columns, vec!["tenant_id", "order_id"]);
+        assert_eq!(fk.to_columns, vec!["tenant_id", "id"]);
+        assert_eq!(fk.on_delete, Some("CASCADE".to_string()));
+        assert_eq!(fk.referencing_column(), None);
+        assert_eq!(analysis.creation_order, vec!["orders", "order_lines"]);
+
+        // Without a column list the target's composite primary key is used
+        let analysis = DependencyAnalyzer::analyze_sql(
+            "CREATE TABLE orders (tenant_id INT, id INT, PRIMARY KEY (tenant_id, id));\n\
+             CREATE TABLE refs (tenant_id INT, order_id INT, FOREIGN KEY (tenant_id, order_id) REFERENCES orders);",
+        )
+        .unwrap();
+        let refs = analysis.tables.iter().find(|t| t.name == "refs").unwrap();
+        assert_eq!(refs.foreign_keys[0].to_columns, vec!["tenant_id", "id"]);
+    }
+
+    #[test]
+    fn test_referential_actions_with_trailing_constraints() {
+        let sql = r#"
+            CREATE TABLE users (id SERIAL PRIMARY KEY);
+            CREATE TABLE posts (
+                id SERIAL PRIMARY KEY,
+                author_id INT REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+                editor_id INT NOT NULL REFERENCES users(id) DEFERRABLE INITIALLY DEFERRED ON UPDATE CASCADE ON DELETE SET DEFAULT,
+                reviewer_id INT REFERENCES users ON UPDATE RESTRICT DEFERRABLE INITIALLY DEFERRED ON DELETE SET NULL DEFAULT 0,
+                owner_id INT REFERENCES users(id) DEFAULT 1 CHECK (owner_id > 0) ON DELETE NO ACTION NOT NULL,
+                label TEXT REFERENCES users(id) DEFAULT 'on delete cascade'
+            );
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let posts = analysis.tables.iter().find(|t| t.name == "posts").unwrap();
+        let column = |name: &str| posts.columns.iter().find(|c| c.name == name).unwrap();
+        let actions = |name: &str| {
+            let refs = column(name).references.clone().unwrap();
+            (refs.on_delete, refs.on_update)
+        };
+
+        assert_eq!(actions("author_id"), (Some("CASCADE".to_string()), None));
+        assert!(!column("author_id").is_nullable);
+
+        assert_eq!(
+            actions("editor_id"),
+            (Some("SET DEFAULT".to_string()), Some("CASCADE".to_string()))
+        );
+        assert!(!column("editor_id").is_nullable);
+        assert!(!column("editor_id").has_default);
+
+        assert_eq!(
+            actions("reviewer_id"),
+            (Some("SET NULL".to_string()), Some("RESTRICT".to_string()))
+        );
+        assert!(column("reviewer_id").has_default);
+        assert_eq!(column("reviewer_id").references.as_ref().unwrap().column, "id");
+
+        assert_eq!(actions("owner_id"), (Some("NO ACTION".to_string()), None));
+        assert!(!column("owner_id").is_nullable);
+        assert_eq!(column("owner_id").check_constraint.as_deref(), Some("owner_id > 0"));
+
+        assert_eq!(actions("label"), (None, None));
+
+        // Table-level FK entries carry the same actions
+        let editor_fk = posts.foreign_keys.iter().find(|fk| fk.referencing_column() == Some("editor_id")).unwrap();
+        assert_eq!(editor_fk.on_delete.as_deref(), Some("SET DEFAULT"));
+        assert_eq!(editor_fk.on_update.as_deref(), Some("CASCADE"));
+    }
+
+    #[test]
+    fn test_on_delete_set_null_column_list() {
+        let sql = r#"
+            CREATE TABLE tenants (id SERIAL PRIMARY KEY);
+            CREATE TABLE projects (id SERIAL PRIMARY KEY);
+            CREATE TABLE tasks (
+                id SERIAL PRIMARY KEY,
+                tenant_id INT NOT NULL,
+                project_id INT,
+                owner_id INT REFERENCES tenants(id) ON DELETE SET  NULL ("Owner_id", tenant_id) ON UPDATE CASCADE,
+                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE SET NULL (tenant_id)
+            );
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let tasks = analysis.tables.iter().find(|t| t.name == "tasks").unwrap();
+
+        let project_fk = tasks.foreign_keys.iter().find(|fk| fk.referencing_column() == Some("project_id")).unwrap();
+        assert_eq!(project_fk.on_delete, Some("SET NULL (tenant_id)".to_string()));
+
+        let owner_fk = tasks.foreign_keys.iter().find(|fk| fk.referencing_column() == Some("owner_id")).unwrap();
+        assert_eq!(owner_fk.on_delete, Some("SET NULL (Owner_id, tenant_id)".to_string()));
+        assert_eq!(owner_fk.on_update, Some("CASCADE".to_string()));
+
+        // Plain actions are unchanged
+        let analysis = DependencyAnalyzer::analyze_sql(
+            "CREATE TABLE a (id INT PRIMARY KEY, b_id INT REFERENCES b(id) ON DELETE NO   ACTION);",
+        )
+        .unwrap();
+        assert_eq!(analysis.tables[0].foreign_keys[0].on_delete, Some("NO ACTION".to_string()));
+    }
+
+    #[test]
+    fn test_inherits_parent_is_a_dependency() {
+        let sql = r#"
+            CREATE TABLE audit_events (
+                actor TEXT,
+                tenant_id INT REFERENCES tenants(id)
+            ) INHERITS (events, billing.charges);
+            CREATE TABLE events (id SERIAL PRIMARY KEY, created_at TIMESTAMPTZ);
+            CREATE TABLE billing.charges (amount NUMERIC);
+            CREATE TABLE tenants (id SERIAL PRIMARY KEY);
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let child = analysis.tables.iter().find(|t| t.name == "audit_events").unwrap();
+        assert_eq!(child.inherits, vec!["events".to_string(), "billing.charges".to_string()]);
+        assert!(!child.is_partition);
+        assert_eq!(child.columns.len(), 2);
+
+        let mut deps = child.depends_on.clone();
+        deps.sort();
+        assert_eq!(deps, vec!["billing.charges", "events", "tenants"]);
+
+        let pos = |name: &str| analysis.creation_order.iter().position(|t| t == name).unwrap();
+        assert!(pos("events") < pos("audit_events"));
+        assert!(pos("billing.charges") < pos("audit_events"));
+    }
+
+    #[test]
+    fn test_partition_of_depends_on_parent() {
+        // Partitions before the parent, as files sorted by name would list them
+        let sql = r#"
+            CREATE TABLE measurements_2024 PARTITION OF measurements
+                FOR VALUES FROM ('2024-01-01') TO ('2025-01-01');
+            CREATE TABLE IF NOT EXISTS metrics.measurements_default PARTITION OF measurements DEFAULT;
+            CREATE TABLE measurements (
+                id BIGSERIAL,
+                logdate DATE NOT NULL,
+                PRIMARY KEY (id, logdate)
+            ) PARTITION BY RANGE (logdate);
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let names: Vec<String> = analysis.tables.iter().map(|t| t.qualified_name()).collect();
+        assert_eq!(names, vec!["measurements_2024", "metrics.measurements_default", "measurements"]);
+
+        let parent = &analysis.tables[2];
+        assert!(!parent.is_partition);
+        assert_eq!(parent.columns.len(), 2);
+        assert_eq!(parent.primary_key, Some(vec!["id".to_string(), "logdate".to_string()]));
+
+        for partition in &analysis.tables[..2] {
+            assert!(partition.is_partition);
+            assert!(partition.columns.is_empty());
+            assert_eq!(partition.inherits, vec!["measurements".to_string()]);
+            assert_eq!(partition.depends_on, vec!["measurements".to_string()]);
+        }
+        assert_eq!(analysis.creation_order[0], "measurements");
+    }
+
+    #[test]
+    fn test_extract_indexes() {
+        let sql = r#"
+            CREATE TABLE items (id SERIAL PRIMARY KEY, name TEXT, tenant_id INT);
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_items_name ON public.items (name);
+            CREATE INDEX ON items USING btree (tenant_id, name);
+            CREATE INDEX ON items (lower(name));
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let names: Vec<&str> = analysis.indexes.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["idx_items_name", "items_tenant_id_name_idx", "items_expr_idx"]);
+
+        let named = &analysis.indexes[0];
+        assert!(named.unique);
+        assert_eq!(named.table_name(), "items");
+        assert!(named.definition.ends_with("(name)"), "{}", named.definition);
+        assert!(!analysis.indexes[1].unique);
+    }
+
+    #[test]
+    fn test_quoted_reserved_word_table() {
+        let sql = r#"
+            CREATE TABLE "order" (
+                "id" SERIAL PRIMARY KEY,
+                "Total" NUMERIC(10,2) NOT NULL
+            );
+
+            CREATE TABLE order_items (
+                item_id SERIAL PRIMARY KEY,
+                order_id INTEGER NOT NULL REFERENCES "order"("id") ON DELETE CASCADE
+            );
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        assert_eq!(analysis.tables.len(), 2);
+
+        let order = analysis.tables.iter().find(|t| t.name == "order").unwrap();
+        assert_eq!(order.schema, None);
+        assert_eq!(order.columns.len(), 2);
+        assert_eq!(order.columns[0].name, "id");
+        assert!(order.columns[0].is_primary_key);
+        // Quoted identifiers keep their case
+        assert_eq!(order.columns[1].name, "Total");
+        assert!(!order.columns[1].is_nullable);
+        assert_eq!(order.primary_key, Some(vec!["id".to_string()]));
+
+        let items = analysis.tables.iter().find(|t| t.name == "order_items").unwrap();
+        assert_eq!(items.depends_on, vec!["order"]);
+        assert_eq!(items.foreign_keys[0].referenced_column(), Some("id"));
+        assert_eq!(items.foreign_keys[0].on_delete, Some("CASCADE".to_string()));
+
+        let order_pos = analysis.creation_order.iter().position(|x| x == "order").unwrap();
+        let items_pos = analysis.creation_order.iter().position(|x| x == "order_items").unwrap();
+        assert!(order_pos < items_pos);
+    }
+
+    #[test]
+    fn test_quoted_identifier_with_spaces() {
+        let sql = r#"
+            CREATE TABLE "User Data" (
+                "User Id" INTEGER,
+                "Display, Name" TEXT,
+                PRIMARY KEY ("User Id")
+            );
+
+            CREATE TABLE audit (
+                audit_id SERIAL PRIMARY KEY,
+                user_id INTEGER,
+                FOREIGN KEY (user_id) REFERENCES "User Data"("User Id")
+            );
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        assert_eq!(analysis.tables.len(), 2);
+
+        let user_data = analysis.tables.iter().find(|t| t.name == "User Data").unwrap();
+        let names: Vec<&str> = user_data.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["User Id", "Display, Name"]);
+        assert_eq!(user_data.primary_key, Some(vec!["User Id".to_string()]));
+
+        let audit = analysis.tables.iter().find(|t| t.name == "audit").unwrap();
+        assert_eq!(audit.foreign_keys[0].to_table, "User Data");
+        assert_eq!(audit.foreign_keys[0].referenced_column(), Some("User Id"));
+        assert!(analysis.creation_order.contains(&"User Data".to_string()));
+        assert!(analysis.missing_targets.is_empty());
+    }
+
+    #[test]
+    fn test_parse_type_modifiers() {
+        let sql = r#"
+            CREATE TABLE products (
+                sku VARCHAR(50) NOT NULL,
+                code CHAR(3),
+                price NUMERIC(10,2),
+                weight DECIMAL(8),
+                notes TEXT,
+                tags VARCHAR(20)[]
+            );
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let cols = &analysis.tables[0].columns;
+        let modifiers = |i: usize| {
+            (
+                cols[i].character_maximum_length,
+                cols[i].numeric_precision,
+                cols[i].numeric_scale,
+            )
+        };
+
+        assert_eq!(cols[0].data_type, "VARCHAR(50)");
+        assert_eq!(modifiers(0), (Some(50), None, None));
+        assert_eq!(modifiers(1), (Some(3), None, None));
+        assert_eq!(modifiers(2), (None, Some(10), Some(2)));
+        assert_eq!(modifiers(3), (None, Some(8), Some(0)));
+        assert_eq!(modifiers(4), (None, None, None));
+        assert_eq!(modifiers(5), (None, None, None));
+    }
+
+    #[test]
+    fn test_parse_inline_check() {
+        let sql = r#"
+            CREATE TABLE people (
+                id SERIAL PRIMARY KEY,
+                age INT NOT NULL CHECK (age >= 0),
+                status TEXT CONSTRAINT status_valid CHECK (status IN ('a', 'b')) DEFAULT 'a',
+                email TEXT
+            );
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let cols = &analysis.tables[0].columns;
+
+        assert_eq!(cols[1].check_constraint.as_deref(), Some("age >= 0"));
+        assert_eq!(cols[2].check_constraint.as_deref(), Some("status IN ('a', 'b')"));
+        assert_eq!(cols[3].check_constraint, None);
+    }
+
+    #[test]
+    fn test_check_constraint_names() {
+        let sql = r#"
+            CREATE TABLE products (
+                id SERIAL PRIMARY KEY,
+                price NUMERIC CHECK (price >= 0),
+                status TEXT CONSTRAINT status_valid CHECK (status IN ('a', 'b')),
+                name TEXT,
+                CHECK (price < 1000),
+                CHECK (length(name) > 0 AND price > 0),
+                CONSTRAINT "Name_Set" CHECK (name <> 'price'),
+                CHECK (now() IS NOT NULL)
+            );
+        "#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let checks: Vec<(&str, Option<&str>, &str)> = analysis.tables[0]
+            .checks
+            .iter()
+            .map(|c| (c.name.as_str(), c.column.as_deref(), c.expression.as_str()))
+            .collect();
+
+        assert_eq!(
+            checks,
+            vec![
+                ("products_price_check", Some("price"), "price >= 0"),
+                ("status_valid", Some("status"), "status IN ('a', 'b')"),
+                ("products_price_check1", None, "price < 1000"),
+                ("products_check", None, "length(name) > 0 AND price > 0"),
+                ("Name_Set", None, "name <> 'price'"),
+                ("products_check1", None, "now() IS NOT NULL"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_syntax() {
+        assert!(DependencyAnalyzer::check_syntax(
+            "-- (unbalanced in a comment\nCREATE TABLE t (\n  note TEXT DEFAULT ')',\n  body TEXT DEFAULT $$ ( $$\n);"
+        )
+        .is_none());
+
+        assert_eq!(
+            DependencyAnalyzer::check_syntax("CREATE TABLE t (\n  id INT\n));").map(|(line, _)| line),
+            Some(3)
+        );
+        assert_eq!(
+            DependencyAnalyzer::check_syntax("CREATE TABLE t (\n  name TEXT DEFAULT 'oops\n);").map(|(line, _)| line),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_check_syntax_escape_strings() {
+        assert!(DependencyAnalyzer::check_syntax(r"INSERT INTO users (name) VALUES (E'O\'Reilly');").is_none());
+        assert!(DependencyAnalyzer::check_syntax(r"INSERT INTO paths (p) VALUES (e'C:\\dir\\', 'x');").is_none());
+        // Backslashes are literal in standard strings
+        assert!(DependencyAnalyzer::check_syntax(r"INSERT INTO paths (p) VALUES ('C:\dir\');").is_none());
+        // An escaped quote does not close the string
+        assert_eq!(
+            DependencyAnalyzer::check_syntax(r"INSERT INTO users (name) VALUES (E'oops\');").map(|(line, _)| line),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_broken_statement_reports_file_and_line() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("001_users.pssql"),
+            "CREATE TABLE users (\n    id SERIAL PRIMARY KEY\n);\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("002_orders.pssql"),
+            "-- Orders placed by users\nCREATE TABLE orders (\n    id SERIAL PRIMARY KEY,\n    user_id INT REFERENCES users(id\n);\n",
+        )
+        .unwrap();
+
+        let err = DependencyAnalyzer::analyze_directory(temp_dir.path()).unwrap_err();
+
+        assert_eq!(err.file, temp_dir.path().join("002_orders.pssql"));
+        assert_eq!(err.line, Some(2));
+        assert!(err.message.contains("line 5"));
+        assert!(err.to_string().contains("002_orders.pssql:2:"));
+    }
+
+    #[test]
+    fn test_load_external_tables() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(EXTERNAL_TABLES_FILE),
+            "# Managed by the billing app\nAccounts\n\n-- legacy\nlegacy_users  # auth service\n",
+        )
+        .unwrap();
+
+        let external = DependencyAnalyzer::load_external_tables(temp_dir.path());
+        assert_eq!(external, vec!["accounts", "legacy_users"]);
+
+        let empty_dir = tempfile::TempDir::new().unwrap();
+        assert!(DependencyAnalyzer::load_external_tables(empty_dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_detect_cycle() {
+        let graph: HashMap<String, Vec<String>> = [
+            ("a", vec!["b"]),
+            ("b", vec!["c"]),
+            ("c", vec!["a"]),
+            ("d", vec!["a"]),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.into_iter().map(String::from).collect()))
+        .collect();
+
+        let cycles = DependencyAnalyzer::detect_circular_dependencies(&graph);
+        assert_eq!(cycles.len(), 1);
+        let mut cycle = cycles[0].clone();
+        cycle.sort();
+        assert_eq!(cycle, vec!["a", "b", "c"]);
+        assert!(DependencyAnalyzer::topological_sort(&graph).is_err());
+    }
+
+    #[test]
+    fn test_cycle_reported_once() {
+        // A diamond (a -> b/c -> d) feeding a cycle (d -> e -> f -> d) whose
+        // closing edge is declared twice
+        let graph: HashMap<String, Vec<String>> = [
+            ("a", vec!["b", "c"]),
+            ("b", vec!["d"]),
+            ("c", vec!["d"]),
+            ("d", vec!["e"]),
+            ("e", vec!["f"]),
+            ("f", vec!["d", "d"]),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.into_iter().map(String::from).collect()))
+        .collect();
+
+        let cycles = DependencyAnalyzer::detect_circular_dependencies(&graph);
+        assert_eq!(cycles, vec![vec!["d", "e", "f"]]);
+    }
+
+    #[test]
+    fn test_normalize_cycle() {
+        assert_eq!(normalize_cycle(&["c", "a", "b"]), vec!["a", "b", "c"]);
+        assert_eq!(normalize_cycle(&["b", "c", "a"]), vec!["a", "b", "c"]);
+        assert_eq!(normalize_cycle(&["a"]), vec!["a"]);
+    }
+
+    #[test]
+    fn test_large_schema_completes() {
+        // 2000 tables in one long FK chain (plus a second FK each): a recursive
+        // DFS this deep overflows the test thread's stack in debug builds, and
+        // re-sorting Kahn's queue on every push was quadratic
+        let tables = 2000;
+        let name = |i: usize| format!("t{:04}", i);
+        let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+        graph.insert(name(0), Vec::new());
+        for i in 1..tables {
+            graph.insert(name(i), vec![name(i - 1), name(i / 2)]);
+        }
+
+        let start = std::time::Instant::now();
+
+        assert!(DependencyAnalyzer::detect_circular_dependencies(&graph).is_empty());
+        let order = DependencyAnalyzer::topological_sort(&graph).unwrap();
+        assert_eq!(order.len(), tables);
+        assert_eq!(order.first(), Some(&name(0)));
+        assert_eq!(order.last(), Some(&name(tables - 1)));
+
+        // Closing the chain turns it into one 2000-table cycle
+        graph.get_mut(&name(0)).unwrap().push(name(tables - 1));
+        let cycles = DependencyAnalyzer::detect_circular_dependencies(&graph);
+        assert!(cycles.iter().any(|c| c.len() == tables));
+        assert!(DependencyAnalyzer::topological_sort(&graph).is_err());
+
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
 }
\ No newline at end of file
@@ -10,15 +10,22 @@
 //! 4. Block migration if DATALOSS detected (unless force=true)
 
 use crate::error::{GatewayError, Result};
-use crate::schema::dependency::DependencyAnalyzer;
+use crate::schema::dependency::{qualified_table_name, DependencyAnalyzer};
 use crate::schema::types::{TypeChecker, TypeCompatibility};
+use crate::schema::policies::normalize_expr;
 use deadpool_postgres::Pool;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 use tracing::{debug, info, warn};
 
+/// File in tables/ listing DB tables the gateway should leave alone
+pub const UNMANAGED_TABLES_FILE: &str = "unmanaged_tables.txt";
+
+/// Tables created by common extensions that are never part of a platform schema
+const KNOWN_EXTENSION_TABLES: &[&str] = &["spatial_ref_sys", "topology.topology", "topology.layer"];
+
 /// Represents a column in the schema
 #[derive(Debug, Clone, Serialize)]
 pub struct ColumnSchema {
@@ -29,6 +36,8 @@ pub struct ColumnSchema {
     pub character_maximum_length: Option<i32>,
     pub numeric_precision: Option<i32>,
     pub numeric_scale: Option<i32>,
+    /// 1-based position among the table's columns (declaration or attribute order)
+    pub ordinal: usize,
 }
 
 impl ColumnSchema {
@@ -55,6 +64,45 @@ impl ColumnSchema {
 pub struct TableSchema {
     pub name: String,
     pub columns: HashMap<String, ColumnSchema>,
+    /// Primary key columns in key order (None = no primary key)
+    pub primary_key: Option<Vec<String>>,
+    /// Indexes created with CREATE INDEX, by name (constraint indexes are excluded)
+    pub indexes: HashMap<String, IndexSchema>,
+    /// Parent tables from INHERITS (qualified names, in declaration order)
+    pub inherits: Vec<String>,
+    /// CHECK constraints by name (inherited ones are left to the parent)
+    pub checks: HashMap<String, CheckSchema>,
+    /// Other tables with a foreign key to this one (live schema only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub referenced_by: Vec<String>,
+}
+
+impl TableSchema {
+    /// Columns in table order
+    pub fn ordered_columns(&self) -> Vec<&ColumnSchema> {
+        let mut columns: Vec<&ColumnSchema> = self.columns.values().collect();
+        columns.sort_by_key(|column| (column.ordinal, &column.name));
+        columns
+    }
+}
+
+/// Represents a CHECK constraint on a table
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckSchema {
+    pub name: String,
+    /// Column the constraint was declared on; always None when read from the DB
+    pub column: Option<String>,
+    /// Expression inside `CHECK (...)` (as written, or `pg_get_constraintdef`)
+    pub expression: String,
+}
+
+/// Represents an index on a table
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexSchema {
+    pub name: String,
+    pub unique: bool,
+    /// CREATE INDEX statement (as written in the file, or `pg_get_indexdef`)
+    pub definition: String,
 }
 
 /// A single schema change
@@ -62,22 +110,35 @@ pub struct TableSchema {
 pub struct SchemaChange {
     pub table: String,
     pub change_type: ChangeType,
+    /// Column name, or the index/constraint name for AddIndex/DropIndex and AddCheck/DropCheck
     pub column: Option<String>,
+    /// Position of the column in the desired table, for column additions and
+    /// modifications; for DropTable, the drop position (referencing tables first)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ordinal: Option<usize>,
     pub from_type: Option<String>,
     pub to_type: Option<String>,
     pub compatibility: ChangeCompatibility,
     pub reason: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+/// Declaration order is the order changes to the same column are reported in
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ChangeType {
     CreateTable,
     DropTable,
     AddColumn,
     DropColumn,
+    /// One-to-one drop+add recognized as a rename (opt-in); from/to hold the old and new names
+    RenameColumn,
     ModifyColumnType,
     ModifyColumnNullable,
     ModifyColumnDefault,
+    ModifyPrimaryKey,
+    AddIndex,
+    DropIndex,
+    AddCheck,
+    DropCheck,
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq)]
@@ -93,6 +154,27 @@ pub struct SchemaDiff {
     pub safe_changes: Vec<SchemaChange>,
     pub dataloss_changes: Vec<SchemaChange>,
     pub incompatible_changes: Vec<SchemaChange>,
+    /// Tables whose physical column order will differ from the declared order
+    /// (informational: not a change, only fixed by a rebuild)
+    pub column_order_drift: Vec<ColumnOrderDrift>,
+}
+
+/// A table whose columns are (or, once the diff is applied, will be) in a
+/// different order than declared
+///
+/// ADD COLUMN always appends, so a column declared in the middle of a table
+/// ends up last in existing databases.
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnOrderDrift {
+    pub table: String,
+    /// Column order in the table file
+    pub declared: Vec<String>,
+    /// Column order once the diff's column changes are applied
+    pub actual: Vec<String>,
+    /// Columns that hold data today (renames applied), in declared order;
+    /// the ones a rebuild has to copy
+    #[serde(skip)]
+    pub preserved: Vec<String>,
 }
 
 impl SchemaDiff {
@@ -101,6 +183,7 @@ impl SchemaDiff {
             safe_changes: Vec::new(),
             dataloss_changes: Vec::new(),
             incompatible_changes: Vec::new(),
+            column_order_drift: Vec::new(),
         }
     }
 
@@ -114,6 +197,14 @@ impl SchemaDiff {
             || !self.incompatible_changes.is_empty()
     }
 
+    /// Data-loss and incompatible changes, the ones that block a migrate
+    pub fn blocking_changes(&self) -> Vec<&SchemaChange> {
+        self.dataloss_changes
+            .iter()
+            .chain(self.incompatible_changes.iter())
+            .collect()
+    }
+
     pub fn add_change(&mut self, change: SchemaChange) {
         match change.compatibility {
             ChangeCompatibility::Safe => self.safe_changes.push(change),
@@ -121,6 +212,21 @@ impl SchemaDiff {
             ChangeCompatibility::Incompatible => self.incompatible_changes.push(change),
         }
     }
+
+    /// Sort each change list by (table, column, change type) so reports are
+    /// stable across runs
+    pub fn sort(&mut self) {
+        for changes in [
+            &mut self.safe_changes,
+            &mut self.dataloss_changes,
+            &mut self.incompatible_changes,
+        ] {
+            changes.sort_by(|a, b| {
+                (&a.table, &a.column, &a.change_type).cmp(&(&b.table, &b.column, &b.change_type))
+            });
+        }
+        self.column_order_drift.sort_by(|a, b| a.table.cmp(&b.table));
+    }
 }
 
 impl Default for SchemaDiff {
@@ -144,6 +250,8 @@ impl SchemaDiffChecker {
     /// Parse desired schema from tables directory
     pub fn parse_desired_schema(&self, tables_dir: &Path) -> Result<HashMap<String, TableSchema>> {
         let mut tables = HashMap::new();
+        let mut indexes = Vec::new();
+        let mut partitions = HashSet::new();
 
         if !tables_dir.exists() {
             debug!("Tables directory {:?} does not exist", tables_dir);
@@ -170,33 +278,65 @@ impl SchemaDiffChecker {
 
                         // Parse tables from this file
                         if let Ok(analysis) = DependencyAnalyzer::analyze_sql(&content) {
+                            indexes.extend(analysis.indexes);
                             for table_info in analysis.tables {
+                                let table_name = table_info.qualified_name();
+                                if table_info.is_partition {
+                                    partitions.insert(table_name.clone());
+                                }
                                 let mut columns = HashMap::new();
 
-                                for col in table_info.columns {
+                                for (i, col) in table_info.columns.into_iter().enumerate() {
+                                    // Length/precision are stored separately, like information_schema
+                                    let has_modifiers = col.character_maximum_length.is_some()
+                                        || col.numeric_precision.is_some();
+                                    let data_type = match col.data_type.find('(') {
+                                        Some(pos) if has_modifiers => col.data_type[..pos].trim().to_string(),
+                                        _ => col.data_type,
+                                    };
+
                                     columns.insert(
                                         col.name.clone(),
                                         ColumnSchema {
                                             name: col.name,
-                                            data_type: col.data_type,
+                                            data_type,
                                             is_nullable: col.is_nullable,
                                             column_default: if col.has_default {
                                                 Some("(has default)".to_string())
                                             } else {
                                                 None
                                             },
-                                            character_maximum_length: None, // Would need enhanced parsing
-                                            numeric_precision: None,
-                                            numeric_scale: None,
+                                            character_maximum_length: col.character_maximum_length,
+                                            numeric_precision: col.numeric_precision,
+                                            numeric_scale: col.numeric_scale,
+                                            ordinal: i + 1,
                                         },
                                     );
                                 }
 
                                 tables.insert(
-                                    table_info.name.clone(),
+                                    table_name.clone(),
                                     TableSchema {
-                                        name: table_info.name,
+                                        name: table_name,
                                         columns,
+                                        primary_key: table_info.primary_key,
+                                        indexes: HashMap::new(),
+                                        inherits: table_info.inherits,
+                                        checks: table_info
+                                            .checks
+                                            .into_iter()
+                                            .map(|check| {
+                                                (
+                                                    check.name.clone(),
+                                                    CheckSchema {
+                                                        name: check.name,
+                                                        column: check.column,
+                                                        expression: check.expression,
+                                                    },
+                                                )
+                                            })
+                                            .collect(),
+                                        referenced_by: Vec::new(),
                                     },
                                 );
                             }
@@ -206,9 +346,79 @@ impl SchemaDiffChecker {
             }
         }
 
+        // An index may be declared in a different file than its table
+        for index in indexes {
+            match tables.get_mut(&index.table_name()) {
+                Some(table) => {
+                    table.indexes.insert(
+                        index.name.clone(),
+                        IndexSchema {
+                            name: index.name,
+                            unique: index.unique,
+                            definition: index.definition,
+                        },
+                    );
+                }
+                None => debug!("Index {} is on undeclared table {}, ignoring", index.name, index.table_name()),
+            }
+        }
+
+        inherit_from_parents(&mut tables, &partitions);
+
         Ok(tables)
     }
 
+    /// List table files with SQL in them that yield neither a table nor an index
+    ///
+    /// Comment-only and empty files are skipped, so an intentionally empty
+    /// tables folder is not reported.
+    fn find_unparseable_table_files(tables_dir: &Path) -> Result<Vec<String>> {
+        let mut unparseable = Vec::new();
+        if !tables_dir.exists() {
+            return Ok(unparseable);
+        }
+
+        for entry in fs::read_dir(tables_dir).map_err(|e| GatewayError::SchemaExtractionFailed {
+            cause: format!("Failed to read tables directory: {}", e),
+        })? {
+            let path = entry
+                .map_err(|e| GatewayError::SchemaExtractionFailed {
+                    cause: format!("Failed to read directory entry: {}", e),
+                })?
+                .path();
+            let is_sql = path
+                .extension()
+                .is_some_and(|ext| ext == "pssql" || ext == "pgsql" || ext == "sql");
+            if !path.is_file() || !is_sql {
+                continue;
+            }
+
+            let content = fs::read_to_string(&path).map_err(|e| GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to read file {:?}: {}", path, e),
+            })?;
+            let has_sql = content
+                .lines()
+                .map(str::trim)
+                .any(|line| !line.is_empty() && !line.starts_with("--"));
+            let parsed = DependencyAnalyzer::analyze_sql(&content)
+                .is_ok_and(|analysis| !analysis.tables.is_empty() || !analysis.indexes.is_empty());
+
+            if has_sql && !parsed {
+                if let Some(name) = path.file_name() {
+                    unparseable.push(name.to_string_lossy().to_string());
+                }
+            }
+        }
+
+        unparseable.sort();
+        Ok(unparseable)
+    }
+
+    /// Load the unmanaged table allow-list from a tables directory, if present
+    pub fn load_unmanaged_tables(tables_dir: &Path) -> Vec<String> {
+        DependencyAnalyzer::load_table_list(&tables_dir.join(UNMANAGED_TABLES_FILE))
+    }
+
     /// Query current schema from database
     pub async fn query_current_schema(
         &self,
@@ -227,6 +437,7 @@ impl SchemaDiffChecker {
             .query(
                 r#"
                 SELECT
+                    t.table_schema,
                     t.table_name,
                     c.column_name,
                     c.data_type,
@@ -234,15 +445,31 @@ impl SchemaDiffChecker {
                     c.column_default,
                     c.character_maximum_length,
                     c.numeric_precision,
-                    c.numeric_scale
+                    c.numeric_scale,
+                    (
+                        SELECT format_type(a.atttypid, a.atttypmod)
+                        FROM pg_attribute a
+                        WHERE a.attrelid = (quote_ident(t.table_schema) || '.' || quote_ident(t.table_name))::regclass
+                          AND a.attname = c.column_name
+                    )
                 FROM information_schema.tables t
                 JOIN information_schema.columns c
                     ON t.table_name = c.table_name
                     AND t.table_schema = c.table_schema
-                WHERE t.table_schema = 'public'
+                WHERE t.table_schema NOT IN ('pg_catalog', 'information_schema')
+                    AND t.table_schema NOT LIKE 'pg_%'
                     AND t.table_type = 'BASE TABLE'
                     AND t.table_name NOT LIKE '_stonescriptdb_gateway_%'
-                ORDER BY t.table_name, c.ordinal_position
+                    AND NOT EXISTS (
+                        SELECT 1 FROM pg_depend d
+                        JOIN pg_class cl ON cl.oid = d.objid
+                        JOIN pg_namespace n ON n.oid = cl.relnamespace
+                        WHERE d.classid = 'pg_class'::regclass
+                          AND d.deptype = 'e'
+                          AND n.nspname = t.table_schema
+                          AND cl.relname = t.table_name
+                    )
+                ORDER BY t.table_schema, t.table_name, c.ordinal_position
                 "#,
                 &[],
             )
@@ -254,45 +481,258 @@ impl SchemaDiffChecker {
             })?;
 
         for row in rows {
-            let table_name: String = row.get(0);
-            let column_name: String = row.get(1);
-            let data_type: String = row.get(2);
-            let is_nullable_str: String = row.get(3);
-            let column_default: Option<String> = row.get(4);
-            let char_max_len: Option<i32> = row.get(5);
-            let numeric_precision: Option<i32> = row.get(6);
-            let numeric_scale: Option<i32> = row.get(7);
+            let table_schema: String = row.get(0);
+            let table_name = qualified_table_name(Some(&table_schema), row.get(1));
+            let column_name: String = row.get(2);
+            let data_type: String = row.get(3);
+            let is_nullable_str: String = row.get(4);
+            let column_default: Option<String> = row.get(5);
+            let char_max_len: Option<i32> = row.get(6);
+            let numeric_precision: Option<i32> = row.get(7);
+            let numeric_scale: Option<i32> = row.get(8);
+            let formatted_type: String = row.get(9);
 
             let is_nullable = is_nullable_str.to_uppercase() == "YES";
 
+            let table = tables
+                .entry(table_name.clone())
+                .or_insert_with(|| TableSchema {
+                    name: table_name,
+                    columns: HashMap::new(),
+                    primary_key: None,
+                    indexes: HashMap::new(),
+                    inherits: Vec::new(),
+                    checks: HashMap::new(),
+                    referenced_by: Vec::new(),
+                });
+
+            // Rows come in ordinal_position order; dropped columns leave no gap here
             let column = ColumnSchema {
                 name: column_name.clone(),
-                data_type: data_type.to_uppercase(),
+                data_type: array_aware_type(&data_type, &formatted_type),
                 is_nullable,
                 column_default,
                 character_maximum_length: char_max_len,
                 numeric_precision,
                 numeric_scale,
+                ordinal: table.columns.len() + 1,
             };
+            table.columns.insert(column_name, column);
+        }
 
-            tables
-                .entry(table_name.clone())
-                .or_insert_with(|| TableSchema {
-                    name: table_name,
-                    columns: HashMap::new(),
-                })
-                .columns
-                .insert(column_name, column);
+        // Primary key columns, in key order
+        let pk_rows = client
+            .query(
+                r#"
+                SELECT tc.table_schema, tc.table_name, kcu.column_name
+                FROM information_schema.table_constraints tc
+                JOIN information_schema.key_column_usage kcu
+                    ON kcu.constraint_schema = tc.constraint_schema
+                    AND kcu.constraint_name = tc.constraint_name
+                    AND kcu.table_name = tc.table_name
+                WHERE tc.constraint_type = 'PRIMARY KEY'
+                    AND tc.table_schema NOT IN ('pg_catalog', 'information_schema')
+                ORDER BY tc.table_schema, tc.table_name, kcu.ordinal_position
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "primary key query".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        for row in pk_rows {
+            let table_schema: String = row.get(0);
+            let table_name = qualified_table_name(Some(&table_schema), row.get(1));
+            if let Some(table) = tables.get_mut(&table_name) {
+                table.primary_key.get_or_insert_with(Vec::new).push(row.get(2));
+            }
+        }
+
+        // Indexes that back a constraint (primary key, unique, exclusion) share
+        // its name and are managed through the table definition; indexes a
+        // partition inherits from its parent's index are managed through the parent
+        let index_rows = client
+            .query(
+                r#"
+                SELECT i.schemaname, i.tablename, i.indexname, i.indexdef
+                FROM pg_indexes i
+                WHERE i.schemaname NOT IN ('pg_catalog', 'information_schema')
+                    AND i.schemaname NOT LIKE 'pg_%'
+                    AND NOT EXISTS (
+                        SELECT 1 FROM pg_constraint c
+                        JOIN pg_namespace n ON n.oid = c.connamespace
+                        WHERE n.nspname = i.schemaname AND c.conname = i.indexname
+                    )
+                    AND NOT EXISTS (
+                        SELECT 1 FROM pg_inherits inh
+                        WHERE inh.inhrelid = to_regclass(quote_ident(i.schemaname) || '.' || quote_ident(i.indexname))
+                    )
+                ORDER BY i.schemaname, i.tablename, i.indexname
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "index query".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        for row in index_rows {
+            let table_schema: String = row.get(0);
+            let table_name = qualified_table_name(Some(&table_schema), row.get(1));
+            if let Some(table) = tables.get_mut(&table_name) {
+                let name: String = row.get(2);
+                let definition: String = row.get(3);
+                table.indexes.insert(
+                    name.clone(),
+                    IndexSchema {
+                        name,
+                        unique: definition.starts_with("CREATE UNIQUE INDEX"),
+                        definition,
+                    },
+                );
+            }
+        }
+
+        // Local CHECK constraints; inherited ones are declared on the parent
+        let check_rows = client
+            .query(
+                r#"
+                SELECT n.nspname, c.relname, con.conname, pg_get_constraintdef(con.oid)
+                FROM pg_constraint con
+                JOIN pg_class c ON c.oid = con.conrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE con.contype = 'c'
+                    AND con.conislocal
+                    AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+                    AND n.nspname NOT LIKE 'pg_%'
+                ORDER BY n.nspname, c.relname, con.conname
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "check constraint query".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        let check_def_re = regex::Regex::new(r"(?is)^CHECK\s*\((.*)\)(?:\s+NOT\s+VALID)?$").unwrap();
+        for row in check_rows {
+            let table_schema: String = row.get(0);
+            let table_name = qualified_table_name(Some(&table_schema), row.get(1));
+            if let Some(table) = tables.get_mut(&table_name) {
+                let name: String = row.get(2);
+                let definition: String = row.get(3);
+                let expression = check_def_re
+                    .captures(&definition)
+                    .map(|cap| cap[1].to_string())
+                    .unwrap_or(definition);
+                table.checks.insert(
+                    name.clone(),
+                    CheckSchema {
+                        name,
+                        column: None,
+                        expression,
+                    },
+                );
+            }
+        }
+
+        let inherits_rows = client
+            .query(
+                r#"
+                SELECT cn.nspname, c.relname, pn.nspname, p.relname
+                FROM pg_inherits i
+                JOIN pg_class c ON c.oid = i.inhrelid
+                JOIN pg_namespace cn ON cn.oid = c.relnamespace
+                JOIN pg_class p ON p.oid = i.inhparent
+                JOIN pg_namespace pn ON pn.oid = p.relnamespace
+                ORDER BY cn.nspname, c.relname, i.inhseqno
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "inheritance query".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        for row in inherits_rows {
+            let table_schema: String = row.get(0);
+            let table_name = qualified_table_name(Some(&table_schema), row.get(1));
+            if let Some(table) = tables.get_mut(&table_name) {
+                let parent_schema: String = row.get(2);
+                table.inherits.push(qualified_table_name(Some(&parent_schema), row.get(3)));
+            }
+        }
+
+        // Foreign keys from other tables, which would make a DROP TABLE fail
+        let reference_rows = client
+            .query(
+                r#"
+                SELECT DISTINCT tn.nspname, t.relname, rn.nspname, r.relname
+                FROM pg_constraint con
+                JOIN pg_class t ON t.oid = con.confrelid
+                JOIN pg_namespace tn ON tn.oid = t.relnamespace
+                JOIN pg_class r ON r.oid = con.conrelid
+                JOIN pg_namespace rn ON rn.oid = r.relnamespace
+                WHERE con.contype = 'f' AND con.conrelid <> con.confrelid
+                ORDER BY 1, 2, 3, 4
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "foreign key query".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        for row in reference_rows {
+            let table_schema: String = row.get(0);
+            let table_name = qualified_table_name(Some(&table_schema), row.get(1));
+            if let Some(table) = tables.get_mut(&table_name) {
+                let referencing_schema: String = row.get(2);
+                table
+                    .referenced_by
+                    .push(qualified_table_name(Some(&referencing_schema), row.get(3)));
+            }
         }
 
         Ok(tables)
     }
 
     /// Compare desired schema against current schema
+    ///
+    /// DB-only tables listed in `unmanaged_tables` (or owned by known extensions)
+    /// are never proposed for drop.
     pub fn diff_schemas(
         &self,
         desired: &HashMap<String, TableSchema>,
         current: &HashMap<String, TableSchema>,
+        unmanaged_tables: &[String],
+    ) -> SchemaDiff {
+        self.diff_schemas_with_renames(desired, current, unmanaged_tables, false)
+    }
+
+    /// Like `diff_schemas`, optionally reporting likely column renames
+    ///
+    /// With `detect_renames`, a table where exactly one column disappears and
+    /// exactly one appears, with an identical or safely widened type, gets a
+    /// `RenameColumn` instead of the drop+add pair. This is a guess: a real
+    /// drop plus an unrelated add of the same type looks the same.
+    pub fn diff_schemas_with_renames(
+        &self,
+        desired: &HashMap<String, TableSchema>,
+        current: &HashMap<String, TableSchema>,
+        unmanaged_tables: &[String],
+        detect_renames: bool,
     ) -> SchemaDiff {
         let mut diff = SchemaDiff::new();
 
@@ -305,6 +745,7 @@ impl SchemaDiffChecker {
                         table: table_name.clone(),
                         change_type: ChangeType::CreateTable,
                         column: None,
+                        ordinal: None,
                         from_type: None,
                         to_type: None,
                         compatibility: ChangeCompatibility::Safe,
@@ -313,29 +754,95 @@ impl SchemaDiffChecker {
                 }
                 Some(current_table) => {
                     // Compare columns
-                    self.diff_table_columns(&mut diff, table_name, desired_table, current_table);
+                    self.diff_table_columns(&mut diff, table_name, desired_table, current_table, detect_renames);
                 }
             }
         }
 
-        // Check for dropped tables
+        // Check for dropped tables - only in the default schema and schemas the
+        // desired schema declares, so tables in unrelated schemas are ignored
+        let managed_schemas: std::collections::HashSet<Option<&str>> = desired
+            .keys()
+            .map(|name| name.split_once('.').map(|(schema, _)| schema))
+            .chain(std::iter::once(None))
+            .collect();
+
+        let is_dropped = |table_name: &str| {
+            let schema = table_name.split_once('.').map(|(schema, _)| schema);
+            managed_schemas.contains(&schema)
+                && !Self::is_unmanaged(table_name, unmanaged_tables)
+                && !desired.contains_key(table_name)
+        };
+
+        // Reverse creation order: a table is dropped after the dropped tables referencing it
+        let dropped: Vec<&str> = current.keys().map(String::as_str).filter(|t| is_dropped(t)).collect();
+        let mut drop_rank: HashMap<&str, usize> = dropped.iter().map(|t| (*t, 0)).collect();
+        for _ in 0..dropped.len() {
+            for table in &dropped {
+                let rank = current[*table]
+                    .referenced_by
+                    .iter()
+                    .filter(|dependent| dependent.as_str() != *table)
+                    .filter_map(|dependent| drop_rank.get(dependent.as_str()))
+                    .map(|rank| rank + 1)
+                    .max()
+                    .unwrap_or(0);
+                drop_rank.insert(table, rank);
+            }
+        }
+
         for table_name in current.keys() {
+            let schema = table_name.split_once('.').map(|(schema, _)| schema);
+            if !managed_schemas.contains(&schema) {
+                continue;
+            }
+            if Self::is_unmanaged(table_name, unmanaged_tables) {
+                debug!("Skipping unmanaged table {}", table_name);
+                continue;
+            }
             if !desired.contains_key(table_name) {
+                // DROP TABLE fails while other tables still reference it, unless
+                // those tables are dropped as well
+                let dependents: Vec<&str> = current[table_name]
+                    .referenced_by
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|dependent| !is_dropped(dependent))
+                    .collect();
+                let (compatibility, reason) = if dependents.is_empty() {
+                    (ChangeCompatibility::DataLoss, "Dropping table will delete all data".to_string())
+                } else {
+                    (
+                        ChangeCompatibility::Incompatible,
+                        format!(
+                            "Referenced by foreign keys from {}; drop those constraints or tables first",
+                            dependents.join(", ")
+                        ),
+                    )
+                };
                 diff.add_change(SchemaChange {
                     table: table_name.clone(),
                     change_type: ChangeType::DropTable,
                     column: None,
+                    ordinal: drop_rank.get(table_name.as_str()).copied(),
                     from_type: None,
                     to_type: None,
-                    compatibility: ChangeCompatibility::DataLoss,
-                    reason: Some("Dropping table will delete all data".to_string()),
+                    compatibility,
+                    reason: Some(reason),
                 });
             }
         }
 
+        diff.sort();
         diff
     }
 
+    /// Check if a DB-only table is allow-listed or belongs to a known extension
+    fn is_unmanaged(table_name: &str, unmanaged_tables: &[String]) -> bool {
+        unmanaged_tables.iter().any(|t| t == table_name)
+            || KNOWN_EXTENSION_TABLES.contains(&table_name)
+    }
+
     /// Compare columns between desired and current table
     fn diff_table_columns(
         &self,
@@ -343,10 +850,39 @@ impl SchemaDiffChecker {
         table_name: &str,
         desired: &TableSchema,
         current: &TableSchema,
+        detect_renames: bool,
     ) {
+        // (old name, new name) of a column that looks renamed
+        let rename = if detect_renames {
+            self.find_rename(desired, current)
+        } else {
+            None
+        };
+        if let Some((old_name, new_name)) = &rename {
+            diff.add_change(SchemaChange {
+                table: table_name.to_string(),
+                change_type: ChangeType::RenameColumn,
+                column: Some(new_name.clone()),
+                ordinal: Some(desired.columns[new_name].ordinal),
+                from_type: Some(old_name.clone()),
+                to_type: Some(new_name.clone()),
+                compatibility: ChangeCompatibility::Safe,
+                reason: Some(format!(
+                    "Column {} not in schema and {} new with a compatible type; assumed to be a rename",
+                    old_name, new_name
+                )),
+            });
+        }
+
         // Check for new and modified columns
-        for (col_name, desired_col) in &desired.columns {
-            match current.columns.get(col_name) {
+        for desired_col in desired.ordered_columns() {
+            let col_name = &desired_col.name;
+            // A renamed column is compared with its old self
+            let current_col = match &rename {
+                Some((old_name, new_name)) if new_name == col_name => current.columns.get(old_name),
+                _ => current.columns.get(col_name),
+            };
+            match current_col {
                 None => {
                     // New column
                     let compatibility = if !desired_col.is_nullable
@@ -362,6 +898,7 @@ impl SchemaDiffChecker {
                         table: table_name.to_string(),
                         change_type: ChangeType::AddColumn,
                         column: Some(col_name.clone()),
+                        ordinal: Some(desired_col.ordinal),
                         from_type: None,
                         to_type: Some(desired_col.full_type()),
                         compatibility,
@@ -394,6 +931,7 @@ impl SchemaDiffChecker {
                             table: table_name.to_string(),
                             change_type: ChangeType::ModifyColumnNullable,
                             column: Some(col_name.clone()),
+                            ordinal: Some(desired_col.ordinal),
                             from_type: Some(if current_col.is_nullable {
                                 "NULLABLE"
                             } else {
@@ -420,11 +958,13 @@ impl SchemaDiffChecker {
 
         // Check for dropped columns
         for col_name in current.columns.keys() {
-            if !desired.columns.contains_key(col_name) {
+            let renamed = rename.as_ref().is_some_and(|(old_name, _)| old_name == col_name);
+            if !desired.columns.contains_key(col_name) && !renamed {
                 diff.add_change(SchemaChange {
                     table: table_name.to_string(),
                     change_type: ChangeType::DropColumn,
                     column: Some(col_name.clone()),
+                    ordinal: None,
                     from_type: Some(current.columns[col_name].full_type()),
                     to_type: None,
                     compatibility: ChangeCompatibility::DataLoss,
@@ -432,6 +972,198 @@ impl SchemaDiffChecker {
                 });
             }
         }
+
+        Self::diff_primary_key(diff, table_name, desired, current);
+        Self::diff_indexes(diff, table_name, desired, current);
+        Self::diff_checks(diff, table_name, desired, current);
+        Self::diff_column_order(diff, table_name, desired, current, rename.as_ref());
+    }
+
+    /// Report a table whose column order will not match its declaration
+    ///
+    /// Compares the declared order with the order after this diff: surviving
+    /// columns keep their place (a rename keeps its position), added columns
+    /// are appended.
+    fn diff_column_order(
+        diff: &mut SchemaDiff,
+        table_name: &str,
+        desired: &TableSchema,
+        current: &TableSchema,
+        rename: Option<&(String, String)>,
+    ) {
+        let declared: Vec<String> = desired.ordered_columns().iter().map(|c| c.name.clone()).collect();
+
+        let surviving: Vec<String> = current
+            .ordered_columns()
+            .iter()
+            .map(|c| match rename {
+                Some((old_name, new_name)) if *old_name == c.name => new_name.clone(),
+                _ => c.name.clone(),
+            })
+            .filter(|name| desired.columns.contains_key(name))
+            .collect();
+        let mut actual = surviving.clone();
+        actual.extend(declared.iter().filter(|name| !surviving.contains(name)).cloned());
+
+        if actual != declared {
+            diff.column_order_drift.push(ColumnOrderDrift {
+                table: table_name.to_string(),
+                preserved: declared.iter().filter(|name| surviving.contains(name)).cloned().collect(),
+                declared,
+                actual,
+            });
+        }
+    }
+
+    /// The single (dropped, added) column pair of a table, if their types are compatible
+    fn find_rename(&self, desired: &TableSchema, current: &TableSchema) -> Option<(String, String)> {
+        let mut dropped = current.columns.values().filter(|c| !desired.columns.contains_key(&c.name));
+        let mut added = desired.columns.values().filter(|c| !current.columns.contains_key(&c.name));
+
+        let (old, new) = (dropped.next()?, added.next()?);
+        if dropped.next().is_some() || added.next().is_some() {
+            // Ambiguous: several candidates on either side
+            return None;
+        }
+
+        self.type_checker
+            .check_compatibility(&old.full_type(), &new.full_type())
+            .is_safe()
+            .then(|| (old.name.clone(), new.name.clone()))
+    }
+
+    /// Compare CHECK constraints by name, then by normalized expression
+    ///
+    /// Adding a check fails if existing rows violate it, so it is DataLoss;
+    /// dropping one only loosens the table. A changed expression is a drop
+    /// followed by an add. Column-level checks of columns being added come
+    /// with the ADD COLUMN and are not reported separately.
+    fn diff_checks(diff: &mut SchemaDiff, table_name: &str, desired: &TableSchema, current: &TableSchema) {
+        let add = |check: &CheckSchema| SchemaChange {
+            table: table_name.to_string(),
+            change_type: ChangeType::AddCheck,
+            column: Some(check.name.clone()),
+            ordinal: None,
+            from_type: None,
+            to_type: Some(check.expression.clone()),
+            compatibility: ChangeCompatibility::DataLoss,
+            reason: Some("Adding a CHECK constraint fails if existing rows violate it".to_string()),
+        };
+        let drop = |check: &CheckSchema| SchemaChange {
+            table: table_name.to_string(),
+            change_type: ChangeType::DropCheck,
+            column: Some(check.name.clone()),
+            ordinal: None,
+            from_type: Some(check.expression.clone()),
+            to_type: None,
+            compatibility: ChangeCompatibility::Safe,
+            reason: None,
+        };
+
+        for (name, check) in &desired.checks {
+            match current.checks.get(name) {
+                None => {
+                    let added_with_column = check
+                        .column
+                        .as_ref()
+                        .is_some_and(|column| !current.columns.contains_key(column));
+                    if !added_with_column {
+                        diff.add_change(add(check));
+                    }
+                }
+                Some(existing) => {
+                    if normalize_check_expression(&check.expression) != normalize_check_expression(&existing.expression) {
+                        diff.add_change(drop(existing));
+                        diff.add_change(add(check));
+                    }
+                }
+            }
+        }
+
+        for (name, check) in &current.checks {
+            if !desired.checks.contains_key(name) {
+                diff.add_change(drop(check));
+            }
+        }
+    }
+
+    /// Compare indexes by name
+    ///
+    /// Indexes hold no row data, so adding or dropping one is safe, except that
+    /// dropping a unique index lets duplicate values in.
+    fn diff_indexes(diff: &mut SchemaDiff, table_name: &str, desired: &TableSchema, current: &TableSchema) {
+        for (name, index) in &desired.indexes {
+            if !current.indexes.contains_key(name) {
+                diff.add_change(SchemaChange {
+                    table: table_name.to_string(),
+                    change_type: ChangeType::AddIndex,
+                    column: Some(name.clone()),
+                    ordinal: None,
+                    from_type: None,
+                    to_type: Some(index.definition.clone()),
+                    compatibility: ChangeCompatibility::Safe,
+                    reason: None,
+                });
+            }
+        }
+
+        for (name, index) in &current.indexes {
+            if !desired.indexes.contains_key(name) {
+                diff.add_change(SchemaChange {
+                    table: table_name.to_string(),
+                    change_type: ChangeType::DropIndex,
+                    column: Some(name.clone()),
+                    ordinal: None,
+                    from_type: Some(index.definition.clone()),
+                    to_type: None,
+                    compatibility: if index.unique {
+                        ChangeCompatibility::DataLoss
+                    } else {
+                        ChangeCompatibility::Safe
+                    },
+                    reason: index
+                        .unique
+                        .then(|| "Dropping a unique index allows duplicate values".to_string()),
+                });
+            }
+        }
+    }
+
+    /// Compare primary keys, including the order of their columns
+    ///
+    /// Any primary key change rewrites a constraint that foreign keys and
+    /// logical replication (replica identity) rely on, so none of them is safe.
+    fn diff_primary_key(diff: &mut SchemaDiff, table_name: &str, desired: &TableSchema, current: &TableSchema) {
+        if desired.primary_key == current.primary_key {
+            return;
+        }
+
+        let (compatibility, reason) = match (&current.primary_key, &desired.primary_key) {
+            (None, _) => (
+                ChangeCompatibility::DataLoss,
+                "Adding a primary key fails if existing rows have duplicate or NULL key values",
+            ),
+            (_, None) => (
+                ChangeCompatibility::DataLoss,
+                "Dropping the primary key breaks foreign keys referencing it and the table's replica identity",
+            ),
+            _ => (
+                ChangeCompatibility::Incompatible,
+                "Changing primary key columns recreates the constraint, breaking foreign keys referencing it and the table's replica identity",
+            ),
+        };
+        let format_key = |key: &Option<Vec<String>>| key.as_ref().map(|columns| format!("({})", columns.join(", ")));
+
+        diff.add_change(SchemaChange {
+            table: table_name.to_string(),
+            change_type: ChangeType::ModifyPrimaryKey,
+            column: None,
+            ordinal: None,
+            from_type: format_key(&current.primary_key),
+            to_type: format_key(&desired.primary_key),
+            compatibility,
+            reason: Some(reason.to_string()),
+        });
     }
 
     /// Compare column types and check compatibility
@@ -458,6 +1190,7 @@ impl SchemaDiffChecker {
                     table: table_name.to_string(),
                     change_type: ChangeType::ModifyColumnType,
                     column: Some(col_name.to_string()),
+                    ordinal: Some(desired.ordinal),
                     from_type: Some(current_type),
                     to_type: Some(desired_type),
                     compatibility: ChangeCompatibility::Safe,
@@ -469,6 +1202,7 @@ impl SchemaDiffChecker {
                     table: table_name.to_string(),
                     change_type: ChangeType::ModifyColumnType,
                     column: Some(col_name.to_string()),
+                    ordinal: Some(desired.ordinal),
                     from_type: Some(current_type),
                     to_type: Some(desired_type),
                     compatibility: ChangeCompatibility::DataLoss,
@@ -480,6 +1214,7 @@ impl SchemaDiffChecker {
                     table: table_name.to_string(),
                     change_type: ChangeType::ModifyColumnType,
                     column: Some(col_name.to_string()),
+                    ordinal: Some(desired.ordinal),
                     from_type: Some(current_type),
                     to_type: Some(desired_type),
                     compatibility: ChangeCompatibility::Incompatible,
@@ -491,17 +1226,32 @@ impl SchemaDiffChecker {
 
     /// Validate schema changes before migration
     /// Returns Ok if safe, Err if dataloss/incompatible changes detected
+    ///
+    /// `detect_renames` enables the column rename heuristic (see `diff_schemas_with_renames`).
     pub async fn validate_migration(
         &self,
         pool: &Pool,
         database: &str,
         tables_dir: &Path,
         force: bool,
+        detect_renames: bool,
     ) -> Result<SchemaDiff> {
         // Parse desired schema
         let desired = self.parse_desired_schema(tables_dir)?;
 
         if desired.is_empty() {
+            // No tables is fine for a functions/seeders-only schema, but not when
+            // the table files are there and none of them could be parsed
+            let unparseable = Self::find_unparseable_table_files(tables_dir)?;
+            if !unparseable.is_empty() {
+                return Err(GatewayError::SchemaValidationFailed {
+                    issues: unparseable
+                        .iter()
+                        .map(|file| format!("No table definition could be parsed from {}", file))
+                        .collect(),
+                });
+            }
+
             debug!("No tables found in {:?}, skipping schema validation", tables_dir);
             return Ok(SchemaDiff::new());
         }
@@ -510,7 +1260,8 @@ impl SchemaDiffChecker {
         let current = self.query_current_schema(pool, database).await?;
 
         // Compute diff
-        let diff = self.diff_schemas(&desired, &current);
+        let unmanaged = Self::load_unmanaged_tables(tables_dir);
+        let diff = self.diff_schemas_with_renames(&desired, &current, &unmanaged, detect_renames);
 
         // Log changes
         if !diff.safe_changes.is_empty() {
@@ -673,6 +1424,101 @@ impl Default for SchemaDiffChecker {
     }
 }
 
+/// information_schema reports every array column as "ARRAY"; take the type
+/// from `format_type(atttypid, atttypmod)` instead, which keeps the element
+/// modifiers (e.g. "character varying(50)[]" -> "VARCHAR(50)[]")
+fn array_aware_type(data_type: &str, formatted_type: &str) -> String {
+    if !data_type.eq_ignore_ascii_case("ARRAY") {
+        return data_type.to_uppercase();
+    }
+
+    let aliases = regex::Regex::new(r"^(?:CHARACTER VARYING|CHARACTER|BPCHAR)\b").unwrap();
+    let formatted_type = formatted_type.trim().to_uppercase();
+    aliases
+        .replace(&formatted_type, |caps: &regex::Captures| match &caps[0] {
+            "CHARACTER VARYING" => "VARCHAR",
+            _ => "CHAR",
+        })
+        .into_owned()
+}
+
+/// Normalize a CHECK expression for comparison with `pg_get_constraintdef`
+///
+/// On top of the casts, parentheses and whitespace PostgreSQL adds, it
+/// rewrites `x IN (...)` to `x = ANY (ARRAY[...])` (`x = v` for a single
+/// value), `x NOT IN (...)` to `x <> ALL (ARRAY[...])` (`x <> v`) and
+/// `x BETWEEN a AND b` to `x >= a AND x <= b`.
+fn normalize_check_expression(expression: &str) -> String {
+    let between_re =
+        regex::Regex::new(r"(?i)([\w.]+)\s+BETWEEN\s+(\S+)\s+AND\s+(\S+)").unwrap();
+    let expression = between_re.replace_all(expression, "$1 >= $2 AND $1 <= $3");
+    let single_not_in_re = regex::Regex::new(r"(?i)\s+NOT\s+IN\s*\(\s*([^,()]+?)\s*\)").unwrap();
+    let expression = single_not_in_re.replace_all(&expression, " <> $1");
+    let single_in_re = regex::Regex::new(r"(?i)\s+IN\s*\(\s*([^,()]+?)\s*\)").unwrap();
+    let expression = single_in_re.replace_all(&expression, " = $1");
+
+    let normalized = normalize_expr(&expression);
+    let any_re = regex::Regex::new(r"=any\(array\[([^\]]*)\]\)").unwrap();
+    let all_re = regex::Regex::new(r"<>all\(array\[([^\]]*)\]\)").unwrap();
+    let normalized = any_re.replace_all(&normalized, " in ($1)");
+    all_re.replace_all(&normalized, " not in ($1)").into_owned()
+}
+
+/// Give INHERITS children and partitions the columns of their parents, as
+/// PostgreSQL does; partitions also get the parent's primary key
+///
+/// Repeated until nothing changes so multi-level hierarchies are covered
+/// whatever the map's iteration order. Inherited columns come first, in
+/// parent order, and the child's own columns are renumbered after them.
+fn inherit_from_parents(tables: &mut HashMap<String, TableSchema>, partitions: &HashSet<String>) {
+    loop {
+        let mut inherited = Vec::new();
+        let mut primary_keys = Vec::new();
+        for (name, table) in tables.iter() {
+            for parent in table.inherits.iter().filter_map(|p| tables.get(p)) {
+                for column in parent.ordered_columns() {
+                    if !table.columns.contains_key(&column.name) {
+                        inherited.push((name.clone(), column.clone()));
+                    }
+                }
+                if partitions.contains(name) && table.primary_key.is_none() {
+                    if let Some(pk) = &parent.primary_key {
+                        primary_keys.push((name.clone(), pk.clone()));
+                    }
+                }
+            }
+        }
+
+        if inherited.is_empty() && primary_keys.is_empty() {
+            return;
+        }
+        let mut inherited_by_table: HashMap<String, Vec<ColumnSchema>> = HashMap::new();
+        for (name, column) in inherited {
+            let columns = inherited_by_table.entry(name).or_default();
+            // Same column from several parents is merged, as PostgreSQL does
+            if !columns.iter().any(|c| c.name == column.name) {
+                columns.push(column);
+            }
+        }
+        for (name, columns) in inherited_by_table {
+            if let Some(table) = tables.get_mut(&name) {
+                for own in table.columns.values_mut() {
+                    own.ordinal += columns.len();
+                }
+                for (i, mut column) in columns.into_iter().enumerate() {
+                    column.ordinal = i + 1;
+                    table.columns.insert(column.name.clone(), column);
+                }
+            }
+        }
+        for (name, pk) in primary_keys {
+            if let Some(table) = tables.get_mut(&name) {
+                table.primary_key.get_or_insert(pk);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -687,6 +1533,7 @@ mod tests {
             character_maximum_length: Some(100),
             numeric_precision: None,
             numeric_scale: None,
+            ordinal: 1,
         };
         assert_eq!(col.full_type(), "VARCHAR(100)");
 
@@ -698,6 +1545,7 @@ mod tests {
             character_maximum_length: None,
             numeric_precision: Some(10),
             numeric_scale: Some(2),
+            ordinal: 1,
         };
         assert_eq!(col2.full_type(), "NUMERIC(10,2)");
     }
@@ -712,12 +1560,17 @@ mod tests {
             TableSchema {
                 name: "users".to_string(),
                 columns: HashMap::new(),
+                primary_key: None,
+                indexes: HashMap::new(),
+                inherits: Vec::new(),
+                checks: HashMap::new(),
+                referenced_by: Vec::new(),
             },
         );
 
         let current = HashMap::new();
 
-        let diff = checker.diff_schemas(&desired, &current);
+        let diff = checker.diff_schemas(&desired, &current, &[]);
 
         assert!(diff.is_safe());
         assert_eq!(diff.safe_changes.len(), 1);
@@ -736,16 +1589,80 @@ mod tests {
             TableSchema {
                 name: "users".to_string(),
                 columns: HashMap::new(),
+                primary_key: None,
+                indexes: HashMap::new(),
+                inherits: Vec::new(),
+                checks: HashMap::new(),
+                referenced_by: Vec::new(),
             },
         );
 
-        let diff = checker.diff_schemas(&desired, &current);
+        let diff = checker.diff_schemas(&desired, &current, &[]);
 
         assert!(!diff.is_safe());
         assert_eq!(diff.dataloss_changes.len(), 1);
         assert_eq!(diff.dataloss_changes[0].change_type, ChangeType::DropTable);
     }
 
+    #[test]
+    fn test_diff_drop_referenced_table_is_incompatible() {
+        let checker = SchemaDiffChecker::new();
+        let table = |name: &str, referenced_by: Vec<String>| TableSchema {
+            name: name.to_string(),
+            columns: HashMap::new(),
+            primary_key: None,
+            indexes: HashMap::new(),
+            inherits: Vec::new(),
+            checks: HashMap::new(),
+            referenced_by,
+        };
+
+        let mut current = HashMap::new();
+        current.insert("orders".to_string(), table("orders", vec!["order_items".to_string()]));
+        current.insert("order_items".to_string(), table("order_items", Vec::new()));
+        let mut desired = HashMap::new();
+        desired.insert("order_items".to_string(), table("order_items", Vec::new()));
+
+        let diff = checker.diff_schemas(&desired, &current, &[]);
+
+        assert!(diff.dataloss_changes.is_empty());
+        assert_eq!(diff.incompatible_changes.len(), 1);
+        let change = &diff.incompatible_changes[0];
+        assert_eq!(change.change_type, ChangeType::DropTable);
+        assert_eq!(change.table, "orders");
+        assert!(change.reason.as_ref().unwrap().contains("order_items"));
+    }
+
+    #[test]
+    fn test_diff_drop_referenced_table_with_its_dependents() {
+        let checker = SchemaDiffChecker::new();
+        let table = |name: &str, referenced_by: Vec<String>| TableSchema {
+            name: name.to_string(),
+            columns: HashMap::new(),
+            primary_key: None,
+            indexes: HashMap::new(),
+            inherits: Vec::new(),
+            checks: HashMap::new(),
+            referenced_by,
+        };
+
+        let mut current = HashMap::new();
+        current.insert("orders".to_string(), table("orders", vec!["order_items".to_string()]));
+        current.insert("order_items".to_string(), table("order_items", Vec::new()));
+
+        // Both go away, so the foreign key goes with them
+        let diff = checker.diff_schemas(&HashMap::new(), &current, &[]);
+
+        assert!(diff.incompatible_changes.is_empty());
+        assert_eq!(diff.dataloss_changes.len(), 2);
+        assert!(diff
+            .dataloss_changes
+            .iter()
+            .all(|change| change.change_type == ChangeType::DropTable));
+        let rank = |table: &str| diff.dataloss_changes.iter().find(|c| c.table == table).unwrap().ordinal;
+        assert!(rank("order_items") < rank("orders"));
+    }
+
     #[test]
     fn test_diff_add_column() {
         let checker = SchemaDiffChecker::new();
@@ -761,6 +1678,7 @@ mod tests {
                 character_maximum_length: None,
                 numeric_precision: None,
                 numeric_scale: None,
+                ordinal: 1,
             },
         );
         desired_cols.insert(
@@ -773,6 +1691,7 @@ mod tests {
                 character_maximum_length: Some(255),
                 numeric_precision: None,
                 numeric_scale: None,
+                ordinal: 2,
             },
         );
 
@@ -787,6 +1706,7 @@ mod tests {
                 character_maximum_length: None,
                 numeric_precision: None,
                 numeric_scale: None,
+                ordinal: 1,
             },
         );
 
@@ -796,6 +1716,11 @@ mod tests {
             TableSchema {
                 name: "users".to_string(),
                 columns: desired_cols,
+                primary_key: None,
+                indexes: HashMap::new(),
+                inherits: Vec::new(),
+                checks: HashMap::new(),
+                referenced_by: Vec::new(),
             },
         );
 
@@ -805,14 +1730,670 @@ mod tests {
             TableSchema {
                 name: "users".to_string(),
                 columns: current_cols,
+                primary_key: None,
+                indexes: HashMap::new(),
+                inherits: Vec::new(),
+                checks: HashMap::new(),
+                referenced_by: Vec::new(),
             },
         );
 
-        let diff = checker.diff_schemas(&desired, &current);
+        let diff = checker.diff_schemas(&desired, &current, &[]);
 
         assert!(diff.is_safe());
         assert_eq!(diff.safe_changes.len(), 1);
         assert_eq!(diff.safe_changes[0].change_type, ChangeType::AddColumn);
         assert_eq!(diff.safe_changes[0].column, Some("email".to_string()));
+        assert_eq!(diff.safe_changes[0].ordinal, Some(2));
+    }
+
+    #[test]
+    fn test_desired_columns_keep_declaration_order() {
+        let checker = SchemaDiffChecker::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("users.pssql"),
+            "CREATE TABLE users (\n\
+                 id SERIAL PRIMARY KEY,\n\
+                 name TEXT,\n\
+                 email VARCHAR(255),\n\
+                 created_at TIMESTAMPTZ\n\
+             );",
+        )
+        .unwrap();
+
+        let desired = checker.parse_desired_schema(temp_dir.path()).unwrap();
+        let columns: Vec<(&str, usize)> = desired["users"]
+            .ordered_columns()
+            .iter()
+            .map(|c| (c.name.as_str(), c.ordinal))
+            .collect();
+        assert_eq!(columns, vec![("id", 1), ("name", 2), ("email", 3), ("created_at", 4)]);
+
+        let mut current = desired.clone();
+        let users = current.get_mut("users").unwrap();
+        users.columns.remove("name");
+        users.columns.remove("email");
+        users.columns.get_mut("created_at").unwrap().ordinal = 2;
+
+        let diff = checker.diff_schemas(&desired, &current, &[]);
+        let added: Vec<(Option<&str>, Option<usize>)> = diff
+            .safe_changes
+            .iter()
+            .map(|c| (c.column.as_deref(), c.ordinal))
+            .collect();
+        assert_eq!(added, vec![(Some("email"), Some(3)), (Some("name"), Some(2))]);
+    }
+
+    #[test]
+    fn test_column_order_drift_reported() {
+        let checker = SchemaDiffChecker::new();
+        let column = |name: &str, ordinal: usize| ColumnSchema {
+            ordinal,
+            ..live_column(name, "text", None)
+        };
+        let desired = single_table(&[column("id", 1), column("name", 2), column("email", 3), column("created_at", 4)]);
+
+        // Declared order, one column still to be added at the end: no drift
+        let current = single_table(&[column("id", 1), column("name", 2), column("email", 3)]);
+        assert!(checker.diff_schemas(&desired, &current, &[]).column_order_drift.is_empty());
+
+        // name is declared second but will be appended after created_at
+        let current = single_table(&[column("id", 1), column("email", 2), column("created_at", 3)]);
+        let diff = checker.diff_schemas(&desired, &current, &[]);
+        assert_eq!(diff.column_order_drift.len(), 1);
+        let drift = &diff.column_order_drift[0];
+        assert_eq!(drift.table, "users");
+        assert_eq!(drift.declared, vec!["id", "name", "email", "created_at"]);
+        assert_eq!(drift.actual, vec!["id", "email", "created_at", "name"]);
+        assert_eq!(drift.preserved, vec!["id", "email", "created_at"]);
+
+        // Drift is informational, not a change
+        assert_eq!(diff.safe_changes.len(), 1);
+        assert_eq!(diff.safe_changes[0].change_type, ChangeType::AddColumn);
+    }
+
+    #[test]
+    fn test_unmanaged_table_not_dropped() {
+        let checker = SchemaDiffChecker::new();
+
+        let desired = HashMap::new();
+        let mut current = HashMap::new();
+        for name in ["audit_log", "spatial_ref_sys", "old_table"] {
+            current.insert(
+                name.to_string(),
+                TableSchema {
+                    name: name.to_string(),
+                    columns: HashMap::new(),
+                    primary_key: None,
+                    indexes: HashMap::new(),
+                    inherits: Vec::new(),
+                    checks: HashMap::new(),
+                    referenced_by: Vec::new(),
+                },
+            );
+        }
+
+        let unmanaged = vec!["audit_log".to_string()];
+        let diff = checker.diff_schemas(&desired, &current, &unmanaged);
+
+        // audit_log is allow-listed, spatial_ref_sys belongs to PostGIS
+        assert_eq!(diff.dataloss_changes.len(), 1);
+        assert_eq!(diff.dataloss_changes[0].table, "old_table");
+        assert_eq!(diff.dataloss_changes[0].change_type, ChangeType::DropTable);
+    }
+
+    #[test]
+    fn test_load_unmanaged_tables() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(UNMANAGED_TABLES_FILE),
+            "# Written by the audit service\naudit_log\n\nReports.Monthly\n",
+        )
+        .unwrap();
+
+        let unmanaged = SchemaDiffChecker::load_unmanaged_tables(temp_dir.path());
+        assert_eq!(unmanaged, vec!["audit_log", "reports.monthly"]);
+    }
+
+    fn live_column(name: &str, data_type: &str, length: Option<i32>) -> ColumnSchema {
+        ColumnSchema {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: true,
+            column_default: None,
+            character_maximum_length: length,
+            numeric_precision: None,
+            numeric_scale: None,
+            ordinal: 1,
+        }
+    }
+
+    fn single_table(columns: &[ColumnSchema]) -> HashMap<String, TableSchema> {
+        let columns = columns.iter().map(|c| (c.name.clone(), c.clone())).collect();
+        HashMap::from([(
+            "users".to_string(),
+            TableSchema {
+                name: "users".to_string(),
+                columns,
+                primary_key: None,
+                indexes: HashMap::new(),
+                inherits: Vec::new(),
+                checks: HashMap::new(),
+                referenced_by: Vec::new(),
+            },
+        )])
+    }
+
+    #[test]
+    fn test_one_to_one_rename_detected() {
+        let checker = SchemaDiffChecker::new();
+        let desired = single_table(&[live_column("id", "integer", None), live_column("full_name", "text", None)]);
+        let current = single_table(&[live_column("id", "integer", None), live_column("name", "text", None)]);
+
+        // Off by default: drop + add
+        let diff = checker.diff_schemas(&desired, &current, &[]);
+        assert_eq!(diff.dataloss_changes[0].change_type, ChangeType::DropColumn);
+        assert_eq!(diff.safe_changes[0].change_type, ChangeType::AddColumn);
+
+        let diff = checker.diff_schemas_with_renames(&desired, &current, &[], true);
+        assert!(diff.is_safe());
+        assert_eq!(diff.safe_changes.len(), 1);
+        let rename = &diff.safe_changes[0];
+        assert_eq!(rename.change_type, ChangeType::RenameColumn);
+        assert_eq!(rename.column.as_deref(), Some("full_name"));
+        assert_eq!(rename.from_type.as_deref(), Some("name"));
+        assert_eq!(rename.to_type.as_deref(), Some("full_name"));
+
+        // A compatible type change comes with the rename, under the new name
+        let desired = single_table(&[live_column("id", "integer", None), live_column("full_name", "varchar", Some(200))]);
+        let current = single_table(&[live_column("id", "integer", None), live_column("name", "varchar", Some(100))]);
+        let diff = checker.diff_schemas_with_renames(&desired, &current, &[], true);
+        let kinds: Vec<_> = diff.safe_changes.iter().map(|c| (&c.change_type, c.column.as_deref())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (&ChangeType::RenameColumn, Some("full_name")),
+                (&ChangeType::ModifyColumnType, Some("full_name")),
+            ]
+        );
+
+        // Incompatible types are not a rename
+        let desired = single_table(&[live_column("id", "integer", None), live_column("born", "date", None)]);
+        let current = single_table(&[live_column("id", "integer", None), live_column("name", "text", None)]);
+        let diff = checker.diff_schemas_with_renames(&desired, &current, &[], true);
+        assert!(diff.safe_changes.iter().all(|c| c.change_type != ChangeType::RenameColumn));
+    }
+
+    #[test]
+    fn test_ambiguous_rename_not_detected() {
+        let checker = SchemaDiffChecker::new();
+        let desired = single_table(&[live_column("id", "integer", None), live_column("full_name", "text", None)]);
+        let current = single_table(&[
+            live_column("id", "integer", None),
+            live_column("first_name", "text", None),
+            live_column("last_name", "text", None),
+        ]);
+
+        let diff = checker.diff_schemas_with_renames(&desired, &current, &[], true);
+        assert!(diff.safe_changes.iter().all(|c| c.change_type != ChangeType::RenameColumn));
+        assert_eq!(diff.safe_changes.len(), 1);
+        assert_eq!(diff.safe_changes[0].change_type, ChangeType::AddColumn);
+        assert_eq!(diff.dataloss_changes.len(), 2);
+        assert!(diff.dataloss_changes.iter().all(|c| c.change_type == ChangeType::DropColumn));
+    }
+
+    #[test]
+    fn test_diff_order_is_deterministic() {
+        let checker = SchemaDiffChecker::new();
+        // Fresh HashMaps each time, so iteration order differs between runs
+        let schemas = || {
+            let table = |name: &str, columns: &[ColumnSchema]| {
+                let columns = columns.iter().map(|c| (c.name.clone(), c.clone())).collect();
+                (name.to_string(), TableSchema { name: name.to_string(), columns, primary_key: None, indexes: HashMap::new(), inherits: Vec::new(), checks: HashMap::new(), referenced_by: Vec::new() })
+            };
+            let desired: HashMap<_, _> = [
+                table("accounts", &[live_column("id", "integer", None), live_column("email", "text", None)]),
+                table("users", &[live_column("zip", "text", None), live_column("bio", "text", None)]),
+                table("orders", &[]),
+                table("invoices", &[]),
+            ]
+            .into_iter()
+            .collect();
+            let current: HashMap<_, _> = [
+                table("accounts", &[live_column("id", "integer", None), live_column("legacy", "text", None)]),
+                table("users", &[live_column("name", "text", None)]),
+                table("audit", &[]),
+                table("archive", &[]),
+            ]
+            .into_iter()
+            .collect();
+            (desired, current)
+        };
+
+        let summary = |diff: &SchemaDiff| -> Vec<(String, Option<String>, ChangeType)> {
+            diff.safe_changes
+                .iter()
+                .chain(&diff.dataloss_changes)
+                .chain(&diff.incompatible_changes)
+                .map(|c| (c.table.clone(), c.column.clone(), c.change_type.clone()))
+                .collect()
+        };
+
+        let (desired, current) = schemas();
+        let first = summary(&checker.diff_schemas(&desired, &current, &[]));
+        for _ in 0..10 {
+            let (desired, current) = schemas();
+            assert_eq!(summary(&checker.diff_schemas(&desired, &current, &[])), first);
+        }
+
+        let col = |c: &str| Some(c.to_string());
+        assert_eq!(
+            first,
+            vec![
+                ("accounts".to_string(), col("email"), ChangeType::AddColumn),
+                ("invoices".to_string(), None, ChangeType::CreateTable),
+                ("orders".to_string(), None, ChangeType::CreateTable),
+                ("users".to_string(), col("bio"), ChangeType::AddColumn),
+                ("users".to_string(), col("zip"), ChangeType::AddColumn),
+                ("accounts".to_string(), col("legacy"), ChangeType::DropColumn),
+                ("archive".to_string(), None, ChangeType::DropTable),
+                ("audit".to_string(), None, ChangeType::DropTable),
+                ("users".to_string(), col("name"), ChangeType::DropColumn),
+            ]
+        );
+    }
+
+    fn diff_primary_keys(desired_pk: Option<&[&str]>, current_pk: Option<&[&str]>) -> SchemaDiff {
+        let table = |pk: Option<&[&str]>| {
+            let columns = ["tenant_id", "id"]
+                .iter()
+                .map(|name| (name.to_string(), live_column(name, "integer", None)))
+                .collect();
+            let mut tables = HashMap::new();
+            tables.insert(
+                "orders".to_string(),
+                TableSchema {
+                    name: "orders".to_string(),
+                    columns,
+                    primary_key: pk.map(|cols| cols.iter().map(|c| c.to_string()).collect()),
+                    indexes: HashMap::new(),
+                    inherits: Vec::new(),
+                    checks: HashMap::new(),
+                    referenced_by: Vec::new(),
+                },
+            );
+            tables
+        };
+
+        SchemaDiffChecker::new().diff_schemas(&table(desired_pk), &table(current_pk), &[])
+    }
+
+    #[test]
+    fn test_primary_key_added() {
+        let diff = diff_primary_keys(Some(&["id"]), None);
+
+        assert_eq!(diff.dataloss_changes.len(), 1);
+        let change = &diff.dataloss_changes[0];
+        assert_eq!(change.change_type, ChangeType::ModifyPrimaryKey);
+        assert_eq!(change.from_type, None);
+        assert_eq!(change.to_type.as_deref(), Some("(id)"));
+        assert!(change.reason.as_ref().unwrap().contains("duplicate"));
+    }
+
+    #[test]
+    fn test_primary_key_removed() {
+        let diff = diff_primary_keys(None, Some(&["id"]));
+
+        assert_eq!(diff.dataloss_changes.len(), 1);
+        let change = &diff.dataloss_changes[0];
+        assert_eq!(change.change_type, ChangeType::ModifyPrimaryKey);
+        assert_eq!(change.from_type.as_deref(), Some("(id)"));
+        assert_eq!(change.to_type, None);
+        assert!(change.reason.as_ref().unwrap().contains("foreign keys"));
+    }
+
+    #[test]
+    fn test_primary_key_columns_changed_or_reordered() {
+        let diff = diff_primary_keys(Some(&["tenant_id", "id"]), Some(&["id"]));
+        assert_eq!(diff.incompatible_changes.len(), 1);
+        assert_eq!(diff.incompatible_changes[0].change_type, ChangeType::ModifyPrimaryKey);
+        assert_eq!(diff.incompatible_changes[0].from_type.as_deref(), Some("(id)"));
+        assert_eq!(diff.incompatible_changes[0].to_type.as_deref(), Some("(tenant_id, id)"));
+
+        // Same columns in a different order is a different index
+        let diff = diff_primary_keys(Some(&["tenant_id", "id"]), Some(&["id", "tenant_id"]));
+        assert_eq!(diff.incompatible_changes.len(), 1);
+        assert_eq!(diff.incompatible_changes[0].change_type, ChangeType::ModifyPrimaryKey);
+
+        let diff = diff_primary_keys(Some(&["tenant_id", "id"]), Some(&["tenant_id", "id"]));
+        assert!(!diff.has_changes());
+    }
+
+    #[test]
+    fn test_blocking_changes() {
+        let diff = diff_primary_keys(Some(&["tenant_id", "id"]), Some(&["id"]));
+        let blocking = diff.blocking_changes();
+        assert_eq!(blocking.len(), 1);
+        assert_eq!(blocking[0].change_type, ChangeType::ModifyPrimaryKey);
+
+        assert!(SchemaDiff::new().blocking_changes().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_validate_migration_fails_when_no_table_file_parses() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("users.pssql"), "CREATE TABLE users id SERIAL PRIMARY KEY;").unwrap();
+        fs::write(temp_dir.path().join("orders.pssql"), "CRATE TABLE orders (id SERIAL);").unwrap();
+        fs::write(temp_dir.path().join("notes.pssql"), "-- tables go here later\n").unwrap();
+
+        // Never reached: the parse check runs before the database is queried
+        let config = deadpool_postgres::Config {
+            url: Some("postgres://postgres@127.0.0.1:1/postgres".to_string()),
+            ..Default::default()
+        };
+        let pool = config
+            .create_pool(Some(deadpool_postgres::Runtime::Tokio1), tokio_postgres::NoTls)
+            .unwrap();
+
+        let result = SchemaDiffChecker::new()
+            .validate_migration(&pool, "gw_test", temp_dir.path(), true, false)
+            .await;
+        match result {
+            Err(GatewayError::SchemaValidationFailed { issues }) => assert_eq!(
+                issues,
+                vec![
+                    "No table definition could be parsed from orders.pssql",
+                    "No table definition could be parsed from users.pssql",
+                ]
+            ),
+            other => panic!("expected SchemaValidationFailed, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_migration_skips_schema_without_tables() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("notes.pssql"), "-- no tables in this schema\n").unwrap();
+
+        let config = deadpool_postgres::Config {
+            url: Some("postgres://postgres@127.0.0.1:1/postgres".to_string()),
+            ..Default::default()
+        };
+        let pool = config
+            .create_pool(Some(deadpool_postgres::Runtime::Tokio1), tokio_postgres::NoTls)
+            .unwrap();
+
+        let diff = SchemaDiffChecker::new()
+            .validate_migration(&pool, "gw_test", temp_dir.path(), false, false)
+            .await
+            .unwrap();
+        assert!(!diff.has_changes());
+    }
+
+    #[test]
+    fn test_desired_primary_key_parsed_from_file() {
+        let checker = SchemaDiffChecker::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("orders.pssql"),
+            "CREATE TABLE orders (tenant_id INT NOT NULL, id INT NOT NULL, PRIMARY KEY (tenant_id, id));",
+        )
+        .unwrap();
+
+        let desired = checker.parse_desired_schema(temp_dir.path()).unwrap();
+        assert_eq!(
+            desired["orders"].primary_key,
+            Some(vec!["tenant_id".to_string(), "id".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_inheriting_table_gets_parent_columns() {
+        let checker = SchemaDiffChecker::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("events.pssql"),
+            "CREATE TABLE events (id SERIAL PRIMARY KEY, created_at TIMESTAMPTZ NOT NULL);",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("audit_events.pssql"),
+            "CREATE TABLE audit_events (actor TEXT) INHERITS (events);",
+        )
+        .unwrap();
+
+        let desired = checker.parse_desired_schema(temp_dir.path()).unwrap();
+        let audit = &desired["audit_events"];
+        assert_eq!(audit.inherits, vec!["events".to_string()]);
+        let mut columns: Vec<&str> = audit.columns.keys().map(String::as_str).collect();
+        columns.sort();
+        assert_eq!(columns, vec!["actor", "created_at", "id"]);
+        assert!(desired["events"].inherits.is_empty());
+
+        // Inherited columns come first, as in PostgreSQL
+        let ordered: Vec<(&str, usize)> = audit
+            .ordered_columns()
+            .iter()
+            .map(|c| (c.name.as_str(), c.ordinal))
+            .collect();
+        assert_eq!(ordered, vec![("id", 1), ("created_at", 2), ("actor", 3)]);
+    }
+
+    #[test]
+    fn test_check_constraints_diffed() {
+        let checker = SchemaDiffChecker::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("products.pssql"),
+            "CREATE TABLE products (\n\
+                 id INT PRIMARY KEY,\n\
+                 price NUMERIC CHECK (price > 0),\n\
+                 status VARCHAR(10) CONSTRAINT status_valid CHECK (status IN ('a', 'b')),\n\
+                 qty INT CHECK (qty BETWEEN 1 AND 100),\n\
+                 sku TEXT CHECK (sku <> '')\n\
+             );",
+        )
+        .unwrap();
+        let desired = checker.parse_desired_schema(temp_dir.path()).unwrap();
+
+        // Live table as pg_get_constraintdef reports it: price >= 0 is being
+        // tightened, sku is a new column, legacy_check is no longer declared
+        let mut current = desired.clone();
+        let products = current.get_mut("products").unwrap();
+        products.columns.remove("sku");
+        products.checks = [
+            ("products_price_check", "(price >= (0)::numeric)"),
+            ("status_valid", "((status)::text = ANY ((ARRAY['a'::character varying, 'b'::character varying])::text[]))"),
+            ("products_qty_check", "((qty >= 1) AND (qty <= 100))"),
+            ("legacy_check", "(qty < 1000)"),
+        ]
+        .into_iter()
+        .map(|(name, expression)| {
+            (
+                name.to_string(),
+                CheckSchema { name: name.to_string(), column: None, expression: expression.to_string() },
+            )
+        })
+        .collect();
+
+        let diff = checker.diff_schemas(&desired, &current, &[]);
+        let mut checks: Vec<(ChangeType, &str, ChangeCompatibility)> = diff
+            .safe_changes
+            .iter()
+            .chain(diff.dataloss_changes.iter())
+            .filter(|c| matches!(c.change_type, ChangeType::AddCheck | ChangeType::DropCheck))
+            .map(|c| (c.change_type.clone(), c.column.as_deref().unwrap(), c.compatibility.clone()))
+            .collect();
+        checks.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+
+        assert_eq!(
+            checks,
+            vec![
+                (ChangeType::DropCheck, "legacy_check", ChangeCompatibility::Safe),
+                (ChangeType::AddCheck, "products_price_check", ChangeCompatibility::DataLoss),
+                (ChangeType::DropCheck, "products_price_check", ChangeCompatibility::Safe),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index_added_and_dropped() {
+        let checker = SchemaDiffChecker::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("users.pssql"),
+            "CREATE TABLE users (id INT PRIMARY KEY, email TEXT);\n\
+             CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email ON users (email);\n\
+             -- CREATE INDEX idx_commented_out ON users (id);",
+        )
+        .unwrap();
+        let desired = checker.parse_desired_schema(temp_dir.path()).unwrap();
+        assert_eq!(desired["users"].indexes.len(), 1);
+        assert!(desired["users"].indexes["idx_users_email"].unique);
+
+        let index = |name: &str, unique: bool| IndexSchema {
+            name: name.to_string(),
+            unique,
+            definition: format!("CREATE INDEX {} ON public.users USING btree (id)", name),
+        };
+        let mut current_users = desired["users"].clone();
+        current_users.indexes = [index("idx_users_old", false), index("idx_users_legacy_unique", true)]
+            .into_iter()
+            .map(|i| (i.name.clone(), i))
+            .collect();
+        let current: HashMap<_, _> = [("users".to_string(), current_users)].into_iter().collect();
+
+        let diff = checker.diff_schemas(&desired, &current, &[]);
+
+        let changes: Vec<(ChangeType, &str)> = diff
+            .safe_changes
+            .iter()
+            .map(|c| (c.change_type.clone(), c.column.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![(ChangeType::AddIndex, "idx_users_email"), (ChangeType::DropIndex, "idx_users_old")]
+        );
+        assert_eq!(
+            diff.safe_changes[0].to_type.as_deref(),
+            Some("CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email ON users (email)")
+        );
+
+        // A dropped unique index no longer prevents duplicates
+        assert_eq!(diff.dataloss_changes.len(), 1);
+        assert_eq!(diff.dataloss_changes[0].change_type, ChangeType::DropIndex);
+        assert_eq!(diff.dataloss_changes[0].column.as_deref(), Some("idx_users_legacy_unique"));
+    }
+
+    fn diff_against_live(file_sql: &str, live: ColumnSchema) -> SchemaDiff {
+        let checker = SchemaDiffChecker::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("users.pssql"), file_sql).unwrap();
+
+        let desired = checker.parse_desired_schema(temp_dir.path()).unwrap();
+
+        let mut columns = HashMap::new();
+        columns.insert(live.name.clone(), live);
+        let mut current = HashMap::new();
+        current.insert(
+            "users".to_string(),
+            TableSchema {
+                name: "users".to_string(),
+                columns,
+                primary_key: None,
+                indexes: HashMap::new(),
+                inherits: Vec::new(),
+                checks: HashMap::new(),
+                referenced_by: Vec::new(),
+            },
+        );
+
+        checker.diff_schemas(&desired, &current, &[])
+    }
+
+    #[test]
+    fn test_desired_varchar_length_parsed() {
+        let checker = SchemaDiffChecker::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("prices.pssql"),
+            "CREATE TABLE prices (label VARCHAR(100), amount NUMERIC(10,2));",
+        )
+        .unwrap();
+
+        let desired = checker.parse_desired_schema(temp_dir.path()).unwrap();
+        let prices = &desired["prices"];
+        assert_eq!(prices.columns["label"].data_type, "VARCHAR");
+        assert_eq!(prices.columns["label"].character_maximum_length, Some(100));
+        assert_eq!(prices.columns["label"].full_type(), "VARCHAR(100)");
+        assert_eq!(prices.columns["amount"].full_type(), "NUMERIC(10,2)");
+    }
+
+    #[test]
+    fn test_varchar_widening_from_files_is_safe() {
+        // Live VARCHAR(50), file declares VARCHAR(255)
+        let diff = diff_against_live(
+            "CREATE TABLE users (email VARCHAR(255));",
+            live_column("email", "CHARACTER VARYING", Some(50)),
+        );
+
+        assert!(diff.is_safe());
+        assert_eq!(diff.safe_changes.len(), 1);
+        assert_eq!(diff.safe_changes[0].change_type, ChangeType::ModifyColumnType);
+    }
+
+    #[test]
+    fn test_varchar_narrowing_from_files_is_dataloss() {
+        // Live VARCHAR(255), file declares VARCHAR(50)
+        let diff = diff_against_live(
+            "CREATE TABLE users (email VARCHAR(50));",
+            live_column("email", "CHARACTER VARYING", Some(255)),
+        );
+
+        assert!(!diff.is_safe());
+        assert_eq!(diff.dataloss_changes.len(), 1);
+        assert_eq!(diff.dataloss_changes[0].change_type, ChangeType::ModifyColumnType);
+        assert_eq!(diff.dataloss_changes[0].to_type, Some("VARCHAR(50)".to_string()));
+    }
+
+    #[test]
+    fn test_same_varchar_length_no_change() {
+        let diff = diff_against_live(
+            "CREATE TABLE users (email VARCHAR(255));",
+            live_column("email", "CHARACTER VARYING", Some(255)),
+        );
+
+        assert!(!diff.has_changes());
+    }
+
+    #[test]
+    fn test_array_columns_diff_by_element_type() {
+        let live = || live_column("scores", &array_aware_type("ARRAY", "integer[]"), None);
+
+        let diff = diff_against_live("CREATE TABLE users (scores INTEGER[]);", live());
+        assert!(!diff.has_changes());
+
+        let diff = diff_against_live("CREATE TABLE users (scores BIGINT[]);", live());
+        assert!(diff.is_safe());
+        assert_eq!(diff.safe_changes[0].change_type, ChangeType::ModifyColumnType);
+    }
+
+    #[test]
+    fn test_array_columns_keep_element_modifiers() {
+        let cases = [
+            ("tags VARCHAR(50)[]", "tags", "character varying(50)[]"),
+            ("amounts NUMERIC(10,2)[]", "amounts", "numeric(10,2)[]"),
+            ("codes CHAR(3)[]", "codes", "character(3)[]"),
+        ];
+        for (column_sql, name, formatted) in cases {
+            let live = live_column(name, &array_aware_type("ARRAY", formatted), None);
+            let diff = diff_against_live(&format!("CREATE TABLE users ({});", column_sql), live);
+            assert!(!diff.has_changes(), "{} reported as changed", column_sql);
+        }
+
+        let live = live_column("tags", &array_aware_type("ARRAY", "character varying(50)[]"), None);
+        let diff = diff_against_live("CREATE TABLE users (tags VARCHAR(20)[]);", live);
+        assert!(diff.has_changes());
     }
 }
@@ -2,13 +2,26 @@
 //!
 //! Handles installation of PostgreSQL extensions like uuid-ossp, pgvector, etc.
 //! Extensions are defined in the `extensions/` folder with one file per extension.
+//! An installed extension whose file pins a newer version is upgraded with
+//! `ALTER EXTENSION ... UPDATE TO`.
 
 use crate::error::{GatewayError, Result};
+use crate::schema::changelog::ChangelogManager;
+use crate::schema::dependency::DependencyAnalyzer;
 use deadpool_postgres::Pool;
+use std::cmp::Ordering;
 use std::fs;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
 
+/// File (in a schema's extensions folder) listing extensions that may be
+/// installed without being declared, one name per line. It is not an
+/// extension file itself.
+pub const ALLOWED_EXTENSIONS_FILE: &str = "allowed_extensions.txt";
+
+/// Extensions present in every database, never reported as undeclared
+const BUILTIN_EXTENSIONS: &[&str] = &["plpgsql"];
+
 /// Represents a PostgreSQL extension to be installed
 #[derive(Debug, Clone)]
 pub struct Extension {
@@ -46,7 +59,7 @@ impl ExtensionManager {
             })?;
 
             let path = entry.path();
-            if path.is_file() {
+            if path.is_file() && entry.file_name() != ALLOWED_EXTENSIONS_FILE {
                 if let Some(ext) = path.extension() {
                     if ext == "pssql" || ext == "pgsql" || ext == "sql" || ext == "txt" {
                         files.push(path);
@@ -61,6 +74,14 @@ impl ExtensionManager {
         Ok(files)
     }
 
+    /// Extensions that may be installed without a file: the built-in ones
+    /// plus those listed in `allowed_extensions.txt`
+    pub fn allowed_undeclared(&self, extensions_dir: &Path) -> Vec<String> {
+        let mut allowed = DependencyAnalyzer::load_table_list(&extensions_dir.join(ALLOWED_EXTENSIONS_FILE));
+        allowed.extend(BUILTIN_EXTENSIONS.iter().map(|name| name.to_string()));
+        allowed
+    }
+
     /// Parse extension definition from file
     ///
     /// Simple format - just the extension name in filename:
@@ -101,11 +122,16 @@ impl ExtensionManager {
 
     /// Install extensions in the database
     /// Returns the number of extensions installed
+    ///
+    /// An installed extension is upgraded when its file pins a newer version
+    /// than `pg_extension.extversion`, and the upgrade is recorded in the
+    /// changelog; otherwise it is skipped.
     pub async fn install_extensions(
         &self,
         pool: &Pool,
         database: &str,
         extensions_dir: &Path,
+        changelog: &ChangelogManager,
     ) -> Result<usize> {
         let extension_files = self.find_extension_files(extensions_dir)?;
 
@@ -126,17 +152,28 @@ impl ExtensionManager {
         })?;
 
         let mut installed = 0;
+        let mut upgraded = Vec::new();
         let mut skipped = 0;
 
         for file_path in &extension_files {
             let extension = self.parse_extension(file_path)?;
 
             // Check if extension already exists
-            let exists = self.extension_exists(&client, &extension.name).await?;
-
-            if exists {
-                debug!("Extension {} already installed, skipping", extension.name);
-                skipped += 1;
+            if let Some(current) = self.installed_version(&client, &extension.name).await? {
+                match extension.version.as_deref() {
+                    Some(target) if compare_versions(target, &current) == Ordering::Greater => {
+                        self.upgrade_extension(&client, database, &extension, &current, target)
+                            .await?;
+                        upgraded.push(extension);
+                    }
+                    _ => {
+                        debug!(
+                            "Extension {} already installed (version {}), skipping",
+                            extension.name, current
+                        );
+                        skipped += 1;
+                    }
+                }
                 continue;
             }
 
@@ -173,29 +210,105 @@ impl ExtensionManager {
             }
         }
 
+        // Logged with the connection released, so a small pool does not wait on itself
+        drop(client);
+        for extension in &upgraded {
+            changelog
+                .log_extension_installed(
+                    pool,
+                    database,
+                    &extension.name,
+                    extension.version.as_deref(),
+                    extension.schema.as_deref(),
+                )
+                .await
+                .ok();
+        }
+
         info!(
-            "Extension installation complete for {}: {} installed, {} skipped",
-            database, installed, skipped
+            "Extension installation complete for {}: {} installed, {} upgraded, {} skipped",
+            database,
+            installed,
+            upgraded.len(),
+            skipped
         );
 
         Ok(installed)
     }
 
-    /// Check if an extension is already installed
-    async fn extension_exists(
+    /// Installed version of an extension, or None when it is not installed
+    async fn installed_version(
         &self,
         client: &deadpool_postgres::Object,
         extension_name: &str,
-    ) -> Result<bool> {
+    ) -> Result<Option<String>> {
         let row = client
             .query_opt(
-                "SELECT 1 FROM pg_extension WHERE extname = $1",
+                "SELECT extversion FROM pg_extension WHERE extname = $1",
                 &[&extension_name],
             )
             .await
             .unwrap_or(None);
 
-        Ok(row.is_some())
+        Ok(row.map(|r| r.get(0)))
+    }
+
+    /// Update an installed extension to `target`
+    ///
+    /// Fails with ExtensionNotAvailable when the server has no `target`
+    /// version of the extension.
+    async fn upgrade_extension(
+        &self,
+        client: &deadpool_postgres::Object,
+        database: &str,
+        extension: &Extension,
+        current: &str,
+        target: &str,
+    ) -> Result<()> {
+        let available: Vec<String> = client
+            .query(
+                "SELECT version FROM pg_available_extension_versions WHERE name = $1",
+                &[&extension.name],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "pg_available_extension_versions".to_string(),
+                cause: e.to_string(),
+            })?
+            .iter()
+            .map(|r| r.get(0))
+            .collect();
+
+        if !available.iter().any(|v| v == target) {
+            let mut available = available;
+            available.sort_by(|a, b| compare_versions(a, b));
+            return Err(GatewayError::ExtensionNotAvailable {
+                extension: extension.name.clone(),
+                cause: format!(
+                    "version {} is not available on this server (installed: {}, available: {})",
+                    target,
+                    current,
+                    if available.is_empty() { "none".to_string() } else { available.join(", ") }
+                ),
+            });
+        }
+
+        let sql = build_update_extension_sql(&extension.name, target);
+        client
+            .execute(&sql, &[])
+            .await
+            .map_err(|e| GatewayError::ExtensionInstallFailed {
+                database: database.to_string(),
+                extension: extension.name.clone(),
+                cause: format!("upgrade from {} to {}: {}", current, target, e),
+            })?;
+
+        info!(
+            "Upgraded extension {} in database {} from {} to {}",
+            extension.name, database, current, target
+        );
+        Ok(())
     }
 
     /// Build CREATE EXTENSION SQL statement
@@ -238,6 +351,35 @@ impl ExtensionManager {
     }
 }
 
+/// Build ALTER EXTENSION ... UPDATE TO SQL statement
+fn build_update_extension_sql(name: &str, version: &str) -> String {
+    format!(
+        "ALTER EXTENSION \"{}\" UPDATE TO '{}'",
+        name,
+        version.replace('\'', "''")
+    )
+}
+
+/// Compare extension versions ("0.5.0" < "0.5.1" < "0.10.0")
+///
+/// Versions are compared part by part on `.`, `-` and `_`; numeric parts
+/// compare as numbers, others as text, and a missing part sorts first.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<String> { v.split(['.', '-', '_']).map(str::to_string).collect() };
+    let (a, b) = (parts(a), parts(b));
+
+    for (x, y) in a.iter().zip(&b) {
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
 impl Default for ExtensionManager {
     fn default() -> Self {
         Self::new()
@@ -247,6 +389,7 @@ impl Default for ExtensionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schema::test_db::test_pool;
     use std::io::Write;
     use tempfile::TempDir;
 
@@ -312,6 +455,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("0.5.1", "0.5.0"), Ordering::Greater);
+        assert_eq!(compare_versions("0.10.0", "0.9.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.1", "1.1"), Ordering::Equal);
+        assert_eq!(compare_versions("1.1", "1.1.1"), Ordering::Less);
+        assert_eq!(compare_versions("2.0", "10.0"), Ordering::Less);
+        assert_eq!(
+            build_update_extension_sql("vector", "0.7.0"),
+            "ALTER EXTENSION \"vector\" UPDATE TO '0.7.0'"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_installed_extension_upgraded_to_pinned_version() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute("DROP EXTENSION IF EXISTS isn; CREATE EXTENSION isn VERSION '1.1'")
+            .await
+            .unwrap();
+        let changelog = ChangelogManager::new();
+        changelog.ensure_changelog_table(&pool, "gw_test").await.unwrap();
+
+        let manager = ExtensionManager::new();
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("isn.sql");
+        let version = || async {
+            let row = client
+                .query_one("SELECT extversion FROM pg_extension WHERE extname = 'isn'", &[])
+                .await
+                .unwrap();
+            row.get::<_, String>(0)
+        };
+
+        // Newer version pinned: upgraded and recorded
+        fs::write(&file_path, "-- version: 1.2\n").unwrap();
+        let installed = manager
+            .install_extensions(&pool, "gw_test", temp_dir.path(), &changelog)
+            .await
+            .unwrap();
+        assert_eq!(installed, 0);
+        assert_eq!(version().await, "1.2");
+        let entries = changelog
+            .get_entries_by_type(&pool, "gw_test", "extension_installed", 1)
+            .await
+            .unwrap();
+        assert_eq!(entries[0].object_name, "isn");
+        assert_eq!(entries[0].change_detail.as_ref().unwrap()["version"], "1.2");
+
+        // Older version pinned: left alone
+        fs::write(&file_path, "-- version: 1.1\n").unwrap();
+        manager
+            .install_extensions(&pool, "gw_test", temp_dir.path(), &changelog)
+            .await
+            .unwrap();
+        assert_eq!(version().await, "1.2");
+
+        // Version the server does not have
+        fs::write(&file_path, "-- version: 9.9\n").unwrap();
+        let err = manager
+            .install_extensions(&pool, "gw_test", temp_dir.path(), &changelog)
+            .await
+            .unwrap_err();
+        match err {
+            GatewayError::ExtensionNotAvailable { extension, cause } => {
+                assert_eq!(extension, "isn");
+                assert!(cause.contains("version 9.9 is not available"), "{}", cause);
+                assert!(cause.contains("1.1, 1.2"), "{}", cause);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        client.batch_execute("DROP EXTENSION isn").await.unwrap();
+    }
+
     #[test]
     fn test_find_extension_files() {
         let manager = ExtensionManager::new();
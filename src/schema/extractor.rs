@@ -1,8 +1,7 @@
 use crate::error::{GatewayError, Result};
-use flate2::read::GzDecoder;
+use crate::schema::archive::read_archive;
 use std::fs::{self};
 use std::path::{Path, PathBuf};
-use tar::Archive;
 use tempfile::TempDir;
 use tracing::{debug, info};
 
@@ -19,18 +18,20 @@ impl SchemaExtractor {
 
         let extracted_path = temp_dir.path().to_path_buf();
 
-        // Create a GzDecoder to decompress
-        let decoder = GzDecoder::new(data);
-
-        // Create a tar archive reader
-        let mut archive = Archive::new(decoder);
-
-        // Extract all files
-        archive
-            .unpack(&extracted_path)
-            .map_err(|e| GatewayError::SchemaExtractionFailed {
-                cause: format!("Failed to extract tar.gz: {}", e),
+        // Extract all files (tar.gz or zip)
+        for entry in read_archive(data)? {
+            let target = extracted_path.join(&entry.path);
+            if entry.is_dir {
+                fs::create_dir_all(&target).ok();
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).ok();
+            }
+            fs::write(&target, &entry.data).map_err(|e| GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to extract {}: {}", entry.path.display(), e),
             })?;
+        }
 
         info!("Extracted schema to {:?}", extracted_path);
 
@@ -64,6 +65,18 @@ impl SchemaExtractor {
         self.find_postgresql_subdir("types")
     }
 
+    pub fn policies_dir(&self) -> PathBuf {
+        self.find_postgresql_subdir("policies")
+    }
+
+    pub fn matviews_dir(&self) -> PathBuf {
+        self.find_postgresql_subdir("matviews")
+    }
+
+    pub fn grants_dir(&self) -> PathBuf {
+        self.find_postgresql_subdir("grants")
+    }
+
     fn find_postgresql_subdir(&self, subdir: &str) -> PathBuf {
         // First try: direct postgresql/<subdir>
         let direct = self.extracted_path.join("postgresql").join(subdir);
@@ -91,6 +104,12 @@ impl SchemaExtractor {
             }
         }
 
+        // Archive without the postgresql/ wrapper
+        let unwrapped = self.extracted_path.join(subdir);
+        if unwrapped.exists() {
+            return unwrapped;
+        }
+
         // Return the expected path even if it doesn't exist
         direct
     }
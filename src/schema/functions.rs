@@ -3,9 +3,13 @@
 //! Handles function deployment with proper signature change detection.
 //! When a function signature changes (parameter rename, type change, etc.),
 //! the old function is dropped before deploying the new one.
+//!
+//! Deployed bodies can also be compared with their declared source
+//! (`pg_get_functiondef`), catching functions altered by hand in the database.
 
 use crate::error::{GatewayError, Result};
 use deadpool_postgres::Pool;
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -49,6 +53,22 @@ impl FunctionSignature {
     }
 }
 
+/// Result of comparing deployed function bodies with their declared source
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FunctionVerification {
+    pub checked: Vec<String>,
+    /// Declared but not found in the database
+    pub missing: Vec<String>,
+    /// Deployed body differs from the declared one
+    pub drifted: Vec<String>,
+}
+
+impl FunctionVerification {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.drifted.is_empty()
+    }
+}
+
 pub struct FunctionDeployer;
 
 impl FunctionDeployer {
@@ -272,6 +292,21 @@ impl FunctionDeployer {
         whitespace_re.replace_all(sql, " ").trim().to_lowercase()
     }
 
+    /// Normalized body between the `AS $tag$ ... $tag$` quotes of a function definition
+    ///
+    /// Works on both the declared file and `pg_get_functiondef` output, whose
+    /// header PostgreSQL rewrites but whose body it keeps verbatim. Bodies in
+    /// single quotes are not handled.
+    fn extract_body(&self, sql: &str) -> Option<String> {
+        let open_re = regex::Regex::new(r"(?i)\bAS\s+(\$[A-Za-z_0-9]*\$)").unwrap();
+        let open = open_re.captures(sql)?;
+        let tag = open.get(1)?;
+        let rest = &sql[tag.end()..];
+        let body = &rest[..rest.find(tag.as_str())?];
+
+        Some(self.normalize_for_checksum(&self.remove_comments(body)))
+    }
+
     /// Compute checksum of function body (normalized)
     fn compute_body_checksum(&self, sql: &str) -> String {
         let normalized = self.normalize_for_checksum(sql);
@@ -530,6 +565,78 @@ impl FunctionDeployer {
         Ok(())
     }
 
+    /// Compare each declared function's body with the one deployed in the database
+    pub async fn verify_function_bodies(
+        &self,
+        pool: &Pool,
+        database: &str,
+        functions_dir: &Path,
+    ) -> Result<FunctionVerification> {
+        let mut verification = FunctionVerification::default();
+
+        let function_files = self.find_function_files(functions_dir)?;
+        if function_files.is_empty() {
+            return Ok(verification);
+        }
+
+        let client = pool
+            .get()
+            .await
+            .map_err(|e| GatewayError::ConnectionFailed {
+                database: database.to_string(),
+                cause: e.to_string(),
+            })?;
+
+        for file_path in &function_files {
+            let sql = fs::read_to_string(file_path).map_err(|e| {
+                GatewayError::SchemaExtractionFailed {
+                    cause: format!("Failed to read file {:?}: {}", file_path, e),
+                }
+            })?;
+            let (Some(signature), Some(declared)) =
+                (self.parse_signature(&sql), self.extract_body(&sql))
+            else {
+                debug!("Cannot compare body of {:?}, skipping", file_path);
+                continue;
+            };
+
+            // PostgreSQL resolves the parameter type names, so aliases match
+            let regprocedure = if signature.parameters.is_empty() {
+                format!("{}()", signature.name)
+            } else {
+                signature.drop_signature()
+            };
+            let row = client
+                .query_one(
+                    "SELECT pg_get_functiondef(to_regprocedure($1))",
+                    &[&regprocedure],
+                )
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: "function body verification".to_string(),
+                    cause: e.to_string(),
+                })?;
+
+            let name = signature.drop_signature();
+            verification.checked.push(name.clone());
+            match row.get::<_, Option<String>>(0) {
+                None => verification.missing.push(name),
+                Some(definition) => {
+                    if self.extract_body(&definition).as_deref() != Some(declared.as_str()) {
+                        warn!(
+                            "Function {} in {} differs from its declared source",
+                            name, database
+                        );
+                        verification.drifted.push(name);
+                    }
+                }
+            }
+        }
+
+        Ok(verification)
+    }
+
     pub async fn deploy_single_function(
         &self,
         pool: &Pool,
@@ -564,6 +671,83 @@ impl Default for FunctionDeployer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schema::test_db::test_pool;
+
+    #[test]
+    fn test_extract_body() {
+        let deployer = FunctionDeployer::new();
+        let declared = r#"
+            CREATE OR REPLACE FUNCTION get_user(p_id INT) RETURNS TEXT AS $$
+            BEGIN
+                -- look up the name
+                RETURN (SELECT name FROM users WHERE id = p_id);
+            END;
+            $$ LANGUAGE plpgsql;
+        "#;
+        // pg_get_functiondef output: rewritten header, same body
+        let deployed = "CREATE OR REPLACE FUNCTION public.get_user(p_id integer)\n RETURNS text\n LANGUAGE plpgsql\nAS $function$\n            BEGIN\n                -- look up the name\n                RETURN (SELECT name FROM users WHERE id = p_id);\n            END;\n            $function$\n";
+
+        assert_eq!(
+            deployer.extract_body(declared).unwrap(),
+            "begin return (select name from users where id = p_id); end;"
+        );
+        assert_eq!(
+            deployer.extract_body(declared),
+            deployer.extract_body(deployed)
+        );
+        assert!(deployer
+            .extract_body("CREATE FUNCTION f() RETURNS INT AS 'SELECT 1' LANGUAGE sql;")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_manually_altered_function_is_drifted() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+
+        let functions_dir = tempfile::TempDir::new().unwrap();
+        let declared = "CREATE OR REPLACE FUNCTION fn_drift_check(p_value INT) RETURNS INT AS $$\nBEGIN\n    RETURN p_value * 2;\nEND;\n$$ LANGUAGE plpgsql;";
+        fs::write(functions_dir.path().join("fn_drift_check.pssql"), declared).unwrap();
+        fs::write(
+            functions_dir.path().join("fn_drift_absent.pssql"),
+            "CREATE OR REPLACE FUNCTION fn_drift_absent() RETURNS INT AS $$ SELECT 1 $$ LANGUAGE sql;",
+        )
+        .unwrap();
+        client.batch_execute(declared).await.unwrap();
+
+        let deployer = FunctionDeployer::new();
+        let verification = deployer
+            .verify_function_bodies(&pool, "gw_test", functions_dir.path())
+            .await
+            .unwrap();
+        assert!(
+            verification.drifted.is_empty(),
+            "{:?}",
+            verification.drifted
+        );
+        assert_eq!(verification.missing, vec!["fn_drift_absent"]);
+
+        // Altered by hand, same signature
+        client
+            .batch_execute(
+                "CREATE OR REPLACE FUNCTION fn_drift_check(p_value INT) RETURNS INT AS $$\nBEGIN\n    RETURN p_value * 3;\nEND;\n$$ LANGUAGE plpgsql;",
+            )
+            .await
+            .unwrap();
+        let verification = deployer
+            .verify_function_bodies(&pool, "gw_test", functions_dir.path())
+            .await
+            .unwrap();
+        assert_eq!(verification.drifted, vec!["fn_drift_check(INT)"]);
+        assert!(!verification.is_ok());
+
+        client
+            .batch_execute("DROP FUNCTION fn_drift_check(INT)")
+            .await
+            .unwrap();
+    }
 
     #[test]
     fn test_parse_simple_function() {
@@ -0,0 +1,555 @@
+//! Migration SQL generator
+//!
+//! Turns a SchemaDiff into executable DDL so a diff can be previewed (and applied)
+//! without maintaining hand-written migrations for every declarative change.
+//!
+//! Statements are emitted in dependency-safe order:
+//! 1. CREATE TABLE (referenced tables first)
+//! 2. ADD COLUMN
+//! 3. DROP CONSTRAINT (CHECK), ALTER COLUMN ... TYPE
+//! 4. SET NOT NULL / DROP NOT NULL, primary keys, CREATE INDEX, ADD CONSTRAINT (CHECK)
+//! 5. DROP INDEX, DROP COLUMN
+//! 6. DROP TABLE
+//!
+//! DataLoss and Incompatible changes are emitted commented out, prefixed with
+//! their reason, so a human has to review them before they can run.
+//!
+//! Column order drift is not part of the diff's changes; `rebuild_plan` turns
+//! it into a data-preserving table rebuild on request.
+
+use crate::error::Result;
+use crate::schema::dependency::DependencyAnalyzer;
+use crate::schema::diff::{ChangeCompatibility, ChangeType, ColumnOrderDrift, SchemaChange, SchemaDiff};
+use crate::schema::sql::{quote_ident, quote_qualified};
+use crate::schema::tables::{TableDefinition, TableDeployer};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Generates ALTER/CREATE/DROP statements from a schema diff
+pub struct MigrationGenerator {
+    /// Table definitions in creation order
+    tables: Vec<TableDefinition>,
+    /// Column definitions as written in the table files, per table
+    column_definitions: HashMap<String, HashMap<String, String>>,
+}
+
+impl MigrationGenerator {
+    /// Build a generator from table definitions already in creation order
+    pub fn new(tables: Vec<TableDefinition>) -> Self {
+        let mut column_definitions = HashMap::new();
+
+        for table in &tables {
+            if let Ok(analysis) = DependencyAnalyzer::analyze_sql(&table.sql) {
+                for table_info in analysis.tables {
+                    let columns: HashMap<String, String> = table_info
+                        .columns
+                        .into_iter()
+                        .map(|col| (col.name, col.definition))
+                        .collect();
+                    column_definitions.insert(table.name.clone(), columns);
+                }
+            }
+        }
+
+        Self {
+            tables,
+            column_definitions,
+        }
+    }
+
+    /// Build a generator from a tables/ directory
+    pub fn from_directory(tables_dir: &Path) -> Result<Self> {
+        let deployer = TableDeployer::new();
+        let mut tables = Vec::new();
+
+        for file in deployer.find_table_files(tables_dir)? {
+            tables.extend(deployer.parse_table_definitions(&file)?);
+        }
+
+        Ok(Self::new(deployer.order_by_dependencies(tables)?))
+    }
+
+    /// Generate migration statements for every change in the diff
+    ///
+    /// Safe changes are executable as-is; DataLoss and Incompatible changes are
+    /// commented out with their reason.
+    pub fn generate(&self, diff: &SchemaDiff) -> Vec<String> {
+        let mut changes: Vec<&SchemaChange> = diff
+            .safe_changes
+            .iter()
+            .chain(diff.dataloss_changes.iter())
+            .chain(diff.incompatible_changes.iter())
+            .collect();
+
+        changes.sort_by_key(|change| {
+            // Dropped tables are no longer declared; the diff ranks them in reverse creation order
+            let rank = match change.change_type {
+                ChangeType::DropTable => change.ordinal.unwrap_or(usize::MAX),
+                _ => self.creation_rank(&change.table),
+            };
+            (
+                Self::phase(&change.change_type),
+                rank,
+                change.table.clone(),
+                change.ordinal,
+                change.column.clone(),
+            )
+        });
+
+        changes
+            .into_iter()
+            .map(|change| {
+                let statement = self.statement(change);
+                match change.compatibility {
+                    ChangeCompatibility::Safe => statement,
+                    ChangeCompatibility::DataLoss => Self::comment_out("DATALOSS", change, &statement),
+                    ChangeCompatibility::Incompatible => {
+                        Self::comment_out("INCOMPATIBLE", change, &statement)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Position of a change type in the emitted script
+    fn phase(change_type: &ChangeType) -> u8 {
+        match change_type {
+            ChangeType::CreateTable => 0,
+            ChangeType::AddColumn | ChangeType::RenameColumn => 1,
+            ChangeType::ModifyColumnType | ChangeType::DropCheck => 2,
+            ChangeType::ModifyColumnNullable | ChangeType::ModifyColumnDefault | ChangeType::ModifyPrimaryKey => 3,
+            ChangeType::AddIndex | ChangeType::AddCheck => 3,
+            ChangeType::DropIndex | ChangeType::DropColumn => 4,
+            ChangeType::DropTable => 5,
+        }
+    }
+
+    /// Position of a table in dependency order (unknown tables sort last)
+    fn creation_rank(&self, table: &str) -> usize {
+        self.tables
+            .iter()
+            .position(|t| t.name == table)
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Build the SQL for a single change
+    fn statement(&self, change: &SchemaChange) -> String {
+        let table = quote_qualified(&change.table);
+        let column = change.column.as_deref().unwrap_or_default();
+        let to_type = change.to_type.as_deref().unwrap_or_default();
+
+        match change.change_type {
+            ChangeType::CreateTable => match self.tables.iter().find(|t| t.name == change.table) {
+                Some(definition) => terminate(&definition.sql),
+                None => format!("-- No definition found for table {}", table),
+            },
+            ChangeType::DropTable => format!("DROP TABLE {};", table),
+            ChangeType::AddColumn => {
+                let definition = self
+                    .column_definitions
+                    .get(&change.table)
+                    .and_then(|columns| columns.get(column))
+                    .cloned()
+                    .unwrap_or_else(|| format!("{} {}", quote_ident(column), to_type));
+                format!("ALTER TABLE {} ADD COLUMN {};", table, definition)
+            }
+            ChangeType::DropColumn => {
+                format!("ALTER TABLE {} DROP COLUMN {};", table, quote_ident(column))
+            }
+            ChangeType::RenameColumn => format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {};",
+                table,
+                quote_ident(change.from_type.as_deref().unwrap_or_default()),
+                quote_ident(to_type)
+            ),
+            ChangeType::ModifyColumnType => {
+                let column = quote_ident(column);
+                format!(
+                    "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::{};",
+                    table, column, to_type, column, to_type
+                )
+            }
+            ChangeType::ModifyColumnNullable => {
+                let action = if to_type == "NOT NULL" {
+                    "SET NOT NULL"
+                } else {
+                    "DROP NOT NULL"
+                };
+                format!(
+                    "ALTER TABLE {} ALTER COLUMN {} {};",
+                    table,
+                    quote_ident(column),
+                    action
+                )
+            }
+            ChangeType::ModifyColumnDefault => format!(
+                "-- Default of {}.{} changed, set it with ALTER COLUMN ... SET DEFAULT",
+                table,
+                quote_ident(column)
+            ),
+            ChangeType::ModifyPrimaryKey => {
+                let mut statements = Vec::new();
+                if change.from_type.is_some() {
+                    // PostgreSQL's default name for the constraint
+                    let bare_table = change.table.rsplit('.').next().unwrap_or(&change.table);
+                    statements.push(format!(
+                        "ALTER TABLE {} DROP CONSTRAINT {};",
+                        table,
+                        quote_ident(&format!("{}_pkey", bare_table))
+                    ));
+                }
+                if let Some(columns) = &change.to_type {
+                    statements.push(format!("ALTER TABLE {} ADD PRIMARY KEY {};", table, columns));
+                }
+                statements.join("\n")
+            }
+            ChangeType::AddIndex => terminate(to_type),
+            ChangeType::DropIndex => {
+                // Indexes live in their table's schema
+                let index = match change.table.split_once('.') {
+                    Some((schema, _)) => format!("{}.{}", quote_ident(schema), quote_ident(column)),
+                    None => quote_ident(column),
+                };
+                format!("DROP INDEX {};", index)
+            }
+            ChangeType::AddCheck => format!(
+                "ALTER TABLE {} ADD CONSTRAINT {} CHECK ({});",
+                table,
+                quote_ident(column),
+                to_type
+            ),
+            ChangeType::DropCheck => {
+                format!("ALTER TABLE {} DROP CONSTRAINT {};", table, quote_ident(column))
+            }
+        }
+    }
+
+    /// Statements rebuilding a table so its columns follow the declared order
+    ///
+    /// The rows are copied to a temporary table, the table is dropped and
+    /// created again from its file, and the preserved columns are copied back
+    /// (identity values included); serial/identity sequences are then moved
+    /// past the copied values. The `DROP TABLE` fails rather than cascades when
+    /// views or foreign keys depend on the table. Run the plan in one
+    /// transaction.
+    pub fn rebuild_plan(&self, drift: &ColumnOrderDrift) -> Vec<String> {
+        let table = quote_qualified(&drift.table);
+        let Some(definition) = self.tables.iter().find(|t| t.name == drift.table) else {
+            return vec![format!("-- No definition found for table {}, cannot rebuild it", table)];
+        };
+
+        let copy = quote_ident(&format!("_gw_reorder_{}", drift.table.replace('.', "_")));
+        let columns = drift
+            .preserved
+            .iter()
+            .map(|c| quote_ident(c))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut plan = vec![
+            format!(
+                "-- Rebuild {} in declared column order ({})",
+                table,
+                drift.declared.join(", ")
+            ),
+            format!("CREATE TEMP TABLE {} AS SELECT * FROM {};", copy, table),
+            format!("DROP TABLE {};", table),
+            terminate(&definition.sql),
+        ];
+        if !drift.preserved.is_empty() {
+            plan.push(format!(
+                "INSERT INTO {} ({}) OVERRIDING SYSTEM VALUE SELECT {} FROM {};",
+                table, columns, columns, copy
+            ));
+        }
+        // setval ignores columns without a sequence (NULL sequence name)
+        for column in drift.preserved.iter().filter(|c| self.is_integer_column(&drift.table, c)) {
+            let name = quote_ident(column);
+            plan.push(format!(
+                "SELECT setval(pg_get_serial_sequence('{}', '{}'), MAX({})) FROM {};",
+                table.replace('\'', "''"),
+                column.replace('\'', "''"),
+                name,
+                table
+            ));
+        }
+        plan.push(format!("DROP TABLE {};", copy));
+
+        plan
+    }
+
+    /// A column whose declared type can be backed by a sequence
+    fn is_integer_column(&self, table: &str, column: &str) -> bool {
+        const INTEGER_TYPES: &[&str] = &[
+            "smallint", "integer", "int", "int2", "int4", "int8", "bigint", "smallserial", "serial",
+            "serial2", "serial4", "serial8", "bigserial",
+        ];
+
+        self.column_definitions
+            .get(table)
+            .and_then(|columns| columns.get(column))
+            .and_then(|definition| definition.split_whitespace().nth(1))
+            .is_some_and(|ty| INTEGER_TYPES.contains(&ty.to_lowercase().as_str()))
+    }
+
+    /// Comment out a statement that needs human review
+    fn comment_out(label: &str, change: &SchemaChange, statement: &str) -> String {
+        let reason = change.reason.as_deref().unwrap_or("requires manual review");
+        let mut lines = vec![format!("-- {}: {}", label, reason)];
+        lines.extend(statement.lines().map(|line| {
+            if line.starts_with("--") {
+                line.to_string()
+            } else {
+                format!("-- {}", line)
+            }
+        }));
+        lines.join("\n")
+    }
+}
+
+/// Ensure a statement ends with a semicolon
+fn terminate(sql: &str) -> String {
+    let sql = sql.trim();
+    if sql.ends_with(';') {
+        sql.to_string()
+    } else {
+        format!("{};", sql)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn change(
+        table: &str,
+        change_type: ChangeType,
+        column: Option<&str>,
+        to_type: Option<&str>,
+        compatibility: ChangeCompatibility,
+        reason: Option<&str>,
+    ) -> SchemaChange {
+        SchemaChange {
+            table: table.to_string(),
+            change_type,
+            column: column.map(String::from),
+            ordinal: None,
+            from_type: None,
+            to_type: to_type.map(String::from),
+            compatibility,
+            reason: reason.map(String::from),
+        }
+    }
+
+    fn generator() -> (TempDir, MigrationGenerator) {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("orders.pssql"),
+            "CREATE TABLE orders (\n    id SERIAL PRIMARY KEY,\n    user_id INT REFERENCES users(id)\n);",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("users.pssql"),
+            "CREATE TABLE users (\n    id SERIAL PRIMARY KEY,\n    email VARCHAR(255) NOT NULL DEFAULT ''\n);",
+        )
+        .unwrap();
+
+        let generator = MigrationGenerator::from_directory(temp_dir.path()).unwrap();
+        (temp_dir, generator)
+    }
+
+    #[test]
+    fn test_generate_orders_statements() {
+        let (_temp_dir, generator) = generator();
+
+        let mut diff = SchemaDiff::new();
+        diff.add_change(change("logs", ChangeType::DropColumn, Some("payload"), None,
+            ChangeCompatibility::DataLoss, Some("Dropping column will delete all data")));
+        diff.add_change(change("orders", ChangeType::CreateTable, None, None,
+            ChangeCompatibility::Safe, None));
+        diff.add_change(change("accounts", ChangeType::ModifyColumnType, Some("balance"),
+            Some("BIGINT"), ChangeCompatibility::Safe, None));
+        diff.add_change(change("users", ChangeType::CreateTable, None, None,
+            ChangeCompatibility::Safe, None));
+
+        let sql = generator.generate(&diff);
+
+        assert_eq!(sql.len(), 4);
+        assert!(sql[0].starts_with("CREATE TABLE users"));
+        assert!(sql[1].starts_with("CREATE TABLE orders"));
+        assert_eq!(
+            sql[2],
+            "ALTER TABLE accounts ALTER COLUMN balance TYPE BIGINT USING balance::BIGINT;"
+        );
+        assert_eq!(
+            sql[3],
+            "-- DATALOSS: Dropping column will delete all data\n-- ALTER TABLE logs DROP COLUMN payload;"
+        );
+    }
+
+    #[test]
+    fn test_dropped_tables_follow_diff_drop_order() {
+        let generator = MigrationGenerator::new(Vec::new());
+
+        let mut diff = SchemaDiff::new();
+        for (table, ordinal) in [("orders", 1), ("order_items", 0)] {
+            let mut drop = change(table, ChangeType::DropTable, None, None,
+                ChangeCompatibility::DataLoss, Some("Dropping table will delete all data"));
+            drop.ordinal = Some(ordinal);
+            diff.add_change(drop);
+        }
+
+        let sql = generator.generate(&diff);
+
+        assert_eq!(sql.len(), 2);
+        assert!(sql[0].ends_with("-- DROP TABLE order_items;"));
+        assert!(sql[1].ends_with("-- DROP TABLE orders;"));
+    }
+
+    #[test]
+    fn test_primary_key_change_is_commented_out() {
+        let (_temp_dir, generator) = generator();
+
+        let mut diff = SchemaDiff::new();
+        let mut pk_change = change("orders", ChangeType::ModifyPrimaryKey, None, Some("(tenant_id, id)"),
+            ChangeCompatibility::Incompatible, Some("Changing primary key columns"));
+        pk_change.from_type = Some("(id)".to_string());
+        diff.add_change(pk_change);
+
+        assert_eq!(
+            generator.generate(&diff),
+            vec!["-- INCOMPATIBLE: Changing primary key columns\n\
+                  -- ALTER TABLE orders DROP CONSTRAINT orders_pkey;\n\
+                  -- ALTER TABLE orders ADD PRIMARY KEY (tenant_id, id);"]
+        );
+    }
+
+    #[test]
+    fn test_index_changes() {
+        let (_temp_dir, generator) = generator();
+
+        let mut diff = SchemaDiff::new();
+        diff.add_change(change("users", ChangeType::AddIndex, Some("idx_users_email"),
+            Some("CREATE INDEX idx_users_email ON users (email)"), ChangeCompatibility::Safe, None));
+        diff.add_change(change("billing.invoices", ChangeType::DropIndex, Some("idx_invoices_old"), None,
+            ChangeCompatibility::Safe, None));
+
+        assert_eq!(
+            generator.generate(&diff),
+            vec![
+                "CREATE INDEX idx_users_email ON users (email);",
+                "DROP INDEX billing.idx_invoices_old;",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changed_check_is_dropped_before_it_is_added() {
+        let (_temp_dir, generator) = generator();
+
+        let mut diff = SchemaDiff::new();
+        diff.add_change(change("users", ChangeType::AddCheck, Some("users_age_check"),
+            Some("age >= 18"), ChangeCompatibility::DataLoss, Some("May fail")));
+        diff.add_change(change("users", ChangeType::DropCheck, Some("users_age_check"), None,
+            ChangeCompatibility::Safe, None));
+
+        assert_eq!(
+            generator.generate(&diff),
+            vec![
+                "ALTER TABLE users DROP CONSTRAINT users_age_check;",
+                "-- DATALOSS: May fail\n-- ALTER TABLE users ADD CONSTRAINT users_age_check CHECK (age >= 18);",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_column_uses_file_definition() {
+        let (_temp_dir, generator) = generator();
+
+        let mut diff = SchemaDiff::new();
+        diff.add_change(change("users", ChangeType::AddColumn, Some("email"),
+            Some("VARCHAR(255)"), ChangeCompatibility::Safe, None));
+        diff.add_change(change("users", ChangeType::ModifyColumnNullable, Some("email"),
+            Some("NULLABLE"), ChangeCompatibility::Safe, None));
+
+        let sql = generator.generate(&diff);
+
+        assert_eq!(
+            sql[0],
+            "ALTER TABLE users ADD COLUMN email VARCHAR(255) NOT NULL DEFAULT '';"
+        );
+        assert_eq!(sql[1], "ALTER TABLE users ALTER COLUMN email DROP NOT NULL;");
+    }
+
+    #[test]
+    fn test_added_columns_follow_declaration_order() {
+        let (_temp_dir, generator) = generator();
+
+        let mut diff = SchemaDiff::new();
+        for (column, ordinal) in [("zip", 2), ("city", 3), ("address", 4)] {
+            diff.add_change(SchemaChange {
+                ordinal: Some(ordinal),
+                ..change("users", ChangeType::AddColumn, Some(column), Some("TEXT"), ChangeCompatibility::Safe, None)
+            });
+        }
+
+        let sql = generator.generate(&diff);
+
+        assert_eq!(
+            sql,
+            vec![
+                "ALTER TABLE users ADD COLUMN zip TEXT;",
+                "ALTER TABLE users ADD COLUMN city TEXT;",
+                "ALTER TABLE users ADD COLUMN address TEXT;",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_column_keeps_check_constraint() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("people.pssql"),
+            "CREATE TABLE people (\n    id SERIAL PRIMARY KEY,\n    age INT CHECK (age >= 0)\n);",
+        )
+        .unwrap();
+        let generator = MigrationGenerator::from_directory(temp_dir.path()).unwrap();
+
+        let mut diff = SchemaDiff::new();
+        diff.add_change(change("people", ChangeType::AddColumn, Some("age"),
+            Some("INT"), ChangeCompatibility::Safe, None));
+
+        let sql = generator.generate(&diff);
+
+        assert_eq!(sql, vec!["ALTER TABLE people ADD COLUMN age INT CHECK (age >= 0);"]);
+    }
+
+    #[test]
+    fn test_rebuild_plan_for_column_order_drift() {
+        let (_temp_dir, generator) = generator();
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let drift = ColumnOrderDrift {
+            table: "users".to_string(),
+            declared: names(&["id", "email"]),
+            actual: names(&["email", "id"]),
+            preserved: names(&["id", "email"]),
+        };
+
+        let plan = generator.rebuild_plan(&drift);
+
+        assert_eq!(
+            plan,
+            vec![
+                "-- Rebuild users in declared column order (id, email)".to_string(),
+                "CREATE TEMP TABLE _gw_reorder_users AS SELECT * FROM users;".to_string(),
+                "DROP TABLE users;".to_string(),
+                "CREATE TABLE users (\n    id SERIAL PRIMARY KEY,\n    email VARCHAR(255) NOT NULL DEFAULT ''\n);".to_string(),
+                "INSERT INTO users (id, email) OVERRIDING SYSTEM VALUE SELECT id, email FROM _gw_reorder_users;".to_string(),
+                "SELECT setval(pg_get_serial_sequence('users', 'id'), MAX(id)) FROM users;".to_string(),
+                "DROP TABLE _gw_reorder_users;".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,558 @@
+//! Privilege manager
+//!
+//! Privileges are declared in the `grants/` folder as `GRANT` and `REVOKE`
+//! statements (any number per file, applied in file order). They are applied
+//! after every other object exists, and table privileges are verified against
+//! `information_schema.role_table_grants`. Grants on other objects (sequences,
+//! functions, schemas, materialized views) and role memberships are applied
+//! but not verified.
+
+use crate::error::{GatewayError, Result};
+use crate::schema::dependency::{qualified_table_name, split_qualified_name};
+use crate::schema::sql::{split_statements, strip_comments};
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Privileges `ALL [PRIVILEGES]` expands to on a table
+const TABLE_PRIVILEGES: &[&str] = &["DELETE", "INSERT", "REFERENCES", "SELECT", "TRIGGER", "TRUNCATE", "UPDATE"];
+
+/// Role specifications that are not role names
+const ROLE_KEYWORDS: &[&str] = &["CURRENT_USER", "SESSION_USER", "CURRENT_ROLE"];
+
+/// A `GRANT` or `REVOKE` statement from the grants folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrantStatement {
+    /// false for REVOKE
+    pub grant: bool,
+    /// Table privileges, uppercased and sorted; empty unless the statement is
+    /// a table-level privilege grant (the only kind that is verified)
+    pub privileges: Vec<String>,
+    /// Qualified table names the privileges apply to
+    pub tables: Vec<String>,
+    /// Role names the statement refers to (grantees, and the granted roles of
+    /// a membership grant), `PUBLIC` for the pseudo-role
+    pub roles: Vec<String>,
+    /// Grantees that table privileges apply to
+    pub grantees: Vec<String>,
+    /// Source statement
+    pub sql: String,
+}
+
+/// Result of comparing declared table privileges against the database
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GrantVerification {
+    pub expected: Vec<String>,
+    /// Declared by GRANT but not held
+    pub missing: Vec<String>,
+    /// Removed by REVOKE but still held
+    pub unexpected: Vec<String>,
+}
+
+impl GrantVerification {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+pub struct GrantManager;
+
+impl GrantManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Find grant files in the grants directory
+    pub fn find_grant_files(&self, grants_dir: &Path) -> Result<Vec<PathBuf>> {
+        if !grants_dir.exists() {
+            debug!(
+                "Grants directory {:?} does not exist, returning empty list",
+                grants_dir
+            );
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+
+        for entry in fs::read_dir(grants_dir).map_err(|e| GatewayError::SchemaExtractionFailed {
+            cause: format!("Failed to read grants directory: {}", e),
+        })? {
+            let entry = entry.map_err(|e| GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to read directory entry: {}", e),
+            })?;
+
+            let path = entry.path();
+            if path.is_file() {
+                if let Some(ext) = path.extension() {
+                    if ext == "pssql" || ext == "pgsql" || ext == "sql" {
+                        files.push(path);
+                    }
+                }
+            }
+        }
+
+        // Sort for consistent ordering
+        files.sort();
+
+        Ok(files)
+    }
+
+    /// Parse every GRANT/REVOKE statement in a file
+    pub fn parse_grants(&self, file_path: &Path) -> Result<Vec<GrantStatement>> {
+        let content = fs::read_to_string(file_path).map_err(|e| {
+            GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to read grant file {:?}: {}", file_path, e),
+            }
+        })?;
+
+        let mut statements = Vec::new();
+        for statement in split_statements(&strip_comments(&content)) {
+            match parse_grant(&statement) {
+                Some(grant) => statements.push(grant),
+                None => {
+                    return Err(GatewayError::SchemaExtractionFailed {
+                        cause: format!(
+                            "Grant file {:?} contains a statement that is not GRANT or REVOKE: {}",
+                            file_path, statement
+                        ),
+                    })
+                }
+            }
+        }
+
+        Ok(statements)
+    }
+
+    /// Parse all grant files in the directory, in order
+    pub fn load_grants(&self, grants_dir: &Path) -> Result<Vec<GrantStatement>> {
+        let mut statements = Vec::new();
+        for file in self.find_grant_files(grants_dir)? {
+            statements.extend(self.parse_grants(&file)?);
+        }
+        Ok(statements)
+    }
+
+    /// Apply every GRANT/REVOKE statement in one transaction
+    ///
+    /// Fails before changing anything if a statement names a role that does
+    /// not exist. Returns the number of statements applied.
+    pub async fn deploy_grants(&self, pool: &Pool, database: &str, grants_dir: &Path) -> Result<usize> {
+        let statements = self.load_grants(grants_dir)?;
+        if statements.is_empty() {
+            debug!("No grants to deploy for database {}", database);
+            return Ok(0);
+        }
+
+        let mut client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let referenced: BTreeSet<&str> = statements
+            .iter()
+            .flat_map(|s| s.roles.iter().map(String::as_str))
+            .filter(|role| *role != "PUBLIC")
+            .collect();
+        let rows = client
+            .query("SELECT rolname::text FROM pg_roles", &[])
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "list roles".to_string(),
+                cause: e.to_string(),
+            })?;
+        let existing: HashSet<String> = rows.iter().map(|r| r.get(0)).collect();
+        let unknown: Vec<&str> = referenced.into_iter().filter(|role| !existing.contains(*role)).collect();
+        if !unknown.is_empty() {
+            return Err(GatewayError::MigrationFailed {
+                database: database.to_string(),
+                migration: "grants".to_string(),
+                cause: format!(
+                    "grants/ refers to roles that do not exist: {} (create them before migrating)",
+                    unknown.join(", ")
+                ),
+            });
+        }
+
+        let transaction = client.transaction().await.map_err(|e| GatewayError::QueryFailed {
+            database: database.to_string(),
+            function: "deploy_grants".to_string(),
+            cause: e.to_string(),
+        })?;
+
+        for statement in &statements {
+            transaction
+                .batch_execute(&statement.sql)
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: format!("grant statement: {}", statement.sql),
+                    cause: e.to_string(),
+                })?;
+        }
+
+        transaction.commit().await.map_err(|e| GatewayError::QueryFailed {
+            database: database.to_string(),
+            function: "deploy_grants".to_string(),
+            cause: e.to_string(),
+        })?;
+
+        info!("Applied {} grant statements for {}", statements.len(), database);
+        Ok(statements.len())
+    }
+
+    /// Table privileges held in the database, as `(table, grantee, privilege)`
+    pub async fn list_table_grants(&self, pool: &Pool, database: &str) -> Result<BTreeSet<(String, String, String)>> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let rows = client
+            .query(
+                r#"
+                SELECT table_schema::text, table_name::text, grantee::text, privilege_type::text
+                FROM information_schema.role_table_grants
+                WHERE table_schema NOT IN ('pg_catalog', 'information_schema')
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "list_table_grants".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let schema: String = row.get(0);
+                let table: String = row.get(1);
+                (qualified_table_name(Some(&schema), &table), row.get(2), row.get(3))
+            })
+            .collect())
+    }
+
+    /// Compare declared table privileges against the database
+    pub async fn verify_grants(&self, pool: &Pool, database: &str, grants_dir: &Path) -> Result<GrantVerification> {
+        let declared = self.load_grants(grants_dir)?;
+        if declared.iter().all(|s| s.privileges.is_empty()) {
+            return Ok(GrantVerification::default());
+        }
+
+        // information_schema leaves out materialized views, so their grants
+        // are applied but cannot be checked here
+        let matviews = self.list_matviews(pool, database).await?;
+        let declared: Vec<GrantStatement> = declared
+            .into_iter()
+            .map(|mut statement| {
+                statement.tables.retain(|t| !matviews.contains(t));
+                statement
+            })
+            .collect();
+
+        let held = self.list_table_grants(pool, database).await?;
+        Ok(Self::compare(&declared, &held))
+    }
+
+    async fn list_matviews(&self, pool: &Pool, database: &str) -> Result<HashSet<String>> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let rows = client
+            .query("SELECT schemaname::text, matviewname::text FROM pg_matviews", &[])
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "list materialized views".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let schema: String = row.get(0);
+                let name: String = row.get(1);
+                qualified_table_name(Some(&schema), &name)
+            })
+            .collect())
+    }
+
+    /// Report declared privileges that are missing, and revoked ones still held
+    ///
+    /// Statements are applied in order, so a later REVOKE cancels an earlier
+    /// GRANT of the same privilege and vice versa.
+    pub fn compare(declared: &[GrantStatement], held: &BTreeSet<(String, String, String)>) -> GrantVerification {
+        let mut desired: BTreeMap<(String, String, String), bool> = BTreeMap::new();
+        for statement in declared {
+            for table in &statement.tables {
+                for grantee in &statement.grantees {
+                    for privilege in &statement.privileges {
+                        desired.insert((table.clone(), grantee.clone(), privilege.clone()), statement.grant);
+                    }
+                }
+            }
+        }
+
+        let mut verification = GrantVerification::default();
+        for (key, granted) in desired {
+            let (table, grantee, privilege) = &key;
+            let description = format!("{} ON {} TO {}", privilege, table, grantee);
+            if granted {
+                verification.expected.push(description.clone());
+                if !held.contains(&key) {
+                    verification.missing.push(description);
+                }
+            } else if held.contains(&key) {
+                verification.unexpected.push(description);
+            }
+        }
+
+        verification
+    }
+}
+
+impl Default for GrantManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse one GRANT or REVOKE statement
+fn parse_grant(statement: &str) -> Option<GrantStatement> {
+    let grant_re = regex::Regex::new(
+        r"(?is)^\s*GRANT\s+(.+?)(?:\s+ON\s+(.+?))?\s+TO\s+(.+?)(?:\s+WITH\s+(?:GRANT|ADMIN|INHERIT|SET)\s+\w+)?(?:\s+GRANTED\s+BY\s+\S+)?\s*$",
+    )
+    .unwrap();
+    let revoke_re = regex::Regex::new(
+        r"(?is)^\s*REVOKE\s+(?:(?:GRANT|ADMIN|INHERIT|SET)\s+OPTION\s+FOR\s+)?(.+?)(?:\s+ON\s+(.+?))?\s+FROM\s+(.+?)(?:\s+GRANTED\s+BY\s+\S+)?(?:\s+(?:CASCADE|RESTRICT))?\s*$",
+    )
+    .unwrap();
+
+    let (grant, caps) = match grant_re.captures(statement) {
+        Some(caps) => (true, caps),
+        None => (false, revoke_re.captures(statement)?),
+    };
+    let grantees = parse_roles(&caps[3]);
+    let mut roles = grantees.clone();
+
+    let (privileges, tables) = match caps.get(2) {
+        Some(object) => match (table_privileges(&caps[1]), table_names(object.as_str())) {
+            (Some(privileges), Some(tables)) => (privileges, tables),
+            _ => (Vec::new(), Vec::new()),
+        },
+        // Role membership: GRANT role TO role
+        None => {
+            roles.extend(parse_roles(&caps[1]));
+            (Vec::new(), Vec::new())
+        }
+    };
+    roles.retain(|role| !ROLE_KEYWORDS.contains(&role.to_uppercase().as_str()));
+    let grantees = if tables.is_empty() { Vec::new() } else { grantees };
+
+    Some(GrantStatement {
+        grant,
+        privileges,
+        tables,
+        grantees,
+        roles,
+        sql: format!("{};", statement.trim()),
+    })
+}
+
+/// Table privileges of a privilege list, or None if it has column lists or
+/// privileges that do not apply to tables
+fn table_privileges(list: &str) -> Option<Vec<String>> {
+    let list = list.trim().to_uppercase();
+    if regex::Regex::new(r"^ALL(\s+PRIVILEGES)?$").unwrap().is_match(&list) {
+        return Some(TABLE_PRIVILEGES.iter().map(|p| p.to_string()).collect());
+    }
+
+    let mut privileges = Vec::new();
+    for privilege in list.split(',').map(str::trim) {
+        if !TABLE_PRIVILEGES.contains(&privilege) {
+            return None;
+        }
+        privileges.push(privilege.to_string());
+    }
+    privileges.sort();
+    privileges.dedup();
+    Some(privileges)
+}
+
+/// Qualified table names of `[TABLE] name, ...`, or None for other objects
+fn table_names(object: &str) -> Option<Vec<String>> {
+    let object = object.trim();
+    let other_objects = regex::Regex::new(
+        r"(?i)^(SEQUENCE|FUNCTION|PROCEDURE|ROUTINE|SCHEMA|DATABASE|TYPE|DOMAIN|LANGUAGE|FOREIGN|LARGE|TABLESPACE|PARAMETER|ALL)\b",
+    )
+    .unwrap();
+    if other_objects.is_match(object) {
+        return None;
+    }
+
+    let names = regex::Regex::new(r"(?i)^TABLE\s+").unwrap().replace(object, "");
+    Some(
+        names
+            .split(',')
+            .map(|name| {
+                let (schema, table) = split_qualified_name(name);
+                qualified_table_name(schema.as_deref(), &table)
+            })
+            .collect(),
+    )
+}
+
+/// Role names of a comma-separated role list, folded like identifiers
+fn parse_roles(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|role| {
+            let role = role.trim();
+            let role = regex::Regex::new(r"(?i)^GROUP\s+").unwrap().replace(role, "");
+            if role.len() >= 2 && role.starts_with('"') && role.ends_with('"') {
+                role[1..role.len() - 1].replace("\"\"", "\"")
+            } else if role.eq_ignore_ascii_case("public") {
+                "PUBLIC".to_string()
+            } else {
+                role.to_lowercase()
+            }
+        })
+        .filter(|role| !role.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::VerificationResult;
+    use crate::schema::test_db::test_pool;
+    use tempfile::TempDir;
+
+    const GRANT_FILE: &str = r#"
+-- Read-only reporting role
+GRANT SELECT ON orders, public.customers TO reporting;
+GRANT ALL PRIVILEGES ON TABLE orders TO "App Writer" WITH GRANT OPTION;
+REVOKE TRUNCATE ON orders FROM "App Writer";
+GRANT USAGE ON SEQUENCE orders_id_seq TO "App Writer";
+GRANT reporting TO analyst;
+"#;
+
+    fn held(grants: &[(&str, &str, &str)]) -> BTreeSet<(String, String, String)> {
+        grants
+            .iter()
+            .map(|(t, g, p)| (t.to_string(), g.to_string(), p.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_grant_file() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("roles.pssql");
+        fs::write(&file, GRANT_FILE).unwrap();
+
+        let statements = GrantManager::new().parse_grants(&file).unwrap();
+        assert_eq!(statements.len(), 5);
+
+        assert_eq!(statements[0].privileges, vec!["SELECT"]);
+        assert_eq!(statements[0].tables, vec!["orders", "customers"]);
+        assert_eq!(statements[0].grantees, vec!["reporting"]);
+
+        assert_eq!(statements[1].privileges.len(), 7);
+        assert_eq!(statements[1].grantees, vec!["App Writer"]);
+
+        assert!(!statements[2].grant);
+        assert_eq!(statements[2].privileges, vec!["TRUNCATE"]);
+
+        // Not a table grant: applied and role-checked, but not verified
+        assert!(statements[3].privileges.is_empty());
+        assert_eq!(statements[3].roles, vec!["App Writer"]);
+
+        assert!(statements[4].privileges.is_empty());
+        assert_eq!(statements[4].roles, vec!["analyst", "reporting"]);
+
+        fs::write(&file, "GRANT SELECT ON orders TO reporting;\nCREATE ROLE oops;").unwrap();
+        assert!(GrantManager::new().parse_grants(&file).is_err());
+    }
+
+    #[test]
+    fn test_compare_reports_missing_and_revoked_grants() {
+        let declared: Vec<GrantStatement> = split_statements(&strip_comments(GRANT_FILE))
+            .iter()
+            .filter_map(|s| parse_grant(s))
+            .collect();
+
+        let mut grants = vec![("orders", "reporting", "SELECT"), ("customers", "reporting", "SELECT")];
+        for privilege in TABLE_PRIVILEGES {
+            if *privilege != "TRUNCATE" {
+                grants.push(("orders", "App Writer", privilege));
+            }
+        }
+        assert!(GrantManager::compare(&declared, &held(&grants)).is_ok());
+
+        grants.retain(|g| g.1 != "reporting" || g.0 != "customers");
+        grants.push(("orders", "App Writer", "TRUNCATE"));
+        let verification = GrantManager::compare(&declared, &held(&grants));
+        assert_eq!(verification.missing, vec!["SELECT ON customers TO reporting"]);
+        assert_eq!(verification.unexpected, vec!["TRUNCATE ON orders TO App Writer"]);
+    }
+
+    /// Requires a PostgreSQL database: TEST_DATABASE_URL=postgres://... cargo test
+    #[tokio::test]
+    async fn test_missing_grant_is_a_verification_finding() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS grant_test_orders;
+                 CREATE TABLE grant_test_orders (id INT PRIMARY KEY);
+                 DO $$ BEGIN
+                     IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'grant_test_reader') THEN
+                         CREATE ROLE grant_test_reader;
+                     END IF;
+                 END $$;",
+            )
+            .await
+            .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("reader.pssql"),
+            "GRANT SELECT, INSERT ON grant_test_orders TO grant_test_reader;",
+        )
+        .unwrap();
+
+        let manager = GrantManager::new();
+        assert_eq!(manager.deploy_grants(&pool, "test", dir.path()).await.unwrap(), 1);
+        assert!(manager.verify_grants(&pool, "test", dir.path()).await.unwrap().is_ok());
+
+        // Privilege lost outside the gateway
+        client
+            .batch_execute("REVOKE INSERT ON grant_test_orders FROM grant_test_reader")
+            .await
+            .unwrap();
+        let mut result = VerificationResult::new();
+        result.grants = manager.verify_grants(&pool, "test", dir.path()).await.unwrap();
+        assert_eq!(
+            result.findings(),
+            vec!["Missing grant: INSERT ON grant_test_orders TO grant_test_reader"]
+        );
+
+        // Unknown roles are rejected before anything is applied
+        fs::write(dir.path().join("reader.pssql"), "GRANT SELECT ON grant_test_orders TO grant_test_nobody;").unwrap();
+        let err = manager.deploy_grants(&pool, "test", dir.path()).await.unwrap_err();
+        assert!(err.to_string().contains("grant_test_nobody"), "{}", err);
+
+        client
+            .batch_execute("DROP TABLE grant_test_orders; DROP ROLE grant_test_reader;")
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,216 @@
+//! Pre- and post-migration SQL hooks
+//!
+//! A schema may ship `hooks/pre_migrate.pssql` and `hooks/post_migrate.pssql`.
+//! The pre hook runs before migrations, the post hook after migrations,
+//! function deployment and verification (e.g. to disable triggers and refresh
+//! materialized views around a batch). Both are optional.
+
+use crate::error::{GatewayError, Result};
+use deadpool_postgres::Pool;
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// When a hook runs relative to the migration batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookStage {
+    PreMigrate,
+    PostMigrate,
+}
+
+impl HookStage {
+    /// Hook file name inside the schema's `hooks/` directory
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            HookStage::PreMigrate => "pre_migrate.pssql",
+            HookStage::PostMigrate => "post_migrate.pssql",
+        }
+    }
+}
+
+pub struct HookRunner;
+
+impl HookRunner {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Run the hook for `stage` if the schema has one
+    ///
+    /// Returns the hook's path relative to the schema (e.g. `hooks/pre_migrate.pssql`),
+    /// or None when there is no hook file.
+    pub async fn run(
+        &self,
+        pool: &Pool,
+        database: &str,
+        hooks_dir: &Path,
+        stage: HookStage,
+    ) -> Result<Option<String>> {
+        let path = hooks_dir.join(stage.file_name());
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let hook_name = format!("hooks/{}", stage.file_name());
+        let hook_error = |cause: String| GatewayError::MigrationFailed {
+            database: database.to_string(),
+            migration: hook_name.clone(),
+            cause,
+        };
+
+        let sql = fs::read_to_string(&path)
+            .map_err(|e| hook_error(format!("Failed to read file: {}", e)))?;
+
+        let mut client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        info!("Running {} on {}", hook_name, database);
+
+        if requires_autocommit(&sql) {
+            // e.g. REFRESH MATERIALIZED VIEW CONCURRENTLY cannot run in a transaction block
+            client
+                .batch_execute(&sql)
+                .await
+                .map_err(|e| hook_error(describe_error(&e)))?;
+        } else {
+            let transaction = client
+                .transaction()
+                .await
+                .map_err(|e| hook_error(format!("Failed to start transaction: {}", e)))?;
+            transaction
+                .batch_execute(&sql)
+                .await
+                .map_err(|e| hook_error(describe_error(&e)))?;
+            transaction
+                .commit()
+                .await
+                .map_err(|e| hook_error(format!("Failed to commit: {}", e)))?;
+        }
+
+        Ok(Some(hook_name))
+    }
+}
+
+impl Default for HookRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check if a hook contains statements that PostgreSQL refuses inside a transaction
+fn requires_autocommit(sql: &str) -> bool {
+    let re = regex::Regex::new(r"(?i)\b(CONCURRENTLY|VACUUM)\b|\bALTER\s+TYPE\s+\w+\s+ADD\s+VALUE\b").unwrap();
+    re.is_match(sql)
+}
+
+fn describe_error(e: &tokio_postgres::Error) -> String {
+    match e.as_db_error() {
+        Some(db_err) => db_err.message().to_string(),
+        None => {
+            warn!("Hook failed without a database error: {}", e);
+            e.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::MigrationRunner;
+    use crate::schema::test_db::{DB_LOCK, test_pool};
+
+    #[test]
+    fn test_requires_autocommit() {
+        assert!(requires_autocommit("REFRESH MATERIALIZED VIEW CONCURRENTLY totals;"));
+        assert!(requires_autocommit("vacuum analyze orders;"));
+        assert!(!requires_autocommit("ALTER TABLE orders DISABLE TRIGGER ALL;"));
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_around_migrations() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let _guard = DB_LOCK.lock().await;
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS hook_order_log;
+                 CREATE TABLE hook_order_log (id SERIAL PRIMARY KEY, step TEXT NOT NULL);",
+            )
+            .await
+            .unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let hooks_dir = dir.path().join("hooks");
+        let migrations_dir = dir.path().join("migrations");
+        fs::create_dir_all(&hooks_dir).unwrap();
+        fs::create_dir_all(&migrations_dir).unwrap();
+        fs::write(
+            hooks_dir.join("pre_migrate.pssql"),
+            "INSERT INTO hook_order_log (step) VALUES ('pre');",
+        )
+        .unwrap();
+        fs::write(
+            hooks_dir.join("post_migrate.pssql"),
+            "INSERT INTO hook_order_log (step) VALUES ('post');",
+        )
+        .unwrap();
+        fs::write(
+            migrations_dir.join("900_hook_order.pssql"),
+            "INSERT INTO hook_order_log (step) VALUES ('migration');",
+        )
+        .unwrap();
+
+        let runner = HookRunner::new();
+        let pre = runner
+            .run(&pool, "gw_test", &hooks_dir, HookStage::PreMigrate)
+            .await
+            .unwrap();
+        assert_eq!(pre.as_deref(), Some("hooks/pre_migrate.pssql"));
+
+        MigrationRunner::new()
+            .run_migrations(&pool, "gw_test", &migrations_dir, false)
+            .await
+            .unwrap();
+
+        let post = runner
+            .run(&pool, "gw_test", &hooks_dir, HookStage::PostMigrate)
+            .await
+            .unwrap();
+        assert_eq!(post.as_deref(), Some("hooks/post_migrate.pssql"));
+
+        let steps: Vec<String> = client
+            .query("SELECT step FROM hook_order_log ORDER BY id", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(steps, vec!["pre", "migration", "post"]);
+
+        client
+            .batch_execute(
+                "DROP TABLE hook_order_log;
+                 DELETE FROM _stonescriptdb_gateway_migrations WHERE migration_file = '900_hook_order.pssql';",
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_missing_hook_is_skipped() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let dir = tempfile::TempDir::new().unwrap();
+
+        let ran = HookRunner::new()
+            .run(&pool, "gw_test", dir.path(), HookStage::PreMigrate)
+            .await
+            .unwrap();
+        assert!(ran.is_none());
+    }
+}
@@ -0,0 +1,500 @@
+//! Drop impact analysis
+//!
+//! Reports everything that would be affected by dropping a table
+//! (dependent tables, views, functions and row count) without dropping anything,
+//! and which table columns would be affected by changing a custom type.
+
+use crate::error::{GatewayError, Result};
+use crate::schema::dependency::DependencyAnalyzer;
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use tracing::debug;
+
+/// A foreign key edge from a child table to its parent
+#[derive(Debug, Clone, Serialize)]
+pub struct ForeignKeyEdge {
+    pub from_table: String,
+    pub from_column: String,
+    pub to_table: String,
+    pub constraint_name: String,
+    pub on_delete: String,
+}
+
+/// A view (or materialized view) built on top of the table
+#[derive(Debug, Clone, Serialize)]
+pub struct DependentView {
+    pub name: String,
+    pub materialized: bool,
+}
+
+/// Full blast radius of dropping a table
+#[derive(Debug, Clone, Serialize)]
+pub struct DropImpact {
+    pub table: String,
+    pub row_count: i64,
+    /// Tables with a foreign key directly referencing this table
+    pub dependent_tables: Vec<ForeignKeyEdge>,
+    /// Every table that depends on this one, directly or through other tables
+    pub transitive_dependents: Vec<String>,
+    pub views: Vec<DependentView>,
+    pub functions: Vec<String>,
+}
+
+impl DropImpact {
+    /// Check if dropping the table would affect nothing but its own rows
+    pub fn is_isolated(&self) -> bool {
+        self.dependent_tables.is_empty() && self.views.is_empty() && self.functions.is_empty()
+    }
+}
+
+pub struct DropImpactAnalyzer;
+
+impl DropImpactAnalyzer {
+    /// Analyze the impact of dropping a table in a live database
+    pub async fn analyze(pool: &Pool, database: &str, table: &str) -> Result<DropImpact> {
+        if !is_valid_table_name(table) {
+            return Err(GatewayError::InvalidRequest {
+                message: format!("Invalid table name: {}", table),
+            });
+        }
+
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let exists = client
+            .query_one(
+                "SELECT EXISTS (SELECT 1 FROM pg_tables WHERE schemaname = 'public' AND tablename = $1)",
+                &[&table],
+            )
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Failed to check table: {}", e)))?;
+
+        if !exists.get::<_, bool>(0) {
+            return Err(GatewayError::InvalidRequest {
+                message: format!("Table '{}' does not exist in database '{}'", table, database),
+            });
+        }
+
+        // All foreign keys in the public schema (needed for transitive dependents)
+        let fk_rows = client
+            .query(
+                r#"
+                SELECT child.relname::text, a.attname::text, parent.relname::text,
+                       c.conname::text, c.confdeltype::text
+                FROM pg_constraint c
+                JOIN pg_class child ON child.oid = c.conrelid
+                JOIN pg_class parent ON parent.oid = c.confrelid
+                JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = c.conkey[1]
+                WHERE c.contype = 'f'
+                  AND child.relnamespace = 'public'::regnamespace
+                ORDER BY child.relname, c.conname
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Failed to query foreign keys: {}", e)))?;
+
+        let fk_edges: Vec<ForeignKeyEdge> = fk_rows
+            .iter()
+            .map(|row| ForeignKeyEdge {
+                from_table: row.get(0),
+                from_column: row.get(1),
+                to_table: row.get(2),
+                constraint_name: row.get(3),
+                on_delete: fk_action_name(row.get::<_, String>(4).as_str()).to_string(),
+            })
+            .collect();
+
+        // Views depend on the table through their rewrite rules
+        let view_rows = client
+            .query(
+                r#"
+                SELECT DISTINCT v.relname::text, v.relkind = 'm'
+                FROM pg_depend d
+                JOIN pg_rewrite r ON r.oid = d.objid
+                JOIN pg_class v ON v.oid = r.ev_class
+                WHERE d.classid = 'pg_rewrite'::regclass
+                  AND d.refclassid = 'pg_class'::regclass
+                  AND d.refobjid = to_regclass('public.' || quote_ident($1))
+                  AND v.oid <> d.refobjid
+                "#,
+                &[&table],
+            )
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Failed to query views: {}", e)))?;
+
+        let views: Vec<DependentView> = view_rows
+            .iter()
+            .map(|row| DependentView {
+                name: row.get(0),
+                materialized: row.get(1),
+            })
+            .collect();
+
+        // SQL-standard function bodies are tracked in pg_depend; PL/pgSQL bodies
+        // are not, so their source is scanned for the table name as well
+        let function_rows = client
+            .query(
+                r#"
+                SELECT p.proname::text, COALESCE(p.prosrc, ''),
+                       EXISTS (
+                           SELECT 1 FROM pg_depend d
+                           WHERE d.classid = 'pg_proc'::regclass
+                             AND d.objid = p.oid
+                             AND d.refobjid = to_regclass('public.' || quote_ident($1))
+                       )
+                FROM pg_proc p
+                WHERE p.pronamespace = 'public'::regnamespace
+                "#,
+                &[&table],
+            )
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Failed to query functions: {}", e)))?;
+
+        let functions: Vec<(String, String, bool)> = function_rows
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect();
+
+        let count_sql = format!("SELECT COUNT(*) FROM \"{}\"", table);
+        let row_count: i64 = client
+            .query_one(&count_sql, &[])
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Failed to count rows: {}", e)))?
+            .get(0);
+
+        let impact = Self::build_impact(table, &fk_edges, views, &functions, row_count);
+
+        debug!(
+            "Drop impact for {}.{}: {} dependent tables, {} views, {} functions, {} rows",
+            database,
+            table,
+            impact.dependent_tables.len(),
+            impact.views.len(),
+            impact.functions.len(),
+            impact.row_count
+        );
+
+        Ok(impact)
+    }
+
+    /// Assemble the impact report from raw catalog data
+    fn build_impact(
+        table: &str,
+        fk_edges: &[ForeignKeyEdge],
+        views: Vec<DependentView>,
+        functions: &[(String, String, bool)],
+        row_count: i64,
+    ) -> DropImpact {
+        let dependent_tables: Vec<ForeignKeyEdge> = fk_edges
+            .iter()
+            .filter(|edge| edge.to_table == table && edge.from_table != table)
+            .cloned()
+            .collect();
+
+        // table -> tables it depends on, then reversed
+        let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+        for edge in fk_edges {
+            graph
+                .entry(edge.from_table.clone())
+                .or_default()
+                .push(edge.to_table.clone());
+        }
+        let reverse = DependencyAnalyzer::build_reverse_dependencies(&graph);
+        let transitive_dependents = Self::transitive_dependents(table, &reverse);
+
+        let reference = regex::Regex::new(&format!(r"(?i)\b{}\b", regex::escape(table))).unwrap();
+        let functions: BTreeSet<String> = functions
+            .iter()
+            .filter(|(_, source, tracked)| *tracked || reference.is_match(source))
+            .map(|(name, _, _)| name.clone())
+            .collect();
+
+        let mut views = views;
+        views.sort_by(|a, b| a.name.cmp(&b.name));
+
+        DropImpact {
+            table: table.to_string(),
+            row_count,
+            dependent_tables,
+            transitive_dependents,
+            views,
+            functions: functions.into_iter().collect(),
+        }
+    }
+
+    /// Walk the reverse dependency graph breadth-first
+    fn transitive_dependents(table: &str, reverse: &HashMap<String, Vec<String>>) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        let mut queue = VecDeque::from([table.to_string()]);
+
+        while let Some(current) = queue.pop_front() {
+            if let Some(dependents) = reverse.get(&current) {
+                for dependent in dependents {
+                    if dependent != table && seen.insert(dependent.clone()) {
+                        queue.push_back(dependent.clone());
+                    }
+                }
+            }
+        }
+
+        seen.into_iter().collect()
+    }
+}
+
+/// A table column declared with the type, an array of it, or a domain over it
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeUsage {
+    pub table: String,
+    pub column: String,
+    /// Declared column type, e.g. `order_status[]`
+    pub data_type: String,
+}
+
+/// Columns affected by changing a custom type
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeImpact {
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// enum, composite, domain, range or base
+    pub kind: String,
+    pub columns: Vec<TypeUsage>,
+}
+
+impl TypeImpact {
+    /// Check if no table column uses the type
+    pub fn is_unused(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+pub struct TypeImpactAnalyzer;
+
+impl TypeImpactAnalyzer {
+    /// Find every table column in a live database that uses a type
+    pub async fn analyze(pool: &Pool, database: &str, type_name: &str) -> Result<TypeImpact> {
+        if !is_valid_table_name(type_name) {
+            return Err(GatewayError::InvalidRequest {
+                message: format!("Invalid type name: {}", type_name),
+            });
+        }
+
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        // Row types of tables and views are composite too, but not custom types
+        let type_row = client
+            .query_opt(
+                r#"
+                SELECT t.oid, t.typtype::text
+                FROM pg_type t
+                LEFT JOIN pg_class c ON c.oid = t.typrelid
+                WHERE t.typnamespace = 'public'::regnamespace
+                  AND t.typname = $1
+                  AND (t.typrelid = 0 OR c.relkind = 'c')
+                "#,
+                &[&type_name],
+            )
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Failed to look up type: {}", e)))?;
+
+        let Some(type_row) = type_row else {
+            return Err(GatewayError::InvalidRequest {
+                message: format!("Type '{}' does not exist in database '{}'", type_name, database),
+            });
+        };
+        let type_oid: u32 = type_row.get(0);
+        let kind = type_kind_name(type_row.get::<_, String>(1).as_str());
+
+        // A column depends on the type directly, through its array type or
+        // through a domain declared over it
+        let column_rows = client
+            .query(
+                r#"
+                SELECT c.relname::text, a.attname::text, format_type(a.atttypid, a.atttypmod)
+                FROM pg_attribute a
+                JOIN pg_class c ON c.oid = a.attrelid
+                JOIN pg_type t ON t.oid = a.atttypid
+                WHERE c.relnamespace = 'public'::regnamespace
+                  AND c.relkind IN ('r', 'p')
+                  AND NOT c.relispartition
+                  AND a.attnum > 0
+                  AND NOT a.attisdropped
+                  AND (a.atttypid = $1 OR t.typelem = $1 OR t.typbasetype = $1)
+                ORDER BY c.relname, a.attnum
+                "#,
+                &[&type_oid],
+            )
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Failed to query columns: {}", e)))?;
+
+        let columns: Vec<TypeUsage> = column_rows
+            .iter()
+            .map(|row| TypeUsage {
+                table: row.get(0),
+                column: row.get(1),
+                data_type: row.get(2),
+            })
+            .collect();
+
+        debug!(
+            "Type impact for {}.{}: {} columns",
+            database,
+            type_name,
+            columns.len()
+        );
+
+        Ok(TypeImpact {
+            type_name: type_name.to_string(),
+            kind: kind.to_string(),
+            columns,
+        })
+    }
+}
+
+/// Map pg_type.typtype to the kind of type
+fn type_kind_name(code: &str) -> &'static str {
+    match code {
+        "e" => "enum",
+        "c" => "composite",
+        "d" => "domain",
+        "r" | "m" => "range",
+        _ => "base",
+    }
+}
+
+/// Map pg_constraint.confdeltype to its SQL action
+fn fk_action_name(code: &str) -> &'static str {
+    match code {
+        "c" => "CASCADE",
+        "n" => "SET NULL",
+        "d" => "SET DEFAULT",
+        "r" => "RESTRICT",
+        _ => "NO ACTION",
+    }
+}
+
+fn is_valid_table_name(name: &str) -> bool {
+    let re = regex::Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]{0,62}$").unwrap();
+    re.is_match(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_db::test_pool;
+
+    fn edge(from: &str, to: &str, on_delete: &str) -> ForeignKeyEdge {
+        ForeignKeyEdge {
+            from_table: from.to_string(),
+            from_column: format!("{}_id", to),
+            to_table: to.to_string(),
+            constraint_name: format!("{}_{}_fkey", from, to),
+            on_delete: on_delete.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_impact_reports_child_table_and_view() {
+        let fk_edges = vec![
+            edge("orders", "users", "CASCADE"),
+            edge("order_items", "orders", "NO ACTION"),
+            edge("products", "categories", "NO ACTION"),
+        ];
+        let views = vec![DependentView {
+            name: "active_users".to_string(),
+            materialized: false,
+        }];
+        let functions = vec![
+            (
+                "get_user".to_string(),
+                "SELECT * FROM users WHERE id = p_id".to_string(),
+                false,
+            ),
+            (
+                "get_superusers".to_string(),
+                "SELECT * FROM superusers".to_string(),
+                false,
+            ),
+        ];
+
+        let impact = DropImpactAnalyzer::build_impact("users", &fk_edges, views, &functions, 42);
+
+        assert_eq!(impact.row_count, 42);
+        assert_eq!(impact.dependent_tables.len(), 1);
+        assert_eq!(impact.dependent_tables[0].from_table, "orders");
+        assert_eq!(impact.dependent_tables[0].on_delete, "CASCADE");
+        assert_eq!(impact.transitive_dependents, vec!["order_items", "orders"]);
+        assert_eq!(impact.views.len(), 1);
+        assert_eq!(impact.views[0].name, "active_users");
+        assert_eq!(impact.functions, vec!["get_user"]);
+        assert!(!impact.is_isolated());
+    }
+
+    #[test]
+    fn test_impact_isolated_table() {
+        let fk_edges = vec![edge("orders", "users", "NO ACTION")];
+
+        let impact = DropImpactAnalyzer::build_impact("audit_log", &fk_edges, Vec::new(), &[], 0);
+
+        assert!(impact.is_isolated());
+        assert!(impact.transitive_dependents.is_empty());
+    }
+
+    #[test]
+    fn test_fk_action_name() {
+        assert_eq!(fk_action_name("c"), "CASCADE");
+        assert_eq!(fk_action_name("n"), "SET NULL");
+        assert_eq!(fk_action_name("a"), "NO ACTION");
+    }
+
+    #[tokio::test]
+    async fn test_enum_impact_lists_every_column_using_it() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+
+        let cleanup = "DROP TABLE IF EXISTS type_impact_orders, type_impact_shipments;
+                       DROP TYPE IF EXISTS type_impact_status;";
+        let client = pool.get().await.unwrap();
+        client.batch_execute(cleanup).await.unwrap();
+        client
+            .batch_execute(
+                "CREATE TYPE type_impact_status AS ENUM ('pending', 'done');
+                 CREATE TABLE type_impact_orders (id INT, status type_impact_status);
+                 CREATE TABLE type_impact_shipments (id INT, history type_impact_status[]);",
+            )
+            .await
+            .unwrap();
+        drop(client);
+
+        let impact = TypeImpactAnalyzer::analyze(&pool, "gw_test", "type_impact_status").await;
+        pool.get().await.unwrap().batch_execute(cleanup).await.unwrap();
+        let impact = impact.unwrap();
+
+        assert_eq!(impact.kind, "enum");
+        assert!(!impact.is_unused());
+        let columns: Vec<(&str, &str, &str)> = impact
+            .columns
+            .iter()
+            .map(|c| (c.table.as_str(), c.column.as_str(), c.data_type.as_str()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("type_impact_orders", "status", "type_impact_status"),
+                ("type_impact_shipments", "history", "type_impact_status[]"),
+            ]
+        );
+
+        let err = TypeImpactAnalyzer::analyze(&pool, "gw_test", "type_impact_status")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not exist"), "{}", err);
+    }
+}
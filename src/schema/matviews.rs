@@ -0,0 +1,725 @@
+//! Materialized view deployer
+//!
+//! Materialized views are defined in the `matviews/` folder, one
+//! `CREATE MATERIALIZED VIEW` per file (optionally followed by its indexes).
+//! They are created after tables and functions, in dependency order, and
+//! recreated only when their definition changes (checksum tracked in
+//! `_stonescriptdb_gateway_matviews`).
+//!
+//! A file can declare how the view is refreshed:
+//!
+//! ```sql
+//! -- @refresh: concurrently
+//! CREATE MATERIALIZED VIEW daily_sales AS ...;
+//! CREATE UNIQUE INDEX daily_sales_day ON daily_sales (day);
+//! ```
+//!
+//! `concurrently` refreshes without locking out readers, which PostgreSQL
+//! only allows for views with a unique index.
+
+use crate::error::{GatewayError, Result};
+use crate::schema::sql::strip_comments;
+use deadpool_postgres::Pool;
+use regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// How `REFRESH MATERIALIZED VIEW` is run for a view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshStrategy {
+    /// Plain refresh (locks the view against reads)
+    #[default]
+    Standard,
+    /// `REFRESH ... CONCURRENTLY` (requires a unique index)
+    Concurrently,
+}
+
+impl RefreshStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RefreshStrategy::Standard => "standard",
+            RefreshStrategy::Concurrently => "concurrently",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "standard" => Some(RefreshStrategy::Standard),
+            "concurrently" => Some(RefreshStrategy::Concurrently),
+            _ => None,
+        }
+    }
+}
+
+/// A materialized view parsed from a matviews file
+#[derive(Debug, Clone)]
+pub struct MaterializedView {
+    pub name: String,
+    pub refresh: RefreshStrategy,
+    /// Relations referenced in FROM/JOIN clauses (tables or other views)
+    pub depends_on: Vec<String>,
+    /// Whole file, executed on deploy
+    pub sql: String,
+    /// Checksum of the normalized file, without comments
+    pub checksum: String,
+    pub source_file: String,
+}
+
+/// Result of comparing declared materialized views against the database
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MatviewVerification {
+    pub expected: Vec<String>,
+    pub missing: Vec<String>,
+    pub mismatches: Vec<MatviewMismatch>,
+}
+
+impl MatviewVerification {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.mismatches.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatviewMismatch {
+    pub matview: String,
+    pub issue: String,
+}
+
+pub struct MatviewDeployer;
+
+impl MatviewDeployer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Ensure the materialized view tracking table exists
+    pub async fn ensure_tracking_table(&self, pool: &Pool, database: &str) -> Result<()> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        client
+            .execute(
+                r#"
+                CREATE TABLE IF NOT EXISTS _stonescriptdb_gateway_matviews (
+                    matview_name TEXT PRIMARY KEY,
+                    checksum TEXT NOT NULL,
+                    refresh_strategy TEXT NOT NULL,
+                    source_file TEXT NOT NULL,
+                    deployed_at TIMESTAMPTZ DEFAULT NOW()
+                )
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "_stonescriptdb_gateway_matviews table creation".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        Ok(())
+    }
+
+    /// Find materialized view files in the matviews directory
+    pub fn find_matview_files(&self, matviews_dir: &Path) -> Result<Vec<PathBuf>> {
+        if !matviews_dir.exists() {
+            debug!(
+                "Matviews directory {:?} does not exist, returning empty list",
+                matviews_dir
+            );
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+
+        for entry in fs::read_dir(matviews_dir).map_err(|e| GatewayError::SchemaExtractionFailed {
+            cause: format!("Failed to read matviews directory: {}", e),
+        })? {
+            let entry = entry.map_err(|e| GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to read directory entry: {}", e),
+            })?;
+
+            let path = entry.path();
+            if path.is_file() {
+                if let Some(ext) = path.extension() {
+                    if ext == "pssql" || ext == "pgsql" || ext == "sql" {
+                        files.push(path);
+                    }
+                }
+            }
+        }
+
+        // Sort for consistent ordering
+        files.sort();
+
+        Ok(files)
+    }
+
+    /// Parse a materialized view file
+    pub fn parse_matview(&self, file_path: &Path) -> Result<MaterializedView> {
+        let content = fs::read_to_string(file_path).map_err(|e| {
+            GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to read matview file {:?}: {}", file_path, e),
+            }
+        })?;
+        let source_file = file_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        parse_matview_sql(&content, &source_file)
+    }
+
+    /// Parse all matview files in the directory, in creation order
+    pub fn load_matviews(&self, matviews_dir: &Path) -> Result<Vec<MaterializedView>> {
+        let mut views = Vec::new();
+        for file in self.find_matview_files(matviews_dir)? {
+            views.push(self.parse_matview(&file)?);
+        }
+
+        let order = creation_order(&[], &views)?;
+        views.sort_by_key(|view| order.iter().position(|name| *name == view.name));
+        Ok(views)
+    }
+
+    /// Create new and changed materialized views
+    ///
+    /// A changed view is dropped (with CASCADE) and recreated with its data;
+    /// views that depend on it come later in the order and are recreated too,
+    /// since they no longer exist. Returns the number of views deployed.
+    pub async fn deploy_matviews(&self, pool: &Pool, database: &str, matviews_dir: &Path) -> Result<usize> {
+        let views = self.load_matviews(matviews_dir)?;
+        if views.is_empty() {
+            debug!("No materialized views to deploy for database {}", database);
+            return Ok(0);
+        }
+
+        self.ensure_tracking_table(pool, database).await?;
+
+        let mut client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let mut deployed = 0;
+        for view in &views {
+            let row = client
+                .query_opt(
+                    r#"
+                    SELECT t.checksum
+                    FROM pg_matviews m
+                    LEFT JOIN _stonescriptdb_gateway_matviews t ON t.matview_name = m.matviewname
+                    WHERE m.schemaname = 'public' AND m.matviewname = $1
+                    "#,
+                    &[&view.name],
+                )
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: format!("materialized view {}", view.name),
+                    cause: e.to_string(),
+                })?;
+
+            if let Some(row) = row {
+                if row.get::<_, Option<String>>(0).as_deref() == Some(view.checksum.as_str()) {
+                    debug!("Skipping materialized view {} - unchanged", view.name);
+                    // The refresh strategy is not part of the checksum
+                    client
+                        .execute(
+                            "UPDATE _stonescriptdb_gateway_matviews SET refresh_strategy = $2 WHERE matview_name = $1",
+                            &[&view.name, &view.refresh.as_str()],
+                        )
+                        .await
+                        .ok();
+                    continue;
+                }
+            }
+
+            let transaction = client.transaction().await.map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "deploy_matviews".to_string(),
+                cause: e.to_string(),
+            })?;
+
+            let sql = format!(
+                "DROP MATERIALIZED VIEW IF EXISTS \"{}\" CASCADE;\n{}",
+                view.name, view.sql
+            );
+            transaction
+                .batch_execute(&sql)
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: format!("materialized view {} ({})", view.name, view.source_file),
+                    cause: e.to_string(),
+                })?;
+
+            transaction
+                .execute(
+                    r#"
+                    INSERT INTO _stonescriptdb_gateway_matviews
+                        (matview_name, checksum, refresh_strategy, source_file, deployed_at)
+                    VALUES ($1, $2, $3, $4, NOW())
+                    ON CONFLICT (matview_name)
+                    DO UPDATE SET
+                        checksum = EXCLUDED.checksum,
+                        refresh_strategy = EXCLUDED.refresh_strategy,
+                        source_file = EXCLUDED.source_file,
+                        deployed_at = NOW()
+                    "#,
+                    &[&view.name, &view.checksum, &view.refresh.as_str(), &view.source_file],
+                )
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: format!("materialized view {} tracking", view.name),
+                    cause: e.to_string(),
+                })?;
+
+            transaction.commit().await.map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "deploy_matviews".to_string(),
+                cause: e.to_string(),
+            })?;
+
+            deployed += 1;
+        }
+
+        info!(
+            "Deployed {} materialized views to {} ({} unchanged)",
+            deployed,
+            database,
+            views.len() - deployed
+        );
+        Ok(deployed)
+    }
+
+    /// Compare declared materialized views against `pg_matviews`
+    ///
+    /// A view whose tracked checksum differs from its file was not recreated
+    /// from the current definition; a view declared `concurrently` needs a
+    /// unique index.
+    pub async fn verify_matviews(
+        &self,
+        pool: &Pool,
+        database: &str,
+        matviews_dir: &Path,
+    ) -> Result<MatviewVerification> {
+        let declared = self.load_matviews(matviews_dir)?;
+        let mut verification = MatviewVerification::default();
+        if declared.is_empty() {
+            return Ok(verification);
+        }
+
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let tracked = client
+            .query_one(
+                "SELECT to_regclass('_stonescriptdb_gateway_matviews') IS NOT NULL",
+                &[],
+            )
+            .await
+            .map(|row| row.get::<_, bool>(0))
+            .unwrap_or(false);
+
+        for view in &declared {
+            verification.expected.push(view.name.clone());
+
+            let row = client
+                .query_opt(
+                    r#"
+                    SELECT EXISTS (
+                        SELECT 1 FROM pg_index i
+                        WHERE i.indrelid = format('%I.%I', m.schemaname, m.matviewname)::regclass
+                        AND i.indisunique
+                    )
+                    FROM pg_matviews m
+                    WHERE m.schemaname = 'public' AND m.matviewname = $1
+                    "#,
+                    &[&view.name],
+                )
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: "verify_matviews".to_string(),
+                    cause: e.to_string(),
+                })?;
+
+            let Some(row) = row else {
+                verification.missing.push(view.name.clone());
+                continue;
+            };
+
+            if view.refresh == RefreshStrategy::Concurrently && !row.get::<_, bool>(0) {
+                verification.mismatches.push(MatviewMismatch {
+                    matview: view.name.clone(),
+                    issue: "refresh concurrently requires a unique index".to_string(),
+                });
+            }
+
+            let checksum: Option<String> = if tracked {
+                client
+                    .query_opt(
+                        "SELECT checksum FROM _stonescriptdb_gateway_matviews WHERE matview_name = $1",
+                        &[&view.name],
+                    )
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|row| row.get(0))
+            } else {
+                None
+            };
+            if checksum.as_deref() != Some(view.checksum.as_str()) {
+                verification.mismatches.push(MatviewMismatch {
+                    matview: view.name.clone(),
+                    issue: "definition differs from the deployed view".to_string(),
+                });
+            }
+        }
+
+        Ok(verification)
+    }
+
+    /// Run `REFRESH MATERIALIZED VIEW` on a deployed view
+    ///
+    /// Uses the strategy recorded when the view was deployed unless
+    /// `concurrently` overrides it. Returns the strategy used.
+    pub async fn refresh_matview(
+        &self,
+        pool: &Pool,
+        database: &str,
+        name: &str,
+        concurrently: Option<bool>,
+    ) -> Result<RefreshStrategy> {
+        if !is_valid_identifier(name) {
+            return Err(GatewayError::InvalidRequest {
+                message: format!("Invalid materialized view name: {}", name),
+            });
+        }
+
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let exists = client
+            .query_opt(
+                "SELECT 1 FROM pg_matviews WHERE schemaname = 'public' AND matviewname = $1",
+                &[&name],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "refresh_matview".to_string(),
+                cause: e.to_string(),
+            })?
+            .is_some();
+        if !exists {
+            return Err(GatewayError::InvalidRequest {
+                message: format!("Materialized view '{}' does not exist in {}", name, database),
+            });
+        }
+
+        let strategy = match concurrently {
+            Some(true) => RefreshStrategy::Concurrently,
+            Some(false) => RefreshStrategy::Standard,
+            None => client
+                .query_opt(
+                    "SELECT refresh_strategy FROM _stonescriptdb_gateway_matviews WHERE matview_name = $1",
+                    &[&name],
+                )
+                .await
+                .ok()
+                .flatten()
+                .and_then(|row| RefreshStrategy::parse(row.get(0)))
+                .unwrap_or_default(),
+        };
+
+        let sql = match strategy {
+            RefreshStrategy::Standard => format!("REFRESH MATERIALIZED VIEW \"{}\"", name),
+            RefreshStrategy::Concurrently => format!("REFRESH MATERIALIZED VIEW CONCURRENTLY \"{}\"", name),
+        };
+        client.batch_execute(&sql).await.map_err(|e| GatewayError::QueryFailed {
+            database: database.to_string(),
+            function: format!("refresh materialized view {}", name),
+            cause: e.to_string(),
+        })?;
+
+        info!("Refreshed materialized view {} in {} ({})", name, database, strategy.as_str());
+        Ok(strategy)
+    }
+}
+
+impl Default for MatviewDeployer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Order in which tables and materialized views can be created
+///
+/// `tables` come first, in the given order; each view follows every table
+/// and view it selects from. Relations that are neither (e.g. catalog
+/// tables) are ignored. Fails on views that depend on each other in a cycle.
+pub fn creation_order(tables: &[String], views: &[MaterializedView]) -> Result<Vec<String>> {
+    let names: HashSet<&str> = views.iter().map(|v| v.name.as_str()).collect();
+    let mut order: Vec<String> = tables.to_vec();
+    let mut pending: Vec<&MaterializedView> = views.iter().collect();
+
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|view| {
+            view.depends_on
+                .iter()
+                .all(|dep| !names.contains(dep.as_str()) || order.contains(dep))
+        });
+
+        match ready {
+            Some(index) => order.push(pending.remove(index).name.clone()),
+            None => {
+                let stuck: Vec<&str> = pending.iter().map(|v| v.name.as_str()).collect();
+                return Err(GatewayError::SchemaExtractionFailed {
+                    cause: format!(
+                        "Circular dependency between materialized views: {}",
+                        stuck.join(", ")
+                    ),
+                });
+            }
+        }
+    }
+
+    Ok(order)
+}
+
+/// Parse the content of a matview file
+fn parse_matview_sql(content: &str, source_file: &str) -> Result<MaterializedView> {
+    let refresh_re = Regex::new(r"(?im)^\s*--\s*@refresh:\s*(\S+)").unwrap();
+    let refresh = match refresh_re.captures(content) {
+        Some(caps) => RefreshStrategy::parse(&caps[1]).ok_or_else(|| GatewayError::SchemaExtractionFailed {
+            cause: format!(
+                "Matview file {} has an unknown refresh strategy '{}' (expected standard or concurrently)",
+                source_file, &caps[1]
+            ),
+        })?,
+        None => RefreshStrategy::Standard,
+    };
+
+    let sql = strip_comments(content);
+    let create_re = Regex::new(
+        r#"(?is)CREATE\s+MATERIALIZED\s+VIEW\s+(?:IF\s+NOT\s+EXISTS\s+)?((?:"?\w+"?\.)?"?\w+"?)[^;]*?\bAS\b([^;]*)"#,
+    )
+    .unwrap();
+
+    let mut creates = create_re.captures_iter(&sql);
+    let caps = creates.next().ok_or_else(|| GatewayError::SchemaExtractionFailed {
+        cause: format!("Matview file {} has no CREATE MATERIALIZED VIEW statement", source_file),
+    })?;
+    if creates.next().is_some() {
+        return Err(GatewayError::SchemaExtractionFailed {
+            cause: format!("Matview file {} defines more than one materialized view", source_file),
+        });
+    }
+
+    let name = relation_name(&caps[1]);
+    let query = &caps[2];
+
+    // Names defined by WITH ... AS ( are not relations
+    let cte_re = Regex::new(r"(?i)(?:\bWITH(?:\s+RECURSIVE)?|,)\s*(\w+)\s+AS\s*\(").unwrap();
+    let ctes: HashSet<String> = cte_re
+        .captures_iter(query)
+        .map(|c| c[1].to_lowercase())
+        .collect();
+
+    let source_re = Regex::new(r#"(?i)\b(?:FROM|JOIN)\s+((?:"?\w+"?\.)?"?\w+"?)"#).unwrap();
+    let mut depends_on: Vec<String> = Vec::new();
+    for caps in source_re.captures_iter(query) {
+        let source = relation_name(&caps[1]);
+        if source != name && !ctes.contains(&source) && !depends_on.contains(&source) {
+            depends_on.push(source);
+        }
+    }
+
+    let normalized = Regex::new(r"\s+").unwrap().replace_all(&sql, " ").trim().to_lowercase();
+    let mut hasher = Sha256::new();
+    hasher.update(normalized.as_bytes());
+
+    Ok(MaterializedView {
+        name,
+        refresh,
+        depends_on,
+        sql: content.to_string(),
+        checksum: hex::encode(hasher.finalize()),
+        source_file: source_file.to_string(),
+    })
+}
+
+/// Lowercased, unquoted relation name without the `public.` schema
+fn relation_name(raw: &str) -> String {
+    let name = raw.replace('"', "").to_lowercase();
+    match name.strip_prefix("public.") {
+        Some(unqualified) => unqualified.to_string(),
+        None => name,
+    }
+}
+
+fn is_valid_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 63
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::DependencyAnalyzer;
+    use crate::schema::test_db::{DB_LOCK, test_pool};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_concurrent_matview_ordered_after_sources() {
+        let dir = TempDir::new().unwrap();
+        let tables_dir = dir.path().join("tables");
+        fs::create_dir_all(&tables_dir).unwrap();
+        fs::write(
+            tables_dir.join("customers.pssql"),
+            "CREATE TABLE customers (id SERIAL PRIMARY KEY, name TEXT);",
+        )
+        .unwrap();
+        fs::write(
+            tables_dir.join("orders.pssql"),
+            "CREATE TABLE orders (id SERIAL PRIMARY KEY, customer_id INT REFERENCES customers(id), total NUMERIC, created_at DATE);",
+        )
+        .unwrap();
+
+        let matviews_dir = dir.path().join("matviews");
+        fs::create_dir_all(&matviews_dir).unwrap();
+        fs::write(
+            matviews_dir.join("a_top_customers.pssql"),
+            "CREATE MATERIALIZED VIEW top_customers AS\n\
+             SELECT customer_id, SUM(revenue) AS revenue FROM customer_sales GROUP BY customer_id;",
+        )
+        .unwrap();
+        fs::write(
+            matviews_dir.join("customer_sales.pssql"),
+            "-- Revenue per customer and day\n\
+             -- @refresh: concurrently\n\
+             CREATE MATERIALIZED VIEW customer_sales AS\n\
+             WITH recent AS (SELECT * FROM orders WHERE created_at > now() - interval '1 year')\n\
+             SELECT c.id AS customer_id, r.created_at AS day, SUM(r.total) AS revenue\n\
+             FROM recent r JOIN public.customers c ON c.id = r.customer_id\n\
+             GROUP BY 1, 2\n\
+             WITH DATA;\n\
+             CREATE UNIQUE INDEX customer_sales_key ON customer_sales (customer_id, day);",
+        )
+        .unwrap();
+
+        let deployer = MatviewDeployer::new();
+        let view = deployer.parse_matview(&matviews_dir.join("customer_sales.pssql")).unwrap();
+        assert_eq!(view.name, "customer_sales");
+        assert_eq!(view.refresh, RefreshStrategy::Concurrently);
+        assert_eq!(view.depends_on, vec!["orders", "customers"]);
+
+        // Views are loaded in creation order, not file order
+        let views = deployer.load_matviews(&matviews_dir).unwrap();
+        let names: Vec<&str> = views.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["customer_sales", "top_customers"]);
+        assert_eq!(views[1].refresh, RefreshStrategy::Standard);
+
+        let tables = DependencyAnalyzer::analyze_directory(&tables_dir).unwrap().creation_order;
+        let order = creation_order(&tables, &views).unwrap();
+        let position = |name: &str| order.iter().position(|n| n == name).unwrap();
+        assert!(position("customer_sales") > position("orders"));
+        assert!(position("customer_sales") > position("customers"));
+        assert!(position("top_customers") > position("customer_sales"));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_verify_and_refresh() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let _guard = DB_LOCK.lock().await;
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS mv_test_sales CASCADE;
+                 DROP TABLE IF EXISTS _stonescriptdb_gateway_matviews;
+                 CREATE TABLE mv_test_sales (id SERIAL PRIMARY KEY, day DATE NOT NULL, amount INT NOT NULL);
+                 INSERT INTO mv_test_sales (day, amount) VALUES ('2024-01-01', 5), ('2024-01-01', 7);",
+            )
+            .await
+            .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("mv_test_daily.pssql"),
+            "-- @refresh: concurrently\n\
+             CREATE MATERIALIZED VIEW mv_test_daily AS SELECT day, SUM(amount) AS total FROM mv_test_sales GROUP BY day;\n\
+             CREATE UNIQUE INDEX mv_test_daily_day ON mv_test_daily (day);",
+        )
+        .unwrap();
+
+        let deployer = MatviewDeployer::new();
+        assert_eq!(deployer.deploy_matviews(&pool, "gw_test", dir.path()).await.unwrap(), 1);
+        // Unchanged views are skipped
+        assert_eq!(deployer.deploy_matviews(&pool, "gw_test", dir.path()).await.unwrap(), 0);
+
+        let verification = deployer.verify_matviews(&pool, "gw_test", dir.path()).await.unwrap();
+        assert!(verification.is_ok(), "{:?}", verification);
+
+        client
+            .batch_execute("INSERT INTO mv_test_sales (day, amount) VALUES ('2024-01-01', 30)")
+            .await
+            .unwrap();
+        let strategy = deployer.refresh_matview(&pool, "gw_test", "mv_test_daily", None).await.unwrap();
+        assert_eq!(strategy, RefreshStrategy::Concurrently);
+        let total: i64 = client
+            .query_one("SELECT total FROM mv_test_daily", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(total, 42);
+
+        // A view dropped by hand is reported and recreated on the next deploy
+        client.batch_execute("DROP MATERIALIZED VIEW mv_test_daily").await.unwrap();
+        let verification = deployer.verify_matviews(&pool, "gw_test", dir.path()).await.unwrap();
+        assert_eq!(verification.missing, vec!["mv_test_daily"]);
+        assert_eq!(deployer.deploy_matviews(&pool, "gw_test", dir.path()).await.unwrap(), 1);
+
+        assert!(deployer.refresh_matview(&pool, "gw_test", "mv_test_daily; DROP", None).await.is_err());
+
+        client
+            .batch_execute(
+                "DROP TABLE mv_test_sales CASCADE;
+                 DROP TABLE _stonescriptdb_gateway_matviews;",
+            )
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_comments_and_refresh_do_not_change_checksum() {
+        let a = parse_matview_sql("CREATE MATERIALIZED VIEW v AS SELECT 1 FROM t;", "v.pssql").unwrap();
+        let b = parse_matview_sql(
+            "-- @refresh: concurrently\ncreate materialized view v as\n  select 1 from t;",
+            "v.pssql",
+        )
+        .unwrap();
+        assert_eq!(a.checksum, b.checksum);
+
+        let err = parse_matview_sql("-- @refresh: sometimes\nCREATE MATERIALIZED VIEW v AS SELECT 1;", "v.pssql");
+        assert!(err.is_err());
+    }
+}
@@ -1,11 +1,26 @@
 use crate::error::{GatewayError, Result};
+use crate::schema::checksum_drift::AppliedChecksum;
 use crate::schema::DependencyAnalyzer;
-use deadpool_postgres::Pool;
+use deadpool_postgres::{Object, Pool};
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::Instant;
 use tracing::{debug, info, warn};
 
+/// Suffix of paired down-migration files (`002_add_email.down.pssql`)
+pub const DOWN_MIGRATION_SUFFIX: &str = ".down.pssql";
+
+/// How long a migrate run waits for another run on the same database
+pub const MIGRATION_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// First advisory lock key, keeping ours apart from locks taken by applications
+const MIGRATION_LOCK_NAMESPACE: i32 = 0x5344_4247;
+
+/// Delay between attempts to take the migration lock
+const MIGRATION_LOCK_POLL: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Clone)]
 pub struct MigrationFile {
     pub name: String,
@@ -30,11 +45,106 @@ pub struct DependencyIssue {
     pub message: String,
 }
 
-pub struct MigrationRunner;
+/// What a migration run would do, as computed by [`MigrationRunner::plan_migrations`]
+#[derive(Debug, Clone, Default)]
+pub struct MigrationPlan {
+    /// Files that would be applied, in execution order
+    pub pending: Vec<String>,
+    /// Applied files whose content changed since (only with allow_checksum_drift)
+    pub drifted: Vec<String>,
+}
+
+pub struct MigrationRunner {
+    lock_timeout: Duration,
+}
+
+/// Session-level advisory lock serializing migrate runs on one database
+///
+/// The run applies its migrations on the connection holding the lock, so the
+/// lock costs no extra pool connection. If the guard is dropped without
+/// `release` (error or cancelled request), the connection is taken out of the
+/// pool and closed, which releases the lock with the session.
+struct MigrationLock {
+    client: Option<Object>,
+    database: String,
+}
+
+impl MigrationLock {
+    async fn acquire(pool: &Pool, database: &str, timeout: Duration) -> Result<Self> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            // Only hold a connection while trying, so waiting runs do not
+            // tie up pool connections
+            let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+                database: database.to_string(),
+                cause: e.to_string(),
+            })?;
+            let locked: bool = client
+                .query_one(
+                    "SELECT pg_try_advisory_lock($1, hashtext($2))",
+                    &[&MIGRATION_LOCK_NAMESPACE, &database],
+                )
+                .await
+                .map_err(|e| GatewayError::MigrationFailed {
+                    database: database.to_string(),
+                    migration: "migration lock".to_string(),
+                    cause: e.to_string(),
+                })?
+                .get(0);
+            if locked {
+                return Ok(Self {
+                    client: Some(client),
+                    database: database.to_string(),
+                });
+            }
+            drop(client);
+
+            if Instant::now() >= deadline {
+                return Err(GatewayError::MigrationInProgress {
+                    database: database.to_string(),
+                });
+            }
+            debug!("Waiting for another migration of {} to finish", database);
+            tokio::time::sleep(MIGRATION_LOCK_POLL).await;
+        }
+    }
+
+    fn client(&mut self) -> &mut Object {
+        self.client.as_mut().expect("migration lock already released")
+    }
+
+    async fn release(mut self) {
+        let Some(client) = self.client.take() else {
+            return;
+        };
+        let unlocked = client
+            .execute(
+                "SELECT pg_advisory_unlock($1, hashtext($2))",
+                &[&MIGRATION_LOCK_NAMESPACE, &self.database],
+            )
+            .await;
+        if let Err(e) = unlocked {
+            warn!("Failed to release migration lock for {}: {}", self.database, e);
+            drop(Object::take(client));
+        }
+    }
+}
+
+impl Drop for MigrationLock {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            // Closing the session is the only synchronous way to unlock
+            drop(Object::take(client));
+        }
+    }
+}
 
 impl MigrationRunner {
     pub fn new() -> Self {
-        Self
+        Self {
+            lock_timeout: MIGRATION_LOCK_TIMEOUT,
+        }
     }
 
     /// Validate that migrations are in correct dependency order
@@ -65,7 +175,7 @@ impl MigrationRunner {
             // Track which migration defines which tables
             if let Ok(analysis) = DependencyAnalyzer::analyze_sql(&content) {
                 for table in &analysis.tables {
-                    table_to_migration.insert(table.name.clone(), migration.name.clone());
+                    table_to_migration.insert(table.qualified_name(), migration.name.clone());
                 }
             }
         }
@@ -141,7 +251,10 @@ impl MigrationRunner {
             database: database.to_string(),
             cause: e.to_string(),
         })?;
+        self.create_migrations_table(&client, database).await
+    }
 
+    async fn create_migrations_table(&self, client: &Object, database: &str) -> Result<()> {
         client
             .execute(
                 r#"
@@ -164,12 +277,7 @@ impl MigrationRunner {
         Ok(())
     }
 
-    pub async fn get_applied_migrations(&self, pool: &Pool, database: &str) -> Result<Vec<String>> {
-        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
-            database: database.to_string(),
-            cause: e.to_string(),
-        })?;
-
+    async fn applied_migration_names(&self, client: &Object, database: &str) -> Result<Vec<String>> {
         let rows = client
             .query(
                 "SELECT migration_file FROM _stonescriptdb_gateway_migrations ORDER BY id",
@@ -185,6 +293,110 @@ impl MigrationRunner {
         Ok(rows.iter().map(|row| row.get(0)).collect())
     }
 
+    /// Applied migrations with the checksum recorded when each was applied, in order
+    ///
+    /// A database without a tracking table has nothing applied.
+    pub async fn applied_checksums(&self, pool: &Pool, database: &str) -> Result<Vec<AppliedChecksum>> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let rows = client
+            .query(
+                "SELECT migration_file, checksum FROM _stonescriptdb_gateway_migrations ORDER BY id",
+                &[],
+            )
+            .await;
+
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(e) if e.code() == Some(&tokio_postgres::error::SqlState::UNDEFINED_TABLE) => {
+                return Ok(Vec::new())
+            }
+            Err(e) => {
+                return Err(GatewayError::MigrationFailed {
+                    database: database.to_string(),
+                    migration: "query applied migrations".to_string(),
+                    cause: e.to_string(),
+                })
+            }
+        };
+
+        Ok(rows
+            .iter()
+            .map(|row| AppliedChecksum {
+                migration_file: row.get(0),
+                checksum: row.get(1),
+            })
+            .collect())
+    }
+
+    /// Mark migrations as applied without running them (e.g. to mirror another database)
+    pub async fn record_applied(&self, pool: &Pool, database: &str, applied: &[AppliedChecksum]) -> Result<()> {
+        self.ensure_migrations_table(pool, database).await?;
+
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        for m in applied {
+            client
+                .execute(
+                    "INSERT INTO _stonescriptdb_gateway_migrations (migration_file, checksum) VALUES ($1, $2) \
+                     ON CONFLICT (migration_file) DO NOTHING",
+                    &[&m.migration_file, &m.checksum],
+                )
+                .await
+                .map_err(|e| GatewayError::MigrationFailed {
+                    database: database.to_string(),
+                    migration: m.migration_file.clone(),
+                    cause: format!("Failed to record migration: {}", e),
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Migration files that a run would apply, in execution order, without applying anything
+    ///
+    /// Applied files are checked against their recorded checksums the same way
+    /// a run checks them: an edited file fails the plan unless
+    /// `allow_checksum_drift` is set, in which case it is listed as drifted.
+    /// Does not create the tracking table; a database without one has nothing applied.
+    pub async fn plan_migrations(
+        &self,
+        pool: &Pool,
+        database: &str,
+        migrations_dir: &Path,
+        allow_checksum_drift: bool,
+    ) -> Result<MigrationPlan> {
+        let applied = self.applied_checksums(pool, database).await?;
+
+        let migration_files = self.find_migration_files(migrations_dir)?;
+        let migration_files = if migration_files.is_empty() {
+            migration_files
+        } else {
+            self.order_by_dependencies(migration_files)?
+        };
+
+        let drifted = drifted_migrations(&migration_files, &applied);
+        if let Some(migration) = drifted.first().filter(|_| !allow_checksum_drift) {
+            return Err(GatewayError::MigrationFailed {
+                database: database.to_string(),
+                migration: migration.clone(),
+                cause: "File changed after it was applied (checksum mismatch). Add a new migration instead of editing an applied one, or set allow_checksum_drift".to_string(),
+            });
+        }
+
+        let applied: Vec<String> = applied.into_iter().map(|m| m.migration_file).collect();
+        Ok(MigrationPlan {
+            pending: pending_migrations(&migration_files, &applied),
+            drifted,
+        })
+    }
+
     pub fn find_migration_files(&self, migrations_dir: &Path) -> Result<Vec<MigrationFile>> {
         if !migrations_dir.exists() {
             debug!(
@@ -213,6 +425,11 @@ impl MigrationRunner {
                             .unwrap_or("")
                             .to_string();
 
+                        // Down files are only run by rollback_last
+                        if name.ends_with(DOWN_MIGRATION_SUFFIX) {
+                            continue;
+                        }
+
                         let content = fs::read_to_string(&path).map_err(|e| {
                             GatewayError::SchemaExtractionFailed {
                                 cause: format!("Failed to read migration file {:?}: {}", path, e),
@@ -257,8 +474,8 @@ impl MigrationRunner {
 
             if let Ok(analysis) = DependencyAnalyzer::analyze_sql(&content) {
                 for table in &analysis.tables {
-                    table_to_migration.insert(table.name.clone(), i);
-                    tables.push(table.name.clone());
+                    table_to_migration.insert(table.qualified_name(), i);
+                    tables.push(table.qualified_name());
 
                     for dep in &table.depends_on {
                         deps.insert(dep.clone());
@@ -334,11 +551,29 @@ impl MigrationRunner {
                 .map(|(_, m)| m.name.clone())
                 .collect();
 
+            let graph: std::collections::HashMap<String, Vec<String>> = migration_graph
+                .iter()
+                .enumerate()
+                .map(|(i, deps)| {
+                    let deps = deps.iter().map(|&dep| migrations[dep].name.clone()).collect();
+                    (migrations[i].name.clone(), deps)
+                })
+                .collect();
+            let cycles = DependencyAnalyzer::describe_cycles(&graph);
+
             return Err(GatewayError::SchemaExtractionFailed {
-                cause: format!(
-                    "Circular dependency detected in migrations: {}",
-                    remaining.join(", ")
-                ),
+                cause: if cycles.is_empty() {
+                    format!(
+                        "Circular dependency detected in migrations: {}",
+                        remaining.join(", ")
+                    )
+                } else {
+                    format!(
+                        "Circular dependency detected in migrations: {} (migrations left unordered: {})",
+                        cycles.join("; "),
+                        remaining.join(", ")
+                    )
+                },
             });
         }
 
@@ -365,6 +600,7 @@ impl MigrationRunner {
         database: &str,
         migrations_dir: &Path,
         validate_deps: bool,
+        allow_checksum_drift: bool,
     ) -> Result<(usize, Option<DependencyValidation>)> {
         // Validate dependencies first if requested
         let validation = if validate_deps {
@@ -385,7 +621,9 @@ impl MigrationRunner {
             None
         };
 
-        let count = self.run_migrations(pool, database, migrations_dir).await?;
+        let count = self
+            .run_migrations(pool, database, migrations_dir, allow_checksum_drift)
+            .await?;
         Ok((count, validation))
     }
 
@@ -394,23 +632,48 @@ impl MigrationRunner {
         pool: &Pool,
         database: &str,
         migrations_dir: &Path,
+        allow_checksum_drift: bool,
     ) -> Result<usize> {
-        self.run_migrations_ordered(pool, database, migrations_dir, true).await
+        self.run_migrations_ordered(pool, database, migrations_dir, true, allow_checksum_drift)
+            .await
     }
 
     /// Run migrations with optional automatic dependency ordering
+    ///
+    /// An already-applied migration whose file no longer matches its stored
+    /// checksum is an error unless `allow_checksum_drift` is set. Concurrent
+    /// runs on the same database are serialized with an advisory lock; a run
+    /// that cannot get it within the lock timeout fails with
+    /// `MigrationInProgress`.
     pub async fn run_migrations_ordered(
         &self,
         pool: &Pool,
         database: &str,
         migrations_dir: &Path,
         auto_order: bool,
+        allow_checksum_drift: bool,
+    ) -> Result<usize> {
+        let mut lock = MigrationLock::acquire(pool, database, self.lock_timeout).await?;
+        let result = self
+            .apply_pending_migrations(lock.client(), database, migrations_dir, auto_order, allow_checksum_drift)
+            .await;
+        lock.release().await;
+        result
+    }
+
+    async fn apply_pending_migrations(
+        &self,
+        client: &mut Object,
+        database: &str,
+        migrations_dir: &Path,
+        auto_order: bool,
+        allow_checksum_drift: bool,
     ) -> Result<usize> {
         // Ensure migrations table exists
-        self.ensure_migrations_table(pool, database).await?;
+        self.create_migrations_table(client, database).await?;
 
         // Get already applied migrations
-        let applied = self.get_applied_migrations(pool, database).await?;
+        let applied = self.applied_migration_names(client, database).await?;
         debug!(
             "Database {} has {} applied migrations",
             database,
@@ -436,6 +699,22 @@ impl MigrationRunner {
 
         for migration in migration_files {
             if applied.contains(&migration.name) {
+                if !self
+                    .verify_checksum(client, database, &migration.name, &migration.checksum)
+                    .await?
+                {
+                    if !allow_checksum_drift {
+                        return Err(GatewayError::MigrationFailed {
+                            database: database.to_string(),
+                            migration: migration.name.clone(),
+                            cause: "File changed after it was applied (checksum mismatch). Add a new migration instead of editing an applied one, or set allow_checksum_drift".to_string(),
+                        });
+                    }
+                    warn!(
+                        "Applied migration {} changed on disk, ignoring (allow_checksum_drift)",
+                        migration.name
+                    );
+                }
                 debug!("Skipping already applied migration: {}", migration.name);
                 continue;
             }
@@ -451,12 +730,16 @@ impl MigrationRunner {
                 }
             })?;
 
-            let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            // Apply the migration and record it atomically, so a failure halfway
+            // through a multi-statement file leaves nothing behind (dropping the
+            // transaction without commit rolls it back)
+            let transaction = client.transaction().await.map_err(|e| GatewayError::MigrationFailed {
                 database: database.to_string(),
-                cause: e.to_string(),
+                migration: migration.name.clone(),
+                cause: format!("Failed to start transaction: {}", e),
             })?;
 
-            client
+            transaction
                 .batch_execute(&sql)
                 .await
                 .map_err(|e| {
@@ -485,7 +768,7 @@ impl MigrationRunner {
                 })?;
 
             // Record the migration
-            client
+            transaction
                 .execute(
                     "INSERT INTO _stonescriptdb_gateway_migrations (migration_file, checksum) VALUES ($1, $2)",
                     &[&migration.name, &migration.checksum],
@@ -497,6 +780,12 @@ impl MigrationRunner {
                     cause: format!("Failed to record migration: {}", e),
                 })?;
 
+            transaction.commit().await.map_err(|e| GatewayError::MigrationFailed {
+                database: database.to_string(),
+                migration: migration.name.clone(),
+                cause: format!("Failed to commit migration: {}", e),
+            })?;
+
             count += 1;
             info!(
                 "Successfully applied migration: {} (checksum: {})",
@@ -507,18 +796,131 @@ impl MigrationRunner {
         Ok(count)
     }
 
-    pub async fn verify_checksum(
+    /// Undo the most recently applied migration using its paired `.down.pssql` file
+    ///
+    /// The down file runs in one transaction together with the removal of the
+    /// tracking row. Takes the same advisory lock as migrate runs, so the two
+    /// never interleave on one database. Returns the name of the migration
+    /// that was rolled back.
+    pub async fn rollback_last(
         &self,
         pool: &Pool,
         database: &str,
-        migration_name: &str,
-        expected_checksum: &str,
-    ) -> Result<bool> {
-        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+        migrations_dir: &Path,
+    ) -> Result<String> {
+        let mut lock = MigrationLock::acquire(pool, database, self.lock_timeout).await?;
+        let result = self.undo_latest_migration(lock.client(), database, migrations_dir).await;
+        lock.release().await;
+        result
+    }
+
+    async fn undo_latest_migration(
+        &self,
+        client: &mut Object,
+        database: &str,
+        migrations_dir: &Path,
+    ) -> Result<String> {
+        self.create_migrations_table(client, database).await?;
+
+        let row = client
+            .query_opt(
+                "SELECT migration_file FROM _stonescriptdb_gateway_migrations ORDER BY id DESC LIMIT 1",
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::MigrationFailed {
+                database: database.to_string(),
+                migration: "query applied migrations".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        let migration_name: String = match row {
+            Some(row) => row.get(0),
+            None => {
+                return Err(GatewayError::InvalidRequest {
+                    message: format!("No applied migrations to roll back in database '{}'", database),
+                })
+            }
+        };
+
+        let down_name = down_file_name(&migration_name);
+        let down_path = migrations_dir.join(&down_name);
+        if !down_path.is_file() {
+            return Err(GatewayError::MigrationFailed {
+                database: database.to_string(),
+                migration: migration_name,
+                cause: format!("No down migration found: expected {}", down_name),
+            });
+        }
+
+        let sql = fs::read_to_string(&down_path).map_err(|e| GatewayError::MigrationFailed {
             database: database.to_string(),
-            cause: e.to_string(),
+            migration: down_name.clone(),
+            cause: format!("Failed to read file: {}", e),
         })?;
 
+        info!("Rolling back migration: {} on {} using {}", migration_name, database, down_name);
+
+        let transaction = client.transaction().await.map_err(|e| GatewayError::MigrationFailed {
+            database: database.to_string(),
+            migration: down_name.clone(),
+            cause: format!("Failed to start transaction: {}", e),
+        })?;
+
+        transaction.batch_execute(&sql).await.map_err(|e| {
+            let error_detail = if let Some(db_err) = e.as_db_error() {
+                format!(
+                    "{} - {} (HINT: {})",
+                    db_err.message(),
+                    db_err.detail().unwrap_or("no additional detail"),
+                    db_err.hint().unwrap_or("no hint provided")
+                )
+            } else {
+                e.to_string()
+            };
+
+            warn!(
+                "Down migration '{}' failed for database '{}': {}",
+                down_name, database, error_detail
+            );
+
+            GatewayError::MigrationFailed {
+                database: database.to_string(),
+                migration: down_name.clone(),
+                cause: error_detail,
+            }
+        })?;
+
+        transaction
+            .execute(
+                "DELETE FROM _stonescriptdb_gateway_migrations WHERE migration_file = $1",
+                &[&migration_name],
+            )
+            .await
+            .map_err(|e| GatewayError::MigrationFailed {
+                database: database.to_string(),
+                migration: down_name.clone(),
+                cause: format!("Failed to remove migration record: {}", e),
+            })?;
+
+        transaction.commit().await.map_err(|e| GatewayError::MigrationFailed {
+            database: database.to_string(),
+            migration: down_name.clone(),
+            cause: format!("Failed to commit rollback: {}", e),
+        })?;
+
+        info!("Rolled back migration: {} on {}", migration_name, database);
+
+        Ok(migration_name)
+    }
+
+    pub async fn verify_checksum(
+        &self,
+        client: &Object,
+        database: &str,
+        migration_name: &str,
+        expected_checksum: &str,
+    ) -> Result<bool> {
         let row = client
             .query_opt(
                 "SELECT checksum FROM _stonescriptdb_gateway_migrations WHERE migration_file = $1",
@@ -555,6 +957,34 @@ impl Default for MigrationRunner {
     }
 }
 
+/// Names of the (ordered) migration files not applied yet
+fn pending_migrations(migration_files: &[MigrationFile], applied: &[String]) -> Vec<String> {
+    migration_files
+        .iter()
+        .filter(|m| !applied.contains(&m.name))
+        .map(|m| m.name.clone())
+        .collect()
+}
+
+/// Names of the applied migration files whose checksum no longer matches the file on disk
+fn drifted_migrations(migration_files: &[MigrationFile], applied: &[AppliedChecksum]) -> Vec<String> {
+    migration_files
+        .iter()
+        .filter(|m| {
+            applied
+                .iter()
+                .any(|a| a.migration_file == m.name && a.checksum != m.checksum)
+        })
+        .map(|m| m.name.clone())
+        .collect()
+}
+
+/// Name of the down file paired with a migration (`002_add_email.pssql` -> `002_add_email.down.pssql`)
+pub fn down_file_name(migration_name: &str) -> String {
+    let stem = migration_name.strip_suffix(".pssql").unwrap_or(migration_name);
+    format!("{}{}", stem, DOWN_MIGRATION_SUFFIX)
+}
+
 fn compute_checksum(content: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(content.as_bytes());
@@ -564,6 +994,7 @@ fn compute_checksum(content: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schema::test_db::{DB_LOCK, test_pool};
 
     #[test]
     fn test_compute_checksum() {
@@ -579,4 +1010,337 @@ mod tests {
         let checksum3 = compute_checksum("CREATE TABLE other (id INT);");
         assert_ne!(checksum, checksum3);
     }
+
+    #[test]
+    fn test_down_files_are_not_migrations() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("002_add_email.pssql"), "SELECT 1;").unwrap();
+        fs::write(temp_dir.path().join("002_add_email.down.pssql"), "SELECT 1;").unwrap();
+
+        let files = MigrationRunner::new().find_migration_files(temp_dir.path()).unwrap();
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+
+        assert_eq!(names, vec!["002_add_email.pssql"]);
+        assert_eq!(down_file_name("002_add_email.pssql"), "002_add_email.down.pssql");
+    }
+
+    #[test]
+    fn test_pending_migrations_keep_execution_order() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        // 001 references a table created in 002, so 002 has to run first
+        fs::write(
+            temp_dir.path().join("001_orders.pssql"),
+            "CREATE TABLE orders (id SERIAL PRIMARY KEY, user_id INT REFERENCES users(id));",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("002_users.pssql"),
+            "CREATE TABLE users (id SERIAL PRIMARY KEY);",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("003_seed.pssql"), "SELECT 1;").unwrap();
+
+        let runner = MigrationRunner::new();
+        let files = runner.find_migration_files(temp_dir.path()).unwrap();
+        let ordered = runner.order_by_dependencies(files).unwrap();
+
+        let all = pending_migrations(&ordered, &[]);
+        let users = all.iter().position(|m| m == "002_users.pssql").unwrap();
+        let orders = all.iter().position(|m| m == "001_orders.pssql").unwrap();
+        assert!(users < orders);
+        assert_eq!(all.len(), 3);
+
+        let remaining = pending_migrations(&ordered, &["002_users.pssql".to_string()]);
+        assert!(!remaining.contains(&"002_users.pssql".to_string()));
+        assert_eq!(remaining.len(), 2);
+    }
+
+    #[test]
+    fn test_migration_cycle_names_path() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for (file, table, target) in [
+            ("001_a.pssql", "a", "b"),
+            ("002_b.pssql", "b", "c"),
+            ("003_c.pssql", "c", "a"),
+        ] {
+            fs::write(
+                temp_dir.path().join(file),
+                format!("CREATE TABLE {} (id INT PRIMARY KEY, ref_id INT REFERENCES {}(id));", table, target),
+            )
+            .unwrap();
+        }
+
+        let runner = MigrationRunner::new();
+        let files = runner.find_migration_files(temp_dir.path()).unwrap();
+        let err = runner.order_by_dependencies(files).unwrap_err().to_string();
+        assert!(
+            err.contains("001_a.pssql -> 002_b.pssql -> 003_c.pssql -> 001_a.pssql"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_migration_rolls_back() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let _guard = DB_LOCK.lock().await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("001_partial.pssql"),
+            "CREATE TABLE migration_tx_test (id INT);\nCREATE TABLE migration_tx_broken (id NOT_A_TYPE);",
+        )
+        .unwrap();
+
+        let runner = MigrationRunner::new();
+        let result = runner
+            .run_migrations_ordered(&pool, "test", temp_dir.path(), false, false)
+            .await;
+        assert!(result.is_err());
+
+        let client = pool.get().await.unwrap();
+        let table_exists: bool = client
+            .query_one("SELECT to_regclass('migration_tx_test') IS NOT NULL", &[])
+            .await
+            .unwrap()
+            .get(0);
+        let tracked: bool = client
+            .query_one(
+                "SELECT EXISTS (SELECT 1 FROM _stonescriptdb_gateway_migrations WHERE migration_file = $1)",
+                &[&"001_partial.pssql"],
+            )
+            .await
+            .unwrap()
+            .get(0);
+
+        assert!(!table_exists, "first statement should be rolled back");
+        assert!(!tracked, "failed migration should not be recorded");
+    }
+
+    #[tokio::test]
+    async fn test_rollback_last_undoes_migration() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let _guard = DB_LOCK.lock().await;
+
+        // Start from an empty tracking table
+        let runner = MigrationRunner::new();
+        runner.ensure_migrations_table(&pool, "test").await.unwrap();
+        pool.get()
+            .await
+            .unwrap()
+            .batch_execute(
+                "DELETE FROM _stonescriptdb_gateway_migrations; DROP TABLE IF EXISTS rollback_test_users;",
+            )
+            .await
+            .unwrap();
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("001_rollback_test.pssql"),
+            "CREATE TABLE rollback_test_users (id INT);",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("001_rollback_test.down.pssql"),
+            "DROP TABLE rollback_test_users;",
+        )
+        .unwrap();
+
+        let applied = runner
+            .run_migrations_ordered(&pool, "test", temp_dir.path(), false, false)
+            .await
+            .unwrap();
+        assert_eq!(applied, 1);
+
+        let rolled_back = runner.rollback_last(&pool, "test", temp_dir.path()).await.unwrap();
+        assert_eq!(rolled_back, "001_rollback_test.pssql");
+
+        assert!(runner.applied_checksums(&pool, "test").await.unwrap().is_empty());
+        let table_exists: bool = pool
+            .get()
+            .await
+            .unwrap()
+            .query_one("SELECT to_regclass('rollback_test_users') IS NOT NULL", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert!(!table_exists);
+
+        // Nothing left to roll back
+        assert!(runner.rollback_last(&pool, "test", temp_dir.path()).await.is_err());
+
+        // A migration without a down file cannot be rolled back
+        fs::remove_file(temp_dir.path().join("001_rollback_test.down.pssql")).unwrap();
+        runner
+            .run_migrations_ordered(&pool, "test", temp_dir.path(), false, false)
+            .await
+            .unwrap();
+        let err = runner
+            .rollback_last(&pool, "test", temp_dir.path())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("001_rollback_test.down.pssql"));
+
+        pool.get()
+            .await
+            .unwrap()
+            .batch_execute("DELETE FROM _stonescriptdb_gateway_migrations; DROP TABLE rollback_test_users;")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_edited_applied_migration_is_rejected() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let _guard = DB_LOCK.lock().await;
+
+        let runner = MigrationRunner::new();
+        runner.ensure_migrations_table(&pool, "test").await.unwrap();
+        pool.get()
+            .await
+            .unwrap()
+            .execute(
+                "DELETE FROM _stonescriptdb_gateway_migrations WHERE migration_file = $1",
+                &[&"001_drift_test.pssql"],
+            )
+            .await
+            .unwrap();
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("001_drift_test.pssql");
+        fs::write(&path, "SELECT 1;").unwrap();
+        runner
+            .run_migrations_ordered(&pool, "test", temp_dir.path(), false, false)
+            .await
+            .unwrap();
+
+        let plan = runner
+            .plan_migrations(&pool, "test", temp_dir.path(), false)
+            .await
+            .unwrap();
+        assert!(plan.pending.is_empty() && plan.drifted.is_empty());
+
+        // Editing the applied file is caught on the next run, and by a dry run
+        fs::write(&path, "SELECT 2;").unwrap();
+        let err = runner
+            .plan_migrations(&pool, "test", temp_dir.path(), false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("001_drift_test.pssql"));
+        let plan = runner
+            .plan_migrations(&pool, "test", temp_dir.path(), true)
+            .await
+            .unwrap();
+        assert_eq!(plan.drifted, vec!["001_drift_test.pssql"]);
+        assert!(plan.pending.is_empty());
+        let err = runner
+            .run_migrations_ordered(&pool, "test", temp_dir.path(), false, false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("001_drift_test.pssql"));
+
+        // ...unless drift is explicitly allowed
+        let applied = runner
+            .run_migrations_ordered(&pool, "test", temp_dir.path(), false, true)
+            .await
+            .unwrap();
+        assert_eq!(applied, 0);
+
+        pool.get()
+            .await
+            .unwrap()
+            .execute(
+                "DELETE FROM _stonescriptdb_gateway_migrations WHERE migration_file = $1",
+                &[&"001_drift_test.pssql"],
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_runs_apply_migration_once() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let _guard = DB_LOCK.lock().await;
+
+        let runner = MigrationRunner::new();
+        runner.ensure_migrations_table(&pool, "test").await.unwrap();
+        pool.get()
+            .await
+            .unwrap()
+            .batch_execute(
+                "DELETE FROM _stonescriptdb_gateway_migrations WHERE migration_file = '001_lock_test.pssql';
+                 DROP TABLE IF EXISTS migration_lock_test;",
+            )
+            .await
+            .unwrap();
+
+        // Slow enough that the second run starts while the first is applying
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("001_lock_test.pssql"),
+            "SELECT pg_sleep(0.5);\nCREATE TABLE migration_lock_test (id INT);",
+        )
+        .unwrap();
+
+        let (first, second) = tokio::join!(
+            runner.run_migrations_ordered(&pool, "test", temp_dir.path(), false, false),
+            runner.run_migrations_ordered(&pool, "test", temp_dir.path(), false, false),
+        );
+        let mut applied = [first.unwrap(), second.unwrap()];
+        applied.sort();
+        assert_eq!(applied, [0, 1]);
+
+        pool.get()
+            .await
+            .unwrap()
+            .batch_execute(
+                "DELETE FROM _stonescriptdb_gateway_migrations WHERE migration_file = '001_lock_test.pssql';
+                 DROP TABLE migration_lock_test;",
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_fails_while_another_holds_the_lock() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let _guard = DB_LOCK.lock().await;
+
+        let holder = MigrationLock::acquire(&pool, "test", MIGRATION_LOCK_TIMEOUT)
+            .await
+            .unwrap();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let runner = MigrationRunner {
+            lock_timeout: Duration::from_millis(300),
+        };
+        let err = runner
+            .run_migrations_ordered(&pool, "test", temp_dir.path(), false, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, GatewayError::MigrationInProgress { .. }), "{}", err);
+
+        // Rollbacks wait on the same lock
+        let err = runner.rollback_last(&pool, "test", temp_dir.path()).await.unwrap_err();
+        assert!(matches!(err, GatewayError::MigrationInProgress { .. }), "{}", err);
+
+        // Free again once the holder releases
+        holder.release().await;
+        assert_eq!(
+            runner
+                .run_migrations_ordered(&pool, "test", temp_dir.path(), false, false)
+                .await
+                .unwrap(),
+            0
+        );
+    }
 }
@@ -1,27 +1,60 @@
+pub(crate) mod archive;
 mod audit;
 mod changelog;
+mod checksum_drift;
 mod custom_types;
 mod dependency;
 mod diff;
 mod extensions;
 mod extractor;
 mod functions;
+mod generator;
+mod grants;
+mod hooks;
+mod impact;
+mod matviews;
 mod migration;
+mod ownership;
+mod policies;
+mod rollout;
 mod seeder;
+mod sequences;
+mod sql;
+#[cfg(feature = "sql-parser-fallback")]
+mod sql_fallback;
 mod tables;
+#[cfg(test)]
+pub(crate) mod test_db;
+mod tracking;
 mod types;
+mod updated_at;
+mod validation;
 mod verifier;
 
+pub use archive::ArchiveReport;
 pub use audit::AuditLogger;
 pub use changelog::{ChangelogManager, ChangelogEntry, ChangelogRecord, ChangeType as ChangelogChangeType};
+pub use checksum_drift::{find_checksum_drift, AppliedChecksum, ChecksumDrift};
 pub use custom_types::CustomTypeManager;
-pub use dependency::{DependencyAnalyzer, DependencyAnalysis, TableInfo, ForeignKeyDependency};
-pub use diff::{SchemaDiffChecker, SchemaDiff, SchemaChange, ChangeType, ChangeCompatibility, ColumnSchema, TableSchema};
+pub use dependency::{DependencyAnalyzer, DependencyAnalysis, ParseError, TableInfo, ForeignKeyDependency};
+pub use diff::{SchemaDiffChecker, SchemaDiff, SchemaChange, ChangeType, ChangeCompatibility, ColumnOrderDrift, ColumnSchema, TableSchema};
 pub use extensions::ExtensionManager;
 pub use extractor::SchemaExtractor;
-pub use functions::FunctionDeployer;
-pub use migration::MigrationRunner;
+pub use functions::{FunctionDeployer, FunctionVerification};
+pub use generator::MigrationGenerator;
+pub use grants::{GrantManager, GrantVerification};
+pub use hooks::{HookRunner, HookStage};
+pub use impact::{DropImpact, DropImpactAnalyzer, TypeImpact, TypeImpactAnalyzer};
+pub use matviews::{MatviewDeployer, MatviewVerification, RefreshStrategy};
+pub use migration::{down_file_name, MigrationRunner};
+pub use ownership::{OutOfScopeObject, OwnershipChecker};
+pub use policies::{PolicyManager, PolicyVerification};
+pub use rollout::RolloutCheckpoint;
 pub use seeder::{SeederRunner, SeederResult, SeederValidation};
+pub use sequences::{LaggingSequence, SequenceChecker};
 pub use tables::{TableDeployer, TableDefinition, TableDeployResult};
+pub use tracking::{TrackingRepair, TrackingRepairReport};
 pub use types::{TypeChecker, TypeCompatibility};
+pub use updated_at::{UpdatedAtTriggerManager, UpdatedAtVerification};
+pub use validation::SchemaValidator;
 pub use verifier::{SchemaVerifier, VerificationResult};
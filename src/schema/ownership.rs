@@ -0,0 +1,236 @@
+//! Ownership check for migrations
+//!
+//! In a shared database a migration can reach a table that belongs to another
+//! application. The tables a migration alters or writes to are compared with
+//! the tables the schema owns: the ones declared in `tables/` and the ones its
+//! migrations create.
+
+use crate::error::{GatewayError, Result};
+use crate::schema::dependency::{qualified_table_name, split_qualified_name};
+use crate::schema::migration::DOWN_MIGRATION_SUFFIX;
+use crate::schema::sql::strip_comments;
+use crate::schema::DependencyAnalyzer;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Prefix of the tables the gateway keeps in every database
+const GATEWAY_TABLE_PREFIX: &str = "_stonescriptdb_gateway";
+
+/// A table a migration touches that the schema does not own
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutOfScopeObject {
+    pub migration: String,
+    pub table: String,
+    /// Kind of statement, e.g. `ALTER TABLE` or `INSERT INTO`
+    pub statement: String,
+}
+
+impl OutOfScopeObject {
+    pub fn finding(&self) -> String {
+        format!(
+            "Migration {} runs {} on '{}', which is not declared by the schema",
+            self.migration, self.statement, self.table
+        )
+    }
+}
+
+/// Tables owned by one schema
+pub struct OwnershipChecker {
+    owned: BTreeSet<String>,
+}
+
+impl OwnershipChecker {
+    /// Collect the tables declared in `tables/` and created by `migrations/`
+    pub fn from_schema(tables_dir: &Path, migrations_dir: &Path) -> Result<Self> {
+        let mut owned = BTreeSet::new();
+
+        if tables_dir.exists() {
+            let analysis = DependencyAnalyzer::analyze_directory(tables_dir).map_err(|e| {
+                GatewayError::SchemaExtractionFailed {
+                    cause: e.to_string(),
+                }
+            })?;
+            owned.extend(analysis.tables.iter().map(|table| table.qualified_name()));
+        }
+
+        if migrations_dir.exists() {
+            let entries = fs::read_dir(migrations_dir).map_err(|e| GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to read {:?}: {}", migrations_dir, e),
+            })?;
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let path = entry.path();
+                let is_down = path.to_string_lossy().ends_with(DOWN_MIGRATION_SUFFIX);
+                if path.extension().map(|ext| ext == "pssql").unwrap_or(false) && !is_down {
+                    let sql = fs::read_to_string(&path).map_err(|e| GatewayError::SchemaExtractionFailed {
+                        cause: format!("Failed to read {:?}: {}", path, e),
+                    })?;
+                    owned.extend(created_tables(&sql));
+                }
+            }
+        }
+
+        Ok(Self { owned })
+    }
+
+    /// Tables the migration touches outside the schema, in statement order
+    pub fn check(&self, migration: &str, sql: &str) -> Vec<OutOfScopeObject> {
+        let mut seen = BTreeSet::new();
+        target_tables(sql)
+            .into_iter()
+            .filter(|(_, table)| !self.owns(table))
+            .filter(|(statement, table)| seen.insert((statement.clone(), table.clone())))
+            .map(|(statement, table)| OutOfScopeObject {
+                migration: migration.to_string(),
+                table,
+                statement,
+            })
+            .collect()
+    }
+
+    fn owns(&self, table: &str) -> bool {
+        table.starts_with(GATEWAY_TABLE_PREFIX) || self.owned.contains(table)
+    }
+}
+
+/// Tables created by `CREATE TABLE` statements
+fn created_tables(sql: &str) -> Vec<String> {
+    let create_re = Regex::new(
+        r#"(?i)\bcreate\s+(?:(?:global|local)\s+)?(?:(?:temp|temporary|unlogged)\s+)?table\s+(?:if\s+not\s+exists\s+)?([\w."]+)"#,
+    )
+    .unwrap();
+
+    create_re
+        .captures_iter(&strip_comments(sql))
+        .map(|caps| table_name(&caps[1]))
+        .collect()
+}
+
+/// (statement, table) for every statement that alters or writes to a table
+fn target_tables(sql: &str) -> Vec<(String, String)> {
+    let patterns = [
+        ("ALTER TABLE", r#"\balter\s+table\s+(?:if\s+exists\s+)?(?:only\s+)?([\w."]+)"#),
+        ("DROP TABLE", r#"\bdrop\s+table\s+(?:if\s+exists\s+)?([\w."]+(?:\s*,\s*[\w."]+)*)"#),
+        ("TRUNCATE", r#"\btruncate\s+(?:table\s+)?(?:only\s+)?([\w."]+(?:\s*,\s*[\w."]+)*)"#),
+        (
+            "CREATE INDEX",
+            r#"\bcreate\s+(?:unique\s+)?index\s+(?:concurrently\s+)?(?:(?:if\s+not\s+exists\s+)?[\w."]+\s+)?on\s+(?:only\s+)?([\w."]+)"#,
+        ),
+        ("INSERT INTO", r#"\binsert\s+into\s+([\w."]+)"#),
+        ("UPDATE", r#"\bupdate\s+(?:only\s+)?([\w."]+)\s+(?:(?:as\s+)?\w+\s+)?set\b"#),
+        ("DELETE FROM", r#"\bdelete\s+from\s+(?:only\s+)?([\w."]+)"#),
+    ];
+
+    let sql = strip_comments(sql);
+    let mut targets: Vec<(usize, String, String)> = Vec::new();
+    for (statement, pattern) in patterns {
+        let re = Regex::new(&format!("(?i){}", pattern)).unwrap();
+        for caps in re.captures_iter(&sql) {
+            let position = caps.get(0).unwrap().start();
+            for name in caps[1].split(',') {
+                targets.push((position, statement.to_string(), table_name(name)));
+            }
+        }
+    }
+
+    targets.sort_by_key(|(position, _, _)| *position);
+    targets
+        .into_iter()
+        .map(|(_, statement, table)| (statement, table))
+        .collect()
+}
+
+/// Name as used by `TableInfo::qualified_name` (quoted identifiers keep their case)
+fn table_name(raw: &str) -> String {
+    let (schema, table) = split_qualified_name(raw);
+    qualified_table_name(schema.as_deref(), &table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_altering_undeclared_table_is_out_of_scope() {
+        let dir = TempDir::new().unwrap();
+        let tables_dir = dir.path().join("tables");
+        let migrations_dir = dir.path().join("migrations");
+        fs::create_dir_all(&tables_dir).unwrap();
+        fs::create_dir_all(&migrations_dir).unwrap();
+        fs::write(
+            tables_dir.join("orders.pssql"),
+            "CREATE TABLE orders (id SERIAL PRIMARY KEY, total NUMERIC);",
+        )
+        .unwrap();
+        fs::write(
+            migrations_dir.join("001_audit.pssql"),
+            "CREATE TABLE order_audit (id SERIAL PRIMARY KEY);",
+        )
+        .unwrap();
+
+        let checker = OwnershipChecker::from_schema(&tables_dir, &migrations_dir).unwrap();
+        let sql = "ALTER TABLE orders ADD COLUMN note TEXT;
+                   INSERT INTO order_audit DEFAULT VALUES;
+                   -- ALTER TABLE commented_out ADD COLUMN x INT;
+                   ALTER TABLE public.\"Invoices\" ADD COLUMN order_id INT;
+                   UPDATE billing.accounts SET active = true;
+                   INSERT INTO _stonescriptdb_gateway_migrations (migration_file, checksum) VALUES ('x', 'y');";
+        let out_of_scope = checker.check("002_touch_invoices.pssql", sql);
+
+        assert_eq!(
+            out_of_scope,
+            vec![
+                OutOfScopeObject {
+                    migration: "002_touch_invoices.pssql".to_string(),
+                    table: "Invoices".to_string(),
+                    statement: "ALTER TABLE".to_string(),
+                },
+                OutOfScopeObject {
+                    migration: "002_touch_invoices.pssql".to_string(),
+                    table: "billing.accounts".to_string(),
+                    statement: "UPDATE".to_string(),
+                },
+            ]
+        );
+        assert!(out_of_scope[0].finding().contains("'Invoices'"));
+    }
+
+    #[test]
+    fn test_quoted_names_keep_their_case() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("accounts.pssql"),
+            "CREATE TABLE \"Accounts\" (id SERIAL PRIMARY KEY);",
+        )
+        .unwrap();
+
+        let checker = OwnershipChecker::from_schema(dir.path(), &dir.path().join("migrations")).unwrap();
+        assert!(checker.check("001.pssql", "ALTER TABLE \"Accounts\" ADD COLUMN note TEXT;").is_empty());
+        // Unquoted, PostgreSQL folds it to a different table
+        let out_of_scope = checker.check("001.pssql", "ALTER TABLE Accounts ADD COLUMN note TEXT;");
+        assert_eq!(out_of_scope.len(), 1);
+        assert_eq!(out_of_scope[0].table, "accounts");
+    }
+
+    #[test]
+    fn test_target_tables_cover_index_and_drop_statements() {
+        let targets = target_tables(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_email ON ONLY users (email);
+             DROP TABLE IF EXISTS legacy_a, legacy_b;
+             DELETE FROM sessions WHERE expired;",
+        );
+
+        assert_eq!(
+            targets,
+            vec![
+                ("CREATE INDEX".to_string(), "users".to_string()),
+                ("DROP TABLE".to_string(), "legacy_a".to_string()),
+                ("DROP TABLE".to_string(), "legacy_b".to_string()),
+                ("DELETE FROM".to_string(), "sessions".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,939 @@
+//! Row-level security policy manager
+//!
+//! Policies are defined in the `policies/` folder as `CREATE POLICY` statements
+//! (any number per file). Deploying a policy enables ROW LEVEL SECURITY on its
+//! table and recreates the policy; verification compares the declared policies
+//! against `pg_policies`.
+
+use crate::error::{GatewayError, Result};
+use crate::schema::sql::{split_statements, strip_comments};
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// A row-level security policy, as declared or as found in the database
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    pub name: String,
+    pub table: String,
+    /// PERMISSIVE (default) or RESTRICTIVE
+    pub permissive: bool,
+    /// ALL, SELECT, INSERT, UPDATE or DELETE
+    pub command: String,
+    /// Lowercased role names, sorted (`public` when not restricted)
+    pub roles: Vec<String>,
+    pub using: Option<String>,
+    pub with_check: Option<String>,
+    /// Source statement (empty for policies read from the database)
+    pub sql: String,
+}
+
+impl Policy {
+    /// `table.policy`, as used in verification findings
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.table, self.name)
+    }
+}
+
+/// Result of comparing declared policies against the database
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PolicyVerification {
+    pub expected: Vec<String>,
+    pub missing: Vec<String>,
+    pub mismatches: Vec<PolicyMismatch>,
+    /// Tables with declared policies but ROW LEVEL SECURITY disabled
+    pub rls_disabled: Vec<String>,
+}
+
+impl PolicyVerification {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.mismatches.is_empty() && self.rls_disabled.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyMismatch {
+    pub policy: String,
+    pub issue: String,
+}
+
+pub struct PolicyManager;
+
+impl PolicyManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Find policy files in the policies directory
+    pub fn find_policy_files(&self, policies_dir: &Path) -> Result<Vec<PathBuf>> {
+        if !policies_dir.exists() {
+            debug!(
+                "Policies directory {:?} does not exist, returning empty list",
+                policies_dir
+            );
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+
+        for entry in fs::read_dir(policies_dir).map_err(|e| GatewayError::SchemaExtractionFailed {
+            cause: format!("Failed to read policies directory: {}", e),
+        })? {
+            let entry = entry.map_err(|e| GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to read directory entry: {}", e),
+            })?;
+
+            let path = entry.path();
+            if path.is_file() {
+                if let Some(ext) = path.extension() {
+                    if ext == "pssql" || ext == "pgsql" || ext == "sql" {
+                        files.push(path);
+                    }
+                }
+            }
+        }
+
+        // Sort for consistent ordering
+        files.sort();
+
+        Ok(files)
+    }
+
+    /// Parse every CREATE POLICY statement in a file
+    pub fn parse_policies(&self, file_path: &Path) -> Result<Vec<Policy>> {
+        let content = fs::read_to_string(file_path).map_err(|e| {
+            GatewayError::SchemaExtractionFailed {
+                cause: format!("Failed to read policy file {:?}: {}", file_path, e),
+            }
+        })?;
+
+        let mut policies = Vec::new();
+        for statement in split_statements(&strip_comments(&content)) {
+            if let Some(policy) = parse_create_policy(&statement) {
+                policies.push(policy);
+            } else {
+                return Err(GatewayError::SchemaExtractionFailed {
+                    cause: format!(
+                        "Policy file {:?} contains a statement that is not CREATE POLICY: {}",
+                        file_path, statement
+                    ),
+                });
+            }
+        }
+
+        Ok(policies)
+    }
+
+    /// Parse all policy files in the directory
+    pub fn load_policies(&self, policies_dir: &Path) -> Result<Vec<Policy>> {
+        let mut policies = Vec::new();
+        for file in self.find_policy_files(policies_dir)? {
+            policies.extend(self.parse_policies(&file)?);
+        }
+        Ok(policies)
+    }
+
+    /// Enable ROW LEVEL SECURITY and (re)create every declared policy
+    ///
+    /// Runs in one transaction; returns the number of policies deployed.
+    pub async fn deploy_policies(
+        &self,
+        pool: &Pool,
+        database: &str,
+        policies_dir: &Path,
+    ) -> Result<usize> {
+        let policies = self.load_policies(policies_dir)?;
+        if policies.is_empty() {
+            debug!("No policies to deploy for database {}", database);
+            return Ok(0);
+        }
+
+        let mut client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let transaction = client.transaction().await.map_err(|e| GatewayError::QueryFailed {
+            database: database.to_string(),
+            function: "deploy_policies".to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let tables: BTreeSet<&str> = policies.iter().map(|p| p.table.as_str()).collect();
+        for table in tables {
+            transaction
+                .batch_execute(&format!("ALTER TABLE \"{}\" ENABLE ROW LEVEL SECURITY", table))
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: format!("enable row level security on {}", table),
+                    cause: e.to_string(),
+                })?;
+        }
+
+        for policy in &policies {
+            let sql = format!(
+                "DROP POLICY IF EXISTS \"{}\" ON \"{}\";\n{}",
+                policy.name, policy.table, policy.sql
+            );
+            transaction
+                .batch_execute(&sql)
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: format!("policy {}", policy.qualified_name()),
+                    cause: e.to_string(),
+                })?;
+        }
+
+        transaction.commit().await.map_err(|e| GatewayError::QueryFailed {
+            database: database.to_string(),
+            function: "deploy_policies".to_string(),
+            cause: e.to_string(),
+        })?;
+
+        info!("Deployed {} policies for {}", policies.len(), database);
+        Ok(policies.len())
+    }
+
+    /// List policies on tables in the public schema
+    pub async fn list_policies(&self, pool: &Pool, database: &str) -> Result<Vec<Policy>> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let rows = client
+            .query(
+                r#"
+                SELECT policyname::text, tablename::text, permissive, cmd,
+                       roles::text[], qual, with_check
+                FROM pg_policies
+                WHERE schemaname = 'public'
+                ORDER BY tablename, policyname
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "list_policies".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let mut roles: Vec<String> = row.get::<_, Vec<String>>(4)
+                    .into_iter()
+                    .map(|r| r.to_lowercase())
+                    .collect();
+                roles.sort();
+                Policy {
+                    name: row.get(0),
+                    table: row.get(1),
+                    permissive: row.get::<_, String>(2) == "PERMISSIVE",
+                    command: row.get(3),
+                    roles,
+                    using: row.get(5),
+                    with_check: row.get(6),
+                    sql: String::new(),
+                }
+            })
+            .collect())
+    }
+
+    /// List public tables with ROW LEVEL SECURITY enabled
+    pub async fn list_rls_tables(&self, pool: &Pool, database: &str) -> Result<Vec<String>> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let rows = client
+            .query(
+                r#"
+                SELECT relname::text
+                FROM pg_class
+                WHERE relnamespace = 'public'::regnamespace
+                AND relkind IN ('r', 'p')
+                AND relrowsecurity
+                ORDER BY relname
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "list_rls_tables".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        Ok(rows.iter().map(|r| r.get(0)).collect())
+    }
+
+    /// Compare declared policies against the database
+    pub async fn verify_policies(
+        &self,
+        pool: &Pool,
+        database: &str,
+        policies_dir: &Path,
+    ) -> Result<PolicyVerification> {
+        let declared = self.load_policies(policies_dir)?;
+        if declared.is_empty() {
+            return Ok(PolicyVerification::default());
+        }
+
+        let deployed = self.list_policies(pool, database).await?;
+        let rls_tables = self.list_rls_tables(pool, database).await?;
+
+        Ok(Self::compare(&declared, &deployed, &rls_tables))
+    }
+
+    /// Report declared policies that are missing or differ from the deployed ones
+    pub fn compare(declared: &[Policy], deployed: &[Policy], rls_tables: &[String]) -> PolicyVerification {
+        let mut verification = PolicyVerification::default();
+
+        for policy in declared {
+            verification.expected.push(policy.qualified_name());
+
+            let Some(found) = deployed
+                .iter()
+                .find(|p| p.table == policy.table && p.name == policy.name)
+            else {
+                verification.missing.push(policy.qualified_name());
+                continue;
+            };
+
+            for issue in policy_differences(policy, found) {
+                verification.mismatches.push(PolicyMismatch {
+                    policy: policy.qualified_name(),
+                    issue,
+                });
+            }
+        }
+
+        let tables: BTreeSet<&str> = declared.iter().map(|p| p.table.as_str()).collect();
+        for table in tables {
+            if !rls_tables.iter().any(|t| t == table) {
+                verification.rls_disabled.push(table.to_string());
+            }
+        }
+
+        verification
+    }
+}
+
+impl Default for PolicyManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Describe how a deployed policy differs from its declaration
+fn policy_differences(declared: &Policy, deployed: &Policy) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    if declared.permissive != deployed.permissive {
+        let kind = |p: bool| if p { "PERMISSIVE" } else { "RESTRICTIVE" };
+        issues.push(format!(
+            "{} in database, declared {}",
+            kind(deployed.permissive),
+            kind(declared.permissive)
+        ));
+    }
+    if declared.command != deployed.command {
+        issues.push(format!(
+            "FOR {} in database, declared FOR {}",
+            deployed.command, declared.command
+        ));
+    }
+    if declared.roles != deployed.roles {
+        issues.push(format!(
+            "TO {} in database, declared TO {}",
+            deployed.roles.join(", "),
+            declared.roles.join(", ")
+        ));
+    }
+
+    let same_expr = |a: &Option<String>, b: &Option<String>| {
+        a.as_deref().map(normalize_expr) == b.as_deref().map(normalize_expr)
+    };
+    if !same_expr(&declared.using, &deployed.using) {
+        issues.push(format!(
+            "USING {} in database, declared {}",
+            deployed.using.as_deref().unwrap_or("(none)"),
+            declared.using.as_deref().unwrap_or("(none)")
+        ));
+    }
+    if !same_expr(&declared.with_check, &deployed.with_check) {
+        issues.push(format!(
+            "WITH CHECK {} in database, declared {}",
+            deployed.with_check.as_deref().unwrap_or("(none)"),
+            declared.with_check.as_deref().unwrap_or("(none)")
+        ));
+    }
+
+    issues
+}
+
+/// Normalize a policy expression for comparison
+///
+/// PostgreSQL stores expressions deparsed: parentheses around every
+/// operation, explicit casts (`'x'::text`) and its own whitespace. Casts and
+/// whitespace are dropped and case is folded, outside quoted literals and
+/// identifiers only; the expression is parsed with PostgreSQL's operator
+/// precedence and written back with only the parentheses that change its
+/// meaning, so `(a OR b) AND c` and `a OR (b AND c)` stay apart.
+pub(crate) fn normalize_expr(expr: &str) -> String {
+    let tokens = strip_casts(expr_tokens(expr));
+
+    let mut parser = ExprParser { tokens, pos: 0 };
+    let mut parts = Vec::new();
+    while parser.pos < parser.tokens.len() {
+        parts.push(parser.sequence(None).render(0, false));
+    }
+    parts.join(" ")
+}
+
+/// Operators spelled as (possibly two) keywords
+const KEYWORD_OPERATORS: &[&str] = &[
+    "or", "and", "is", "is not", "in", "not in", "like", "not like", "ilike", "not ilike", "between",
+];
+
+/// Split an expression into literals, words, operators and punctuation
+fn expr_tokens(expr: &str) -> Vec<String> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if c == '\'' || c == '"' {
+            i += 1;
+            while i < chars.len() {
+                if chars[i] == c {
+                    // Doubled quote is an escaped quote
+                    if chars.get(i + 1) == Some(&c) {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+        } else if c.is_alphanumeric() || c == '_' || c == '.' || c == '$' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.' | '$')) {
+                i += 1;
+            }
+        } else if "()[],".contains(c) {
+            i += 1;
+        } else {
+            while i < chars.len() && "+-*/<>=~!@#%^&|`?:".contains(chars[i]) {
+                i += 1;
+            }
+            i = i.max(start + 1);
+        }
+
+        let token: String = chars[start..i].iter().collect();
+        let token = if c == '\'' || c == '"' { token } else { token.to_lowercase() };
+        // Two-word operators become one token
+        match (tokens.last().map(String::as_str), token.as_str()) {
+            (Some("is"), "not") | (Some("not"), "in" | "like" | "ilike") => {
+                let first = tokens.pop().unwrap();
+                tokens.push(format!("{} {}", first, token));
+            }
+            _ => tokens.push(token),
+        }
+    }
+
+    tokens
+}
+
+/// Drop `::type` casts, including `character varying` and array types
+fn strip_casts(tokens: Vec<String>) -> Vec<String> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        if token != "::" {
+            out.push(token);
+            continue;
+        }
+        tokens.next();
+        tokens.next_if(|t| t == "varying");
+        while tokens.peek().map(String::as_str) == Some("[") {
+            tokens.next();
+            tokens.next_if(|t| t == "]");
+        }
+    }
+
+    out
+}
+
+/// Binding strength of a binary operator, following PostgreSQL's table
+fn binary_precedence(token: &str) -> Option<u8> {
+    let is_operator = token.chars().all(|c| "+-*/<>=~!@#%^&|`?:".contains(c));
+    match token {
+        "or" => Some(1),
+        "and" => Some(2),
+        "is" | "is not" => Some(4),
+        "=" | "<>" | "!=" | "<" | ">" | "<=" | ">=" => Some(5),
+        _ if KEYWORD_OPERATORS.contains(&token) => Some(6),
+        "+" | "-" => Some(8),
+        "*" | "/" | "%" => Some(9),
+        "^" => Some(10),
+        _ if is_operator && !token.is_empty() => Some(7),
+        _ => None,
+    }
+}
+
+const NOT_PRECEDENCE: u8 = 3;
+const UNARY_MINUS_PRECEDENCE: u8 = 11;
+
+enum ExprNode {
+    /// Literal, name, function call or list, already rendered
+    Atom(String),
+    Unary(String, Box<ExprNode>),
+    Binary(String, Box<ExprNode>, Box<ExprNode>),
+    /// Items the parser does not understand, kept in order
+    Sequence(Vec<ExprNode>),
+}
+
+impl ExprNode {
+    fn precedence(&self) -> u8 {
+        match self {
+            ExprNode::Atom(_) => u8::MAX,
+            ExprNode::Unary(op, _) if op == "not" => NOT_PRECEDENCE,
+            ExprNode::Unary(_, _) => UNARY_MINUS_PRECEDENCE,
+            ExprNode::Binary(op, _, _) => binary_precedence(op).unwrap_or(0),
+            ExprNode::Sequence(_) => 0,
+        }
+    }
+
+    /// Write the node, parenthesized only where precedence requires it
+    fn render(&self, parent: u8, right_operand: bool) -> String {
+        let own = self.precedence();
+        let text = match self {
+            ExprNode::Atom(text) => return text.clone(),
+            ExprNode::Unary(op, operand) if op == "not" => format!("not {}", operand.render(own, true)),
+            ExprNode::Unary(op, operand) => format!("{}{}", op, operand.render(own, true)),
+            ExprNode::Binary(op, lhs, rhs) => {
+                let op_text = if op.starts_with(|c: char| c.is_alphabetic()) {
+                    format!(" {} ", op)
+                } else {
+                    op.clone()
+                };
+                format!("{}{}{}", lhs.render(own, false), op_text, rhs.render(own, true))
+            }
+            ExprNode::Sequence(items) => items.iter().map(|i| i.render(0, false)).collect::<Vec<_>>().join(" "),
+        };
+
+        // AND and OR are associative; other operators keep right-nested groups
+        let associative = matches!(self, ExprNode::Binary(op, _, _) if op == "and" || op == "or");
+        if own < parent || (own == parent && right_operand && !associative) {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+}
+
+fn render_list(items: &[ExprNode]) -> String {
+    items.iter().map(|item| item.render(0, false)).collect::<Vec<_>>().join(",")
+}
+
+struct ExprParser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Expressions up to a comma, the closing bracket or the end
+    fn sequence(&mut self, closing: Option<&str>) -> ExprNode {
+        let mut items = vec![self.expression(0)];
+        while let Some(token) = self.peek() {
+            if token == "," || Some(token) == closing {
+                break;
+            }
+            items.push(self.expression(0));
+        }
+        if items.len() == 1 {
+            items.pop().unwrap()
+        } else {
+            ExprNode::Sequence(items)
+        }
+    }
+
+    /// Comma-separated items after an opening bracket
+    fn list(&mut self, closing: &str) -> Vec<ExprNode> {
+        let mut items = Vec::new();
+        if self.peek() == Some(closing) {
+            self.pos += 1;
+            return items;
+        }
+        while self.peek().is_some() {
+            items.push(self.sequence(Some(closing)));
+            match self.next().as_deref() {
+                Some(",") => continue,
+                _ => break,
+            }
+        }
+        items
+    }
+
+    fn expression(&mut self, min_precedence: u8) -> ExprNode {
+        let mut lhs = self.prefix();
+        while let Some(precedence) = self.peek().and_then(binary_precedence) {
+            if precedence < min_precedence {
+                break;
+            }
+            let op = self.next().unwrap();
+            let rhs = self.expression(precedence + 1);
+            lhs = ExprNode::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        lhs
+    }
+
+    fn prefix(&mut self) -> ExprNode {
+        let Some(token) = self.next() else {
+            return ExprNode::Atom(String::new());
+        };
+        match token.as_str() {
+            "not" => ExprNode::Unary(token, Box::new(self.expression(NOT_PRECEDENCE))),
+            "-" | "+" => ExprNode::Unary(token, Box::new(self.expression(UNARY_MINUS_PRECEDENCE))),
+            "(" => {
+                let mut items = self.list(")");
+                if items.len() == 1 {
+                    // Redundant parentheses; precedence puts back the needed ones
+                    items.pop().unwrap()
+                } else {
+                    ExprNode::Atom(format!("({})", render_list(&items)))
+                }
+            }
+            _ if token.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+                && !KEYWORD_OPERATORS.contains(&token.as_str()) =>
+            {
+                match self.peek() {
+                    Some("(") => {
+                        self.pos += 1;
+                        let args = self.list(")");
+                        ExprNode::Atom(format!("{}({})", token, render_list(&args)))
+                    }
+                    Some("[") => {
+                        self.pos += 1;
+                        let items = self.list("]");
+                        ExprNode::Atom(format!("{}[{}]", token, render_list(&items)))
+                    }
+                    _ => ExprNode::Atom(token),
+                }
+            }
+            _ => ExprNode::Atom(token),
+        }
+    }
+}
+
+/// Parse one CREATE POLICY statement
+fn parse_create_policy(statement: &str) -> Option<Policy> {
+    let header = regex::Regex::new(
+        r#"(?is)^\s*CREATE\s+POLICY\s+"?(\w+)"?\s+ON\s+(?:"?public"?\.)?"?(\w+)"?(.*)$"#,
+    )
+    .unwrap();
+    let caps = header.captures(statement)?;
+    let name = caps[1].to_string();
+    let table = caps[2].to_string();
+    let rest = caps[3].to_string();
+
+    let using = clause_expression(&rest, "USING");
+    let with_check = clause_expression(&rest, "WITH CHECK");
+
+    // AS / FOR / TO all come before the expressions
+    let options_end = [find_clause(&rest, "USING"), find_clause(&rest, "WITH CHECK")]
+        .into_iter()
+        .flatten()
+        .map(|(start, _)| start)
+        .min()
+        .unwrap_or(rest.len());
+    let options = &rest[..options_end];
+
+    let permissive = !regex::Regex::new(r"(?i)\bAS\s+RESTRICTIVE\b")
+        .unwrap()
+        .is_match(options);
+
+    let command = regex::Regex::new(r"(?i)\bFOR\s+(ALL|SELECT|INSERT|UPDATE|DELETE)\b")
+        .unwrap()
+        .captures(options)
+        .map(|c| c[1].to_uppercase())
+        .unwrap_or_else(|| "ALL".to_string());
+
+    let mut roles: Vec<String> = regex::Regex::new(r"(?is)\bTO\s+(.+)$")
+        .unwrap()
+        .captures(options)
+        .map(|c| {
+            c[1].split(',')
+                .map(|r| r.trim().trim_matches('"').to_lowercase())
+                .filter(|r| !r.is_empty())
+                .collect()
+        })
+        .unwrap_or_else(|| vec!["public".to_string()]);
+    roles.sort();
+
+    Some(Policy {
+        name,
+        table,
+        permissive,
+        command,
+        roles,
+        using,
+        with_check,
+        sql: format!("{};", statement.trim()),
+    })
+}
+
+/// Find a clause keyword at parenthesis depth 0, followed by `(`
+///
+/// Returns the keyword's start and the position of the opening parenthesis.
+fn find_clause(sql: &str, keyword: &str) -> Option<(usize, usize)> {
+    let pattern = regex::Regex::new(&format!(
+        r"(?i)\b{}\s*\(",
+        keyword.replace(' ', r"\s+")
+    ))
+    .unwrap();
+
+    for m in pattern.find_iter(sql) {
+        let prefix = &sql[..m.start()];
+        let depth = prefix.matches('(').count() as i64 - prefix.matches(')').count() as i64;
+        let quotes = prefix.matches('\'').count();
+        if depth == 0 && quotes.is_multiple_of(2) {
+            return Some((m.start(), m.end() - 1));
+        }
+    }
+    None
+}
+
+/// Extract the parenthesized expression after a clause keyword
+fn clause_expression(sql: &str, keyword: &str) -> Option<String> {
+    let (_, open) = find_clause(sql, keyword)?;
+    let mut depth = 0;
+    let mut in_quote = false;
+
+    for (i, c) in sql[open..].char_indices() {
+        match c {
+            '\'' => in_quote = !in_quote,
+            '(' if !in_quote => depth += 1,
+            ')' if !in_quote => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(sql[open + 1..open + i].trim().to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::VerificationResult;
+    use crate::schema::test_db::test_pool;
+    use tempfile::TempDir;
+
+    const POLICY_FILE: &str = r#"
+-- Tenants only see their own documents
+CREATE POLICY tenant_isolation ON documents
+    FOR SELECT
+    TO app_user, "Reporting"
+    USING (tenant_id = current_setting('app.tenant_id')::int);
+
+CREATE POLICY tenant_write ON public.documents AS RESTRICTIVE FOR INSERT
+    WITH CHECK ((tenant_id = current_setting('app.tenant_id')::int));
+"#;
+
+    #[test]
+    fn test_parse_policy_file() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("documents.pssql");
+        fs::write(&file, POLICY_FILE).unwrap();
+
+        let policies = PolicyManager::new().parse_policies(&file).unwrap();
+        assert_eq!(policies.len(), 2);
+
+        let read = &policies[0];
+        assert_eq!(read.qualified_name(), "documents.tenant_isolation");
+        assert!(read.permissive);
+        assert_eq!(read.command, "SELECT");
+        assert_eq!(read.roles, vec!["app_user", "reporting"]);
+        assert_eq!(
+            read.using.as_deref(),
+            Some("tenant_id = current_setting('app.tenant_id')::int")
+        );
+        assert!(read.with_check.is_none());
+
+        let write = &policies[1];
+        assert_eq!(write.table, "documents");
+        assert!(!write.permissive);
+        assert_eq!(write.command, "INSERT");
+        assert_eq!(write.roles, vec!["public"]);
+        assert!(write.using.is_none());
+        assert!(write.with_check.is_some());
+    }
+
+    #[test]
+    fn test_missing_policy_is_a_finding() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("documents.pssql"), POLICY_FILE).unwrap();
+        let declared = PolicyManager::new().load_policies(dir.path()).unwrap();
+
+        // Only the write policy exists, as PostgreSQL reports it
+        let deployed = vec![Policy {
+            name: "tenant_write".to_string(),
+            table: "documents".to_string(),
+            permissive: false,
+            command: "INSERT".to_string(),
+            roles: vec!["public".to_string()],
+            using: None,
+            with_check: Some(
+                "(tenant_id = (current_setting('app.tenant_id'::text))::integer)".to_string(),
+            ),
+            sql: String::new(),
+        }];
+
+        let policies = PolicyManager::compare(&declared, &deployed, &["documents".to_string()]);
+        assert_eq!(policies.missing, vec!["documents.tenant_isolation"]);
+        assert!(policies.mismatches.is_empty(), "{:?}", policies.mismatches);
+        assert!(policies.rls_disabled.is_empty());
+
+        let mut result = VerificationResult::new();
+        result.policies = policies;
+        assert_eq!(result.findings(), vec!["Missing policy: documents.tenant_isolation"]);
+    }
+
+    #[test]
+    fn test_changed_policy_and_disabled_rls() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("documents.pssql");
+        fs::write(&file, POLICY_FILE).unwrap();
+        let declared = PolicyManager::new().parse_policies(&file).unwrap();
+
+        let mut deployed = declared.clone();
+        deployed[0].command = "ALL".to_string();
+
+        let policies = PolicyManager::compare(&declared, &deployed, &[]);
+        assert!(policies.missing.is_empty());
+        assert_eq!(policies.mismatches.len(), 1);
+        assert_eq!(policies.mismatches[0].policy, "documents.tenant_isolation");
+        assert!(policies.mismatches[0].issue.contains("FOR ALL in database"));
+        assert_eq!(policies.rls_disabled, vec!["documents"]);
+    }
+
+    #[test]
+    fn test_normalize_expr_keeps_meaningful_parentheses() {
+        // Deparsed forms match what was declared
+        assert_eq!(
+            normalize_expr("tenant_id = current_setting('app.tenant_id')::int AND NOT archived"),
+            normalize_expr("((tenant_id = (current_setting('app.tenant_id'::text))::integer) AND (NOT archived))")
+        );
+        assert_eq!(
+            normalize_expr("(owner_id = auth.uid()) OR (shared AND visible)"),
+            normalize_expr("((owner_id = auth.uid()) OR (shared AND visible))")
+        );
+
+        // Grouping that changes the meaning is kept
+        assert_ne!(normalize_expr("(a OR b) AND c"), normalize_expr("a OR (b AND c)"));
+        assert_eq!(normalize_expr("((a OR b)) AND (c)"), "(a or b) and c");
+        assert_ne!(normalize_expr("(a - b) - c"), normalize_expr("a - (b - c)"));
+        assert_eq!(normalize_expr("(a * (b + 1)) > 0"), "a*(b+1)>0");
+    }
+
+    #[test]
+    fn test_normalize_expr_keeps_literals() {
+        assert_eq!(
+            normalize_expr("Role = 'Admin'"),
+            normalize_expr("(role = 'Admin'::text)")
+        );
+        assert_ne!(normalize_expr("role = 'Admin'"), normalize_expr("role = 'admin'"));
+        assert_ne!(normalize_expr("note = 'a::text'"), normalize_expr("note = 'a'"));
+        assert_ne!(normalize_expr("\"Owner\" = 1"), normalize_expr("\"owner\" = 1"));
+        assert_eq!(
+            normalize_expr("tags = ARRAY['a']::character varying[]"),
+            "tags=array['a']"
+        );
+    }
+
+    #[test]
+    fn test_rejects_other_statements() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("bad.pssql");
+        fs::write(&file, "ALTER TABLE documents DISABLE ROW LEVEL SECURITY;").unwrap();
+
+        assert!(PolicyManager::new().parse_policies(&file).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deploy_and_verify_policies() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS rls_documents;
+                 CREATE TABLE rls_documents (id SERIAL PRIMARY KEY, tenant_id INT NOT NULL);",
+            )
+            .await
+            .unwrap();
+
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("rls_documents.pssql"),
+            "CREATE POLICY tenant_isolation ON rls_documents
+                 USING (tenant_id = current_setting('app.tenant_id')::int);",
+        )
+        .unwrap();
+
+        let manager = PolicyManager::new();
+        let deployed = manager
+            .deploy_policies(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+        assert_eq!(deployed, 1);
+
+        let verification = manager
+            .verify_policies(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+        assert!(verification.is_ok(), "{:?}", verification);
+
+        client
+            .batch_execute("DROP POLICY tenant_isolation ON rls_documents")
+            .await
+            .unwrap();
+        let verification = manager
+            .verify_policies(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+        assert_eq!(verification.missing, vec!["rls_documents.tenant_isolation"]);
+
+        client.batch_execute("DROP TABLE rls_documents").await.unwrap();
+    }
+}
@@ -0,0 +1,192 @@
+//! Bulk rollout checkpoints
+//!
+//! Records which databases finished a bulk migration for a given target version
+//! (the checksum of the schema archive), so an interrupted rollout can be resumed
+//! without re-processing databases that already completed.
+//!
+//! Checkpoints live in `_stonescriptdb_gateway_rollouts` in the gateway's own
+//! database (the one DATABASE_URL points at), not in the tenant databases.
+
+use crate::error::{GatewayError, Result};
+use deadpool_postgres::Pool;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use tracing::debug;
+
+pub struct RolloutCheckpoint;
+
+impl RolloutCheckpoint {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Target version of a rollout: checksum of the uploaded schema archive
+    pub fn target_version(schema_data: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(schema_data);
+        hex::encode(hasher.finalize())
+    }
+
+    /// Ensure the checkpoint table exists
+    pub async fn ensure_table(&self, pool: &Pool) -> Result<()> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: "gateway".to_string(),
+            cause: e.to_string(),
+        })?;
+
+        client
+            .execute(
+                r#"
+                CREATE TABLE IF NOT EXISTS _stonescriptdb_gateway_rollouts (
+                    platform TEXT NOT NULL,
+                    target_version TEXT NOT NULL,
+                    database_name TEXT NOT NULL,
+                    completed_at TIMESTAMPTZ DEFAULT NOW(),
+                    PRIMARY KEY (platform, target_version, database_name)
+                )
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::MigrationFailed {
+                database: "gateway".to_string(),
+                migration: "_stonescriptdb_gateway_rollouts".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        Ok(())
+    }
+
+    /// Databases already marked complete for this platform and target version
+    pub async fn completed_databases(
+        &self,
+        pool: &Pool,
+        platform: &str,
+        target_version: &str,
+    ) -> Result<HashSet<String>> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: "gateway".to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let rows = client
+            .query(
+                "SELECT database_name FROM _stonescriptdb_gateway_rollouts WHERE platform = $1 AND target_version = $2",
+                &[&platform, &target_version],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: "gateway".to_string(),
+                function: "rollout checkpoints".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        Ok(rows.iter().map(|r| r.get(0)).collect())
+    }
+
+    /// Mark a database as complete for this platform and target version
+    pub async fn mark_complete(
+        &self,
+        pool: &Pool,
+        platform: &str,
+        target_version: &str,
+        database: &str,
+    ) -> Result<()> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: "gateway".to_string(),
+            cause: e.to_string(),
+        })?;
+
+        client
+            .execute(
+                r#"
+                INSERT INTO _stonescriptdb_gateway_rollouts (platform, target_version, database_name)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (platform, target_version, database_name) DO UPDATE SET completed_at = NOW()
+                "#,
+                &[&platform, &target_version, &database],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: "gateway".to_string(),
+                function: "rollout checkpoints".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        debug!(
+            "Rollout checkpoint: {} complete for {} at {}",
+            database, platform, target_version
+        );
+
+        Ok(())
+    }
+
+    /// Filter out databases that already completed, keeping the original order
+    pub fn remaining(databases: &[String], completed: &HashSet<String>) -> Vec<String> {
+        databases
+            .iter()
+            .filter(|db| !completed.contains(*db))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for RolloutCheckpoint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_db::test_pool;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_remaining_skips_completed() {
+        let all = names(&["myapp_t1", "myapp_t2", "myapp_t3", "myapp_t4"]);
+        let completed: HashSet<String> = names(&["myapp_t3", "myapp_t1"]).into_iter().collect();
+
+        assert_eq!(
+            RolloutCheckpoint::remaining(&all, &completed),
+            names(&["myapp_t2", "myapp_t4"])
+        );
+        assert_eq!(RolloutCheckpoint::remaining(&all, &HashSet::new()), all);
+    }
+
+    #[test]
+    fn test_target_version_is_archive_checksum() {
+        let v1 = RolloutCheckpoint::target_version(b"archive-1");
+        assert_eq!(v1.len(), 64);
+        assert_eq!(v1, RolloutCheckpoint::target_version(b"archive-1"));
+        assert_ne!(v1, RolloutCheckpoint::target_version(b"archive-2"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_processes_only_remainder() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+
+        let checkpoint = RolloutCheckpoint::new();
+        let version = RolloutCheckpoint::target_version(b"rollout-resume-test");
+        checkpoint.ensure_table(&pool).await.unwrap();
+
+        // First run was interrupted after two databases
+        checkpoint.mark_complete(&pool, "resumetest", &version, "resumetest_t1").await.unwrap();
+        checkpoint.mark_complete(&pool, "resumetest", &version, "resumetest_t2").await.unwrap();
+
+        let all = names(&["resumetest_t1", "resumetest_t2", "resumetest_t3"]);
+        let completed = checkpoint.completed_databases(&pool, "resumetest", &version).await.unwrap();
+        assert_eq!(RolloutCheckpoint::remaining(&all, &completed), names(&["resumetest_t3"]));
+
+        // A different target version starts from scratch
+        let other = RolloutCheckpoint::target_version(b"rollout-resume-test-v2");
+        let completed = checkpoint.completed_databases(&pool, "resumetest", &other).await.unwrap();
+        assert_eq!(RolloutCheckpoint::remaining(&all, &completed), all);
+    }
+}
@@ -1,12 +1,15 @@
 //! Seeder runner with validation
 //!
 //! Rules:
-//! - register: Run seeders only if table is empty
+//! - register: Run seeders only if table is empty; statements with an
+//!   `ON CONFLICT ... DO ...` clause are upserts and also run on a non-empty table
+//! - reseed (development only): Truncate the seeded tables and insert every record again
 //! - migrate: Never run seeders, only validate
 //! - Validation: After migration, check all seeder records exist
 //! - If validation fails: Rollback the entire transaction
 
 use crate::error::{GatewayError, Result};
+use crate::schema::changelog::ChangelogManager;
 use deadpool_postgres::Pool;
 use std::fs;
 use std::path::Path;
@@ -26,6 +29,199 @@ pub struct SeederFile {
 pub struct SeederRecord {
     pub columns: Vec<String>,
     pub values: Vec<String>,
+    /// `ON CONFLICT ... DO ...` clause of the statement the record came from
+    pub on_conflict: Option<String>,
+}
+
+/// A single value token from a seeder VALUES tuple
+///
+/// Literals are bound as text parameters and cast to the column's type
+/// (`$1::text::int4`), so PostgreSQL's own input functions parse them. This
+/// works for every column type (numeric, enums, uuid, dates) without a Rust
+/// encoding per type, and quotes in strings never reach the SQL text.
+#[derive(Debug, Clone, PartialEq)]
+enum SeederValue {
+    /// Quoted string with `''` escapes resolved
+    Text(String),
+    Number(String),
+    Boolean(bool),
+    Null,
+    /// `DEFAULT` keyword, passed through as-is
+    Default,
+    /// Anything else (e.g. `NOW()`, `'{}'::jsonb`), passed through as SQL
+    Expression(String),
+}
+
+impl SeederValue {
+    fn parse(token: &str) -> Self {
+        let token = token.trim();
+
+        if token.eq_ignore_ascii_case("NULL") {
+            SeederValue::Null
+        } else if token.eq_ignore_ascii_case("DEFAULT") {
+            SeederValue::Default
+        } else if token.eq_ignore_ascii_case("TRUE") {
+            SeederValue::Boolean(true)
+        } else if token.eq_ignore_ascii_case("FALSE") {
+            SeederValue::Boolean(false)
+        } else if is_numeric_literal(token) {
+            SeederValue::Number(token.to_string())
+        } else if let Some(text) = unquote_string(token) {
+            SeederValue::Text(text)
+        } else {
+            SeederValue::Expression(token.to_string())
+        }
+    }
+
+    /// Text to bind for a parameter, or None when the value is inlined in the SQL
+    fn param(&self) -> Option<Option<String>> {
+        match self {
+            SeederValue::Text(text) => Some(Some(text.clone())),
+            SeederValue::Number(number) => Some(Some(number.clone())),
+            SeederValue::Boolean(value) => Some(Some(value.to_string())),
+            SeederValue::Null => Some(None),
+            SeederValue::Default | SeederValue::Expression(_) => None,
+        }
+    }
+}
+
+/// Check for a plain numeric literal (`42`, `-12.50`, `1e6`)
+///
+/// Called for every seeder value, so this avoids compiling a regex per token.
+fn is_numeric_literal(token: &str) -> bool {
+    token.chars().any(|c| c.is_ascii_digit())
+        && token.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+        && token.parse::<f64>().is_ok()
+}
+
+/// Unquote a single-quoted SQL string literal, or None if `token` is not exactly one
+fn unquote_string(token: &str) -> Option<String> {
+    let inner = token.strip_prefix('\'')?.strip_suffix('\'')?;
+    // Any quote left inside must be part of a doubled '' escape
+    if inner.replace("''", "").contains('\'') {
+        return None;
+    }
+    Some(inner.replace("''", "'"))
+}
+
+/// SQL for a column type, as returned by a prepared statement
+fn type_sql(ty: &tokio_postgres::types::Type) -> String {
+    if ty.schema() == "pg_catalog" {
+        ty.name().to_string()
+    } else {
+        format!("\"{}\".\"{}\"", ty.schema(), ty.name())
+    }
+}
+
+/// Placeholder or inline SQL for each value, plus the text parameters to bind
+fn bind_values(
+    values: &[SeederValue],
+    column_types: &[String],
+    first_param: usize,
+) -> (Vec<String>, Vec<Option<String>>) {
+    let mut sql = Vec::new();
+    let mut params = Vec::new();
+
+    for (value, column_type) in values.iter().zip(column_types) {
+        match (value.param(), value) {
+            (Some(param), _) => {
+                params.push(param);
+                sql.push(format!("${}::text::{}", first_param + params.len() - 1, column_type));
+            }
+            (None, SeederValue::Expression(expr)) => sql.push(expr.clone()),
+            (None, _) => sql.push("DEFAULT".to_string()),
+        }
+    }
+
+    (sql, params)
+}
+
+/// Rows per multi-row seeder INSERT
+const SEEDER_BATCH_ROWS: usize = 1000;
+
+/// PostgreSQL's limit on bind parameters in one statement
+const MAX_BIND_PARAMS: usize = 65535;
+
+/// Rows per INSERT, shrunk for wide tables so a batch stays under the parameter limit
+fn batch_rows(columns: usize) -> usize {
+    SEEDER_BATCH_ROWS.min(MAX_BIND_PARAMS / columns.max(1)).max(1)
+}
+
+/// Build a parameterized multi-row INSERT for seeder records sharing one column list
+fn build_insert(
+    table: &str,
+    records: &[SeederRecord],
+    column_types: &[String],
+) -> (String, Vec<Option<String>>) {
+    let mut rows = Vec::with_capacity(records.len());
+    let mut params = Vec::new();
+
+    for record in records {
+        let values: Vec<SeederValue> = record.values.iter().map(|v| SeederValue::parse(v)).collect();
+        let (placeholders, mut row_params) = bind_values(&values, column_types, params.len() + 1);
+        rows.push(format!("({})", placeholders.join(", ")));
+        params.append(&mut row_params);
+    }
+
+    let columns = records.first().map(|r| r.columns.join(", ")).unwrap_or_default();
+    let mut sql = format!("INSERT INTO {} ({}) VALUES {}", table, columns, rows.join(", "));
+    if let Some(on_conflict) = records.first().and_then(|r| r.on_conflict.as_deref()) {
+        sql.push(' ');
+        sql.push_str(on_conflict);
+    }
+
+    (sql, params)
+}
+
+/// Build a parameterized primary key lookup for one seeder record
+///
+/// Returns None when the record has no primary key columns.
+fn build_pk_lookup(
+    table: &str,
+    primary_key_columns: &[String],
+    record: &SeederRecord,
+    column_types: &[String],
+) -> Option<(String, Vec<Option<String>>)> {
+    let mut conditions = Vec::new();
+    let mut params = Vec::new();
+
+    for pk_col in primary_key_columns {
+        let idx = match record.columns.iter().position(|c| c == pk_col) {
+            Some(idx) => idx,
+            None => continue,
+        };
+        let value = SeederValue::parse(&record.values[idx]);
+
+        if value == SeederValue::Null {
+            conditions.push(format!("{} IS NULL", pk_col));
+            continue;
+        }
+
+        let (sql, mut value_params) = bind_values(
+            std::slice::from_ref(&value),
+            &column_types[idx..=idx],
+            params.len() + 1,
+        );
+        conditions.push(format!("{} = {}", pk_col, sql[0]));
+        params.append(&mut value_params);
+    }
+
+    if conditions.is_empty() {
+        return None;
+    }
+
+    Some((
+        format!("SELECT 1 FROM {} WHERE {} LIMIT 1", table, conditions.join(" AND ")),
+        params,
+    ))
+}
+
+/// Borrow text parameters as `ToSql` trait objects
+fn as_sql_params(params: &[Option<String>]) -> Vec<&(dyn tokio_postgres::types::ToSql + Sync)> {
+    params
+        .iter()
+        .map(|p| p as &(dyn tokio_postgres::types::ToSql + Sync))
+        .collect()
 }
 
 /// Result of seeder execution
@@ -35,6 +231,8 @@ pub struct SeederResult {
     pub inserted: usize,
     pub skipped: usize,
     pub total_expected: usize,
+    /// Rows the table already had; records are skipped when it is not zero
+    pub existing_rows: i64,
 }
 
 /// Result of seeder validation
@@ -79,22 +277,23 @@ impl SeederRunner {
                             }
                         })?;
 
-                        if let Some(seeder) = self.parse_seeder(&path, &content)? {
-                            seeders.push(seeder);
-                        }
+                        seeders.extend(self.parse_seeder(&path, &content)?);
                     }
                 }
             }
         }
 
-        // Sort by filename for deterministic order
+        // Sort by filename for deterministic order (stable: tables keep file order)
         seeders.sort_by(|a, b| a.name.cmp(&b.name));
 
         Ok(seeders)
     }
 
-    /// Parse a seeder file to extract table name, columns, and values
-    fn parse_seeder(&self, path: &Path, content: &str) -> Result<Option<SeederFile>> {
+    /// Parse a seeder file into one SeederFile per seeded table
+    ///
+    /// A file may hold several INSERT statements; statements for the same table
+    /// are merged, and tables keep the order of their first statement.
+    fn parse_seeder(&self, path: &Path, content: &str) -> Result<Vec<SeederFile>> {
         let name = path
             .file_name()
             .and_then(|n| n.to_str())
@@ -104,45 +303,57 @@ impl SeederRunner {
         // Remove comments
         let content = self.remove_comments(content);
 
-        // Find INSERT INTO statement
-        // Capture everything after VALUES but stop at ON CONFLICT, ON DUPLICATE KEY, or semicolon
+        // Find INSERT INTO statements
+        // Capture everything after VALUES up to the semicolon; an ON CONFLICT clause
+        // is kept separately, MySQL's ON DUPLICATE KEY is dropped
         let insert_re = regex::Regex::new(
-            r"(?is)INSERT\s+INTO\s+(\w+)\s*\(\s*([^)]+)\s*\)\s*VALUES\s+(.*?)(?:ON\s+(?:CONFLICT|DUPLICATE\s+KEY)|;|$)"
+            r"(?is)INSERT\s+INTO\s+(\w+)\s*\(\s*([^)]+)\s*\)\s*VALUES\s+(.*?)(?:(ON\s+CONFLICT\b.*?)|ON\s+DUPLICATE\s+KEY\b.*?)?\s*(?:;|$)"
         ).unwrap();
 
-        let caps = match insert_re.captures(&content) {
-            Some(c) => c,
-            None => {
-                debug!("No INSERT statement found in seeder: {}", name);
-                return Ok(None);
-            }
-        };
+        let mut seeders: Vec<SeederFile> = Vec::new();
 
-        let table_name = caps[1].to_lowercase();
-        let columns: Vec<String> = caps[2]
-            .split(',')
-            .map(|s| s.trim().to_lowercase())
-            .collect();
+        for caps in insert_re.captures_iter(&content) {
+            let table_name = caps[1].to_lowercase();
+            let columns: Vec<String> = caps[2]
+                .split(',')
+                .map(|s| s.trim().to_lowercase())
+                .collect();
 
-        let values_str = &caps[3];
+            let values_str = &caps[3];
+            let on_conflict = caps.get(4).map(|m| m.as_str().trim().to_string());
 
-        // Parse individual value tuples
-        let records = self.parse_values(values_str, &columns, &name, &table_name)?;
+            // Parse individual value tuples
+            let mut records = self.parse_values(values_str, &columns, &name, &table_name)?;
+            for record in &mut records {
+                record.on_conflict = on_conflict.clone();
+            }
 
-        // First column is assumed to be primary key (common convention)
-        // TODO: Could be enhanced to detect actual PK from table definition
-        let primary_key_columns = if !columns.is_empty() {
-            vec![columns[0].clone()]
-        } else {
-            Vec::new()
-        };
+            if let Some(existing) = seeders.iter_mut().find(|s| s.table_name == table_name) {
+                existing.records.append(&mut records);
+                continue;
+            }
+
+            // First column is assumed to be primary key (common convention);
+            // validation replaces this with the table's real primary key when it can
+            let primary_key_columns = if !columns.is_empty() {
+                vec![columns[0].clone()]
+            } else {
+                Vec::new()
+            };
+
+            seeders.push(SeederFile {
+                name: name.clone(),
+                table_name,
+                records,
+                primary_key_columns,
+            });
+        }
 
-        Ok(Some(SeederFile {
-            name,
-            table_name,
-            records,
-            primary_key_columns,
-        }))
+        if seeders.is_empty() {
+            debug!("No INSERT statement found in seeder: {}", name);
+        }
+
+        Ok(seeders)
     }
 
     /// Remove SQL comments
@@ -158,17 +369,15 @@ impl SeederRunner {
     fn parse_values(&self, values_str: &str, columns: &[String], file_name: &str, table_name: &str) -> Result<Vec<SeederRecord>> {
         let mut records = Vec::new();
 
-        // Match individual value tuples: (val1, val2, ...)
-        let tuple_re = regex::Regex::new(r"\(([^)]+)\)").unwrap();
-
-        for cap in tuple_re.captures_iter(values_str) {
-            let values_inner = &cap[1];
+        for values_inner in split_tuples(values_str) {
+            let values_inner = values_inner.as_str();
             let values = self.parse_value_tuple(values_inner);
 
             if values.len() == columns.len() {
                 records.push(SeederRecord {
                     columns: columns.to_vec(),
                     values,
+                    on_conflict: None,
                 });
             } else {
                 warn!(
@@ -187,12 +396,13 @@ impl SeederRunner {
         Ok(records)
     }
 
-    /// Parse a single value tuple, handling quoted strings
+    /// Parse a single value tuple, handling quoted strings and nested parentheses
     fn parse_value_tuple(&self, tuple_str: &str) -> Vec<String> {
         let mut values = Vec::new();
         let mut current = String::new();
         let mut in_string = false;
         let mut string_char = ' ';
+        let mut depth = 0;
 
         for ch in tuple_str.chars() {
             match ch {
@@ -205,7 +415,15 @@ impl SeederRunner {
                     in_string = false;
                     current.push(ch);
                 }
-                ',' if !in_string => {
+                '(' if !in_string => {
+                    depth += 1;
+                    current.push(ch);
+                }
+                ')' if !in_string => {
+                    depth -= 1;
+                    current.push(ch);
+                }
+                ',' if !in_string && depth == 0 => {
                     values.push(current.trim().to_string());
                     current = String::new();
                 }
@@ -247,14 +465,135 @@ impl SeederRunner {
         Ok(results)
     }
 
+    /// Empty every seeded table and insert all seeder records again (development)
+    ///
+    /// All tables are truncated in one statement and refilled in one
+    /// transaction, so a failed insert leaves the old data in place. A table
+    /// referenced by a foreign key from a table outside the seeders is refused
+    /// unless `cascade` is set, in which case the referencing tables are
+    /// emptied as well.
+    pub async fn run_seeders_force(
+        &self,
+        pool: &Pool,
+        database: &str,
+        seeders_dir: &Path,
+        cascade: bool,
+    ) -> Result<Vec<SeederResult>> {
+        let seeders = self.find_seeder_files(seeders_dir)?;
+
+        if seeders.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        let tables: Vec<String> = seeders.iter().map(|s| s.table_name.clone()).collect();
+        let referencing = self.inbound_foreign_keys(&client, database, &tables).await?;
+        if !referencing.is_empty() && !cascade {
+            return Err(GatewayError::InvalidRequest {
+                message: format!(
+                    "Reseeding would empty tables referenced by foreign keys: {}. Pass cascade to also truncate the referencing tables.",
+                    referencing.join(", ")
+                ),
+            });
+        }
+
+        let mut batches = Vec::with_capacity(seeders.len());
+        for seeder in &seeders {
+            batches.push(self.seeder_batches(&client, database, seeder).await?);
+        }
+
+        let truncate_sql = format!(
+            "TRUNCATE {}{}",
+            tables.join(", "),
+            if cascade { " CASCADE" } else { "" }
+        );
+        let mut transaction = client
+            .transaction()
+            .await
+            .map_err(|e| insert_error(database, &tables.join(", "), e, "BEGIN", None))?;
+        transaction
+            .batch_execute(&truncate_sql)
+            .await
+            .map_err(|e| insert_error(database, &tables.join(", "), e, &truncate_sql, None))?;
+
+        let mut results = Vec::with_capacity(seeders.len());
+        for (seeder, batches) in seeders.iter().zip(batches) {
+            let inserted = self.insert_batches(&mut transaction, database, seeder, batches).await?;
+            results.push(SeederResult {
+                table: seeder.table_name.clone(),
+                inserted,
+                skipped: 0,
+                total_expected: seeder.records.len(),
+                existing_rows: 0,
+            });
+        }
+
+        transaction
+            .commit()
+            .await
+            .map_err(|e| insert_error(database, &tables.join(", "), e, "COMMIT", None))?;
+
+        info!(
+            "Reseeded {} tables in {}{}",
+            tables.len(),
+            database,
+            if referencing.is_empty() {
+                String::new()
+            } else {
+                format!(" (cascaded to {})", referencing.join(", "))
+            }
+        );
+
+        Ok(results)
+    }
+
+    /// Foreign keys into `tables` from tables outside the set, as `referencing -> referenced`
+    async fn inbound_foreign_keys(
+        &self,
+        client: &deadpool_postgres::Client,
+        database: &str,
+        tables: &[String],
+    ) -> Result<Vec<String>> {
+        let rows = client
+            .query(
+                r#"
+                SELECT DISTINCT c.conrelid::regclass::text, c.confrelid::regclass::text
+                FROM pg_constraint c
+                WHERE c.contype = 'f'
+                  AND c.confrelid IN (SELECT to_regclass(t) FROM unnest($1::text[]) AS t WHERE to_regclass(t) IS NOT NULL)
+                  AND c.conrelid NOT IN (SELECT to_regclass(t) FROM unnest($1::text[]) AS t WHERE to_regclass(t) IS NOT NULL)
+                ORDER BY 1, 2
+                "#,
+                &[&tables],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "seeder foreign keys".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|r| format!("{} -> {}", r.get::<_, String>(0), r.get::<_, String>(1)))
+            .collect())
+    }
+
     /// Run a single seeder only if the table is empty
+    ///
+    /// On a non-empty table only the records of upsert statements (with an
+    /// ON CONFLICT clause) are run; the others are skipped.
     async fn run_seeder_if_empty(
         &self,
         pool: &Pool,
         database: &str,
         seeder: &SeederFile,
     ) -> Result<SeederResult> {
-        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+        let mut client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
             database: database.to_string(),
             cause: e.to_string(),
         })?;
@@ -271,66 +610,211 @@ impl SeederRunner {
 
         let count: i64 = row.get(0);
 
-        if count > 0 {
+        let upserts;
+        let to_insert = if count > 0 {
+            let records: Vec<SeederRecord> = seeder
+                .records
+                .iter()
+                .filter(|r| r.on_conflict.is_some())
+                .cloned()
+                .collect();
+            if records.is_empty() {
+                info!(
+                    "Skipping seeder for {} - table has {} existing rows",
+                    seeder.table_name, count
+                );
+                return Ok(SeederResult {
+                    table: seeder.table_name.clone(),
+                    inserted: 0,
+                    skipped: seeder.records.len(),
+                    total_expected: seeder.records.len(),
+                    existing_rows: count,
+                });
+            }
             info!(
-                "Skipping seeder for {} - table has {} existing rows",
-                seeder.table_name, count
+                "Table {} has {} existing rows - running only the {} upsert records of seeder {}",
+                seeder.table_name,
+                count,
+                records.len(),
+                seeder.name
             );
-            return Ok(SeederResult {
-                table: seeder.table_name.clone(),
-                inserted: 0,
-                skipped: seeder.records.len(),
-                total_expected: seeder.records.len(),
-            });
+            upserts = SeederFile {
+                records,
+                ..seeder.clone()
+            };
+            &upserts
+        } else {
+            seeder
+        };
+
+        // Insert the records in batches within one transaction
+        let batches = self.seeder_batches(&client, database, to_insert).await?;
+        let mut transaction = client
+            .transaction()
+            .await
+            .map_err(|e| insert_error(database, &seeder.table_name, e, "BEGIN", None))?;
+        let inserted = self.insert_batches(&mut transaction, database, to_insert, batches).await?;
+        transaction
+            .commit()
+            .await
+            .map_err(|e| insert_error(database, &seeder.table_name, e, "COMMIT", None))?;
+
+        info!(
+            "Seeder {} inserted {} records into {}",
+            seeder.name, inserted, seeder.table_name
+        );
+
+        Ok(SeederResult {
+            table: seeder.table_name.clone(),
+            inserted,
+            skipped: seeder.records.len() - inserted,
+            total_expected: seeder.records.len(),
+            existing_rows: count,
+        })
+    }
+
+    /// Seeder records in INSERT batches, with the column types of each batch
+    ///
+    /// Statements with different column lists are batched separately.
+    async fn seeder_batches<'a>(
+        &self,
+        client: &deadpool_postgres::Client,
+        database: &str,
+        seeder: &'a SeederFile,
+    ) -> Result<Vec<(&'a [SeederRecord], Vec<String>)>> {
+        let mut batches = Vec::new();
+        for group in column_groups(&seeder.records) {
+            let column_types = self
+                .column_types(client, database, &seeder.table_name, &group[0].columns)
+                .await?;
+            for batch in group.chunks(batch_rows(column_types.len())) {
+                batches.push((batch, column_types.clone()));
+            }
         }
+        Ok(batches)
+    }
 
-        // Table is empty, insert all records
+    /// Insert the batches of one seeder inside the caller's transaction
+    async fn insert_batches(
+        &self,
+        transaction: &mut deadpool_postgres::Transaction<'_>,
+        database: &str,
+        seeder: &SeederFile,
+        batches: Vec<(&[SeederRecord], Vec<String>)>,
+    ) -> Result<usize> {
+        let table = seeder.table_name.as_str();
         let mut inserted = 0;
+        let mut statements = 0;
 
-        for record in &seeder.records {
-            let columns_str = record.columns.join(", ");
-            let values_str = record.values.join(", ");
+        for (batch, column_types) in batches {
+            let (insert_sql, params) = build_insert(table, batch, &column_types);
 
-            let insert_sql = format!(
-                "INSERT INTO {} ({}) VALUES ({})",
-                seeder.table_name, columns_str, values_str
+            debug!(
+                "Executing seeder batch for {}: {} records",
+                table,
+                batch.len()
             );
 
-            debug!("Executing seeder SQL for {}: {}", seeder.table_name, insert_sql);
+            // A savepoint lets a failed batch be retried row by row to find the bad record
+            let savepoint = transaction
+                .savepoint("seeder_batch")
+                .await
+                .map_err(|e| insert_error(database, table, e, "SAVEPOINT", None))?;
+            statements += 1;
+
+            match savepoint.execute(&insert_sql, &as_sql_params(&params)).await {
+                Ok(_) => {
+                    savepoint
+                        .commit()
+                        .await
+                        .map_err(|e| insert_error(database, table, e, "RELEASE SAVEPOINT", None))?;
+                }
+                Err(e) => {
+                    debug!("Seeder batch for {} failed ({}), retrying row by row", table, e);
+                    savepoint
+                        .rollback()
+                        .await
+                        .map_err(|e| insert_error(database, table, e, "ROLLBACK TO SAVEPOINT", None))?;
+
+                    for record in batch {
+                        let (row_sql, row_params) = build_insert(
+                            table,
+                            std::slice::from_ref(record),
+                            &column_types,
+                        );
+                        statements += 1;
+                        transaction
+                            .execute(&row_sql, &as_sql_params(&row_params))
+                            .await
+                            .map_err(|e| insert_error(database, table, e, &row_sql, Some(record)))?;
+                    }
+                }
+            }
 
-            client.execute(&insert_sql, &[]).await.map_err(|e| {
-                // Extract detailed error message from PostgreSQL error
-                let error_detail = if let Some(db_err) = e.as_db_error() {
-                    format!("{} - {}", db_err.message(),
-                        db_err.detail().unwrap_or("no additional detail"))
-                } else {
-                    e.to_string()
-                };
+            inserted += batch.len();
+        }
 
-                warn!("Seeder insert failed for table {}: SQL = '{}', Error = {}",
-                    seeder.table_name, insert_sql, error_detail);
+        debug!(
+            "Seeder {} used {} INSERT statements for {} records",
+            seeder.name, statements, inserted
+        );
 
-                GatewayError::QueryFailed {
-                    database: database.to_string(),
-                    function: format!("seeder insert: {}", seeder.table_name),
-                    cause: error_detail,
-                }
-            })?;
+        Ok(inserted)
+    }
 
-            inserted += 1;
+    /// SQL type of each seeder column, from the live table
+    async fn column_types(
+        &self,
+        client: &deadpool_postgres::Client,
+        database: &str,
+        table: &str,
+        columns: &[String],
+    ) -> Result<Vec<String>> {
+        if columns.is_empty() {
+            return Ok(Vec::new());
         }
 
-        info!(
-            "Seeder {} inserted {} records into {}",
-            seeder.name, inserted, seeder.table_name
-        );
+        let sql = format!("SELECT {} FROM {} LIMIT 0", columns.join(", "), table);
+        let statement = client.prepare(&sql).await.map_err(|e| GatewayError::QueryFailed {
+            database: database.to_string(),
+            function: format!("seeder columns: {}", table),
+            cause: e.to_string(),
+        })?;
 
-        Ok(SeederResult {
-            table: seeder.table_name.clone(),
-            inserted,
-            skipped: 0,
-            total_expected: seeder.records.len(),
-        })
+        Ok(statement.columns().iter().map(|c| type_sql(c.type_())).collect())
+    }
+
+    /// Primary key columns of a table in key order, empty if it has none
+    async fn primary_key_columns(
+        &self,
+        client: &deadpool_postgres::Client,
+        database: &str,
+        table: &str,
+    ) -> Result<Vec<String>> {
+        let rows = client
+            .query(
+                r#"
+                SELECT kcu.column_name::text
+                FROM information_schema.table_constraints tc
+                JOIN information_schema.key_column_usage kcu
+                  ON kcu.constraint_schema = tc.constraint_schema
+                 AND kcu.constraint_name = tc.constraint_name
+                 AND kcu.table_name = tc.table_name
+                WHERE tc.constraint_type = 'PRIMARY KEY'
+                  AND tc.table_schema = current_schema()
+                  AND tc.table_name = $1
+                ORDER BY kcu.ordinal_position
+                "#,
+                &[&table],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: format!("seeder primary key: {}", table),
+                cause: e.to_string(),
+            })?;
+
+        Ok(rows.iter().map(|r| r.get(0)).collect())
     }
 
     /// Validate seeders after migration (check all records exist)
@@ -365,19 +849,25 @@ impl SeederRunner {
         }
 
         if has_errors {
-            let missing_details: Vec<String> = validations
-                .iter()
-                .filter(|v| v.found < v.expected)
-                .map(|v| {
-                    format!(
-                        "{}: {}/{} (missing: {})",
-                        v.table,
-                        v.found,
-                        v.expected,
-                        v.missing.join(", ")
-                    )
-                })
-                .collect();
+            let changelog = ChangelogManager::new();
+            let mut missing_details = Vec::new();
+            for v in validations.iter().filter(|v| v.found < v.expected) {
+                let mut detail = format!(
+                    "{}: {}/{} (missing: {})",
+                    v.table,
+                    v.found,
+                    v.expected,
+                    v.missing.join(", ")
+                );
+                // A register that skipped the seeder explains the gap better than the generic causes
+                if let Ok(Some(rows)) = changelog.seeder_skipped_rows(pool, database, &v.table).await {
+                    detail.push_str(&format!(
+                        " - seeder was skipped at register because table had {} rows; seed data may be missing",
+                        rows
+                    ));
+                }
+                missing_details.push(detail);
+            }
 
             return Err(GatewayError::MigrationFailed {
                 database: database.to_string(),
@@ -411,52 +901,56 @@ impl SeederRunner {
 
         let mut found = 0;
         let mut missing = Vec::new();
+        let discovered = self
+            .primary_key_columns(&client, database, &seeder.table_name)
+            .await?;
+
+        for group in column_groups(&seeder.records) {
+            let columns = &group[0].columns;
+            let column_types = self
+                .column_types(&client, database, &seeder.table_name, columns)
+                .await?;
+            let primary_key_columns =
+                resolve_primary_key(discovered.clone(), columns, &seeder.primary_key_columns);
+
+            for record in group {
+                // Build WHERE clause using primary key
+                let (check_sql, params) = match build_pk_lookup(
+                    &seeder.table_name,
+                    &primary_key_columns,
+                    record,
+                    &column_types,
+                ) {
+                    Some(lookup) => lookup,
+                    None => {
+                        // No PK defined, skip validation for this record
+                        found += 1;
+                        continue;
+                    }
+                };
 
-        for record in &seeder.records {
-            // Build WHERE clause using primary key
-            let pk_conditions: Vec<String> = seeder
-                .primary_key_columns
-                .iter()
-                .filter_map(|pk_col| {
-                    let idx = record.columns.iter().position(|c| c == pk_col)?;
-                    Some(format!("{} = {}", pk_col, record.values[idx]))
-                })
-                .collect();
-
-            if pk_conditions.is_empty() {
-                // No PK defined, skip validation for this record
-                found += 1;
-                continue;
-            }
-
-            let check_sql = format!(
-                "SELECT 1 FROM {} WHERE {} LIMIT 1",
-                seeder.table_name,
-                pk_conditions.join(" AND ")
-            );
+                let row = client.query_opt(&check_sql, &as_sql_params(&params)).await.map_err(|e| {
+                    GatewayError::QueryFailed {
+                        database: database.to_string(),
+                        function: format!("seeder validation: {}", seeder.table_name),
+                        cause: e.to_string(),
+                    }
+                })?;
 
-            let row = client.query_opt(&check_sql, &[]).await.map_err(|e| {
-                GatewayError::QueryFailed {
-                    database: database.to_string(),
-                    function: format!("seeder validation: {}", seeder.table_name),
-                    cause: e.to_string(),
+                if row.is_some() {
+                    found += 1;
+                } else {
+                    // Record PK value for error message
+                    let pk_value: String = primary_key_columns
+                        .iter()
+                        .filter_map(|pk_col| {
+                            let idx = record.columns.iter().position(|c| c == pk_col)?;
+                            Some(record.values[idx].clone())
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    missing.push(pk_value);
                 }
-            })?;
-
-            if row.is_some() {
-                found += 1;
-            } else {
-                // Record PK value for error message
-                let pk_value: String = seeder
-                    .primary_key_columns
-                    .iter()
-                    .filter_map(|pk_col| {
-                        let idx = record.columns.iter().position(|c| c == pk_col)?;
-                        Some(record.values[idx].clone())
-                    })
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                missing.push(pk_value);
             }
         }
 
@@ -469,6 +963,96 @@ impl SeederRunner {
     }
 }
 
+/// Choose the columns used to look up seeded records
+///
+/// The table's real primary key is used when the seeder supplies all of its
+/// columns (a generated key the seeder leaves out cannot identify a record);
+/// otherwise this falls back to the first-column heuristic.
+fn resolve_primary_key(discovered: Vec<String>, columns: &[String], fallback: &[String]) -> Vec<String> {
+    if !discovered.is_empty() && discovered.iter().all(|c| columns.contains(c)) {
+        discovered
+    } else {
+        fallback.to_vec()
+    }
+}
+
+/// Error for a failed seeder statement, with PostgreSQL's detail and the bad record if known
+fn insert_error(
+    database: &str,
+    table: &str,
+    e: tokio_postgres::Error,
+    sql: &str,
+    record: Option<&SeederRecord>,
+) -> GatewayError {
+    // Extract detailed error message from PostgreSQL error
+    let mut error_detail = if let Some(db_err) = e.as_db_error() {
+        format!("{} - {}", db_err.message(),
+            db_err.detail().unwrap_or("no additional detail"))
+    } else {
+        e.to_string()
+    };
+    if let Some(record) = record {
+        error_detail = format!("{} (record: ({}))", error_detail, record.values.join(", "));
+    }
+
+    warn!("Seeder insert failed for table {}: SQL = '{}', Error = {}",
+        table, sql, error_detail);
+
+    GatewayError::QueryFailed {
+        database: database.to_string(),
+        function: format!("seeder insert: {}", table),
+        cause: error_detail,
+    }
+}
+
+/// Split records into consecutive runs that share a column list and conflict clause
+fn column_groups(records: &[SeederRecord]) -> Vec<&[SeederRecord]> {
+    records
+        .chunk_by(|a, b| a.columns == b.columns && a.on_conflict == b.on_conflict)
+        .collect()
+}
+
+/// Split a VALUES clause into the contents of its top-level tuples
+///
+/// Parentheses and commas inside string literals (including `''` escapes)
+/// and nested function calls are kept within their tuple.
+fn split_tuples(values_str: &str) -> Vec<String> {
+    let mut tuples = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut depth = 0;
+
+    for ch in values_str.chars() {
+        match ch {
+            '\'' => {
+                // A doubled '' closes and immediately reopens, keeping the escape intact
+                in_string = !in_string;
+                if depth > 0 {
+                    current.push(ch);
+                }
+            }
+            '(' if !in_string => {
+                if depth > 0 {
+                    current.push(ch);
+                }
+                depth += 1;
+            }
+            ')' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    tuples.push(std::mem::take(&mut current));
+                } else {
+                    current.push(ch);
+                }
+            }
+            _ if depth > 0 => current.push(ch),
+            _ => {}
+        }
+    }
+
+    tuples
+}
+
 impl Default for SeederRunner {
     fn default() -> Self {
         Self::new()
@@ -478,6 +1062,7 @@ impl Default for SeederRunner {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schema::test_db::test_pool;
 
     #[test]
     fn test_parse_value_tuple() {
@@ -490,6 +1075,586 @@ mod tests {
         assert_eq!(values, vec!["'USD'", "'US Dollar'", "'$'"]);
     }
 
+    #[test]
+    fn test_split_tuples_keeps_quoted_parentheses() {
+        let tuples = split_tuples("(1, 'O''Brien (Jr), Esq'), (2, lower('X'))");
+        assert_eq!(tuples, vec!["1, 'O''Brien (Jr), Esq'", "2, lower('X')"]);
+
+        let runner = SeederRunner::new();
+        let values = runner.parse_value_tuple(&tuples[0]);
+        assert_eq!(values, vec!["1", "'O''Brien (Jr), Esq'"]);
+        let values = runner.parse_value_tuple(&tuples[1]);
+        assert_eq!(values, vec!["2", "lower('X')"]);
+    }
+
+    #[test]
+    fn test_parse_seeder_value() {
+        assert_eq!(SeederValue::parse("'O''Brien'"), SeederValue::Text("O'Brien".to_string()));
+        assert_eq!(SeederValue::parse("''"), SeederValue::Text(String::new()));
+        assert_eq!(SeederValue::parse("-12.50"), SeederValue::Number("-12.50".to_string()));
+        assert_eq!(SeederValue::parse("null"), SeederValue::Null);
+        assert_eq!(SeederValue::parse("TRUE"), SeederValue::Boolean(true));
+        assert_eq!(SeederValue::parse("DEFAULT"), SeederValue::Default);
+        assert_eq!(SeederValue::parse("NOW()"), SeederValue::Expression("NOW()".to_string()));
+        assert_eq!(
+            SeederValue::parse("'{}'::jsonb"),
+            SeederValue::Expression("'{}'::jsonb".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_insert_binds_escaped_quotes_as_parameters() {
+        let record = SeederRecord {
+            columns: vec!["name", "note", "score", "active", "created_at"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            values: vec!["'O''Brien'", "NULL", "3", "FALSE", "DEFAULT"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            on_conflict: None,
+        };
+        let types: Vec<String> = vec!["text", "text", "numeric", "bool", "timestamptz"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        let (sql, params) = build_insert("people", std::slice::from_ref(&record), &types);
+        assert_eq!(
+            sql,
+            "INSERT INTO people (name, note, score, active, created_at) VALUES \
+             ($1::text::text, $2::text::text, $3::text::numeric, $4::text::bool, DEFAULT)"
+        );
+        assert_eq!(
+            params,
+            vec![Some("O'Brien".to_string()), None, Some("3".to_string()), Some("false".to_string())]
+        );
+
+        let pk = vec!["name".to_string()];
+        let (sql, params) = build_pk_lookup("people", &pk, &record, &types).unwrap();
+        assert_eq!(sql, "SELECT 1 FROM people WHERE name = $1::text::text LIMIT 1");
+        assert_eq!(params, vec![Some("O'Brien".to_string())]);
+    }
+
+    #[test]
+    fn test_build_insert_batches_rows() {
+        let records: Vec<SeederRecord> = (1..=2500)
+            .map(|i| SeederRecord {
+                columns: vec!["id".to_string(), "code".to_string()],
+                values: vec![i.to_string(), format!("'C{}'", i)],
+                on_conflict: None,
+            })
+            .collect();
+        let types = vec!["int4".to_string(), "text".to_string()];
+
+        // 2500 rows take 3 statements instead of 2500 round-trips
+        let batches: Vec<&[SeederRecord]> = records.chunks(batch_rows(2)).collect();
+        assert_eq!(batches.len(), 3);
+
+        let (sql, params) = build_insert("currencies", &records[..2], &types);
+        assert_eq!(
+            sql,
+            "INSERT INTO currencies (id, code) VALUES \
+             ($1::text::int4, $2::text::text), ($3::text::int4, $4::text::text)"
+        );
+        assert_eq!(params.len(), 4);
+    }
+
+    #[test]
+    fn test_batch_rows_respects_parameter_limit() {
+        assert_eq!(batch_rows(5), SEEDER_BATCH_ROWS);
+        assert_eq!(batch_rows(100), MAX_BIND_PARAMS / 100);
+        assert!(batch_rows(100) * 100 <= MAX_BIND_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_seeder_with_escaped_quotes_inserts_and_validates() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS seeder_quote_test;
+                 CREATE TABLE seeder_quote_test (
+                     name TEXT PRIMARY KEY,
+                     note TEXT,
+                     score NUMERIC(5,2),
+                     active BOOLEAN,
+                     created_at TIMESTAMPTZ DEFAULT NOW()
+                 );",
+            )
+            .await
+            .unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("seeder_quote_test.pssql"),
+            "INSERT INTO seeder_quote_test (name, note, score, active, created_at) VALUES
+                ('O''Brien', 'It''s (fine), really', 12.50, TRUE, DEFAULT),
+                ('D''Arcy', NULL, 3, FALSE, NOW());",
+        )
+        .unwrap();
+
+        let runner = SeederRunner::new();
+        let results = runner
+            .run_seeders_on_register(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+        assert_eq!(results[0].inserted, 2);
+
+        let validations = runner
+            .validate_seeders(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+        assert_eq!(validations[0].found, 2);
+        assert!(validations[0].missing.is_empty());
+
+        let row = client
+            .query_one(
+                "SELECT note, score::text FROM seeder_quote_test WHERE name = 'O''Brien'",
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(row.get::<_, String>(0), "It's (fine), really");
+        assert_eq!(row.get::<_, String>(1), "12.50");
+
+        client
+            .batch_execute("DROP TABLE seeder_quote_test")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_reports_failing_record() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS seeder_batch_test;
+                 CREATE TABLE seeder_batch_test (id INT PRIMARY KEY, code TEXT NOT NULL);",
+            )
+            .await
+            .unwrap();
+
+        let mut rows: Vec<String> = (1..=1500).map(|i| format!("({}, 'C{}')", i, i)).collect();
+        rows[1200] = "(1201, NULL)".to_string();
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("seeder_batch_test.pssql"),
+            format!("INSERT INTO seeder_batch_test (id, code) VALUES {};", rows.join(",\n")),
+        )
+        .unwrap();
+
+        let runner = SeederRunner::new();
+        let err = runner
+            .run_seeders_on_register(&pool, "gw_test", dir.path())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("record: (1201, NULL)"), "{}", err);
+
+        // The whole seeder rolls back, including the batch that succeeded
+        let count: i64 = client
+            .query_one("SELECT COUNT(*) FROM seeder_batch_test", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(count, 0);
+
+        rows[1200] = "(1201, 'C1201')".to_string();
+        std::fs::write(
+            dir.path().join("seeder_batch_test.pssql"),
+            format!("INSERT INTO seeder_batch_test (id, code) VALUES {};", rows.join(",\n")),
+        )
+        .unwrap();
+        let results = runner
+            .run_seeders_on_register(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+        assert_eq!(results[0].inserted, 1500);
+
+        client
+            .batch_execute("DROP TABLE seeder_batch_test")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_force_reseed_replaces_data_and_guards_foreign_keys() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS reseed_orders, reseed_statuses;
+                 CREATE TABLE reseed_statuses (code TEXT PRIMARY KEY, label TEXT NOT NULL);
+                 CREATE TABLE reseed_orders (id INT PRIMARY KEY, status TEXT REFERENCES reseed_statuses(code));
+                 INSERT INTO reseed_statuses VALUES ('new', 'Edited by hand'), ('stale', 'Removed from seeder');",
+            )
+            .await
+            .unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("reseed_statuses.pssql"),
+            "INSERT INTO reseed_statuses (code, label) VALUES ('new', 'New'), ('done', 'Done');",
+        )
+        .unwrap();
+
+        let runner = SeederRunner::new();
+
+        // Register leaves a non-empty table alone
+        let results = runner.run_seeders_on_register(&pool, "gw_test", dir.path()).await.unwrap();
+        assert_eq!(results[0].inserted, 0);
+
+        // reseed_orders references the seeded table, so cascade is required
+        let err = runner
+            .run_seeders_force(&pool, "gw_test", dir.path(), false)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("reseed_orders -> reseed_statuses"), "{}", err);
+        let count: i64 = client
+            .query_one("SELECT COUNT(*) FROM reseed_statuses", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(count, 2);
+
+        client
+            .batch_execute("INSERT INTO reseed_orders VALUES (1, 'new')")
+            .await
+            .unwrap();
+        let results = runner.run_seeders_force(&pool, "gw_test", dir.path(), true).await.unwrap();
+        assert_eq!(results[0].inserted, 2);
+
+        let rows: Vec<(String, String)> = client
+            .query("SELECT code, label FROM reseed_statuses ORDER BY code", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| (r.get(0), r.get(1)))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("done".to_string(), "Done".to_string()),
+                ("new".to_string(), "New".to_string())
+            ]
+        );
+        let orders: i64 = client
+            .query_one("SELECT COUNT(*) FROM reseed_orders", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(orders, 0);
+
+        client
+            .batch_execute("DROP TABLE reseed_orders, reseed_statuses")
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_resolve_primary_key() {
+        let columns: Vec<String> = vec!["label", "tenant_id", "code"].into_iter().map(String::from).collect();
+        let fallback = vec!["label".to_string()];
+
+        let pk = vec!["tenant_id".to_string(), "code".to_string()];
+        assert_eq!(resolve_primary_key(pk.clone(), &columns, &fallback), pk);
+
+        // No PK discovered, or a PK the seeder does not supply
+        assert_eq!(resolve_primary_key(Vec::new(), &columns, &fallback), fallback);
+        assert_eq!(
+            resolve_primary_key(vec!["id".to_string()], &columns, &fallback),
+            fallback
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validation_uses_composite_primary_key() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS seeder_pk_test;
+                 CREATE TABLE seeder_pk_test (
+                     label TEXT NOT NULL,
+                     tenant_id INT NOT NULL,
+                     code TEXT NOT NULL,
+                     PRIMARY KEY (tenant_id, code)
+                 );",
+            )
+            .await
+            .unwrap();
+
+        // Same label on every row: checking the first column would find them all
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("seeder_pk_test.pssql"),
+            "INSERT INTO seeder_pk_test (label, tenant_id, code) VALUES
+                ('Default', 1, 'a'),
+                ('Default', 1, 'b'),
+                ('Default', 2, 'a');",
+        )
+        .unwrap();
+
+        let runner = SeederRunner::new();
+        runner
+            .run_seeders_on_register(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+
+        let validations = runner
+            .validate_seeders(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+        assert_eq!(validations[0].found, 3);
+
+        client
+            .batch_execute("DELETE FROM seeder_pk_test WHERE tenant_id = 1 AND code = 'b'")
+            .await
+            .unwrap();
+
+        let err = runner
+            .validate_seeders(&pool, "gw_test", dir.path())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("seeder_pk_test: 2/3 (missing: 1, 'b')"), "{}", err);
+
+        client
+            .batch_execute("DROP TABLE seeder_pk_test")
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_seeder_with_several_tables() {
+        let runner = SeederRunner::new();
+        let sql = "
+            -- Lookup tables
+            INSERT INTO currencies (code, name) VALUES ('USD', 'US Dollar'), ('EUR', 'Euro');
+            INSERT INTO countries (code, name) VALUES ('US', 'United States');
+            INSERT INTO currencies (code, name, symbol) VALUES ('GBP', 'Pound', '£')
+                ON CONFLICT DO NOTHING;
+            INSERT INTO languages (code) VALUES ('en');
+        ";
+
+        let seeders = runner
+            .parse_seeder(Path::new("reference_data.pssql"), sql)
+            .unwrap();
+        let tables: Vec<&str> = seeders.iter().map(|s| s.table_name.as_str()).collect();
+        assert_eq!(tables, vec!["currencies", "countries", "languages"]);
+        assert!(seeders.iter().all(|s| s.name == "reference_data.pssql"));
+
+        // Both currencies statements are kept, each with its own column list
+        assert_eq!(seeders[0].records.len(), 3);
+        let groups = column_groups(&seeders[0].records);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1][0].columns, vec!["code", "name", "symbol"]);
+    }
+
+    #[test]
+    fn test_parse_seeder_keeps_on_conflict_clause() {
+        let runner = SeederRunner::new();
+        let sql = "
+            INSERT INTO currencies (code, name) VALUES ('USD', 'US Dollar'), ('EUR', 'Euro')
+            ON CONFLICT (code) DO UPDATE SET name = EXCLUDED.name;
+            INSERT INTO currencies (code, name) VALUES ('GBP', 'Pound');
+            INSERT INTO products (sku) VALUES ('A1') ON DUPLICATE KEY UPDATE sku = sku;
+        ";
+
+        let seeders = runner.parse_seeder(Path::new("currencies.pssql"), sql).unwrap();
+        let upsert = Some("ON CONFLICT (code) DO UPDATE SET name = EXCLUDED.name".to_string());
+        assert_eq!(seeders[0].records.len(), 3);
+        assert_eq!(seeders[0].records[0].on_conflict, upsert);
+        assert_eq!(seeders[0].records[2].on_conflict, None);
+        // MySQL's clause is not PostgreSQL and is dropped
+        assert_eq!(seeders[1].records[0].on_conflict, None);
+
+        // Same columns, different conflict handling: separate statements
+        let groups = column_groups(&seeders[0].records);
+        assert_eq!(groups.len(), 2);
+
+        let types = vec!["text".to_string(), "text".to_string()];
+        let (sql, _) = build_insert("currencies", groups[0], &types);
+        assert_eq!(
+            sql,
+            "INSERT INTO currencies (code, name) VALUES ($1::text::text, $2::text::text), ($3::text::text, $4::text::text) \
+             ON CONFLICT (code) DO UPDATE SET name = EXCLUDED.name"
+        );
+        let (sql, _) = build_insert("currencies", groups[1], &types);
+        assert!(sql.ends_with("VALUES ($1::text::text, $2::text::text)"), "{}", sql);
+    }
+
+    #[tokio::test]
+    async fn test_validation_names_seeder_skipped_at_register() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS seeder_skipped_roles;
+                 CREATE TABLE seeder_skipped_roles (id INT PRIMARY KEY, name TEXT);
+                 INSERT INTO seeder_skipped_roles VALUES (99, 'legacy');",
+            )
+            .await
+            .unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("roles.pssql"),
+            "INSERT INTO seeder_skipped_roles (id, name) VALUES (1, 'admin'), (2, 'user');",
+        )
+        .unwrap();
+
+        let changelog = ChangelogManager::new();
+        changelog.ensure_changelog_table(&pool, "gw_test").await.unwrap();
+
+        // Register: the table is not empty, so nothing is inserted, and the skip is recorded
+        let runner = SeederRunner::new();
+        let results = runner
+            .run_seeders_on_register(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+        assert_eq!((results[0].inserted, results[0].existing_rows), (0, 1));
+        changelog
+            .log_seeder_skipped(&pool, "gw_test", "seeder_skipped_roles", "table not empty", 1, 2)
+            .await
+            .unwrap();
+
+        let err = runner
+            .validate_seeders(&pool, "gw_test", dir.path())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("seeder_skipped_roles: 0/2 (missing: 1, 2) - seeder was skipped at register because table had 1 rows; seed data may be missing"),
+            "{}",
+            err
+        );
+
+        // Once a later run is recorded, the skip no longer explains the gap
+        changelog
+            .log_seeder_run(&pool, "gw_test", "seeder_skipped_roles", 2, 0)
+            .await
+            .unwrap();
+        let err = runner
+            .validate_seeders(&pool, "gw_test", dir.path())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(!err.contains("skipped at register"), "{}", err);
+
+        client.batch_execute("DROP TABLE seeder_skipped_roles").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_upsert_seeder_runs_on_non_empty_table() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS seeder_upsert_currencies;
+                 CREATE TABLE seeder_upsert_currencies (code TEXT PRIMARY KEY, name TEXT);
+                 INSERT INTO seeder_upsert_currencies VALUES ('USD', 'Old name'), ('JPY', 'Yen');",
+            )
+            .await
+            .unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("currencies.pssql"),
+            "INSERT INTO seeder_upsert_currencies (code, name) VALUES ('USD', 'US Dollar'), ('EUR', 'Euro')
+                 ON CONFLICT (code) DO UPDATE SET name = EXCLUDED.name;
+             INSERT INTO seeder_upsert_currencies (code, name) VALUES ('JPY', 'Japanese Yen');",
+        )
+        .unwrap();
+
+        let runner = SeederRunner::new();
+        for _ in 0..2 {
+            // The table is not empty, yet the upsert runs (twice, idempotently)
+            let results = runner
+                .run_seeders_on_register(&pool, "gw_test", dir.path())
+                .await
+                .unwrap();
+            assert_eq!((results[0].inserted, results[0].skipped), (2, 1));
+        }
+
+        let rows = client
+            .query("SELECT code, name FROM seeder_upsert_currencies ORDER BY code", &[])
+            .await
+            .unwrap();
+        let rows: Vec<(String, String)> = rows.iter().map(|r| (r.get(0), r.get(1))).collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("EUR".to_string(), "Euro".to_string()),
+                ("JPY".to_string(), "Yen".to_string()),
+                ("USD".to_string(), "US Dollar".to_string()),
+            ]
+        );
+
+        client.batch_execute("DROP TABLE seeder_upsert_currencies").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_seeder_file_with_several_tables_runs_and_validates() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS seeder_multi_a, seeder_multi_b;
+                 CREATE TABLE seeder_multi_a (code TEXT PRIMARY KEY, label TEXT);
+                 CREATE TABLE seeder_multi_b (id INT PRIMARY KEY);",
+            )
+            .await
+            .unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("reference_data.pssql"),
+            "INSERT INTO seeder_multi_a (code, label) VALUES ('a', 'A'), ('b', 'B');
+             INSERT INTO seeder_multi_b (id) VALUES (1), (2), (3);
+             INSERT INTO seeder_multi_a (code) VALUES ('c');",
+        )
+        .unwrap();
+
+        let runner = SeederRunner::new();
+        let results = runner
+            .run_seeders_on_register(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+        let inserted: Vec<(&str, usize)> = results.iter().map(|r| (r.table.as_str(), r.inserted)).collect();
+        assert_eq!(inserted, vec![("seeder_multi_a", 3), ("seeder_multi_b", 3)]);
+
+        let validations = runner
+            .validate_seeders(&pool, "gw_test", dir.path())
+            .await
+            .unwrap();
+        assert_eq!(validations.len(), 2);
+        assert!(validations.iter().all(|v| v.found == v.expected));
+
+        client
+            .batch_execute("DROP TABLE seeder_multi_a, seeder_multi_b")
+            .await
+            .unwrap();
+    }
+
     #[test]
     fn test_remove_comments() {
         let runner = SeederRunner::new();
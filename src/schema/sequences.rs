@@ -0,0 +1,252 @@
+//! Sequence checks for serial/identity columns
+//!
+//! Seeding a table with explicit IDs does not advance its sequence, so the next
+//! default value collides with an existing row. This finds sequences whose next
+//! value is not above `MAX(column)` and can reset them with `setval`.
+
+use crate::error::{GatewayError, Result};
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+/// A serial/identity sequence that would hand out an existing value next
+#[derive(Debug, Clone, Serialize)]
+pub struct LaggingSequence {
+    /// Table as `regclass` text (quoted and schema-qualified when needed)
+    pub table: String,
+    pub column: String,
+    /// Sequence as `regclass` text
+    pub sequence: String,
+    pub last_value: i64,
+    pub max_value: i64,
+}
+
+impl LaggingSequence {
+    /// Statement that moves the sequence to the column's current maximum
+    pub fn fix_sql(&self) -> String {
+        format!(
+            "SELECT setval('{}', {}, true);",
+            self.sequence.replace('\'', "''"),
+            self.max_value
+        )
+    }
+}
+
+pub struct SequenceChecker;
+
+impl SequenceChecker {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Find sequences owned by serial/identity columns that are behind their column
+    pub async fn find_lagging(&self, pool: &Pool, database: &str) -> Result<Vec<LaggingSequence>> {
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        // deptype 'a' links a serial column's sequence, 'i' an identity column's
+        let rows = client
+            .query(
+                r#"
+                SELECT c.oid::regclass::text, a.attname::text, quote_ident(a.attname), s.oid::regclass::text
+                FROM pg_depend d
+                JOIN pg_class s ON s.oid = d.objid AND s.relkind = 'S'
+                JOIN pg_class c ON c.oid = d.refobjid AND c.relkind IN ('r', 'p')
+                JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum = d.refobjsubid
+                WHERE d.classid = 'pg_class'::regclass
+                  AND d.refclassid = 'pg_class'::regclass
+                  AND d.deptype IN ('a', 'i')
+                  AND c.relnamespace NOT IN ('pg_catalog'::regnamespace, 'information_schema'::regnamespace)
+                ORDER BY 1, 2
+                "#,
+                &[],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "sequence ownership".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        let mut lagging = Vec::new();
+
+        for row in &rows {
+            let table: String = row.get(0);
+            let column: String = row.get(1);
+            let quoted_column: String = row.get(2);
+            let sequence: String = row.get(3);
+
+            let state = client
+                .query_one(&format!("SELECT last_value, is_called FROM {}", sequence), &[])
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: format!("sequence {}", sequence),
+                    cause: e.to_string(),
+                })?;
+            let last_value: i64 = state.get(0);
+            let is_called: bool = state.get(1);
+
+            let max_value: Option<i64> = client
+                .query_one(
+                    &format!("SELECT MAX({})::bigint FROM {}", quoted_column, table),
+                    &[],
+                )
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: format!("max of {}.{}", table, column),
+                    cause: e.to_string(),
+                })?
+                .get(0);
+
+            if let Some(max_value) = max_value {
+                if is_behind(last_value, is_called, max_value) {
+                    warn!(
+                        "Sequence {} in {} is behind {}.{}: last_value={}, max={}",
+                        sequence, database, table, column, last_value, max_value
+                    );
+                    lagging.push(LaggingSequence {
+                        table,
+                        column,
+                        sequence,
+                        last_value,
+                        max_value,
+                    });
+                }
+            }
+        }
+
+        debug!("{} lagging sequences in {}", lagging.len(), database);
+        Ok(lagging)
+    }
+
+    /// Reset lagging sequences to their column's maximum
+    pub async fn fix(&self, pool: &Pool, database: &str, lagging: &[LaggingSequence]) -> Result<()> {
+        if lagging.is_empty() {
+            return Ok(());
+        }
+
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+
+        for sequence in lagging {
+            client
+                .batch_execute(&sequence.fix_sql())
+                .await
+                .map_err(|e| GatewayError::QueryFailed {
+                    database: database.to_string(),
+                    function: format!("setval {}", sequence.sequence),
+                    cause: e.to_string(),
+                })?;
+            info!(
+                "Reset sequence {} in {} to {}",
+                sequence.sequence, database, sequence.max_value
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for SequenceChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the sequence's next value is not above the column's maximum
+///
+/// Until the first `nextval` (`is_called = false`) the next value is
+/// `last_value` itself, afterwards it is `last_value + 1`.
+fn is_behind(last_value: i64, is_called: bool, max_value: i64) -> bool {
+    let next_value = if is_called { last_value + 1 } else { last_value };
+    next_value <= max_value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_db::test_pool;
+
+    #[test]
+    fn test_is_behind() {
+        // Fresh sequence, table seeded with ids 1..=10
+        assert!(is_behind(1, false, 10));
+        // Sequence used up to 10, max id 10
+        assert!(!is_behind(10, true, 10));
+        // Fresh sequence on a table whose max id is below the start
+        assert!(!is_behind(1, false, 0));
+        // Sequence at 5 but rows up to 5 were inserted explicitly
+        assert!(is_behind(4, true, 5));
+    }
+
+    #[test]
+    fn test_fix_sql() {
+        let sequence = LaggingSequence {
+            table: "\"User's\"".to_string(),
+            column: "id".to_string(),
+            sequence: "\"User's_id_seq\"".to_string(),
+            last_value: 1,
+            max_value: 42,
+        };
+
+        assert_eq!(
+            sequence.fix_sql(),
+            "SELECT setval('\"User''s_id_seq\"', 42, true);"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lagging_sequence_detected_and_fixed() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+
+        pool.get()
+            .await
+            .unwrap()
+            .batch_execute(
+                r#"
+                DROP TABLE IF EXISTS sequence_test_users;
+                CREATE TABLE sequence_test_users (id SERIAL PRIMARY KEY, name TEXT);
+                INSERT INTO sequence_test_users (id, name) VALUES (1, 'a'), (7, 'b');
+                "#,
+            )
+            .await
+            .unwrap();
+
+        let checker = SequenceChecker::new();
+        let lagging: Vec<LaggingSequence> = checker
+            .find_lagging(&pool, "test")
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.table == "sequence_test_users")
+            .collect();
+
+        assert_eq!(lagging.len(), 1);
+        assert_eq!(lagging[0].column, "id");
+        assert_eq!(lagging[0].max_value, 7);
+        assert_eq!(
+            lagging[0].fix_sql(),
+            "SELECT setval('sequence_test_users_id_seq', 7, true);"
+        );
+
+        checker.fix(&pool, "test", &lagging).await.unwrap();
+
+        let client = pool.get().await.unwrap();
+        let next: i32 = client
+            .query_one("INSERT INTO sequence_test_users (name) VALUES ('c') RETURNING id", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(next, 8);
+
+        client.batch_execute("DROP TABLE sequence_test_users").await.unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+//! Helpers for reading and writing SQL text shared by the schema modules
+
+use regex::Regex;
+
+/// Remove `--` line comments and `/* */` block comments
+pub(crate) fn strip_comments(sql: &str) -> String {
+    let single_line_re = Regex::new(r"--[^\n]*").unwrap();
+    let sql = single_line_re.replace_all(sql, "");
+
+    let multi_line_re = Regex::new(r"/\*[\s\S]*?\*/").unwrap();
+    multi_line_re.replace_all(&sql, "").to_string()
+}
+
+/// Split on semicolons outside string literals, trimming each statement
+pub(crate) fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut in_quote = false;
+
+    for c in sql.chars() {
+        match c {
+            '\'' => {
+                in_quote = !in_quote;
+                current.push(c);
+            }
+            ';' if !in_quote => {
+                if !current.trim().is_empty() {
+                    statements.push(current.trim().to_string());
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        statements.push(current.trim().to_string());
+    }
+
+    statements
+}
+
+/// Quote an identifier unless it is a plain lowercase name
+pub(crate) fn quote_ident(name: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "check", "default", "from", "group", "order", "primary", "references", "select",
+        "table", "user", "where",
+    ];
+
+    let re = Regex::new(r"^[a-z_][a-z0-9_]*$").unwrap();
+    if re.is_match(name) && !RESERVED.contains(&name) {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// Quote a possibly schema-qualified table name
+pub(crate) fn quote_qualified(name: &str) -> String {
+    match name.split_once('.') {
+        Some((schema, table)) => format!("{}.{}", quote_ident(schema), quote_ident(table)),
+        None => quote_ident(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_comments() {
+        let sql = "-- header\nSELECT 1; -- trailing\n/* block\n comment */SELECT 2;";
+        assert_eq!(split_statements(&strip_comments(sql)), vec!["SELECT 1", "SELECT 2"]);
+    }
+
+    #[test]
+    fn test_split_statements_ignores_quoted_semicolons() {
+        assert_eq!(
+            split_statements("INSERT INTO t VALUES ('a;b'); SELECT 1;\n"),
+            vec!["INSERT INTO t VALUES ('a;b')", "SELECT 1"]
+        );
+    }
+
+    #[test]
+    fn test_quote_ident() {
+        assert_eq!(quote_ident("users"), "users");
+        assert_eq!(quote_ident("order"), "\"order\"");
+        assert_eq!(quote_ident("User Data"), "\"User Data\"");
+        assert_eq!(quote_qualified("billing.order"), "billing.\"order\"");
+    }
+}
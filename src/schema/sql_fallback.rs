@@ -0,0 +1,363 @@
+//! Fallback parsing of CREATE TABLE statements with `sqlparser`
+//!
+//! The regex-based [`DependencyAnalyzer`](super::DependencyAnalyzer) skips
+//! statements it cannot match and can misread bodies it splits wrongly (e.g.
+//! a `'"'` default hides the columns that follow it).
+//! With the `sql-parser-fallback` feature every CREATE TABLE statement is also
+//! parsed with sqlparser's PostgreSQL dialect; tables the regex parser missed
+//! are added, tables whose columns disagree are replaced, and both cases are
+//! logged as warnings. Statements sqlparser cannot parse keep the regex result.
+
+use super::dependency::{
+    name_check_constraints, normalize_identifier, CheckConstraint, ColumnInfo, ColumnReference,
+    DependencyAnalyzer, ForeignKeyDependency, TableInfo,
+};
+use sqlparser::ast::{
+    ColumnDef, ColumnOption, CreateTable, GeneratedAs, Ident, ObjectName, Statement, TableConstraint,
+};
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::parser::Parser;
+use tracing::{debug, warn};
+
+/// Merge sqlparser's reading of `sql` into the regex parser's tables
+///
+/// `tables` holds (statement offset, table) pairs; added tables get the offset
+/// of their statement so file order is preserved.
+pub(super) fn reconcile(sql: &str, tables: &mut Vec<(usize, TableInfo)>) {
+    let create_table_re = regex::Regex::new(
+        r"(?is)^CREATE\s+(?:(?:GLOBAL|LOCAL)\s+)?(?:(?:TEMP|TEMPORARY|UNLOGGED)\s+)?TABLE\b",
+    )
+    .unwrap();
+
+    for (offset, statement) in split_statements(sql) {
+        if !create_table_re.is_match(statement) {
+            continue;
+        }
+
+        let Some(parsed) = parse_create_table(statement) else {
+            continue;
+        };
+        let name = parsed.qualified_name();
+
+        match tables.iter_mut().find(|(_, t)| t.qualified_name() == name) {
+            None => {
+                warn!(
+                    "Regex parser did not recognise table {}; using sqlparser result ({} columns)",
+                    name,
+                    parsed.columns.len()
+                );
+                tables.push((offset, parsed));
+            }
+            Some((_, existing)) if !existing.is_partition => {
+                let regex_columns = column_names(existing);
+                let parsed_columns = column_names(&parsed);
+                if regex_columns != parsed_columns {
+                    warn!(
+                        "Parsers disagree on the columns of {}: regex [{}], sqlparser [{}]; using sqlparser result",
+                        name,
+                        regex_columns.join(", "),
+                        parsed_columns.join(", ")
+                    );
+                    existing.columns = parsed.columns;
+                    existing.primary_key = parsed.primary_key;
+                    existing.foreign_keys = parsed.foreign_keys;
+                    existing.checks = parsed.checks;
+                }
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+fn column_names(table: &TableInfo) -> Vec<&str> {
+    table.columns.iter().map(|c| c.name.as_str()).collect()
+}
+
+/// Parse a single CREATE TABLE statement, or None if sqlparser rejects it
+///
+/// `CREATE TABLE ... AS SELECT` and partitions are left to the regex parser.
+fn parse_create_table(statement: &str) -> Option<TableInfo> {
+    let parsed = match Parser::parse_sql(&PostgreSqlDialect {}, statement) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            debug!("sqlparser could not parse CREATE TABLE statement: {}", e);
+            return None;
+        }
+    };
+
+    match parsed.as_slice() {
+        [Statement::CreateTable(create)] if create.query.is_none() => Some(convert_table(create)),
+        _ => None,
+    }
+}
+
+fn convert_table(create: &CreateTable) -> TableInfo {
+    let (schema, name) = split_object_name(&create.name);
+    let mut columns = Vec::new();
+    let mut foreign_keys = Vec::new();
+    let mut primary_key: Option<Vec<String>> = None;
+    let mut declared_checks: Vec<(Option<String>, Option<String>, String)> = Vec::new();
+
+    for def in &create.columns {
+        let column = convert_column(def);
+
+        if column.is_primary_key && primary_key.is_none() {
+            primary_key = Some(vec![column.name.clone()]);
+        }
+        for option in &def.options {
+            if let ColumnOption::Check(expr) = &option.option {
+                declared_checks.push((option.name.as_ref().map(ident), Some(column.name.clone()), expr.to_string()));
+            }
+        }
+        if let Some(refs) = &column.references {
+            foreign_keys.push(ForeignKeyDependency {
+                from_table: name.clone(),
+                from_columns: vec![column.name.clone()],
+                to_schema: refs.schema.clone(),
+                to_table: refs.table.clone(),
+                to_columns: if refs.column.is_empty() { Vec::new() } else { vec![refs.column.clone()] },
+                on_delete: refs.on_delete.clone(),
+                on_update: refs.on_update.clone(),
+            });
+        }
+
+        columns.push(column);
+    }
+
+    for constraint in &create.constraints {
+        match constraint {
+            TableConstraint::PrimaryKey { columns: pk, .. } => {
+                primary_key = Some(pk.iter().map(ident).collect());
+            }
+            TableConstraint::ForeignKey {
+                columns: from,
+                foreign_table,
+                referred_columns,
+                on_delete,
+                on_update,
+                ..
+            } => {
+                let (to_schema, to_table) = split_object_name(foreign_table);
+                foreign_keys.push(ForeignKeyDependency {
+                    from_table: name.clone(),
+                    from_columns: from.iter().map(ident).collect(),
+                    to_schema,
+                    to_table,
+                    to_columns: referred_columns.iter().map(ident).collect(),
+                    on_delete: on_delete.map(|a| a.to_string()),
+                    on_update: on_update.map(|a| a.to_string()),
+                });
+            }
+            TableConstraint::Check { name: check_name, expr } => {
+                declared_checks.push((check_name.as_ref().map(ident), None, expr.to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    let checks: Vec<CheckConstraint> = name_check_constraints(&name, &columns, declared_checks);
+
+    TableInfo {
+        schema,
+        name,
+        columns,
+        primary_key,
+        foreign_keys,
+        inherits: Vec::new(),
+        is_partition: false,
+        checks,
+        depends_on: Vec::new(),
+    }
+}
+
+fn convert_column(def: &ColumnDef) -> ColumnInfo {
+    let data_type = def.data_type.to_string().to_uppercase();
+    let (character_maximum_length, numeric_precision, numeric_scale) =
+        DependencyAnalyzer::parse_type_modifiers(&data_type);
+
+    let mut column = ColumnInfo {
+        name: ident(&def.name),
+        has_default: data_type.ends_with("SERIAL"),
+        data_type,
+        character_maximum_length,
+        numeric_precision,
+        numeric_scale,
+        is_nullable: true,
+        is_primary_key: false,
+        references: None,
+        check_constraint: None,
+        definition: def.to_string(),
+    };
+
+    for option in &def.options {
+        match &option.option {
+            ColumnOption::NotNull => column.is_nullable = false,
+            ColumnOption::Default(_) => column.has_default = true,
+            ColumnOption::Generated { generated_as: GeneratedAs::ByDefault, .. } => column.has_default = true,
+            ColumnOption::Unique { is_primary: true, .. } => column.is_primary_key = true,
+            ColumnOption::Check(expr) => column.check_constraint = Some(expr.to_string()),
+            ColumnOption::ForeignKey {
+                foreign_table,
+                referred_columns,
+                on_delete,
+                on_update,
+                ..
+            } => {
+                let (schema, table) = split_object_name(foreign_table);
+                column.references = Some(ColumnReference {
+                    schema,
+                    table,
+                    column: referred_columns.first().map(ident).unwrap_or_default(),
+                    on_delete: on_delete.map(|a| a.to_string()),
+                    on_update: on_update.map(|a| a.to_string()),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    column
+}
+
+/// Identifier folded the way PostgreSQL does (see `normalize_identifier`)
+fn ident(ident: &Ident) -> String {
+    match ident.quote_style {
+        Some(_) => ident.value.clone(),
+        None => normalize_identifier(&ident.value),
+    }
+}
+
+/// (schema, name) of a possibly schema-qualified name
+fn split_object_name(name: &ObjectName) -> (Option<String>, String) {
+    match name.0.as_slice() {
+        [.., schema, table] => (Some(ident(schema)), ident(table)),
+        [table] => (None, ident(table)),
+        [] => (None, String::new()),
+    }
+}
+
+/// Split on semicolons outside quotes and dollar-quoted bodies
+///
+/// Returns each statement with its byte offset in `sql`, trimmed.
+fn split_statements(sql: &str) -> Vec<(usize, &str)> {
+    let dollar_tag_re = regex::Regex::new(r"^\$(?:[A-Za-z_][A-Za-z0-9_]*)?\$").unwrap();
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    let mut push = |from: usize, to: usize| {
+        let text = &sql[from..to];
+        let trimmed = text.trim_start();
+        if !trimmed.trim().is_empty() {
+            statements.push((from + text.len() - trimmed.len(), trimmed.trim_end()));
+        }
+    };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == quote {
+                        // Doubled quote is an escaped quote
+                        if bytes.get(i + 1) == Some(&quote) {
+                            i += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                    i += 1;
+                }
+            }
+            b'$' => {
+                if let Some(tag) = dollar_tag_re.find(&sql[i..]) {
+                    let body_start = i + tag.len();
+                    match sql[body_start..].find(tag.as_str()) {
+                        Some(end) => i = body_start + end + tag.len() - 1,
+                        None => i = bytes.len(),
+                    }
+                }
+            }
+            b';' => {
+                push(start, i);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    push(start, bytes.len());
+
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        let sql = "CREATE TABLE a (note TEXT DEFAULT 'x; y');\nCREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql;\n";
+        let statements = split_statements(sql);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0], (0, "CREATE TABLE a (note TEXT DEFAULT 'x; y')"));
+        assert!(statements[1].1.starts_with("CREATE FUNCTION"), "{:?}", statements[1]);
+        assert_eq!(&sql[statements[1].0..statements[1].0 + 6], "CREATE");
+    }
+
+    #[test]
+    fn test_fallback_parses_what_the_regex_parser_mishandles() {
+        let sql = r#"
+CREATE TABLE users (id SERIAL PRIMARY KEY, email VARCHAR(255) NOT NULL);
+
+-- The '"' default makes the regex parser treat the rest of the body as quoted
+CREATE TABLE csv_imports (
+    id SERIAL PRIMARY KEY,
+    quote_char CHAR(1) NOT NULL DEFAULT '"',
+    delimiter CHAR(1) NOT NULL DEFAULT ',',
+    owner_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    row_count NUMERIC(10,2) CHECK (row_count >= 0)
+);
+
+CREATE TABLE import_errors (
+    id BIGSERIAL PRIMARY KEY,
+    import_id INT,
+    CONSTRAINT fk_import FOREIGN KEY (import_id) REFERENCES csv_imports(id)
+) WITH (fillfactor = 70);
+"#;
+
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        let names: Vec<&str> = analysis.tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["users", "csv_imports", "import_errors"]);
+
+        let imports = &analysis.tables[1];
+        assert_eq!(column_names(imports), vec!["id", "quote_char", "delimiter", "owner_id", "row_count"]);
+        assert!(imports.columns[2].has_default);
+        assert!(!imports.columns[2].is_nullable);
+        assert_eq!(imports.columns[2].character_maximum_length, Some(1));
+        assert_eq!(imports.columns[4].data_type, "NUMERIC(10,2)");
+        assert_eq!(imports.columns[4].numeric_precision, Some(10));
+        assert_eq!(imports.checks.len(), 1);
+        assert_eq!(imports.checks[0].name, "csv_imports_row_count_check");
+        assert_eq!(imports.foreign_keys.len(), 1);
+        assert_eq!(imports.foreign_keys[0].to_table, "users");
+        assert_eq!(imports.foreign_keys[0].on_delete.as_deref(), Some("CASCADE"));
+        assert_eq!(imports.depends_on, vec!["users"]);
+
+        let errors = &analysis.tables[2];
+        assert_eq!(column_names(errors), vec!["id", "import_id"]);
+        assert_eq!(errors.primary_key, Some(vec!["id".to_string()]));
+        assert_eq!(errors.depends_on, vec!["csv_imports"]);
+
+        assert_eq!(analysis.creation_order, vec!["users", "csv_imports", "import_errors"]);
+    }
+
+    #[test]
+    fn test_unparseable_statement_keeps_regex_result() {
+        let sql = "CREATE TABLE child (extra TEXT) INHERITS (parent);";
+        let analysis = DependencyAnalyzer::analyze_sql(sql).unwrap();
+        assert_eq!(analysis.tables.len(), 1);
+        assert_eq!(analysis.tables[0].inherits, vec!["parent"]);
+    }
+}
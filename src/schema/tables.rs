@@ -13,10 +13,10 @@
 //! 5. Track deployed tables in `_stonescriptdb_gateway_tables`
 
 use crate::error::{GatewayError, Result};
-use crate::schema::dependency::DependencyAnalyzer;
+use crate::schema::dependency::{DependencyAnalyzer, TableInfo, DEFAULT_SCHEMA, EXTERNAL_TABLES_FILE};
 use deadpool_postgres::Pool;
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
@@ -111,8 +111,13 @@ impl TableDeployer {
         Ok(files)
     }
 
-    /// Parse a table definition from a file
-    pub fn parse_table_definition(&self, file_path: &Path) -> Result<Option<TableDefinition>> {
+    /// Parse the table definitions in a file
+    ///
+    /// A file usually holds one table, whose SQL and checksum are the whole
+    /// file. When it defines several, each table gets its own `CREATE TABLE`
+    /// statement plus the statements following it (indexes, comments) up to
+    /// the next `CREATE TABLE`, and a checksum of that SQL.
+    pub fn parse_table_definitions(&self, file_path: &Path) -> Result<Vec<TableDefinition>> {
         let content = fs::read_to_string(file_path).map_err(|e| {
             GatewayError::SchemaExtractionFailed {
                 cause: format!("Failed to read table file {:?}: {}", file_path, e),
@@ -125,32 +130,57 @@ impl TableDeployer {
             .unwrap_or("unknown")
             .to_string();
 
+        // CTAS has no column list to parse; without this check the file is silently skipped
+        if let Some(table) = create_table_as(&content) {
+            return Err(GatewayError::SchemaExtractionFailed {
+                cause: format!(
+                    "{}: CREATE TABLE {} AS ... is not supported in tables/, because its content depends on the data at deploy time. \
+                     Create it in a migration instead, or declare its columns with CREATE TABLE {} (...)",
+                    file_name, table, table
+                ),
+            });
+        }
+
         // Use DependencyAnalyzer to extract table info
-        let analysis = match DependencyAnalyzer::analyze_sql(&content) {
-            Ok(a) => a,
-            Err(e) => {
-                warn!("Failed to analyze table file {}: {}", file_name, e);
-                return Ok(None);
-            }
-        };
+        let analysis = DependencyAnalyzer::analyze_file_sql(file_path, &content)?;
 
         if analysis.tables.is_empty() {
             debug!("No CREATE TABLE found in {}", file_name);
-            return Ok(None);
+            return Ok(Vec::new());
         }
 
-        // Get the first table (normally one table per file)
-        let table_info = &analysis.tables[0];
-
-        let checksum = compute_checksum(&content);
-
-        Ok(Some(TableDefinition {
-            name: table_info.name.clone(),
+        let definition = |table_info: &TableInfo, sql: &str| TableDefinition {
+            name: table_info.qualified_name(),
             file_path: file_path.to_path_buf(),
-            sql: content.trim().to_string(),
-            checksum,
+            sql: sql.trim().to_string(),
+            checksum: compute_checksum(sql),
             depends_on: table_info.depends_on.clone(),
-        }))
+        };
+
+        if analysis.tables.len() == 1 {
+            return Ok(vec![definition(&analysis.tables[0], &content)]);
+        }
+
+        let segments = split_table_statements(&content);
+        if segments.len() != analysis.tables.len() {
+            return Err(GatewayError::SchemaExtractionFailed {
+                cause: format!(
+                    "{}: found {} tables but could not split the file into {} CREATE TABLE statements",
+                    file_name,
+                    analysis.tables.len(),
+                    segments.len()
+                ),
+            });
+        }
+
+        debug!("{} defines {} tables", file_name, segments.len());
+
+        Ok(analysis
+            .tables
+            .iter()
+            .zip(&segments)
+            .map(|(table_info, sql)| definition(table_info, sql))
+            .collect())
     }
 
     /// Order tables by dependencies (topological sort)
@@ -162,6 +192,8 @@ impl TableDeployer {
             return Ok(tables);
         }
 
+        Self::check_name_collisions(&tables)?;
+
         // Build lookup map
         let table_map: HashMap<String, &TableDefinition> =
             tables.iter().map(|t| (t.name.clone(), t)).collect();
@@ -220,11 +252,34 @@ impl TableDeployer {
                 .map(|(_, t)| t.name.clone())
                 .collect();
 
+            // Name the cycles rather than everything stuck behind them
+            let graph: HashMap<String, Vec<String>> = tables
+                .iter()
+                .map(|t| {
+                    let deps = t
+                        .depends_on
+                        .iter()
+                        .filter(|dep| **dep != t.name && name_to_idx.contains_key(dep))
+                        .cloned()
+                        .collect();
+                    (t.name.clone(), deps)
+                })
+                .collect();
+            let cycles = DependencyAnalyzer::describe_cycles(&graph);
+
             return Err(GatewayError::SchemaExtractionFailed {
-                cause: format!(
-                    "Circular dependency detected in table definitions: {}",
-                    remaining.join(", ")
-                ),
+                cause: if cycles.is_empty() {
+                    format!(
+                        "Circular dependency detected in table definitions: {}",
+                        remaining.join(", ")
+                    )
+                } else {
+                    format!(
+                        "Circular dependency detected in table definitions: {} (tables left unordered: {})",
+                        cycles.join("; "),
+                        remaining.join(", ")
+                    )
+                },
             });
         }
 
@@ -242,20 +297,82 @@ impl TableDeployer {
         Ok(ordered)
     }
 
+    /// Reject distinct files that define the same table name, ignoring case
+    ///
+    /// Without this, the lookup maps below would silently keep only one of them.
+    fn check_name_collisions(tables: &[TableDefinition]) -> Result<()> {
+        let mut by_name: HashMap<String, Vec<&TableDefinition>> = HashMap::new();
+        for table in tables {
+            by_name.entry(table.name.to_lowercase()).or_default().push(table);
+        }
+
+        let mut collisions: Vec<String> = by_name
+            .values()
+            .filter(|defs| defs.len() > 1)
+            .map(|defs| {
+                let sources: Vec<String> = defs
+                    .iter()
+                    .map(|d| format!("'{}' in {}", d.name, d.file_path.display()))
+                    .collect();
+                sources.join(" and ")
+            })
+            .collect();
+
+        if collisions.is_empty() {
+            return Ok(());
+        }
+
+        collisions.sort();
+        Err(GatewayError::SchemaExtractionFailed {
+            cause: format!(
+                "Table name collision (names differ only by case or are defined twice): {}",
+                collisions.join("; ")
+            ),
+        })
+    }
+
+    /// Find FK targets that are neither defined in `tables` nor allow-listed as external
+    ///
+    /// Returns (table, missing dependency) pairs.
+    pub fn unresolved_dependencies(
+        &self,
+        tables: &[TableDefinition],
+        external_tables: &[String],
+    ) -> Vec<(String, String)> {
+        let defined: HashSet<&str> = tables.iter().map(|t| t.name.as_str()).collect();
+
+        let mut unresolved = Vec::new();
+        for table in tables {
+            for dep in &table.depends_on {
+                if !defined.contains(dep.as_str()) && !external_tables.contains(dep) {
+                    unresolved.push((table.name.clone(), dep.clone()));
+                }
+            }
+        }
+
+        unresolved.sort();
+        unresolved
+    }
+
     /// Check if a table already exists in the database
     async fn table_exists(
         &self,
         client: &deadpool_postgres::Object,
         table_name: &str,
     ) -> Result<bool> {
+        // Qualified names (schema.table) are used for non-default schemas
+        let (schema, table) = table_name
+            .split_once('.')
+            .unwrap_or((DEFAULT_SCHEMA, table_name));
+
         let row = client
             .query_opt(
                 r#"
                 SELECT 1 FROM information_schema.tables
-                WHERE table_schema = 'public'
-                AND table_name = $1
+                WHERE table_schema = $1
+                AND table_name = $2
                 "#,
-                &[&table_name],
+                &[&schema, &table],
             )
             .await
             .unwrap_or(None);
@@ -343,9 +460,7 @@ impl TableDeployer {
         // Parse all table definitions
         let mut tables = Vec::new();
         for file_path in &table_files {
-            if let Some(table_def) = self.parse_table_definition(file_path)? {
-                tables.push(table_def);
-            }
+            tables.extend(self.parse_table_definitions(file_path)?);
         }
 
         if tables.is_empty() {
@@ -361,6 +476,25 @@ impl TableDeployer {
             cause: e.to_string(),
         })?;
 
+        // Every FK target must be defined here, allow-listed as external, or already exist
+        let external_tables = DependencyAnalyzer::load_external_tables(tables_dir);
+        let mut missing = Vec::new();
+        for (table, dep) in self.unresolved_dependencies(&ordered_tables, &external_tables) {
+            if !self.table_exists(&client, &dep).await? {
+                missing.push(format!("{} -> {}", table, dep));
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(GatewayError::SchemaExtractionFailed {
+                cause: format!(
+                    "Foreign keys reference undefined tables: {}. Define them in tables/ or list them in tables/{} if they are managed externally.",
+                    missing.join(", "),
+                    EXTERNAL_TABLES_FILE
+                ),
+            });
+        }
+
         // Get already deployed tables
         let deployed = self.get_deployed_tables(&client).await?;
 
@@ -463,6 +597,87 @@ impl Default for TableDeployer {
     }
 }
 
+/// Split a file into one chunk per `CREATE TABLE` statement
+///
+/// Statements after a `CREATE TABLE` belong to it until the next one; anything
+/// before the first belongs to the first.
+fn split_table_statements(content: &str) -> Vec<String> {
+    let create_table = regex::Regex::new(
+        r"(?i)^create\s+(?:(?:global|local)\s+)?(?:(?:temp|temporary|unlogged)\s+)?table\b",
+    )
+    .unwrap();
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut preamble = String::new();
+    for statement in split_statements(content) {
+        if create_table.is_match(&statement_code(&statement)) {
+            chunks.push(std::mem::take(&mut preamble) + &statement);
+        } else if let Some(last) = chunks.last_mut() {
+            last.push_str(&statement);
+        } else {
+            preamble.push_str(&statement);
+        }
+    }
+
+    chunks
+}
+
+/// Name of the first `CREATE TABLE ... AS` (query or `EXECUTE`) statement, if any
+fn create_table_as(content: &str) -> Option<String> {
+    // An optional column name list, then AS; a real definition is followed by ';' instead
+    let ctas = regex::Regex::new(
+        r#"(?is)^create\s+(?:(?:global|local)\s+)?(?:(?:temp|temporary|unlogged)\s+)?table\s+(?:if\s+not\s+exists\s+)?([\w."]+)\s*(?:\([^()]*\)\s*)?as\b"#,
+    )
+    .unwrap();
+
+    split_statements(content)
+        .iter()
+        .find_map(|statement| ctas.captures(&statement_code(statement)).map(|caps| caps[1].to_string()))
+}
+
+/// Statement text without its `--` comment lines, leading whitespace trimmed
+fn statement_code(statement: &str) -> String {
+    statement
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("--"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_start()
+        .to_string()
+}
+
+/// Split on semicolons outside quotes and line comments, keeping the text of each statement
+fn split_statements(content: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_comment = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        current.push(c);
+        if in_comment {
+            in_comment = c != '\n';
+            continue;
+        }
+        match (c, quote) {
+            (_, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('\'' | '"', None) => quote = Some(c),
+            ('-', None) if chars.peek() == Some(&'-') => in_comment = true,
+            (';', None) => {
+                statements.push(std::mem::take(&mut current));
+            }
+            _ => {}
+        }
+    }
+    if !current.trim().is_empty() {
+        statements.push(current);
+    }
+
+    statements
+}
+
 fn compute_checksum(content: &str) -> String {
     // Normalize: remove comments, collapse whitespace, lowercase
     let single_line_re = regex::Regex::new(r"--[^\n]*").unwrap();
@@ -482,6 +697,7 @@ fn compute_checksum(content: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schema::test_db::test_pool;
     use tempfile::TempDir;
 
     #[test]
@@ -522,7 +738,9 @@ CREATE TABLE users (
 "#;
         fs::write(&file_path, content).unwrap();
 
-        let table_def = deployer.parse_table_definition(&file_path).unwrap().unwrap();
+        let table_defs = deployer.parse_table_definitions(&file_path).unwrap();
+        assert_eq!(table_defs.len(), 1);
+        let table_def = &table_defs[0];
         assert_eq!(table_def.name, "users");
         assert!(table_def.depends_on.is_empty());
     }
@@ -542,7 +760,9 @@ CREATE TABLE posts (
 "#;
         fs::write(&file_path, content).unwrap();
 
-        let table_def = deployer.parse_table_definition(&file_path).unwrap().unwrap();
+        let table_defs = deployer.parse_table_definitions(&file_path).unwrap();
+        assert_eq!(table_defs.len(), 1);
+        let table_def = &table_defs[0];
         assert_eq!(table_def.name, "posts");
         assert!(table_def.depends_on.contains(&"users".to_string()));
     }
@@ -587,6 +807,36 @@ CREATE TABLE posts (
         assert!(post_idx < comment_idx);
     }
 
+    #[test]
+    fn test_case_only_name_collision() {
+        let temp_dir = TempDir::new().unwrap();
+        let deployer = TableDeployer::new();
+
+        fs::write(
+            temp_dir.path().join("Users.pssql"),
+            "CREATE TABLE Users (id SERIAL PRIMARY KEY);",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("users.pssql"),
+            "CREATE TABLE users (id SERIAL PRIMARY KEY, email TEXT);",
+        )
+        .unwrap();
+
+        let tables: Vec<TableDefinition> = deployer
+            .find_table_files(temp_dir.path())
+            .unwrap()
+            .iter()
+            .flat_map(|f| deployer.parse_table_definitions(f).unwrap())
+            .collect();
+        assert_eq!(tables.len(), 2);
+
+        let err = deployer.order_by_dependencies(tables).unwrap_err().to_string();
+        assert!(err.contains("collision"));
+        assert!(err.contains("Users.pssql"));
+        assert!(err.contains("users.pssql"));
+    }
+
     #[test]
     fn test_circular_dependency_detection() {
         let deployer = TableDeployer::new();
@@ -613,6 +863,56 @@ CREATE TABLE posts (
         assert!(result.unwrap_err().to_string().contains("Circular dependency"));
     }
 
+    #[test]
+    fn test_circular_dependency_names_cycle_path() {
+        let deployer = TableDeployer::new();
+        let table = |name: &str, deps: &[&str]| TableDefinition {
+            name: name.to_string(),
+            file_path: PathBuf::from(format!("{}.pssql", name)),
+            sql: format!("CREATE TABLE {}...", name),
+            checksum: name.to_string(),
+            depends_on: deps.iter().map(|d| d.to_string()).collect(),
+        };
+
+        // invoices only depends on the cycle, it is not part of it
+        let tables = vec![
+            table("orders", &["customers"]),
+            table("customers", &["regions"]),
+            table("regions", &["orders"]),
+            table("invoices", &["orders"]),
+        ];
+
+        let err = deployer.order_by_dependencies(tables).unwrap_err().to_string();
+        assert!(
+            err.contains("customers -> regions -> orders -> customers"),
+            "{}",
+            err
+        );
+        assert!(!err.contains("-> invoices"), "{}", err);
+    }
+
+    #[test]
+    fn test_external_tables_not_unresolved() {
+        let deployer = TableDeployer::new();
+
+        let tables = vec![TableDefinition {
+            name: "invoices".to_string(),
+            file_path: PathBuf::from("invoices.pssql"),
+            sql: "CREATE TABLE invoices...".to_string(),
+            checksum: "abc".to_string(),
+            depends_on: vec!["accounts".to_string(), "owners".to_string()],
+        }];
+
+        let unresolved = deployer.unresolved_dependencies(&tables, &["accounts".to_string()]);
+        assert_eq!(
+            unresolved,
+            vec![("invoices".to_string(), "owners".to_string())]
+        );
+
+        let external = vec!["accounts".to_string(), "owners".to_string()];
+        assert!(deployer.unresolved_dependencies(&tables, &external).is_empty());
+    }
+
     #[test]
     fn test_checksum_normalization() {
         let sql1 = "CREATE TABLE users (id INT);";
@@ -622,4 +922,119 @@ CREATE TABLE posts (
         assert_eq!(compute_checksum(sql1), compute_checksum(sql2));
         assert_eq!(compute_checksum(sql1), compute_checksum(sql3));
     }
+
+    const TWO_TABLE_FILE: &str = r#"
+-- Line items reference their order, which is defined below
+CREATE TABLE multi_file_items (
+    id SERIAL PRIMARY KEY,
+    order_id INT NOT NULL REFERENCES multi_file_orders(id),
+    note TEXT DEFAULT 'a; b'
+);
+CREATE INDEX idx_multi_file_items_order ON multi_file_items (order_id);
+
+CREATE TABLE multi_file_orders (
+    id SERIAL PRIMARY KEY,
+    placed_at TIMESTAMPTZ DEFAULT NOW()
+);
+"#;
+
+    #[test]
+    fn test_parse_multiple_tables_in_one_file() {
+        let deployer = TableDeployer::new();
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("orders.pssql");
+        fs::write(&file_path, TWO_TABLE_FILE).unwrap();
+
+        let tables = deployer.parse_table_definitions(&file_path).unwrap();
+        assert_eq!(tables.len(), 2);
+
+        assert_eq!(tables[0].name, "multi_file_items");
+        assert!(tables[0].sql.starts_with("-- Line items"));
+        assert!(tables[0].sql.contains("'a; b'"));
+        assert!(tables[0].sql.contains("CREATE INDEX idx_multi_file_items_order"));
+        assert_eq!(tables[0].depends_on, vec!["multi_file_orders"]);
+
+        assert_eq!(tables[1].name, "multi_file_orders");
+        assert!(tables[1].sql.starts_with("CREATE TABLE multi_file_orders"));
+        assert!(!tables[1].sql.contains("multi_file_items"));
+        assert_ne!(tables[0].checksum, tables[1].checksum);
+        assert_eq!(tables[1].checksum, compute_checksum(&tables[1].sql));
+
+        let order: Vec<String> = deployer
+            .order_by_dependencies(tables)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(order, vec!["multi_file_orders", "multi_file_items"]);
+    }
+
+    #[test]
+    fn test_create_table_as_rejected() {
+        let deployer = TableDeployer::new();
+        let temp_dir = TempDir::new().unwrap();
+
+        let file_path = temp_dir.path().join("active_users.pssql");
+        fs::write(
+            &file_path,
+            "-- Snapshot of active users\nCREATE TABLE active_users AS\n    SELECT id, email FROM users WHERE active;\n",
+        )
+        .unwrap();
+        let err = deployer.parse_table_definitions(&file_path).unwrap_err().to_string();
+        assert!(err.contains("active_users.pssql"), "{}", err);
+        assert!(err.contains("CREATE TABLE active_users AS"), "{}", err);
+        assert!(err.contains("migration"), "{}", err);
+
+        // With a column name list, and after a regular table in the same file
+        fs::write(
+            &file_path,
+            "CREATE TABLE users (id SERIAL PRIMARY KEY, email TEXT);\n\
+             CREATE TABLE IF NOT EXISTS user_emails (id, email) AS TABLE users;\n",
+        )
+        .unwrap();
+        let err = deployer.parse_table_definitions(&file_path).unwrap_err().to_string();
+        assert!(err.contains("CREATE TABLE user_emails AS"), "{}", err);
+
+        // Regular definitions are not mistaken for CTAS
+        assert_eq!(create_table_as(TWO_TABLE_FILE), None);
+        assert_eq!(create_table_as("CREATE TABLE t (id INT) WITH (fillfactor = 70);"), None);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_creates_every_table_in_a_file() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let deployer = TableDeployer::new();
+        deployer.ensure_tracking_table(&pool, "test").await.unwrap();
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS multi_file_items, multi_file_orders;
+                 DELETE FROM _stonescriptdb_gateway_tables WHERE table_name LIKE 'multi_file_%';",
+            )
+            .await
+            .unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("orders.pssql"), TWO_TABLE_FILE).unwrap();
+
+        let created = deployer.deploy_tables(&pool, "test", temp_dir.path()).await.unwrap();
+        assert_eq!(created, 2);
+
+        let tables = deployer.list_tables(&pool, "test").await.unwrap();
+        assert!(tables.contains(&"multi_file_items".to_string()));
+        assert!(tables.contains(&"multi_file_orders".to_string()));
+
+        // Each table is tracked with its own checksum, so a redeploy skips both
+        assert_eq!(deployer.deploy_tables(&pool, "test", temp_dir.path()).await.unwrap(), 0);
+
+        client
+            .batch_execute(
+                "DROP TABLE multi_file_items, multi_file_orders;
+                 DELETE FROM _stonescriptdb_gateway_tables WHERE table_name LIKE 'multi_file_%';",
+            )
+            .await
+            .unwrap();
+    }
 }
@@ -0,0 +1,31 @@
+//! Live PostgreSQL helpers for tests
+//!
+//! Tests that need a server read its URL from TEST_DATABASE_URL and skip
+//! themselves when it is not set.
+
+use deadpool_postgres::Pool;
+
+/// Serializes tests that share the live database's tracking table
+pub(crate) static DB_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// TEST_DATABASE_URL, or `None` (after noting the skip) when it is not set
+pub(crate) fn test_database_url() -> Option<String> {
+    let url = std::env::var("TEST_DATABASE_URL").ok();
+    if url.is_none() {
+        eprintln!("TEST_DATABASE_URL not set, skipping");
+    }
+    url
+}
+
+/// Pool on the TEST_DATABASE_URL database, or `None` when it is not set
+pub(crate) fn test_pool() -> Option<Pool> {
+    let config = deadpool_postgres::Config {
+        url: Some(test_database_url()?),
+        ..Default::default()
+    };
+    Some(
+        config
+            .create_pool(Some(deadpool_postgres::Runtime::Tokio1), tokio_postgres::NoTls)
+            .unwrap(),
+    )
+}
@@ -0,0 +1,301 @@
+//! Tracking table repair
+//!
+//! The deployers skip objects recorded in `_stonescriptdb_gateway_tables`,
+//! `_stonescriptdb_gateway_types` and `_stonescriptdb_gateway_functions`. When
+//! objects are created or dropped by hand those records no longer match the
+//! database, so a dropped table is never recreated and a hand-edited function
+//! is never redeployed. Repair re-scans the database and rewrites the records.
+
+use crate::error::{GatewayError, Result};
+use crate::schema::dependency::DEFAULT_SCHEMA;
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+/// What a repair changed in the tracking tables
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrackingRepairReport {
+    /// Tracked tables that no longer exist (records removed)
+    pub tables_removed: Vec<String>,
+    /// Tracked types that no longer exist (records removed)
+    pub types_removed: Vec<String>,
+    /// Tracked functions that no longer exist (records removed)
+    pub functions_removed: Vec<String>,
+    /// Functions whose checksum was recomputed from their live definition
+    /// (`pg_get_functiondef`); the next deploy replaces them from their files
+    pub functions_rechecksummed: Vec<String>,
+}
+
+impl TrackingRepairReport {
+    /// True when no stale record was removed
+    pub fn is_empty(&self) -> bool {
+        self.tables_removed.is_empty()
+            && self.types_removed.is_empty()
+            && self.functions_removed.is_empty()
+    }
+}
+
+pub struct TrackingRepair;
+
+impl TrackingRepair {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Reconcile the tracking tables of a database with the objects it contains
+    ///
+    /// Tracking tables that do not exist yet are left alone.
+    pub async fn repair(&self, pool: &Pool, database: &str) -> Result<TrackingRepairReport> {
+        let mut client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+        let query_failed = |e: tokio_postgres::Error| GatewayError::QueryFailed {
+            database: database.to_string(),
+            function: "repair tracking".to_string(),
+            cause: e.to_string(),
+        };
+
+        let transaction = client.transaction().await.map_err(query_failed)?;
+        let mut report = TrackingRepairReport::default();
+
+        if tracking_table_exists(&transaction, "_stonescriptdb_gateway_tables").await.map_err(query_failed)? {
+            let rows = transaction
+                .query("SELECT table_name FROM _stonescriptdb_gateway_tables ORDER BY table_name", &[])
+                .await
+                .map_err(query_failed)?;
+            for row in rows {
+                let table_name: String = row.get(0);
+                let (schema, table) = table_name.split_once('.').unwrap_or((DEFAULT_SCHEMA, &table_name));
+                let exists = transaction
+                    .query_opt(
+                        "SELECT 1 FROM information_schema.tables WHERE table_schema = $1 AND table_name = $2",
+                        &[&schema, &table],
+                    )
+                    .await
+                    .map_err(query_failed)?
+                    .is_some();
+                if !exists {
+                    transaction
+                        .execute("DELETE FROM _stonescriptdb_gateway_tables WHERE table_name = $1", &[&table_name])
+                        .await
+                        .map_err(query_failed)?;
+                    report.tables_removed.push(table_name);
+                }
+            }
+        }
+
+        if tracking_table_exists(&transaction, "_stonescriptdb_gateway_types").await.map_err(query_failed)? {
+            // Custom types are deployed to the public schema
+            let rows = transaction
+                .query(
+                    r#"
+                    SELECT g.type_name FROM _stonescriptdb_gateway_types g
+                    WHERE NOT EXISTS (
+                        SELECT 1 FROM pg_type t
+                        JOIN pg_namespace n ON t.typnamespace = n.oid
+                        WHERE t.typname = g.type_name AND n.nspname = 'public'
+                    )
+                    ORDER BY g.type_name
+                    "#,
+                    &[],
+                )
+                .await
+                .map_err(query_failed)?;
+            for row in rows {
+                let type_name: String = row.get(0);
+                transaction
+                    .execute("DELETE FROM _stonescriptdb_gateway_types WHERE type_name = $1", &[&type_name])
+                    .await
+                    .map_err(query_failed)?;
+                report.types_removed.push(type_name);
+            }
+        }
+
+        if tracking_table_exists(&transaction, "_stonescriptdb_gateway_functions").await.map_err(query_failed)? {
+            let rows = transaction
+                .query(
+                    r#"
+                    SELECT function_name, param_types
+                    FROM _stonescriptdb_gateway_functions
+                    ORDER BY function_name, param_types
+                    "#,
+                    &[],
+                )
+                .await
+                .map_err(query_failed)?;
+            for row in rows {
+                let name: String = row.get(0);
+                let param_types: Vec<String> = row.get(1);
+                let signature = format!("{}({})", name, param_types.join(", "));
+
+                // to_regprocedure yields NULL for functions that do not exist
+                let definition: Option<String> = transaction
+                    .query_one("SELECT pg_get_functiondef(to_regprocedure($1))", &[&signature])
+                    .await
+                    .map_err(query_failed)?
+                    .get(0);
+
+                match definition {
+                    None => {
+                        transaction
+                            .execute(
+                                "DELETE FROM _stonescriptdb_gateway_functions WHERE function_name = $1 AND param_types = $2",
+                                &[&name, &param_types],
+                            )
+                            .await
+                            .map_err(query_failed)?;
+                        report.functions_removed.push(signature);
+                    }
+                    Some(definition) => {
+                        transaction
+                            .execute(
+                                "UPDATE _stonescriptdb_gateway_functions SET body_checksum = $3 WHERE function_name = $1 AND param_types = $2",
+                                &[&name, &param_types, &definition_checksum(&definition)],
+                            )
+                            .await
+                            .map_err(query_failed)?;
+                        report.functions_rechecksummed.push(signature);
+                    }
+                }
+            }
+        }
+
+        transaction.commit().await.map_err(query_failed)?;
+
+        if report.is_empty() {
+            info!("Tracking tables of {} match the database", database);
+        } else {
+            warn!(
+                "Repaired tracking of {}: removed {} tables, {} types, {} functions",
+                database,
+                report.tables_removed.len(),
+                report.types_removed.len(),
+                report.functions_removed.len()
+            );
+        }
+
+        Ok(report)
+    }
+}
+
+impl Default for TrackingRepair {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn tracking_table_exists(
+    transaction: &tokio_postgres::Transaction<'_>,
+    table: &str,
+) -> std::result::Result<bool, tokio_postgres::Error> {
+    let row = transaction
+        .query_one("SELECT to_regclass($1) IS NOT NULL", &[&table])
+        .await?;
+    Ok(row.get(0))
+}
+
+fn definition_checksum(definition: &str) -> String {
+    hex::encode(Sha256::digest(definition.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::TableDeployer;
+    use crate::schema::test_db::{DB_LOCK, test_pool};
+    use std::fs;
+
+    async fn tracked_tables(pool: &Pool) -> Vec<String> {
+        pool.get()
+            .await
+            .unwrap()
+            .query(
+                "SELECT table_name FROM _stonescriptdb_gateway_tables WHERE table_name LIKE 'repair_test_%' ORDER BY 1",
+                &[],
+            )
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_dropped_table_removed_from_tracking() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let _guard = DB_LOCK.lock().await;
+
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                r#"
+                DROP TABLE IF EXISTS repair_test_kept, repair_test_dropped;
+                DROP FUNCTION IF EXISTS repair_test_fn(integer);
+                CREATE FUNCTION repair_test_fn(n integer) RETURNS integer AS $$ SELECT n $$ LANGUAGE sql;
+                "#,
+            )
+            .await
+            .unwrap();
+
+        let tables_dir = tempfile::TempDir::new().unwrap();
+        fs::write(tables_dir.path().join("kept.pssql"), "CREATE TABLE repair_test_kept (id SERIAL PRIMARY KEY);").unwrap();
+        fs::write(tables_dir.path().join("dropped.pssql"), "CREATE TABLE repair_test_dropped (id SERIAL PRIMARY KEY);").unwrap();
+        TableDeployer::new().deploy_tables(&pool, "test", tables_dir.path()).await.unwrap();
+        assert_eq!(tracked_tables(&pool).await, vec!["repair_test_dropped", "repair_test_kept"]);
+
+        // Tracked functions: one that exists, one dropped by hand
+        crate::schema::FunctionDeployer::new().ensure_tracking_table(&pool, "test").await.unwrap();
+        client
+            .batch_execute(
+                r#"
+                DELETE FROM _stonescriptdb_gateway_functions WHERE function_name LIKE 'repair_test_%';
+                INSERT INTO _stonescriptdb_gateway_functions
+                    (function_name, signature, param_types, return_type, body_checksum, source_file)
+                VALUES
+                    ('repair_test_fn', 'repair_test_fn(INTEGER)', ARRAY['INTEGER'], 'integer', 'file', 'fn.pssql'),
+                    ('repair_test_gone', 'repair_test_gone(TEXT)', ARRAY['TEXT'], 'text', 'file', 'gone.pssql');
+                DROP TABLE repair_test_dropped;
+                "#,
+            )
+            .await
+            .unwrap();
+
+        let report = TrackingRepair::new().repair(&pool, "test").await.unwrap();
+
+        assert!(report.tables_removed.contains(&"repair_test_dropped".to_string()));
+        assert!(!report.tables_removed.contains(&"repair_test_kept".to_string()));
+        assert_eq!(tracked_tables(&pool).await, vec!["repair_test_kept"]);
+
+        assert!(report.functions_removed.contains(&"repair_test_gone(TEXT)".to_string()));
+        assert!(report.functions_rechecksummed.contains(&"repair_test_fn(INTEGER)".to_string()));
+        let checksum: String = client
+            .query_one(
+                "SELECT body_checksum FROM _stonescriptdb_gateway_functions WHERE function_name = 'repair_test_fn'",
+                &[],
+            )
+            .await
+            .unwrap()
+            .get(0);
+        assert_ne!(checksum, "file");
+
+        // Redeploying recreates the dropped table now that it is no longer tracked
+        TableDeployer::new().deploy_tables(&pool, "test", tables_dir.path()).await.unwrap();
+        assert_eq!(tracked_tables(&pool).await, vec!["repair_test_dropped", "repair_test_kept"]);
+
+        client
+            .batch_execute(
+                r#"
+                DELETE FROM _stonescriptdb_gateway_tables WHERE table_name LIKE 'repair_test_%';
+                DELETE FROM _stonescriptdb_gateway_functions WHERE function_name LIKE 'repair_test_%';
+                DROP TABLE repair_test_kept, repair_test_dropped;
+                DROP FUNCTION repair_test_fn(integer);
+                "#,
+            )
+            .await
+            .unwrap();
+    }
+}
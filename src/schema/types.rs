@@ -7,9 +7,17 @@
 //! - SAFE: Can be done without data loss
 //! - DATALOSS: May truncate or lose data
 //! - INCOMPATIBLE: Cannot be cast at all
+//!
+//! Rules for domain or vendor types can be added from a rules file
+//! (`TYPE_RULES_FILE`), loaded once at startup.
 
 use serde::Serialize;
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Rules from the rules file, applied by every `TypeChecker::new`
+static CUSTOM_RULES: OnceLock<Vec<CustomTypeRule>> = OnceLock::new();
 
 /// Result of a type compatibility check
 #[derive(Debug, Clone, PartialEq, Serialize)]
@@ -30,12 +38,89 @@ impl TypeCompatibility {
     }
 }
 
+/// A safe widening rule in the compatibility matrix
+#[derive(Debug, Clone, Serialize)]
+pub struct SafeWidening {
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+/// A dataloss narrowing rule in the compatibility matrix
+#[derive(Debug, Clone, Serialize)]
+pub struct DataLossNarrowing {
+    pub from: String,
+    pub to: String,
+    pub reason: String,
+}
+
+/// A user-supplied rule: `from_type,to_type,classification[,reason]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomTypeRule {
+    pub from: String,
+    pub to: String,
+    /// Safe or DataLoss
+    pub compatibility: TypeCompatibility,
+}
+
+impl CustomTypeRule {
+    /// Parse a rules file; blank lines and `#` comments are ignored
+    ///
+    /// The classification is `safe` or `dataloss`; a dataloss rule without a
+    /// reason gets a generic one.
+    pub fn parse_rules(contents: &str) -> anyhow::Result<Vec<CustomTypeRule>> {
+        let mut rules = Vec::new();
+
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.splitn(4, ',').map(str::trim).collect();
+            if fields.len() < 3 || fields[0].is_empty() || fields[1].is_empty() {
+                anyhow::bail!(
+                    "line {}: expected from_type,to_type,classification[,reason], got '{}'",
+                    i + 1,
+                    line
+                );
+            }
+
+            let (from, to) = (fields[0].to_string(), fields[1].to_string());
+            let reason = fields.get(3).filter(|r| !r.is_empty());
+            let compatibility = match fields[2].to_ascii_lowercase().as_str() {
+                "safe" => TypeCompatibility::Safe,
+                "dataloss" => TypeCompatibility::DataLoss {
+                    reason: reason
+                        .map(|r| r.to_string())
+                        .unwrap_or_else(|| format!("{} -> {} may lose data (custom rule)", from, to)),
+                },
+                other => anyhow::bail!(
+                    "line {}: unknown classification '{}' (expected safe or dataloss)",
+                    i + 1,
+                    other
+                ),
+            };
+
+            rules.push(CustomTypeRule { from, to, compatibility });
+        }
+
+        Ok(rules)
+    }
+}
+
+/// Structured form of the compatibility matrix (for tooling)
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeMatrix {
+    pub safe_widenings: Vec<SafeWidening>,
+    pub dataloss_narrowings: Vec<DataLossNarrowing>,
+}
+
 /// Checks type compatibility for PostgreSQL column changes
 pub struct TypeChecker {
     /// Widening rules: from_type -> list of safe target types
-    safe_widenings: HashMap<&'static str, Vec<&'static str>>,
+    safe_widenings: HashMap<String, Vec<String>>,
     /// Narrowing rules: from_type -> (to_type, reason)
-    dataloss_narrowings: HashMap<(&'static str, &'static str), &'static str>,
+    dataloss_narrowings: HashMap<(String, String), String>,
 }
 
 impl TypeChecker {
@@ -160,6 +245,51 @@ impl TypeChecker {
         dataloss_narrowings.insert(("TEXT", "JSON"), "May fail: TEXT must contain valid JSON");
         dataloss_narrowings.insert(("TEXT", "JSONB"), "May fail: TEXT must contain valid JSON");
 
+        // ═══════════════════════════════════════════════════════════════
+        // NETWORK ADDRESS TYPES
+        // ═══════════════════════════════════════════════════════════════
+
+        // CIDR -> INET (safe, every network is a valid INET value)
+        safe_widenings.insert("CIDR", vec!["INET", "TEXT", "VARCHAR"]);
+
+        // INET -> TEXT/VARCHAR (safe, just string representation)
+        safe_widenings.insert("INET", vec!["TEXT", "VARCHAR"]);
+
+        // INET -> CIDR = DATALOSS (host bits must be zero, host loses its implicit /32)
+        dataloss_narrowings.insert(("INET", "CIDR"), "May fail: INET host addresses lose their implicit /32 and must have no bits set right of the netmask");
+
+        // TEXT -> INET/CIDR = may fail if not valid
+        dataloss_narrowings.insert(("TEXT", "INET"), "May fail if not valid: TEXT must contain a valid IP address");
+        dataloss_narrowings.insert(("VARCHAR", "INET"), "May fail if not valid: VARCHAR must contain a valid IP address");
+        dataloss_narrowings.insert(("TEXT", "CIDR"), "May fail if not valid: TEXT must contain a valid network address");
+        dataloss_narrowings.insert(("VARCHAR", "CIDR"), "May fail if not valid: VARCHAR must contain a valid network address");
+
+        // MACADDR -> MACADDR8 (safe, 6-byte addresses are padded to EUI-64)
+        safe_widenings.insert("MACADDR", vec!["MACADDR8", "TEXT", "VARCHAR"]);
+        safe_widenings.insert("MACADDR8", vec!["TEXT", "VARCHAR"]);
+
+        // MACADDR8 -> MACADDR = DATALOSS (only FF:FE-padded addresses convert)
+        dataloss_narrowings.insert(("MACADDR8", "MACADDR"), "May fail: only MACADDR8 values with FF:FE in bytes 4-5 fit in MACADDR");
+
+        // ═══════════════════════════════════════════════════════════════
+        // MONEY / BYTEA
+        // ═══════════════════════════════════════════════════════════════
+
+        // MONEY -> NUMERIC/TEXT (safe, exact value or formatted string)
+        safe_widenings.insert("MONEY", vec!["NUMERIC", "DECIMAL", "TEXT", "VARCHAR"]);
+
+        // NUMERIC -> MONEY = DATALOSS (rounded to the locale's fractional digits)
+        dataloss_narrowings.insert(("NUMERIC", "MONEY"), "May lose precision: MONEY rounds to the lc_monetary fractional digits");
+        dataloss_narrowings.insert(("DECIMAL", "MONEY"), "May lose precision: MONEY rounds to the lc_monetary fractional digits");
+
+        // TEXT -> MONEY = may fail, parsing depends on lc_monetary
+        dataloss_narrowings.insert(("TEXT", "MONEY"), "May fail: TEXT must be a valid amount for the lc_monetary locale");
+        dataloss_narrowings.insert(("VARCHAR", "MONEY"), "May fail: VARCHAR must be a valid amount for the lc_monetary locale");
+
+        // BYTEA <-> TEXT = DATALOSS (both directions depend on encoding/escape format)
+        dataloss_narrowings.insert(("BYTEA", "TEXT"), "Encoding dependent: BYTEA is cast to its hex/escape representation, not decoded");
+        dataloss_narrowings.insert(("TEXT", "BYTEA"), "May fail: TEXT must be valid hex or escape format");
+
         // ═══════════════════════════════════════════════════════════════
         // SERIAL types (just aliases for INTEGER + sequence)
         // ═══════════════════════════════════════════════════════════════
@@ -168,10 +298,99 @@ impl TypeChecker {
         safe_widenings.insert("SMALLSERIAL", vec!["SERIAL", "BIGSERIAL", "SMALLINT", "INTEGER", "BIGINT"]);
         safe_widenings.insert("BIGSERIAL", vec!["BIGINT", "NUMERIC"]);
 
-        Self {
-            safe_widenings,
-            dataloss_narrowings,
+        let mut checker = Self {
+            safe_widenings: safe_widenings
+                .into_iter()
+                .map(|(from, to)| (from.to_string(), to.into_iter().map(String::from).collect()))
+                .collect(),
+            dataloss_narrowings: dataloss_narrowings
+                .into_iter()
+                .map(|((from, to), reason)| ((from.to_string(), to.to_string()), reason.to_string()))
+                .collect(),
+        };
+
+        // ═══════════════════════════════════════════════════════════════
+        // FULL-TEXT SEARCH, EXTENSION AND RANGE TYPES
+        // ═══════════════════════════════════════════════════════════════
+
+        // Text representations are safe; parsing text back may fail
+        for (specialized, literal) in [
+            ("TSVECTOR", "a valid tsvector literal (it is not run through to_tsvector)"),
+            ("TSQUERY", "a valid tsquery literal"),
+            ("LTREE", "a valid label path"),
+            ("HSTORE", "valid hstore key/value pairs"),
+            ("INT4RANGE", "a valid range literal"),
+            ("INT8RANGE", "a valid range literal"),
+            ("NUMRANGE", "a valid range literal"),
+            ("TSRANGE", "a valid range literal"),
+            ("TSTZRANGE", "a valid range literal"),
+            ("DATERANGE", "a valid range literal"),
+        ] {
+            checker.add_safe_widening(specialized, "TEXT");
+            checker.add_safe_widening(specialized, "VARCHAR");
+            checker.add_dataloss_narrowing("TEXT", specialized, &format!("May fail: TEXT must contain {}", literal));
         }
+
+        // HSTORE -> JSON/JSONB (safe, casts provided by the hstore extension)
+        checker.add_safe_widening("HSTORE", "JSON");
+        checker.add_safe_widening("HSTORE", "JSONB");
+
+        if let Some(rules) = CUSTOM_RULES.get() {
+            checker.apply_rules(rules);
+        }
+
+        checker
+    }
+
+    /// Load the rules file applied by every later `TypeChecker::new`
+    ///
+    /// Returns the number of rules; the file can only be loaded once.
+    pub fn load_rules_file(path: &Path) -> anyhow::Result<usize> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read type rules file {:?}: {}", path, e))?;
+        let rules = CustomTypeRule::parse_rules(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid type rules file {:?}: {}", path, e))?;
+        let count = rules.len();
+
+        CUSTOM_RULES
+            .set(rules)
+            .map_err(|_| anyhow::anyhow!("Type rules were already loaded"))?;
+        Ok(count)
+    }
+
+    /// Merge custom rules into the matrix, replacing built-in ones for the same types
+    pub fn apply_rules(&mut self, rules: &[CustomTypeRule]) {
+        for rule in rules {
+            match &rule.compatibility {
+                TypeCompatibility::DataLoss { reason } => {
+                    self.add_dataloss_narrowing(&rule.from, &rule.to, reason)
+                }
+                _ => self.add_safe_widening(&rule.from, &rule.to),
+            }
+        }
+    }
+
+    /// Register an additional safe change, e.g. for a domain or vendor type
+    pub fn add_safe_widening(&mut self, from_type: &str, to_type: &str) {
+        let from = self.extract_base_type(&self.normalize_type(from_type));
+        let to = self.extract_base_type(&self.normalize_type(to_type));
+
+        self.dataloss_narrowings.remove(&(from.clone(), to.clone()));
+        let targets = self.safe_widenings.entry(from).or_default();
+        if !targets.contains(&to) {
+            targets.push(to);
+        }
+    }
+
+    /// Register an additional change that may lose data
+    pub fn add_dataloss_narrowing(&mut self, from_type: &str, to_type: &str, reason: &str) {
+        let from = self.extract_base_type(&self.normalize_type(from_type));
+        let to = self.extract_base_type(&self.normalize_type(to_type));
+
+        if let Some(targets) = self.safe_widenings.get_mut(&from) {
+            targets.retain(|t| t != &to);
+        }
+        self.dataloss_narrowings.insert((from, to), reason.to_string());
     }
 
     /// Check if a type change is compatible
@@ -184,6 +403,31 @@ impl TypeChecker {
             return TypeCompatibility::Identical;
         }
 
+        // Arrays are compatible when their element types are
+        let (from_element, from_is_array) = self.split_array_type(&from_normalized);
+        let (to_element, to_is_array) = self.split_array_type(&to_normalized);
+
+        if from_is_array || to_is_array {
+            if from_is_array != to_is_array {
+                return TypeCompatibility::Incompatible {
+                    reason: format!(
+                        "Cannot convert between array and scalar: {} -> {}",
+                        from_type, to_type
+                    ),
+                };
+            }
+
+            return match self.check_compatibility(&from_element, &to_element) {
+                TypeCompatibility::DataLoss { reason } => TypeCompatibility::DataLoss {
+                    reason: format!("Array elements: {}", reason),
+                },
+                TypeCompatibility::Incompatible { reason } => TypeCompatibility::Incompatible {
+                    reason: format!("Array elements: {}", reason),
+                },
+                result => result,
+            };
+        }
+
         // Check for VARCHAR length changes
         if let Some(result) = self.check_varchar_change(&from_normalized, &to_normalized) {
             return result;
@@ -198,22 +442,22 @@ impl TypeChecker {
         let from_base = self.extract_base_type(&from_normalized);
         let to_base = self.extract_base_type(&to_normalized);
 
-        if let Some(safe_targets) = self.safe_widenings.get(from_base.as_str()) {
-            if safe_targets.iter().any(|t| *t == to_base) {
+        if let Some(safe_targets) = self.safe_widenings.get(&from_base) {
+            if safe_targets.contains(&to_base) {
                 return TypeCompatibility::Safe;
             }
         }
 
         // Check known dataloss narrowings
-        if let Some(reason) = self.dataloss_narrowings.get(&(from_base.as_str(), to_base.as_str())) {
+        if let Some(reason) = self.dataloss_narrowings.get(&(from_base.clone(), to_base.clone())) {
             return TypeCompatibility::DataLoss {
-                reason: reason.to_string(),
+                reason: reason.clone(),
             };
         }
 
         // Check reverse (if to->from is safe, then from->to is dataloss)
-        if let Some(safe_targets) = self.safe_widenings.get(to_base.as_str()) {
-            if safe_targets.iter().any(|t| *t == from_base) {
+        if let Some(safe_targets) = self.safe_widenings.get(&to_base) {
+            if safe_targets.contains(&from_base) {
                 return TypeCompatibility::DataLoss {
                     reason: format!("Narrowing from {} to {} may lose data", from_type, to_type),
                 };
@@ -227,19 +471,39 @@ impl TypeChecker {
     }
 
     /// Normalize type name for comparison
+    ///
+    /// Aliases are replaced as whole words, so names like INT4RANGE are kept.
     fn normalize_type(&self, type_name: &str) -> String {
-        type_name
+        let aliases = regex::Regex::new(r"\b(?:INT4|INT8|INT2|FLOAT4|FLOAT8|BOOL)\b").unwrap();
+        let type_name = type_name
             .trim()
             .to_uppercase()
             .replace("CHARACTER VARYING", "VARCHAR")
-            .replace("INT4", "INTEGER")
-            .replace("INT8", "BIGINT")
-            .replace("INT2", "SMALLINT")
-            .replace("FLOAT4", "REAL")
-            .replace("FLOAT8", "DOUBLE PRECISION")
-            .replace("BOOL", "BOOLEAN")
             .replace("TIMESTAMP WITHOUT TIME ZONE", "TIMESTAMP")
-            .replace("TIMESTAMP WITH TIME ZONE", "TIMESTAMPTZ")
+            .replace("TIMESTAMP WITH TIME ZONE", "TIMESTAMPTZ");
+
+        aliases
+            .replace_all(&type_name, |caps: &regex::Captures| match &caps[0] {
+                "INT4" => "INTEGER",
+                "INT8" => "BIGINT",
+                "INT2" => "SMALLINT",
+                "FLOAT4" => "REAL",
+                "FLOAT8" => "DOUBLE PRECISION",
+                _ => "BOOLEAN",
+            })
+            .into_owned()
+    }
+
+    /// Split an array type into its element type (e.g., INTEGER[][] -> INTEGER)
+    ///
+    /// PostgreSQL does not enforce array dimensions, so INTEGER[] and INTEGER[][]
+    /// share the same element type and are treated alike.
+    fn split_array_type(&self, type_name: &str) -> (String, bool) {
+        let re = regex::Regex::new(r"^(.+?)\s*(?:(?:\[\s*\d*\s*\]\s*)+|\s+ARRAY)$").unwrap();
+        match re.captures(type_name) {
+            Some(caps) => (caps[1].trim().to_string(), true),
+            None => (type_name.to_string(), false),
+        }
     }
 
     /// Extract base type without parameters (e.g., VARCHAR(100) -> VARCHAR)
@@ -348,6 +612,40 @@ impl TypeChecker {
         }
     }
 
+    /// Get the compatibility matrix as structured data, sorted by type name
+    pub fn matrix(&self) -> TypeMatrix {
+        let mut safe_widenings: Vec<SafeWidening> = self
+            .safe_widenings
+            .iter()
+            .map(|(from, to_list)| SafeWidening {
+                from: from.to_string(),
+                to: to_list.iter().map(|t| t.to_string()).collect(),
+            })
+            .collect();
+        safe_widenings.sort_by(|a, b| a.from.cmp(&b.from));
+
+        let mut dataloss_narrowings: Vec<DataLossNarrowing> = self
+            .dataloss_narrowings
+            .iter()
+            .map(|((from, to), reason)| DataLossNarrowing {
+                from: from.to_string(),
+                to: to.to_string(),
+                reason: reason.to_string(),
+            })
+            .collect();
+        dataloss_narrowings.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+
+        TypeMatrix {
+            safe_widenings,
+            dataloss_narrowings,
+        }
+    }
+
+    /// Get the compatibility matrix as JSON
+    pub fn matrix_json(&self) -> serde_json::Value {
+        serde_json::to_value(self.matrix()).unwrap_or(serde_json::Value::Null)
+    }
+
     /// Format the compatibility matrix as a readable string
     pub fn format_matrix(&self) -> String {
         let mut output = String::new();
@@ -360,7 +658,7 @@ impl TypeChecker {
         output.push_str("───────────────────────────────────────────────────────────────\n");
 
         let mut safe_entries: Vec<_> = self.safe_widenings.iter().collect();
-        safe_entries.sort_by_key(|(k, _)| *k);
+        safe_entries.sort_by_key(|(k, _)| k.as_str());
 
         for (from, to_list) in safe_entries {
             output.push_str(&format!("  {} → {}\n", from, to_list.join(", ")));
@@ -370,7 +668,7 @@ impl TypeChecker {
         output.push_str("───────────────────────────────────────────────────────────────\n");
 
         let mut dataloss_entries: Vec<_> = self.dataloss_narrowings.iter().collect();
-        dataloss_entries.sort_by_key(|((from, to), _)| (*from, *to));
+        dataloss_entries.sort_by_key(|((from, to), _)| (from.as_str(), to.as_str()));
 
         for ((from, to), reason) in dataloss_entries {
             output.push_str(&format!("  {} → {}\n    Reason: {}\n", from, to, reason));
@@ -491,4 +789,181 @@ mod tests {
         let result = checker.check_compatibility("BOOLEAN", "TEXT");
         assert!(matches!(result, TypeCompatibility::Incompatible { .. }));
     }
+
+    #[test]
+    fn test_array_element_widening() {
+        let checker = TypeChecker::new();
+
+        assert!(checker.check_compatibility("INTEGER[]", "BIGINT[]").is_safe());
+        assert!(checker.check_compatibility("VARCHAR(50)[]", "VARCHAR(100)[]").is_safe());
+        assert!(checker.check_compatibility("INTEGER[][]", "BIGINT[][]").is_safe());
+        assert!(checker.check_compatibility("INT4[]", "INTEGER ARRAY").is_safe());
+        assert_eq!(
+            checker.check_compatibility("INTEGER []", "INTEGER[]"),
+            TypeCompatibility::Identical
+        );
+    }
+
+    #[test]
+    fn test_array_element_narrowing() {
+        let checker = TypeChecker::new();
+
+        let result = checker.check_compatibility("BIGINT[]", "INTEGER[]");
+        assert!(matches!(result, TypeCompatibility::DataLoss { .. }));
+
+        let result = checker.check_compatibility("TEXT[][]", "VARCHAR(10)[][]");
+        assert!(matches!(result, TypeCompatibility::DataLoss { .. }));
+
+        let result = checker.check_compatibility("UUID[]", "INTEGER[]");
+        assert!(matches!(result, TypeCompatibility::Incompatible { .. }));
+    }
+
+    #[test]
+    fn test_array_scalar_incompatible() {
+        let checker = TypeChecker::new();
+
+        let result = checker.check_compatibility("INTEGER", "INTEGER[]");
+        assert!(matches!(result, TypeCompatibility::Incompatible { .. }));
+
+        let result = checker.check_compatibility("TEXT[]", "TEXT");
+        assert!(matches!(result, TypeCompatibility::Incompatible { .. }));
+    }
+
+    #[test]
+    fn test_network_types() {
+        let checker = TypeChecker::new();
+
+        assert!(checker.check_compatibility("CIDR", "INET").is_safe());
+        assert!(checker.check_compatibility("INET", "TEXT").is_safe());
+        assert!(checker.check_compatibility("CIDR", "VARCHAR(50)").is_safe());
+        assert!(checker.check_compatibility("MACADDR", "MACADDR8").is_safe());
+
+        let result = checker.check_compatibility("INET", "CIDR");
+        assert!(matches!(result, TypeCompatibility::DataLoss { .. }));
+
+        let result = checker.check_compatibility("CHARACTER VARYING(50)", "INET");
+        match result {
+            TypeCompatibility::DataLoss { reason } => assert!(reason.contains("May fail if not valid")),
+            other => panic!("expected DataLoss, got {:?}", other),
+        }
+
+        let result = checker.check_compatibility("MACADDR8", "MACADDR");
+        assert!(matches!(result, TypeCompatibility::DataLoss { .. }));
+    }
+
+    #[test]
+    fn test_money_and_bytea() {
+        let checker = TypeChecker::new();
+
+        assert_eq!(checker.check_compatibility("MONEY", "NUMERIC"), TypeCompatibility::Safe);
+        assert_eq!(checker.check_compatibility("MONEY", "NUMERIC(12,2)"), TypeCompatibility::Safe);
+        assert_eq!(checker.check_compatibility("money", "text"), TypeCompatibility::Safe);
+
+        let reason = |from: &str, to: &str| match checker.check_compatibility(from, to) {
+            TypeCompatibility::DataLoss { reason } => reason,
+            other => panic!("expected DataLoss for {} -> {}, got {:?}", from, to, other),
+        };
+        assert!(reason("NUMERIC", "MONEY").contains("lc_monetary"));
+        assert!(reason("NUMERIC(10,2)", "MONEY").contains("rounds"));
+        assert!(reason("TEXT", "MONEY").contains("valid amount"));
+        assert!(reason("BYTEA", "TEXT").contains("Encoding dependent"));
+        assert!(reason("TEXT", "BYTEA").contains("hex or escape"));
+    }
+
+    #[test]
+    fn test_specialized_types() {
+        let checker = TypeChecker::new();
+
+        assert_eq!(checker.check_compatibility("tsvector", "TSVECTOR"), TypeCompatibility::Identical);
+        assert_eq!(checker.check_compatibility("inet", "text"), TypeCompatibility::Safe);
+        assert_eq!(checker.check_compatibility("ltree", "text"), TypeCompatibility::Safe);
+        assert_eq!(checker.check_compatibility("hstore", "jsonb"), TypeCompatibility::Safe);
+        // Not mistaken for INT4/INT8 by alias normalization
+        assert_eq!(checker.check_compatibility("int4range", "text"), TypeCompatibility::Safe);
+        assert_eq!(checker.check_compatibility("int8range", "INT8RANGE"), TypeCompatibility::Identical);
+
+        match checker.check_compatibility("text", "tsvector") {
+            TypeCompatibility::DataLoss { reason } => assert!(reason.contains("tsvector literal")),
+            other => panic!("expected DataLoss, got {:?}", other),
+        }
+        assert!(matches!(
+            checker.check_compatibility("tsvector", "tsquery"),
+            TypeCompatibility::Incompatible { .. }
+        ));
+    }
+
+    #[test]
+    fn test_registered_rules() {
+        let mut checker = TypeChecker::new();
+        assert!(matches!(
+            checker.check_compatibility("email", "citext"),
+            TypeCompatibility::Incompatible { .. }
+        ));
+
+        checker.add_safe_widening("email", "citext");
+        checker.add_dataloss_narrowing("citext", "email", "May fail: must satisfy the email domain check");
+
+        assert_eq!(checker.check_compatibility("EMAIL", "citext"), TypeCompatibility::Safe);
+        match checker.check_compatibility("citext", "email") {
+            TypeCompatibility::DataLoss { reason } => assert!(reason.contains("email domain")),
+            other => panic!("expected DataLoss, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_custom_rules_file() {
+        let rules = CustomTypeRule::parse_rules(
+            "# vendor types\n\
+             email_address, text, safe\n\
+             TEXT,email_address,dataloss,May fail: must be a valid address\n\
+             BIGINT,INTEGER,safe\n",
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 3);
+
+        let mut checker = TypeChecker::new();
+        assert!(matches!(
+            checker.check_compatibility("email_address", "TEXT"),
+            TypeCompatibility::Incompatible { .. }
+        ));
+
+        checker.apply_rules(&rules);
+        assert_eq!(checker.check_compatibility("email_address", "TEXT"), TypeCompatibility::Safe);
+        match checker.check_compatibility("TEXT", "email_address") {
+            TypeCompatibility::DataLoss { reason } => assert!(reason.contains("valid address")),
+            other => panic!("expected DataLoss, got {:?}", other),
+        }
+        // Overrides the built-in narrowing
+        assert_eq!(checker.check_compatibility("BIGINT", "INTEGER"), TypeCompatibility::Safe);
+
+        assert!(CustomTypeRule::parse_rules("a,b").is_err());
+        assert!(CustomTypeRule::parse_rules("a,b,maybe").is_err());
+    }
+
+    #[test]
+    fn test_matrix_json() {
+        let checker = TypeChecker::new();
+        let json = checker.matrix_json();
+
+        let widenings = json["safe_widenings"].as_array().unwrap();
+        let integer = widenings
+            .iter()
+            .find(|w| w["from"] == "INTEGER")
+            .unwrap();
+        assert!(integer["to"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|t| t == "BIGINT"));
+
+        let narrowings = json["dataloss_narrowings"].as_array().unwrap();
+        let bigint_to_int = narrowings
+            .iter()
+            .find(|n| n["from"] == "BIGINT" && n["to"] == "INTEGER")
+            .unwrap();
+        assert_eq!(
+            bigint_to_int["reason"],
+            "May overflow: BIGINT max 9.2e18, INTEGER max 2.1e9"
+        );
+    }
 }
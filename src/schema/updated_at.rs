@@ -0,0 +1,305 @@
+//! Managed `updated_at` triggers
+//!
+//! Platforms that opt in get one trigger function per database and a
+//! `BEFORE UPDATE` trigger on every declared table with an `updated_at`
+//! column, so schemas do not each have to define their own.
+
+use crate::error::{GatewayError, Result};
+use crate::schema::dependency::qualified_table_name;
+use crate::schema::sql::quote_ident;
+use crate::schema::DependencyAnalyzer;
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Trigger function setting `NEW.updated_at`, created in the public schema
+pub const UPDATED_AT_FUNCTION: &str = "_stonescriptdb_gateway_set_updated_at";
+
+/// Name of the trigger attached to each table
+pub const UPDATED_AT_TRIGGER: &str = "_stonescriptdb_gateway_updated_at";
+
+const UPDATED_AT_COLUMN: &str = "updated_at";
+
+/// Result of checking the managed triggers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdatedAtVerification {
+    /// Declared tables with an `updated_at` column but no managed trigger
+    pub missing: Vec<String>,
+}
+
+impl UpdatedAtVerification {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// A declared table with an `updated_at` column
+struct ManagedTable {
+    schema: Option<String>,
+    name: String,
+}
+
+impl ManagedTable {
+    fn qualified_name(&self) -> String {
+        qualified_table_name(self.schema.as_deref(), &self.name)
+    }
+
+    fn quoted_name(&self) -> String {
+        match &self.schema {
+            Some(schema) => format!("{}.{}", quote_ident(schema), quote_ident(&self.name)),
+            None => quote_ident(&self.name),
+        }
+    }
+}
+
+pub struct UpdatedAtTriggerManager;
+
+impl UpdatedAtTriggerManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Create the trigger function and attach it to tables that lack the trigger
+    ///
+    /// Returns the tables that got a trigger.
+    pub async fn deploy_triggers(&self, pool: &Pool, database: &str, tables_dir: &Path) -> Result<Vec<String>> {
+        let tables = managed_tables(tables_dir)?;
+        if tables.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+        let existing = self.tables_with_trigger(&client, database).await?;
+
+        let deploy_error = |target: &str, e: tokio_postgres::Error| GatewayError::FunctionDeployFailed {
+            database: database.to_string(),
+            function: format!("{} on {}", UPDATED_AT_TRIGGER, target),
+            cause: e.to_string(),
+        };
+
+        let transaction = client
+            .transaction()
+            .await
+            .map_err(|e| deploy_error("BEGIN", e))?;
+        transaction
+            .batch_execute(&format!(
+                "CREATE OR REPLACE FUNCTION public.{}() RETURNS trigger LANGUAGE plpgsql AS $$
+                 BEGIN
+                     NEW.{} := now();
+                     RETURN NEW;
+                 END;
+                 $$;",
+                UPDATED_AT_FUNCTION, UPDATED_AT_COLUMN
+            ))
+            .await
+            .map_err(|e| deploy_error(UPDATED_AT_FUNCTION, e))?;
+
+        let mut created = Vec::new();
+        for table in tables.iter().filter(|t| !existing.contains(&t.qualified_name())) {
+            transaction
+                .batch_execute(&format!(
+                    "CREATE TRIGGER {} BEFORE UPDATE ON {} FOR EACH ROW EXECUTE FUNCTION public.{}()",
+                    UPDATED_AT_TRIGGER,
+                    table.quoted_name(),
+                    UPDATED_AT_FUNCTION
+                ))
+                .await
+                .map_err(|e| deploy_error(&table.qualified_name(), e))?;
+            created.push(table.qualified_name());
+        }
+        transaction
+            .commit()
+            .await
+            .map_err(|e| deploy_error("COMMIT", e))?;
+
+        if !created.is_empty() {
+            info!(
+                "Created {} triggers in {}: {}",
+                UPDATED_AT_TRIGGER,
+                database,
+                created.join(", ")
+            );
+        }
+        Ok(created)
+    }
+
+    /// Report declared tables with an `updated_at` column but no managed trigger
+    pub async fn verify_triggers(
+        &self,
+        pool: &Pool,
+        database: &str,
+        tables_dir: &Path,
+    ) -> Result<UpdatedAtVerification> {
+        let tables = managed_tables(tables_dir)?;
+        if tables.is_empty() {
+            return Ok(UpdatedAtVerification::default());
+        }
+
+        let client = pool.get().await.map_err(|e| GatewayError::ConnectionFailed {
+            database: database.to_string(),
+            cause: e.to_string(),
+        })?;
+        let existing = self.tables_with_trigger(&client, database).await?;
+
+        let missing: Vec<String> = tables
+            .iter()
+            .map(|table| table.qualified_name())
+            .filter(|table| !existing.contains(table))
+            .collect();
+        debug!(
+            "{} of {} updated_at tables in {} lack the managed trigger",
+            missing.len(),
+            tables.len(),
+            database
+        );
+
+        Ok(UpdatedAtVerification { missing })
+    }
+
+    /// Tables carrying the managed trigger, calling the managed function
+    async fn tables_with_trigger(
+        &self,
+        client: &deadpool_postgres::Object,
+        database: &str,
+    ) -> Result<HashSet<String>> {
+        let rows = client
+            .query(
+                r#"
+                SELECT n.nspname::text, c.relname::text
+                FROM pg_trigger t
+                JOIN pg_class c ON c.oid = t.tgrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                JOIN pg_proc p ON p.oid = t.tgfoid
+                WHERE t.tgname = $1 AND p.proname = $2 AND t.tgenabled <> 'D'
+                "#,
+                &[&UPDATED_AT_TRIGGER, &UPDATED_AT_FUNCTION],
+            )
+            .await
+            .map_err(|e| GatewayError::QueryFailed {
+                database: database.to_string(),
+                function: "updated_at trigger query".to_string(),
+                cause: e.to_string(),
+            })?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let schema: String = row.get(0);
+                qualified_table_name(Some(&schema), row.get(1))
+            })
+            .collect())
+    }
+}
+
+impl Default for UpdatedAtTriggerManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Declared tables with an `updated_at` column
+///
+/// Partitions are skipped: a trigger on the partitioned parent covers them.
+fn managed_tables(tables_dir: &Path) -> Result<Vec<ManagedTable>> {
+    if !tables_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let analysis = DependencyAnalyzer::analyze_directory(tables_dir).map_err(|e| {
+        GatewayError::SchemaExtractionFailed {
+            cause: e.to_string(),
+        }
+    })?;
+
+    Ok(analysis
+        .tables
+        .into_iter()
+        .filter(|table| !table.is_partition)
+        .filter(|table| table.columns.iter().any(|c| c.name == UPDATED_AT_COLUMN))
+        .map(|table| ManagedTable {
+            schema: table.schema,
+            name: table.name,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::test_db::test_pool;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_only_tables_with_updated_at_are_managed() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("tables.pssql"),
+            "CREATE TABLE orders (id SERIAL PRIMARY KEY, updated_at TIMESTAMPTZ);
+             CREATE TABLE billing.invoices (id SERIAL PRIMARY KEY, updated_at TIMESTAMPTZ NOT NULL DEFAULT now());
+             CREATE TABLE audit_log (id SERIAL PRIMARY KEY, created_at TIMESTAMPTZ);",
+        )
+        .unwrap();
+
+        let tables: Vec<String> = managed_tables(dir.path())
+            .unwrap()
+            .iter()
+            .map(|t| t.qualified_name())
+            .collect();
+        assert_eq!(tables, vec!["orders", "billing.invoices"]);
+        assert_eq!(managed_tables(&dir.path().join("missing")).unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_managed_trigger_created_and_verified() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("updated_at_items.pssql"),
+            "CREATE TABLE updated_at_items (id SERIAL PRIMARY KEY, name TEXT, updated_at TIMESTAMPTZ);",
+        )
+        .unwrap();
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS updated_at_items;
+                 CREATE TABLE updated_at_items (id SERIAL PRIMARY KEY, name TEXT, updated_at TIMESTAMPTZ);
+                 INSERT INTO updated_at_items (name) VALUES ('a');",
+            )
+            .await
+            .unwrap();
+        drop(client);
+
+        // The verifier reports the table until the trigger exists
+        let manager = UpdatedAtTriggerManager::new();
+        let verification = manager.verify_triggers(&pool, "gw_test", dir.path()).await.unwrap();
+        assert_eq!(verification.missing, vec!["updated_at_items"]);
+
+        let created = manager.deploy_triggers(&pool, "gw_test", dir.path()).await.unwrap();
+        assert_eq!(created, vec!["updated_at_items"]);
+        assert!(manager.verify_triggers(&pool, "gw_test", dir.path()).await.unwrap().is_ok());
+
+        // Deploying again leaves the existing trigger alone
+        assert!(manager.deploy_triggers(&pool, "gw_test", dir.path()).await.unwrap().is_empty());
+
+        let client = pool.get().await.unwrap();
+        let bumped: bool = client
+            .query_one(
+                "UPDATE updated_at_items SET name = 'b' RETURNING updated_at IS NOT NULL",
+                &[],
+            )
+            .await
+            .unwrap()
+            .get(0);
+        client.batch_execute("DROP TABLE updated_at_items").await.unwrap();
+        assert!(bumped);
+    }
+}
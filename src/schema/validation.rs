@@ -0,0 +1,201 @@
+//! Upload-time schema validation
+//!
+//! Parses an uploaded schema the way provisioning and migrate will, so broken
+//! files and circular table dependencies are rejected at registration instead
+//! of surfacing during a migration against a live database.
+
+use crate::error::Result;
+use crate::schema::dependency::IndexInfo;
+use crate::schema::{CustomTypeManager, DependencyAnalyzer, ParseError};
+use std::path::{Path, PathBuf};
+
+pub struct SchemaValidator;
+
+impl SchemaValidator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Collect every problem found in the schema's tables, migrations and types
+    ///
+    /// An empty list means the schema can be stored.
+    pub fn validate(&self, tables_dir: &Path, migrations_dir: &Path, types_dir: &Path) -> Result<Vec<String>> {
+        let mut issues = Vec::new();
+        // Report paths relative to the schema root (e.g. tables/users.pssql)
+        let relative = |path: &Path| -> PathBuf {
+            tables_dir
+                .parent()
+                .and_then(|root| path.strip_prefix(root).ok())
+                .unwrap_or(path)
+                .to_path_buf()
+        };
+
+        for dir in [tables_dir, migrations_dir] {
+            if dir.exists() {
+                if let Err(e) = DependencyAnalyzer::analyze_directory(dir) {
+                    let e = ParseError { file: relative(&e.file), ..e };
+                    issues.push(e.to_string());
+                }
+            }
+        }
+
+        let type_manager = CustomTypeManager::new();
+        for file in type_manager.find_type_files(types_dir)? {
+            if let Err(e) = type_manager.parse_type(&file) {
+                issues.push(format!("{}: {}", relative(&file).display(), e));
+            }
+        }
+
+        Ok(issues)
+    }
+
+    /// Collect warnings that do not block registration
+    ///
+    /// Currently duplicate and overlapping CREATE INDEX statements in the
+    /// tables directory. Parse failures are left to [`Self::validate`].
+    pub fn lint(&self, tables_dir: &Path) -> Vec<String> {
+        if !tables_dir.exists() {
+            return Vec::new();
+        }
+
+        match DependencyAnalyzer::analyze_directory(tables_dir) {
+            Ok(analysis) => redundant_indexes(&analysis.indexes),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// Pairs of indexes where one makes the other unnecessary
+///
+/// Indexes with the same table, method, keys and predicate are duplicates. A
+/// non-unique btree index whose keys are a prefix of another btree index's
+/// keys is covered by it.
+fn redundant_indexes(indexes: &[IndexInfo]) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    for (i, a) in indexes.iter().enumerate() {
+        for b in &indexes[i + 1..] {
+            if a.table_name() != b.table_name() || a.method != b.method || a.predicate != b.predicate {
+                continue;
+            }
+
+            if a.keys == b.keys {
+                warnings.push(format!(
+                    "Duplicate index: '{}' and '{}' both index {} ({})",
+                    a.name,
+                    b.name,
+                    a.table_name(),
+                    a.keys.join(", ")
+                ));
+                continue;
+            }
+
+            let (short, long) = if a.keys.len() < b.keys.len() { (a, b) } else { (b, a) };
+            if short.method == "btree" && !short.unique && long.keys.starts_with(&short.keys) {
+                warnings.push(format!(
+                    "Redundant index: '{}' ({}) on {} is covered by '{}' ({})",
+                    short.name,
+                    short.keys.join(", "),
+                    short.table_name(),
+                    long.name,
+                    long.keys.join(", ")
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+impl Default for SchemaValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn schema_dirs(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn validate(dir: &TempDir) -> Vec<String> {
+        SchemaValidator::new()
+            .validate(
+                &dir.path().join("tables"),
+                &dir.path().join("migrations"),
+                &dir.path().join("types"),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_valid_schema_has_no_issues() {
+        let dir = schema_dirs(&[
+            ("tables/users.pssql", "CREATE TABLE users (id SERIAL PRIMARY KEY);"),
+            ("tables/posts.pssql", "CREATE TABLE posts (id SERIAL PRIMARY KEY, user_id INT REFERENCES users(id));"),
+            ("migrations/001_add_email.pssql", "ALTER TABLE users ADD COLUMN email TEXT;"),
+            ("types/status.pssql", "CREATE TYPE status AS ENUM ('a', 'b');"),
+        ]);
+
+        assert!(validate(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_broken_schema_reports_every_issue() {
+        let dir = schema_dirs(&[
+            ("tables/a.pssql", "CREATE TABLE a (id INT PRIMARY KEY, b_id INT REFERENCES b(id));"),
+            ("tables/b.pssql", "CREATE TABLE b (id INT PRIMARY KEY, a_id INT REFERENCES a(id));"),
+            ("migrations/001_broken.pssql", "CREATE TABLE c (id INT PRIMARY KEY;"),
+            ("types/nameless.pssql", "-- TODO: define the type"),
+        ]);
+
+        let issues = validate(&dir);
+        assert_eq!(issues.len(), 3, "{:?}", issues);
+        assert!(issues[0].contains("Circular dependency"), "{}", issues[0]);
+        assert!(issues[0].contains("a -> b -> a") || issues[0].contains("b -> a -> b"), "{}", issues[0]);
+        assert!(issues[0].starts_with("tables: "), "{}", issues[0]);
+        assert!(issues[1].starts_with("migrations/001_broken.pssql:1: "), "{}", issues[1]);
+        assert!(issues[2].starts_with("types/nameless.pssql: "), "{}", issues[2]);
+    }
+
+    #[test]
+    fn test_duplicate_index_warning() {
+        let dir = schema_dirs(&[
+            (
+                "tables/orders.pssql",
+                "CREATE TABLE orders (id SERIAL PRIMARY KEY, customer_id INT, created_at TIMESTAMPTZ);\n\
+                 CREATE INDEX idx_orders_customer ON orders (customer_id);",
+            ),
+            (
+                "tables/orders_indexes.pssql",
+                "CREATE INDEX orders_by_customer ON orders USING btree (Customer_Id);\n\
+                 CREATE INDEX idx_orders_customer_created ON orders (customer_id, created_at);\n\
+                 CREATE INDEX idx_orders_created ON orders (created_at) WHERE customer_id IS NOT NULL;",
+            ),
+        ]);
+
+        // Lint findings never block registration
+        assert!(validate(&dir).is_empty());
+
+        let warnings = SchemaValidator::new().lint(&dir.path().join("tables"));
+        assert_eq!(
+            warnings,
+            vec![
+                "Duplicate index: 'idx_orders_customer' and 'orders_by_customer' both index orders (customer_id)",
+                "Redundant index: 'idx_orders_customer' (customer_id) on orders is covered by 'idx_orders_customer_created' (customer_id, created_at)",
+                "Redundant index: 'orders_by_customer' (customer_id) on orders is covered by 'idx_orders_customer_created' (customer_id, created_at)",
+            ]
+        );
+    }
+}
@@ -1,14 +1,26 @@
 //! Schema Verifier for Migrate
 //!
 //! After running migrations, verifies that the database schema matches
-//! the declarative schema definition (extensions, types, tables, functions, seeders).
+//! the declarative schema definition (extensions, types, tables, functions, seeders,
+//! row-level security policies, materialized views, table privileges).
 //!
 //! If verification fails, the migrate operation should rollback and return
 //! a detailed error log explaining what doesn't match.
+//!
+//! Serial/identity sequences that fell behind their column are reported as
+//! well, but do not fail verification.
+//!
+//! Deployed function bodies are compared with their source only when deep
+//! function verification is enabled, as it fetches every function definition.
+//! Managed `updated_at` triggers are checked only for platforms that use them.
 
 use crate::error::Result;
 use crate::schema::{
-    CustomTypeManager, ExtensionManager, SchemaDiffChecker, SeederRunner,
+    ChangeType, CustomTypeManager, ExtensionManager, FunctionDeployer, FunctionVerification, GrantManager,
+    GrantVerification, LaggingSequence,
+    MatviewDeployer, MatviewVerification,
+    PolicyManager, PolicyVerification, SchemaDiffChecker, SeederRunner, SequenceChecker,
+    UpdatedAtTriggerManager, UpdatedAtVerification,
 };
 use deadpool_postgres::Pool;
 use serde::Serialize;
@@ -23,6 +35,15 @@ pub struct VerificationResult {
     pub types: TypeVerification,
     pub tables: TableVerification,
     pub seeders: SeederVerification,
+    pub policies: PolicyVerification,
+    pub matviews: MatviewVerification,
+    pub grants: GrantVerification,
+    /// Empty unless deep function verification is enabled
+    pub functions: FunctionVerification,
+    /// Empty unless managed `updated_at` triggers are enabled
+    pub updated_at_triggers: UpdatedAtVerification,
+    /// Sequences behind their column (warning only, does not affect `passed`)
+    pub lagging_sequences: Vec<LaggingSequence>,
 }
 
 impl VerificationResult {
@@ -33,9 +54,77 @@ impl VerificationResult {
             types: TypeVerification::default(),
             tables: TableVerification::default(),
             seeders: SeederVerification::default(),
+            policies: PolicyVerification::default(),
+            matviews: MatviewVerification::default(),
+            grants: GrantVerification::default(),
+            functions: FunctionVerification::default(),
+            updated_at_triggers: UpdatedAtVerification::default(),
+            lagging_sequences: Vec::new(),
         }
     }
 
+    /// List every verification problem as a one-line finding
+    pub fn findings(&self) -> Vec<String> {
+        let mut findings = Vec::new();
+
+        for ext in &self.extensions.missing {
+            findings.push(format!("Missing extension: {}", ext));
+        }
+        for t in &self.types.missing {
+            findings.push(format!("Missing type: {}", t));
+        }
+        for t in &self.tables.missing {
+            findings.push(format!("Missing table: {}", t));
+        }
+        for m in &self.tables.mismatches {
+            findings.push(format!("Table mismatch: {}: {}", m.table, m.issue));
+        }
+        for s in &self.seeders.missing {
+            findings.push(format!("Missing seeder records: {} ({} missing)", s.table, s.count));
+        }
+        for p in &self.policies.missing {
+            findings.push(format!("Missing policy: {}", p));
+        }
+        for m in &self.policies.mismatches {
+            findings.push(format!("Policy mismatch: {}: {}", m.policy, m.issue));
+        }
+        for t in &self.policies.rls_disabled {
+            findings.push(format!("Row level security disabled: {}", t));
+        }
+        for v in &self.matviews.missing {
+            findings.push(format!("Missing materialized view: {}", v));
+        }
+        for m in &self.matviews.mismatches {
+            findings.push(format!("Materialized view mismatch: {}: {}", m.matview, m.issue));
+        }
+        for g in &self.grants.missing {
+            findings.push(format!("Missing grant: {}", g));
+        }
+        for g in &self.grants.unexpected {
+            findings.push(format!("Revoked grant still held: {}", g));
+        }
+        for f in &self.functions.missing {
+            findings.push(format!("Missing function: {}", f));
+        }
+        for f in &self.functions.drifted {
+            findings.push(format!("Function body differs from declared source: {}", f));
+        }
+        for t in &self.updated_at_triggers.missing {
+            findings.push(format!("Missing managed updated_at trigger: {}", t));
+        }
+
+        findings
+    }
+
+    /// List problems that do not fail verification, but fail a strict migrate
+    pub fn warnings(&self) -> Vec<String> {
+        self.extensions
+            .extra
+            .iter()
+            .map(|ext| format!("Undeclared extension: {}", ext))
+            .collect()
+    }
+
     /// Generate a human-readable error log
     pub fn error_log(&self) -> String {
         let mut log = String::new();
@@ -52,6 +141,14 @@ impl VerificationResult {
             log.push('\n');
         }
 
+        if !self.extensions.extra.is_empty() {
+            log.push_str("UNDECLARED EXTENSIONS (warning):\n");
+            for ext in &self.extensions.extra {
+                log.push_str(&format!("  - {}\n", ext));
+            }
+            log.push('\n');
+        }
+
         if !self.types.missing.is_empty() {
             log.push_str("MISSING TYPES:\n");
             for t in &self.types.missing {
@@ -90,6 +187,102 @@ impl VerificationResult {
             log.push('\n');
         }
 
+        if !self.policies.missing.is_empty() {
+            log.push_str("MISSING POLICIES:\n");
+            for p in &self.policies.missing {
+                log.push_str(&format!("  - {}\n", p));
+            }
+            log.push('\n');
+        }
+
+        if !self.policies.mismatches.is_empty() {
+            log.push_str("POLICY MISMATCHES:\n");
+            for m in &self.policies.mismatches {
+                log.push_str(&format!("  - {}: {}\n", m.policy, m.issue));
+            }
+            log.push('\n');
+        }
+
+        if !self.policies.rls_disabled.is_empty() {
+            log.push_str("ROW LEVEL SECURITY DISABLED:\n");
+            for t in &self.policies.rls_disabled {
+                log.push_str(&format!("  - {}\n", t));
+            }
+            log.push('\n');
+        }
+
+        if !self.matviews.missing.is_empty() {
+            log.push_str("MISSING MATERIALIZED VIEWS:\n");
+            for v in &self.matviews.missing {
+                log.push_str(&format!("  - {}\n", v));
+            }
+            log.push('\n');
+        }
+
+        if !self.matviews.mismatches.is_empty() {
+            log.push_str("MATERIALIZED VIEW MISMATCHES:\n");
+            for m in &self.matviews.mismatches {
+                log.push_str(&format!("  - {}: {}\n", m.matview, m.issue));
+            }
+            log.push('\n');
+        }
+
+        if !self.grants.missing.is_empty() {
+            log.push_str("MISSING GRANTS:\n");
+            for g in &self.grants.missing {
+                log.push_str(&format!("  - {}\n", g));
+            }
+            log.push('\n');
+        }
+
+        if !self.grants.unexpected.is_empty() {
+            log.push_str("REVOKED GRANTS STILL HELD:\n");
+            for g in &self.grants.unexpected {
+                log.push_str(&format!("  - {}\n", g));
+            }
+            log.push('\n');
+        }
+
+        if !self.functions.missing.is_empty() {
+            log.push_str("MISSING FUNCTIONS:\n");
+            for f in &self.functions.missing {
+                log.push_str(&format!("  - {}\n", f));
+            }
+            log.push('\n');
+        }
+
+        if !self.functions.drifted.is_empty() {
+            log.push_str("FUNCTION BODIES ALTERED IN DATABASE (redeploy or update the source):\n");
+            for f in &self.functions.drifted {
+                log.push_str(&format!("  - {}\n", f));
+            }
+            log.push('\n');
+        }
+
+        if !self.updated_at_triggers.missing.is_empty() {
+            log.push_str("MISSING MANAGED UPDATED_AT TRIGGERS:\n");
+            for t in &self.updated_at_triggers.missing {
+                log.push_str(&format!("  - {}\n", t));
+            }
+            log.push('\n');
+        }
+
+        if !self.lagging_sequences.is_empty() {
+            log.push_str("LAGGING SEQUENCES (next value already used):\n");
+            for s in &self.lagging_sequences {
+                log.push_str(&format!(
+                    "  - {}.{}: {} at {}, max {} (fix: {})\n",
+                    s.table,
+                    s.column,
+                    s.sequence,
+                    s.last_value,
+                    s.max_value,
+                    s.fix_sql()
+                ));
+            }
+            log.push('\n');
+        }
+
         log.push_str("═══════════════════════════════════════════════════════════════\n");
         log.push_str("ACTION REQUIRED: Add migration(s) to fix schema drift\n");
         log.push_str("═══════════════════════════════════════════════════════════════\n");
@@ -109,6 +302,8 @@ pub struct ExtensionVerification {
     pub expected: Vec<String>,
     pub found: Vec<String>,
     pub missing: Vec<String>,
+    /// Installed but neither declared nor allow-listed (warning only)
+    pub extra: Vec<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize)]
@@ -150,6 +345,16 @@ pub struct SchemaVerifier {
     type_manager: CustomTypeManager,
     diff_checker: SchemaDiffChecker,
     seeder_runner: SeederRunner,
+    sequence_checker: SequenceChecker,
+    policy_manager: PolicyManager,
+    matview_deployer: MatviewDeployer,
+    grant_manager: GrantManager,
+    function_deployer: FunctionDeployer,
+    updated_at_triggers: UpdatedAtTriggerManager,
+    /// Compare deployed function bodies with their source
+    verify_function_bodies: bool,
+    /// Check the managed trigger on every table with an `updated_at` column
+    verify_updated_at_triggers: bool,
 }
 
 impl SchemaVerifier {
@@ -159,10 +364,31 @@ impl SchemaVerifier {
             type_manager: CustomTypeManager::new(),
             diff_checker: SchemaDiffChecker::new(),
             seeder_runner: SeederRunner::new(),
+            sequence_checker: SequenceChecker::new(),
+            policy_manager: PolicyManager::new(),
+            matview_deployer: MatviewDeployer::new(),
+            grant_manager: GrantManager::new(),
+            function_deployer: FunctionDeployer::new(),
+            updated_at_triggers: UpdatedAtTriggerManager::new(),
+            verify_function_bodies: false,
+            verify_updated_at_triggers: false,
         }
     }
 
+    /// Also compare each deployed function's body with its declared source
+    pub fn with_function_bodies(mut self, enabled: bool) -> Self {
+        self.verify_function_bodies = enabled;
+        self
+    }
+
+    /// Also check the managed `updated_at` triggers (platforms that enable them)
+    pub fn with_updated_at_triggers(mut self, enabled: bool) -> Self {
+        self.verify_updated_at_triggers = enabled;
+        self
+    }
+
     /// Verify all schema components after migration
+    #[allow(clippy::too_many_arguments)]
     pub async fn verify_schema(
         &self,
         pool: &Pool,
@@ -171,6 +397,10 @@ impl SchemaVerifier {
         types_dir: &Path,
         tables_dir: &Path,
         seeders_dir: &Path,
+        policies_dir: &Path,
+        matviews_dir: &Path,
+        grants_dir: &Path,
+        functions_dir: &Path,
     ) -> Result<VerificationResult> {
         let mut result = VerificationResult::new();
 
@@ -180,6 +410,9 @@ impl SchemaVerifier {
         if !result.extensions.missing.is_empty() {
             result.passed = false;
         }
+        if !result.extensions.extra.is_empty() {
+            warn!("Undeclared extensions in {}: {}", database, result.extensions.extra.join(", "));
+        }
 
         // 2. Verify types
         debug!("Verifying types for {}", database);
@@ -202,6 +435,64 @@ impl SchemaVerifier {
             result.passed = false;
         }
 
+        // 5. Verify row-level security policies
+        debug!("Verifying policies for {}", database);
+        result.policies = self
+            .policy_manager
+            .verify_policies(pool, database, policies_dir)
+            .await?;
+        if !result.policies.is_ok() {
+            result.passed = false;
+        }
+
+        // 6. Verify materialized views
+        debug!("Verifying materialized views for {}", database);
+        result.matviews = self
+            .matview_deployer
+            .verify_matviews(pool, database, matviews_dir)
+            .await?;
+        if !result.matviews.is_ok() {
+            result.passed = false;
+        }
+
+        // 7. Verify table privileges
+        debug!("Verifying grants for {}", database);
+        result.grants = self
+            .grant_manager
+            .verify_grants(pool, database, grants_dir)
+            .await?;
+        if !result.grants.is_ok() {
+            result.passed = false;
+        }
+
+        // 8. Compare function bodies with their source (deep verification only)
+        if self.verify_function_bodies {
+            debug!("Verifying function bodies for {}", database);
+            result.functions = self
+                .function_deployer
+                .verify_function_bodies(pool, database, functions_dir)
+                .await?;
+            if !result.functions.is_ok() {
+                result.passed = false;
+            }
+        }
+
+        // 9. Check managed updated_at triggers (platforms that enable them)
+        if self.verify_updated_at_triggers {
+            debug!("Verifying updated_at triggers for {}", database);
+            result.updated_at_triggers = self
+                .updated_at_triggers
+                .verify_triggers(pool, database, tables_dir)
+                .await?;
+            if !result.updated_at_triggers.is_ok() {
+                result.passed = false;
+            }
+        }
+
+        // 10. Check serial/identity sequences (warning only)
+        debug!("Checking sequences for {}", database);
+        result.lagging_sequences = self.sequence_checker.find_lagging(pool, database).await?;
+
         if result.passed {
             info!("Schema verification PASSED for {}", database);
         } else {
@@ -237,6 +528,15 @@ impl SchemaVerifier {
             }
         }
 
+        // Find installed extensions nobody declared (e.g. installed by hand)
+        let allowed = self.extension_manager.allowed_undeclared(extensions_dir);
+        verification.extra = verification
+            .found
+            .iter()
+            .filter(|name| !verification.expected.contains(name) && !allowed.contains(name))
+            .cloned()
+            .collect();
+
         Ok(verification)
     }
 
@@ -301,14 +601,30 @@ impl SchemaVerifier {
         }
 
         // Find mismatches in existing tables
-        let diff = self.diff_checker.diff_schemas(&desired, &current);
+        let unmanaged = SchemaDiffChecker::load_unmanaged_tables(tables_dir);
+        let diff = self.diff_checker.diff_schemas(&desired, &current, &unmanaged);
 
         // Convert dataloss and incompatible changes to mismatches
-        for change in diff.dataloss_changes.iter().chain(diff.incompatible_changes.iter()) {
+        for change in diff.blocking_changes() {
+            if change.change_type == ChangeType::ModifyPrimaryKey {
+                let desired_pk = desired.get(&change.table).and_then(|t| t.primary_key.as_deref());
+                let current_pk = current.get(&change.table).and_then(|t| t.primary_key.as_deref());
+                verification.mismatches.push(TableMismatch {
+                    table: change.table.clone(),
+                    issue: primary_key_issue(desired_pk, current_pk),
+                });
+                continue;
+            }
+
             let issue = match &change.column {
                 Some(col) => format!(
-                    "{:?} column '{}': {} -> {}",
+                    "{:?} {} '{}': {} -> {}",
                     change.change_type,
+                    match change.change_type {
+                        ChangeType::AddIndex | ChangeType::DropIndex => "index",
+                        ChangeType::AddCheck | ChangeType::DropCheck => "constraint",
+                        _ => "column",
+                    },
                     col,
                     change.from_type.as_deref().unwrap_or("-"),
                     change.to_type.as_deref().unwrap_or("-")
@@ -322,6 +638,23 @@ impl SchemaVerifier {
             });
         }
 
+        // Check INHERITS parents against pg_inherits
+        for (table_name, desired_table) in &desired {
+            let Some(current_table) = current.get(table_name) else {
+                continue;
+            };
+            if desired_table.inherits != current_table.inherits {
+                verification.mismatches.push(TableMismatch {
+                    table: table_name.clone(),
+                    issue: format!(
+                        "INHERITS ({}) expected, found ({})",
+                        desired_table.inherits.join(", "),
+                        current_table.inherits.join(", ")
+                    ),
+                });
+            }
+        }
+
         Ok(verification)
     }
 
@@ -364,6 +697,38 @@ impl SchemaVerifier {
     }
 }
 
+/// Describe a primary key mismatch, calling out keys that only differ in column order
+///
+/// The key column order is the order of the backing index, so `(a, b)` and
+/// `(b, a)` serve different queries even though they enforce the same constraint.
+fn primary_key_issue(desired: Option<&[String]>, current: Option<&[String]>) -> String {
+    let format_key = |key: Option<&[String]>| match key {
+        Some(columns) => format!("({})", columns.join(", ")),
+        None => "none".to_string(),
+    };
+
+    let same_columns = match (desired, current) {
+        (Some(desired), Some(current)) => {
+            let mut desired = desired.to_vec();
+            let mut current = current.to_vec();
+            desired.sort();
+            current.sort();
+            desired == current
+        }
+        _ => false,
+    };
+
+    if same_columns {
+        format!(
+            "PRIMARY KEY column order {} expected, found {}",
+            format_key(desired),
+            format_key(current)
+        )
+    } else {
+        format!("PRIMARY KEY {} expected, found {}", format_key(desired), format_key(current))
+    }
+}
+
 impl Default for SchemaVerifier {
     fn default() -> Self {
         Self::new()
@@ -373,6 +738,100 @@ impl Default for SchemaVerifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schema::extensions::ALLOWED_EXTENSIONS_FILE;
+    use crate::schema::test_db::test_pool;
+
+    #[tokio::test]
+    async fn test_undeclared_extension_reported_as_extra() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client.batch_execute("CREATE EXTENSION IF NOT EXISTS seg").await.unwrap();
+
+        let extensions_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(extensions_dir.path().join("pg_trgm.sql"), "").unwrap();
+        let verifier = SchemaVerifier::new();
+
+        let verification = verifier
+            .verify_extensions(&pool, "gw_test", extensions_dir.path())
+            .await
+            .unwrap();
+        assert_eq!(verification.missing, vec!["pg_trgm"]);
+        assert!(verification.extra.contains(&"seg".to_string()), "{:?}", verification.extra);
+        assert!(!verification.extra.contains(&"plpgsql".to_string()));
+
+        // Allow-listed extensions are not reported (and the list is not an extension file)
+        std::fs::write(extensions_dir.path().join(ALLOWED_EXTENSIONS_FILE), "# benign\nseg\n").unwrap();
+        let verification = verifier
+            .verify_extensions(&pool, "gw_test", extensions_dir.path())
+            .await
+            .unwrap();
+        assert_eq!(verification.expected, vec!["pg_trgm"]);
+        assert!(!verification.extra.contains(&"seg".to_string()));
+
+        client.batch_execute("DROP EXTENSION seg").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_primary_key_column_order_mismatch() {
+        let Some(pool) = test_pool() else {
+            return;
+        };
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(
+                "DROP SCHEMA IF EXISTS pkorder CASCADE;
+                 CREATE SCHEMA pkorder;
+                 CREATE TABLE pkorder.items (a INT NOT NULL, b INT NOT NULL, PRIMARY KEY (b, a));",
+            )
+            .await
+            .unwrap();
+
+        let tables_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            tables_dir.path().join("items.pssql"),
+            "CREATE TABLE pkorder.items (a INTEGER NOT NULL, b INTEGER NOT NULL, PRIMARY KEY (a, b));",
+        )
+        .unwrap();
+
+        let verification = SchemaVerifier::new()
+            .verify_tables(&pool, "gw_test", tables_dir.path())
+            .await
+            .unwrap();
+        let issues: Vec<&str> = verification
+            .mismatches
+            .iter()
+            .filter(|m| m.table == "pkorder.items")
+            .map(|m| m.issue.as_str())
+            .collect();
+        assert_eq!(issues, vec!["PRIMARY KEY column order (a, b) expected, found (b, a)"]);
+
+        client.batch_execute("DROP SCHEMA pkorder CASCADE").await.unwrap();
+    }
+
+    #[test]
+    fn test_primary_key_issue() {
+        let key = |columns: &[&str]| columns.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            primary_key_issue(Some(&key(&["tenant_id", "id"])), Some(&key(&["id"]))),
+            "PRIMARY KEY (tenant_id, id) expected, found (id)"
+        );
+        assert_eq!(
+            primary_key_issue(Some(&key(&["id"])), None),
+            "PRIMARY KEY (id) expected, found none"
+        );
+    }
+
+    #[test]
+    fn test_extra_extensions_are_warnings_only() {
+        let mut result = VerificationResult::new();
+        result.extensions.extra.push("pg_stat_statements".to_string());
+
+        assert!(result.findings().is_empty());
+        assert_eq!(result.warnings(), vec!["Undeclared extension: pg_stat_statements"]);
+    }
 
     #[test]
     fn test_verification_result_error_log() {
@@ -392,6 +851,52 @@ mod tests {
         assert!(log.contains("ACTION REQUIRED"));
     }
 
+    #[test]
+    fn test_error_log_includes_lagging_sequences() {
+        let mut result = VerificationResult::new();
+        result.lagging_sequences.push(LaggingSequence {
+            table: "users".to_string(),
+            column: "id".to_string(),
+            sequence: "users_id_seq".to_string(),
+            last_value: 1,
+            max_value: 50,
+        });
+
+        // Lagging sequences are reported, but do not fail verification
+        assert!(result.passed);
+        assert!(result
+            .error_log()
+            .contains("SELECT setval('users_id_seq', 50, true);"));
+    }
+
+    #[test]
+    fn test_missing_updated_at_trigger_is_reported() {
+        let mut result = VerificationResult::new();
+        result.updated_at_triggers.missing.push("orders".to_string());
+
+        assert_eq!(result.findings(), vec!["Missing managed updated_at trigger: orders"]);
+        assert!(result.error_log().contains("MISSING MANAGED UPDATED_AT TRIGGERS:\n  - orders"));
+    }
+
+    #[test]
+    fn test_verification_result_findings() {
+        let mut result = VerificationResult::new();
+        result.tables.missing = vec!["orders".to_string()];
+        result.tables.mismatches.push(TableMismatch {
+            table: "users".to_string(),
+            issue: "DropColumn column 'legacy': TEXT -> -".to_string(),
+        });
+
+        assert_eq!(
+            result.findings(),
+            vec![
+                "Missing table: orders",
+                "Table mismatch: users: DropColumn column 'legacy': TEXT -> -",
+            ]
+        );
+        assert!(VerificationResult::new().findings().is_empty());
+    }
+
     #[test]
     fn test_verification_result_empty_is_passed() {
         let result = VerificationResult::new();
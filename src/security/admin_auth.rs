@@ -1,6 +1,6 @@
 use axum::{
     extract::{ConnectInfo, Request, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     middleware::Next,
     response::Response,
 };
@@ -94,6 +94,21 @@ pub async fn admin_auth_middleware(
     Ok(next.run(req).await)
 }
 
+/// Whether the request carries the admin token as `Authorization: Bearer`
+///
+/// Used by non-admin endpoints that show more to an admin caller; the IP
+/// allow list is not checked here.
+pub fn has_admin_token(headers: &HeaderMap, admin_token: Option<&str>) -> bool {
+    let Some(admin_token) = admin_token else {
+        return false;
+    };
+    headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_compare(token, admin_token))
+}
+
 /// Extract client IP from request
 ///
 /// Priority:
@@ -128,6 +143,9 @@ fn extract_client_ip(req: &Request, conn_ip: IpAddr) -> IpAddr {
 
 /// Check if IP is in allowed list
 fn is_ip_allowed(allowed_networks: &[IpNetwork], ip: IpAddr) -> bool {
+    // Match IPv4-mapped IPv6 addresses against IPv4 networks
+    let ip = ip.to_canonical();
+
     // Always allow loopback for local admin access
     if ip.is_loopback() {
         return true;
@@ -193,6 +211,18 @@ mod tests {
         assert!(!is_ip_allowed(&allowed, "1.1.1.1".parse().unwrap()));
     }
 
+    #[test]
+    fn test_has_admin_token() {
+        let mut headers = HeaderMap::new();
+        assert!(!has_admin_token(&headers, Some("secret")));
+
+        headers.insert("authorization", "Bearer secret".parse().unwrap());
+        assert!(has_admin_token(&headers, Some("secret")));
+        assert!(!has_admin_token(&headers, Some("other")));
+        // No configured token means nobody is admin
+        assert!(!has_admin_token(&headers, None));
+    }
+
     #[test]
     fn test_admin_auth_config_is_enabled() {
         let config_enabled = AdminAuthConfig::new(Some("token".to_string()), vec![]);
@@ -0,0 +1,196 @@
+use super::EXEMPT_PATHS;
+use axum::{
+    body::Body,
+    http::{HeaderMap, Request, StatusCode},
+    response::Response,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+use tracing::warn;
+
+/// Header carrying the key when `Authorization: Bearer` is not used
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Require one of a set of API keys on every request
+///
+/// Keys are sent as `Authorization: Bearer <key>` or `X-API-Key: <key>`.
+/// With no keys configured the layer lets every request through, so it can be
+/// stacked with [`super::IpFilterLayer`] unconditionally.
+#[derive(Clone)]
+pub struct ApiKeyLayer {
+    /// SHA-256 digests of the valid keys
+    keys: Arc<Vec<[u8; 32]>>,
+}
+
+impl ApiKeyLayer {
+    pub fn new(keys: &[String]) -> Self {
+        Self {
+            keys: Arc::new(keys.iter().map(|k| digest(k)).collect()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+}
+
+impl<S> Layer<S> for ApiKeyLayer {
+    type Service = ApiKeyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ApiKeyService {
+            inner,
+            keys: self.keys.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ApiKeyService<S> {
+    inner: S,
+    keys: Arc<Vec<[u8; 32]>>,
+}
+
+impl<S> Service<Request<Body>> for ApiKeyService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let keys = self.keys.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            if keys.is_empty() || EXEMPT_PATHS.contains(&req.uri().path()) {
+                return inner.call(req).await;
+            }
+
+            match presented_key(req.headers()) {
+                Some(key) if is_valid_key(&keys, key) => inner.call(req).await,
+                presented => {
+                    let message = if presented.is_some() { "Invalid API key" } else { "Missing API key" };
+                    warn!("Unauthorized request to {}: {}", req.uri().path(), message);
+                    let response = Response::builder()
+                        .status(StatusCode::UNAUTHORIZED)
+                        .header("content-type", "application/json")
+                        .header("www-authenticate", "Bearer")
+                        .body(Body::from(format!(
+                            r#"{{"error":"unauthorized","message":"{}"}}"#,
+                            message
+                        )))
+                        .unwrap();
+                    Ok(response)
+                }
+            }
+        })
+    }
+}
+
+/// Key from `Authorization: Bearer`, falling back to `X-API-Key`
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let header = || headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok());
+
+    bearer.or_else(header).map(str::trim).filter(|k| !k.is_empty())
+}
+
+/// Compare against every configured key in constant time
+///
+/// Keys are compared as fixed-length digests and the loop never exits early,
+/// so the response time depends neither on the key length nor on which key
+/// (if any) matched.
+fn is_valid_key(keys: &[[u8; 32]], key: &str) -> bool {
+    let presented = digest(key);
+    let mut matched = 0u8;
+    for valid in keys {
+        let mut diff = 0u8;
+        for (x, y) in presented.iter().zip(valid.iter()) {
+            diff |= x ^ y;
+        }
+        matched |= (diff == 0) as u8;
+    }
+    matched == 1
+}
+
+fn digest(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+
+    fn app(keys: &[&str]) -> Router {
+        let keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+        Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .route("/call", get(|| async { "ok" }))
+            .layer(ApiKeyLayer::new(&keys))
+    }
+
+    async fn status(app: &mut Router, path: &str, header: Option<(&str, &str)>) -> StatusCode {
+        let mut req = Request::get(path);
+        if let Some((name, value)) = header {
+            req = req.header(name, value);
+        }
+        app.call(req.body(Body::empty()).unwrap()).await.unwrap().status()
+    }
+
+    #[test]
+    fn test_is_valid_key() {
+        let keys = vec![digest("key-one"), digest("key-two")];
+        assert!(is_valid_key(&keys, "key-one"));
+        assert!(is_valid_key(&keys, "key-two"));
+        assert!(!is_valid_key(&keys, "key-three"));
+        assert!(!is_valid_key(&keys, "key-on"));
+        assert!(!is_valid_key(&[], "key-one"));
+    }
+
+    #[tokio::test]
+    async fn test_key_required() {
+        let mut app = app(&["s3cret", "other"]);
+
+        assert_eq!(status(&mut app, "/call", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status(&mut app, "/call", Some(("authorization", "Bearer wrong"))).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&mut app, "/call", Some(("authorization", "Basic s3cret"))).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&mut app, "/call", Some(("authorization", "Bearer s3cret"))).await,
+            StatusCode::OK
+        );
+        assert_eq!(status(&mut app, "/call", Some(("x-api-key", "other"))).await, StatusCode::OK);
+        assert_eq!(status(&mut app, "/call", Some(("x-api-key", ""))).await, StatusCode::UNAUTHORIZED);
+        // Health checks stay unauthenticated
+        assert_eq!(status(&mut app, "/health", None).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_no_keys_disables_check() {
+        let mut app = app(&[]);
+        assert!(!ApiKeyLayer::new(&[]).is_enabled());
+        assert_eq!(status(&mut app, "/call", None).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,97 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{request::Parts, Extensions, HeaderMap},
+};
+use ipnetwork::IpNetwork;
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+/// Client address of a request, as checked by the IP filters
+///
+/// Handlers use it to apply per-platform allowlists on top of the global
+/// [`super::IpFilterLayer`], which resolves it once per request. Outside the
+/// filter only the peer address is used. `None` when the address is unknown.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(client) = parts.extensions.get::<ClientIp>() {
+            return Ok(*client);
+        }
+        Ok(ClientIp(client_ip(&parts.headers, &parts.extensions, &[])))
+    }
+}
+
+/// Peer address, or the forwarded client when the peer is a trusted proxy
+///
+/// X-Forwarded-For is read right to left, skipping trusted proxies, so a
+/// client cannot pick its address by sending the header itself.
+pub(super) fn client_ip(
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    trusted_proxies: &[IpNetwork],
+) -> Option<IpAddr> {
+    let peer = extensions.get::<ConnectInfo<SocketAddr>>().map(|ci| ci.0.ip())?;
+    if !is_trusted(trusted_proxies, peer) {
+        return Some(peer);
+    }
+
+    let Some(forwarded) = headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) else {
+        return Some(peer);
+    };
+    for entry in forwarded.rsplit(',') {
+        match entry.trim().parse::<IpAddr>() {
+            Ok(ip) if is_trusted(trusted_proxies, ip) => continue,
+            Ok(ip) => return Some(ip),
+            Err(_) => break,
+        }
+    }
+    Some(peer)
+}
+
+fn is_trusted(trusted_proxies: &[IpNetwork], ip: IpAddr) -> bool {
+    let ip = ip.to_canonical();
+    trusted_proxies.iter().any(|network| network.contains(ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn resolve(peer: &str, forwarded: Option<&str>, trusted: &[&str]) -> Option<IpAddr> {
+        let mut headers = HeaderMap::new();
+        if let Some(forwarded) = forwarded {
+            headers.insert("x-forwarded-for", forwarded.parse().unwrap());
+        }
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 40000)));
+        let trusted: Vec<IpNetwork> = trusted.iter().map(|n| IpNetwork::from_str(n).unwrap()).collect();
+        client_ip(&headers, &extensions, &trusted)
+    }
+
+    #[test]
+    fn test_forwarded_header_only_trusted_from_proxies() {
+        let ip = |s: &str| Some(s.parse::<IpAddr>().unwrap());
+
+        // Without trusted proxies the header is ignored
+        assert_eq!(resolve("203.0.113.9", Some("127.0.0.1"), &[]), ip("203.0.113.9"));
+        // A peer outside the trusted proxies cannot spoof its address
+        assert_eq!(resolve("203.0.113.9", Some("10.0.0.5"), &["10.1.0.0/16"]), ip("203.0.113.9"));
+        // Behind a trusted proxy the right-most untrusted entry is the client
+        assert_eq!(
+            resolve("10.1.0.2", Some("127.0.0.1, 198.51.100.7, 10.1.0.3"), &["10.1.0.0/16"]),
+            ip("198.51.100.7")
+        );
+        // Nothing usable in the header falls back to the proxy itself
+        assert_eq!(resolve("10.1.0.2", Some("garbage"), &["10.1.0.0/16"]), ip("10.1.0.2"));
+        assert_eq!(resolve("10.1.0.2", None, &["10.1.0.0/16"]), ip("10.1.0.2"));
+    }
+}
@@ -1,13 +1,14 @@
+use super::client_ip::{client_ip, ClientIp};
+use super::EXEMPT_PATHS;
 use axum::{
     body::Body,
-    extract::ConnectInfo,
     http::{Request, StatusCode},
     response::Response,
 };
-use ipnetwork::IpNetwork;
+use ipnetwork::{IpNetwork, Ipv4Network, Ipv6Network};
 use std::{
     future::Future,
-    net::{IpAddr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
@@ -18,14 +19,30 @@ use tracing::warn;
 #[derive(Clone)]
 pub struct IpFilterLayer {
     allowed_networks: Arc<Vec<IpNetwork>>,
+    trusted_proxies: Arc<Vec<IpNetwork>>,
 }
 
 impl IpFilterLayer {
     pub fn new(allowed_networks: Vec<IpNetwork>) -> Self {
         Self {
             allowed_networks: Arc::new(allowed_networks),
+            trusted_proxies: Arc::new(Vec::new()),
         }
     }
+
+    /// Take the client address from X-Forwarded-For when the peer is one of these proxies
+    pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<IpNetwork>) -> Self {
+        self.trusted_proxies = Arc::new(trusted_proxies);
+        self
+    }
+
+    /// A filter that lets every address through (`IP_FILTER=false`)
+    pub fn allow_all() -> Self {
+        Self::new(vec![
+            IpNetwork::V4(Ipv4Network::new(Ipv4Addr::UNSPECIFIED, 0).unwrap()),
+            IpNetwork::V6(Ipv6Network::new(Ipv6Addr::UNSPECIFIED, 0).unwrap()),
+        ])
+    }
 }
 
 impl<S> Layer<S> for IpFilterLayer {
@@ -35,6 +52,7 @@ impl<S> Layer<S> for IpFilterLayer {
         IpFilterService {
             inner,
             allowed_networks: self.allowed_networks.clone(),
+            trusted_proxies: self.trusted_proxies.clone(),
         }
     }
 }
@@ -43,6 +61,7 @@ impl<S> Layer<S> for IpFilterLayer {
 pub struct IpFilterService<S> {
     inner: S,
     allowed_networks: Arc<Vec<IpNetwork>>,
+    trusted_proxies: Arc<Vec<IpNetwork>>,
 }
 
 impl<S> Service<Request<Body>> for IpFilterService<S>
@@ -58,27 +77,19 @@ where
         self.inner.poll_ready(cx)
     }
 
-    fn call(&mut self, req: Request<Body>) -> Self::Future {
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
         let allowed_networks = self.allowed_networks.clone();
+        let trusted_proxies = self.trusted_proxies.clone();
         let mut inner = self.inner.clone();
 
         Box::pin(async move {
-            // Try to get client IP from ConnectInfo extension
-            let client_ip = req
-                .extensions()
-                .get::<ConnectInfo<SocketAddr>>()
-                .map(|ci| ci.0.ip());
-
-            // Also check X-Forwarded-For header (for proxied requests)
-            let forwarded_ip = req
-                .headers()
-                .get("x-forwarded-for")
-                .and_then(|v| v.to_str().ok())
-                .and_then(|s| s.split(',').next())
-                .and_then(|s| s.trim().parse::<IpAddr>().ok());
-
-            // Prefer forwarded IP, fallback to direct connection
-            let ip_to_check = forwarded_ip.or(client_ip);
+            if EXEMPT_PATHS.contains(&req.uri().path()) {
+                return inner.call(req).await;
+            }
+
+            // Forwarded IP from a trusted proxy, else the direct connection
+            let ip_to_check = client_ip(req.headers(), req.extensions(), &trusted_proxies);
+            req.extensions_mut().insert(ClientIp(ip_to_check));
 
             match ip_to_check {
                 Some(ip) if is_allowed(&allowed_networks, ip) => {
@@ -115,7 +126,13 @@ where
     }
 }
 
+/// Check an IPv4 or IPv6 address against the allowed networks
+///
+/// IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`, as reported by a socket
+/// listening on `[::]`) are matched as the IPv4 address they carry.
 fn is_allowed(allowed_networks: &[IpNetwork], ip: IpAddr) -> bool {
+    let ip = ip.to_canonical();
+
     // Always allow loopback
     if ip.is_loopback() {
         return true;
@@ -134,6 +151,8 @@ fn is_allowed(allowed_networks: &[IpNetwork], ip: IpAddr) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::extract::ConnectInfo;
+    use std::net::SocketAddr;
     use std::str::FromStr;
 
     #[test]
@@ -152,6 +171,79 @@ mod tests {
         assert!(!is_allowed(&allowed, "192.168.1.1".parse().unwrap()));
     }
 
+    #[test]
+    fn test_ipv6_network_allowed() {
+        let allowed = vec![IpNetwork::from_str("2001:db8:abcd:12::/64").unwrap()];
+        assert!(is_allowed(&allowed, "2001:db8:abcd:12::1".parse().unwrap()));
+        assert!(is_allowed(&allowed, "2001:db8:abcd:12:ffff:ffff:ffff:ffff".parse().unwrap()));
+        assert!(!is_allowed(&allowed, "2001:db8:abcd:13::1".parse().unwrap()));
+        assert!(!is_allowed(&allowed, "fe80::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_mapped_ipv4_matches_ipv4_network() {
+        let allowed = vec![IpNetwork::from_str("192.168.1.0/24").unwrap()];
+        assert!(is_allowed(&allowed, "::ffff:192.168.1.5".parse().unwrap()));
+        assert!(!is_allowed(&allowed, "::ffff:10.0.2.1".parse().unwrap()));
+        // Mapped loopback is loopback
+        assert!(is_allowed(&[], "::ffff:127.0.0.1".parse().unwrap()));
+    }
+
+    fn request(path: &str, peer: &str) -> Request<Body> {
+        let mut req = Request::get(path).body(Body::empty()).unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 40000)));
+        req
+    }
+
+    #[tokio::test]
+    async fn test_filter_layer_with_ipv6_peers() {
+        use axum::{routing::get, Router};
+
+        let mut app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .route("/call", get(|| async { "ok" }))
+            .layer(IpFilterLayer::new(vec![
+                IpNetwork::from_str("2001:db8::/64").unwrap(),
+                IpNetwork::from_str("10.1.0.0/16").unwrap(),
+            ]));
+
+        let status = |response: Response| response.status();
+        assert_eq!(status(app.call(request("/call", "2001:db8::42")).await.unwrap()), StatusCode::OK);
+        assert_eq!(status(app.call(request("/call", "::ffff:10.1.2.3")).await.unwrap()), StatusCode::OK);
+        assert_eq!(
+            status(app.call(request("/call", "2001:db8:0:1::42")).await.unwrap()),
+            StatusCode::FORBIDDEN
+        );
+        // Health checks are not filtered
+        assert_eq!(status(app.call(request("/health", "2001:db8:0:1::42")).await.unwrap()), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_loopback_from_untrusted_peer_denied() {
+        use axum::{routing::get, Router};
+
+        let allowed = vec![IpNetwork::from_str("10.1.0.0/16").unwrap()];
+        let mut app = Router::new()
+            .route("/call", get(|| async { "ok" }))
+            .layer(IpFilterLayer::new(allowed).with_trusted_proxies(vec![IpNetwork::from_str("10.9.0.1/32").unwrap()]));
+
+        let forwarded = |peer: &str| {
+            let mut req = request("/call", peer);
+            req.headers_mut().insert("x-forwarded-for", "127.0.0.1".parse().unwrap());
+            req
+        };
+        assert_eq!(app.call(forwarded("203.0.113.9")).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(app.call(forwarded("10.9.0.1")).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_allow_all() {
+        let filter = IpFilterLayer::allow_all();
+        assert!(is_allowed(&filter.allowed_networks, "8.8.8.8".parse().unwrap()));
+        assert!(is_allowed(&filter.allowed_networks, "2001:db8::1".parse().unwrap()));
+    }
+
     #[test]
     fn test_external_ip_denied() {
         let allowed = vec![IpNetwork::from_str("192.168.1.0/24").unwrap()];
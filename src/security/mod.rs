@@ -1,5 +1,12 @@
 mod admin_auth;
+mod api_key;
+mod client_ip;
 mod ip_filter;
 
-pub use admin_auth::{admin_auth_middleware, AdminAuthConfig};
+pub use admin_auth::{admin_auth_middleware, has_admin_token, AdminAuthConfig};
+pub use api_key::ApiKeyLayer;
+pub use client_ip::ClientIp;
 pub use ip_filter::IpFilterLayer;
+
+/// Paths served without IP or API key checks (load balancer health checks)
+const EXEMPT_PATHS: &[&str] = &["/health", "/health/ready"];